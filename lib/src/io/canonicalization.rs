@@ -0,0 +1,632 @@
+//! Implementation of the [RDF Dataset Canonicalization](https://www.w3.org/TR/rdf-canon/) algorithm (RDFC-1.0, formerly URDNA2015).
+
+use crate::model::isomorphism::map_blank_nodes;
+use crate::model::vocab::xsd;
+use crate::model::*;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io;
+use std::io::Write;
+
+/// Canonicalizes a set of quads using the [RDFC-1.0](https://www.w3.org/TR/rdf-canon/) algorithm.
+///
+/// The blank nodes are relabeled `c14n0`, `c14n1`... in a deterministic way,
+/// the duplicated quads are removed and the quads are sorted following their canonical N-Quads serialization.
+/// Two [isomorphic](https://www.w3.org/TR/rdf11-concepts/#dfn-dataset-isomorphism) datasets have the same canonical form.
+///
+/// Usage example:
+/// ```
+/// use oxigraph::io::canonicalize_quads;
+/// use oxigraph::model::*;
+///
+/// let ex = NamedNode::new("http://example.com")?;
+/// let quads = canonicalize_quads(vec![
+///     Quad::new(BlankNode::new("foo")?, ex.clone(), ex.clone(), None),
+/// ]);
+/// assert_eq!(quads, vec![Quad::new(BlankNode::new("c14n0")?, ex.clone(), ex, None)]);
+/// # Result::<_,Box<dyn std::error::Error>>::Ok(())
+/// ```
+///
+/// Warning: This implementation worst-case complexity is exponential in the number of blank nodes
+/// that could not be distinguished from their neighborhood.
+pub fn canonicalize_quads(quads: impl IntoIterator<Item = Quad>) -> Vec<Quad> {
+    canonical_lines(quads)
+        .into_iter()
+        .map(|(_, quad)| quad)
+        .collect()
+}
+
+/// Writes a set of quads as [canonical N-Quads](https://www.w3.org/TR/rdf-canon/#canonical-quads).
+///
+/// The quads are first [canonicalized](canonicalize_quads()) so two isomorphic datasets give the same bytes.
+///
+/// Usage example:
+/// ```
+/// use oxigraph::io::write_canonical_n_quads;
+/// use oxigraph::model::*;
+///
+/// let ex = NamedNode::new("http://example.com")?;
+/// let mut buffer = Vec::new();
+/// write_canonical_n_quads(vec![Quad::new(BlankNode::default(), ex.clone(), ex, None)], &mut buffer)?;
+/// assert_eq!(buffer.as_slice(), "_:c14n0 <http://example.com> <http://example.com> .\n".as_bytes());
+/// # Result::<_,Box<dyn std::error::Error>>::Ok(())
+/// ```
+pub fn write_canonical_n_quads(
+    quads: impl IntoIterator<Item = Quad>,
+    mut writer: impl Write,
+) -> Result<(), io::Error> {
+    for (line, _) in canonical_lines(quads) {
+        writer.write_all(line.as_bytes())?;
+    }
+    Ok(())
+}
+
+/// Returns the hexadecimal SHA-256 hash of the [canonical N-Quads](write_canonical_n_quads()) serialization of a set of quads.
+///
+/// Two isomorphic datasets have the same hash.
+///
+/// Usage example:
+/// ```
+/// use oxigraph::io::canonical_dataset_hash;
+/// use oxigraph::model::*;
+///
+/// let ex = NamedNode::new("http://example.com")?;
+/// assert_eq!(
+///     canonical_dataset_hash(vec![Quad::new(BlankNode::new("a")?, ex.clone(), ex.clone(), None)]),
+///     canonical_dataset_hash(vec![Quad::new(BlankNode::new("b")?, ex.clone(), ex, None)])
+/// );
+/// # Result::<_,Box<dyn std::error::Error>>::Ok(())
+/// ```
+pub fn canonical_dataset_hash(quads: impl IntoIterator<Item = Quad>) -> String {
+    let mut hasher = Sha256::new();
+    for (line, _) in canonical_lines(quads) {
+        hasher.update(line.as_bytes());
+    }
+    hex::encode(hasher.finalize())
+}
+
+/// Returns the canonicalized quads with their canonical N-Quads serialization, sorted by serialization
+fn canonical_lines(quads: impl IntoIterator<Item = Quad>) -> Vec<(String, Quad)> {
+    let quads = quads
+        .into_iter()
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect::<Vec<_>>();
+    let labels = canonical_blank_node_labels(&quads);
+    let mut lines = quads
+        .iter()
+        .map(|quad| {
+            let quad = map_blank_nodes(quad, &|node| {
+                labels
+                    .get(node.as_str())
+                    .map_or_else(|| node.clone(), BlankNode::new_unchecked)
+            });
+            let mut line = String::new();
+            write_quad(&mut line, quad.as_ref(), &|id, out| out.push_str(id));
+            (line, quad)
+        })
+        .collect::<Vec<_>>();
+    lines.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+    lines
+}
+
+/// Returns the mapping from the blank node labels of the quads to their canonical labels.
+///
+/// The quads must not contain duplicates.
+pub(crate) fn canonical_blank_node_labels(quads: &[Quad]) -> HashMap<String, String> {
+    Canonicalizer::new(quads).canonical_labels()
+}
+
+struct Canonicalizer<'a> {
+    quads_for_blank_node: HashMap<&'a str, Vec<&'a Quad>>,
+    first_degree_hashes: HashMap<&'a str, String>,
+    canonical_issuer: IdentifierIssuer<'a>,
+}
+
+impl<'a> Canonicalizer<'a> {
+    fn new(quads: &'a [Quad]) -> Self {
+        let mut quads_for_blank_node = HashMap::<_, Vec<&Quad>>::new();
+        for quad in quads {
+            for (node, _) in quad_blank_nodes(quad) {
+                let node_quads = quads_for_blank_node.entry(node).or_default();
+                // The same blank node might appear multiple times in the quad
+                if !matches!(node_quads.last(), Some(q) if std::ptr::eq(*q, quad)) {
+                    node_quads.push(quad);
+                }
+            }
+        }
+        Self {
+            quads_for_blank_node,
+            first_degree_hashes: HashMap::new(),
+            canonical_issuer: IdentifierIssuer::new("c14n"),
+        }
+    }
+
+    /// Returns the mapping from the original blank node labels to the canonical ones
+    fn canonical_labels(mut self) -> HashMap<String, String> {
+        let mut nodes = self
+            .quads_for_blank_node
+            .keys()
+            .copied()
+            .collect::<Vec<_>>();
+        nodes.sort_unstable();
+        let mut nodes_for_hash = BTreeMap::<_, Vec<_>>::new();
+        for node in nodes {
+            let hash = self.hash_first_degree_quads(node);
+            nodes_for_hash.entry(hash).or_default().push(node);
+        }
+
+        // The blank nodes with a unique first degree hash are labeled first
+        for nodes in nodes_for_hash.values() {
+            if let [node] = nodes.as_slice() {
+                self.canonical_issuer.issue(node);
+            }
+        }
+
+        for nodes in nodes_for_hash.values() {
+            if nodes.len() < 2 {
+                continue;
+            }
+            let mut hash_paths = Vec::new();
+            for node in nodes {
+                if self.canonical_issuer.get(node).is_some() {
+                    continue;
+                }
+                let mut issuer = IdentifierIssuer::new("b");
+                issuer.issue(node);
+                hash_paths.push(self.hash_n_degree_quads(node, issuer));
+            }
+            hash_paths.sort_by(|(a, _), (b, _)| a.cmp(b));
+            for (_, issuer) in hash_paths {
+                for node in issuer.issued_order {
+                    self.canonical_issuer.issue(node);
+                }
+            }
+        }
+
+        self.canonical_issuer
+            .issued
+            .into_iter()
+            .map(|(node, label)| (node.to_owned(), label))
+            .collect()
+    }
+
+    fn hash_first_degree_quads(&mut self, node: &'a str) -> String {
+        if let Some(hash) = self.first_degree_hashes.get(node) {
+            return hash.clone();
+        }
+        let mut lines = self
+            .quads_for_blank_node
+            .get(node)
+            .map(|quads| {
+                quads
+                    .iter()
+                    .map(|quad| {
+                        let mut line = String::new();
+                        write_quad(&mut line, quad.as_ref(), &|id, out| {
+                            out.push_str(if id == node { "a" } else { "z" })
+                        });
+                        line
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        lines.sort_unstable();
+        let hash = sha256_hex(&lines.concat());
+        self.first_degree_hashes.insert(node, hash.clone());
+        hash
+    }
+
+    fn hash_related_blank_node(
+        &mut self,
+        related: &'a str,
+        quad: &Quad,
+        issuer: &IdentifierIssuer<'a>,
+        position: &str,
+    ) -> String {
+        let mut input = position.to_owned();
+        if position != "g" {
+            input.push('<');
+            input.push_str(quad.predicate.as_str());
+            input.push('>');
+        }
+        if let Some(label) = self
+            .canonical_issuer
+            .get(related)
+            .or_else(|| issuer.get(related))
+        {
+            input.push_str("_:");
+            input.push_str(label);
+        } else {
+            input.push_str(&self.hash_first_degree_quads(related));
+        }
+        sha256_hex(&input)
+    }
+
+    fn hash_n_degree_quads(
+        &mut self,
+        node: &'a str,
+        mut issuer: IdentifierIssuer<'a>,
+    ) -> (String, IdentifierIssuer<'a>) {
+        let mut related_for_hash = BTreeMap::<_, Vec<_>>::new();
+        let quads = self
+            .quads_for_blank_node
+            .get(node)
+            .cloned()
+            .unwrap_or_default();
+        for quad in quads {
+            for (related, position) in quad_blank_nodes(quad) {
+                if related != node {
+                    let hash = self.hash_related_blank_node(related, quad, &issuer, position);
+                    related_for_hash.entry(hash).or_default().push(related);
+                }
+            }
+        }
+
+        let mut data_to_hash = String::new();
+        for (related_hash, related) in related_for_hash {
+            data_to_hash.push_str(&related_hash);
+            let mut chosen_path = String::new();
+            let mut chosen_issuer = None;
+            let mut permutation = (0..related.len()).collect::<Vec<_>>();
+            loop {
+                if let Some((path, issuer)) = self.permutation_path(
+                    permutation.iter().map(|i| related[*i]),
+                    &issuer,
+                    &chosen_path,
+                ) {
+                    if chosen_issuer.is_none() || path < chosen_path {
+                        chosen_path = path;
+                        chosen_issuer = Some(issuer);
+                    }
+                }
+                if !next_permutation(&mut permutation) {
+                    break;
+                }
+            }
+            data_to_hash.push_str(&chosen_path);
+            if let Some(chosen_issuer) = chosen_issuer {
+                issuer = chosen_issuer;
+            }
+        }
+        (sha256_hex(&data_to_hash), issuer)
+    }
+
+    /// Computes the path of a permutation of related blank nodes.
+    ///
+    /// Returns `None` if the path is already known to be greater than the chosen one.
+    fn permutation_path(
+        &mut self,
+        permutation: impl Iterator<Item = &'a str>,
+        issuer: &IdentifierIssuer<'a>,
+        chosen_path: &str,
+    ) -> Option<(String, IdentifierIssuer<'a>)> {
+        let is_worse = |path: &str| {
+            !chosen_path.is_empty() && path.len() >= chosen_path.len() && path > chosen_path
+        };
+        let mut issuer = issuer.clone();
+        let mut path = String::new();
+        let mut recursion_list = Vec::new();
+        for related in permutation {
+            path.push_str("_:");
+            if let Some(label) = self.canonical_issuer.get(related) {
+                path.push_str(label);
+            } else {
+                if issuer.get(related).is_none() {
+                    recursion_list.push(related);
+                }
+                path.push_str(&issuer.issue(related));
+            }
+            if is_worse(&path) {
+                return None;
+            }
+        }
+        for related in recursion_list {
+            let (hash, result_issuer) = self.hash_n_degree_quads(related, issuer.clone());
+            path.push_str("_:");
+            path.push_str(&issuer.issue(related));
+            path.push('<');
+            path.push_str(&hash);
+            path.push('>');
+            issuer = result_issuer;
+            if is_worse(&path) {
+                return None;
+            }
+        }
+        Some((path, issuer))
+    }
+}
+
+/// Issues blank node identifiers with a prefix and a counter, remembering the issuing order
+#[derive(Clone)]
+struct IdentifierIssuer<'a> {
+    prefix: &'static str,
+    issued: HashMap<&'a str, String>,
+    issued_order: Vec<&'a str>,
+}
+
+impl<'a> IdentifierIssuer<'a> {
+    fn new(prefix: &'static str) -> Self {
+        Self {
+            prefix,
+            issued: HashMap::new(),
+            issued_order: Vec::new(),
+        }
+    }
+
+    fn get(&self, node: &str) -> Option<&str> {
+        self.issued.get(node).map(String::as_str)
+    }
+
+    fn issue(&mut self, node: &'a str) -> String {
+        if let Some(label) = self.issued.get(node) {
+            return label.clone();
+        }
+        let label = format!("{}{}", self.prefix, self.issued_order.len());
+        self.issued.insert(node, label.clone());
+        self.issued_order.push(node);
+        label
+    }
+}
+
+/// Returns the blank nodes of a quad with their position: `s`, `o` or `g`.
+///
+/// The blank nodes inside of quoted triples get the position of the quoted triple.
+pub(crate) fn quad_blank_nodes(quad: &Quad) -> Vec<(&str, &'static str)> {
+    let mut nodes = Vec::new();
+    add_term_blank_nodes(quad.subject.as_ref().into(), "s", &mut nodes);
+    add_term_blank_nodes(quad.object.as_ref(), "o", &mut nodes);
+    if let GraphName::BlankNode(node) = &quad.graph_name {
+        nodes.push((node.as_str(), "g"));
+    }
+    nodes
+}
+
+fn add_term_blank_nodes<'a>(
+    term: TermRef<'a>,
+    position: &'static str,
+    nodes: &mut Vec<(&'a str, &'static str)>,
+) {
+    match term {
+        TermRef::BlankNode(node) => nodes.push((node.as_str(), position)),
+        TermRef::Triple(triple) => {
+            add_term_blank_nodes(triple.subject.as_ref().into(), position, nodes);
+            add_term_blank_nodes(triple.object.as_ref(), position, nodes);
+        }
+        TermRef::NamedNode(_) | TermRef::Literal(_) => (),
+    }
+}
+
+/// Writes a quad as a canonical N-Quads line.
+///
+/// `label` writes the label of the given blank node.
+pub(crate) fn write_quad(out: &mut String, quad: QuadRef<'_>, label: &impl Fn(&str, &mut String)) {
+    write_term(out, quad.subject.into(), label);
+    out.push(' ');
+    write_term(out, quad.predicate.into(), label);
+    out.push(' ');
+    write_term(out, quad.object, label);
+    match quad.graph_name {
+        GraphNameRef::NamedNode(node) => {
+            out.push(' ');
+            write_term(out, node.into(), label);
+        }
+        GraphNameRef::BlankNode(node) => {
+            out.push(' ');
+            write_term(out, node.into(), label);
+        }
+        GraphNameRef::DefaultGraph => (),
+    }
+    out.push_str(" .\n");
+}
+
+fn write_term(out: &mut String, term: TermRef<'_>, label: &impl Fn(&str, &mut String)) {
+    match term {
+        TermRef::NamedNode(node) => {
+            out.push('<');
+            out.push_str(node.as_str());
+            out.push('>');
+        }
+        TermRef::BlankNode(node) => {
+            out.push_str("_:");
+            label(node.as_str(), out);
+        }
+        TermRef::Literal(literal) => {
+            out.push('"');
+            for c in literal.value().chars() {
+                match c {
+                    '\u{08}' => out.push_str("\\b"),
+                    '\t' => out.push_str("\\t"),
+                    '\n' => out.push_str("\\n"),
+                    '\u{0C}' => out.push_str("\\f"),
+                    '\r' => out.push_str("\\r"),
+                    '"' => out.push_str("\\\""),
+                    '\\' => out.push_str("\\\\"),
+                    '\u{00}'..='\u{1F}' | '\u{7F}' => {
+                        out.push_str(&format!("\\u{:04X}", u32::from(c)))
+                    }
+                    c => out.push(c),
+                }
+            }
+            out.push('"');
+            if let Some(language) = literal.language() {
+                out.push('@');
+                out.push_str(language);
+            } else if literal.datatype() != xsd::STRING {
+                out.push_str("^^");
+                write_term(out, literal.datatype().into(), label);
+            }
+        }
+        TermRef::Triple(triple) => {
+            out.push_str("<< ");
+            write_term(out, triple.subject.as_ref().into(), label);
+            out.push(' ');
+            write_term(out, triple.predicate.as_ref().into(), label);
+            out.push(' ');
+            write_term(out, triple.object.as_ref(), label);
+            out.push_str(" >>");
+        }
+    }
+}
+
+fn sha256_hex(input: &str) -> String {
+    hex::encode(Sha256::digest(input.as_bytes()))
+}
+
+/// Moves to the next permutation in the lexicographic order.
+///
+/// Returns `false` if the permutation was the last one.
+fn next_permutation(permutation: &mut [usize]) -> bool {
+    let pivot = if let Some(pivot) = (1..permutation.len())
+        .rev()
+        .find(|i| permutation[i - 1] < permutation[*i])
+    {
+        pivot - 1
+    } else {
+        return false;
+    };
+    let successor = (pivot + 1..permutation.len())
+        .rev()
+        .find(|i| permutation[*i] > permutation[pivot])
+        .unwrap_or(pivot);
+    permutation.swap(pivot, successor);
+    permutation[pivot + 1..].reverse();
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::{DatasetFormat, DatasetParser};
+    use crate::store::MemoryStore;
+    use std::io::Cursor;
+
+    fn parse(file: &str) -> Vec<Quad> {
+        DatasetParser::from_format(DatasetFormat::NQuads)
+            .read_quads(file.as_bytes())
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap()
+    }
+
+    fn canonical_n_quads(quads: Vec<Quad>) -> String {
+        let mut buffer = Vec::new();
+        write_canonical_n_quads(quads, &mut buffer).unwrap();
+        String::from_utf8(buffer).unwrap()
+    }
+
+    #[test]
+    fn test_unique_hashes() {
+        // Example from the RDFC-1.0 specification
+        assert_eq!(
+            canonical_n_quads(parse(
+                "<http://example.com/#p> <http://example.com/#q> _:e0 .
+<http://example.com/#p> <http://example.com/#r> _:e1 .
+_:e0 <http://example.com/#s> <http://example.com/#u> .
+_:e1 <http://example.com/#t> <http://example.com/#u> .
+"
+            )),
+            "<http://example.com/#p> <http://example.com/#q> _:c14n0 .
+<http://example.com/#p> <http://example.com/#r> _:c14n1 .
+_:c14n0 <http://example.com/#s> <http://example.com/#u> .
+_:c14n1 <http://example.com/#t> <http://example.com/#u> .
+"
+        );
+    }
+
+    #[test]
+    fn test_shared_hashes() {
+        // Example from the RDFC-1.0 specification
+        assert_eq!(
+            canonical_n_quads(parse(
+                "<http://example.com/#p> <http://example.com/#q> _:e0 .
+<http://example.com/#p> <http://example.com/#q> _:e1 .
+_:e0 <http://example.com/#p> _:e2 .
+_:e1 <http://example.com/#p> _:e3 .
+_:e2 <http://example.com/#r> _:e3 .
+"
+            )),
+            "<http://example.com/#p> <http://example.com/#q> _:c14n2 .
+<http://example.com/#p> <http://example.com/#q> _:c14n3 .
+_:c14n0 <http://example.com/#r> _:c14n1 .
+_:c14n2 <http://example.com/#p> _:c14n1 .
+_:c14n3 <http://example.com/#p> _:c14n0 .
+"
+        );
+    }
+
+    #[test]
+    fn test_isomorphic_inputs() {
+        let cycle = |labels: [&str; 4], graph: &str| {
+            parse(&format!(
+                "_:{0} <http://example.com/p> _:{1} {4} .
+_:{1} <http://example.com/p> _:{2} {4} .
+_:{2} <http://example.com/p> _:{3} {4} .
+_:{3} <http://example.com/p> _:{0} {4} .
+_:{0} <http://example.com/q> \"a\\tb\\u0001\"@en {4} .
+_:{0} <http://example.com/q> \"a\\tb\\u0001\"@en {4} .
+",
+                labels[0], labels[1], labels[2], labels[3], graph
+            ))
+        };
+        let expected = canonical_n_quads(cycle(["a", "b", "c", "d"], "_:g"));
+        assert_eq!(
+            canonical_n_quads(cycle(["d", "a", "c", "b"], "_:foo")),
+            expected
+        );
+        assert_ne!(
+            canonical_n_quads(cycle(["a", "b", "c", "d"], "<http://example.com/g>")),
+            expected
+        );
+        assert_eq!(expected.lines().count(), 5);
+        assert!(expected.contains("\"a\\tb\\u0001\"@en"));
+        assert_eq!(
+            canonical_dataset_hash(cycle(["a", "b", "c", "d"], "_:g")),
+            canonical_dataset_hash(cycle(["b", "c", "d", "a"], "_:h"))
+        );
+    }
+
+    #[test]
+    fn test_next_permutation() {
+        let mut permutation = vec![0, 1, 2];
+        let mut count = 1;
+        while next_permutation(&mut permutation) {
+            count += 1;
+        }
+        assert_eq!(count, 6);
+        assert_eq!(permutation, vec![2, 1, 0]);
+    }
+
+    #[test]
+    fn test_store_canonical_dump() -> Result<(), Box<dyn std::error::Error>> {
+        let file = "_:a <http://example.com/p> _:b _:g .
+_:b <http://example.com/p> _:c _:g .
+_:c <http://example.com/p> _:a _:g .
+_:a <http://example.com/name> \"a\" .
+";
+        let renamed = "_:x <http://example.com/name> \"a\" .
+_:z <http://example.com/p> _:x _:h .
+_:y <http://example.com/p> _:z _:h .
+_:x <http://example.com/p> _:y _:h .
+";
+        let store = MemoryStore::new();
+        store.load_dataset(Cursor::new(file), DatasetFormat::NQuads, None)?;
+        let mut buffer = Vec::new();
+        store.dump_canonical_dataset(&mut buffer)?;
+
+        let other = MemoryStore::new();
+        other.load_dataset(Cursor::new(renamed), DatasetFormat::NQuads, None)?;
+        let mut other_buffer = Vec::new();
+        other.dump_canonical_dataset(&mut other_buffer)?;
+        assert_eq!(buffer, other_buffer);
+        assert_eq!(
+            canonical_dataset_hash(store.iter()),
+            canonical_dataset_hash(other.iter())
+        );
+
+        let dump = String::from_utf8(buffer)?;
+        assert_eq!(dump.lines().count(), 4);
+        assert!(dump.lines().all(|l| l.starts_with("_:c14n")));
+        Ok(())
+    }
+}
@@ -0,0 +1,372 @@
+//! Transparent decompression of the parsed files and compression of the serialized ones.
+//!
+//! The actual (de)compression requires the `"compression"` optional feature.
+
+#[cfg(not(feature = "compression"))]
+use crate::error::invalid_input_error;
+#[cfg(feature = "compression")]
+use bzip2::{bufread::MultiBzDecoder, write::BzEncoder};
+#[cfg(feature = "compression")]
+use flate2::{bufread::MultiGzDecoder, write::GzEncoder};
+use std::io;
+#[cfg(feature = "compression")]
+use std::io::BufReader;
+use std::io::{BufRead, Read, Write};
+
+/// Compression formats that could wrap RDF serializations like `.nt.gz` or `.ttl.zst` files.
+///
+/// This enumeration is non exhaustive. New formats might be added in the future.
+#[derive(Eq, PartialEq, Debug, Clone, Copy, Hash)]
+#[non_exhaustive]
+pub enum CompressionFormat {
+    /// [gzip](https://tools.ietf.org/html/rfc1952)
+    Gzip,
+    /// [bzip2](https://sourceware.org/bzip2/)
+    Bzip2,
+    /// [Zstandard](https://tools.ietf.org/html/rfc8878)
+    Zstd,
+}
+
+impl CompressionFormat {
+    /// The format [HTTP content coding](https://www.iana.org/assignments/http-parameters/http-parameters.xhtml#content-coding).
+    ///
+    /// ```
+    /// use oxigraph::io::CompressionFormat;
+    ///
+    /// assert_eq!(CompressionFormat::Gzip.content_encoding(), "gzip")
+    /// ```
+    #[inline]
+    pub fn content_encoding(self) -> &'static str {
+        match self {
+            CompressionFormat::Gzip => "gzip",
+            CompressionFormat::Bzip2 => "bzip2",
+            CompressionFormat::Zstd => "zstd",
+        }
+    }
+
+    /// The format usual file extension.
+    ///
+    /// ```
+    /// use oxigraph::io::CompressionFormat;
+    ///
+    /// assert_eq!(CompressionFormat::Gzip.file_extension(), "gz")
+    /// ```
+    #[inline]
+    pub fn file_extension(self) -> &'static str {
+        match self {
+            CompressionFormat::Gzip => "gz",
+            CompressionFormat::Bzip2 => "bz2",
+            CompressionFormat::Zstd => "zst",
+        }
+    }
+
+    /// Looks for a known format from an HTTP `Content-Encoding` value.
+    ///
+    /// Returns `None` for the `identity` coding.
+    ///
+    /// Example:
+    /// ```
+    /// use oxigraph::io::CompressionFormat;
+    ///
+    /// assert_eq!(CompressionFormat::from_content_encoding("x-gzip"), Some(CompressionFormat::Gzip))
+    /// ```
+    pub fn from_content_encoding(content_encoding: &str) -> Option<Self> {
+        match content_encoding.trim().to_ascii_lowercase().as_str() {
+            "gzip" | "x-gzip" => Some(CompressionFormat::Gzip),
+            "bzip2" | "x-bzip2" => Some(CompressionFormat::Bzip2),
+            "zstd" => Some(CompressionFormat::Zstd),
+            _ => None,
+        }
+    }
+
+    /// Looks for a known format from an extension.
+    ///
+    /// It supports some aliases.
+    ///
+    /// Example:
+    /// ```
+    /// use oxigraph::io::CompressionFormat;
+    ///
+    /// assert_eq!(CompressionFormat::from_extension("zst"), Some(CompressionFormat::Zstd))
+    /// ```
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension {
+            "gz" | "gzip" => Some(CompressionFormat::Gzip),
+            "bz2" | "bzip2" => Some(CompressionFormat::Bzip2),
+            "zst" | "zstd" => Some(CompressionFormat::Zstd),
+            _ => None,
+        }
+    }
+
+    /// Looks for a known format from the first bytes of a file.
+    ///
+    /// Example:
+    /// ```
+    /// use oxigraph::io::CompressionFormat;
+    ///
+    /// assert_eq!(CompressionFormat::from_magic_bytes(b"BZh91AY&SY"), Some(CompressionFormat::Bzip2))
+    /// ```
+    pub fn from_magic_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.starts_with(&[0x1F, 0x8B]) {
+            Some(CompressionFormat::Gzip)
+        } else if bytes.starts_with(b"BZh") {
+            Some(CompressionFormat::Bzip2)
+        } else if bytes.starts_with(&[0x28, 0xB5, 0x2F, 0xFD]) {
+            Some(CompressionFormat::Zstd)
+        } else {
+            None
+        }
+    }
+}
+
+/// A [`BufRead`](std::io::BufRead) implementation decompressing the wrapped reader content.
+///
+/// It could be given to the [`GraphParser`](super::GraphParser) and [`DatasetParser`](super::DatasetParser) methods
+/// or to the store `load_graph` and `load_dataset` methods.
+///
+/// ```
+/// use oxigraph::io::{DecompressingReader, GraphFormat, GraphParser};
+/// use std::io::Cursor;
+///
+/// // Not compressed files are read as is
+/// let file = "<http://example.com/s> <http://example.com/p> <http://example.com/o> .";
+/// let reader = DecompressingReader::new(Cursor::new(file))?;
+/// assert_eq!(reader.compression_format(), None);
+///
+/// let triples = GraphParser::from_format(GraphFormat::NTriples).read_triples(reader)?.collect::<Result<Vec<_>,_>>()?;
+/// assert_eq!(triples.len(), 1);
+/// # std::io::Result::Ok(())
+/// ```
+pub struct DecompressingReader<R: BufRead> {
+    inner: DecompressingReaderKind<R>,
+}
+
+enum DecompressingReaderKind<R: BufRead> {
+    Plain(R),
+    #[cfg(feature = "compression")]
+    Gzip(BufReader<MultiGzDecoder<R>>),
+    #[cfg(feature = "compression")]
+    Bzip2(BufReader<MultiBzDecoder<R>>),
+    #[cfg(feature = "compression")]
+    Zstd(BufReader<zstd::Decoder<'static, R>>),
+}
+
+impl<R: BufRead> DecompressingReader<R> {
+    /// Wraps `reader` and decompresses its content if it starts with the magic bytes of a supported compression format.
+    pub fn new(mut reader: R) -> Result<Self, io::Error> {
+        let format = CompressionFormat::from_magic_bytes(reader.fill_buf()?);
+        Self::with_format(reader, format)
+    }
+
+    /// Wraps `reader` and decompresses its content using the given format.
+    ///
+    /// The content is read as is if `format` is `None`.
+    pub fn with_format(reader: R, format: Option<CompressionFormat>) -> Result<Self, io::Error> {
+        Ok(Self {
+            inner: match format {
+                None => DecompressingReaderKind::Plain(reader),
+                #[cfg(feature = "compression")]
+                Some(CompressionFormat::Gzip) => {
+                    DecompressingReaderKind::Gzip(BufReader::new(MultiGzDecoder::new(reader)))
+                }
+                #[cfg(feature = "compression")]
+                Some(CompressionFormat::Bzip2) => {
+                    DecompressingReaderKind::Bzip2(BufReader::new(MultiBzDecoder::new(reader)))
+                }
+                #[cfg(feature = "compression")]
+                Some(CompressionFormat::Zstd) => DecompressingReaderKind::Zstd(BufReader::new(
+                    zstd::Decoder::with_buffer(reader)?,
+                )),
+                #[cfg(not(feature = "compression"))]
+                Some(format) => return Err(disabled_compression_error(format)),
+            },
+        })
+    }
+
+    /// The format used to decompress the content
+    pub fn compression_format(&self) -> Option<CompressionFormat> {
+        match self.inner {
+            DecompressingReaderKind::Plain(_) => None,
+            #[cfg(feature = "compression")]
+            DecompressingReaderKind::Gzip(_) => Some(CompressionFormat::Gzip),
+            #[cfg(feature = "compression")]
+            DecompressingReaderKind::Bzip2(_) => Some(CompressionFormat::Bzip2),
+            #[cfg(feature = "compression")]
+            DecompressingReaderKind::Zstd(_) => Some(CompressionFormat::Zstd),
+        }
+    }
+}
+
+impl<R: BufRead> Read for DecompressingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match &mut self.inner {
+            DecompressingReaderKind::Plain(reader) => reader.read(buf),
+            #[cfg(feature = "compression")]
+            DecompressingReaderKind::Gzip(reader) => reader.read(buf),
+            #[cfg(feature = "compression")]
+            DecompressingReaderKind::Bzip2(reader) => reader.read(buf),
+            #[cfg(feature = "compression")]
+            DecompressingReaderKind::Zstd(reader) => reader.read(buf),
+        }
+    }
+}
+
+impl<R: BufRead> BufRead for DecompressingReader<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        match &mut self.inner {
+            DecompressingReaderKind::Plain(reader) => reader.fill_buf(),
+            #[cfg(feature = "compression")]
+            DecompressingReaderKind::Gzip(reader) => reader.fill_buf(),
+            #[cfg(feature = "compression")]
+            DecompressingReaderKind::Bzip2(reader) => reader.fill_buf(),
+            #[cfg(feature = "compression")]
+            DecompressingReaderKind::Zstd(reader) => reader.fill_buf(),
+        }
+    }
+
+    fn consume(&mut self, amt: usize) {
+        match &mut self.inner {
+            DecompressingReaderKind::Plain(reader) => reader.consume(amt),
+            #[cfg(feature = "compression")]
+            DecompressingReaderKind::Gzip(reader) => reader.consume(amt),
+            #[cfg(feature = "compression")]
+            DecompressingReaderKind::Bzip2(reader) => reader.consume(amt),
+            #[cfg(feature = "compression")]
+            DecompressingReaderKind::Zstd(reader) => reader.consume(amt),
+        }
+    }
+}
+
+/// A writer compressing the serializers output
+pub(crate) enum CompressingWriter<W: Write> {
+    Plain(W),
+    #[cfg(feature = "compression")]
+    Gzip(GzEncoder<W>),
+    #[cfg(feature = "compression")]
+    Bzip2(BzEncoder<W>),
+    #[cfg(feature = "compression")]
+    Zstd(zstd::Encoder<'static, W>),
+}
+
+impl<W: Write> CompressingWriter<W> {
+    pub fn new(writer: W, format: Option<CompressionFormat>) -> Result<Self, io::Error> {
+        Ok(match format {
+            None => CompressingWriter::Plain(writer),
+            #[cfg(feature = "compression")]
+            Some(CompressionFormat::Gzip) => {
+                CompressingWriter::Gzip(GzEncoder::new(writer, flate2::Compression::default()))
+            }
+            #[cfg(feature = "compression")]
+            Some(CompressionFormat::Bzip2) => {
+                CompressingWriter::Bzip2(BzEncoder::new(writer, bzip2::Compression::default()))
+            }
+            #[cfg(feature = "compression")]
+            Some(CompressionFormat::Zstd) => {
+                CompressingWriter::Zstd(zstd::Encoder::new(writer, 0)?)
+            }
+            #[cfg(not(feature = "compression"))]
+            Some(format) => return Err(disabled_compression_error(format)),
+        })
+    }
+
+    /// Writes the end of the compressed stream and returns the wrapped writer
+    pub fn finish(self) -> Result<W, io::Error> {
+        match self {
+            CompressingWriter::Plain(writer) => Ok(writer),
+            #[cfg(feature = "compression")]
+            CompressingWriter::Gzip(writer) => writer.finish(),
+            #[cfg(feature = "compression")]
+            CompressingWriter::Bzip2(writer) => writer.finish(),
+            #[cfg(feature = "compression")]
+            CompressingWriter::Zstd(writer) => writer.finish(),
+        }
+    }
+}
+
+impl<W: Write> Write for CompressingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            CompressingWriter::Plain(writer) => writer.write(buf),
+            #[cfg(feature = "compression")]
+            CompressingWriter::Gzip(writer) => writer.write(buf),
+            #[cfg(feature = "compression")]
+            CompressingWriter::Bzip2(writer) => writer.write(buf),
+            #[cfg(feature = "compression")]
+            CompressingWriter::Zstd(writer) => writer.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            CompressingWriter::Plain(writer) => writer.flush(),
+            #[cfg(feature = "compression")]
+            CompressingWriter::Gzip(writer) => writer.flush(),
+            #[cfg(feature = "compression")]
+            CompressingWriter::Bzip2(writer) => writer.flush(),
+            #[cfg(feature = "compression")]
+            CompressingWriter::Zstd(writer) => writer.flush(),
+        }
+    }
+}
+
+#[cfg(not(feature = "compression"))]
+fn disabled_compression_error(format: CompressionFormat) -> io::Error {
+    invalid_input_error(format!(
+        "The {} compression is not supported, the \"compression\" feature of Oxigraph is not enabled",
+        format.content_encoding()
+    ))
+}
+
+#[cfg(all(test, feature = "compression"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() -> io::Result<()> {
+        let data = b"<http://example.com/s> <http://example.com/p> \"foo\" .\n".repeat(100);
+        for format in &[
+            CompressionFormat::Gzip,
+            CompressionFormat::Bzip2,
+            CompressionFormat::Zstd,
+        ] {
+            let mut writer = CompressingWriter::new(Vec::new(), Some(*format))?;
+            writer.write_all(&data)?;
+            let compressed = writer.finish()?;
+            assert_eq!(
+                CompressionFormat::from_magic_bytes(&compressed),
+                Some(*format)
+            );
+
+            let mut reader = DecompressingReader::new(compressed.as_slice())?;
+            assert_eq!(reader.compression_format(), Some(*format));
+            let mut decompressed = Vec::new();
+            reader.read_to_end(&mut decompressed)?;
+            assert_eq!(decompressed, data);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_concatenated_members() -> io::Result<()> {
+        let mut compressed = Vec::new();
+        for part in &[b"foo".as_ref(), b"bar".as_ref()] {
+            let mut writer = CompressingWriter::new(Vec::new(), Some(CompressionFormat::Gzip))?;
+            writer.write_all(part)?;
+            compressed.extend_from_slice(&writer.finish()?);
+        }
+        let mut decompressed = String::new();
+        DecompressingReader::new(compressed.as_slice())?.read_to_string(&mut decompressed)?;
+        assert_eq!(decompressed, "foobar");
+        Ok(())
+    }
+
+    #[test]
+    fn test_plain() -> io::Result<()> {
+        let mut reader = DecompressingReader::new(b"BZ".as_ref())?;
+        assert_eq!(reader.compression_format(), None);
+        let mut decompressed = Vec::new();
+        reader.read_to_end(&mut decompressed)?;
+        assert_eq!(decompressed, b"BZ");
+        Ok(())
+    }
+}
@@ -0,0 +1,620 @@
+//! Detection of the RDF serialization format of a file from its name or its content.
+
+use crate::error::invalid_input_error;
+use crate::io::{CompressionFormat, DatasetFormat, DecompressingReader, GraphFormat};
+use std::fs::File;
+use std::io;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+/// An RDF serialization format, either a [graph format](GraphFormat) or a [dataset format](DatasetFormat).
+///
+/// It is returned by the format detection functions.
+///
+/// ```
+/// use oxigraph::io::{GraphFormat, RdfFormat};
+///
+/// assert_eq!(RdfFormat::from_path("dump.nt.gz"), Some(RdfFormat::Graph(GraphFormat::NTriples)))
+/// ```
+#[derive(Eq, PartialEq, Debug, Clone, Copy, Hash)]
+pub enum RdfFormat {
+    /// A format for RDF graphs
+    Graph(GraphFormat),
+    /// A format for RDF datasets
+    Dataset(DatasetFormat),
+}
+
+impl RdfFormat {
+    /// The format [IANA media type](https://tools.ietf.org/html/rfc2046).
+    #[inline]
+    pub fn media_type(self) -> &'static str {
+        match self {
+            RdfFormat::Graph(format) => format.media_type(),
+            RdfFormat::Dataset(format) => format.media_type(),
+        }
+    }
+
+    /// The format [IANA-registered](https://tools.ietf.org/html/rfc2046) file extension.
+    #[inline]
+    pub fn file_extension(self) -> &'static str {
+        match self {
+            RdfFormat::Graph(format) => format.file_extension(),
+            RdfFormat::Dataset(format) => format.file_extension(),
+        }
+    }
+
+    /// Looks for a known format from a media type.
+    ///
+    /// It supports the same aliases as [`GraphFormat::from_media_type`] and [`DatasetFormat::from_media_type`].
+    ///
+    /// Example:
+    /// ```
+    /// use oxigraph::io::{DatasetFormat, RdfFormat};
+    ///
+    /// assert_eq!(RdfFormat::from_media_type("application/trig"), Some(RdfFormat::Dataset(DatasetFormat::TriG)))
+    /// ```
+    pub fn from_media_type(media_type: &str) -> Option<Self> {
+        GraphFormat::from_media_type(media_type)
+            .map(RdfFormat::Graph)
+            .or_else(|| DatasetFormat::from_media_type(media_type).map(RdfFormat::Dataset))
+    }
+
+    /// Looks for a known format from an extension.
+    ///
+    /// It supports the same aliases as [`GraphFormat::from_extension`] and [`DatasetFormat::from_extension`].
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        GraphFormat::from_extension(extension)
+            .map(RdfFormat::Graph)
+            .or_else(|| DatasetFormat::from_extension(extension).map(RdfFormat::Dataset))
+    }
+
+    /// Looks for a known format from a file name.
+    ///
+    /// The extension of a [compression format](CompressionFormat) is ignored, `dump.nq.bz2` is an N-Quads file.
+    ///
+    /// Example:
+    /// ```
+    /// use oxigraph::io::{DatasetFormat, RdfFormat};
+    ///
+    /// assert_eq!(RdfFormat::from_path("/data/dump.nq.bz2"), Some(RdfFormat::Dataset(DatasetFormat::NQuads)))
+    /// ```
+    pub fn from_path(path: impl AsRef<Path>) -> Option<Self> {
+        let path = path.as_ref();
+        let extension = path.extension()?.to_str()?;
+        if CompressionFormat::from_extension(&extension.to_ascii_lowercase()).is_some() {
+            Self::from_extension(Path::new(path.file_stem()?).extension()?.to_str()?)
+        } else {
+            Self::from_extension(extension)
+        }
+    }
+
+    /// Guesses the format of a file from its beginning.
+    ///
+    /// `content` should be the first bytes of the (decompressed) file, a few kilobytes are usually enough.
+    /// It looks for an XML prolog or an `rdf:RDF` element, for a JSON object, for Turtle and TriG directives or graph blocks
+    /// and for lines shaped like N-Triples or N-Quads statements.
+    ///
+    /// Returns `None` if the content does not look like any supported format.
+    ///
+    /// Example:
+    /// ```
+    /// use oxigraph::io::{DetectionConfidence, GraphFormat, RdfFormat};
+    ///
+    /// let guess = RdfFormat::from_content(b"@prefix ex: <http://example.com/> .\nex:s ex:p ex:o .").unwrap();
+    /// assert_eq!(guess.format(), RdfFormat::Graph(GraphFormat::Turtle));
+    /// assert_eq!(guess.confidence(), DetectionConfidence::Medium);
+    /// ```
+    pub fn from_content(content: &[u8]) -> Option<FormatGuess> {
+        let content = content.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(content);
+        let content = trim_start(content);
+        if content.is_empty() {
+            return None;
+        }
+        if let Some(guess) = detect_xml(content) {
+            return Some(guess);
+        }
+        if let Some(guess) = detect_json(content) {
+            return Some(guess);
+        }
+        if let Some(guess) = detect_line_based(content) {
+            return Some(guess);
+        }
+        detect_turtle(content)
+    }
+}
+
+impl From<GraphFormat> for RdfFormat {
+    fn from(format: GraphFormat) -> Self {
+        RdfFormat::Graph(format)
+    }
+}
+
+impl From<DatasetFormat> for RdfFormat {
+    fn from(format: DatasetFormat) -> Self {
+        RdfFormat::Dataset(format)
+    }
+}
+
+/// Opens a file, decompresses it if needed and finds its format from its name or, if unknown, from its content
+///
+/// The extension is not trusted if the content has markers of a format it does not include.
+pub(crate) fn open_rdf_file(
+    path: &Path,
+) -> Result<(DecompressingReader<BufReader<File>>, RdfFormat), io::Error> {
+    let mut reader = DecompressingReader::new(BufReader::new(File::open(path)?))?;
+    let guess = RdfFormat::from_content(reader.fill_buf()?);
+    let format = match (RdfFormat::from_path(path), guess) {
+        (Some(format), Some(guess))
+            if guess.confidence() == DetectionConfidence::High
+                && !is_included_in(guess.format(), format) =>
+        {
+            guess.format()
+        }
+        (Some(format), _) => format,
+        (None, Some(guess)) => guess.format(),
+        (None, None) => {
+            return Err(invalid_input_error(format!(
+                "The RDF format of {} could not be detected",
+                path.display()
+            )))
+        }
+    };
+    Ok((reader, format))
+}
+
+/// Checks if the documents of the format `content` are also valid in the format `syntax`
+///
+/// For example an N-Triples file is a valid Turtle file.
+fn is_included_in(content: RdfFormat, syntax: RdfFormat) -> bool {
+    match content {
+        RdfFormat::Graph(GraphFormat::NTriples) => matches!(
+            syntax,
+            RdfFormat::Graph(GraphFormat::NTriples)
+                | RdfFormat::Graph(GraphFormat::Turtle)
+                | RdfFormat::Dataset(DatasetFormat::NQuads)
+                | RdfFormat::Dataset(DatasetFormat::TriG)
+        ),
+        RdfFormat::Graph(GraphFormat::Turtle) => matches!(
+            syntax,
+            RdfFormat::Graph(GraphFormat::Turtle) | RdfFormat::Dataset(DatasetFormat::TriG)
+        ),
+        content => content == syntax,
+    }
+}
+
+/// A format guessed by [`RdfFormat::from_content`].
+#[derive(Eq, PartialEq, Debug, Clone, Copy, Hash)]
+pub struct FormatGuess {
+    format: RdfFormat,
+    confidence: DetectionConfidence,
+}
+
+impl FormatGuess {
+    /// The guessed format
+    #[inline]
+    pub fn format(self) -> RdfFormat {
+        self.format
+    }
+
+    /// How much the guess could be trusted
+    #[inline]
+    pub fn confidence(self) -> DetectionConfidence {
+        self.confidence
+    }
+}
+
+/// How much a [`FormatGuess`] could be trusted.
+///
+/// The levels are ordered from the less to the most trustworthy.
+#[derive(Eq, PartialEq, Ord, PartialOrd, Debug, Clone, Copy, Hash)]
+pub enum DetectionConfidence {
+    /// The content has no marker specific to the format but it is the most likely one.
+    Low,
+    /// The content has some markers of the format but it could also be a close format.
+    /// For example a Turtle file could be the beginning of a TriG file.
+    Medium,
+    /// The content has markers specific to the format.
+    High,
+}
+
+fn guess(format: impl Into<RdfFormat>, confidence: DetectionConfidence) -> Option<FormatGuess> {
+    Some(FormatGuess {
+        format: format.into(),
+        confidence,
+    })
+}
+
+fn detect_xml(content: &[u8]) -> Option<FormatGuess> {
+    let is_rdf_element = content.starts_with(b"<rdf:RDF")
+        && match content.get(8) {
+            Some(c) => c.is_ascii_whitespace() || *c == b'>',
+            None => true,
+        };
+    let is_xml = content.starts_with(b"<?xml") || content.starts_with(b"<!");
+    if is_rdf_element
+        || (is_xml && contains(content, b"http://www.w3.org/1999/02/22-rdf-syntax-ns#"))
+    {
+        guess(GraphFormat::RdfXml, DetectionConfidence::High)
+    } else if is_xml {
+        // An XML file without the RDF namespace in its beginning
+        guess(GraphFormat::RdfXml, DetectionConfidence::Medium)
+    } else {
+        None
+    }
+}
+
+fn detect_json(content: &[u8]) -> Option<FormatGuess> {
+    // TriG default graph blocks and Turtle blank node property lists also start with { and [
+    let is_json = match content[0] {
+        b'{' => matches!(
+            trim_start(&content[1..]).first(),
+            None | Some(b'"') | Some(b'}')
+        ),
+        b'[' => matches!(trim_start(&content[1..]).first(), None | Some(b'{')),
+        _ => false,
+    };
+    if !is_json {
+        return None;
+    }
+    if [
+        &b"\"@context\""[..],
+        b"\"@id\"",
+        b"\"@graph\"",
+        b"\"@type\"",
+    ]
+    .iter()
+    .any(|keyword| contains(content, keyword))
+    {
+        guess(DatasetFormat::JsonLd, DetectionConfidence::High)
+    } else {
+        guess(DatasetFormat::JsonLd, DetectionConfidence::Medium)
+    }
+}
+
+/// Checks if all the lines are N-Triples or N-Quads statements
+fn detect_line_based(content: &[u8]) -> Option<FormatGuess> {
+    let mut has_triples = false;
+    let mut has_quads = false;
+    let mut lines = content.split(|c| *c == b'\n').peekable();
+    while let Some(line) = lines.next() {
+        let is_last = lines.peek().is_none();
+        match statement_term_count(line) {
+            Some(0) => (),
+            Some(3) => has_triples = true,
+            Some(4) => has_quads = true,
+            // The last line might have been truncated
+            _ if is_last && (has_triples || has_quads) => (),
+            _ => return None,
+        }
+    }
+    if has_quads {
+        guess(DatasetFormat::NQuads, DetectionConfidence::High)
+    } else if has_triples {
+        guess(GraphFormat::NTriples, DetectionConfidence::High)
+    } else {
+        None
+    }
+}
+
+/// Returns the number of terms of a N-Triples or N-Quads line or `None` if the line is not valid
+fn statement_term_count(line: &[u8]) -> Option<usize> {
+    let mut i = skip_whitespaces(line, 0);
+    let mut count = 0;
+    while i < line.len() && line[i] != b'.' && line[i] != b'#' {
+        i = skip_whitespaces(line, skip_term(line, i)?);
+        count += 1;
+    }
+    if count > 0 {
+        if line.get(i) != Some(&b'.') {
+            return None;
+        }
+        i = skip_whitespaces(line, i + 1);
+    }
+    if i < line.len() && line[i] != b'#' {
+        return None;
+    }
+    Some(count)
+}
+
+/// Returns the position after the term starting at `i`
+fn skip_term(line: &[u8], i: usize) -> Option<usize> {
+    match line.get(i)? {
+        b'<' if line.get(i + 1) == Some(&b'<') => {
+            // Quoted triple
+            let mut i = skip_whitespaces(line, i + 2);
+            for _ in 0..3 {
+                i = skip_whitespaces(line, skip_term(line, i)?);
+            }
+            if line.get(i..i + 2) == Some(b">>") {
+                Some(i + 2)
+            } else {
+                None
+            }
+        }
+        b'<' => {
+            let end = i + 1 + line[i + 1..].iter().position(|c| *c == b'>')?;
+            if line[i + 1..end]
+                .iter()
+                .any(|c| c.is_ascii_whitespace() || *c == b'<' || *c == b'"')
+            {
+                None
+            } else {
+                Some(end + 1)
+            }
+        }
+        b'_' if line.get(i + 1) == Some(&b':') => {
+            let mut end = i + 2;
+            while end < line.len() && !line[end].is_ascii_whitespace() && line[end] != b'>' {
+                end += 1;
+            }
+            // The label can not end with a dot
+            while end > i + 2 && line[end - 1] == b'.' {
+                end -= 1;
+            }
+            if end > i + 2 {
+                Some(end)
+            } else {
+                None
+            }
+        }
+        b'"' => {
+            let mut i = i + 1;
+            loop {
+                match line.get(i)? {
+                    b'\\' => i += 2,
+                    b'"' => break,
+                    _ => i += 1,
+                }
+            }
+            i += 1;
+            if line.get(i) == Some(&b'@') {
+                i += 1;
+                while i < line.len() && (line[i].is_ascii_alphanumeric() || line[i] == b'-') {
+                    i += 1;
+                }
+                Some(i)
+            } else if line.get(i..i + 2) == Some(b"^^") {
+                if line.get(i + 2) == Some(&b'<') {
+                    skip_term(line, i + 2)
+                } else {
+                    None
+                }
+            } else {
+                Some(i)
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Looks for Turtle and TriG directives and TriG graph blocks
+fn detect_turtle(content: &[u8]) -> Option<FormatGuess> {
+    let mut has_directive = false;
+    let mut has_graph_block = false;
+    let mut statement_start = true;
+    let mut in_sparql_directive = false;
+    let mut i = 0;
+    while i < content.len() {
+        let c = content[i];
+        if c.is_ascii_whitespace() {
+            i += 1;
+            continue;
+        }
+        if statement_start {
+            let rest = &content[i..];
+            if starts_with_keyword(rest, b"@prefix") || starts_with_keyword(rest, b"@base") {
+                has_directive = true;
+            } else if starts_with_keyword(rest, b"prefix") || starts_with_keyword(rest, b"base") {
+                has_directive = true;
+                in_sparql_directive = true;
+            } else if starts_with_keyword(rest, b"graph") {
+                has_graph_block = true;
+            }
+        }
+        statement_start = false;
+        match c {
+            b'#' => {
+                // Comment
+                i += content[i..]
+                    .iter()
+                    .position(|c| *c == b'\n')
+                    .unwrap_or(content.len() - i);
+                statement_start = true;
+                continue;
+            }
+            b'<' if content.get(i + 1) != Some(&b'<') => {
+                // IRI
+                if let Some(end) = content[i + 1..].iter().position(|c| *c == b'>') {
+                    i += end + 1;
+                }
+                // The SPARQL syntax directives do not end with a dot
+                statement_start = in_sparql_directive;
+                in_sparql_directive = false;
+            }
+            b'"' | b'\'' => {
+                // String
+                let mut j = i + 1;
+                while j < content.len() && content[j] != c {
+                    if content[j] == b'\\' {
+                        j += 1;
+                    }
+                    j += 1;
+                }
+                i = j;
+            }
+            b'{' | b'}' => {
+                has_graph_block = true;
+                statement_start = true;
+            }
+            b'.' => statement_start = true,
+            _ => (),
+        }
+        i += 1;
+    }
+    if has_graph_block {
+        guess(DatasetFormat::TriG, DetectionConfidence::High)
+    } else if has_directive {
+        guess(GraphFormat::Turtle, DetectionConfidence::Medium)
+    } else {
+        guess(GraphFormat::Turtle, DetectionConfidence::Low)
+    }
+}
+
+fn starts_with_keyword(content: &[u8], keyword: &[u8]) -> bool {
+    content.len() > keyword.len()
+        && content[..keyword.len()].eq_ignore_ascii_case(keyword)
+        && (content[keyword.len()].is_ascii_whitespace()
+            || (keyword[0] == b'@' && content[keyword.len()] == b':'))
+}
+
+fn trim_start(content: &[u8]) -> &[u8] {
+    &content[skip_whitespaces(content, 0)..]
+}
+
+fn skip_whitespaces(content: &[u8], mut i: usize) -> usize {
+    while i < content.len() && content[i].is_ascii_whitespace() {
+        i += 1;
+    }
+    i
+}
+
+fn contains(content: &[u8], needle: &[u8]) -> bool {
+    content.windows(needle.len()).any(|w| w == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detect(content: &str) -> Option<(RdfFormat, DetectionConfidence)> {
+        RdfFormat::from_content(content.as_bytes()).map(|g| (g.format(), g.confidence()))
+    }
+
+    #[test]
+    fn test_from_path() {
+        assert_eq!(
+            RdfFormat::from_path("foo.ttl"),
+            Some(RdfFormat::Graph(GraphFormat::Turtle))
+        );
+        assert_eq!(
+            RdfFormat::from_path("foo.NQ.GZ"),
+            Some(RdfFormat::Dataset(DatasetFormat::NQuads))
+        );
+        assert_eq!(
+            RdfFormat::from_path("foo.trig.zst"),
+            Some(RdfFormat::Dataset(DatasetFormat::TriG))
+        );
+        assert_eq!(RdfFormat::from_path("foo.gz"), None);
+        assert_eq!(RdfFormat::from_path("foo.txt"), None);
+        assert_eq!(RdfFormat::from_path("ttl"), None);
+    }
+
+    #[test]
+    fn test_detect_line_based() {
+        assert_eq!(
+            detect("<http://example.com/s> <http://example.com/p> \"o\\\"\"@en-us .\n# comment\n\n_:s <http://example.com/p> \"1\"^^<http://www.w3.org/2001/XMLSchema#integer>.\r\n"),
+            Some((GraphFormat::NTriples.into(), DetectionConfidence::High))
+        );
+        assert_eq!(
+            detect("<http://example.com/s> <http://example.com/p> <http://example.com/o> .\n_:s <http://example.com/p> _:o _:g.\n<http://example.com/s> <http://exa"),
+            Some((DatasetFormat::NQuads.into(), DetectionConfidence::High))
+        );
+        assert_eq!(
+            detect("<< <http://example.com/s> <http://example.com/p> _:o >> <http://example.com/p> <http://example.com/o> ."),
+            Some((GraphFormat::NTriples.into(), DetectionConfidence::High))
+        );
+    }
+
+    #[test]
+    fn test_detect_turtle() {
+        assert_eq!(
+            detect("PREFIX ex: <http://example.com/>\nex:s ex:p \"{\" ."),
+            Some((GraphFormat::Turtle.into(), DetectionConfidence::Medium))
+        );
+        assert_eq!(
+            detect("<http://example.com/s> a <http://example.com/C> ; <http://example.com/p> 1 ."),
+            Some((GraphFormat::Turtle.into(), DetectionConfidence::Low))
+        );
+        assert_eq!(
+            detect("[] <http://example.com/p> 1 ."),
+            Some((GraphFormat::Turtle.into(), DetectionConfidence::Low))
+        );
+        assert_eq!(
+            detect("[ <http://example.com/p> 1 ] ."),
+            Some((GraphFormat::Turtle.into(), DetectionConfidence::Low))
+        );
+        assert_eq!(
+            detect("@prefix ex: <http://example.com/> .\n# {\nex:g { ex:s ex:p ex:o }"),
+            Some((DatasetFormat::TriG.into(), DetectionConfidence::High))
+        );
+        assert_eq!(
+            detect("{ <http://example.com/s> <http://example.com/p> <http://example.com/o> }"),
+            Some((DatasetFormat::TriG.into(), DetectionConfidence::High))
+        );
+    }
+
+    #[test]
+    fn test_detect_xml_and_json() {
+        assert_eq!(
+            detect("\u{feff}<?xml version=\"1.0\"?>\n<rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\">"),
+            Some((GraphFormat::RdfXml.into(), DetectionConfidence::High))
+        );
+        assert_eq!(
+            detect("<rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\"/>"),
+            Some((GraphFormat::RdfXml.into(), DetectionConfidence::High))
+        );
+        assert_eq!(
+            detect("<?xml version=\"1.0\"?>\n<foo/>"),
+            Some((GraphFormat::RdfXml.into(), DetectionConfidence::Medium))
+        );
+        assert_eq!(
+            detect("  {\"@context\": {}, \"@id\": \"http://example.com\"}"),
+            Some((DatasetFormat::JsonLd.into(), DetectionConfidence::High))
+        );
+        assert_eq!(
+            detect("[{\"http://example.com/p\": 1}]"),
+            Some((DatasetFormat::JsonLd.into(), DetectionConfidence::Medium))
+        );
+        assert_eq!(detect("  \n"), None);
+    }
+
+    #[test]
+    fn test_open_rdf_file() -> io::Result<()> {
+        let directory = std::env::temp_dir();
+        for (name, content, expected) in &[
+            (
+                "oxigraph_detection_nt.ttl",
+                "<http://example.com/s> <http://example.com/p> <http://example.com/o> .",
+                Some(RdfFormat::Graph(GraphFormat::Turtle)),
+            ),
+            (
+                "oxigraph_detection_json.ttl",
+                "{\"@id\": \"http://example.com/s\"}",
+                Some(RdfFormat::Dataset(DatasetFormat::JsonLd)),
+            ),
+            (
+                "oxigraph_detection_trig.nt",
+                "<http://example.com/g> { <http://example.com/s> <http://example.com/p> 1 }",
+                Some(RdfFormat::Dataset(DatasetFormat::TriG)),
+            ),
+            (
+                "oxigraph_detection_turtle.nq",
+                "<http://example.com/s> <http://example.com/p> 1 .",
+                Some(RdfFormat::Dataset(DatasetFormat::NQuads)),
+            ),
+            (
+                "oxigraph_detection_turtle.data",
+                "@prefix ex: <http://example.com/> . ex:s ex:p 1 .",
+                Some(RdfFormat::Graph(GraphFormat::Turtle)),
+            ),
+            ("oxigraph_detection_empty.data", "", None),
+        ] {
+            let path = directory.join(name);
+            std::fs::write(&path, content)?;
+            let format = open_rdf_file(&path).map(|(_, format)| format);
+            std::fs::remove_file(&path)?;
+            assert_eq!(format.ok(), *expected, "{}", name);
+        }
+        Ok(())
+    }
+}
@@ -60,6 +60,26 @@ impl GraphFormat {
             GraphFormat::RdfXml => "rdf",
         }
     }
+
+    /// Looks for a known format from an extension.
+    ///
+    /// It supports some aliases and ignores the case.
+    ///
+    /// Example:
+    /// ```
+    /// use oxigraph::io::GraphFormat;
+    ///
+    /// assert_eq!(GraphFormat::from_extension("owl"), Some(GraphFormat::RdfXml))
+    /// ```
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension.to_ascii_lowercase().as_str() {
+            "nt" | "ntriples" => Some(GraphFormat::NTriples),
+            "ttl" | "turtle" => Some(GraphFormat::Turtle),
+            "rdf" | "owl" | "xml" => Some(GraphFormat::RdfXml),
+            _ => None,
+        }
+    }
+
     /// Looks for a known format from a media type.
     ///
     /// It supports some media type aliases.
@@ -89,7 +109,7 @@ impl GraphFormat {
 
 /// [RDF dataset](https://www.w3.org/TR/rdf11-concepts/#dfn-rdf-dataset) serialization formats.
 ///
-/// This enumeration is non exhaustive. New formats like JSON-LD will be added in the future.
+/// This enumeration is non exhaustive. New formats will be added in the future.
 #[derive(Eq, PartialEq, Debug, Clone, Copy, Hash)]
 #[non_exhaustive]
 pub enum DatasetFormat {
@@ -97,6 +117,8 @@ pub enum DatasetFormat {
     NQuads,
     /// [TriG](https://www.w3.org/TR/trig/)
     TriG,
+    /// [JSON-LD 1.1](https://www.w3.org/TR/json-ld11/)
+    JsonLd,
 }
 
 impl DatasetFormat {
@@ -112,6 +134,7 @@ impl DatasetFormat {
         match self {
             DatasetFormat::NQuads => "http://www.w3.org/ns/formats/N-Quads",
             DatasetFormat::TriG => "http://www.w3.org/ns/formats/TriG",
+            DatasetFormat::JsonLd => "http://www.w3.org/ns/formats/JSON-LD",
         }
     }
 
@@ -127,6 +150,7 @@ impl DatasetFormat {
         match self {
             DatasetFormat::NQuads => "application/n-quads",
             DatasetFormat::TriG => "application/trig",
+            DatasetFormat::JsonLd => "application/ld+json",
         }
     }
 
@@ -142,8 +166,29 @@ impl DatasetFormat {
         match self {
             DatasetFormat::NQuads => "nq",
             DatasetFormat::TriG => "trig",
+            DatasetFormat::JsonLd => "jsonld",
         }
     }
+
+    /// Looks for a known format from an extension.
+    ///
+    /// It supports some aliases and ignores the case.
+    ///
+    /// Example:
+    /// ```
+    /// use oxigraph::io::DatasetFormat;
+    ///
+    /// assert_eq!(DatasetFormat::from_extension("nq"), Some(DatasetFormat::NQuads))
+    /// ```
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension.to_ascii_lowercase().as_str() {
+            "nq" | "nquads" => Some(DatasetFormat::NQuads),
+            "trig" => Some(DatasetFormat::TriG),
+            "jsonld" => Some(DatasetFormat::JsonLd),
+            _ => None,
+        }
+    }
+
     /// Looks for a known format from a media type.
     ///
     /// It supports some media type aliases.
@@ -161,6 +206,7 @@ impl DatasetFormat {
                     Some(DatasetFormat::NQuads)
                 }
                 "application/trig" | "application/x-trig" => Some(DatasetFormat::TriG),
+                "application/ld+json" => Some(DatasetFormat::JsonLd),
                 _ => None,
             }
         } else {
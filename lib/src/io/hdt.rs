@@ -0,0 +1,1029 @@
+//! Reader for the [HDT](https://www.rdfhdt.org/hdt-binary-format/) binary RDF format.
+//!
+//! Only the default HDT layout is supported: a four sections dictionary with plain front coding
+//! and bitmap triples in the subject-predicate-object order.
+//! The checksums are not verified.
+
+use crate::error::{invalid_data_error, invalid_input_error};
+use crate::model::vocab::xsd;
+use crate::model::*;
+#[cfg(not(target_arch = "wasm32"))]
+use memmap2::Mmap;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+#[cfg(target_arch = "wasm32")]
+use std::fs;
+use std::io;
+use std::ops::Deref;
+use std::path::Path;
+use std::sync::Arc;
+
+const COOKIE: &[u8] = b"$HDT";
+const GLOBAL_CONTROL: u8 = 1;
+const HEADER_CONTROL: u8 = 2;
+const DICTIONARY_CONTROL: u8 = 3;
+const TRIPLES_CONTROL: u8 = 4;
+const FOUR_SECTION_DICTIONARY: &str = "<http://purl.org/HDT/hdt#dictionaryFour>";
+const BITMAP_TRIPLES: &str = "<http://purl.org/HDT/hdt#triplesBitmap>";
+const PFC_SECTION: u8 = 2;
+const LOG_ARRAY: u8 = 1;
+const PLAIN_BITMAP: u8 = 1;
+const SPO_ORDER: &str = "1";
+
+/// The position of a term in a triple.
+///
+/// HDT dictionaries give different ids to the subjects, the predicates and the objects.
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+pub(crate) enum TripleRole {
+    Subject,
+    Predicate,
+    Object,
+}
+
+/// An opened HDT file
+pub(crate) struct Hdt {
+    data: HdtData,
+    dictionary: Dictionary,
+    triples: BitmapTriples,
+}
+
+/// The content of an HDT file
+enum HdtData {
+    #[cfg(any(test, target_arch = "wasm32"))]
+    Bytes(Vec<u8>),
+    #[cfg(not(target_arch = "wasm32"))]
+    Mmap(Mmap),
+}
+
+impl Deref for HdtData {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            #[cfg(any(test, target_arch = "wasm32"))]
+            Self::Bytes(data) => data,
+            #[cfg(not(target_arch = "wasm32"))]
+            Self::Mmap(data) => data,
+        }
+    }
+}
+
+impl Hdt {
+    /// Opens an HDT file, mapping it into memory
+    ///
+    /// The file should not be modified while it is opened: the data read from it is then undefined.
+    #[cfg(not(target_arch = "wasm32"))]
+    #[allow(unsafe_code)]
+    pub fn open(path: &Path) -> Result<Self, io::Error> {
+        let file = std::fs::File::open(path)?;
+        // SAFETY: the map is read-only and private to the store.
+        // Only a concurrent modification of the file by another process could change the mapped bytes.
+        let data = unsafe { Mmap::map(&file)? };
+        Self::from_data(HdtData::Mmap(data))
+    }
+
+    /// Opens an HDT file, reading it fully into memory
+    #[cfg(target_arch = "wasm32")]
+    pub fn open(path: &Path) -> Result<Self, io::Error> {
+        Self::from_bytes(fs::read(path)?)
+    }
+
+    #[cfg(any(test, target_arch = "wasm32"))]
+    pub fn from_bytes(data: Vec<u8>) -> Result<Self, io::Error> {
+        Self::from_data(HdtData::Bytes(data))
+    }
+
+    fn from_data(data: HdtData) -> Result<Self, io::Error> {
+        let mut reader = ByteReader {
+            data: &data,
+            position: 0,
+        };
+
+        read_control_information(&mut reader, GLOBAL_CONTROL)?;
+
+        let (_, properties) = read_control_information(&mut reader, HEADER_CONTROL)?;
+        let header_length = properties
+            .get("length")
+            .ok_or_else(|| invalid_data_error("The HDT header length is not set"))?
+            .parse()
+            .map_err(invalid_data_error)?;
+        reader.skip(header_length)?;
+
+        let (format, _) = read_control_information(&mut reader, DICTIONARY_CONTROL)?;
+        if format != FOUR_SECTION_DICTIONARY {
+            return Err(invalid_data_error(format!(
+                "The HDT dictionary format {} is not supported",
+                format
+            )));
+        }
+        let dictionary = Dictionary {
+            shared: PfcSection::read(&mut reader)?,
+            subjects: PfcSection::read(&mut reader)?,
+            predicates: PfcSection::read(&mut reader)?,
+            objects: PfcSection::read(&mut reader)?,
+        };
+
+        let (format, properties) = read_control_information(&mut reader, TRIPLES_CONTROL)?;
+        if format != BITMAP_TRIPLES {
+            return Err(invalid_data_error(format!(
+                "The HDT triples format {} is not supported",
+                format
+            )));
+        }
+        if matches!(properties.get("order"), Some(order) if order != SPO_ORDER) {
+            return Err(invalid_data_error(
+                "Only the HDT triples in the subject-predicate-object order are supported",
+            ));
+        }
+        let triples = BitmapTriples {
+            bitmap_y: Bitmap::read(&mut reader)?,
+            bitmap_z: Bitmap::read(&mut reader)?,
+            array_y: LogArray::read(&mut reader)?,
+            array_z: LogArray::read(&mut reader)?,
+        };
+        if triples.bitmap_y.len != triples.array_y.len
+            || triples.bitmap_z.len != triples.array_z.len
+            || triples.bitmap_z.ones != triples.array_y.len
+            || triples.bitmap_y.ones > dictionary.shared.len + dictionary.subjects.len
+        {
+            return Err(invalid_data_error("The HDT triples are not consistent"));
+        }
+
+        Ok(Self {
+            data,
+            dictionary,
+            triples,
+        })
+    }
+
+    /// The number of triples
+    pub fn len(&self) -> u64 {
+        self.triples.array_z.len
+    }
+
+    /// Returns the id of a term serialized as in the HDT dictionary
+    pub fn locate(&self, role: TripleRole, term: &str) -> Result<Option<u64>, io::Error> {
+        let data = &self.data;
+        let term = term.as_bytes();
+        let dictionary = &self.dictionary;
+        Ok(match role {
+            TripleRole::Subject | TripleRole::Object => {
+                if let Some(id) = dictionary.shared.locate(data, term)? {
+                    Some(id)
+                } else {
+                    let section = if role == TripleRole::Subject {
+                        &dictionary.subjects
+                    } else {
+                        &dictionary.objects
+                    };
+                    section
+                        .locate(data, term)?
+                        .map(|id| dictionary.shared.len + id)
+                }
+            }
+            TripleRole::Predicate => dictionary.predicates.locate(data, term)?,
+        })
+    }
+
+    /// Returns the serialization of a term from its id
+    pub fn extract(&self, role: TripleRole, id: u64) -> Result<String, io::Error> {
+        let data = &self.data;
+        let dictionary = &self.dictionary;
+        let value = match role {
+            TripleRole::Subject | TripleRole::Object => {
+                if id <= dictionary.shared.len {
+                    dictionary.shared.extract(data, id)?
+                } else if role == TripleRole::Subject {
+                    dictionary
+                        .subjects
+                        .extract(data, id - dictionary.shared.len)?
+                } else {
+                    dictionary
+                        .objects
+                        .extract(data, id - dictionary.shared.len)?
+                }
+            }
+            TripleRole::Predicate => dictionary.predicates.extract(data, id)?,
+        };
+        String::from_utf8(value).map_err(invalid_data_error)
+    }
+
+    /// Returns the ids of the triples matching a pattern.
+    ///
+    /// The triples are returned in the subject-predicate-object order.
+    /// The patterns without subject scan all the triples.
+    pub fn triple_ids(
+        self: &Arc<Self>,
+        subject: Option<u64>,
+        predicate: Option<u64>,
+        object: Option<u64>,
+    ) -> HdtTripleIds {
+        let data = &self.data;
+        let triples = &self.triples;
+        let (current_subject, y, z, end_z) = if let Some(subject) = subject {
+            if subject == 0 || subject > triples.bitmap_y.ones {
+                (subject, 0, 0, 0)
+            } else {
+                let start_y = triples.start_y(data, subject);
+                let end_y = triples.start_y(data, subject + 1);
+                (
+                    subject,
+                    start_y,
+                    triples.start_z(data, start_y),
+                    triples.start_z(data, end_y),
+                )
+            }
+        } else {
+            (1, 0, 0, triples.array_z.len)
+        };
+        HdtTripleIds {
+            hdt: self.clone(),
+            predicate,
+            object,
+            subject: current_subject,
+            y,
+            z,
+            end_z,
+        }
+    }
+}
+
+/// An iterator on the triples of an [`Hdt`] file as subject, predicate and object ids
+pub(crate) struct HdtTripleIds {
+    hdt: Arc<Hdt>,
+    predicate: Option<u64>,
+    object: Option<u64>,
+    subject: u64,
+    y: u64,
+    z: u64,
+    end_z: u64,
+}
+
+impl Iterator for HdtTripleIds {
+    type Item = [u64; 3];
+
+    fn next(&mut self) -> Option<[u64; 3]> {
+        let data = &self.hdt.data;
+        let triples = &self.hdt.triples;
+        while self.z < self.end_z {
+            let subject = self.subject;
+            let predicate = triples.array_y.get(data, self.y);
+            let object = if self.predicate.unwrap_or(predicate) == predicate {
+                let object = triples.array_z.get(data, self.z);
+                self.z += 1;
+                Some(object)
+            } else {
+                // We skip the objects of this subject-predicate pair
+                self.z = triples.start_z(data, self.y + 1);
+                None
+            };
+            if object.is_none() || triples.bitmap_z.get(data, self.z - 1) {
+                // We move to the next subject-predicate pair
+                if triples.bitmap_y.get(data, self.y) {
+                    self.subject += 1;
+                }
+                self.y += 1;
+            }
+            if let Some(object) = object {
+                if self.object.unwrap_or(object) == object {
+                    return Some([subject, predicate, object]);
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Parses a term serialized as in the HDT dictionaries.
+///
+/// The literals are written like in N-Triples but without escaping.
+pub(crate) fn parse_hdt_term(value: &str) -> Result<Term, io::Error> {
+    if let Some(value) = value.strip_prefix('"') {
+        let end = value
+            .rfind('"')
+            .ok_or_else(|| invalid_data_error(format!("Invalid HDT literal: \"{}", value)))?;
+        let suffix = &value[end + 1..];
+        let value = &value[..end];
+        Ok(if suffix.is_empty() {
+            Literal::new_simple_literal(value)
+        } else if let Some(language) = suffix.strip_prefix('@') {
+            Literal::new_language_tagged_literal(value, language).map_err(invalid_data_error)?
+        } else if let Some(datatype) = suffix
+            .strip_prefix("^^<")
+            .and_then(|datatype| datatype.strip_suffix('>'))
+        {
+            Literal::new_typed_literal(value, NamedNode::new_unchecked(datatype))
+        } else {
+            return Err(invalid_data_error(format!(
+                "Invalid HDT literal: \"{}\"{}",
+                value, suffix
+            )));
+        }
+        .into())
+    } else if let Some(id) = value.strip_prefix("_:") {
+        Ok(BlankNode::new_unchecked(id).into())
+    } else {
+        Ok(NamedNode::new_unchecked(value).into())
+    }
+}
+
+/// Returns the possible serializations of a term in the HDT dictionaries
+pub(crate) fn hdt_term_serializations(term: TermRef<'_>) -> Vec<String> {
+    match term {
+        TermRef::NamedNode(node) => vec![node.as_str().to_owned()],
+        TermRef::BlankNode(node) => vec![format!("_:{}", node.as_str())],
+        TermRef::Literal(literal) => {
+            if let Some(language) = literal.language() {
+                vec![format!("\"{}\"@{}", literal.value(), language)]
+            } else if literal.datatype() == xsd::STRING {
+                // Some HDT files are explicit about the xsd:string datatype
+                vec![
+                    format!("\"{}\"", literal.value()),
+                    format!("\"{}\"^^<{}>", literal.value(), xsd::STRING.as_str()),
+                ]
+            } else {
+                vec![format!(
+                    "\"{}\"^^<{}>",
+                    literal.value(),
+                    literal.datatype().as_str()
+                )]
+            }
+        }
+        TermRef::Triple(_) => Vec::new(),
+    }
+}
+
+struct Dictionary {
+    shared: PfcSection,
+    subjects: PfcSection,
+    predicates: PfcSection,
+    objects: PfcSection,
+}
+
+/// A dictionary section encoded with plain front coding.
+///
+/// The strings are sorted and grouped in blocks.
+/// The first string of each block is written entirely and the other ones as the length of the prefix shared with the previous string and the remaining suffix.
+struct PfcSection {
+    len: u64,
+    block_size: u64,
+    blocks: LogArray,
+    text_start: usize,
+    text_end: usize,
+}
+
+impl PfcSection {
+    fn read(reader: &mut ByteReader<'_>) -> Result<Self, io::Error> {
+        if reader.byte()? != PFC_SECTION {
+            return Err(invalid_data_error(
+                "Only the plain front coding HDT dictionary sections are supported",
+            ));
+        }
+        let len = reader.vbyte()?;
+        let text_len = usize_from(reader.vbyte()?)?;
+        let block_size = reader.vbyte()?;
+        reader.skip(1)?; // CRC8
+        let blocks = LogArray::read(reader)?;
+        let text_start = reader.position;
+        reader.skip(text_len)?;
+        reader.skip(4)?; // CRC32
+        if len > 0 && (block_size == 0 || blocks.len < len.div_ceil(block_size)) {
+            return Err(invalid_data_error("Invalid HDT dictionary section blocks"));
+        }
+        Ok(Self {
+            len,
+            block_size,
+            blocks,
+            text_start,
+            text_end: text_start + text_len,
+        })
+    }
+
+    /// Returns the string with the given id (starting from 1)
+    fn extract(&self, data: &[u8], id: u64) -> Result<Vec<u8>, io::Error> {
+        if id == 0 || id > self.len {
+            return Err(invalid_data_error(format!(
+                "The HDT dictionary section does not contain the id {}",
+                id
+            )));
+        }
+        let index = id - 1;
+        let mut reader = self.block_reader(data, index / self.block_size)?;
+        let mut value = reader.c_string()?.to_vec();
+        for _ in 0..index % self.block_size {
+            reader.next_string(&mut value)?;
+        }
+        Ok(value)
+    }
+
+    /// Returns the id (starting from 1) of the given string
+    fn locate(&self, data: &[u8], value: &[u8]) -> Result<Option<u64>, io::Error> {
+        if self.len == 0 {
+            return Ok(None);
+        }
+        // We look for the last block starting with a string lower or equal to the value
+        let mut start = 0;
+        let mut end = self.len.div_ceil(self.block_size);
+        while start < end {
+            let middle = (start + end) / 2;
+            match self.block_reader(data, middle)?.c_string()?.cmp(value) {
+                Ordering::Equal => return Ok(Some(middle * self.block_size + 1)),
+                Ordering::Less => start = middle + 1,
+                Ordering::Greater => end = middle,
+            }
+        }
+        if start == 0 {
+            return Ok(None);
+        }
+        let block = start - 1;
+        let mut reader = self.block_reader(data, block)?;
+        let mut current = reader.c_string()?.to_vec();
+        for i in 1..self.block_size {
+            let index = block * self.block_size + i;
+            if index >= self.len {
+                break;
+            }
+            reader.next_string(&mut current)?;
+            match current.as_slice().cmp(value) {
+                Ordering::Equal => return Ok(Some(index + 1)),
+                Ordering::Less => (),
+                Ordering::Greater => break,
+            }
+        }
+        Ok(None)
+    }
+
+    fn block_reader<'a>(&self, data: &'a [u8], block: u64) -> Result<ByteReader<'a>, io::Error> {
+        let start = self.text_start + usize_from(self.blocks.get(data, block))?;
+        if start >= self.text_end {
+            return Err(invalid_data_error("Invalid HDT dictionary block position"));
+        }
+        Ok(ByteReader {
+            data: &data[..self.text_end],
+            position: start,
+        })
+    }
+}
+
+/// Triples encoded as two levels of adjacency lists.
+///
+/// `array_y` contains the predicates of each subject and `array_z` the objects of each subject-predicate pair.
+/// The bitmaps mark the last element of each list.
+struct BitmapTriples {
+    bitmap_y: Bitmap,
+    bitmap_z: Bitmap,
+    array_y: LogArray,
+    array_z: LogArray,
+}
+
+impl BitmapTriples {
+    /// The position of the first predicate of the subject in `array_y`
+    fn start_y(&self, data: &[u8], subject: u64) -> u64 {
+        if subject <= 1 {
+            0
+        } else {
+            self.bitmap_y
+                .select(data, subject - 1)
+                .map_or(self.array_y.len, |p| p + 1)
+        }
+    }
+
+    /// The position of the first object of the subject-predicate pair at position `y` in `array_z`
+    fn start_z(&self, data: &[u8], y: u64) -> u64 {
+        if y == 0 {
+            0
+        } else {
+            self.bitmap_z
+                .select(data, y)
+                .map_or(self.array_z.len, |p| p + 1)
+        }
+    }
+}
+
+/// An array of integers all encoded with the same number of bits
+struct LogArray {
+    bits: u64,
+    len: u64,
+    start: usize,
+}
+
+impl LogArray {
+    fn read(reader: &mut ByteReader<'_>) -> Result<Self, io::Error> {
+        if reader.byte()? != LOG_ARRAY {
+            return Err(invalid_data_error(
+                "Only the log arrays HDT sequences are supported",
+            ));
+        }
+        let bits = u64::from(reader.byte()?);
+        if bits > 64 {
+            return Err(invalid_data_error(
+                "HDT sequences elements could not have more than 64 bits",
+            ));
+        }
+        let len = reader.vbyte()?;
+        reader.skip(1)?; // CRC8
+        let start = reader.position;
+        let bit_len = bits
+            .checked_mul(len)
+            .ok_or_else(|| invalid_data_error("Too big HDT sequence"))?;
+        reader.skip(usize_from(bit_len.div_ceil(8))?)?;
+        reader.skip(4)?; // CRC32
+        Ok(Self { bits, len, start })
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn get(&self, data: &[u8], index: u64) -> u64 {
+        if self.bits == 0 || index >= self.len {
+            return 0;
+        }
+        let bit = index * self.bits;
+        let value = read_le_u128(data, self.start + (bit / 8) as usize) >> (bit % 8);
+        if self.bits == 64 {
+            value as u64
+        } else {
+            (value as u64) & ((1 << self.bits) - 1)
+        }
+    }
+}
+
+/// A bitmap with a directory of the number of set bits before each 64 bits word allowing fast rank and select operations
+struct Bitmap {
+    len: u64,
+    ones: u64,
+    start: usize,
+    end: usize,
+    ones_before_word: Vec<u64>,
+}
+
+impl Bitmap {
+    fn read(reader: &mut ByteReader<'_>) -> Result<Self, io::Error> {
+        if reader.byte()? != PLAIN_BITMAP {
+            return Err(invalid_data_error("Only plain HDT bitmaps are supported"));
+        }
+        let len = reader.vbyte()?;
+        reader.skip(1)?; // CRC8
+        let start = reader.position;
+        reader.skip(usize_from(len.div_ceil(8))?)?;
+        let end = reader.position;
+        reader.skip(4)?; // CRC32
+        let mut bitmap = Self {
+            len,
+            ones: 0,
+            start,
+            end,
+            ones_before_word: Vec::new(),
+        };
+        let data = reader.data;
+        let mut ones = 0;
+        bitmap.ones_before_word = (0..len.div_ceil(64))
+            .map(|word| {
+                let before = ones;
+                ones += u64::from(bitmap.word(data, word).count_ones());
+                before
+            })
+            .collect();
+        bitmap.ones = ones;
+        Ok(bitmap)
+    }
+
+    fn get(&self, data: &[u8], index: u64) -> bool {
+        index < self.len && (self.word(data, index / 64) >> (index % 64)) & 1 == 1
+    }
+
+    /// The position of the `rank`-th set bit (starting from 1)
+    fn select(&self, data: &[u8], rank: u64) -> Option<u64> {
+        if rank == 0 || rank > self.ones {
+            return None;
+        }
+        let word = self
+            .ones_before_word
+            .partition_point(|ones| *ones < rank)
+            .checked_sub(1)?;
+        let mut bits = self.word(data, word as u64);
+        for _ in 1..rank - self.ones_before_word[word] {
+            bits &= bits - 1; // We remove the lowest set bit
+        }
+        Some(word as u64 * 64 + u64::from(bits.trailing_zeros()))
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn word(&self, data: &[u8], word: u64) -> u64 {
+        let start = self.start + (word * 8) as usize;
+        let mut bytes = [0; 8];
+        let end = self.end.min(start + 8);
+        bytes[..end - start].copy_from_slice(&data[start..end]);
+        let value = u64::from_le_bytes(bytes);
+        let used_bits = self.len - word * 64;
+        if used_bits < 64 {
+            value & ((1 << used_bits) - 1)
+        } else {
+            value
+        }
+    }
+}
+
+/// Reads a control information block and returns its format and its properties
+fn read_control_information(
+    reader: &mut ByteReader<'_>,
+    kind: u8,
+) -> Result<(String, HashMap<String, String>), io::Error> {
+    if reader.bytes(COOKIE.len())? != COOKIE {
+        return Err(invalid_input_error("The file is not an HDT file"));
+    }
+    if reader.byte()? != kind {
+        return Err(invalid_data_error("Unexpected HDT section"));
+    }
+    let format = String::from_utf8(reader.c_string()?.to_vec()).map_err(invalid_data_error)?;
+    let properties = std::str::from_utf8(reader.c_string()?)
+        .map_err(invalid_data_error)?
+        .split(';')
+        .filter_map(|property| {
+            let mut parts = property.splitn(2, '=');
+            Some((parts.next()?.to_owned(), parts.next()?.to_owned()))
+        })
+        .collect();
+    reader.skip(2)?; // CRC16
+    Ok((format, properties))
+}
+
+struct ByteReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    fn byte(&mut self) -> Result<u8, io::Error> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], io::Error> {
+        let end = self
+            .position
+            .checked_add(len)
+            .filter(|end| *end <= self.data.len())
+            .ok_or_else(|| {
+                io::Error::new(io::ErrorKind::UnexpectedEof, "Unexpected end of HDT file")
+            })?;
+        let bytes = &self.data[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+
+    fn skip(&mut self, len: usize) -> Result<(), io::Error> {
+        self.bytes(len)?;
+        Ok(())
+    }
+
+    /// Reads a variable length integer: 7 bits per byte, the least significant first, the last byte having its most significant bit set
+    fn vbyte(&mut self) -> Result<u64, io::Error> {
+        let mut value = 0;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            value |= u64::from(byte & 0x7F) << shift;
+            if byte & 0x80 != 0 {
+                return Ok(value);
+            }
+        }
+        Err(invalid_data_error("Too long HDT variable length integer"))
+    }
+
+    /// Reads a null terminated string
+    fn c_string(&mut self) -> Result<&'a [u8], io::Error> {
+        let len = self.data[self.position..]
+            .iter()
+            .position(|b| *b == 0)
+            .ok_or_else(|| {
+                io::Error::new(io::ErrorKind::UnexpectedEof, "Unexpected end of HDT file")
+            })?;
+        let value = self.bytes(len)?;
+        self.skip(1)?;
+        Ok(value)
+    }
+
+    /// Reads the next string of a plain front coding block
+    fn next_string(&mut self, previous: &mut Vec<u8>) -> Result<(), io::Error> {
+        let prefix_len = usize_from(self.vbyte()?)?;
+        if prefix_len > previous.len() {
+            return Err(invalid_data_error("Invalid HDT front coding prefix length"));
+        }
+        previous.truncate(prefix_len);
+        previous.extend_from_slice(self.c_string()?);
+        Ok(())
+    }
+}
+
+fn read_le_u128(data: &[u8], start: usize) -> u128 {
+    let mut bytes = [0; 16];
+    let end = data.len().min(start + 16);
+    if start < end {
+        bytes[..end - start].copy_from_slice(&data[start..end]);
+    }
+    u128::from_le_bytes(bytes)
+}
+
+fn usize_from(value: u64) -> Result<usize, io::Error> {
+    usize::try_from(value).map_err(invalid_data_error)
+}
+
+/// Writes a minimal HDT file
+#[cfg(test)]
+pub(crate) fn write_hdt(triples: &[Triple], block_size: u64) -> Vec<u8> {
+    use std::collections::{BTreeMap, BTreeSet};
+
+    fn vbyte(out: &mut Vec<u8>, mut value: u64) {
+        while value > 127 {
+            out.push((value & 127) as u8);
+            value >>= 7;
+        }
+        out.push(value as u8 | 0x80);
+    }
+
+    fn control_information(out: &mut Vec<u8>, kind: u8, format: &str, properties: &str) {
+        out.extend_from_slice(COOKIE);
+        out.push(kind);
+        out.extend_from_slice(format.as_bytes());
+        out.push(0);
+        out.extend_from_slice(properties.as_bytes());
+        out.push(0);
+        out.extend_from_slice(&[0; 2]);
+    }
+
+    fn log_array(out: &mut Vec<u8>, values: &[u64]) {
+        let bits = values
+            .iter()
+            .map(|v| 64 - v.leading_zeros())
+            .max()
+            .unwrap_or(0)
+            .max(1);
+        out.push(LOG_ARRAY);
+        out.push(bits as u8);
+        vbyte(out, values.len() as u64);
+        out.push(0);
+        let mut bytes = vec![0_u8; (values.len() * bits as usize + 7) / 8];
+        for (i, value) in values.iter().enumerate() {
+            for b in 0..bits as usize {
+                if (value >> b) & 1 == 1 {
+                    let bit = i * bits as usize + b;
+                    bytes[bit / 8] |= 1 << (bit % 8);
+                }
+            }
+        }
+        out.extend_from_slice(&bytes);
+        out.extend_from_slice(&[0; 4]);
+    }
+
+    fn bitmap(out: &mut Vec<u8>, values: &[bool]) {
+        out.push(PLAIN_BITMAP);
+        vbyte(out, values.len() as u64);
+        out.push(0);
+        let mut bytes = vec![0_u8; (values.len() + 7) / 8];
+        for (i, value) in values.iter().enumerate() {
+            if *value {
+                bytes[i / 8] |= 1 << (i % 8);
+            }
+        }
+        out.extend_from_slice(&bytes);
+        out.extend_from_slice(&[0; 4]);
+    }
+
+    fn section(out: &mut Vec<u8>, strings: &BTreeSet<String>, block_size: u64) {
+        let mut text = Vec::new();
+        let mut blocks = Vec::new();
+        let mut previous: &[u8] = &[];
+        for (i, string) in strings.iter().enumerate() {
+            let string = string.as_bytes();
+            if i as u64 % block_size == 0 {
+                blocks.push(text.len() as u64);
+                text.extend_from_slice(string);
+            } else {
+                let prefix = previous
+                    .iter()
+                    .zip(string)
+                    .take_while(|(a, b)| a == b)
+                    .count();
+                vbyte(&mut text, prefix as u64);
+                text.extend_from_slice(&string[prefix..]);
+            }
+            text.push(0);
+            previous = string;
+        }
+        blocks.push(text.len() as u64);
+        out.push(PFC_SECTION);
+        vbyte(out, strings.len() as u64);
+        vbyte(out, text.len() as u64);
+        vbyte(out, block_size);
+        out.push(0);
+        log_array(out, &blocks);
+        out.extend_from_slice(&text);
+        out.extend_from_slice(&[0; 4]);
+    }
+
+    let serialize = |term: TermRef<'_>| hdt_term_serializations(term).remove(0);
+    let subjects = triples
+        .iter()
+        .map(|t| serialize(t.subject.as_ref().into()))
+        .collect::<BTreeSet<_>>();
+    let predicates = triples
+        .iter()
+        .map(|t| serialize(t.predicate.as_ref().into()))
+        .collect::<BTreeSet<_>>();
+    let objects = triples
+        .iter()
+        .map(|t| serialize(t.object.as_ref()))
+        .collect::<BTreeSet<_>>();
+    let shared = subjects
+        .intersection(&objects)
+        .cloned()
+        .collect::<BTreeSet<_>>();
+    let subjects = subjects
+        .difference(&shared)
+        .cloned()
+        .collect::<BTreeSet<_>>();
+    let objects = objects
+        .difference(&shared)
+        .cloned()
+        .collect::<BTreeSet<_>>();
+    let ids = |section: &BTreeSet<String>, offset: usize| {
+        section
+            .iter()
+            .enumerate()
+            .map(|(i, s)| (s.clone(), (offset + i + 1) as u64))
+            .collect::<BTreeMap<_, _>>()
+    };
+    let mut subject_ids = ids(&shared, 0);
+    subject_ids.extend(ids(&subjects, shared.len()));
+    let mut object_ids = ids(&shared, 0);
+    object_ids.extend(ids(&objects, shared.len()));
+    let predicate_ids = ids(&predicates, 0);
+    let encoded = triples
+        .iter()
+        .map(|t| {
+            (
+                subject_ids[&serialize(t.subject.as_ref().into())],
+                predicate_ids[&serialize(t.predicate.as_ref().into())],
+                object_ids[&serialize(t.object.as_ref())],
+            )
+        })
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect::<Vec<_>>();
+
+    let (mut array_y, mut bitmap_y, mut array_z, mut bitmap_z) =
+        (Vec::new(), Vec::new(), Vec::new(), Vec::new());
+    for (i, (s, p, o)) in encoded.iter().enumerate() {
+        let next = encoded.get(i + 1);
+        array_z.push(*o);
+        bitmap_z.push(next.map_or(true, |(ns, np, _)| ns != s || np != p));
+        if bitmap_z[bitmap_z.len() - 1] {
+            array_y.push(*p);
+            bitmap_y.push(next.map_or(true, |(ns, _, _)| ns != s));
+        }
+    }
+
+    let mut out = Vec::new();
+    control_information(
+        &mut out,
+        GLOBAL_CONTROL,
+        "<http://purl.org/HDT/hdt#HDTv1>",
+        "",
+    );
+    let header = "<file:///example.hdt> <http://purl.org/HDT/hdt#triples> _:triples .\n";
+    control_information(
+        &mut out,
+        HEADER_CONTROL,
+        "ntriples",
+        &format!("length={};", header.len()),
+    );
+    out.extend_from_slice(header.as_bytes());
+    control_information(
+        &mut out,
+        DICTIONARY_CONTROL,
+        FOUR_SECTION_DICTIONARY,
+        "mapping=1;",
+    );
+    section(&mut out, &shared, block_size);
+    section(&mut out, &subjects, block_size);
+    section(&mut out, &predicates, block_size);
+    section(&mut out, &objects, block_size);
+    control_information(&mut out, TRIPLES_CONTROL, BITMAP_TRIPLES, "order=1;");
+    bitmap(&mut out, &bitmap_y);
+    bitmap(&mut out, &bitmap_z);
+    log_array(&mut out, &array_y);
+    log_array(&mut out, &array_z);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example() -> Vec<Triple> {
+        let ex = |name: &str| NamedNode::new_unchecked(format!("http://example.com/{}", name));
+        let mut triples = Vec::new();
+        for i in 0..20 {
+            triples.push(Triple::new(
+                ex(&format!("s{}", i)),
+                ex("p"),
+                Literal::from(i),
+            ));
+            triples.push(Triple::new(
+                ex(&format!("s{}", i)),
+                ex("q"),
+                ex(&format!("s{}", i + 1)),
+            ));
+        }
+        triples.push(Triple::new(
+            BlankNode::new_unchecked("b"),
+            ex("p"),
+            Literal::new_language_tagged_literal_unchecked("foo\"\nbar", "en"),
+        ));
+        triples.push(Triple::new(
+            ex("s0"),
+            ex("r"),
+            Literal::new_simple_literal("baz"),
+        ));
+        triples
+    }
+
+    #[test]
+    fn test_dictionary() -> io::Result<()> {
+        let triples = example();
+        for block_size in &[1, 3, 16] {
+            let hdt = Hdt::from_bytes(write_hdt(&triples, *block_size))?;
+            assert_eq!(hdt.len(), triples.len() as u64);
+            for triple in &triples {
+                for (role, term) in &[
+                    (TripleRole::Subject, Term::from(triple.subject.clone())),
+                    (TripleRole::Predicate, triple.predicate.clone().into()),
+                    (TripleRole::Object, triple.object.clone()),
+                ] {
+                    let id = hdt
+                        .locate(*role, &hdt_term_serializations(term.as_ref())[0])?
+                        .unwrap();
+                    assert_eq!(&parse_hdt_term(&hdt.extract(*role, id)?)?, term);
+                }
+            }
+            assert_eq!(
+                hdt.locate(TripleRole::Subject, "http://example.com/s20")?,
+                None
+            );
+            assert_eq!(
+                hdt.locate(TripleRole::Object, "http://example.com/p")?,
+                None
+            );
+            assert_eq!(hdt.locate(TripleRole::Predicate, "")?, None);
+            assert_eq!(
+                hdt.locate(TripleRole::Predicate, "http://example.com/z")?,
+                None
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_triple_ids() -> io::Result<()> {
+        let triples = example();
+        let hdt = Arc::new(Hdt::from_bytes(write_hdt(&triples, 4))?);
+        let all = hdt.triple_ids(None, None, None).collect::<Vec<_>>();
+        assert_eq!(all.len(), triples.len());
+        let mut sorted = all.clone();
+        sorted.sort_unstable();
+        assert_eq!(all, sorted);
+
+        for pattern in &[
+            (Some(all[5][0]), None, None),
+            (Some(all[5][0]), Some(all[5][1]), None),
+            (Some(all[5][0]), None, Some(all[5][2])),
+            (Some(all[5][0]), Some(all[5][1]), Some(all[5][2])),
+            (None, Some(all[5][1]), None),
+            (None, Some(all[5][1]), Some(all[5][2])),
+            (None, None, Some(all[5][2])),
+            (Some(1000), None, None),
+        ] {
+            let expected = all
+                .iter()
+                .filter(|t| {
+                    pattern.0.map_or(true, |s| s == t[0])
+                        && pattern.1.map_or(true, |p| p == t[1])
+                        && pattern.2.map_or(true, |o| o == t[2])
+                })
+                .cloned()
+                .collect::<Vec<_>>();
+            assert_eq!(
+                hdt.triple_ids(pattern.0, pattern.1, pattern.2)
+                    .collect::<Vec<_>>(),
+                expected
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_file() {
+        assert!(Hdt::from_bytes(b"foo".to_vec()).is_err());
+        let mut file = write_hdt(&example(), 4);
+        file.truncate(file.len() - 10);
+        assert!(Hdt::from_bytes(file).is_err());
+    }
+}
@@ -0,0 +1,2074 @@
+//! Parser and formatter for [JSON-LD 1.1](https://www.w3.org/TR/json-ld11/).
+//!
+//! The parser applies the [expansion](https://www.w3.org/TR/json-ld11-api/#expansion-algorithm)
+//! and the [RDF serialization](https://www.w3.org/TR/json-ld11-api/#deserialize-json-ld-to-rdf-algorithm) algorithms
+//! in a single walk over each document. Remote contexts are never fetched: they must be provided by the caller.
+//!
+//! The formatter writes one node object per subject and graph as soon as all the quads of this subject have been given.
+
+use crate::error::{invalid_data_error, invalid_input_error};
+use crate::model::vocab::{rdf, xsd};
+use crate::model::*;
+use oxiri::Iri;
+use serde_json::de::IoRead;
+use serde_json::{Map, Number, StreamDeserializer, Value};
+use std::collections::{HashMap, VecDeque};
+use std::io;
+use std::io::{BufRead, Write};
+use std::slice;
+use std::sync::Arc;
+
+const RDF_JSON: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#JSON";
+/// The maximal number of nested remote contexts
+const MAX_REMOTE_CONTEXTS: usize = 32;
+const CONTAINERS: &[&str] = &[
+    "@graph",
+    "@id",
+    "@index",
+    "@language",
+    "@list",
+    "@set",
+    "@type",
+];
+
+/// A parser returning the quads of a stream of JSON-LD documents.
+///
+/// The input could contain several concatenated JSON documents: they are parsed one after the other
+/// and only one document is kept in memory at a time.
+pub(crate) struct JsonLdParser<R: BufRead> {
+    documents: StreamDeserializer<'static, IoRead<R>, Value>,
+    base_iri: Option<Iri<String>>,
+    contexts: HashMap<String, Value>,
+    output: VecDeque<Quad>,
+}
+
+impl<R: BufRead> JsonLdParser<R> {
+    /// Builds a new parser
+    ///
+    /// `contexts` are the remote context documents that could be referenced by their IRI.
+    pub fn new(reader: R, base_iri: Option<Iri<String>>, contexts: HashMap<String, Value>) -> Self {
+        Self {
+            documents: serde_json::Deserializer::from_reader(reader).into_iter(),
+            base_iri,
+            contexts,
+            output: VecDeque::new(),
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for JsonLdParser<R> {
+    type Item = Result<Quad, io::Error>;
+
+    fn next(&mut self) -> Option<Result<Quad, io::Error>> {
+        loop {
+            if let Some(quad) = self.output.pop_front() {
+                return Some(Ok(quad));
+            }
+            let document = match self.documents.next()? {
+                Ok(document) => document,
+                Err(error) => return Some(Err(error.into())),
+            };
+            let mut expander = Expander {
+                contexts: &self.contexts,
+                base_iri: &self.base_iri,
+                bnodes: HashMap::new(),
+                output: &mut self.output,
+            };
+            if let Err(error) = expander.process_document(&document) {
+                self.output.clear();
+                return Some(Err(error));
+            }
+        }
+    }
+}
+
+/// Parses a context document given by the user
+///
+/// The document could be a JSON-LD document with a `@context` member or directly a context.
+pub(crate) fn parse_context_document(document: &str) -> Result<Value, io::Error> {
+    match serde_json::from_str(document)? {
+        Value::Object(mut document) if document.contains_key("@context") => {
+            Ok(document.remove("@context").unwrap_or(Value::Null))
+        }
+        context => Ok(context),
+    }
+}
+
+/// A processed [active context](https://www.w3.org/TR/json-ld11-api/#dfn-active-context)
+#[derive(Clone, Default)]
+struct Context {
+    base: Option<Iri<String>>,
+    vocab: Option<String>,
+    language: Option<String>,
+    terms: HashMap<String, Arc<TermDefinition>>,
+    /// The context to use in the nested node objects if this context should not be propagated
+    previous: Option<Arc<Context>>,
+}
+
+#[derive(Default)]
+struct TermDefinition {
+    /// The IRI or the keyword the term maps to. `None` if the term is explicitly mapped to `null`
+    iri: Option<String>,
+    reverse: bool,
+    type_mapping: Option<String>,
+    /// The language mapping. `Some(None)` if the term explicitly has no language
+    language: Option<Option<String>>,
+    container: Vec<String>,
+    context: Option<Value>,
+    prefix: bool,
+}
+
+impl TermDefinition {
+    fn has_container(&self, container: &str) -> bool {
+        self.container.iter().any(|c| c == container)
+    }
+}
+
+impl Context {
+    /// [IRI expansion](https://www.w3.org/TR/json-ld11-api/#iri-expansion)
+    ///
+    /// Returns `None` if the value is mapped to `null` or looks like an unknown keyword.
+    fn expand_iri(&self, value: &str, document_relative: bool, vocab: bool) -> Option<String> {
+        if is_keyword(value) {
+            return Some(value.to_owned());
+        }
+        if looks_like_keyword(value) {
+            return None;
+        }
+        if vocab {
+            if let Some(definition) = self.terms.get(value) {
+                return definition.iri.clone();
+            }
+        }
+        if let Some((prefix, suffix)) = split_compact_iri(value) {
+            if prefix == "_" || suffix.starts_with("//") {
+                return Some(value.to_owned());
+            }
+            if let Some(definition) = self.terms.get(prefix) {
+                if let (Some(iri), true) = (&definition.iri, definition.prefix) {
+                    return Some(format!("{}{}", iri, suffix));
+                }
+            }
+            if Iri::parse(value).is_ok() {
+                return Some(value.to_owned());
+            }
+        }
+        if vocab {
+            if let Some(vocab) = &self.vocab {
+                return Some(format!("{}{}", vocab, value));
+            }
+        }
+        if document_relative {
+            if let Some(base) = &self.base {
+                return Some(
+                    base.resolve(value)
+                        .map_or_else(|_| value.to_owned(), Iri::into_inner),
+                );
+            }
+        }
+        Some(value.to_owned())
+    }
+
+    fn is_keyword_key(&self, key: &str, keyword: &str) -> bool {
+        self.expand_iri(key, false, true).as_deref() == Some(keyword)
+    }
+}
+
+/// Expands JSON-LD documents directly into RDF quads
+struct Expander<'a> {
+    contexts: &'a HashMap<String, Value>,
+    base_iri: &'a Option<Iri<String>>,
+    bnodes: HashMap<String, BlankNode>,
+    output: &'a mut VecDeque<Quad>,
+}
+
+impl<'a> Expander<'a> {
+    fn process_document(&mut self, document: &Value) -> Result<(), io::Error> {
+        let context = Arc::new(Context {
+            base: self.base_iri.clone(),
+            ..Context::default()
+        });
+        self.process_top_level(&context, document)
+    }
+
+    fn process_top_level(&mut self, active: &Arc<Context>, value: &Value) -> Result<(), io::Error> {
+        match value {
+            Value::Array(items) => {
+                for item in items {
+                    self.process_top_level(active, item)?;
+                }
+            }
+            Value::Object(node) => {
+                self.process_node(active, node, &GraphName::DefaultGraph, true)?;
+            }
+            _ => (),
+        }
+        Ok(())
+    }
+
+    /// [Context processing](https://www.w3.org/TR/json-ld11-api/#context-processing-algorithm)
+    fn process_context(
+        &self,
+        active: &Arc<Context>,
+        local: &Value,
+        remote: &mut Vec<String>,
+        propagate: bool,
+    ) -> Result<Arc<Context>, io::Error> {
+        let mut propagate = propagate;
+        let mut result = active.as_ref().clone();
+        let locals = if let Value::Array(locals) = local {
+            locals.as_slice()
+        } else {
+            slice::from_ref(local)
+        };
+        for local in locals {
+            match local {
+                Value::Null => {
+                    result = Context {
+                        base: self.base_iri.clone(),
+                        ..Context::default()
+                    }
+                }
+                Value::String(iri) => {
+                    let iri = resolve_context_iri(&result, iri);
+                    if remote.contains(&iri) || remote.len() >= MAX_REMOTE_CONTEXTS {
+                        return Err(invalid_data_error(format!(
+                            "Recursive inclusion of the JSON-LD context {}",
+                            iri
+                        )));
+                    }
+                    let context = self.load_context(&iri)?;
+                    remote.push(iri);
+                    result = self
+                        .process_context(&Arc::new(result), context, remote, true)?
+                        .as_ref()
+                        .clone();
+                    remote.pop();
+                }
+                Value::Object(local) => {
+                    if let Some(value) = local.get("@propagate") {
+                        propagate = value.as_bool().ok_or_else(|| {
+                            invalid_data_error("@propagate value must be a boolean")
+                        })?;
+                    }
+                    self.process_context_object(&mut result, local, remote)?;
+                }
+                _ => return Err(invalid_data_error("Invalid JSON-LD local context")),
+            }
+        }
+        if !propagate && result.previous.is_none() {
+            result.previous = Some(active.clone());
+        }
+        Ok(Arc::new(result))
+    }
+
+    fn process_context_object(
+        &self,
+        result: &mut Context,
+        local: &Map<String, Value>,
+        remote: &mut Vec<String>,
+    ) -> Result<(), io::Error> {
+        if let Some(version) = local.get("@version") {
+            if version.as_f64() != Some(1.1) {
+                return Err(invalid_data_error("The only supported @version is 1.1"));
+            }
+        }
+        let imported;
+        let local = if let Some(import) = local.get("@import") {
+            let iri = import
+                .as_str()
+                .ok_or_else(|| invalid_data_error("@import value must be a string"))?;
+            let mut merged = match self.load_context(&resolve_context_iri(result, iri))? {
+                Value::Object(imported) if !imported.contains_key("@import") => imported.clone(),
+                _ => {
+                    return Err(invalid_data_error(format!(
+                        "The imported JSON-LD context {} must be a single object without @import",
+                        iri
+                    )))
+                }
+            };
+            for (key, value) in local {
+                if key != "@import" {
+                    merged.insert(key.clone(), value.clone());
+                }
+            }
+            imported = merged;
+            &imported
+        } else {
+            local
+        };
+        if let Some(base) = local.get("@base") {
+            if remote.is_empty() {
+                result.base = match base {
+                    Value::Null => None,
+                    Value::String(base) => Some(
+                        if let Some(current) = &result.base {
+                            current.resolve(base)
+                        } else {
+                            Iri::parse(base.clone())
+                        }
+                        .map_err(invalid_data_error)?,
+                    ),
+                    _ => return Err(invalid_data_error("@base value must be a string")),
+                }
+            }
+        }
+        if let Some(vocab) = local.get("@vocab") {
+            result.vocab = match vocab {
+                Value::Null => None,
+                Value::String(vocab) => Some(
+                    result
+                        .expand_iri(vocab, true, true)
+                        .unwrap_or_else(|| vocab.clone()),
+                ),
+                _ => return Err(invalid_data_error("@vocab value must be a string")),
+            }
+        }
+        if let Some(language) = local.get("@language") {
+            result.language = match language {
+                Value::Null => None,
+                Value::String(language) => Some(language.to_ascii_lowercase()),
+                _ => return Err(invalid_data_error("@language value must be a string")),
+            }
+        }
+        if let Some(direction) = local.get("@direction") {
+            if !matches!(direction, Value::Null)
+                && direction.as_str() != Some("ltr")
+                && direction.as_str() != Some("rtl")
+            {
+                return Err(invalid_data_error(
+                    "@direction value must be 'ltr' or 'rtl'",
+                ));
+            }
+        }
+        let mut defined = HashMap::new();
+        for term in local.keys() {
+            if !matches!(
+                term.as_str(),
+                "@base"
+                    | "@direction"
+                    | "@import"
+                    | "@language"
+                    | "@propagate"
+                    | "@protected"
+                    | "@version"
+                    | "@vocab"
+            ) {
+                self.create_term_definition(result, local, term, &mut defined, remote)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// [Create term definition](https://www.w3.org/TR/json-ld11-api/#create-term-definition)
+    fn create_term_definition(
+        &self,
+        active: &mut Context,
+        local: &Map<String, Value>,
+        term: &str,
+        defined: &mut HashMap<String, bool>,
+        remote: &mut Vec<String>,
+    ) -> Result<(), io::Error> {
+        match defined.get(term) {
+            Some(true) => return Ok(()),
+            Some(false) => {
+                return Err(invalid_data_error(format!(
+                    "Cyclic IRI mapping for the JSON-LD term {}",
+                    term
+                )))
+            }
+            None => (),
+        }
+        if term.is_empty() {
+            return Err(invalid_data_error("JSON-LD terms must not be empty"));
+        }
+        if term == "@type" {
+            // Only the @container of @type could be set to @set
+            let is_valid = if let Value::Object(value) = &local[term] {
+                value.get("@container").and_then(Value::as_str) == Some("@set")
+                    && value
+                        .keys()
+                        .all(|key| key == "@container" || key == "@protected")
+            } else {
+                false
+            };
+            if !is_valid {
+                return Err(invalid_data_error(
+                    "The JSON-LD keyword @type could only be redefined with a @set container",
+                ));
+            }
+            defined.insert(term.to_owned(), true);
+            return Ok(());
+        }
+        if looks_like_keyword(term) {
+            // The unknown keywords are ignored
+            defined.insert(term.to_owned(), true);
+            return Ok(());
+        }
+        if is_keyword(term) {
+            return Err(invalid_data_error(format!(
+                "The JSON-LD keyword {} could not be redefined",
+                term
+            )));
+        }
+        defined.insert(term.to_owned(), false);
+
+        let simple_term;
+        let value = match &local[term] {
+            Value::Null => {
+                simple_term = false;
+                let mut value = Map::new();
+                value.insert("@id".into(), Value::Null);
+                value
+            }
+            Value::String(id) => {
+                simple_term = true;
+                let mut value = Map::new();
+                value.insert("@id".into(), Value::String(id.clone()));
+                value
+            }
+            Value::Object(value) => {
+                simple_term = false;
+                value.clone()
+            }
+            _ => {
+                return Err(invalid_data_error(format!(
+                    "Invalid JSON-LD term definition for {}",
+                    term
+                )))
+            }
+        };
+
+        let mut definition = TermDefinition::default();
+        if let Some(reverse) = value.get("@reverse") {
+            if value.contains_key("@id") || value.contains_key("@nest") {
+                return Err(invalid_data_error(
+                    "A reverse JSON-LD property must not have @id or @nest",
+                ));
+            }
+            let reverse = reverse
+                .as_str()
+                .ok_or_else(|| invalid_data_error("@reverse value must be a string"))?;
+            definition.iri = self
+                .expand_iri_for_definition(active, local, reverse, defined, remote)?
+                .filter(|iri| iri.contains(':'));
+            if definition.iri.is_none() {
+                return Err(invalid_data_error(format!(
+                    "Invalid JSON-LD reverse IRI mapping {}",
+                    reverse
+                )));
+            }
+            definition.reverse = true;
+        } else if let Some(id) = value.get("@id").filter(|id| id.as_str() != Some(term)) {
+            match id {
+                Value::Null => (),
+                Value::String(id) => {
+                    if !is_keyword(id) && looks_like_keyword(id) {
+                        defined.insert(term.to_owned(), true);
+                        return Ok(());
+                    }
+                    let iri = self
+                        .expand_iri_for_definition(active, local, id, defined, remote)?
+                        .filter(|iri| is_keyword(iri) || iri.contains(':'))
+                        .ok_or_else(|| {
+                            invalid_data_error(format!("Invalid JSON-LD IRI mapping {}", id))
+                        })?;
+                    if iri == "@context" {
+                        return Err(invalid_data_error("@context could not be aliased"));
+                    }
+                    definition.prefix = simple_term
+                        && split_compact_iri(term).is_none()
+                        && !term.contains('/')
+                        && (iri.starts_with("_:") || iri.ends_with(is_gen_delim));
+                    definition.iri = Some(iri);
+                }
+                _ => return Err(invalid_data_error("@id value must be a string")),
+            }
+        } else if let Some((prefix, suffix)) = split_compact_iri(term) {
+            if local.contains_key(prefix) {
+                self.create_term_definition(active, local, prefix, defined, remote)?;
+            }
+            definition.iri = Some(
+                match active.terms.get(prefix).and_then(|d| d.iri.as_ref()) {
+                    Some(iri) => format!("{}{}", iri, suffix),
+                    None => term.to_owned(),
+                },
+            );
+        } else if term.contains('/') {
+            definition.iri = active
+                .expand_iri(term, false, true)
+                .filter(|iri| iri.contains(':'));
+            if definition.iri.is_none() {
+                return Err(invalid_data_error(format!(
+                    "Invalid JSON-LD IRI mapping {}",
+                    term
+                )));
+            }
+        } else if let Some(vocab) = &active.vocab {
+            definition.iri = Some(format!("{}{}", vocab, term));
+        } else {
+            return Err(invalid_data_error(format!(
+                "The JSON-LD term {} has no IRI mapping and there is no @vocab",
+                term
+            )));
+        }
+
+        for (key, value) in &value {
+            match key.as_str() {
+                "@id" | "@reverse" | "@nest" | "@index" | "@protected" | "@direction" => (),
+                "@type" => {
+                    let type_mapping = value
+                        .as_str()
+                        .ok_or_else(|| invalid_data_error("@type value must be a string"))?;
+                    definition.type_mapping = Some(
+                        if matches!(type_mapping, "@id" | "@json" | "@none" | "@vocab") {
+                            type_mapping.to_owned()
+                        } else {
+                            self.expand_iri_for_definition(
+                                active,
+                                local,
+                                type_mapping,
+                                defined,
+                                remote,
+                            )?
+                            .filter(|iri| iri.contains(':'))
+                            .ok_or_else(|| {
+                                invalid_data_error(format!(
+                                    "Invalid JSON-LD type mapping {}",
+                                    type_mapping
+                                ))
+                            })?
+                        },
+                    );
+                }
+                "@container" => {
+                    let containers = if let Value::Array(containers) = value {
+                        containers.as_slice()
+                    } else {
+                        slice::from_ref(value)
+                    };
+                    for container in containers {
+                        match container.as_str() {
+                            Some(container) if CONTAINERS.contains(&container) => {
+                                definition.container.push(container.to_owned())
+                            }
+                            _ => {
+                                return Err(invalid_data_error(format!(
+                                    "Invalid JSON-LD container mapping for {}",
+                                    term
+                                )))
+                            }
+                        }
+                    }
+                }
+                "@language" => {
+                    definition.language = Some(match value {
+                        Value::Null => None,
+                        Value::String(language) => Some(language.to_ascii_lowercase()),
+                        _ => return Err(invalid_data_error("@language value must be a string")),
+                    })
+                }
+                "@context" => definition.context = Some(value.clone()),
+                "@prefix" => {
+                    if split_compact_iri(term).is_some() || term.contains('/') {
+                        return Err(invalid_data_error(format!(
+                            "The JSON-LD term {} could not be a prefix",
+                            term
+                        )));
+                    }
+                    definition.prefix = value
+                        .as_bool()
+                        .ok_or_else(|| invalid_data_error("@prefix value must be a boolean"))?;
+                }
+                _ => {
+                    return Err(invalid_data_error(format!(
+                        "Invalid JSON-LD term definition member {}",
+                        key
+                    )))
+                }
+            }
+        }
+        active.terms.insert(term.to_owned(), Arc::new(definition));
+        defined.insert(term.to_owned(), true);
+        Ok(())
+    }
+
+    /// IRI expansion that first defines the terms of the local context it depends on
+    fn expand_iri_for_definition(
+        &self,
+        active: &mut Context,
+        local: &Map<String, Value>,
+        value: &str,
+        defined: &mut HashMap<String, bool>,
+        remote: &mut Vec<String>,
+    ) -> Result<Option<String>, io::Error> {
+        if local.contains_key(value) && defined.get(value) != Some(&true) {
+            self.create_term_definition(active, local, value, defined, remote)?;
+        }
+        if let Some((prefix, _)) = split_compact_iri(value) {
+            if local.contains_key(prefix) && defined.get(prefix) != Some(&true) {
+                self.create_term_definition(active, local, prefix, defined, remote)?;
+            }
+        }
+        Ok(active.expand_iri(value, false, true))
+    }
+
+    fn load_context(&self, iri: &str) -> Result<&'a Value, io::Error> {
+        let document = self.contexts.get(iri).ok_or_else(|| {
+            invalid_data_error(format!(
+                "The JSON-LD context {} has not been provided. Remote contexts are not fetched",
+                iri
+            ))
+        })?;
+        Ok(document)
+    }
+
+    /// Emits the quads of a node object and returns its identifier
+    fn process_node(
+        &mut self,
+        active: &Arc<Context>,
+        node: &Map<String, Value>,
+        graph: &GraphName,
+        top_level: bool,
+    ) -> Result<Option<Subject>, io::Error> {
+        let mut active = active.previous.clone().unwrap_or_else(|| active.clone());
+        if let Some(local) = node.get("@context") {
+            active = self.process_context(&active, local, &mut Vec::new(), true)?;
+        }
+
+        // Type scoped contexts
+        let type_scoped = active.clone();
+        let mut types = Vec::new();
+        for (key, value) in node {
+            if type_scoped.is_keyword_key(key, "@type") {
+                for value in as_array(value) {
+                    types.push(
+                        value
+                            .as_str()
+                            .ok_or_else(|| invalid_data_error("@type values must be strings"))?,
+                    );
+                }
+            }
+        }
+        let mut sorted_types = types.clone();
+        sorted_types.sort_unstable();
+        for value in sorted_types {
+            if let Some(context) = type_scoped
+                .terms
+                .get(value)
+                .and_then(|definition| definition.context.as_ref())
+            {
+                active = self.process_context(&active, context, &mut Vec::new(), false)?;
+            }
+        }
+
+        if top_level
+            && node.keys().all(|key| {
+                active.is_keyword_key(key, "@context") || active.is_keyword_key(key, "@graph")
+            })
+        {
+            // Top level object only containing the default graph nodes
+            for (key, value) in node {
+                if active.is_keyword_key(key, "@graph") {
+                    for item in as_array(value) {
+                        if let Value::Object(item) = item {
+                            self.process_node(&active, item, graph, false)?;
+                        }
+                    }
+                }
+            }
+            return Ok(None);
+        }
+
+        let mut subject = None;
+        for (key, value) in node {
+            if active.is_keyword_key(key, "@id") {
+                let id = value
+                    .as_str()
+                    .ok_or_else(|| invalid_data_error("@id value must be a string"))?;
+                subject = Some(
+                    match active
+                        .expand_iri(id, true, false)
+                        .and_then(|id| self.node_from_id(&id))
+                    {
+                        Some(subject) => subject,
+                        None => return Ok(None),
+                    },
+                );
+            }
+        }
+        let subject = subject.unwrap_or_else(|| BlankNode::default().into());
+
+        for value in types {
+            if let Some(object) = type_scoped
+                .expand_iri(value, true, true)
+                .and_then(|iri| self.node_from_id(&iri))
+            {
+                self.emit(subject.clone(), rdf::TYPE.into(), object.into(), graph);
+            }
+        }
+        self.process_properties(&active, &subject, node, graph)?;
+        Ok(Some(subject))
+    }
+
+    fn process_properties(
+        &mut self,
+        active: &Arc<Context>,
+        subject: &Subject,
+        node: &Map<String, Value>,
+        graph: &GraphName,
+    ) -> Result<(), io::Error> {
+        for (key, value) in node {
+            let property = if let Some(property) = active.expand_iri(key, false, true) {
+                property
+            } else {
+                continue;
+            };
+            match property.as_str() {
+                "@graph" => {
+                    let graph_name = subject_to_graph_name(subject.clone());
+                    for item in as_array(value) {
+                        if let Value::Object(item) = item {
+                            self.process_node(active, item, &graph_name, false)?;
+                        }
+                    }
+                }
+                "@reverse" => {
+                    let reverse = if let Value::Object(reverse) = value {
+                        reverse
+                    } else {
+                        return Err(invalid_data_error("@reverse value must be an object"));
+                    };
+                    for (key, value) in reverse {
+                        if let Some(predicate) = self.property(active, key) {
+                            for object in self.process_property_value(active, key, value, graph)? {
+                                if let Some(object) = term_to_subject(object) {
+                                    self.emit(
+                                        object,
+                                        predicate.clone(),
+                                        subject.clone().into(),
+                                        graph,
+                                    );
+                                }
+                            }
+                        }
+                    }
+                }
+                "@included" => {
+                    for item in as_array(value) {
+                        if let Value::Object(item) = item {
+                            self.process_node(active, item, graph, false)?;
+                        }
+                    }
+                }
+                "@nest" => {
+                    for item in as_array(value) {
+                        if let Value::Object(item) = item {
+                            self.process_properties(active, subject, item, graph)?;
+                        }
+                    }
+                }
+                property if property.starts_with('@') => (),
+                _ => {
+                    let predicate = if let Some(predicate) = self.property(active, key) {
+                        predicate
+                    } else {
+                        continue;
+                    };
+                    let reverse = active.terms.get(key).map_or(false, |d| d.reverse);
+                    for object in self.process_property_value(active, key, value, graph)? {
+                        if reverse {
+                            if let Some(object) = term_to_subject(object) {
+                                self.emit(object, predicate.clone(), subject.clone().into(), graph);
+                            }
+                        } else {
+                            self.emit(subject.clone(), predicate.clone(), object, graph);
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the predicate of a property if it is an absolute IRI
+    fn property(&self, active: &Context, key: &str) -> Option<NamedNode> {
+        let iri = active.expand_iri(key, false, true)?;
+        if iri.starts_with('@') || iri.starts_with("_:") {
+            return None;
+        }
+        Iri::parse(iri)
+            .ok()
+            .map(|iri| NamedNode::new_unchecked(iri.into_inner()))
+    }
+
+    /// Returns the objects of a property
+    fn process_property_value(
+        &mut self,
+        active: &Arc<Context>,
+        key: &str,
+        value: &Value,
+        graph: &GraphName,
+    ) -> Result<Vec<Term>, io::Error> {
+        let definition = active.terms.get(key).cloned();
+        let active = if let Some(context) = definition.as_ref().and_then(|d| d.context.as_ref()) {
+            let mut scoped = self.process_context(active, context, &mut Vec::new(), true)?;
+            if let Some(previous) = &active.previous {
+                // The nested node objects revert the non propagated contexts before applying the property-scoped one
+                let previous = self.process_context(previous, context, &mut Vec::new(), true)?;
+                Arc::make_mut(&mut scoped).previous = Some(previous);
+            }
+            scoped
+        } else {
+            active.clone()
+        };
+        let definition = definition.as_deref();
+        let has_container = |container| definition.map_or(false, |d| d.has_container(container));
+        let mut objects = Vec::new();
+
+        if let (true, Value::Object(map)) = (has_container("@language"), value) {
+            for (language, values) in map {
+                let language = if active.is_keyword_key(language, "@none") {
+                    None
+                } else {
+                    Some(language.to_ascii_lowercase())
+                };
+                for value in as_array(values) {
+                    match value {
+                        Value::String(value) => {
+                            if let Some(literal) = new_literal(value, language.clone()) {
+                                objects.push(literal.into());
+                            }
+                        }
+                        Value::Null => (),
+                        _ => return Err(invalid_data_error("Language map values must be strings")),
+                    }
+                }
+            }
+        } else if let (true, Value::Object(map)) = (has_container("@graph"), value) {
+            if has_container("@id") || has_container("@index") {
+                for (key, value) in map {
+                    let graph_name = if has_container("@id") && !active.is_keyword_key(key, "@none")
+                    {
+                        active
+                            .expand_iri(key, true, false)
+                            .and_then(|id| self.node_from_id(&id))
+                    } else {
+                        None
+                    };
+                    objects.push(self.process_graph_object(&active, value, graph_name)?);
+                }
+            } else {
+                objects.push(self.process_graph_object(&active, value, None)?);
+            }
+        } else if let (true, Value::Array(values)) = (has_container("@graph"), value) {
+            for value in values {
+                objects.push(self.process_graph_object(&active, value, None)?);
+            }
+        } else if let (true, Value::Object(map)) = (has_container("@index"), value) {
+            for value in map.values() {
+                self.push_values(&active, definition, value, graph, &mut objects)?;
+            }
+        } else if let (true, Value::Object(map)) = (has_container("@id"), value) {
+            for (id, values) in map {
+                for value in as_array(values) {
+                    let mut node = if let Value::Object(node) = value {
+                        node.clone()
+                    } else {
+                        return Err(invalid_data_error("Id map values must be node objects"));
+                    };
+                    if !active.is_keyword_key(id, "@none") {
+                        node.insert("@id".into(), Value::String(id.clone()));
+                    }
+                    if let Some(subject) = self.process_node(&active, &node, graph, false)? {
+                        objects.push(subject.into());
+                    }
+                }
+            }
+        } else if let (true, Value::Object(map)) = (has_container("@type"), value) {
+            for (type_value, values) in map {
+                for value in as_array(values) {
+                    let mut node = match value {
+                        Value::Object(node) => node.clone(),
+                        Value::String(id) => {
+                            let mut node = Map::new();
+                            node.insert("@id".into(), Value::String(id.clone()));
+                            node
+                        }
+                        _ => {
+                            return Err(invalid_data_error("Type map values must be node objects"))
+                        }
+                    };
+                    if !active.is_keyword_key(type_value, "@none") {
+                        let mut types = vec![Value::String(type_value.clone())];
+                        if let Some(existing) = node.remove("@type") {
+                            types.extend(as_array(&existing).iter().cloned());
+                        }
+                        node.insert("@type".into(), Value::Array(types));
+                    }
+                    if let Some(subject) = self.process_node(&active, &node, graph, false)? {
+                        objects.push(subject.into());
+                    }
+                }
+            }
+        } else if has_container("@list") && !is_list_object(&active, value) {
+            objects.push(self.build_list(&active, definition, as_array(value), graph)?);
+        } else {
+            self.push_values(&active, definition, value, graph, &mut objects)?;
+        }
+        Ok(objects)
+    }
+
+    /// Emits the content of a graph object in a new graph and returns the graph name
+    fn process_graph_object(
+        &mut self,
+        active: &Arc<Context>,
+        value: &Value,
+        graph_name: Option<Subject>,
+    ) -> Result<Term, io::Error> {
+        let graph_name = graph_name.unwrap_or_else(|| BlankNode::default().into());
+        let graph = subject_to_graph_name(graph_name.clone());
+        for item in as_array(value) {
+            if let Value::Object(item) = item {
+                let content = item
+                    .iter()
+                    .find(|(key, _)| active.is_keyword_key(key, "@graph"))
+                    .map(|(_, content)| content);
+                if let Some(content) = content {
+                    for node in as_array(content) {
+                        if let Value::Object(node) = node {
+                            self.process_node(active, node, &graph, false)?;
+                        }
+                    }
+                } else {
+                    self.process_node(active, item, &graph, false)?;
+                }
+            }
+        }
+        Ok(graph_name.into())
+    }
+
+    fn push_values(
+        &mut self,
+        active: &Arc<Context>,
+        definition: Option<&TermDefinition>,
+        value: &Value,
+        graph: &GraphName,
+        objects: &mut Vec<Term>,
+    ) -> Result<(), io::Error> {
+        if definition.and_then(|d| d.type_mapping.as_deref()) == Some("@json") {
+            objects.push(json_literal(value).into());
+            return Ok(());
+        }
+        match value {
+            Value::Array(values) => {
+                for value in values {
+                    self.push_values(active, definition, value, graph, objects)?;
+                }
+            }
+            Value::Object(object) => {
+                if let Some((_, set)) = object
+                    .iter()
+                    .find(|(key, _)| active.is_keyword_key(key, "@set"))
+                {
+                    self.push_values(active, definition, set, graph, objects)?;
+                } else if let Some(object) = self.process_value(active, definition, value, graph)? {
+                    objects.push(object);
+                }
+            }
+            value => {
+                if let Some(object) = self.process_value(active, definition, value, graph)? {
+                    objects.push(object);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn process_value(
+        &mut self,
+        active: &Arc<Context>,
+        definition: Option<&TermDefinition>,
+        value: &Value,
+        graph: &GraphName,
+    ) -> Result<Option<Term>, io::Error> {
+        let type_mapping = definition.and_then(|d| d.type_mapping.as_deref());
+        if type_mapping == Some("@json") {
+            return Ok(Some(json_literal(value).into()));
+        }
+        Ok(match value {
+            Value::Null => None,
+            Value::String(value) => match type_mapping {
+                Some("@id") => active
+                    .expand_iri(value, true, false)
+                    .and_then(|id| self.node_from_id(&id))
+                    .map(Term::from),
+                Some("@vocab") => active
+                    .expand_iri(value, true, true)
+                    .and_then(|id| self.node_from_id(&id))
+                    .map(Term::from),
+                Some("@none") | None => {
+                    let language = definition
+                        .and_then(|d| d.language.clone())
+                        .unwrap_or_else(|| active.language.clone());
+                    new_literal(value, language).map(Term::from)
+                }
+                Some(datatype) => Some(
+                    Literal::new_typed_literal(value, NamedNode::new_unchecked(datatype)).into(),
+                ),
+            },
+            Value::Bool(value) => Some(
+                Literal::new_typed_literal(
+                    value.to_string(),
+                    match type_mapping {
+                        Some(datatype) if !datatype.starts_with('@') => {
+                            NamedNode::new_unchecked(datatype)
+                        }
+                        _ => xsd::BOOLEAN.into(),
+                    },
+                )
+                .into(),
+            ),
+            Value::Number(value) => {
+                Some(number_literal(value, type_mapping.filter(|t| !t.starts_with('@'))).into())
+            }
+            Value::Array(values) => Some(self.build_list(active, definition, values, graph)?),
+            Value::Object(object) => {
+                if object
+                    .keys()
+                    .any(|key| active.is_keyword_key(key, "@value"))
+                {
+                    self.process_value_object(active, object)?.map(Term::from)
+                } else if let Some((_, list)) = object
+                    .iter()
+                    .find(|(key, _)| active.is_keyword_key(key, "@list"))
+                {
+                    Some(self.build_list(active, definition, as_array(list), graph)?)
+                } else {
+                    self.process_node(active, object, graph, false)?
+                        .map(Term::from)
+                }
+            }
+        })
+    }
+
+    fn process_value_object(
+        &self,
+        active: &Context,
+        object: &Map<String, Value>,
+    ) -> Result<Option<Literal>, io::Error> {
+        let mut value = &Value::Null;
+        let mut datatype = None;
+        let mut language = None;
+        for (key, v) in object {
+            match active.expand_iri(key, false, true).as_deref() {
+                Some("@value") => value = v,
+                Some("@type") => {
+                    datatype = Some(
+                        v.as_str()
+                            .ok_or_else(|| invalid_data_error("@type value must be a string"))?,
+                    )
+                }
+                Some("@language") => {
+                    language = Some(
+                        v.as_str()
+                            .ok_or_else(|| invalid_data_error("@language value must be a string"))?
+                            .to_ascii_lowercase(),
+                    )
+                }
+                Some("@direction") | Some("@index") | Some("@context") => (),
+                _ => {
+                    return Err(invalid_data_error(format!(
+                        "Invalid JSON-LD value object member {}",
+                        key
+                    )))
+                }
+            }
+        }
+        let datatype = match datatype {
+            Some(datatype) => match active.expand_iri(datatype, true, true) {
+                Some(datatype) if datatype == "@json" => return Ok(Some(json_literal(value))),
+                Some(datatype) if Iri::parse(datatype.as_str()).is_ok() => {
+                    Some(NamedNode::new_unchecked(datatype))
+                }
+                _ => return Ok(None),
+            },
+            None => None,
+        };
+        Ok(match value {
+            Value::Null => None,
+            Value::String(value) => match (datatype, language) {
+                (Some(datatype), None) => Some(Literal::new_typed_literal(value, datatype)),
+                (None, language) => new_literal(value, language),
+                (Some(_), Some(_)) => {
+                    return Err(invalid_data_error(
+                        "A JSON-LD value object could not have both @type and @language",
+                    ))
+                }
+            },
+            Value::Bool(value) => Some(Literal::new_typed_literal(
+                value.to_string(),
+                datatype.unwrap_or_else(|| xsd::BOOLEAN.into()),
+            )),
+            Value::Number(value) => Some(number_literal(
+                value,
+                datatype.as_ref().map(NamedNode::as_str),
+            )),
+            _ => {
+                return Err(invalid_data_error(
+                    "A JSON-LD @value must be a string, a number or a boolean",
+                ))
+            }
+        })
+    }
+
+    /// Emits the RDF list of the given values and returns its head
+    fn build_list(
+        &mut self,
+        active: &Arc<Context>,
+        definition: Option<&TermDefinition>,
+        items: &[Value],
+        graph: &GraphName,
+    ) -> Result<Term, io::Error> {
+        let mut values = Vec::with_capacity(items.len());
+        for item in items {
+            if let Some(value) = self.process_value(active, definition, item, graph)? {
+                values.push(value);
+            }
+        }
+        let mut head: Term = rdf::NIL.into();
+        for value in values.into_iter().rev() {
+            let node = BlankNode::default();
+            self.emit(node.clone().into(), rdf::FIRST.into(), value, graph);
+            self.emit(node.clone().into(), rdf::REST.into(), head, graph);
+            head = node.into();
+        }
+        Ok(head)
+    }
+
+    fn node_from_id(&mut self, id: &str) -> Option<Subject> {
+        if let Some(label) = id.strip_prefix("_:") {
+            Some(
+                self.bnodes
+                    .entry(label.to_owned())
+                    .or_insert_with(BlankNode::default)
+                    .clone()
+                    .into(),
+            )
+        } else {
+            Iri::parse(id)
+                .ok()
+                .map(|iri| NamedNode::new_unchecked(iri.into_inner()).into())
+        }
+    }
+
+    fn emit(&mut self, subject: Subject, predicate: NamedNode, object: Term, graph: &GraphName) {
+        self.output
+            .push_back(Quad::new(subject, predicate, object, graph.clone()))
+    }
+}
+
+/// A formatter writing quads as a JSON-LD document
+///
+/// The quads are grouped by consecutive subjects and graph names.
+/// If a context is given, the properties, types and values are compacted using its terms.
+pub(crate) struct JsonLdFormatter<W: Write> {
+    writer: W,
+    context: Option<(Value, CompactionContext)>,
+    is_started: bool,
+    is_first_node: bool,
+    current_graph: Option<GraphName>,
+    current_subject: Option<Subject>,
+    current_types: Vec<Term>,
+    current_properties: Vec<(NamedNode, Vec<Term>)>,
+}
+
+impl<W: Write> JsonLdFormatter<W> {
+    pub fn new(writer: W, context: Option<Value>) -> Result<Self, io::Error> {
+        let context = if let Some(context) = context {
+            let active = Expander {
+                contexts: &HashMap::new(),
+                base_iri: &None,
+                bnodes: HashMap::new(),
+                output: &mut VecDeque::new(),
+            }
+            .process_context(&Arc::default(), &context, &mut Vec::new(), true)
+            .map_err(|e| invalid_input_error(e.to_string()))?;
+            Some((context, CompactionContext::new(&active)))
+        } else {
+            None
+        };
+        Ok(Self {
+            writer,
+            context,
+            is_started: false,
+            is_first_node: true,
+            current_graph: None,
+            current_subject: None,
+            current_types: Vec::new(),
+            current_properties: Vec::new(),
+        })
+    }
+
+    pub fn format(&mut self, quad: QuadRef<'_>) -> Result<(), io::Error> {
+        if let SubjectRef::Triple(_) = quad.subject {
+            return Err(quoted_triple_error());
+        }
+        if let TermRef::Triple(_) = quad.object {
+            return Err(quoted_triple_error());
+        }
+        self.start()?;
+        if self.current_graph.as_ref().map(GraphName::as_ref) != Some(quad.graph_name) {
+            self.flush_node()?;
+            if let Some(GraphName::NamedNode(_)) | Some(GraphName::BlankNode(_)) =
+                &self.current_graph
+            {
+                self.writer.write_all(b"]}")?;
+            }
+            if !quad.graph_name.is_default_graph() {
+                self.write_separator()?;
+                self.writer.write_all(b"{\"@id\":")?;
+                let id = match quad.graph_name {
+                    GraphNameRef::NamedNode(node) => self.id(node.as_str()),
+                    GraphNameRef::BlankNode(node) => format!("_:{}", node.as_str()),
+                    GraphNameRef::DefaultGraph => unreachable!(),
+                };
+                serde_json::to_writer(&mut self.writer, &id)?;
+                self.writer.write_all(b",\"@graph\":[\n")?;
+                self.is_first_node = true;
+            }
+            self.current_graph = Some(quad.graph_name.into_owned());
+        }
+        if self.current_subject.as_ref().map(Subject::as_ref) != Some(quad.subject) {
+            self.flush_node()?;
+            self.current_subject = Some(quad.subject.into_owned());
+        }
+        if quad.predicate == rdf::TYPE && !quad.object.is_literal() {
+            self.current_types.push(quad.object.into_owned());
+        } else if let Some((_, objects)) = self
+            .current_properties
+            .iter_mut()
+            .find(|(predicate, _)| predicate.as_ref() == quad.predicate)
+        {
+            objects.push(quad.object.into_owned());
+        } else {
+            self.current_properties
+                .push((quad.predicate.into_owned(), vec![quad.object.into_owned()]));
+        }
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> Result<W, io::Error> {
+        self.start()?;
+        self.flush_node()?;
+        if let Some(GraphName::NamedNode(_)) | Some(GraphName::BlankNode(_)) = &self.current_graph {
+            self.writer.write_all(b"]}")?;
+        }
+        if self.context.is_some() {
+            self.writer.write_all(b"\n]}")?;
+        } else {
+            self.writer.write_all(b"\n]")?;
+        }
+        Ok(self.writer)
+    }
+
+    fn start(&mut self) -> Result<(), io::Error> {
+        if !self.is_started {
+            if let Some((context, _)) = &self.context {
+                self.writer.write_all(b"{\"@context\":")?;
+                serde_json::to_writer(&mut self.writer, context)?;
+                self.writer.write_all(b",\"@graph\":[\n")?;
+            } else {
+                self.writer.write_all(b"[\n")?;
+            }
+            self.is_started = true;
+        }
+        Ok(())
+    }
+
+    fn write_separator(&mut self) -> Result<(), io::Error> {
+        if self.is_first_node {
+            self.is_first_node = false;
+        } else {
+            self.writer.write_all(b",\n")?;
+        }
+        Ok(())
+    }
+
+    fn flush_node(&mut self) -> Result<(), io::Error> {
+        let subject = if let Some(subject) = self.current_subject.take() {
+            subject
+        } else {
+            return Ok(());
+        };
+        let mut node = Map::new();
+        node.insert(
+            "@id".into(),
+            Value::String(match &subject {
+                Subject::NamedNode(node) => self.id(node.as_str()),
+                Subject::BlankNode(node) => format!("_:{}", node.as_str()),
+                Subject::Triple(_) => return Err(quoted_triple_error()),
+            }),
+        );
+        if !self.current_types.is_empty() {
+            let types = std::mem::take(&mut self.current_types)
+                .into_iter()
+                .map(|t| match t {
+                    Term::NamedNode(t) => {
+                        Value::String(if let Some((_, context)) = &self.context {
+                            context.compact_iri(t.as_str(), true)
+                        } else {
+                            t.into_string()
+                        })
+                    }
+                    Term::BlankNode(t) => Value::String(format!("_:{}", t.as_str())),
+                    Term::Literal(_) | Term::Triple(_) => unreachable!(),
+                })
+                .collect::<Vec<_>>();
+            node.insert("@type".into(), unwrap_single(types, self.context.is_some()));
+        }
+        let mut keys = Vec::<(String, Vec<Value>, bool)>::new();
+        for (predicate, objects) in self.current_properties.drain(..) {
+            for object in objects {
+                let (key, value, is_set) = if let Some((_, context)) = &self.context {
+                    context.compact_property(&predicate, object)
+                } else {
+                    (predicate.as_str().to_owned(), expanded_value(object), true)
+                };
+                if let Some((_, values, _)) = keys.iter_mut().find(|(k, _, _)| *k == key) {
+                    values.push(value);
+                } else {
+                    keys.push((key, vec![value], is_set));
+                }
+            }
+        }
+        for (key, values, is_set) in keys {
+            node.insert(key, unwrap_single(values, !is_set));
+        }
+        self.write_separator()?;
+        serde_json::to_writer(&mut self.writer, &node)?;
+        Ok(())
+    }
+
+    fn id(&self, iri: &str) -> String {
+        if let Some((_, context)) = &self.context {
+            context.compact_iri(iri, false)
+        } else {
+            iri.to_owned()
+        }
+    }
+}
+
+/// The terms of a context indexed for IRI compaction
+struct CompactionContext {
+    vocab: Option<String>,
+    language: Option<String>,
+    /// The terms sorted by length and then lexicographically
+    terms: Vec<(String, Arc<TermDefinition>)>,
+}
+
+impl CompactionContext {
+    fn new(context: &Context) -> Self {
+        let mut terms = context
+            .terms
+            .iter()
+            .filter(|(_, definition)| {
+                definition
+                    .iri
+                    .as_ref()
+                    .map_or(false, |iri| !iri.starts_with('@'))
+            })
+            .map(|(term, definition)| (term.clone(), definition.clone()))
+            .collect::<Vec<_>>();
+        terms.sort_unstable_by(|(a, _), (b, _)| a.len().cmp(&b.len()).then_with(|| a.cmp(b)));
+        Self {
+            vocab: context.vocab.clone(),
+            language: context.language.clone(),
+            terms,
+        }
+    }
+
+    /// [IRI compaction](https://www.w3.org/TR/json-ld11-api/#iri-compaction) without term selection for values
+    fn compact_iri(&self, iri: &str, vocab: bool) -> String {
+        if vocab {
+            if let Some((term, _)) = self.terms.iter().find(|(_, definition)| {
+                !definition.reverse && definition.iri.as_deref() == Some(iri)
+            }) {
+                return term.clone();
+            }
+        }
+        for (term, definition) in &self.terms {
+            if let (true, Some(prefix)) = (definition.prefix, &definition.iri) {
+                if let Some(suffix) = iri.strip_prefix(prefix.as_str()) {
+                    if !suffix.is_empty()
+                        && !suffix.starts_with("//")
+                        && !self.terms.iter().any(|(t, _)| {
+                            t.len() == term.len() + 1 + suffix.len()
+                                && *t == format!("{}:{}", term, suffix)
+                        })
+                    {
+                        return format!("{}:{}", term, suffix);
+                    }
+                }
+            }
+        }
+        if vocab {
+            if let Some(suffix) = self
+                .vocab
+                .as_ref()
+                .and_then(|vocab| iri.strip_prefix(vocab.as_str()))
+            {
+                if !suffix.is_empty()
+                    && split_compact_iri(suffix).is_none()
+                    && !self.terms.iter().any(|(t, _)| t == suffix)
+                {
+                    return suffix.to_owned();
+                }
+            }
+        }
+        iri.to_owned()
+    }
+
+    /// Returns the key, the value and if the value should always be in an array
+    fn compact_property(&self, predicate: &NamedNode, object: Term) -> (String, Value, bool) {
+        for (term, definition) in &self.terms {
+            if definition.iri.as_deref() == Some(predicate.as_str())
+                && !definition.reverse
+                && definition.container.iter().all(|c| c == "@set")
+            {
+                if let Some(value) = self.compact_value(Some(definition), &object) {
+                    return (term.clone(), value, definition.has_container("@set"));
+                }
+            }
+        }
+        let key = self.compact_iri_without_term(predicate.as_str());
+        let value = self
+            .compact_value(None, &object)
+            .unwrap_or_else(|| expanded_value(object));
+        (key, value, false)
+    }
+
+    fn compact_iri_without_term(&self, iri: &str) -> String {
+        let compacted = self.compact_iri(iri, true);
+        if self.terms.iter().any(|(term, _)| *term == compacted) {
+            iri.to_owned()
+        } else {
+            compacted
+        }
+    }
+
+    /// Compacts the value if it is compatible with the term definition
+    fn compact_value(&self, definition: Option<&TermDefinition>, object: &Term) -> Option<Value> {
+        let type_mapping = definition
+            .and_then(|d| d.type_mapping.as_deref())
+            .filter(|t| *t != "@none");
+        Some(match (type_mapping, object) {
+            (Some("@id"), Term::NamedNode(node)) => {
+                Value::String(self.compact_iri(node.as_str(), false))
+            }
+            (Some("@vocab"), Term::NamedNode(node)) => {
+                Value::String(self.compact_iri(node.as_str(), true))
+            }
+            (Some("@id"), Term::BlankNode(node)) | (Some("@vocab"), Term::BlankNode(node)) => {
+                Value::String(format!("_:{}", node.as_str()))
+            }
+            (Some(datatype), Term::Literal(literal))
+                if !datatype.starts_with('@')
+                    && literal.language().is_none()
+                    && literal.datatype().as_str() == datatype =>
+            {
+                Value::String(literal.value().to_owned())
+            }
+            (None, Term::NamedNode(node)) => {
+                let mut value = Map::new();
+                value.insert(
+                    "@id".into(),
+                    Value::String(self.compact_iri(node.as_str(), false)),
+                );
+                Value::Object(value)
+            }
+            (None, Term::BlankNode(node)) => {
+                let mut value = Map::new();
+                value.insert("@id".into(), Value::String(format!("_:{}", node.as_str())));
+                Value::Object(value)
+            }
+            (None, Term::Literal(literal)) => {
+                let language = definition
+                    .and_then(|d| d.language.clone())
+                    .unwrap_or_else(|| self.language.clone());
+                let mut value = Map::new();
+                value.insert("@value".into(), Value::String(literal.value().to_owned()));
+                if let Some(literal_language) = literal.language() {
+                    if language.as_deref() == Some(literal_language) {
+                        return Some(Value::String(literal.value().to_owned()));
+                    }
+                    value.insert(
+                        "@language".into(),
+                        Value::String(literal_language.to_owned()),
+                    );
+                } else if literal.datatype() == xsd::STRING {
+                    if language.is_none() {
+                        return Some(Value::String(literal.value().to_owned()));
+                    }
+                } else {
+                    value.insert(
+                        "@type".into(),
+                        Value::String(self.compact_iri(literal.datatype().as_str(), true)),
+                    );
+                }
+                Value::Object(value)
+            }
+            _ => return None,
+        })
+    }
+}
+
+/// The [expanded form](https://www.w3.org/TR/json-ld11/#expanded-document-form) of a value
+fn expanded_value(object: Term) -> Value {
+    let mut value = Map::new();
+    match object {
+        Term::NamedNode(node) => {
+            value.insert("@id".into(), Value::String(node.into_string()));
+        }
+        Term::BlankNode(node) => {
+            value.insert("@id".into(), Value::String(format!("_:{}", node.as_str())));
+        }
+        Term::Literal(literal) => {
+            value.insert("@value".into(), Value::String(literal.value().to_owned()));
+            if let Some(language) = literal.language() {
+                value.insert("@language".into(), Value::String(language.to_owned()));
+            } else if literal.datatype() != xsd::STRING {
+                value.insert(
+                    "@type".into(),
+                    Value::String(literal.datatype().as_str().to_owned()),
+                );
+            }
+        }
+        Term::Triple(_) => unreachable!("quoted triples are rejected before"),
+    }
+    Value::Object(value)
+}
+
+fn unwrap_single(mut values: Vec<Value>, unwrap: bool) -> Value {
+    if unwrap && values.len() == 1 {
+        values.pop().unwrap()
+    } else {
+        Value::Array(values)
+    }
+}
+
+fn quoted_triple_error() -> io::Error {
+    invalid_input_error("JSON-LD does not support RDF-star quoted triples")
+}
+
+fn as_array(value: &Value) -> &[Value] {
+    if let Value::Array(values) = value {
+        values
+    } else {
+        slice::from_ref(value)
+    }
+}
+
+fn is_list_object(active: &Context, value: &Value) -> bool {
+    if let Value::Object(object) = value {
+        object.keys().any(|key| active.is_keyword_key(key, "@list"))
+    } else {
+        false
+    }
+}
+
+fn resolve_context_iri(active: &Context, iri: &str) -> String {
+    if let Some(base) = &active.base {
+        if let Ok(iri) = base.resolve(iri) {
+            return iri.into_inner();
+        }
+    }
+    iri.to_owned()
+}
+
+fn is_keyword(value: &str) -> bool {
+    matches!(
+        value,
+        "@base"
+            | "@container"
+            | "@context"
+            | "@direction"
+            | "@graph"
+            | "@id"
+            | "@import"
+            | "@included"
+            | "@index"
+            | "@json"
+            | "@language"
+            | "@list"
+            | "@nest"
+            | "@none"
+            | "@prefix"
+            | "@propagate"
+            | "@protected"
+            | "@reverse"
+            | "@set"
+            | "@type"
+            | "@value"
+            | "@version"
+            | "@vocab"
+    )
+}
+
+fn looks_like_keyword(value: &str) -> bool {
+    value.strip_prefix('@').map_or(false, |v| {
+        !v.is_empty() && v.chars().all(|c| c.is_ascii_alphabetic())
+    })
+}
+
+fn is_gen_delim(c: char) -> bool {
+    matches!(c, ':' | '/' | '?' | '#' | '[' | ']' | '@')
+}
+
+/// Splits a compact IRI `prefix:suffix`
+fn split_compact_iri(value: &str) -> Option<(&str, &str)> {
+    let position = value.find(':')?;
+    if position == 0 {
+        None
+    } else {
+        Some((&value[..position], &value[position + 1..]))
+    }
+}
+
+fn subject_to_graph_name(subject: Subject) -> GraphName {
+    match subject {
+        Subject::NamedNode(node) => node.into(),
+        Subject::BlankNode(node) => node.into(),
+        Subject::Triple(_) => GraphName::DefaultGraph,
+    }
+}
+
+fn term_to_subject(term: Term) -> Option<Subject> {
+    match term {
+        Term::NamedNode(node) => Some(node.into()),
+        Term::BlankNode(node) => Some(node.into()),
+        Term::Literal(_) | Term::Triple(_) => None,
+    }
+}
+
+/// Builds a simple or a language-tagged literal, ignoring the invalid language tags
+fn new_literal(value: &str, language: Option<String>) -> Option<Literal> {
+    if let Some(language) = language {
+        Literal::new_language_tagged_literal(value, language).ok()
+    } else {
+        Some(Literal::new_simple_literal(value))
+    }
+}
+
+/// Converts a native JSON number following the [RDF serialization algorithm](https://www.w3.org/TR/json-ld11-api/#data-round-tripping)
+fn number_literal(value: &Number, datatype: Option<&str>) -> Literal {
+    let double = value.as_f64().unwrap_or(0.);
+    let is_integer =
+        value.is_i64() || value.is_u64() || (double.fract() == 0. && double.abs() < 1e21);
+    if !is_integer || datatype == Some(xsd::DOUBLE.as_str()) {
+        let lexical = format!("{:E}", double);
+        let lexical = match lexical.split_once('E') {
+            Some((mantissa, exponent)) if !mantissa.contains('.') => {
+                format!("{}.0E{}", mantissa, exponent)
+            }
+            _ => lexical,
+        };
+        Literal::new_typed_literal(
+            lexical,
+            datatype.map_or_else(|| xsd::DOUBLE.into(), NamedNode::new_unchecked),
+        )
+    } else {
+        let lexical = if value.is_i64() || value.is_u64() {
+            value.to_string()
+        } else {
+            format!("{:.0}", double)
+        };
+        Literal::new_typed_literal(
+            lexical,
+            datatype.map_or_else(|| xsd::INTEGER.into(), NamedNode::new_unchecked),
+        )
+    }
+}
+
+/// Builds a `rdf:JSON` literal with a canonical serialization of the value
+fn json_literal(value: &Value) -> Literal {
+    Literal::new_typed_literal(value.to_string(), NamedNode::new_unchecked(RDF_JSON))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::canonicalization::canonicalize_quads;
+    use crate::io::turtle::{TurtleParser, TurtleSyntax};
+    use crate::io::{DatasetFormat, DatasetParser, DatasetSerializer, GraphFormat};
+    use crate::sparql::QueryResults;
+    use crate::store::MemoryStore;
+    use std::error::Error;
+    use std::io::Cursor;
+
+    fn canonical(quads: Vec<Quad>) -> Vec<String> {
+        let mut lines = canonicalize_quads(quads)
+            .into_iter()
+            .map(|quad| quad.to_string())
+            .collect::<Vec<_>>();
+        lines.sort();
+        lines
+    }
+
+    fn parse(data: &str, contexts: HashMap<String, Value>) -> io::Result<Vec<Quad>> {
+        JsonLdParser::new(
+            Cursor::new(data),
+            Some(Iri::parse("http://example.com/doc".to_owned()).unwrap()),
+            contexts,
+        )
+        .collect()
+    }
+
+    fn assert_to_rdf(data: &str, expected: &str) {
+        let expected = TurtleParser::new(Cursor::new(expected), TurtleSyntax::NQuads, None)
+            .collect::<io::Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(
+            canonical(parse(data, HashMap::new()).unwrap()),
+            canonical(expected),
+            "{}",
+            data
+        );
+    }
+
+    #[test]
+    fn test_contexts() {
+        assert_to_rdf(
+            r#"{
+                "@context": {
+                    "@vocab": "http://example.com/vocab#",
+                    "ex": "http://example.com/",
+                    "name": "http://xmlns.com/foaf/0.1/name",
+                    "knows": {"@id": "ex:knows", "@type": "@id"}
+                },
+                "@id": "ex:alice",
+                "name": "Alice",
+                "age": 42,
+                "knows": "bob",
+                "ex:nick": "al"
+            }"#,
+            "<http://example.com/alice> <http://xmlns.com/foaf/0.1/name> \"Alice\" .\n\
+             <http://example.com/alice> <http://example.com/vocab#age> \"42\"^^<http://www.w3.org/2001/XMLSchema#integer> .\n\
+             <http://example.com/alice> <http://example.com/knows> <http://example.com/bob> .\n\
+             <http://example.com/alice> <http://example.com/nick> \"al\" .",
+        );
+        // Nested and type-scoped contexts
+        assert_to_rdf(
+            r#"{
+                "@context": {
+                    "@base": "http://example.com/base/",
+                    "ex": "http://example.com/",
+                    "Person": {"@id": "ex:Person", "@context": {"name": "ex:fullName"}},
+                    "name": "ex:name",
+                    "friend": {"@id": "ex:friend", "@context": {"name": "ex:nickName"}}
+                },
+                "@id": "a",
+                "@type": "Person",
+                "name": "A",
+                "friend": {"@id": "b", "name": "B"}
+            }"#,
+            "<http://example.com/base/a> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://example.com/Person> .\n\
+             <http://example.com/base/a> <http://example.com/fullName> \"A\" .\n\
+             <http://example.com/base/a> <http://example.com/friend> <http://example.com/base/b> .\n\
+             <http://example.com/base/b> <http://example.com/nickName> \"B\" .",
+        );
+        // Remote contexts are only resolved from the given documents
+        let mut contexts = HashMap::new();
+        contexts.insert(
+            "http://example.com/context".to_owned(),
+            parse_context_document(r#"{"@context": {"p": "http://example.com/p"}}"#).unwrap(),
+        );
+        let quads = parse(
+            r#"{"@context": "http://example.com/context", "@id": "http://example.com/s", "p": "o"}"#,
+            contexts,
+        )
+        .unwrap();
+        assert_eq!(quads.len(), 1);
+        assert_eq!(quads[0].predicate.as_str(), "http://example.com/p");
+        assert!(parse(
+            r#"{"@context": "http://example.com/other", "@id": "http://example.com/s"}"#,
+            HashMap::new()
+        )
+        .is_err());
+        // Keywords can not be redefined
+        assert!(parse(
+            r#"{"@context": {"@type": {"@container": "@set"}}, "@id": "http://example.com/s"}"#,
+            HashMap::new()
+        )
+        .is_ok());
+        assert!(parse(
+            r#"{"@context": {"@type": "http://example.com/type"}, "@id": "http://example.com/s"}"#,
+            HashMap::new()
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_reverse() {
+        assert_to_rdf(
+            r#"{
+                "@context": {"ex": "http://example.com/", "children": {"@reverse": "ex:parent"}},
+                "@id": "ex:alice",
+                "children": [{"@id": "ex:bob"}, {"@id": "ex:carol", "ex:age": 3}],
+                "@reverse": {"ex:knows": {"@id": "ex:dave"}}
+            }"#,
+            "<http://example.com/bob> <http://example.com/parent> <http://example.com/alice> .\n\
+             <http://example.com/carol> <http://example.com/parent> <http://example.com/alice> .\n\
+             <http://example.com/carol> <http://example.com/age> \"3\"^^<http://www.w3.org/2001/XMLSchema#integer> .\n\
+             <http://example.com/dave> <http://example.com/knows> <http://example.com/alice> .",
+        );
+    }
+
+    #[test]
+    fn test_list() {
+        assert_to_rdf(
+            r#"{
+                "@context": {
+                    "ex": "http://example.com/",
+                    "items": {"@id": "ex:items", "@container": "@list"}
+                },
+                "@id": "ex:s",
+                "items": ["a", {"@id": "ex:b"}],
+                "ex:empty": {"@list": []},
+                "ex:nested": {"@list": [{"@list": [1]}]}
+            }"#,
+            "<http://example.com/s> <http://example.com/items> _:l1 .\n\
+             _:l1 <http://www.w3.org/1999/02/22-rdf-syntax-ns#first> \"a\" .\n\
+             _:l1 <http://www.w3.org/1999/02/22-rdf-syntax-ns#rest> _:l2 .\n\
+             _:l2 <http://www.w3.org/1999/02/22-rdf-syntax-ns#first> <http://example.com/b> .\n\
+             _:l2 <http://www.w3.org/1999/02/22-rdf-syntax-ns#rest> <http://www.w3.org/1999/02/22-rdf-syntax-ns#nil> .\n\
+             <http://example.com/s> <http://example.com/empty> <http://www.w3.org/1999/02/22-rdf-syntax-ns#nil> .\n\
+             <http://example.com/s> <http://example.com/nested> _:n1 .\n\
+             _:n1 <http://www.w3.org/1999/02/22-rdf-syntax-ns#first> _:n2 .\n\
+             _:n1 <http://www.w3.org/1999/02/22-rdf-syntax-ns#rest> <http://www.w3.org/1999/02/22-rdf-syntax-ns#nil> .\n\
+             _:n2 <http://www.w3.org/1999/02/22-rdf-syntax-ns#first> \"1\"^^<http://www.w3.org/2001/XMLSchema#integer> .\n\
+             _:n2 <http://www.w3.org/1999/02/22-rdf-syntax-ns#rest> <http://www.w3.org/1999/02/22-rdf-syntax-ns#nil> .",
+        );
+    }
+
+    #[test]
+    fn test_graph() {
+        assert_to_rdf(
+            r#"{
+                "@context": {"ex": "http://example.com/"},
+                "@graph": [
+                    {"@id": "ex:s", "ex:p": "o"},
+                    {"@id": "ex:g", "ex:q": "r", "@graph": {"@id": "ex:s", "ex:p": {"@id": "ex:o"}}}
+                ]
+            }"#,
+            "<http://example.com/s> <http://example.com/p> \"o\" .\n\
+             <http://example.com/g> <http://example.com/q> \"r\" .\n\
+             <http://example.com/s> <http://example.com/p> <http://example.com/o> <http://example.com/g> .",
+        );
+        // Graph containers create a blank node graph
+        assert_to_rdf(
+            r#"{
+                "@context": {"ex": "http://example.com/", "claim": {"@id": "ex:claim", "@container": "@graph"}},
+                "@id": "ex:s",
+                "claim": {"@id": "ex:a", "ex:p": "b"}
+            }"#,
+            "<http://example.com/s> <http://example.com/claim> _:g .\n\
+             <http://example.com/a> <http://example.com/p> \"b\" _:g .",
+        );
+    }
+
+    #[test]
+    fn test_type_coercion() {
+        assert_to_rdf(
+            r#"{
+                "@context": {
+                    "ex": "http://example.com/",
+                    "xsd": "http://www.w3.org/2001/XMLSchema#",
+                    "date": {"@id": "ex:date", "@type": "xsd:date"},
+                    "link": {"@id": "ex:link", "@type": "@id"},
+                    "term": {"@id": "ex:term", "@type": "@vocab"},
+                    "Foo": "ex:Foo",
+                    "label": {"@id": "ex:label", "@language": "fr"},
+                    "data": {"@id": "ex:data", "@type": "@json"}
+                },
+                "@id": "ex:s",
+                "@type": ["Foo", "ex:Bar"],
+                "date": "2021-01-01",
+                "link": "other",
+                "term": "Foo",
+                "label": "chat",
+                "data": {"b": [1, true], "a": null},
+                "ex:double": 1.5,
+                "ex:bool": false,
+                "ex:typed": {"@value": "x", "@type": "ex:T"},
+                "ex:tagged": {"@value": "cat", "@language": "en-GB"}
+            }"#,
+            "<http://example.com/s> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://example.com/Foo> .\n\
+             <http://example.com/s> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://example.com/Bar> .\n\
+             <http://example.com/s> <http://example.com/date> \"2021-01-01\"^^<http://www.w3.org/2001/XMLSchema#date> .\n\
+             <http://example.com/s> <http://example.com/link> <http://example.com/other> .\n\
+             <http://example.com/s> <http://example.com/term> <http://example.com/Foo> .\n\
+             <http://example.com/s> <http://example.com/label> \"chat\"@fr .\n\
+             <http://example.com/s> <http://example.com/data> \"{\\\"a\\\":null,\\\"b\\\":[1,true]}\"^^<http://www.w3.org/1999/02/22-rdf-syntax-ns#JSON> .\n\
+             <http://example.com/s> <http://example.com/double> \"1.5E0\"^^<http://www.w3.org/2001/XMLSchema#double> .\n\
+             <http://example.com/s> <http://example.com/bool> \"false\"^^<http://www.w3.org/2001/XMLSchema#boolean> .\n\
+             <http://example.com/s> <http://example.com/typed> \"x\"^^<http://example.com/T> .\n\
+             <http://example.com/s> <http://example.com/tagged> \"cat\"@en-gb .",
+        );
+    }
+
+    #[test]
+    fn test_from_rdf() -> io::Result<()> {
+        let data = "<http://example.com/s> <http://example.com/p> \"o\"@en <http://example.com/g> .\n\
+             <http://example.com/s> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://example.com/T> .\n\
+             <http://example.com/s> <http://example.com/p> _:b .\n\
+             _:b <http://example.com/p> \"1\"^^<http://www.w3.org/2001/XMLSchema#integer> .\n\
+             _:b <http://example.com/p> \"x\"^^<http://example.com/T> .";
+        let quads = TurtleParser::new(Cursor::new(data), TurtleSyntax::NQuads, None)
+            .collect::<io::Result<Vec<_>>>()?;
+        for context in &[
+            None,
+            Some(parse_context_document(
+                r#"{"@context": {"ex": "http://example.com/", "p": {"@id": "ex:p"}}}"#,
+            )?),
+        ] {
+            let mut formatter = JsonLdFormatter::new(Vec::new(), context.clone())?;
+            for quad in &quads {
+                formatter.format(quad.as_ref())?;
+            }
+            let buffer = formatter.finish()?;
+            let json = String::from_utf8(buffer).map_err(invalid_data_error)?;
+            assert_eq!(
+                canonical(parse(&json, HashMap::new())?),
+                canonical(quads.clone()),
+                "{}",
+                json
+            );
+            if context.is_some() {
+                assert!(
+                    json.contains("\"ex:s\"") && json.contains("\"p\""),
+                    "{}",
+                    json
+                );
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_round_trip() -> Result<(), Box<dyn Error>> {
+        let data = r#"
+            @prefix schema: <http://schema.org/> .
+            @prefix wd: <http://www.wikidata.org/entity/> .
+            @prefix xsd: <http://www.w3.org/2001/XMLSchema#> .
+
+            wd:Q90 a schema:City ;
+                schema:name "Paris"@fr , "la ville lumière"@fr ;
+                schema:country wd:Q142 ;
+                schema:population 2000000 ;
+                schema:startDate "-300"^^xsd:gYear ;
+                schema:url "https://www.paris.fr/"^^xsd:anyURI ;
+                schema:postalCode "75001" .
+        "#;
+        let store = MemoryStore::new();
+        store.load_graph(
+            Cursor::new(data),
+            GraphFormat::Turtle,
+            NamedNodeRef::new_unchecked("http://www.wikidata.org/wiki/Special:EntityData/Q90"),
+            None,
+        )?;
+        assert_eq!(store.len(), 8);
+
+        let mut buffer = Vec::new();
+        store.dump_dataset(&mut buffer, DatasetFormat::JsonLd)?;
+        let expanded_store = MemoryStore::new();
+        expanded_store.load_dataset(Cursor::new(&buffer), DatasetFormat::JsonLd, None)?;
+        assert!(expanded_store.is_isomorphic(&store));
+
+        let mut buffer = Vec::new();
+        let mut writer = DatasetSerializer::from_format(DatasetFormat::JsonLd)
+            .with_json_ld_context(
+                r#"{"@context": {
+                    "@vocab": "http://schema.org/",
+                    "wd": "http://www.wikidata.org/entity/",
+                    "xsd": "http://www.w3.org/2001/XMLSchema#",
+                    "country": {"@type": "@id"},
+                    "name": {"@language": "fr", "@container": "@set"}
+                }}"#,
+            )?
+            .quad_writer(&mut buffer)?;
+        for quad in store.iter() {
+            writer.write(&quad)?;
+        }
+        writer.finish()?;
+        let compacted = String::from_utf8(buffer)?;
+        assert!(compacted.contains(r#""@type":"City""#));
+        assert!(compacted.contains(r#""country":"wd:Q142""#));
+        assert!(compacted.contains(r#""name":["#));
+        assert!(compacted.contains(r#""postalCode":"75001""#));
+
+        let compacted_store = MemoryStore::new();
+        compacted_store.load_dataset(Cursor::new(compacted), DatasetFormat::JsonLd, None)?;
+        assert!(compacted_store.is_isomorphic(&store));
+        Ok(())
+    }
+
+    #[test]
+    fn test_parsing_with_given_context() -> Result<(), Box<dyn Error>> {
+        let context = r#"{
+            "@vocab": "http://schema.org/",
+            "knows": {"@type": "@id"},
+            "children": {"@reverse": "parent"},
+            "tags": {"@container": "@list"},
+            "labels": {"@container": "@language"}
+        }"#;
+        let document = r#"{
+            "@context": ["http://example.com/context", {"@base": "http://example.com/"}],
+            "@id": "alice",
+            "@type": "Person",
+            "knows": "bob",
+            "age": 42,
+            "height": 1.65,
+            "tags": ["a", "b"],
+            "labels": {"en": "Alice", "fr": "Alice"},
+            "children": {"@id": "carol"},
+            "@graph": [{"@id": "bob", "name": "Bob"}]
+        }
+        {"@id": "_:b", "http://example.com/p": {"@value": "1", "@type": "http://www.w3.org/2001/XMLSchema#integer"}}"#;
+        let quads = DatasetParser::from_format(DatasetFormat::JsonLd)
+            .with_json_ld_context("http://example.com/context", context)?
+            .read_quads(Cursor::new(document))?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let store = MemoryStore::new();
+        for quad in quads {
+            store.insert(quad);
+        }
+        assert_eq!(store.len(), 14);
+        let query = r#"PREFIX schema: <http://schema.org/>
+            PREFIX xsd: <http://www.w3.org/2001/XMLSchema#>
+            ASK {
+                <http://example.com/alice> a schema:Person ;
+                    schema:knows <http://example.com/bob> ;
+                    schema:age 42 ;
+                    schema:height "1.65E0"^^xsd:double ;
+                    schema:tags ("a" "b") ;
+                    schema:labels "Alice"@en, "Alice"@fr .
+                <http://example.com/carol> schema:parent <http://example.com/alice> .
+                GRAPH <http://example.com/alice> { <http://example.com/bob> schema:name "Bob" }
+                ?b <http://example.com/p> 1 .
+            }"#;
+        if let QueryResults::Boolean(result) = store.query(query)? {
+            assert!(result);
+        } else {
+            return Err("ASK query expected".into());
+        }
+
+        assert!(DatasetParser::from_format(DatasetFormat::JsonLd)
+            .read_quads(Cursor::new(r#"{"@context": "http://example.com/context"}"#))?
+            .next()
+            .unwrap()
+            .is_err());
+        Ok(())
+    }
+}
@@ -1,12 +1,26 @@
 //! Utilities to read and write RDF graphs and datasets
 
+pub(crate) mod canonicalization;
+mod compression;
+pub(crate) mod detection;
 mod format;
+pub(crate) mod hdt;
+pub(crate) mod jsonld;
 pub mod read;
+pub(crate) mod turtle;
 pub mod write;
 
+pub use self::canonicalization::{
+    canonical_dataset_hash, canonicalize_quads, write_canonical_n_quads,
+};
+pub use self::compression::{CompressionFormat, DecompressingReader};
+pub use self::detection::{DetectionConfidence, FormatGuess, RdfFormat};
 pub use self::format::DatasetFormat;
 pub use self::format::GraphFormat;
 pub use self::read::DatasetParser;
 pub use self::read::GraphParser;
+pub use self::read::PrefixMap;
+pub use self::read::SyntaxError;
+pub use self::read::TextPosition;
 pub use self::write::DatasetSerializer;
 pub use self::write::GraphSerializer;
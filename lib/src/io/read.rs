@@ -1,15 +1,22 @@
 //! Utilities to read RDF graphs and datasets
 
+use crate::error::invalid_input_error;
+use crate::io::jsonld::{parse_context_document, JsonLdParser};
+use crate::io::turtle::{ParallelTurtleParser, TurtleParser, TurtleSyntax};
 use crate::io::{DatasetFormat, GraphFormat};
 use crate::model::*;
 use oxiri::{Iri, IriParseError};
 use rio_api::model as rio;
-use rio_api::parser::{QuadsParser, TriplesParser};
-use rio_turtle::{NQuadsParser, NTriplesParser, TriGParser, TurtleParser};
+use rio_api::parser::TriplesParser;
 use rio_xml::RdfXmlParser;
+use siphasher::sip128::{Hasher128, SipHasher24};
 use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::hash::Hasher;
 use std::io;
 use std::io::BufRead;
+use std::sync::Arc;
 
 /// Parsers for RDF graph serialization formats.
 ///
@@ -18,6 +25,8 @@ use std::io::BufRead;
 /// * [Turtle](https://www.w3.org/TR/turtle/) ([`GraphFormat::Turtle`](super::GraphFormat::Turtle))
 /// * [RDF/XML](https://www.w3.org/TR/rdf-syntax-grammar/) ([`GraphFormat::RdfXml`](super::GraphFormat::RdfXml))
 ///
+/// N-Triples and Turtle are parsed with their [RDF-star](https://w3c.github.io/rdf-star/cg-spec/editors_draft.html) extensions.
+///
 /// ```
 /// use oxigraph::io::{GraphFormat, GraphParser};
 /// use std::io::Cursor;
@@ -32,8 +41,9 @@ use std::io::BufRead;
 /// # std::io::Result::Ok(())
 /// ```
 pub struct GraphParser {
-    format: GraphFormat,
-    base_iri: Option<Iri<String>>,
+    pub(crate) format: GraphFormat,
+    pub(crate) base_iri: Option<Iri<String>>,
+    pub(crate) on_error: Option<SyntaxErrorHandler>,
 }
 
 impl GraphParser {
@@ -42,6 +52,7 @@ impl GraphParser {
         Self {
             format,
             base_iri: None,
+            on_error: None,
         }
     }
 
@@ -65,15 +76,50 @@ impl GraphParser {
         Ok(self)
     }
 
+    /// Switches the parser to the lenient mode: invalid statements are skipped and the parsing goes on with the next statement.
+    ///
+    /// Each skipped statement is reported to the `on_error` callback with its position in the file.
+    /// The lenient mode is only supported by N-Triples and Turtle. The other formats still abort on the first error.
+    /// The blank node labels used before a skipped Turtle statement do not identify the same blank nodes after it.
+    ///
+    /// ```
+    /// use oxigraph::io::{GraphFormat, GraphParser};
+    /// use std::io::Cursor;
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// let file = "<http://example.com/s> <http://example.com/p> <http://example.com/o> .
+    /// <http://example.com/s> <http://example.com/p> \"foo .
+    /// <http://example.com/s> <http://example.com/p> <http://example.com/o2> .";
+    ///
+    /// let errors = Arc::new(Mutex::new(Vec::new()));
+    /// let errors2 = errors.clone();
+    /// let parser = GraphParser::from_format(GraphFormat::NTriples).lenient(move |e| errors2.lock().unwrap().push(e));
+    /// let triples = parser.read_triples(Cursor::new(file))?.collect::<Result<Vec<_>,_>>()?;
+    ///
+    ///assert_eq!(triples.len(), 2);
+    ///assert_eq!(errors.lock().unwrap().len(), 1);
+    ///assert_eq!(errors.lock().unwrap()[0].position().line, 2);
+    /// # std::io::Result::Ok(())
+    /// ```
+    pub fn lenient(mut self, on_error: impl Fn(SyntaxError) + Send + Sync + 'static) -> Self {
+        self.on_error = Some(Arc::new(on_error));
+        self
+    }
+
     /// Executes the parsing itself on a [`BufRead`](std::io::BufRead) implementation and returns an iterator of triples
     pub fn read_triples<R: BufRead>(&self, reader: R) -> Result<TripleReader<R>, io::Error> {
         Ok(TripleReader {
             mapper: RioMapper::default(),
+            prefixes: PrefixMap::with_base_iri(self.base_iri.clone()),
             parser: match self.format {
-                GraphFormat::NTriples => TripleReaderKind::NTriples(NTriplesParser::new(reader)),
-                GraphFormat::Turtle => {
-                    TripleReaderKind::Turtle(TurtleParser::new(reader, self.base_iri.clone()))
-                }
+                GraphFormat::NTriples => TripleReaderKind::Turtle(Box::new(
+                    TurtleParser::new(reader, TurtleSyntax::NTriples, None)
+                        .with_error_handler(self.on_error.clone()),
+                )),
+                GraphFormat::Turtle => TripleReaderKind::Turtle(Box::new(
+                    TurtleParser::new(reader, TurtleSyntax::Turtle, self.base_iri.clone())
+                        .with_error_handler(self.on_error.clone()),
+                )),
                 GraphFormat::RdfXml => {
                     TripleReaderKind::RdfXml(RdfXmlParser::new(reader, self.base_iri.clone()))
                 }
@@ -81,6 +127,54 @@ impl GraphParser {
             buffer: Vec::new(),
         })
     }
+
+    /// Executes the parsing on a [`BufRead`](std::io::BufRead) implementation like [`read_triples`](GraphParser::read_triples())
+    /// but splits the file in chunks of lines that are parsed in parallel using the [rayon](https://docs.rs/rayon) global thread pool.
+    ///
+    /// The triples are returned in the file order.
+    /// The chunks are parsed one after the other if the `"parallel"` feature is disabled.
+    /// Only N-Triples is supported and each triple must be on its own line as required by the N-Triples specification.
+    ///
+    /// ```
+    /// use oxigraph::io::{GraphFormat, GraphParser};
+    /// use std::io::Cursor;
+    ///
+    /// let file = "<http://example.com/s> <http://example.com/p> _:o .\n_:o <http://example.com/p> <http://example.com/o> .";
+    ///
+    /// let parser = GraphParser::from_format(GraphFormat::NTriples);
+    /// let triples = parser.read_triples_in_parallel(Cursor::new(file))?.collect::<Result<Vec<_>,_>>()?;
+    ///
+    ///assert_eq!(triples.len(), 2);
+    ///assert_eq!(triples[0].object, triples[1].subject.clone().into());
+    /// # std::io::Result::Ok(())
+    /// ```
+    pub fn read_triples_in_parallel<R: BufRead>(
+        &self,
+        reader: R,
+    ) -> Result<TripleReader<R>, io::Error> {
+        if self.format != GraphFormat::NTriples {
+            return Err(invalid_input_error(format!(
+                "The {} format could not be parsed in parallel",
+                self.format.media_type()
+            )));
+        }
+        Ok(TripleReader {
+            mapper: RioMapper::default(),
+            prefixes: PrefixMap::default(),
+            parser: TripleReaderKind::ParallelTurtle(ParallelTurtleParser::new(
+                reader,
+                TurtleSyntax::NTriples,
+                self.on_error.clone(),
+            )),
+            buffer: Vec::new(),
+        })
+    }
+}
+
+impl From<GraphFormat> for GraphParser {
+    fn from(format: GraphFormat) -> Self {
+        Self::from_format(format)
+    }
 }
 
 /// An iterator yielding read triples.
@@ -102,13 +196,14 @@ impl GraphParser {
 #[must_use]
 pub struct TripleReader<R: BufRead> {
     mapper: RioMapper,
+    prefixes: PrefixMap,
     parser: TripleReaderKind<R>,
     buffer: Vec<Triple>,
 }
 
 enum TripleReaderKind<R: BufRead> {
-    NTriples(NTriplesParser<R>),
-    Turtle(TurtleParser<R>),
+    Turtle(Box<TurtleParser<R>>),
+    ParallelTurtle(ParallelTurtleParser<R>),
     RdfXml(RdfXmlParser<R>),
 }
 
@@ -116,29 +211,46 @@ impl<R: BufRead> Iterator for TripleReader<R> {
     type Item = Result<Triple, io::Error>;
 
     fn next(&mut self) -> Option<Result<Triple, io::Error>> {
-        loop {
-            if let Some(r) = self.buffer.pop() {
-                return Some(Ok(r));
-            }
-
-            if let Err(error) = match &mut self.parser {
-                TripleReaderKind::NTriples(parser) => {
-                    Self::read(parser, &mut self.buffer, &mut self.mapper)
-                }
-                TripleReaderKind::Turtle(parser) => {
-                    Self::read(parser, &mut self.buffer, &mut self.mapper)
+        match &mut self.parser {
+            TripleReaderKind::Turtle(parser) => parser.next().map(|q| q.map(Triple::from)),
+            TripleReaderKind::ParallelTurtle(parser) => parser.next().map(|q| q.map(Triple::from)),
+            TripleReaderKind::RdfXml(parser) => loop {
+                if let Some(r) = self.buffer.pop() {
+                    return Some(Ok(r));
                 }
-                TripleReaderKind::RdfXml(parser) => {
-                    Self::read(parser, &mut self.buffer, &mut self.mapper)
+                if let Err(error) = Self::read(parser, &mut self.buffer, &mut self.mapper)? {
+                    return Some(Err(error));
                 }
-            }? {
-                return Some(Err(error));
-            }
+            },
         }
     }
 }
 
 impl<R: BufRead> TripleReader<R> {
+    /// The prefixes declared in the file read so far and the base IRI given to the parser.
+    ///
+    /// Only Turtle declares prefixes. The `@base` declarations of the file are not tracked.
+    ///
+    /// ```
+    /// use oxigraph::io::{GraphFormat, GraphParser};
+    /// use std::io::Cursor;
+    ///
+    /// let file = "@prefix ex: <http://example.com/ns#> . <s> ex:p ex:o .";
+    ///
+    /// let mut reader = GraphParser::from_format(GraphFormat::Turtle).with_base_iri("http://example.com/")?.read_triples(Cursor::new(file))?;
+    /// reader.next().unwrap()?;
+    ///
+    /// assert_eq!(reader.prefixes().base_iri(), Some("http://example.com/"));
+    /// assert_eq!(reader.prefixes().get("ex"), Some("http://example.com/ns#"));
+    /// # Result::<_,Box<dyn std::error::Error>>::Ok(())
+    /// ```
+    pub fn prefixes(&self) -> &PrefixMap {
+        match &self.parser {
+            TripleReaderKind::Turtle(parser) => parser.prefixes(),
+            TripleReaderKind::ParallelTurtle(_) | TripleReaderKind::RdfXml(_) => &self.prefixes,
+        }
+    }
+
     fn read<P: TriplesParser>(
         parser: &mut P,
         buffer: &mut Vec<Triple>,
@@ -165,6 +277,9 @@ impl<R: BufRead> TripleReader<R> {
 /// It currently supports the following formats:
 /// * [N-Quads](https://www.w3.org/TR/n-quads/) ([`DatasetFormat::NQuads`](super::DatasetFormat::NQuads))
 /// * [TriG](https://www.w3.org/TR/trig/) ([`DatasetFormat::TriG`](super::DatasetFormat::TriG))
+/// * [JSON-LD 1.1](https://www.w3.org/TR/json-ld11/) ([`DatasetFormat::JsonLd`](super::DatasetFormat::JsonLd))
+///
+/// N-Quads and TriG are parsed with their [RDF-star](https://w3c.github.io/rdf-star/cg-spec/editors_draft.html) extensions.
 ///
 /// ```
 /// use oxigraph::io::{DatasetFormat, DatasetParser};
@@ -180,8 +295,10 @@ impl<R: BufRead> TripleReader<R> {
 /// # std::io::Result::Ok(())
 /// ```
 pub struct DatasetParser {
-    format: DatasetFormat,
-    base_iri: Option<Iri<String>>,
+    pub(crate) format: DatasetFormat,
+    pub(crate) base_iri: Option<Iri<String>>,
+    pub(crate) json_ld_contexts: HashMap<String, serde_json::Value>,
+    pub(crate) on_error: Option<SyntaxErrorHandler>,
 }
 
 impl DatasetParser {
@@ -190,6 +307,8 @@ impl DatasetParser {
         Self {
             format,
             base_iri: None,
+            json_ld_contexts: HashMap::new(),
+            on_error: None,
         }
     }
 
@@ -213,21 +332,115 @@ impl DatasetParser {
         Ok(self)
     }
 
+    /// Provides a JSON-LD context that could be referenced by its IRI from the parsed JSON-LD documents
+    ///
+    /// The parser never fetches remote contexts: all the contexts referenced by IRI must be provided using this method.
+    /// `context` could be a JSON-LD document with a `@context` key or directly the context value.
+    ///
+    /// ```
+    /// use oxigraph::io::{DatasetFormat, DatasetParser};
+    /// use std::io::Cursor;
+    ///
+    /// let context = r#"{"@context": {"@vocab": "http://schema.org/"}}"#;
+    /// let file = r#"{"@context": "http://schema.org/", "@id": "http://example.com/s", "name": "foo"}"#;
+    ///
+    /// let parser = DatasetParser::from_format(DatasetFormat::JsonLd).with_json_ld_context("http://schema.org/", context)?;
+    /// let quads = parser.read_quads(Cursor::new(file))?.collect::<Result<Vec<_>,_>>()?;
+    ///
+    ///assert_eq!(quads.len(), 1);
+    ///assert_eq!(quads[0].predicate.to_string(), "<http://schema.org/name>");
+    /// # std::io::Result::Ok(())
+    /// ```
+    pub fn with_json_ld_context(
+        mut self,
+        iri: impl Into<String>,
+        context: &str,
+    ) -> Result<Self, io::Error> {
+        self.json_ld_contexts
+            .insert(iri.into(), parse_context_document(context)?);
+        Ok(self)
+    }
+
+    /// Switches the parser to the lenient mode: invalid statements are skipped and the parsing goes on with the next statement.
+    ///
+    /// Each skipped statement is reported to the `on_error` callback with its position in the file.
+    /// The lenient mode is only supported by N-Quads and TriG. JSON-LD parsing still aborts on the first error.
+    ///
+    /// See [`GraphParser::lenient`] for a usage example.
+    pub fn lenient(mut self, on_error: impl Fn(SyntaxError) + Send + Sync + 'static) -> Self {
+        self.on_error = Some(Arc::new(on_error));
+        self
+    }
+
     /// Executes the parsing itself on a [`BufRead`](std::io::BufRead) implementation and returns an iterator of quads
     pub fn read_quads<R: BufRead>(&self, reader: R) -> Result<QuadReader<R>, io::Error> {
         Ok(QuadReader {
-            mapper: RioMapper::default(),
+            prefixes: PrefixMap::with_base_iri(self.base_iri.clone()),
             parser: match self.format {
-                DatasetFormat::NQuads => QuadReaderKind::NQuads(NQuadsParser::new(reader)),
-                DatasetFormat::TriG => {
-                    QuadReaderKind::TriG(TriGParser::new(reader, self.base_iri.clone()))
-                }
+                DatasetFormat::NQuads => QuadReaderKind::Turtle(Box::new(
+                    TurtleParser::new(reader, TurtleSyntax::NQuads, None)
+                        .with_error_handler(self.on_error.clone()),
+                )),
+                DatasetFormat::TriG => QuadReaderKind::Turtle(Box::new(
+                    TurtleParser::new(reader, TurtleSyntax::TriG, self.base_iri.clone())
+                        .with_error_handler(self.on_error.clone()),
+                )),
+                DatasetFormat::JsonLd => QuadReaderKind::JsonLd(JsonLdParser::new(
+                    reader,
+                    self.base_iri.clone(),
+                    self.json_ld_contexts.clone(),
+                )),
             },
-            buffer: Vec::new(),
+        })
+    }
+
+    /// Executes the parsing on a [`BufRead`](std::io::BufRead) implementation like [`read_quads`](DatasetParser::read_quads())
+    /// but splits the file in chunks of lines that are parsed in parallel using the [rayon](https://docs.rs/rayon) global thread pool.
+    ///
+    /// The quads are returned in the file order.
+    /// The chunks are parsed one after the other if the `"parallel"` feature is disabled.
+    /// Only N-Quads is supported and each quad must be on its own line as required by the N-Quads specification.
+    ///
+    /// ```
+    /// use oxigraph::io::{DatasetFormat, DatasetParser};
+    /// use std::io::Cursor;
+    ///
+    /// let file = "<http://example.com/s> <http://example.com/p> <http://example.com/o> <http://example.com/g> .";
+    ///
+    /// let parser = DatasetParser::from_format(DatasetFormat::NQuads);
+    /// let quads = parser.read_quads_in_parallel(Cursor::new(file))?.collect::<Result<Vec<_>,_>>()?;
+    ///
+    ///assert_eq!(quads.len(), 1);
+    ///assert_eq!(quads[0].subject.to_string(), "<http://example.com/s>");
+    /// # std::io::Result::Ok(())
+    /// ```
+    pub fn read_quads_in_parallel<R: BufRead>(
+        &self,
+        reader: R,
+    ) -> Result<QuadReader<R>, io::Error> {
+        if self.format != DatasetFormat::NQuads {
+            return Err(invalid_input_error(format!(
+                "The {} format could not be parsed in parallel",
+                self.format.media_type()
+            )));
+        }
+        Ok(QuadReader {
+            prefixes: PrefixMap::default(),
+            parser: QuadReaderKind::ParallelTurtle(ParallelTurtleParser::new(
+                reader,
+                TurtleSyntax::NQuads,
+                self.on_error.clone(),
+            )),
         })
     }
 }
 
+impl From<DatasetFormat> for DatasetParser {
+    fn from(format: DatasetFormat) -> Self {
+        Self::from_format(format)
+    }
+}
+
 /// An iterator yielding read quads.
 /// Could be built using a [`DatasetParser`].
 ///
@@ -246,72 +459,228 @@ impl DatasetParser {
 /// ```
 #[must_use]
 pub struct QuadReader<R: BufRead> {
-    mapper: RioMapper,
+    prefixes: PrefixMap,
     parser: QuadReaderKind<R>,
-    buffer: Vec<Quad>,
+}
+
+impl<R: BufRead> QuadReader<R> {
+    /// The prefixes declared in the file read so far and the base IRI given to the parser.
+    ///
+    /// Only TriG declares prefixes. The `@base` declarations of the file are not tracked.
+    pub fn prefixes(&self) -> &PrefixMap {
+        match &self.parser {
+            QuadReaderKind::Turtle(parser) => parser.prefixes(),
+            QuadReaderKind::ParallelTurtle(_) | QuadReaderKind::JsonLd(_) => &self.prefixes,
+        }
+    }
 }
 
 enum QuadReaderKind<R: BufRead> {
-    NQuads(NQuadsParser<R>),
-    TriG(TriGParser<R>),
+    Turtle(Box<TurtleParser<R>>),
+    ParallelTurtle(ParallelTurtleParser<R>),
+    JsonLd(JsonLdParser<R>),
 }
 
 impl<R: BufRead> Iterator for QuadReader<R> {
     type Item = Result<Quad, io::Error>;
 
     fn next(&mut self) -> Option<Result<Quad, io::Error>> {
-        loop {
-            if let Some(r) = self.buffer.pop() {
-                return Some(Ok(r));
-            }
-
-            if let Err(error) = match &mut self.parser {
-                QuadReaderKind::NQuads(parser) => {
-                    Self::read(parser, &mut self.buffer, &mut self.mapper)
-                }
-                QuadReaderKind::TriG(parser) => {
-                    Self::read(parser, &mut self.buffer, &mut self.mapper)
-                }
-            }? {
-                return Some(Err(error));
-            }
+        match &mut self.parser {
+            QuadReaderKind::Turtle(parser) => parser.next(),
+            QuadReaderKind::ParallelTurtle(parser) => parser.next(),
+            QuadReaderKind::JsonLd(parser) => parser.next(),
         }
     }
 }
 
-impl<R: BufRead> QuadReader<R> {
-    fn read<P: QuadsParser>(
-        parser: &mut P,
-        buffer: &mut Vec<Quad>,
-        mapper: &mut RioMapper,
-    ) -> Option<Result<(), io::Error>>
-    where
-        io::Error: From<P::Error>,
-    {
-        if parser.is_end() {
-            None
-        } else if let Err(e) = parser.parse_step(&mut |t| {
-            buffer.push(mapper.quad(&t));
-            Ok(())
-        }) {
-            Some(Err(e))
+/// The prefixes declared in a Turtle or TriG file and the base IRI used to parse it.
+///
+/// They are returned by [`TripleReader::prefixes`] and [`QuadReader::prefixes`] and could be given back
+/// to the serializers using [`GraphSerializer::with_prefix_map`](super::GraphSerializer::with_prefix_map()).
+///
+/// Its [`Display`](std::fmt::Display) implementation writes the `BASE` and `PREFIX` declarations using the syntax shared by
+/// SPARQL and Turtle so it could be used as a SPARQL query prologue.
+///
+/// ```
+/// use oxigraph::io::{GraphFormat, GraphParser};
+/// use std::io::Cursor;
+///
+/// let file = "@prefix ex: <http://example.com/> . ex:s ex:p ex:o .";
+///
+/// let mut reader = GraphParser::from_format(GraphFormat::Turtle).read_triples(Cursor::new(file))?;
+/// reader.next().unwrap()?;
+/// let query = format!("{}SELECT ?o WHERE {{ ex:s ex:p ?o }}", reader.prefixes());
+///
+/// assert_eq!(query, "PREFIX ex: <http://example.com/>\nSELECT ?o WHERE { ex:s ex:p ?o }");
+/// # std::io::Result::Ok(())
+/// ```
+#[derive(Eq, PartialEq, Debug, Clone, Default)]
+pub struct PrefixMap {
+    pub(crate) base_iri: Option<Iri<String>>,
+    prefixes: Vec<(String, String)>,
+}
+
+impl PrefixMap {
+    pub(crate) fn with_base_iri(base_iri: Option<Iri<String>>) -> Self {
+        Self {
+            base_iri,
+            prefixes: Vec::new(),
+        }
+    }
+
+    /// Adds a prefix, replacing the previous declaration with the same name
+    pub(crate) fn insert(&mut self, prefix_name: String, prefix_iri: String) {
+        if let Some((_, iri)) = self.prefixes.iter_mut().find(|(n, _)| *n == prefix_name) {
+            *iri = prefix_iri;
         } else {
-            Some(Ok(()))
+            self.prefixes.push((prefix_name, prefix_iri));
         }
     }
+
+    /// The current base IRI
+    pub fn base_iri(&self) -> Option<&str> {
+        self.base_iri.as_ref().map(|iri| iri.as_str())
+    }
+
+    /// The IRI of the prefix with the given name
+    pub fn get(&self, prefix_name: &str) -> Option<&str> {
+        self.prefixes
+            .iter()
+            .find(|(n, _)| n == prefix_name)
+            .map(|(_, iri)| iri.as_str())
+    }
+
+    /// The `(name, IRI)` pairs of all prefixes in declaration order
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.prefixes
+            .iter()
+            .map(|(name, iri)| (name.as_str(), iri.as_str()))
+    }
+
+    /// The number of prefixes
+    pub fn len(&self) -> usize {
+        self.prefixes.len()
+    }
+
+    /// Checks if no prefixes are declared
+    pub fn is_empty(&self) -> bool {
+        self.prefixes.is_empty()
+    }
+}
+
+impl fmt::Display for PrefixMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(base_iri) = &self.base_iri {
+            writeln!(f, "BASE <{}>", base_iri.as_str())?;
+        }
+        for (name, iri) in &self.prefixes {
+            writeln!(f, "PREFIX {}: <{}>", name, iri)?;
+        }
+        Ok(())
+    }
+}
+
+/// A syntax error raised by the N-Triples, N-Quads, Turtle and TriG parsers.
+///
+/// It is returned wrapped in an [`io::Error`](std::io::Error) with the [`InvalidData`](std::io::ErrorKind::InvalidData) kind
+/// and could be retrieved using [`io::Error::get_ref`](std::io::Error::get_ref()).
+/// In the lenient mode it is given to the error callback.
+///
+/// ```
+/// use oxigraph::io::{GraphFormat, GraphParser, SyntaxError};
+/// use std::io::Cursor;
+///
+/// let file = "<http://example.com/s> <http://example.com/p> <http://example.com/o> .\n<http://example.com/s> foo .";
+///
+/// let error = GraphParser::from_format(GraphFormat::NTriples)
+///     .read_triples(Cursor::new(file))?
+///     .collect::<Result<Vec<_>,_>>()
+///     .unwrap_err();
+/// let error = error.get_ref().unwrap().downcast_ref::<SyntaxError>().unwrap();
+///
+/// assert_eq!(error.position().line, 2);
+/// assert_eq!(error.position().column, 24);
+/// # std::io::Result::Ok(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct SyntaxError {
+    message: String,
+    position: TextPosition,
 }
 
+impl SyntaxError {
+    pub(crate) fn new(message: impl Into<String>, position: TextPosition) -> Self {
+        Self {
+            message: message.into(),
+            position,
+        }
+    }
+
+    /// The error message without the position
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// The position in the file where the error has been detected
+    pub fn position(&self) -> TextPosition {
+        self.position
+    }
+}
+
+impl fmt::Display for SyntaxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} on line {} at column {}",
+            self.message, self.position.line, self.position.column
+        )
+    }
+}
+
+impl Error for SyntaxError {}
+
+impl From<SyntaxError> for io::Error {
+    fn from(error: SyntaxError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, error)
+    }
+}
+
+/// A position in a parsed file.
+#[derive(Eq, PartialEq, Debug, Clone, Copy, Hash)]
+pub struct TextPosition {
+    /// The line number, starting from 1
+    pub line: u64,
+    /// The column number in characters, starting from 1
+    pub column: u64,
+    /// The offset in bytes from the file start, starting from 0
+    pub offset: u64,
+}
+
+pub(crate) type SyntaxErrorHandler = Arc<dyn Fn(SyntaxError) + Send + Sync>;
+
 #[derive(Default)]
-struct RioMapper {
+pub(crate) struct RioMapper {
     bnode_map: HashMap<String, BlankNode>,
+    blank_node_seed: Option<(u64, u64)>,
 }
 
 impl<'a> RioMapper {
+    /// Derives the blank nodes from their labels and `seed` so that mappers sharing the same seed return the same blank nodes
+    pub fn with_blank_node_seed(mut self, seed: (u64, u64)) -> Self {
+        self.blank_node_seed = Some(seed);
+        self
+    }
+
     fn named_node(&self, node: rio::NamedNode<'a>) -> NamedNode {
         NamedNode::new_unchecked(node.iri)
     }
 
     fn blank_node(&mut self, node: rio::BlankNode<'a>) -> BlankNode {
+        if let Some(seed) = self.blank_node_seed {
+            let mut hasher = SipHasher24::new_with_keys(seed.0, seed.1);
+            hasher.write(node.id.as_bytes());
+            return BlankNode::new_from_unique_id(u128::from(hasher.finish128()));
+        }
         self.bnode_map
             .entry(node.id.to_owned())
             .or_insert_with(BlankNode::default)
@@ -330,10 +699,11 @@ impl<'a> RioMapper {
         }
     }
 
-    fn named_or_blank_node(&mut self, node: rio::NamedOrBlankNode<'a>) -> NamedOrBlankNode {
+    fn subject(&mut self, node: rio::Subject<'a>) -> Subject {
         match node {
-            rio::NamedOrBlankNode::NamedNode(node) => self.named_node(node).into(),
-            rio::NamedOrBlankNode::BlankNode(node) => self.blank_node(node).into(),
+            rio::Subject::NamedNode(node) => self.named_node(node).into(),
+            rio::Subject::BlankNode(node) => self.blank_node(node).into(),
+            rio::Subject::Triple(triple) => self.triple(triple).into(),
         }
     }
 
@@ -342,28 +712,29 @@ impl<'a> RioMapper {
             rio::Term::NamedNode(node) => self.named_node(node).into(),
             rio::Term::BlankNode(node) => self.blank_node(node).into(),
             rio::Term::Literal(literal) => self.literal(literal).into(),
+            rio::Term::Triple(triple) => self.triple(triple).into(),
         }
     }
 
-    fn triple(&mut self, triple: &rio::Triple<'a>) -> Triple {
+    pub fn triple(&mut self, triple: &rio::Triple<'a>) -> Triple {
         Triple {
-            subject: self.named_or_blank_node(triple.subject),
+            subject: self.subject(triple.subject),
             predicate: self.named_node(triple.predicate),
             object: self.term(triple.object),
         }
     }
 
-    fn graph_name(&mut self, graph_name: Option<rio::NamedOrBlankNode<'a>>) -> GraphName {
+    fn graph_name(&mut self, graph_name: Option<rio::GraphName<'a>>) -> GraphName {
         match graph_name {
-            Some(rio::NamedOrBlankNode::NamedNode(node)) => self.named_node(node).into(),
-            Some(rio::NamedOrBlankNode::BlankNode(node)) => self.blank_node(node).into(),
+            Some(rio::GraphName::NamedNode(node)) => self.named_node(node).into(),
+            Some(rio::GraphName::BlankNode(node)) => self.blank_node(node).into(),
             None => GraphName::DefaultGraph,
         }
     }
 
-    fn quad(&mut self, quad: &rio::Quad<'a>) -> Quad {
+    pub fn quad(&mut self, quad: &rio::Quad<'a>) -> Quad {
         Quad {
-            subject: self.named_or_blank_node(quad.subject),
+            subject: self.subject(quad.subject),
             predicate: self.named_node(quad.predicate),
             object: self.term(quad.object),
             graph_name: self.graph_name(quad.graph_name),
@@ -0,0 +1,1803 @@
+//! Parser and formatters for the Turtle family of formats ([N-Triples](https://www.w3.org/TR/n-triples/), [N-Quads](https://www.w3.org/TR/n-quads/), [Turtle](https://www.w3.org/TR/turtle/) and [TriG](https://www.w3.org/TR/trig/))
+//! including their [RDF-star](https://w3c.github.io/rdf-star/cg-spec/editors_draft.html) extensions.
+
+use crate::error::invalid_input_error;
+use crate::io::read::{PrefixMap, RioMapper, SyntaxError, SyntaxErrorHandler, TextPosition};
+use crate::model::vocab::{rdf, xsd};
+use crate::model::*;
+use oxiri::Iri;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use rio_api::parser::{ParseError, QuadsParser, TriplesParser};
+use rio_turtle::{NQuadsParser, NTriplesParser, TriGParser, TurtleError};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::io;
+use std::io::{BufRead, BufReader, Cursor, Read, Write};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+pub(crate) enum TurtleSyntax {
+    NTriples,
+    NQuads,
+    Turtle,
+    TriG,
+}
+
+impl TurtleSyntax {
+    fn is_line_based(self) -> bool {
+        matches!(self, Self::NTriples | Self::NQuads)
+    }
+}
+
+/// A streaming parser returning the parsed triples as quads.
+///
+/// The parsing itself is done by [Rio](https://docs.rs/rio_turtle).
+/// The triples of N-Triples and Turtle documents are all returned in the default graph.
+///
+/// If an error handler is set, the statements with a syntax error are reported to it and skipped.
+/// The Turtle and TriG parsing goes on after the statement end and the blank node labels used before the error
+/// do not identify the same blank nodes after it.
+pub(crate) struct TurtleParser<R: BufRead> {
+    kind: TurtleParserKind<R>,
+}
+
+enum TurtleParserKind<R: BufRead> {
+    Lines(ParallelTurtleParser<R>, PrefixMap),
+    Document(DocumentParser<R>),
+}
+
+impl<R: BufRead> TurtleParser<R> {
+    pub fn new(reader: R, syntax: TurtleSyntax, base_iri: Option<Iri<String>>) -> Self {
+        Self {
+            kind: if syntax.is_line_based() {
+                TurtleParserKind::Lines(
+                    ParallelTurtleParser::new(reader, syntax, None).sequential(),
+                    PrefixMap::default(),
+                )
+            } else {
+                TurtleParserKind::Document(DocumentParser::new(reader, syntax, base_iri))
+            },
+        }
+    }
+
+    /// Sets the handler the skipped syntax errors are reported to
+    pub fn with_error_handler(mut self, on_error: Option<SyntaxErrorHandler>) -> Self {
+        match &mut self.kind {
+            TurtleParserKind::Lines(parser, _) => parser.on_error = on_error,
+            TurtleParserKind::Document(parser) => parser.on_error = on_error,
+        }
+        self
+    }
+
+    /// The prefixes declared so far and the base IRI given to the parser
+    ///
+    /// The `@base` declarations of the file are not tracked.
+    pub fn prefixes(&self) -> &PrefixMap {
+        match &self.kind {
+            TurtleParserKind::Lines(_, prefixes) => prefixes,
+            TurtleParserKind::Document(parser) => &parser.prefixes,
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for TurtleParser<R> {
+    type Item = io::Result<Quad>;
+
+    fn next(&mut self) -> Option<io::Result<Quad>> {
+        match &mut self.kind {
+            TurtleParserKind::Lines(parser, _) => parser.next(),
+            TurtleParserKind::Document(parser) => parser.next(),
+        }
+    }
+}
+
+/// The minimal size in bytes of the chunks parsed in parallel
+const PARALLEL_CHUNK_SIZE: usize = 1 << 20;
+
+/// A N-Triples or N-Quads parser splitting its input in chunks of lines and parsing them in parallel using the rayon thread pool.
+///
+/// The quads are returned in the file order.
+/// The chunks are parsed sequentially if the `"parallel"` feature is disabled.
+pub(crate) struct ParallelTurtleParser<R: BufRead> {
+    reader: R,
+    syntax: TurtleSyntax,
+    on_error: Option<SyntaxErrorHandler>,
+    blank_node_seed: (u64, u64),
+    /// The number of chunks read before parsing them
+    chunk_count: usize,
+    /// The position of the next chunk
+    position: TextPosition,
+    output: VecDeque<io::Result<Quad>>,
+    is_reader_ended: bool,
+    is_ended: bool,
+}
+
+impl<R: BufRead> ParallelTurtleParser<R> {
+    pub fn new(reader: R, syntax: TurtleSyntax, on_error: Option<SyntaxErrorHandler>) -> Self {
+        debug_assert!(syntax.is_line_based());
+        Self {
+            reader,
+            syntax,
+            on_error,
+            blank_node_seed: rand::random(),
+            #[cfg(feature = "parallel")]
+            chunk_count: rayon::current_num_threads(),
+            #[cfg(not(feature = "parallel"))]
+            chunk_count: 1,
+            position: TextPosition {
+                line: 1,
+                column: 1,
+                offset: 0,
+            },
+            output: VecDeque::new(),
+            is_reader_ended: false,
+            is_ended: false,
+        }
+    }
+
+    /// Parses the chunks one after the other
+    fn sequential(mut self) -> Self {
+        self.chunk_count = 1;
+        self
+    }
+
+    /// Reads a chunk of full lines and returns it with its start position
+    fn read_chunk(&mut self) -> io::Result<Option<(Vec<u8>, TextPosition)>> {
+        let mut chunk = Vec::with_capacity(PARALLEL_CHUNK_SIZE);
+        while chunk.len() < PARALLEL_CHUNK_SIZE {
+            if self.reader.read_until(b'\n', &mut chunk)? == 0 {
+                self.is_reader_ended = true;
+                break;
+            }
+        }
+        if chunk.is_empty() {
+            return Ok(None);
+        }
+        let position = self.position;
+        self.position.line += chunk.iter().filter(|c| **c == b'\n').count() as u64;
+        self.position.offset += chunk.len() as u64;
+        Ok(Some((chunk, position)))
+    }
+
+    #[cfg(feature = "parallel")]
+    fn parse_chunks(&self, chunks: Vec<(Vec<u8>, TextPosition)>) -> Vec<Vec<io::Result<Quad>>> {
+        let (syntax, on_error, seed) = (self.syntax, &self.on_error, self.blank_node_seed);
+        chunks
+            .into_par_iter()
+            .map(|(chunk, position)| parse_chunk(&chunk, position, syntax, on_error, seed))
+            .collect()
+    }
+
+    #[cfg(not(feature = "parallel"))]
+    fn parse_chunks(&self, chunks: Vec<(Vec<u8>, TextPosition)>) -> Vec<Vec<io::Result<Quad>>> {
+        chunks
+            .into_iter()
+            .map(|(chunk, position)| {
+                parse_chunk(
+                    &chunk,
+                    position,
+                    self.syntax,
+                    &self.on_error,
+                    self.blank_node_seed,
+                )
+            })
+            .collect()
+    }
+
+    /// Reads and parses the next chunks, one for each thread
+    fn parse_next_chunks(&mut self) {
+        let mut chunks = Vec::with_capacity(self.chunk_count);
+        let mut read_error = None;
+        while chunks.len() < self.chunk_count && !self.is_reader_ended {
+            match self.read_chunk() {
+                Ok(Some(chunk)) => chunks.push(chunk),
+                Ok(None) => (),
+                Err(error) => {
+                    self.is_reader_ended = true;
+                    read_error = Some(error);
+                }
+            }
+        }
+        for quads in self.parse_chunks(chunks) {
+            self.output.extend(quads);
+        }
+        self.output.extend(read_error.map(Err));
+    }
+}
+
+impl<R: BufRead> Iterator for ParallelTurtleParser<R> {
+    type Item = io::Result<Quad>;
+
+    fn next(&mut self) -> Option<io::Result<Quad>> {
+        loop {
+            if let Some(result) = self.output.pop_front() {
+                if result.is_err() {
+                    self.is_ended = true;
+                    self.output.clear();
+                }
+                return Some(result);
+            }
+            if self.is_ended || self.is_reader_ended {
+                return None;
+            }
+            self.parse_next_chunks();
+        }
+    }
+}
+
+fn parse_chunk(
+    chunk: &[u8],
+    position: TextPosition,
+    syntax: TurtleSyntax,
+    on_error: &Option<SyntaxErrorHandler>,
+    blank_node_seed: (u64, u64),
+) -> Vec<io::Result<Quad>> {
+    let mut mapper = RioMapper::default().with_blank_node_seed(blank_node_seed);
+    let mut quads = Vec::new();
+    if parse_lines(chunk, syntax, &mut mapper, &mut quads).is_ok() {
+        return quads.into_iter().map(Ok).collect();
+    }
+
+    // The chunk is parsed again line by line to locate the errors and skip the invalid lines
+    let mut output = Vec::new();
+    let (mut line, mut offset) = (position.line, position.offset);
+    for data in chunk.split_inclusive(|c| *c == b'\n') {
+        quads.clear();
+        match parse_lines(data, syntax, &mut mapper, &mut quads) {
+            Ok(()) => output.extend(quads.drain(..).map(Ok)),
+            Err(error) => {
+                let byte = error
+                    .textual_position()
+                    .map_or(0, |p| p.byte_number() as usize)
+                    .min(data.len());
+                let error = SyntaxError::new(
+                    turtle_error_message(&error),
+                    TextPosition {
+                        line,
+                        column: char_count(&data[..byte]) + 1,
+                        offset: offset + byte as u64,
+                    },
+                );
+                if let Some(on_error) = on_error {
+                    on_error(error);
+                } else {
+                    output.push(Err(error.into()));
+                    return output;
+                }
+            }
+        }
+        line += 1;
+        offset += data.len() as u64;
+    }
+    output
+}
+
+/// Parses N-Triples or N-Quads lines with Rio
+fn parse_lines(
+    data: &[u8],
+    syntax: TurtleSyntax,
+    mapper: &mut RioMapper,
+    quads: &mut Vec<Quad>,
+) -> Result<(), TurtleError> {
+    if syntax == TurtleSyntax::NQuads {
+        NQuadsParser::new(data).parse_all(&mut |q| {
+            quads.push(mapper.quad(&q));
+            Ok(())
+        })
+    } else {
+        NTriplesParser::new(data).parse_all(&mut |t| {
+            quads.push(mapper.triple(&t).in_graph(GraphName::DefaultGraph));
+            Ok(())
+        })
+    }
+}
+
+/// The number of bytes read before the parsing position that are kept to restart the Turtle and TriG parsing after an error
+const WINDOW_SIZE: u64 = 1 << 20;
+
+/// A Turtle or TriG parser restarting Rio after the invalid statements
+struct DocumentParser<R: BufRead> {
+    syntax: TurtleSyntax,
+    base_iri: Option<Iri<String>>,
+    window: Arc<Mutex<Window<R>>>,
+    parser: RioDocumentParser<R>,
+    /// The number of lines of the prefix declarations given to `parser` before the file content
+    header_lines: u64,
+    /// The offset of the first byte of the file given to `parser`
+    input_offset: u64,
+    /// The line of the first byte of the file given to `parser`
+    input_line: u64,
+    mapper: RioMapper,
+    prefixes: PrefixMap,
+    output: VecDeque<Quad>,
+    on_error: Option<SyntaxErrorHandler>,
+    is_ended: bool,
+}
+
+type RioInput<R> = BufReader<io::Chain<Cursor<Vec<u8>>, WindowReader<R>>>;
+
+enum RioDocumentParser<R: BufRead> {
+    Turtle(rio_turtle::TurtleParser<RioInput<R>>),
+    TriG(TriGParser<RioInput<R>>),
+}
+
+impl<R: BufRead> DocumentParser<R> {
+    fn new(reader: R, syntax: TurtleSyntax, base_iri: Option<Iri<String>>) -> Self {
+        let window = Arc::new(Mutex::new(Window {
+            reader,
+            buffer: Vec::new(),
+            start_offset: 0,
+            start_line: 1,
+            position: 0,
+            is_end: false,
+        }));
+        Self {
+            syntax,
+            parser: RioDocumentParser::new(
+                syntax,
+                Vec::new(),
+                WindowReader(window.clone()),
+                base_iri.clone(),
+            ),
+            prefixes: PrefixMap::with_base_iri(base_iri.clone()),
+            base_iri,
+            window,
+            header_lines: 0,
+            input_offset: 0,
+            input_line: 1,
+            mapper: RioMapper::default(),
+            output: VecDeque::new(),
+            on_error: None,
+            is_ended: false,
+        }
+    }
+
+    fn parse_step(&mut self) -> Result<(), TurtleError> {
+        let (mapper, output) = (&mut self.mapper, &mut self.output);
+        let output_len = output.len();
+        let result = match &mut self.parser {
+            RioDocumentParser::Turtle(parser) => {
+                let result = if parser.is_end() {
+                    self.is_ended = true;
+                    Ok(())
+                } else {
+                    parser.parse_step(&mut |t| {
+                        output.push_back(mapper.triple(&t).in_graph(GraphName::DefaultGraph));
+                        Ok(())
+                    })
+                };
+                sync_prefixes(&mut self.prefixes, parser.prefixes());
+                result
+            }
+            RioDocumentParser::TriG(parser) => {
+                let result = if parser.is_end() {
+                    self.is_ended = true;
+                    Ok(())
+                } else {
+                    parser.parse_step(&mut |q| {
+                        output.push_back(mapper.quad(&q));
+                        Ok(())
+                    })
+                };
+                sync_prefixes(&mut self.prefixes, parser.prefixes());
+                result
+            }
+        };
+        if result.is_err() {
+            // The triples already emitted by the invalid statement are dropped
+            output.truncate(output_len);
+        }
+        result
+    }
+
+    /// Reports the error and restarts the parsing after the invalid statement
+    fn recover(&mut self, error: TurtleError) -> io::Result<()> {
+        let position = error.textual_position();
+        let message = turtle_error_message(&error);
+        let error = io::Error::from(error);
+        if error.kind() != io::ErrorKind::InvalidData {
+            return Err(error);
+        }
+        let position = match position {
+            Some(position) if position.line_number() >= self.header_lines => position,
+            _ => return Err(error),
+        };
+        let mut window = lock_window(&self.window)?;
+        let line = self.input_line + position.line_number() - self.header_lines;
+        let offset = if position.line_number() == self.header_lines {
+            self.input_offset + position.byte_number()
+        } else {
+            window.line_start_after(self.input_offset, line - self.input_line)
+                + position.byte_number()
+        }
+        .min(window.end_offset());
+        let error = SyntaxError::new(
+            message,
+            TextPosition {
+                line,
+                column: window.column(offset),
+                offset,
+            },
+        );
+        let on_error = match &self.on_error {
+            Some(on_error) => on_error.clone(),
+            None => return Err(error.into()),
+        };
+        match window.statement_end(offset, self.syntax)? {
+            Some(end) => {
+                let end_line = line + window.newline_count(offset, end);
+                drop(window);
+                self.restart(end, end_line)?;
+            }
+            None => self.is_ended = true,
+        }
+        on_error(error);
+        Ok(())
+    }
+
+    /// Starts a new Rio parser at the given position with the prefixes declared so far
+    fn restart(&mut self, offset: u64, line: u64) -> io::Result<()> {
+        lock_window(&self.window)?.position = offset;
+        let mut header = Vec::new();
+        for (name, iri) in self.prefixes.iter() {
+            write!(header, "@prefix {}: <{}> . ", name, iri)?;
+        }
+        self.header_lines = if header.is_empty() {
+            0
+        } else {
+            header.push(b'\n');
+            1
+        };
+        self.input_offset = offset;
+        self.input_line = line;
+        self.parser = RioDocumentParser::new(
+            self.syntax,
+            header,
+            WindowReader(self.window.clone()),
+            self.base_iri.clone(),
+        );
+        self.mapper = RioMapper::default();
+        Ok(())
+    }
+}
+
+impl<R: BufRead> Iterator for DocumentParser<R> {
+    type Item = io::Result<Quad>;
+
+    fn next(&mut self) -> Option<io::Result<Quad>> {
+        loop {
+            if let Some(quad) = self.output.pop_front() {
+                return Some(Ok(quad));
+            }
+            if self.is_ended {
+                return None;
+            }
+            if let Err(error) = self.parse_step() {
+                if let Err(error) = self.recover(error) {
+                    self.is_ended = true;
+                    return Some(Err(error));
+                }
+            }
+        }
+    }
+}
+
+impl<R: BufRead> RioDocumentParser<R> {
+    fn new(
+        syntax: TurtleSyntax,
+        header: Vec<u8>,
+        reader: WindowReader<R>,
+        base_iri: Option<Iri<String>>,
+    ) -> Self {
+        let input = BufReader::new(Cursor::new(header).chain(reader));
+        if syntax == TurtleSyntax::TriG {
+            Self::TriG(TriGParser::new(input, base_iri))
+        } else {
+            Self::Turtle(rio_turtle::TurtleParser::new(input, base_iri))
+        }
+    }
+}
+
+/// Adds the prefixes declared since the last call, sorted by name because Rio does not keep their order
+fn sync_prefixes(prefixes: &mut PrefixMap, declared: &HashMap<String, String>) {
+    let mut changed = declared
+        .iter()
+        .filter(|(name, iri)| prefixes.get(name) != Some(iri.as_str()))
+        .collect::<Vec<_>>();
+    changed.sort();
+    for (name, iri) in changed {
+        prefixes.insert(name.clone(), iri.clone());
+    }
+}
+
+/// The message of a Rio error without the position in the Rio input that does not match the one in the file
+fn turtle_error_message(error: &TurtleError) -> String {
+    let mut message = error.to_string();
+    if let Some(end) = message.rfind(" on line ") {
+        message.truncate(end);
+    }
+    message
+}
+
+fn char_count(data: &[u8]) -> u64 {
+    data.iter().filter(|c| (**c & 0xC0) != 0x80).count() as u64
+}
+
+/// The bytes read from the parsed file that are kept to give them again to a new Rio parser
+struct Window<R: BufRead> {
+    reader: R,
+    /// The read bytes, starting at the beginning of a line
+    buffer: Vec<u8>,
+    /// The offset in the file of the first byte of `buffer`
+    start_offset: u64,
+    /// The line of the first byte of `buffer`
+    start_line: u64,
+    /// The offset in the file of the next byte to give to the Rio parser
+    position: u64,
+    is_end: bool,
+}
+
+impl<R: BufRead> Window<R> {
+    /// Reads more bytes from the file and returns `false` if its end is reached
+    fn fill(&mut self) -> io::Result<bool> {
+        if self.is_end {
+            return Ok(false);
+        }
+        let data = self.reader.fill_buf()?;
+        if data.is_empty() {
+            self.is_end = true;
+            return Ok(false);
+        }
+        let len = data.len();
+        self.buffer.extend_from_slice(data);
+        self.reader.consume(len);
+        Ok(true)
+    }
+
+    fn byte(&mut self, offset: u64) -> io::Result<Option<u8>> {
+        while offset >= self.end_offset() {
+            if !self.fill()? {
+                return Ok(None);
+            }
+        }
+        Ok(self
+            .buffer
+            .get((offset - self.start_offset) as usize)
+            .copied())
+    }
+
+    fn end_offset(&self) -> u64 {
+        self.start_offset + self.buffer.len() as u64
+    }
+
+    fn slice(&self, start: u64, end: u64) -> &[u8] {
+        let start = start.saturating_sub(self.start_offset) as usize;
+        let end = end.saturating_sub(self.start_offset) as usize;
+        &self.buffer[start.min(self.buffer.len())..end.min(self.buffer.len())]
+    }
+
+    /// The offset of the start of the line containing `offset`
+    fn line_start(&self, offset: u64) -> u64 {
+        self.slice(self.start_offset, offset)
+            .iter()
+            .rposition(|c| *c == b'\n')
+            .map_or(self.start_offset, |i| self.start_offset + i as u64 + 1)
+    }
+
+    /// The offset of the start of the line after the `lines`-th line jump following `offset`
+    fn line_start_after(&self, offset: u64, lines: u64) -> u64 {
+        let mut count = 0;
+        for (i, c) in self.slice(offset, self.end_offset()).iter().enumerate() {
+            if *c == b'\n' {
+                count += 1;
+                if count == lines {
+                    return offset + i as u64 + 1;
+                }
+            }
+        }
+        self.end_offset()
+    }
+
+    /// The column of the byte at `offset` in characters, starting from 1
+    fn column(&self, offset: u64) -> u64 {
+        char_count(self.slice(self.line_start(offset), offset)) + 1
+    }
+
+    fn newline_count(&self, start: u64, end: u64) -> u64 {
+        self.slice(start, end)
+            .iter()
+            .filter(|c| **c == b'\n')
+            .count() as u64
+    }
+
+    /// Returns the offset after the end of the statement containing the given offset or `None` if the file ends before
+    fn statement_end(&mut self, offset: u64, syntax: TurtleSyntax) -> io::Result<Option<u64>> {
+        if syntax == TurtleSyntax::TriG {
+            // If the error is inside of a graph block the rest of the block is skipped
+            let mut current = offset;
+            while let Some((c, next)) = self.next_token_byte(current)? {
+                match c {
+                    b'}' => return Ok(Some(next)),
+                    b'{' => break,
+                    _ if next - offset > WINDOW_SIZE => break,
+                    _ => current = next,
+                }
+            }
+        }
+        let mut current = offset;
+        while let Some((c, next)) = self.next_token_byte(current)? {
+            if c == b'.'
+                && self
+                    .byte(next)?
+                    .map_or(true, |c| matches!(c, b' ' | b'\t' | b'\n' | b'\r' | b'#'))
+            {
+                return Ok(Some(next));
+            }
+            current = next;
+        }
+        Ok(None)
+    }
+
+    /// Returns the first byte at or after `offset` that is not in an IRI, a string or a comment and the offset after it
+    fn next_token_byte(&mut self, mut offset: u64) -> io::Result<Option<(u8, u64)>> {
+        loop {
+            let c = match self.byte(offset)? {
+                Some(c) => c,
+                None => return Ok(None),
+            };
+            offset += 1;
+            match c {
+                b'<' if self.byte(offset)? == Some(b'<') => offset += 1,
+                b'<' => offset = self.skip_until(offset, |c| matches!(c, b'>' | b'\n'))?,
+                b'#' => offset = self.skip_until(offset, |c| c == b'\n')?,
+                b'"' | b'\'' => offset = self.skip_string(offset, c)?,
+                _ => return Ok(Some((c, offset))),
+            }
+        }
+    }
+
+    /// Returns the offset after the first byte at or after `offset` matching `end`
+    fn skip_until(&mut self, mut offset: u64, end: impl Fn(u8) -> bool) -> io::Result<u64> {
+        while let Some(c) = self.byte(offset)? {
+            offset += 1;
+            if end(c) {
+                break;
+            }
+        }
+        Ok(offset)
+    }
+
+    /// Returns the offset after the end of the string starting just before `offset`
+    fn skip_string(&mut self, mut offset: u64, quote: u8) -> io::Result<u64> {
+        let is_long = self.byte(offset)? == Some(quote) && self.byte(offset + 1)? == Some(quote);
+        if is_long {
+            offset += 2;
+        }
+        while let Some(c) = self.byte(offset)? {
+            offset += 1;
+            match c {
+                b'\\' => offset += 1,
+                b'\n' if !is_long => break,
+                c if c == quote => {
+                    if !is_long {
+                        break;
+                    }
+                    if self.byte(offset)? == Some(quote) && self.byte(offset + 1)? == Some(quote) {
+                        return Ok(offset + 2);
+                    }
+                }
+                _ => (),
+            }
+        }
+        Ok(offset)
+    }
+
+    /// Drops the bytes more than [`WINDOW_SIZE`] bytes before the parsing position
+    fn trim(&mut self) {
+        if self.position < self.start_offset + 2 * WINDOW_SIZE {
+            return;
+        }
+        let limit = (self.position - WINDOW_SIZE - self.start_offset) as usize;
+        if let Some(end) = self.buffer[..limit].iter().rposition(|c| *c == b'\n') {
+            self.start_line += self.buffer[..=end].iter().filter(|c| **c == b'\n').count() as u64;
+            self.start_offset += end as u64 + 1;
+            self.buffer.drain(..=end);
+        }
+    }
+}
+
+/// Gives the bytes of the shared [`Window`] to a Rio parser
+struct WindowReader<R: BufRead>(Arc<Mutex<Window<R>>>);
+
+impl<R: BufRead> Read for WindowReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut window = lock_window(&self.0)?;
+        if window.position >= window.end_offset() && !window.fill()? {
+            return Ok(0);
+        }
+        let data = window.slice(window.position, window.end_offset());
+        let len = data.len().min(buf.len());
+        buf[..len].copy_from_slice(&data[..len]);
+        window.position += len as u64;
+        window.trim();
+        Ok(len)
+    }
+}
+
+fn lock_window<R: BufRead>(window: &Mutex<Window<R>>) -> io::Result<MutexGuard<'_, Window<R>>> {
+    window.lock().map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::Other,
+            "The parser input lock has been poisoned because of a panic",
+        )
+    })
+}
+
+fn is_pn_chars_base(c: char) -> bool {
+    matches!(c,
+        'A'..='Z'
+        | 'a'..='z'
+        | '\u{00C0}'..='\u{00D6}'
+        | '\u{00D8}'..='\u{00F6}'
+        | '\u{00F8}'..='\u{02FF}'
+        | '\u{0370}'..='\u{037D}'
+        | '\u{037F}'..='\u{1FFF}'
+        | '\u{200C}'..='\u{200D}'
+        | '\u{2070}'..='\u{218F}'
+        | '\u{2C00}'..='\u{2FEF}'
+        | '\u{3001}'..='\u{D7FF}'
+        | '\u{F900}'..='\u{FDCF}'
+        | '\u{FDF0}'..='\u{FFFD}'
+        | '\u{10000}'..='\u{EFFFF}')
+}
+
+fn is_pn_chars_u(c: char) -> bool {
+    c == '_' || is_pn_chars_base(c)
+}
+
+fn is_pn_chars(c: char) -> bool {
+    is_pn_chars_u(c)
+        || matches!(c,
+            '-'
+            | '0'..='9'
+            | '\u{00B7}'
+            | '\u{0300}'..='\u{036F}'
+            | '\u{203F}'..='\u{2040}')
+}
+
+/// A [Turtle](https://www.w3.org/TR/turtle/) formatter grouping the consecutive triples with the same subject and predicate
+pub(crate) struct TurtleFormatter<W: Write> {
+    write: W,
+    current_subject: Option<Subject>,
+    current_predicate: Option<NamedNode>,
+}
+
+impl<W: Write> TurtleFormatter<W> {
+    pub fn new(write: W) -> Self {
+        Self {
+            write,
+            current_subject: None,
+            current_predicate: None,
+        }
+    }
+
+    pub fn format(&mut self, triple: TripleRef<'_>) -> io::Result<()> {
+        if let Some(current_subject) = &self.current_subject {
+            if current_subject.as_ref() == triple.subject {
+                if self.current_predicate.as_ref().map(|p| p.as_ref()) == Some(triple.predicate) {
+                    write!(self.write, " , {}", triple.object)?;
+                } else {
+                    write!(self.write, " ;\n\t{} {}", triple.predicate, triple.object)?;
+                }
+            } else {
+                write!(
+                    self.write,
+                    " .\n{} {} {}",
+                    triple.subject, triple.predicate, triple.object
+                )?;
+            }
+        } else {
+            write!(
+                self.write,
+                "{} {} {}",
+                triple.subject, triple.predicate, triple.object
+            )?;
+        }
+        self.current_subject = Some(triple.subject.into_owned());
+        self.current_predicate = Some(triple.predicate.into_owned());
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> io::Result<W> {
+        if self.current_subject.is_some() {
+            writeln!(self.write, " .")?;
+        }
+        Ok(self.write)
+    }
+}
+
+/// A [TriG](https://www.w3.org/TR/trig/) formatter grouping the consecutive quads with the same graph name, subject and predicate
+pub(crate) struct TriGFormatter<W: Write> {
+    write: W,
+    current_graph_name: Option<GraphName>,
+    current_subject: Option<Subject>,
+    current_predicate: Option<NamedNode>,
+}
+
+impl<W: Write> TriGFormatter<W> {
+    pub fn new(write: W) -> Self {
+        Self {
+            write,
+            current_graph_name: None,
+            current_subject: None,
+            current_predicate: None,
+        }
+    }
+
+    pub fn format(&mut self, quad: QuadRef<'_>) -> io::Result<()> {
+        if let Some(current_graph_name) = &self.current_graph_name {
+            if current_graph_name.as_ref() == quad.graph_name {
+                if let Some(current_subject) = &self.current_subject {
+                    if current_subject.as_ref() == quad.subject {
+                        if self.current_predicate.as_ref().map(|p| p.as_ref())
+                            == Some(quad.predicate)
+                        {
+                            write!(self.write, " , {}", quad.object)?;
+                        } else {
+                            write!(self.write, " ;\n\t\t{} {}", quad.predicate, quad.object)?;
+                        }
+                    } else {
+                        write!(
+                            self.write,
+                            " .\n\t{} {} {}",
+                            quad.subject, quad.predicate, quad.object
+                        )?;
+                    }
+                }
+            } else {
+                if current_graph_name.is_default_graph() {
+                    writeln!(self.write, " .")?;
+                } else {
+                    writeln!(self.write, " .\n}}")?;
+                }
+                self.write_first_quad(quad)?;
+            }
+        } else {
+            self.write_first_quad(quad)?;
+        }
+        self.current_graph_name = Some(quad.graph_name.into_owned());
+        self.current_subject = Some(quad.subject.into_owned());
+        self.current_predicate = Some(quad.predicate.into_owned());
+        Ok(())
+    }
+
+    fn write_first_quad(&mut self, quad: QuadRef<'_>) -> io::Result<()> {
+        if quad.graph_name.is_default_graph() {
+            write!(
+                self.write,
+                "{} {} {}",
+                quad.subject, quad.predicate, quad.object
+            )
+        } else {
+            write!(
+                self.write,
+                "{} {{\n\t{} {} {}",
+                quad.graph_name, quad.subject, quad.predicate, quad.object
+            )
+        }
+    }
+
+    pub fn finish(mut self) -> io::Result<W> {
+        if self.current_subject.is_some() {
+            writeln!(self.write, " .")?;
+        }
+        if self
+            .current_graph_name
+            .map_or(false, |g| !g.is_default_graph())
+        {
+            writeln!(self.write, "}}")?;
+        }
+        Ok(self.write)
+    }
+}
+
+/// A [Turtle](https://www.w3.org/TR/turtle/) and [TriG](https://www.w3.org/TR/trig/) formatter for human readers
+///
+/// All the quads are kept in memory and written by [`finish`](PrettyFormatter::finish) grouped by graph and subject.
+/// IRIs are abbreviated using the prefixes and the base IRI, the blank nodes used only once as object are inlined
+/// using the `[ ]` syntax and the well-formed RDF lists are written as `( )` collections.
+/// Only the prefixes used in the output are declared.
+pub(crate) struct PrettyFormatter<W: Write> {
+    write: W,
+    syntax: TurtleSyntax,
+    prefixes: Vec<(String, String)>,
+    base_iri: Option<Iri<String>>,
+    graphs: Vec<(GraphName, Vec<Triple>)>,
+    graph_positions: HashMap<GraphName, usize>,
+}
+
+impl<W: Write> PrettyFormatter<W> {
+    pub fn new(
+        write: W,
+        syntax: TurtleSyntax,
+        prefixes: Vec<(String, String)>,
+        base_iri: Option<Iri<String>>,
+    ) -> Self {
+        Self {
+            write,
+            syntax,
+            prefixes,
+            base_iri,
+            graphs: Vec::new(),
+            graph_positions: HashMap::new(),
+        }
+    }
+
+    pub fn format(&mut self, quad: QuadRef<'_>) -> io::Result<()> {
+        if self.syntax == TurtleSyntax::Turtle && !quad.graph_name.is_default_graph() {
+            return Err(invalid_input_error(
+                "Turtle only supports triples in the default graph",
+            ));
+        }
+        let graph_name = quad.graph_name.into_owned();
+        let position = if let Some(position) = self.graph_positions.get(&graph_name) {
+            *position
+        } else {
+            self.graphs.push((graph_name.clone(), Vec::new()));
+            self.graph_positions
+                .insert(graph_name, self.graphs.len() - 1);
+            self.graphs.len() - 1
+        };
+        self.graphs[position].1.push(Triple::new(
+            quad.subject.into_owned(),
+            quad.predicate.into_owned(),
+            quad.object.into_owned(),
+        ));
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> io::Result<W> {
+        let inlinable = inlinable_blank_nodes(&self.graphs);
+        let mut terms = TermWriter {
+            prefixes: &self.prefixes,
+            used_prefixes: vec![false; self.prefixes.len()],
+            base_iri: self.base_iri.as_ref(),
+        };
+        let mut body = Vec::new();
+        for (graph_name, triples) in &self.graphs {
+            let indent = if graph_name.is_default_graph() {
+                0
+            } else {
+                terms.write_graph_name(&mut body, graph_name.as_ref())?;
+                body.write_all(b" {\n")?;
+                1
+            };
+            GraphWriter::new(triples, &inlinable, &mut terms, &mut body).write(indent)?;
+            if !graph_name.is_default_graph() {
+                body.write_all(b"}\n")?;
+            }
+        }
+
+        if let Some(base_iri) = &self.base_iri {
+            writeln!(self.write, "@base <{}> .", base_iri.as_str())?;
+        }
+        for ((name, iri), used) in self.prefixes.iter().zip(&terms.used_prefixes) {
+            if *used {
+                writeln!(self.write, "@prefix {}: <{}> .", name, iri)?;
+            }
+        }
+        if self.base_iri.is_some() || terms.used_prefixes.contains(&true) {
+            writeln!(self.write)?;
+        }
+        self.write.write_all(&body)?;
+        Ok(self.write)
+    }
+}
+
+/// Returns the blank nodes that are used only once as object, in the graph where they are subject,
+/// and are not used as graph name or in a quoted triple
+fn inlinable_blank_nodes(graphs: &[(GraphName, Vec<Triple>)]) -> HashSet<BlankNode> {
+    let mut object_uses = HashMap::<&BlankNode, (usize, usize)>::new();
+    let mut subject_graphs = HashMap::<&BlankNode, usize>::new();
+    let mut excluded = HashSet::<BlankNode>::new();
+    for (i, (graph_name, triples)) in graphs.iter().enumerate() {
+        if let GraphName::BlankNode(graph_name) = graph_name {
+            excluded.insert(graph_name.clone());
+        }
+        for triple in triples {
+            match &triple.subject {
+                Subject::BlankNode(subject) => {
+                    if *subject_graphs.entry(subject).or_insert(i) != i {
+                        excluded.insert(subject.clone());
+                    }
+                }
+                Subject::Triple(quoted) => add_quoted_blank_nodes(quoted, &mut excluded),
+                Subject::NamedNode(_) => (),
+            }
+            match &triple.object {
+                Term::BlankNode(object) => {
+                    let uses = object_uses.entry(object).or_insert((0, i));
+                    uses.0 += 1;
+                }
+                Term::Triple(quoted) => add_quoted_blank_nodes(quoted, &mut excluded),
+                Term::NamedNode(_) | Term::Literal(_) => (),
+            }
+        }
+    }
+    object_uses
+        .into_iter()
+        .filter(|(node, (count, graph))| {
+            *count == 1
+                && subject_graphs.get(node).map_or(true, |g| g == graph)
+                && !excluded.contains(*node)
+        })
+        .map(|(node, _)| node.clone())
+        .collect()
+}
+
+fn add_quoted_blank_nodes(triple: &Triple, blank_nodes: &mut HashSet<BlankNode>) {
+    match &triple.subject {
+        Subject::BlankNode(node) => {
+            blank_nodes.insert(node.clone());
+        }
+        Subject::Triple(quoted) => add_quoted_blank_nodes(quoted, blank_nodes),
+        Subject::NamedNode(_) => (),
+    }
+    match &triple.object {
+        Term::BlankNode(node) => {
+            blank_nodes.insert(node.clone());
+        }
+        Term::Triple(quoted) => add_quoted_blank_nodes(quoted, blank_nodes),
+        Term::NamedNode(_) | Term::Literal(_) => (),
+    }
+}
+
+type PredicateObjects<'a> = Vec<(&'a NamedNode, Vec<&'a Term>)>;
+
+/// Writes the triples of a graph grouped by subject
+struct GraphWriter<'a, 'b, O: Write> {
+    subjects: Vec<(&'a Subject, PredicateObjects<'a>)>,
+    blank_subjects: HashMap<&'a BlankNode, usize>,
+    inlinable: &'a HashSet<BlankNode>,
+    written: Vec<bool>,
+    terms: &'b mut TermWriter<'a>,
+    output: &'b mut O,
+}
+
+impl<'a, 'b, O: Write> GraphWriter<'a, 'b, O> {
+    fn new(
+        triples: &'a [Triple],
+        inlinable: &'a HashSet<BlankNode>,
+        terms: &'b mut TermWriter<'a>,
+        output: &'b mut O,
+    ) -> Self {
+        let mut subjects = Vec::<(&Subject, PredicateObjects<'a>)>::new();
+        let mut positions = HashMap::<&Subject, usize>::new();
+        for triple in triples {
+            let position = *positions.entry(&triple.subject).or_insert_with(|| {
+                subjects.push((&triple.subject, Vec::new()));
+                subjects.len() - 1
+            });
+            let predicates = &mut subjects[position].1;
+            if let Some((_, objects)) = predicates
+                .iter_mut()
+                .find(|(predicate, _)| **predicate == triple.predicate)
+            {
+                objects.push(&triple.object);
+            } else {
+                predicates.push((&triple.predicate, vec![&triple.object]));
+            }
+        }
+        let blank_subjects = subjects
+            .iter()
+            .enumerate()
+            .filter_map(|(i, (subject, _))| {
+                if let Subject::BlankNode(subject) = subject {
+                    Some((subject, i))
+                } else {
+                    None
+                }
+            })
+            .collect();
+        Self {
+            written: vec![false; subjects.len()],
+            subjects,
+            blank_subjects,
+            inlinable,
+            terms,
+            output,
+        }
+    }
+
+    fn write(mut self, indent: usize) -> io::Result<()> {
+        // We first write the subjects that are not inlined and then the remaining ones that are in reference cycles
+        for only_not_inlinable in &[true, false] {
+            for i in 0..self.subjects.len() {
+                if self.written[i] {
+                    continue;
+                }
+                if *only_not_inlinable {
+                    if let Subject::BlankNode(subject) = self.subjects[i].0 {
+                        if self.inlinable.contains(subject) {
+                            continue;
+                        }
+                    }
+                }
+                self.written[i] = true;
+                write_indent(self.output, indent)?;
+                self.terms
+                    .write_subject(self.output, self.subjects[i].0.as_ref())?;
+                self.output.write_all(b" ")?;
+                self.write_predicate_objects(i, indent + 1)?;
+                self.output.write_all(b" .\n")?;
+            }
+        }
+        Ok(())
+    }
+
+    fn write_predicate_objects(&mut self, subject: usize, indent: usize) -> io::Result<()> {
+        let predicates = self.subjects[subject].1.clone();
+        for (i, (predicate, objects)) in predicates.into_iter().enumerate() {
+            if i > 0 {
+                self.output.write_all(b" ;\n")?;
+                write_indent(self.output, indent)?;
+            }
+            if *predicate == rdf::TYPE {
+                self.output.write_all(b"a")?;
+            } else {
+                self.terms.write_iri(self.output, predicate.as_ref())?;
+            }
+            for (j, object) in objects.into_iter().enumerate() {
+                self.output.write_all(if j > 0 { b" , " } else { b" " })?;
+                self.write_object(object, indent)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn write_object(&mut self, object: &'a Term, indent: usize) -> io::Result<()> {
+        if let Term::BlankNode(node) = object {
+            if self.inlinable.contains(node) {
+                if let Some(items) = self.list_items(node) {
+                    self.output.write_all(b"(")?;
+                    for item in items {
+                        self.output.write_all(b" ")?;
+                        self.write_object(item, indent)?;
+                    }
+                    return self.output.write_all(b" )");
+                }
+                match self.blank_subjects.get(node) {
+                    Some(position) if !self.written[*position] => {
+                        let position = *position;
+                        self.written[position] = true;
+                        self.output.write_all(b"[ ")?;
+                        self.write_predicate_objects(position, indent + 1)?;
+                        return self.output.write_all(b" ]");
+                    }
+                    None => return self.output.write_all(b"[]"),
+                    Some(_) => (),
+                }
+            }
+        }
+        self.terms.write_term(self.output, object.as_ref())
+    }
+
+    /// Returns the items of the list starting with `node` if it could be written as a collection and marks its nodes as written
+    fn list_items(&mut self, node: &BlankNode) -> Option<Vec<&'a Term>> {
+        let mut items = Vec::new();
+        let mut positions = Vec::new();
+        let mut current = node;
+        loop {
+            let position = *self.blank_subjects.get(current)?;
+            if self.written[position] || positions.contains(&position) {
+                return None;
+            }
+            let predicates = &self.subjects[position].1;
+            if predicates.len() != 2 {
+                return None;
+            }
+            let mut first = None;
+            let mut rest = None;
+            for (predicate, objects) in predicates {
+                if objects.len() != 1 {
+                    return None;
+                }
+                if **predicate == rdf::FIRST {
+                    first = Some(objects[0]);
+                } else if **predicate == rdf::REST {
+                    rest = Some(objects[0]);
+                } else {
+                    return None;
+                }
+            }
+            items.push(first?);
+            positions.push(position);
+            match rest? {
+                Term::NamedNode(rest) if *rest == rdf::NIL => break,
+                Term::BlankNode(rest) if self.inlinable.contains(rest) => current = rest,
+                _ => return None,
+            }
+        }
+        for position in positions {
+            self.written[position] = true;
+        }
+        Some(items)
+    }
+}
+
+fn write_indent(output: &mut impl Write, indent: usize) -> io::Result<()> {
+    for _ in 0..indent {
+        output.write_all(b"\t")?;
+    }
+    Ok(())
+}
+
+/// Writes terms using the Turtle abbreviations
+struct TermWriter<'a> {
+    prefixes: &'a [(String, String)],
+    used_prefixes: Vec<bool>,
+    base_iri: Option<&'a Iri<String>>,
+}
+
+impl<'a> TermWriter<'a> {
+    fn write_iri(&mut self, output: &mut impl Write, iri: NamedNodeRef<'_>) -> io::Result<()> {
+        let iri = iri.as_str();
+        let prefix = self
+            .prefixes
+            .iter()
+            .enumerate()
+            .filter(|(_, (_, namespace))| {
+                iri.strip_prefix(namespace.as_str())
+                    .map_or(false, is_prefixed_name_local_part)
+            })
+            .max_by_key(|(_, (_, namespace))| namespace.len());
+        if let Some((i, (name, namespace))) = prefix {
+            self.used_prefixes[i] = true;
+            return write!(output, "{}:{}", name, &iri[namespace.len()..]);
+        }
+        if let Some(relative) = self.relative_iri(iri) {
+            return write!(output, "<{}>", relative);
+        }
+        write!(output, "<{}>", iri)
+    }
+
+    /// Returns a relative IRI resolving to `iri` against the base IRI
+    fn relative_iri<'b>(&self, iri: &'b str) -> Option<&'b str> {
+        let base_iri = self.base_iri?;
+        let base = base_iri.as_str();
+        let directory = &base[..base.rfind('/').map_or(0, |i| i + 1)];
+        [base, directory]
+            .iter()
+            .filter_map(|prefix| iri.strip_prefix(prefix))
+            .find(|relative| {
+                base_iri
+                    .resolve(relative)
+                    .map_or(false, |resolved| resolved.as_str() == iri)
+            })
+    }
+
+    fn write_subject(
+        &mut self,
+        output: &mut impl Write,
+        subject: SubjectRef<'_>,
+    ) -> io::Result<()> {
+        match subject {
+            SubjectRef::NamedNode(node) => self.write_iri(output, node),
+            SubjectRef::BlankNode(node) => write!(output, "{}", node),
+            SubjectRef::Triple(triple) => self.write_quoted_triple(output, triple),
+        }
+    }
+
+    fn write_term(&mut self, output: &mut impl Write, term: TermRef<'_>) -> io::Result<()> {
+        match term {
+            TermRef::NamedNode(node) => self.write_iri(output, node),
+            TermRef::BlankNode(node) => write!(output, "{}", node),
+            TermRef::Literal(literal) => self.write_literal(output, literal),
+            TermRef::Triple(triple) => self.write_quoted_triple(output, triple),
+        }
+    }
+
+    fn write_graph_name(
+        &mut self,
+        output: &mut impl Write,
+        graph_name: GraphNameRef<'_>,
+    ) -> io::Result<()> {
+        match graph_name {
+            GraphNameRef::NamedNode(node) => self.write_iri(output, node),
+            GraphNameRef::BlankNode(node) => write!(output, "{}", node),
+            GraphNameRef::DefaultGraph => Ok(()),
+        }
+    }
+
+    fn write_literal(
+        &mut self,
+        output: &mut impl Write,
+        literal: LiteralRef<'_>,
+    ) -> io::Result<()> {
+        let value = literal.value();
+        let datatype = literal.datatype();
+        if literal.language().is_some() || datatype == xsd::STRING {
+            write!(output, "{}", literal)
+        } else if (datatype == xsd::BOOLEAN && (value == "true" || value == "false"))
+            || (datatype == xsd::INTEGER && is_turtle_integer(value))
+            || (datatype == xsd::DECIMAL && is_turtle_decimal(value))
+            || (datatype == xsd::DOUBLE && is_turtle_double(value))
+        {
+            output.write_all(value.as_bytes())
+        } else {
+            write!(output, "{}^^", LiteralRef::new_simple_literal(value))?;
+            self.write_iri(output, datatype)
+        }
+    }
+
+    fn write_quoted_triple(&mut self, output: &mut impl Write, triple: &Triple) -> io::Result<()> {
+        output.write_all(b"<< ")?;
+        self.write_subject(output, triple.subject.as_ref())?;
+        output.write_all(b" ")?;
+        self.write_iri(output, triple.predicate.as_ref())?;
+        output.write_all(b" ")?;
+        self.write_term(output, triple.object.as_ref())?;
+        output.write_all(b" >>")
+    }
+}
+
+/// Checks if a prefix name is a valid Turtle [PN_PREFIX](https://www.w3.org/TR/turtle/#grammar-production-PN_PREFIX)
+pub(crate) fn is_valid_prefix_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        None => true,
+        Some(c) if is_pn_chars_base(c) => {
+            !name.ends_with('.') && chars.all(|c| is_pn_chars(c) || c == '.')
+        }
+        Some(_) => false,
+    }
+}
+
+/// Checks if the value could be written as the local part of a prefixed name without escapes
+fn is_prefixed_name_local_part(value: &str) -> bool {
+    let mut chars = value.chars();
+    match chars.next() {
+        None => true,
+        Some(c) if is_pn_chars_u(c) || c == ':' || c.is_ascii_digit() => {
+            !value.ends_with('.') && chars.all(|c| is_pn_chars(c) || c == '.' || c == ':')
+        }
+        Some(_) => false,
+    }
+}
+
+fn strip_sign(value: &str) -> &str {
+    value
+        .strip_prefix('+')
+        .or_else(|| value.strip_prefix('-'))
+        .unwrap_or(value)
+}
+
+fn is_turtle_integer(value: &str) -> bool {
+    let digits = strip_sign(value);
+    !digits.is_empty() && digits.bytes().all(|c| c.is_ascii_digit())
+}
+
+fn is_turtle_decimal(value: &str) -> bool {
+    if let Some((integer, fraction)) = strip_sign(value).split_once('.') {
+        integer.bytes().all(|c| c.is_ascii_digit())
+            && !fraction.is_empty()
+            && fraction.bytes().all(|c| c.is_ascii_digit())
+    } else {
+        false
+    }
+}
+
+fn is_turtle_double(value: &str) -> bool {
+    if let Some(position) = value.find(&['e', 'E'][..]) {
+        let (mantissa, exponent) = (strip_sign(&value[..position]), &value[position + 1..]);
+        let (integer, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+        (!integer.is_empty() || !fraction.is_empty())
+            && integer.bytes().all(|c| c.is_ascii_digit())
+            && fraction.bytes().all(|c| c.is_ascii_digit())
+            && is_turtle_integer(exponent)
+    } else {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use std::sync::{Arc, Mutex};
+
+    fn parse(syntax: TurtleSyntax, data: &str) -> io::Result<Vec<Quad>> {
+        TurtleParser::new(Cursor::new(data), syntax, None).collect()
+    }
+
+    #[test]
+    fn test_n_triples() -> io::Result<()> {
+        let quads = parse(
+            TurtleSyntax::NTriples,
+            "<http://example.com/s> <http://example.com/p> \"foo\\n\"@en . # comment\n\
+             _:a <http://example.com/p> \"1\"^^<http://www.w3.org/2001/XMLSchema#integer> .\n\
+             _:a <http://example.com/p> _:a .",
+        )?;
+        assert_eq!(quads.len(), 3);
+        assert_eq!(
+            quads[0].object,
+            Literal::new_language_tagged_literal("foo\n", "en")
+                .unwrap()
+                .into()
+        );
+        assert_eq!(quads[1].subject, quads[2].subject);
+        assert_eq!(Term::from(quads[1].subject.clone()), quads[2].object);
+
+        assert!(parse(TurtleSyntax::NTriples, "<s> <p> <o> .").is_err());
+        assert!(parse(
+            TurtleSyntax::NTriples,
+            "<http://example.com/s> <http://example.com/p> 1 ."
+        )
+        .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_n_quads() -> io::Result<()> {
+        let quads = parse(
+            TurtleSyntax::NQuads,
+            "<http://example.com/s> <http://example.com/p> <http://example.com/o> <http://example.com/g> .\n\
+             <http://example.com/s> <http://example.com/p> <http://example.com/o> .",
+        )?;
+        assert_eq!(quads.len(), 2);
+        assert_eq!(
+            quads[0].graph_name,
+            NamedNode::new("http://example.com/g").unwrap().into()
+        );
+        assert_eq!(quads[1].graph_name, GraphName::DefaultGraph);
+        Ok(())
+    }
+
+    #[test]
+    fn test_turtle() -> io::Result<()> {
+        let quads = TurtleParser::new(
+            Cursor::new(
+                "@prefix ex: <http://example.com/> .\n\
+                 PREFIX : <http://example.com/default#>\n\
+                 <s> a ex:Foo ; ex:p 1, -1.5, .5e1, true, 'a', \"\"\"b\n\"c\\\"\"\"\" ; ex:q ex:a.b .\n\
+                 [ ex:p ex:o ] .\n\
+                 [] ex:p ( 1 [ :p :o ] ) ;; .\n\
+                 ex:s ex:p ex:o.",
+            ),
+            TurtleSyntax::Turtle,
+            Some(Iri::parse("http://example.com/".to_owned()).unwrap()),
+        )
+        .collect::<io::Result<Vec<_>>>()?;
+        let ex = |name: &str| NamedNode::new(format!("http://example.com/{}", name)).unwrap();
+        assert_eq!(quads.len(), 16);
+        assert_eq!(quads[0].subject, ex("s").into());
+        assert_eq!(quads[0].predicate, rdf::TYPE);
+        assert_eq!(quads[0].object, ex("Foo").into());
+        assert_eq!(
+            quads[1].object,
+            Literal::new_typed_literal("1", xsd::INTEGER).into()
+        );
+        assert_eq!(
+            quads[2].object,
+            Literal::new_typed_literal("-1.5", xsd::DECIMAL).into()
+        );
+        assert_eq!(
+            quads[3].object,
+            Literal::new_typed_literal(".5e1", xsd::DOUBLE).into()
+        );
+        assert_eq!(
+            quads[4].object,
+            Literal::new_typed_literal("true", xsd::BOOLEAN).into()
+        );
+        assert_eq!(quads[5].object, Literal::new_simple_literal("a").into());
+        assert_eq!(
+            quads[6].object,
+            Literal::new_simple_literal("b\n\"c\"").into()
+        );
+        assert_eq!(quads[7].object, ex("a.b").into());
+        assert_eq!(quads[15].subject, ex("s").into());
+        assert_eq!(quads[15].object, ex("o").into());
+
+        assert!(parse(TurtleSyntax::Turtle, "ex:s ex:p ex:o .").is_err());
+        assert!(parse(
+            TurtleSyntax::Turtle,
+            "<http://example.com/s> <http://example.com/p> \"o\""
+        )
+        .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_trig() -> io::Result<()> {
+        let quads = parse(
+            TurtleSyntax::TriG,
+            "@prefix ex: <http://example.com/> .\n\
+             ex:s ex:p ex:o .\n\
+             ex:g { ex:s ex:p ex:o . ex:s ex:p ex:o2 }\n\
+             GRAPH _:g { ex:s ex:p ex:o }\n\
+             { ex:s ex:p ex:o3 . }",
+        )?;
+        assert_eq!(quads.len(), 5);
+        assert_eq!(quads[0].graph_name, GraphName::DefaultGraph);
+        assert_eq!(
+            quads[1].graph_name,
+            NamedNode::new("http://example.com/g").unwrap().into()
+        );
+        assert_eq!(quads[1].graph_name, quads[2].graph_name);
+        assert!(quads[3].graph_name.is_blank_node());
+        assert_eq!(quads[4].graph_name, GraphName::DefaultGraph);
+
+        assert!(parse(
+            TurtleSyntax::TriG,
+            "{ <http://example.com/s> <http://example.com/p> <http://example.com/o> "
+        )
+        .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_star() -> io::Result<()> {
+        let ex = |name: &str| NamedNode::new(format!("http://example.com/{}", name)).unwrap();
+        let quoted = Triple::new(ex("s"), ex("p"), ex("o"));
+
+        let quads = parse(
+            TurtleSyntax::NTriples,
+            "<< <http://example.com/s> <http://example.com/p> <http://example.com/o> >> <http://example.com/q> <<<http://example.com/s> <http://example.com/p> <http://example.com/o>>> .",
+        )?;
+        assert_eq!(quads.len(), 1);
+        assert_eq!(quads[0].subject, quoted.clone().into());
+        assert_eq!(quads[0].object, quoted.clone().into());
+
+        let quads = parse(
+            TurtleSyntax::Turtle,
+            "@prefix ex: <http://example.com/> .\n\
+             << ex:s ex:p ex:o >> ex:q << << ex:s ex:p ex:o >> ex:p \"a\" >> .\n\
+             ex:s ex:p ex:o {| ex:q ex:r |} .",
+        )?;
+        assert_eq!(quads.len(), 3);
+        assert_eq!(quads[0].subject, quoted.clone().into());
+        assert_eq!(
+            quads[0].object,
+            Triple::new(quoted.clone(), ex("p"), Literal::new_simple_literal("a")).into()
+        );
+        assert_eq!(quads[1], Quad::new(ex("s"), ex("p"), ex("o"), None));
+        assert_eq!(quads[2], Quad::new(quoted, ex("q"), ex("r"), None));
+        Ok(())
+    }
+
+    fn parse_lenient(syntax: TurtleSyntax, data: &[u8]) -> (Vec<Quad>, Vec<SyntaxError>) {
+        let errors = Arc::new(Mutex::new(Vec::new()));
+        let handler_errors = errors.clone();
+        let quads = TurtleParser::new(Cursor::new(data), syntax, None)
+            .with_error_handler(Some(Arc::new(move |e| {
+                handler_errors.lock().unwrap().push(e)
+            })))
+            .collect::<io::Result<Vec<_>>>()
+            .unwrap();
+        let errors = errors.lock().unwrap().clone();
+        (quads, errors)
+    }
+
+    fn lines(errors: &[SyntaxError]) -> Vec<u64> {
+        errors.iter().map(|e| e.position().line).collect()
+    }
+
+    #[test]
+    fn test_error_position() {
+        let error = parse(
+            TurtleSyntax::NTriples,
+            "<http://example.com/s> <http://example.com/p> <http://example.com/o> .\n\
+             <http://example.com/é> <http://example.com/p> foo .",
+        )
+        .unwrap_err();
+        let error = error
+            .get_ref()
+            .unwrap()
+            .downcast_ref::<SyntaxError>()
+            .unwrap();
+        assert_eq!(error.position().line, 2);
+        // The line starts at the offset 71 and "é" is encoded using two bytes
+        assert_eq!(error.position().offset, 71 + error.position().column);
+    }
+
+    #[test]
+    fn test_lenient_line_based() {
+        let (quads, errors) = parse_lenient(
+            TurtleSyntax::NQuads,
+            b"<http://example.com/s> <http://example.com/p> \"a\" .\n\
+             <http://example.com/s> <http://example.com/p> \"b .\n\
+             <http://example.com/s> <http://example.com/p> \"c\" <http://example.com/g> <http://example.com/g> .\n\
+             <http://example.com/s> <http://example.com/p> \"d\" <http://example.com/g> .\n\
+             <http://example.com/s> <http://example.com/p> \"\xFF\" .\n\
+             <http://example.com/s> <http://example.com/p> \"e\" .\n\
+             <http://example.com/s> <http://example.com/p>",
+        );
+        let objects = quads
+            .iter()
+            .map(|q| q.object.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(objects, vec!["\"a\"", "\"d\"", "\"e\""]);
+        assert_eq!(
+            quads[1].graph_name,
+            NamedNode::new_unchecked("http://example.com/g").into()
+        );
+        assert_eq!(lines(&errors), vec![2, 3, 5, 7]);
+    }
+
+    #[test]
+    fn test_lenient_turtle() {
+        let (quads, errors) = parse_lenient(
+            TurtleSyntax::TriG,
+            b"@prefix ex: <http://example.com/> .\n\
+             ex:s ex:p ex:o, ; ex:p2 \"a. b\" .\n\
+             ex:s ex:p 1.5 .\n\
+             ex:g { ex:s ex:p } ex:g { ex:s ex:p ex:o }\n\
+             ex:s foo:p [ ex:p ex:o ] . ex:s ex:p ( 1 2 ) .",
+        );
+        assert_eq!(quads.len(), 7);
+        assert_eq!(
+            quads[0].object,
+            Literal::new_typed_literal("1.5", xsd::DECIMAL).into()
+        );
+        assert_eq!(
+            quads[1].graph_name,
+            NamedNode::new_unchecked("http://example.com/g").into()
+        );
+        assert_eq!(lines(&errors), vec![2, 4, 5]);
+    }
+
+    #[test]
+    fn test_parallel_parsing() -> io::Result<()> {
+        let mut file = String::new();
+        for i in 0..50_000 {
+            if i == 40_000 {
+                file.push_str("<http://example.com/s> <http://example.com/p> \"foo .\n");
+            }
+            file.push_str(&format!(
+                "_:b{} <http://example.com/p> \"{}\" <http://example.com/g{}> .\n",
+                i % 100,
+                i,
+                i % 3
+            ));
+        }
+        assert!(file.len() > 2 * PARALLEL_CHUNK_SIZE);
+
+        let errors = Arc::new(Mutex::new(Vec::new()));
+        let handler_errors = errors.clone();
+        let quads = ParallelTurtleParser::new(
+            Cursor::new(&file),
+            TurtleSyntax::NQuads,
+            Some(Arc::new(move |e| handler_errors.lock().unwrap().push(e))),
+        )
+        .collect::<io::Result<Vec<_>>>()?;
+        assert_eq!(quads.len(), 50_000);
+        for (i, quad) in quads.iter().enumerate() {
+            assert_eq!(quad.object, Literal::from(i.to_string()).into());
+            assert_eq!(quad.subject, quads[i % 100].subject);
+        }
+        assert_ne!(quads[0].subject, quads[1].subject);
+        let errors = errors.lock().unwrap();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].position().line, 40_001);
+        let line_start = file.find("<http://example.com/s>").unwrap() as u64;
+        assert!((line_start..line_start + 53).contains(&errors[0].position().offset));
+
+        assert!(
+            ParallelTurtleParser::new(Cursor::new(&file), TurtleSyntax::NQuads, None)
+                .collect::<io::Result<Vec<_>>>()
+                .is_err()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_formatters() -> io::Result<()> {
+        let ex = |name: &str| NamedNode::new(format!("http://example.com/{}", name)).unwrap();
+        let quoted = Triple::new(ex("s"), ex("p"), ex("o"));
+
+        let mut formatter = TurtleFormatter::new(Vec::new());
+        formatter.format(quoted.as_ref())?;
+        formatter.format(TripleRef::new(&quoted, &ex("p"), &ex("o")))?;
+        formatter.format(TripleRef::new(&quoted, &ex("p"), &ex("o2")))?;
+        let output = formatter.finish()?;
+        assert_eq!(String::from_utf8(output.clone()).unwrap(), "<http://example.com/s> <http://example.com/p> <http://example.com/o> .\n<< <http://example.com/s> <http://example.com/p> <http://example.com/o> >> <http://example.com/p> <http://example.com/o> , <http://example.com/o2> .\n");
+        let parsed = TurtleParser::new(Cursor::new(output), TurtleSyntax::Turtle, None)
+            .collect::<io::Result<Vec<_>>>()?;
+        assert_eq!(parsed.len(), 3);
+        assert_eq!(parsed[1].subject, quoted.clone().into());
+
+        let mut formatter = TriGFormatter::new(Vec::new());
+        formatter.format(QuadRef::new(&ex("s"), &ex("p"), &ex("o"), &ex("g")))?;
+        formatter.format(QuadRef::new(&ex("s"), &ex("p"), &quoted, &ex("g")))?;
+        formatter.format(QuadRef::new(
+            &ex("s"),
+            &ex("p"),
+            &ex("o"),
+            GraphNameRef::DefaultGraph,
+        ))?;
+        let output = formatter.finish()?;
+        let parsed = TurtleParser::new(Cursor::new(output), TurtleSyntax::TriG, None)
+            .collect::<io::Result<Vec<_>>>()?;
+        assert_eq!(parsed.len(), 3);
+        assert_eq!(parsed[1].object, quoted.into());
+        assert_eq!(parsed[2].graph_name, GraphName::DefaultGraph);
+        Ok(())
+    }
+
+    #[test]
+    fn test_pretty_formatter() -> io::Result<()> {
+        let ex = |name: &str| NamedNode::new(format!("http://example.com/{}", name)).unwrap();
+        let list = [BlankNode::default(), BlankNode::default()];
+        let inlined = BlankNode::default();
+        let shared = BlankNode::default();
+        let cycle = [BlankNode::default(), BlankNode::default()];
+        let quads = vec![
+            Quad::new(ex("s"), rdf::TYPE, ex("C"), None),
+            Quad::new(ex("s"), ex("p"), list[0].clone(), None),
+            Quad::new(list[0].clone(), rdf::FIRST, Literal::from(1), None),
+            Quad::new(list[0].clone(), rdf::REST, list[1].clone(), None),
+            Quad::new(list[1].clone(), rdf::FIRST, inlined.clone(), None),
+            Quad::new(list[1].clone(), rdf::REST, rdf::NIL, None),
+            Quad::new(
+                inlined.clone(),
+                ex("p"),
+                Literal::new_typed_literal("1.5", xsd::DECIMAL),
+                None,
+            ),
+            Quad::new(
+                inlined,
+                ex("q"),
+                Literal::new_typed_literal("a", xsd::DATE),
+                None,
+            ),
+            Quad::new(ex("s"), ex("q"), shared.clone(), None),
+            Quad::new(ex("o"), ex("q"), shared.clone(), None),
+            Quad::new(shared, ex("p"), ex("dir/o"), None),
+            Quad::new(cycle[0].clone(), ex("p"), cycle[1].clone(), None),
+            Quad::new(cycle[1].clone(), ex("p"), cycle[0].clone(), None),
+            Quad::new(
+                ex("s"),
+                ex("p"),
+                Literal::new_simple_literal("a\n"),
+                ex("g"),
+            ),
+        ];
+
+        let mut formatter = PrettyFormatter::new(
+            Vec::new(),
+            TurtleSyntax::TriG,
+            vec![
+                ("ex".into(), "http://example.com/".into()),
+                ("xsd".into(), xsd::DATE.as_str()[..33].into()),
+                ("unused".into(), "http://example.org/".into()),
+            ],
+            Some(Iri::parse("http://example.com/dir/".to_owned()).unwrap()),
+        );
+        for quad in &quads {
+            formatter.format(quad.as_ref())?;
+        }
+        let output = String::from_utf8(formatter.finish()?).unwrap();
+        assert!(output.starts_with(
+            "@base <http://example.com/dir/> .\n\
+             @prefix ex: <http://example.com/> .\n\
+             @prefix xsd: <http://www.w3.org/2001/XMLSchema#> .\n\n\
+             ex:s a ex:C ;\n\tex:p ( 1 [ ex:p 1.5 ;\n\t\tex:q \"a\"^^xsd:date ] ) ;\n"
+        ));
+        assert!(output.contains("ex:g {\n\tex:s ex:p \"a\\n\" .\n}\n"));
+        assert!(!output.contains("unused"));
+
+        let parsed = TurtleParser::new(Cursor::new(output), TurtleSyntax::TriG, None)
+            .collect::<io::Result<Vec<_>>>()?;
+        assert_eq!(parsed.len(), quads.len());
+        for quad in &quads {
+            if !quad.subject.is_blank_node() && !quad.object.is_blank_node() {
+                assert!(parsed.contains(quad));
+            }
+        }
+
+        let mut formatter =
+            PrettyFormatter::new(Vec::new(), TurtleSyntax::Turtle, Vec::new(), None);
+        assert!(formatter.format(quads[13].as_ref()).is_err());
+        Ok(())
+    }
+}
@@ -1,9 +1,16 @@
 //! Utilities to write RDF graphs and datasets
 
-use crate::io::{DatasetFormat, GraphFormat};
+use crate::error::invalid_input_error;
+use crate::io::compression::CompressingWriter;
+use crate::io::jsonld::{parse_context_document, JsonLdFormatter};
+use crate::io::turtle::{
+    is_valid_prefix_name, PrettyFormatter, TriGFormatter, TurtleFormatter, TurtleSyntax,
+};
+use crate::io::{CompressionFormat, DatasetFormat, GraphFormat, PrefixMap};
 use crate::model::*;
-use rio_api::formatter::{QuadsFormatter, TriplesFormatter};
-use rio_turtle::{NQuadsFormatter, NTriplesFormatter, TriGFormatter, TurtleFormatter};
+use oxiri::{Iri, IriParseError};
+use rio_api::formatter::TriplesFormatter;
+use rio_api::model as rio;
 use rio_xml::RdfXmlFormatter;
 use std::io;
 use std::io::Write;
@@ -15,6 +22,11 @@ use std::io::Write;
 /// * [Turtle](https://www.w3.org/TR/turtle/) ([`GraphFormat::Turtle`](super::GraphFormat::Turtle))
 /// * [RDF/XML](https://www.w3.org/TR/rdf-syntax-grammar/) ([`GraphFormat::RdfXml`](super::GraphFormat::RdfXml))
 ///
+/// N-Triples and Turtle are serialized with their [RDF-star](https://w3c.github.io/rdf-star/cg-spec/editors_draft.html) extensions.
+/// RDF/XML does not support quoted triples.
+///
+/// Turtle could also be written in a more readable way using [`pretty`](GraphSerializer::pretty()).
+///
 /// ```
 /// use oxigraph::io::{GraphFormat, GraphSerializer};
 /// use oxigraph::model::*;
@@ -31,22 +43,120 @@ use std::io::Write;
 ///assert_eq!(buffer.as_slice(), "<http://example.com/s> <http://example.com/p> <http://example.com/o> .\n".as_bytes());
 /// # Result::<_,Box<dyn std::error::Error>>::Ok(())
 /// ```
-#[allow(missing_copy_implementations)]
 pub struct GraphSerializer {
     format: GraphFormat,
+    pretty: PrettyOptions,
+    compression: Option<CompressionFormat>,
 }
 
 impl GraphSerializer {
     /// Builds a serializer for the given format
     pub fn from_format(format: GraphFormat) -> Self {
-        Self { format }
+        Self {
+            format,
+            pretty: PrettyOptions::default(),
+            compression: None,
+        }
+    }
+
+    /// Writes Turtle in a way easier to read for humans.
+    ///
+    /// The triples are grouped by subject using `;` and `,`, the blank nodes used only once are inlined using the `[ ]` syntax
+    /// and the RDF lists are written as `( )` collections.
+    /// All the triples are kept in memory until [`finish`](TripleWriter::finish()) is called.
+    ///
+    /// This option is ignored by the other formats.
+    ///
+    /// ```
+    /// use oxigraph::io::{GraphFormat, GraphSerializer};
+    /// use oxigraph::model::*;
+    ///
+    /// let ex = |name| NamedNode::new(format!("http://example.com/{}", name));
+    /// let node = BlankNode::default();
+    ///
+    /// let mut buffer = Vec::new();
+    /// let mut writer = GraphSerializer::from_format(GraphFormat::Turtle)
+    ///     .with_prefix("ex", "http://example.com/")?
+    ///     .triple_writer(&mut buffer)?;
+    /// writer.write(&Triple::new(ex("s")?, ex("p")?, ex("o")?))?;
+    /// writer.write(&Triple::new(ex("s")?, ex("q")?, node.clone()))?;
+    /// writer.write(&Triple::new(node, ex("p")?, Literal::from(1)))?;
+    /// writer.finish()?;
+    ///
+    ///assert_eq!(buffer.as_slice(), "@prefix ex: <http://example.com/> .\n\nex:s ex:p ex:o ;\n\tex:q [ ex:p 1 ] .\n".as_bytes());
+    /// # Result::<_,Box<dyn std::error::Error>>::Ok(())
+    /// ```
+    pub fn pretty(mut self) -> Self {
+        self.pretty.enabled = true;
+        self
+    }
+
+    /// Adds a prefix used to abbreviate the IRIs in Turtle.
+    ///
+    /// It enables the [`pretty`](GraphSerializer::pretty()) mode. Only the prefixes used in the output are declared.
+    pub fn with_prefix(
+        mut self,
+        prefix_name: impl Into<String>,
+        prefix_iri: impl Into<String>,
+    ) -> Result<Self, io::Error> {
+        self.pretty
+            .add_prefix(prefix_name.into(), prefix_iri.into())?;
+        Ok(self)
+    }
+
+    /// Adds the prefixes and the base IRI returned by a parser.
+    ///
+    /// It enables the [`pretty`](GraphSerializer::pretty()) mode.
+    pub fn with_prefix_map(mut self, prefixes: &PrefixMap) -> Self {
+        self.pretty.add_prefix_map(prefixes);
+        self
+    }
+
+    /// Provides a base IRI used to write relative IRIs in Turtle.
+    ///
+    /// It enables the [`pretty`](GraphSerializer::pretty()) mode.
+    pub fn with_base_iri(mut self, base_iri: impl Into<String>) -> Result<Self, IriParseError> {
+        self.pretty.set_base_iri(base_iri.into())?;
+        Ok(self)
+    }
+
+    /// Compresses the output using the given format.
+    ///
+    /// It requires the `"compression"` optional feature, the writers creation fails otherwise.
+    ///
+    /// ```
+    /// # #[cfg(feature = "compression")] {
+    /// use oxigraph::io::{CompressionFormat, DecompressingReader, GraphFormat, GraphParser, GraphSerializer};
+    /// use oxigraph::model::*;
+    ///
+    /// let mut buffer = Vec::new();
+    /// let mut writer = GraphSerializer::from_format(GraphFormat::NTriples)
+    ///     .with_compression(CompressionFormat::Gzip)
+    ///     .triple_writer(&mut buffer)?;
+    /// writer.write(&Triple::new(NamedNode::new("http://example.com/s")?, NamedNode::new("http://example.com/p")?, Literal::from(1)))?;
+    /// writer.finish()?;
+    /// assert_eq!(CompressionFormat::from_magic_bytes(&buffer), Some(CompressionFormat::Gzip));
+    ///
+    /// let reader = DecompressingReader::new(buffer.as_slice())?;
+    /// let triples = GraphParser::from_format(GraphFormat::NTriples).read_triples(reader)?.collect::<Result<Vec<_>,_>>()?;
+    /// assert_eq!(triples.len(), 1);
+    /// # }
+    /// # Result::<_,Box<dyn std::error::Error>>::Ok(())
+    /// ```
+    pub fn with_compression(mut self, format: CompressionFormat) -> Self {
+        self.compression = Some(format);
+        self
     }
 
     /// Returns a `TripleWriter` allowing writing triples into the given [`Write`](std::io::Write) implementation
     pub fn triple_writer<W: Write>(&self, writer: W) -> Result<TripleWriter<W>, io::Error> {
+        let writer = CompressingWriter::new(writer, self.compression)?;
         Ok(TripleWriter {
             formatter: match self.format {
-                GraphFormat::NTriples => TripleWriterKind::NTriples(NTriplesFormatter::new(writer)),
+                GraphFormat::NTriples => TripleWriterKind::NTriples(writer),
+                GraphFormat::Turtle if self.pretty.enabled => TripleWriterKind::PrettyTurtle(
+                    self.pretty.formatter(writer, TurtleSyntax::Turtle),
+                ),
                 GraphFormat::Turtle => TripleWriterKind::Turtle(TurtleFormatter::new(writer)),
                 GraphFormat::RdfXml => TripleWriterKind::RdfXml(RdfXmlFormatter::new(writer)?),
             },
@@ -54,6 +164,12 @@ impl GraphSerializer {
     }
 }
 
+impl From<GraphFormat> for GraphSerializer {
+    fn from(format: GraphFormat) -> Self {
+        Self::from_format(format)
+    }
+}
+
 /// Allows writing triples.
 /// Could be built using a [`GraphSerializer`].
 ///
@@ -77,12 +193,13 @@ impl GraphSerializer {
 /// ```
 #[must_use]
 pub struct TripleWriter<W: Write> {
-    formatter: TripleWriterKind<W>,
+    formatter: TripleWriterKind<CompressingWriter<W>>,
 }
 
 enum TripleWriterKind<W: Write> {
-    NTriples(NTriplesFormatter<W>),
+    NTriples(W),
     Turtle(TurtleFormatter<W>),
+    PrettyTurtle(PrettyFormatter<W>),
     RdfXml(RdfXmlFormatter<W>),
 }
 
@@ -91,9 +208,25 @@ impl<W: Write> TripleWriter<W> {
     pub fn write<'a>(&mut self, triple: impl Into<TripleRef<'a>>) -> Result<(), io::Error> {
         let triple = triple.into();
         match &mut self.formatter {
-            TripleWriterKind::NTriples(formatter) => formatter.format(&triple.into())?,
-            TripleWriterKind::Turtle(formatter) => formatter.format(&triple.into())?,
-            TripleWriterKind::RdfXml(formatter) => formatter.format(&triple.into())?,
+            TripleWriterKind::NTriples(writer) => writeln!(writer, "{}", triple)?,
+            TripleWriterKind::Turtle(formatter) => formatter.format(triple)?,
+            TripleWriterKind::PrettyTurtle(formatter) => {
+                formatter.format(triple.in_graph(GraphNameRef::DefaultGraph))?
+            }
+            TripleWriterKind::RdfXml(formatter) => formatter.format(&rio::Triple {
+                subject: match triple.subject {
+                    SubjectRef::NamedNode(node) => rio::NamedNode::from(node).into(),
+                    SubjectRef::BlankNode(node) => rio::BlankNode::from(node).into(),
+                    SubjectRef::Triple(_) => return Err(quoted_triple_error()),
+                },
+                predicate: triple.predicate.into(),
+                object: match triple.object {
+                    TermRef::NamedNode(node) => rio::NamedNode::from(node).into(),
+                    TermRef::BlankNode(node) => rio::BlankNode::from(node).into(),
+                    TermRef::Literal(literal) => rio::Literal::from(literal).into(),
+                    TermRef::Triple(_) => return Err(quoted_triple_error()),
+                },
+            })?,
         }
         Ok(())
     }
@@ -101,9 +234,10 @@ impl<W: Write> TripleWriter<W> {
     /// Writes the last bytes of the file
     pub fn finish(self) -> Result<(), io::Error> {
         match self.formatter {
-            TripleWriterKind::NTriples(formatter) => formatter.finish(),
-            TripleWriterKind::Turtle(formatter) => formatter.finish()?,
-            TripleWriterKind::RdfXml(formatter) => formatter.finish()?,
+            TripleWriterKind::NTriples(writer) => writer.finish()?.flush()?,
+            TripleWriterKind::Turtle(formatter) => formatter.finish()?.finish()?.flush()?,
+            TripleWriterKind::PrettyTurtle(formatter) => formatter.finish()?.finish()?.flush()?,
+            TripleWriterKind::RdfXml(formatter) => formatter.finish()?.finish()?.flush()?,
         };
         Ok(())
     }
@@ -114,6 +248,11 @@ impl<W: Write> TripleWriter<W> {
 /// It currently supports the following formats:
 /// * [N-Quads](https://www.w3.org/TR/n-quads/) ([`DatasetFormat::NQuads`](super::DatasetFormat::NQuads))
 /// * [TriG](https://www.w3.org/TR/trig/) ([`DatasetFormat::TriG`](super::DatasetFormat::TriG))
+/// * [JSON-LD 1.1](https://www.w3.org/TR/json-ld11/) ([`DatasetFormat::JsonLd`](super::DatasetFormat::JsonLd))
+///
+/// N-Quads and TriG are serialized with their [RDF-star](https://w3c.github.io/rdf-star/cg-spec/editors_draft.html) extensions.
+///
+/// TriG could also be written in a more readable way using [`pretty`](DatasetSerializer::pretty()).
 ///
 /// ```
 /// use oxigraph::io::{DatasetFormat, DatasetSerializer};
@@ -132,23 +271,135 @@ impl<W: Write> TripleWriter<W> {
 ///assert_eq!(buffer.as_slice(), "<http://example.com/s> <http://example.com/p> <http://example.com/o> <http://example.com/g> .\n".as_bytes());
 /// # Result::<_,Box<dyn std::error::Error>>::Ok(())
 /// ```
-#[allow(missing_copy_implementations)]
 pub struct DatasetSerializer {
     format: DatasetFormat,
+    json_ld_context: Option<serde_json::Value>,
+    pretty: PrettyOptions,
+    compression: Option<CompressionFormat>,
 }
 
 impl DatasetSerializer {
     /// Builds a serializer for the given format
     pub fn from_format(format: DatasetFormat) -> Self {
-        Self { format }
+        Self {
+            format,
+            json_ld_context: None,
+            pretty: PrettyOptions::default(),
+            compression: None,
+        }
+    }
+
+    /// Writes TriG in a way easier to read for humans.
+    ///
+    /// The quads are grouped by graph and subject, the blank nodes used only once are inlined using the `[ ]` syntax
+    /// and the RDF lists are written as `( )` collections.
+    /// All the quads are kept in memory until [`finish`](QuadWriter::finish()) is called.
+    ///
+    /// This option is ignored by the other formats.
+    ///
+    /// ```
+    /// use oxigraph::io::{DatasetFormat, DatasetSerializer};
+    /// use oxigraph::model::*;
+    ///
+    /// let ex = |name| NamedNode::new(format!("http://example.com/{}", name));
+    ///
+    /// let mut buffer = Vec::new();
+    /// let mut writer = DatasetSerializer::from_format(DatasetFormat::TriG)
+    ///     .with_prefix("ex", "http://example.com/")?
+    ///     .quad_writer(&mut buffer)?;
+    /// writer.write(&Quad::new(ex("s")?, ex("p")?, ex("o")?, ex("g")?))?;
+    /// writer.write(&Quad::new(ex("s")?, ex("p")?, ex("o2")?, ex("g")?))?;
+    /// writer.finish()?;
+    ///
+    ///assert_eq!(buffer.as_slice(), "@prefix ex: <http://example.com/> .\n\nex:g {\n\tex:s ex:p ex:o , ex:o2 .\n}\n".as_bytes());
+    /// # Result::<_,Box<dyn std::error::Error>>::Ok(())
+    /// ```
+    pub fn pretty(mut self) -> Self {
+        self.pretty.enabled = true;
+        self
+    }
+
+    /// Adds a prefix used to abbreviate the IRIs in TriG.
+    ///
+    /// It enables the [`pretty`](DatasetSerializer::pretty()) mode. Only the prefixes used in the output are declared.
+    pub fn with_prefix(
+        mut self,
+        prefix_name: impl Into<String>,
+        prefix_iri: impl Into<String>,
+    ) -> Result<Self, io::Error> {
+        self.pretty
+            .add_prefix(prefix_name.into(), prefix_iri.into())?;
+        Ok(self)
+    }
+
+    /// Adds the prefixes and the base IRI returned by a parser.
+    ///
+    /// It enables the [`pretty`](DatasetSerializer::pretty()) mode.
+    pub fn with_prefix_map(mut self, prefixes: &PrefixMap) -> Self {
+        self.pretty.add_prefix_map(prefixes);
+        self
+    }
+
+    /// Provides a base IRI used to write relative IRIs in TriG.
+    ///
+    /// It enables the [`pretty`](DatasetSerializer::pretty()) mode.
+    pub fn with_base_iri(mut self, base_iri: impl Into<String>) -> Result<Self, IriParseError> {
+        self.pretty.set_base_iri(base_iri.into())?;
+        Ok(self)
+    }
+
+    /// Compacts the JSON-LD output using the given context
+    ///
+    /// The context is written in the output `@context` key and its terms are used for the properties, types and values.
+    /// `context` could be a JSON-LD document with a `@context` key or directly the context value.
+    /// Without context the [expanded document form](https://www.w3.org/TR/json-ld11/#expanded-document-form) is written.
+    ///
+    /// ```
+    /// use oxigraph::io::{DatasetFormat, DatasetSerializer};
+    /// use oxigraph::model::*;
+    ///
+    /// let mut buffer = Vec::new();
+    /// let mut writer = DatasetSerializer::from_format(DatasetFormat::JsonLd)
+    ///     .with_json_ld_context(r#"{"@vocab": "http://schema.org/"}"#)?
+    ///     .quad_writer(&mut buffer)?;
+    /// writer.write(&Quad {
+    ///    subject: NamedNode::new("http://example.com/s")?.into(),
+    ///    predicate: NamedNode::new("http://schema.org/name")?,
+    ///    object: Literal::new_simple_literal("foo").into(),
+    ///    graph_name: GraphName::DefaultGraph,
+    /// })?;
+    /// writer.finish()?;
+    ///
+    ///assert_eq!(buffer.as_slice(), "{\"@context\":{\"@vocab\":\"http://schema.org/\"},\"@graph\":[\n{\"@id\":\"http://example.com/s\",\"name\":\"foo\"}\n]}".as_bytes());
+    /// # Result::<_,Box<dyn std::error::Error>>::Ok(())
+    /// ```
+    pub fn with_json_ld_context(mut self, context: &str) -> Result<Self, io::Error> {
+        self.json_ld_context = Some(parse_context_document(context)?);
+        Ok(self)
+    }
+
+    /// Compresses the output using the given format.
+    ///
+    /// It requires the `"compression"` optional feature, the writers creation fails otherwise.
+    pub fn with_compression(mut self, format: CompressionFormat) -> Self {
+        self.compression = Some(format);
+        self
     }
 
     /// Returns a `QuadWriter` allowing writing triples into the given [`Write`](std::io::Write) implementation
     pub fn quad_writer<W: Write>(&self, writer: W) -> Result<QuadWriter<W>, io::Error> {
+        let writer = CompressingWriter::new(writer, self.compression)?;
         Ok(QuadWriter {
             formatter: match self.format {
-                DatasetFormat::NQuads => QuadWriterKind::NQuads(NQuadsFormatter::new(writer)),
+                DatasetFormat::NQuads => QuadWriterKind::NQuads(writer),
+                DatasetFormat::TriG if self.pretty.enabled => {
+                    QuadWriterKind::PrettyTriG(self.pretty.formatter(writer, TurtleSyntax::TriG))
+                }
                 DatasetFormat::TriG => QuadWriterKind::TriG(TriGFormatter::new(writer)),
+                DatasetFormat::JsonLd => QuadWriterKind::JsonLd(JsonLdFormatter::new(
+                    writer,
+                    self.json_ld_context.clone(),
+                )?),
             },
         })
     }
@@ -178,12 +429,14 @@ impl DatasetSerializer {
 /// ```
 #[must_use]
 pub struct QuadWriter<W: Write> {
-    formatter: QuadWriterKind<W>,
+    formatter: QuadWriterKind<CompressingWriter<W>>,
 }
 
 enum QuadWriterKind<W: Write> {
-    NQuads(NQuadsFormatter<W>),
+    NQuads(W),
     TriG(TriGFormatter<W>),
+    PrettyTriG(PrettyFormatter<W>),
+    JsonLd(JsonLdFormatter<W>),
 }
 
 impl<W: Write> QuadWriter<W> {
@@ -191,8 +444,10 @@ impl<W: Write> QuadWriter<W> {
     pub fn write<'a>(&mut self, quad: impl Into<QuadRef<'a>>) -> Result<(), io::Error> {
         let quad = quad.into();
         match &mut self.formatter {
-            QuadWriterKind::NQuads(formatter) => formatter.format(&quad.into())?,
-            QuadWriterKind::TriG(formatter) => formatter.format(&quad.into())?,
+            QuadWriterKind::NQuads(writer) => writeln!(writer, "{}", quad)?,
+            QuadWriterKind::TriG(formatter) => formatter.format(quad)?,
+            QuadWriterKind::PrettyTriG(formatter) => formatter.format(quad)?,
+            QuadWriterKind::JsonLd(formatter) => formatter.format(quad)?,
         }
         Ok(())
     }
@@ -200,9 +455,65 @@ impl<W: Write> QuadWriter<W> {
     /// Writes the last bytes of the file
     pub fn finish(self) -> Result<(), io::Error> {
         match self.formatter {
-            QuadWriterKind::NQuads(formatter) => formatter.finish(),
-            QuadWriterKind::TriG(formatter) => formatter.finish()?,
+            QuadWriterKind::NQuads(writer) => writer.finish()?.flush()?,
+            QuadWriterKind::TriG(formatter) => formatter.finish()?.finish()?.flush()?,
+            QuadWriterKind::PrettyTriG(formatter) => formatter.finish()?.finish()?.flush()?,
+            QuadWriterKind::JsonLd(formatter) => formatter.finish()?.finish()?.flush()?,
         };
         Ok(())
     }
 }
+
+impl From<DatasetFormat> for DatasetSerializer {
+    fn from(format: DatasetFormat) -> Self {
+        Self::from_format(format)
+    }
+}
+
+#[derive(Default)]
+struct PrettyOptions {
+    enabled: bool,
+    prefixes: Vec<(String, String)>,
+    base_iri: Option<Iri<String>>,
+}
+
+impl PrettyOptions {
+    fn add_prefix(&mut self, name: String, iri: String) -> Result<(), io::Error> {
+        if !is_valid_prefix_name(&name) {
+            return Err(invalid_input_error(format!(
+                "{} is not a valid prefix name",
+                name
+            )));
+        }
+        let iri = Iri::parse(iri).map_err(invalid_input_error)?.into_inner();
+        self.prefixes.retain(|(n, _)| *n != name);
+        self.prefixes.push((name, iri));
+        self.enabled = true;
+        Ok(())
+    }
+
+    fn add_prefix_map(&mut self, prefixes: &PrefixMap) {
+        for (name, iri) in prefixes.iter() {
+            self.prefixes.retain(|(n, _)| n != name);
+            self.prefixes.push((name.to_owned(), iri.to_owned()));
+        }
+        if let Some(base_iri) = &prefixes.base_iri {
+            self.base_iri = Some(base_iri.clone());
+        }
+        self.enabled = true;
+    }
+
+    fn set_base_iri(&mut self, base_iri: String) -> Result<(), IriParseError> {
+        self.base_iri = Some(Iri::parse(base_iri)?);
+        self.enabled = true;
+        Ok(())
+    }
+
+    fn formatter<W: Write>(&self, writer: W, syntax: TurtleSyntax) -> PrettyFormatter<W> {
+        PrettyFormatter::new(writer, syntax, self.prefixes.clone(), self.base_iri.clone())
+    }
+}
+
+fn quoted_triple_error() -> io::Error {
+    invalid_input_error("RDF/XML does not support RDF-star quoted triples")
+}
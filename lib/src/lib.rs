@@ -17,6 +17,11 @@
 //!
 //! The disabled by default `"sophia"` feature provides [`sophia_api`](https://docs.rs/sophia_api/) traits implemention on Oxigraph terms and stores.
 //!
+//! The disabled by default `"parallel"` feature allows to [evaluate SPARQL queries](sparql::QueryOptions::with_parallel_evaluation()) and to [parse N-Triples and N-Quads](io::GraphParser::read_triples_in_parallel()) using the [rayon](https://docs.rs/rayon) thread pool.
+//!
+//! The disabled by default `"async_service"` feature provides the [`AsyncServiceHandler`](sparql::AsyncServiceHandler) trait to send the SPARQL SERVICE calls concurrently.
+//! The calls are done one after the other without it.
+//!
 //! Oxigraph also provides [a standalone HTTP server](https://crates.io/crates/oxigraph_server) based on this library.
 //!
 //! Usage example with the [`MemoryStore`](store::memory::MemoryStore):
@@ -124,9 +129,11 @@
 mod error;
 pub mod io;
 pub mod model;
+pub mod shacl;
 pub mod sparql;
 pub mod store;
 
+pub use crate::store::hdt::HdtStore;
 pub use crate::store::memory::MemoryStore;
 #[cfg(feature = "rocksdb")]
 pub use crate::store::rocksdb::RocksDbStore;
@@ -0,0 +1,1360 @@
+//! [GeoSPARQL](https://www.ogc.org/standards/geosparql) geometries.
+//!
+//! Geometries are parsed from [WKT](https://www.ogc.org/standards/sfa) and [GeoJSON](https://tools.ietf.org/html/rfc7946) literals.
+//! Only the default GeoSPARQL coordinate reference system, [CRS84](http://www.opengis.net/def/crs/OGC/1.3/CRS84), is supported:
+//! the first coordinate is the longitude and the second one the latitude, both in degrees.
+//!
+//! The spatial relations are computed in the plane of these coordinates.
+//!
+//! Usage example:
+//! ```
+//! use oxigraph::model::geo::Geometry;
+//!
+//! let paris = Geometry::from_wkt("POINT(2.35 48.85)")?;
+//! let france = Geometry::from_wkt("POLYGON((-5 42, 8 42, 8 51, -5 51, -5 42))")?;
+//! assert!(paris.is_within(&france));
+//! assert_eq!(paris.to_string(), "POINT(2.35 48.85)");
+//! # Result::<_,Box<dyn std::error::Error>>::Ok(())
+//! ```
+
+use crate::model::vocab::geo;
+use crate::model::{Literal, LiteralRef};
+use serde_json::Value;
+use std::error::Error;
+use std::f64::consts::PI;
+use std::fmt;
+use std::str::FromStr;
+
+/// The mean radius of the Earth in metres
+pub(crate) const EARTH_RADIUS: f64 = 6_371_008.8;
+/// The number of segments used to approximate circles
+const CIRCLE_SEGMENTS: u32 = 32;
+const CRS84: &str = "http://www.opengis.net/def/crs/OGC/1.3/CRS84";
+/// The namespace of the GeoSPARQL functions
+pub(crate) const GEOF: &str = "http://www.opengis.net/def/function/geosparql/";
+const UOM: &str = "http://www.opengis.net/def/uom/OGC/1.0/";
+
+/// The units of measure supported by the GeoSPARQL functions
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub(crate) enum DistanceUnit {
+    Metre,
+    Degree,
+    Radian,
+}
+
+impl DistanceUnit {
+    pub fn from_iri(iri: &str) -> Option<Self> {
+        match iri.strip_prefix(UOM)? {
+            "metre" | "meter" => Some(Self::Metre),
+            "degree" => Some(Self::Degree),
+            "radian" => Some(Self::Radian),
+            _ => None,
+        }
+    }
+}
+
+/// A point with its longitude as `x` and its latitude as `y`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Point {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl Point {
+    #[inline]
+    pub const fn new(x: f64, y: f64) -> Self {
+        Self { x, y }
+    }
+}
+
+/// A geometry following the [Simple Features](https://www.ogc.org/standards/sfa) model.
+///
+/// Polygons are a list of rings, the first one being the exterior ring and the others the holes.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Geometry {
+    Point(Point),
+    LineString(Vec<Point>),
+    Polygon(Vec<Vec<Point>>),
+    MultiPoint(Vec<Point>),
+    MultiLineString(Vec<Vec<Point>>),
+    MultiPolygon(Vec<Vec<Vec<Point>>>),
+    GeometryCollection(Vec<Geometry>),
+}
+
+/// A bounding box
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Envelope {
+    pub min_x: f64,
+    pub min_y: f64,
+    pub max_x: f64,
+    pub max_y: f64,
+}
+
+impl Envelope {
+    /// Checks if the two boxes share at least a point
+    pub fn intersects(&self, other: &Self) -> bool {
+        self.min_x <= other.max_x
+            && other.min_x <= self.max_x
+            && self.min_y <= other.max_y
+            && other.min_y <= self.max_y
+    }
+
+    /// Returns the smallest box containing the two boxes
+    pub fn union(&self, other: &Self) -> Self {
+        Self {
+            min_x: self.min_x.min(other.min_x),
+            min_y: self.min_y.min(other.min_y),
+            max_x: self.max_x.max(other.max_x),
+            max_y: self.max_y.max(other.max_y),
+        }
+    }
+
+    /// Returns the box extended by the given number of degrees on each side
+    pub fn expand(&self, dx: f64, dy: f64) -> Self {
+        Self {
+            min_x: self.min_x - dx,
+            min_y: self.min_y - dy,
+            max_x: self.max_x + dx,
+            max_y: self.max_y + dy,
+        }
+    }
+
+    pub fn area(&self) -> f64 {
+        (self.max_x - self.min_x) * (self.max_y - self.min_y)
+    }
+
+    /// Returns the box as a polygon
+    pub fn to_geometry(&self) -> Geometry {
+        Geometry::Polygon(vec![vec![
+            Point::new(self.min_x, self.min_y),
+            Point::new(self.max_x, self.min_y),
+            Point::new(self.max_x, self.max_y),
+            Point::new(self.min_x, self.max_y),
+            Point::new(self.min_x, self.min_y),
+        ]])
+    }
+}
+
+impl Geometry {
+    /// Parses a [WKT](https://www.ogc.org/standards/sfa) serialization optionally prefixed by a coordinate reference system IRI like in GeoSPARQL `wktLiteral`s.
+    pub fn from_wkt(wkt: &str) -> Result<Self, GeometryParseError> {
+        let mut wkt = wkt.trim_start();
+        if wkt.starts_with('<') {
+            let end = wkt
+                .find('>')
+                .ok_or_else(|| GeometryParseError::msg("Unterminated CRS IRI"))?;
+            if &wkt[1..end] != CRS84 {
+                return Err(GeometryParseError::msg(format!(
+                    "Unsupported coordinate reference system <{}>",
+                    &wkt[1..end]
+                )));
+            }
+            wkt = &wkt[end + 1..];
+        }
+        let mut parser = WktParser { input: wkt };
+        let geometry = parser.parse_geometry()?;
+        parser.skip_whitespaces();
+        if parser.input.is_empty() {
+            Ok(geometry)
+        } else {
+            Err(GeometryParseError::msg(format!(
+                "Unexpected content after the geometry: {}",
+                parser.input
+            )))
+        }
+    }
+
+    /// Parses a [GeoJSON](https://tools.ietf.org/html/rfc7946) geometry or feature.
+    pub fn from_geojson(geojson: &str) -> Result<Self, GeometryParseError> {
+        let value = serde_json::from_str(geojson)
+            .map_err(|e| GeometryParseError::msg(format!("Invalid GeoJSON: {}", e)))?;
+        geometry_from_json(&value)
+    }
+
+    /// Parses a `geo:wktLiteral` or a `geo:geoJSONLiteral`.
+    pub fn from_literal(literal: LiteralRef<'_>) -> Result<Self, GeometryParseError> {
+        if literal.datatype() == geo::WKT_LITERAL {
+            Self::from_wkt(literal.value())
+        } else if literal.datatype() == geo::GEO_JSON_LITERAL {
+            Self::from_geojson(literal.value())
+        } else {
+            Err(GeometryParseError::msg(format!(
+                "{} is not a geometry literal datatype",
+                literal.datatype()
+            )))
+        }
+    }
+
+    /// Serializes the geometry using [WKT](https://www.ogc.org/standards/sfa).
+    pub fn to_wkt(&self) -> String {
+        self.to_string()
+    }
+
+    /// Serializes the geometry using [GeoJSON](https://tools.ietf.org/html/rfc7946).
+    pub fn to_geojson(&self) -> String {
+        let mut buffer = String::new();
+        write_geojson(self, &mut buffer);
+        buffer
+    }
+
+    /// Returns the bounding box of the geometry or `None` if the geometry is empty.
+    pub fn envelope(&self) -> Option<Envelope> {
+        let mut points = self.points();
+        let first = points.next()?;
+        Some(points.fold(
+            Envelope {
+                min_x: first.x,
+                min_y: first.y,
+                max_x: first.x,
+                max_y: first.y,
+            },
+            |e, p| Envelope {
+                min_x: e.min_x.min(p.x),
+                min_y: e.min_y.min(p.y),
+                max_x: e.max_x.max(p.x),
+                max_y: e.max_y.max(p.y),
+            },
+        ))
+    }
+
+    /// Checks if the two geometries share at least a point ([`sfIntersects`](http://www.opengis.net/def/function/geosparql/sfIntersects)).
+    pub fn intersects(&self, other: &Self) -> bool {
+        let (self_parts, other_parts) = (Parts::new(self), Parts::new(other));
+        if !self_parts.envelope_intersects(&other_parts) {
+            return false;
+        }
+        self_parts.segments.iter().any(|a| {
+            other_parts
+                .segments
+                .iter()
+                .any(|b| segments_intersect(*a, *b))
+        }) || self_parts
+            .first_points()
+            .any(|p| other_parts.contains_point(p))
+            || other_parts
+                .first_points()
+                .any(|p| self_parts.contains_point(p))
+    }
+
+    /// Checks if all the points of the geometry are in the other geometry ([`sfWithin`](http://www.opengis.net/def/function/geosparql/sfWithin)).
+    pub fn is_within(&self, other: &Self) -> bool {
+        let (self_parts, other_parts) = (Parts::new(self), Parts::new(other));
+        if self_parts.is_empty() || !self_parts.envelope_intersects(&other_parts) {
+            return false;
+        }
+        if !self_parts.polygons.is_empty() && other_parts.polygons.is_empty() {
+            return false; // A surface is never contained in lines or points
+        }
+        if !self_parts
+            .points
+            .iter()
+            .all(|p| other_parts.contains_point(*p))
+        {
+            return false;
+        }
+        // We split the segments on the boundaries of the other geometry and test each piece
+        for (start, end) in &self_parts.segments {
+            let mut cuts = vec![0., 1.];
+            for other_segment in &other_parts.segments {
+                cuts.extend(segment_cuts((*start, *end), *other_segment));
+            }
+            cuts.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+            for i in 0..cuts.len() - 1 {
+                let t = (cuts[i] + cuts[i + 1]) / 2.;
+                if !other_parts.contains_point(Point::new(
+                    start.x + t * (end.x - start.x),
+                    start.y + t * (end.y - start.y),
+                )) {
+                    return false;
+                }
+            }
+        }
+        // The holes of the other geometry should not be inside of the geometry surfaces
+        !other_parts.polygons.iter().any(|polygon| {
+            polygon.iter().skip(1).any(|hole| {
+                hole.iter().any(|p| {
+                    self_parts
+                        .polygons
+                        .iter()
+                        .any(|polygon| polygon_strictly_contains_point(polygon, *p))
+                })
+            })
+        })
+    }
+
+    /// Checks if all the points of the other geometry are in the geometry ([`sfContains`](http://www.opengis.net/def/function/geosparql/sfContains)).
+    pub fn contains(&self, other: &Self) -> bool {
+        other.is_within(self)
+    }
+
+    /// Returns the distance between the closest points of the two geometries in degrees
+    /// or `None` if one of the geometries is empty.
+    pub fn distance(&self, other: &Self) -> Option<f64> {
+        if self.intersects(other) {
+            return Some(0.);
+        }
+        let (a, b) = self.closest_points(other)?;
+        Some(((a.x - b.x).powi(2) + (a.y - b.y).powi(2)).sqrt())
+    }
+
+    /// Returns the great circle distance between the closest points of the two geometries in metres
+    /// or `None` if one of the geometries is empty.
+    ///
+    /// The closest points are computed in the plane of the coordinates.
+    pub fn geodesic_distance(&self, other: &Self) -> Option<f64> {
+        if self.intersects(other) {
+            return Some(0.);
+        }
+        let (a, b) = self.closest_points(other)?;
+        Some(haversine_distance(a, b))
+    }
+
+    /// Returns a geometry containing all the points at most `radius` metres away from the geometry.
+    ///
+    /// The circles are approximated by regular polygons.
+    /// Each point, segment and polygon of the geometry is buffered on its own:
+    /// if there are more than one of them the result is the collection of their buffers, which may overlap.
+    ///
+    /// Returns `None` if the geometry is empty or the radius is not a positive number.
+    pub fn buffer(&self, radius: f64) -> Option<Self> {
+        if radius.is_nan() || radius < 0. {
+            return None;
+        }
+        let mut buffers = Vec::new();
+        add_buffers(self, radius, &mut buffers);
+        if buffers.len() > 1 {
+            Some(Geometry::GeometryCollection(buffers))
+        } else {
+            buffers.pop()
+        }
+    }
+
+    /// Returns the smallest convex geometry containing the geometry
+    /// or `None` if the geometry is empty.
+    pub fn convex_hull(&self) -> Option<Self> {
+        convex_hull(self.points().collect())
+    }
+
+    /// Iterates on all the points of the geometry
+    fn points(&self) -> Box<dyn Iterator<Item = Point> + '_> {
+        match self {
+            Geometry::Point(p) => Box::new(Some(*p).into_iter()),
+            Geometry::LineString(points) | Geometry::MultiPoint(points) => {
+                Box::new(points.iter().copied())
+            }
+            Geometry::Polygon(rings) | Geometry::MultiLineString(rings) => {
+                Box::new(rings.iter().flatten().copied())
+            }
+            Geometry::MultiPolygon(polygons) => {
+                Box::new(polygons.iter().flatten().flatten().copied())
+            }
+            Geometry::GeometryCollection(geometries) => {
+                Box::new(geometries.iter().flat_map(|g| g.points()))
+            }
+        }
+    }
+
+    /// Returns the closest points of the two geometries, assuming they do not intersect.
+    ///
+    /// The closest points of two disjoint geometries are either two vertices
+    /// or a vertex and its projection on a segment of the other geometry.
+    fn closest_points(&self, other: &Self) -> Option<(Point, Point)> {
+        let (self_parts, other_parts) = (Parts::new(self), Parts::new(other));
+        let mut best: Option<(f64, Point, Point)> = None;
+        let mut update = |a: Point, b: Point| {
+            let d = (a.x - b.x).powi(2) + (a.y - b.y).powi(2);
+            if best.map_or(true, |(best, _, _)| d < best) {
+                best = Some((d, a, b));
+            }
+        };
+        for a in self.points() {
+            for b in other.points() {
+                update(a, b);
+            }
+            for b in &other_parts.segments {
+                update(a, closest_point_on_segment(a, *b));
+            }
+        }
+        for b in other.points() {
+            for a in &self_parts.segments {
+                update(closest_point_on_segment(b, *a), b);
+            }
+        }
+        best.map(|(_, a, b)| (a, b))
+    }
+}
+
+impl FromStr for Geometry {
+    type Err = GeometryParseError;
+
+    /// Parses a WKT serialization
+    fn from_str(wkt: &str) -> Result<Self, GeometryParseError> {
+        Self::from_wkt(wkt)
+    }
+}
+
+impl fmt::Display for Geometry {
+    /// Writes the WKT serialization
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Geometry::Point(p) => write!(f, "POINT({} {})", p.x, p.y),
+            Geometry::LineString(points) => {
+                write!(f, "LINESTRING")?;
+                write_wkt_points(points, f)
+            }
+            Geometry::Polygon(rings) => {
+                write!(f, "POLYGON")?;
+                write_wkt_rings(rings, f)
+            }
+            Geometry::MultiPoint(points) => {
+                write!(f, "MULTIPOINT")?;
+                write_wkt_points(points, f)
+            }
+            Geometry::MultiLineString(lines) => {
+                write!(f, "MULTILINESTRING")?;
+                write_wkt_rings(lines, f)
+            }
+            Geometry::MultiPolygon(polygons) => {
+                write!(f, "MULTIPOLYGON")?;
+                if polygons.is_empty() {
+                    return write!(f, " EMPTY");
+                }
+                write!(f, "(")?;
+                for (i, polygon) in polygons.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write_wkt_rings(polygon, f)?;
+                }
+                write!(f, ")")
+            }
+            Geometry::GeometryCollection(geometries) => {
+                write!(f, "GEOMETRYCOLLECTION")?;
+                if geometries.is_empty() {
+                    return write!(f, " EMPTY");
+                }
+                write!(f, "(")?;
+                for (i, geometry) in geometries.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    geometry.fmt(f)?;
+                }
+                write!(f, ")")
+            }
+        }
+    }
+}
+
+impl From<Geometry> for Literal {
+    /// Builds a `geo:wktLiteral`
+    fn from(geometry: Geometry) -> Self {
+        Literal::new_typed_literal(geometry.to_wkt(), geo::WKT_LITERAL)
+    }
+}
+
+impl From<Envelope> for Geometry {
+    fn from(envelope: Envelope) -> Self {
+        envelope.to_geometry()
+    }
+}
+
+fn write_wkt_points(points: &[Point], f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    if points.is_empty() {
+        return write!(f, " EMPTY");
+    }
+    write!(f, "(")?;
+    for (i, p) in points.iter().enumerate() {
+        if i > 0 {
+            write!(f, ", ")?;
+        }
+        write!(f, "{} {}", p.x, p.y)?;
+    }
+    write!(f, ")")
+}
+
+fn write_wkt_rings(rings: &[Vec<Point>], f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    if rings.is_empty() {
+        return write!(f, " EMPTY");
+    }
+    write!(f, "(")?;
+    for (i, ring) in rings.iter().enumerate() {
+        if i > 0 {
+            write!(f, ", ")?;
+        }
+        write_wkt_points(ring, f)?;
+    }
+    write!(f, ")")
+}
+
+fn write_geojson(geometry: &Geometry, buffer: &mut String) {
+    fn points(points: &[Point], buffer: &mut String) {
+        buffer.push('[');
+        for (i, p) in points.iter().enumerate() {
+            if i > 0 {
+                buffer.push(',');
+            }
+            buffer.push_str(&format!("[{},{}]", p.x, p.y));
+        }
+        buffer.push(']');
+    }
+    fn rings(rings: &[Vec<Point>], buffer: &mut String) {
+        buffer.push('[');
+        for (i, ring) in rings.iter().enumerate() {
+            if i > 0 {
+                buffer.push(',');
+            }
+            points(ring, buffer);
+        }
+        buffer.push(']');
+    }
+
+    match geometry {
+        Geometry::Point(p) => {
+            buffer.push_str(&format!(
+                "{{\"type\":\"Point\",\"coordinates\":[{},{}]}}",
+                p.x, p.y
+            ));
+        }
+        Geometry::LineString(l) => {
+            buffer.push_str("{\"type\":\"LineString\",\"coordinates\":");
+            points(l, buffer);
+            buffer.push('}');
+        }
+        Geometry::Polygon(p) => {
+            buffer.push_str("{\"type\":\"Polygon\",\"coordinates\":");
+            rings(p, buffer);
+            buffer.push('}');
+        }
+        Geometry::MultiPoint(l) => {
+            buffer.push_str("{\"type\":\"MultiPoint\",\"coordinates\":");
+            points(l, buffer);
+            buffer.push('}');
+        }
+        Geometry::MultiLineString(l) => {
+            buffer.push_str("{\"type\":\"MultiLineString\",\"coordinates\":");
+            rings(l, buffer);
+            buffer.push('}');
+        }
+        Geometry::MultiPolygon(polygons) => {
+            buffer.push_str("{\"type\":\"MultiPolygon\",\"coordinates\":[");
+            for (i, polygon) in polygons.iter().enumerate() {
+                if i > 0 {
+                    buffer.push(',');
+                }
+                rings(polygon, buffer);
+            }
+            buffer.push_str("]}");
+        }
+        Geometry::GeometryCollection(geometries) => {
+            buffer.push_str("{\"type\":\"GeometryCollection\",\"geometries\":[");
+            for (i, geometry) in geometries.iter().enumerate() {
+                if i > 0 {
+                    buffer.push(',');
+                }
+                write_geojson(geometry, buffer);
+            }
+            buffer.push_str("]}");
+        }
+    }
+}
+
+/// The geometry decomposed into its points, segments and polygons
+struct Parts<'a> {
+    /// The isolated points and the first point of each line and polygon
+    points: Vec<Point>,
+    segments: Vec<(Point, Point)>,
+    polygons: Vec<&'a [Vec<Point>]>,
+    envelope: Option<Envelope>,
+}
+
+impl<'a> Parts<'a> {
+    fn new(geometry: &'a Geometry) -> Self {
+        let mut parts = Self {
+            points: Vec::new(),
+            segments: Vec::new(),
+            polygons: Vec::new(),
+            envelope: geometry.envelope(),
+        };
+        parts.add(geometry);
+        parts
+    }
+
+    fn add(&mut self, geometry: &'a Geometry) {
+        match geometry {
+            Geometry::Point(p) => self.points.push(*p),
+            Geometry::MultiPoint(points) => self.points.extend_from_slice(points),
+            Geometry::LineString(line) => self.add_line(line),
+            Geometry::MultiLineString(lines) => {
+                for line in lines {
+                    self.add_line(line)
+                }
+            }
+            Geometry::Polygon(polygon) => self.add_polygon(polygon),
+            Geometry::MultiPolygon(polygons) => {
+                for polygon in polygons {
+                    self.add_polygon(polygon)
+                }
+            }
+            Geometry::GeometryCollection(geometries) => {
+                for geometry in geometries {
+                    self.add(geometry)
+                }
+            }
+        }
+    }
+
+    fn add_line(&mut self, line: &[Point]) {
+        if let Some(first) = line.first() {
+            self.points.push(*first);
+        }
+        self.segments
+            .extend(line.windows(2).map(|points| (points[0], points[1])));
+    }
+
+    fn add_polygon(&mut self, polygon: &'a [Vec<Point>]) {
+        for ring in polygon {
+            self.add_line(ring);
+        }
+        if !polygon.is_empty() {
+            self.polygons.push(polygon);
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.envelope.is_none()
+    }
+
+    fn envelope_intersects(&self, other: &Self) -> bool {
+        match (self.envelope, other.envelope) {
+            (Some(a), Some(b)) => a.intersects(&b),
+            _ => false,
+        }
+    }
+
+    /// The first point of each component, enough to test if a component is inside of an other geometry
+    fn first_points(&self) -> impl Iterator<Item = Point> + '_ {
+        self.points.iter().copied()
+    }
+
+    /// Checks if the point is in the geometry, boundary included
+    fn contains_point(&self, p: Point) -> bool {
+        self.points.contains(&p)
+            || self.segments.iter().any(|s| point_on_segment(p, *s))
+            || self
+                .polygons
+                .iter()
+                .any(|polygon| polygon_contains_point(polygon, p))
+    }
+}
+
+fn cross(o: Point, a: Point, b: Point) -> f64 {
+    (a.x - o.x) * (b.y - o.y) - (a.y - o.y) * (b.x - o.x)
+}
+
+fn point_on_segment(p: Point, (a, b): (Point, Point)) -> bool {
+    cross(a, b, p) == 0.
+        && a.x.min(b.x) <= p.x
+        && p.x <= a.x.max(b.x)
+        && a.y.min(b.y) <= p.y
+        && p.y <= a.y.max(b.y)
+}
+
+fn segments_intersect(s1: (Point, Point), s2: (Point, Point)) -> bool {
+    let d1 = cross(s2.0, s2.1, s1.0);
+    let d2 = cross(s2.0, s2.1, s1.1);
+    let d3 = cross(s1.0, s1.1, s2.0);
+    let d4 = cross(s1.0, s1.1, s2.1);
+    if ((d1 > 0. && d2 < 0.) || (d1 < 0. && d2 > 0.))
+        && ((d3 > 0. && d4 < 0.) || (d3 < 0. && d4 > 0.))
+    {
+        return true;
+    }
+    point_on_segment(s1.0, s2)
+        || point_on_segment(s1.1, s2)
+        || point_on_segment(s2.0, s1)
+        || point_on_segment(s2.1, s1)
+}
+
+/// Returns the positions on the first segment, between 0 and 1, where the second segment intersects it
+fn segment_cuts((a, b): (Point, Point), (c, d): (Point, Point)) -> Vec<f64> {
+    let r = (b.x - a.x, b.y - a.y);
+    let s = (d.x - c.x, d.y - c.y);
+    let denominator = r.0 * s.1 - r.1 * s.0;
+    let length = r.0 * r.0 + r.1 * r.1;
+    if length == 0. {
+        return Vec::new();
+    }
+    if denominator == 0. {
+        // Parallel segments: we cut at the other segment ends if they are on the segment
+        return [c, d]
+            .iter()
+            .filter(|p| point_on_segment(**p, (a, b)))
+            .map(|p| ((p.x - a.x) * r.0 + (p.y - a.y) * r.1) / length)
+            .collect();
+    }
+    let t = ((c.x - a.x) * s.1 - (c.y - a.y) * s.0) / denominator;
+    let u = ((c.x - a.x) * r.1 - (c.y - a.y) * r.0) / denominator;
+    if (0. ..=1.).contains(&t) && (0. ..=1.).contains(&u) {
+        vec![t]
+    } else {
+        Vec::new()
+    }
+}
+
+fn closest_point_on_segment(p: Point, (a, b): (Point, Point)) -> Point {
+    let length = (b.x - a.x).powi(2) + (b.y - a.y).powi(2);
+    if length == 0. {
+        return a;
+    }
+    let t = (((p.x - a.x) * (b.x - a.x) + (p.y - a.y) * (b.y - a.y)) / length).clamp(0., 1.);
+    Point::new(a.x + t * (b.x - a.x), a.y + t * (b.y - a.y))
+}
+
+/// Ray casting test, the result is undefined for the points of the ring boundary
+fn ring_contains_point(ring: &[Point], p: Point) -> bool {
+    let mut inside = false;
+    for segment in ring.windows(2) {
+        let (a, b) = (segment[0], segment[1]);
+        if (a.y > p.y) != (b.y > p.y) && p.x < (b.x - a.x) * (p.y - a.y) / (b.y - a.y) + a.x {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+fn ring_boundary_contains_point(ring: &[Point], p: Point) -> bool {
+    ring.windows(2)
+        .any(|segment| point_on_segment(p, (segment[0], segment[1])))
+}
+
+/// Checks if the point is in the polygon, boundary included
+fn polygon_contains_point(polygon: &[Vec<Point>], p: Point) -> bool {
+    if polygon
+        .iter()
+        .any(|ring| ring_boundary_contains_point(ring, p))
+    {
+        return true;
+    }
+    polygon
+        .first()
+        .map_or(false, |exterior| ring_contains_point(exterior, p))
+        && !polygon
+            .iter()
+            .skip(1)
+            .any(|hole| ring_contains_point(hole, p))
+}
+
+/// Checks if the point is in the polygon interior
+fn polygon_strictly_contains_point(polygon: &[Vec<Point>], p: Point) -> bool {
+    !polygon
+        .iter()
+        .any(|ring| ring_boundary_contains_point(ring, p))
+        && polygon_contains_point(polygon, p)
+}
+
+/// Adds the buffers of the points, segments and polygons of the geometry
+fn add_buffers(geometry: &Geometry, radius: f64, buffers: &mut Vec<Geometry>) {
+    match geometry {
+        Geometry::Point(p) => buffers.extend(convex_hull(circle(*p, radius).collect())),
+        Geometry::MultiPoint(points) => {
+            for p in points {
+                buffers.extend(convex_hull(circle(*p, radius).collect()))
+            }
+        }
+        Geometry::LineString(line) => add_line_buffers(line, radius, buffers),
+        Geometry::MultiLineString(lines) => {
+            for line in lines {
+                add_line_buffers(line, radius, buffers)
+            }
+        }
+        Geometry::Polygon(polygon) => add_polygon_buffers(polygon, radius, buffers),
+        Geometry::MultiPolygon(polygons) => {
+            for polygon in polygons {
+                add_polygon_buffers(polygon, radius, buffers)
+            }
+        }
+        Geometry::GeometryCollection(geometries) => {
+            for geometry in geometries {
+                add_buffers(geometry, radius, buffers)
+            }
+        }
+    }
+}
+
+/// The buffer of a segment is the convex hull of the buffers of its ends
+fn add_line_buffers(line: &[Point], radius: f64, buffers: &mut Vec<Geometry>) {
+    if let [p] = line {
+        buffers.extend(convex_hull(circle(*p, radius).collect()));
+    }
+    for segment in line.windows(2) {
+        buffers.extend(convex_hull(
+            circle(segment[0], radius)
+                .chain(circle(segment[1], radius))
+                .collect(),
+        ));
+    }
+}
+
+/// The buffer of a polygon is the polygon itself and the buffers of its rings
+fn add_polygon_buffers(polygon: &[Vec<Point>], radius: f64, buffers: &mut Vec<Geometry>) {
+    if polygon.is_empty() {
+        return;
+    }
+    buffers.push(Geometry::Polygon(polygon.to_vec()));
+    for ring in polygon {
+        add_line_buffers(ring, radius, buffers);
+    }
+}
+
+/// The vertices of the regular polygon approximating the circle of the given radius in metres
+fn circle(center: Point, radius: f64) -> impl Iterator<Item = Point> {
+    let (dx, dy) = metres_to_degrees(radius, center.y);
+    (0..CIRCLE_SEGMENTS).map(move |i| {
+        let angle = 2. * PI * f64::from(i) / f64::from(CIRCLE_SEGMENTS);
+        Point::new(center.x + dx * angle.cos(), center.y + dy * angle.sin())
+    })
+}
+
+/// Andrew's monotone chain algorithm
+fn convex_hull(mut points: Vec<Point>) -> Option<Geometry> {
+    points.sort_by(|a, b| {
+        a.x.partial_cmp(&b.x)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then(a.y.partial_cmp(&b.y).unwrap_or(std::cmp::Ordering::Equal))
+    });
+    points.dedup();
+    match points.len() {
+        0 => return None,
+        1 => return Some(Geometry::Point(points[0])),
+        2 => return Some(Geometry::LineString(points)),
+        _ => (),
+    }
+    let mut hull: Vec<Point> = Vec::with_capacity(points.len() + 1);
+    for p in points.iter().chain(points.iter().rev().skip(1)) {
+        while hull.len() >= 2 && cross(hull[hull.len() - 2], hull[hull.len() - 1], *p) <= 0. {
+            hull.pop();
+        }
+        hull.push(*p);
+    }
+    Some(if hull.len() < 4 {
+        // All the points are aligned
+        Geometry::LineString(vec![points[0], points[points.len() - 1]])
+    } else {
+        Geometry::Polygon(vec![hull])
+    })
+}
+
+/// Converts a distance in metres into the largest longitude and latitude differences
+/// between two points at most this distance away from each other, the first one being at the given latitude
+pub(crate) fn metres_to_degrees(distance: f64, latitude: f64) -> (f64, f64) {
+    let dy = (distance / EARTH_RADIUS).to_degrees();
+    // The haversine formula gives sin(dx/2) <= sin(distance/2R) / cos(latitude) for the points at most dy away from the latitude
+    let max_latitude = (latitude.abs() + dy).min(90.).to_radians();
+    let sin = (distance / (2. * EARTH_RADIUS)).sin() / max_latitude.cos();
+    let dx = if sin < 1. {
+        (2. * sin.asin()).to_degrees()
+    } else {
+        360.
+    };
+    (dx, dy)
+}
+
+fn haversine_distance(a: Point, b: Point) -> f64 {
+    let (lat1, lat2) = (a.y.to_radians(), b.y.to_radians());
+    let d_lat = lat2 - lat1;
+    let d_lon = (b.x - a.x).to_radians();
+    let h = (d_lat / 2.).sin().powi(2) + lat1.cos() * lat2.cos() * (d_lon / 2.).sin().powi(2);
+    2. * EARTH_RADIUS * h.sqrt().min(1.).asin()
+}
+
+struct WktParser<'a> {
+    input: &'a str,
+}
+
+impl<'a> WktParser<'a> {
+    fn skip_whitespaces(&mut self) {
+        self.input = self.input.trim_start();
+    }
+
+    fn parse_keyword(&mut self) -> String {
+        self.skip_whitespaces();
+        let end = self
+            .input
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(self.input.len());
+        let keyword = self.input[..end].to_ascii_uppercase();
+        self.input = &self.input[end..];
+        keyword
+    }
+
+    fn consume(&mut self, c: char) -> bool {
+        self.skip_whitespaces();
+        if self.input.starts_with(c) {
+            self.input = &self.input[1..];
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), GeometryParseError> {
+        if self.consume(c) {
+            Ok(())
+        } else {
+            Err(GeometryParseError::msg(format!(
+                "Expecting '{}' but found '{}'",
+                c,
+                self.input
+                    .chars()
+                    .next()
+                    .map_or_else(String::new, String::from)
+            )))
+        }
+    }
+
+    fn parse_geometry(&mut self) -> Result<Geometry, GeometryParseError> {
+        let kind = self.parse_keyword();
+        let dimension = self.parse_keyword(); // Z, M and ZM are allowed but the extra ordinates are ignored
+        if !matches!(dimension.as_str(), "" | "Z" | "M" | "ZM") {
+            if dimension == "EMPTY" {
+                return self.empty(&kind);
+            }
+            return Err(GeometryParseError::msg(format!(
+                "Unexpected keyword {}",
+                dimension
+            )));
+        }
+        let before_empty = self.input;
+        if self.parse_keyword() == "EMPTY" {
+            return self.empty(&kind);
+        }
+        self.input = before_empty;
+        Ok(match kind.as_str() {
+            "POINT" => {
+                self.expect('(')?;
+                let point = self.parse_point()?;
+                self.expect(')')?;
+                Geometry::Point(point)
+            }
+            "LINESTRING" => Geometry::LineString(self.parse_points()?),
+            "POLYGON" => Geometry::Polygon(check_polygon(self.parse_rings()?)?),
+            "MULTIPOINT" => Geometry::MultiPoint(self.parse_multi_points()?),
+            "MULTILINESTRING" => Geometry::MultiLineString(self.parse_rings()?),
+            "MULTIPOLYGON" => {
+                let mut polygons = Vec::new();
+                self.expect('(')?;
+                loop {
+                    polygons.push(check_polygon(self.parse_rings()?)?);
+                    if !self.consume(',') {
+                        break;
+                    }
+                }
+                self.expect(')')?;
+                Geometry::MultiPolygon(polygons)
+            }
+            "GEOMETRYCOLLECTION" => {
+                let mut geometries = Vec::new();
+                self.expect('(')?;
+                loop {
+                    geometries.push(self.parse_geometry()?);
+                    if !self.consume(',') {
+                        break;
+                    }
+                }
+                self.expect(')')?;
+                Geometry::GeometryCollection(geometries)
+            }
+            _ => {
+                return Err(GeometryParseError::msg(format!(
+                    "Unsupported geometry type {}",
+                    kind
+                )))
+            }
+        })
+    }
+
+    fn empty(&self, kind: &str) -> Result<Geometry, GeometryParseError> {
+        Ok(match kind {
+            // A point has always coordinates so an empty point is read as an empty set of points
+            "POINT" | "MULTIPOINT" => Geometry::MultiPoint(Vec::new()),
+            "LINESTRING" => Geometry::LineString(Vec::new()),
+            "POLYGON" => Geometry::Polygon(Vec::new()),
+            "MULTILINESTRING" => Geometry::MultiLineString(Vec::new()),
+            "MULTIPOLYGON" => Geometry::MultiPolygon(Vec::new()),
+            "GEOMETRYCOLLECTION" => Geometry::GeometryCollection(Vec::new()),
+            _ => {
+                return Err(GeometryParseError::msg(format!(
+                    "Unsupported empty geometry type {}",
+                    kind
+                )))
+            }
+        })
+    }
+
+    fn parse_number(&mut self) -> Result<f64, GeometryParseError> {
+        self.skip_whitespaces();
+        let end = self
+            .input
+            .find(|c: char| !(c.is_ascii_digit() || matches!(c, '+' | '-' | '.' | 'e' | 'E')))
+            .unwrap_or(self.input.len());
+        let value = self.input[..end].parse().map_err(|_| {
+            GeometryParseError::msg(format!("Invalid number '{}'", &self.input[..end]))
+        })?;
+        self.input = &self.input[end..];
+        Ok(value)
+    }
+
+    fn parse_point(&mut self) -> Result<Point, GeometryParseError> {
+        let x = self.parse_number()?;
+        let y = self.parse_number()?;
+        // We ignore the Z and M ordinates
+        self.skip_whitespaces();
+        while self
+            .input
+            .starts_with(|c: char| c.is_ascii_digit() || matches!(c, '+' | '-' | '.'))
+        {
+            self.parse_number()?;
+            self.skip_whitespaces();
+        }
+        Ok(Point::new(x, y))
+    }
+
+    fn parse_points(&mut self) -> Result<Vec<Point>, GeometryParseError> {
+        let mut points = Vec::new();
+        self.expect('(')?;
+        loop {
+            points.push(self.parse_point()?);
+            if !self.consume(',') {
+                break;
+            }
+        }
+        self.expect(')')?;
+        Ok(points)
+    }
+
+    fn parse_multi_points(&mut self) -> Result<Vec<Point>, GeometryParseError> {
+        // Both MULTIPOINT((1 2), (3 4)) and MULTIPOINT(1 2, 3 4) are allowed
+        let mut points = Vec::new();
+        self.expect('(')?;
+        loop {
+            if self.consume('(') {
+                points.push(self.parse_point()?);
+                self.expect(')')?;
+            } else {
+                points.push(self.parse_point()?);
+            }
+            if !self.consume(',') {
+                break;
+            }
+        }
+        self.expect(')')?;
+        Ok(points)
+    }
+
+    fn parse_rings(&mut self) -> Result<Vec<Vec<Point>>, GeometryParseError> {
+        let mut rings = Vec::new();
+        self.expect('(')?;
+        loop {
+            rings.push(self.parse_points()?);
+            if !self.consume(',') {
+                break;
+            }
+        }
+        self.expect(')')?;
+        Ok(rings)
+    }
+}
+
+fn geometry_from_json(value: &Value) -> Result<Geometry, GeometryParseError> {
+    let kind = if let Some(Value::String(kind)) = value.get("type") {
+        kind.as_str()
+    } else {
+        return Err(GeometryParseError::msg(
+            "GeoJSON objects should have a type",
+        ));
+    };
+    if kind == "Feature" {
+        return match value.get("geometry") {
+            Some(Value::Null) | None => Ok(Geometry::GeometryCollection(Vec::new())),
+            Some(geometry) => geometry_from_json(geometry),
+        };
+    }
+    if kind == "GeometryCollection" {
+        return if let Some(Value::Array(geometries)) = value.get("geometries") {
+            Ok(Geometry::GeometryCollection(
+                geometries
+                    .iter()
+                    .map(geometry_from_json)
+                    .collect::<Result<_, _>>()?,
+            ))
+        } else {
+            Err(GeometryParseError::msg(
+                "GeoJSON geometry collections should have geometries",
+            ))
+        };
+    }
+    let coordinates = value
+        .get("coordinates")
+        .ok_or_else(|| GeometryParseError::msg("GeoJSON geometries should have coordinates"))?;
+    Ok(match kind {
+        "Point" => Geometry::Point(json_point(coordinates)?),
+        "LineString" => Geometry::LineString(json_points(coordinates)?),
+        "Polygon" => Geometry::Polygon(check_polygon(json_rings(coordinates)?)?),
+        "MultiPoint" => Geometry::MultiPoint(json_points(coordinates)?),
+        "MultiLineString" => Geometry::MultiLineString(json_rings(coordinates)?),
+        "MultiPolygon" => Geometry::MultiPolygon(
+            json_array(coordinates)?
+                .iter()
+                .map(|polygon| check_polygon(json_rings(polygon)?))
+                .collect::<Result<_, _>>()?,
+        ),
+        _ => {
+            return Err(GeometryParseError::msg(format!(
+                "Unsupported GeoJSON type {}",
+                kind
+            )))
+        }
+    })
+}
+
+fn json_array(value: &Value) -> Result<&[Value], GeometryParseError> {
+    if let Value::Array(values) = value {
+        Ok(values)
+    } else {
+        Err(GeometryParseError::msg(
+            "GeoJSON coordinates should be arrays",
+        ))
+    }
+}
+
+fn json_point(value: &Value) -> Result<Point, GeometryParseError> {
+    match json_array(value)? {
+        [x, y, ..] => match (x.as_f64(), y.as_f64()) {
+            (Some(x), Some(y)) => Ok(Point::new(x, y)),
+            _ => Err(GeometryParseError::msg(
+                "GeoJSON positions should only contain numbers",
+            )),
+        },
+        _ => Err(GeometryParseError::msg(
+            "GeoJSON positions should have at least two numbers",
+        )),
+    }
+}
+
+fn json_points(value: &Value) -> Result<Vec<Point>, GeometryParseError> {
+    json_array(value)?.iter().map(json_point).collect()
+}
+
+fn json_rings(value: &Value) -> Result<Vec<Vec<Point>>, GeometryParseError> {
+    json_array(value)?.iter().map(json_points).collect()
+}
+
+/// Checks that the polygon rings are closed, i.e. have at least four points and end with their first point
+fn check_polygon(rings: Vec<Vec<Point>>) -> Result<Vec<Vec<Point>>, GeometryParseError> {
+    for ring in &rings {
+        if ring.len() < 4 || ring.first() != ring.last() {
+            return Err(GeometryParseError::msg(
+                "Polygon rings should be closed and have at least four points",
+            ));
+        }
+    }
+    Ok(rings)
+}
+
+/// An error raised during geometry parsing.
+#[derive(Debug, Clone)]
+pub struct GeometryParseError {
+    msg: String,
+}
+
+impl GeometryParseError {
+    fn msg(msg: impl Into<String>) -> Self {
+        Self { msg: msg.into() }
+    }
+}
+
+impl fmt::Display for GeometryParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.msg)
+    }
+}
+
+impl Error for GeometryParseError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_wkt() -> Result<(), GeometryParseError> {
+        assert_eq!(
+            Geometry::from_wkt("POINT(1 2)")?,
+            Geometry::Point(Point::new(1., 2.))
+        );
+        assert_eq!(
+            Geometry::from_wkt("<http://www.opengis.net/def/crs/OGC/1.3/CRS84> Point Z (1 2 3)")?,
+            Geometry::Point(Point::new(1., 2.))
+        );
+        assert_eq!(
+            Geometry::from_wkt("MULTIPOINT((1 2), (3 4))")?,
+            Geometry::from_wkt("MULTIPOINT(1 2, 3 4)")?
+        );
+        assert_eq!(
+            Geometry::from_wkt("LINESTRING EMPTY")?,
+            Geometry::LineString(Vec::new())
+        );
+        assert_eq!(
+            Geometry::from_wkt("POINT EMPTY")?,
+            Geometry::MultiPoint(Vec::new())
+        );
+        assert!(Geometry::from_wkt("POLYGON((0 0, 10 0, 10 10))").is_err());
+        assert!(Geometry::from_wkt("MULTIPOLYGON(((0 0, 10 0, 10 10, 0 1)))").is_err());
+        assert!(
+            Geometry::from_wkt("<http://www.opengis.net/def/crs/EPSG/0/4326> POINT(1 2)").is_err()
+        );
+        assert!(Geometry::from_wkt("POINT(1)").is_err());
+        assert!(Geometry::from_wkt("POINT(1 2) foo").is_err());
+        for wkt in &[
+            "POINT(1.5 -2)",
+            "LINESTRING(0 0, 1 1)",
+            "POLYGON((0 0, 10 0, 10 10, 0 0), (1 1, 2 1, 2 2, 1 1))",
+            "MULTIPOLYGON(((0 0, 1 0, 1 1, 0 0)), ((5 5, 6 5, 6 6, 5 5)))",
+            "GEOMETRYCOLLECTION(POINT(1 2), LINESTRING(0 0, 1 1))",
+        ] {
+            assert_eq!(Geometry::from_wkt(wkt)?.to_string(), *wkt);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn from_geojson() -> Result<(), GeometryParseError> {
+        assert_eq!(
+            Geometry::from_geojson(r#"{"type": "Point", "coordinates": [1, 2.5]}"#)?,
+            Geometry::Point(Point::new(1., 2.5))
+        );
+        assert_eq!(
+            Geometry::from_geojson(
+                r#"{"type": "Feature", "properties": {"name": "a \"b\""}, "geometry": {"type": "LineString", "coordinates": [[0, 0], [1, 1]]}}"#
+            )?,
+            Geometry::from_wkt("LINESTRING(0 0, 1 1)")?
+        );
+        let polygon = Geometry::from_wkt("POLYGON((0 0, 10 0, 10 10, 0 0))")?;
+        assert_eq!(Geometry::from_geojson(&polygon.to_geojson())?, polygon);
+        assert!(Geometry::from_geojson(r#"{"type": "Point"}"#).is_err());
+        assert!(Geometry::from_geojson(
+            r#"{"type": "Polygon", "coordinates": [[[0, 0], [1, 0], [1, 1]]]}"#
+        )
+        .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn relations() -> Result<(), GeometryParseError> {
+        let square = Geometry::from_wkt("POLYGON((0 0, 10 0, 10 10, 0 10, 0 0))")?;
+        let holed = Geometry::from_wkt(
+            "POLYGON((0 0, 10 0, 10 10, 0 10, 0 0), (4 4, 6 4, 6 6, 4 6, 4 4))",
+        )?;
+        let inner = Geometry::from_wkt("POLYGON((1 1, 2 1, 2 2, 1 2, 1 1))")?;
+        let center = Geometry::from_wkt("POINT(5 5)")?;
+        let crossing = Geometry::from_wkt("LINESTRING(-1 5, 11 5)")?;
+        let outside = Geometry::from_wkt("POINT(20 20)")?;
+
+        assert!(center.is_within(&square));
+        assert!(!center.is_within(&holed));
+        assert!(inner.is_within(&square));
+        assert!(inner.is_within(&holed));
+        assert!(!square.is_within(&holed));
+        assert!(square.contains(&inner));
+        assert!(!crossing.is_within(&square));
+        assert!(crossing.intersects(&square));
+        assert!(crossing.intersects(&holed));
+        assert!(!outside.intersects(&square));
+        assert!(Geometry::from_wkt("LINESTRING(0 0, 2 2)")?
+            .intersects(&Geometry::from_wkt("LINESTRING(0 2, 2 0)")?));
+        assert!(!Geometry::from_wkt("LINESTRING(0 0, 2 2)")?
+            .intersects(&Geometry::from_wkt("LINESTRING(0 1, 0.9 1)")?));
+        assert!(square.intersects(&inner));
+        assert!(Geometry::from_wkt("POINT(10 5)")?.is_within(&square));
+        assert!(
+            !Geometry::from_wkt("LINESTRING(0 0, 10 10)")?.is_within(&Geometry::from_wkt(
+                "POLYGON((0 0, 10 0, 10 10, 5 1, 0 10, 0 0))"
+            )?)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn distances() -> Result<(), GeometryParseError> {
+        let square = Geometry::from_wkt("POLYGON((0 0, 10 0, 10 10, 0 10, 0 0))")?;
+        assert_eq!(
+            Geometry::from_wkt("POINT(13 14)")?.distance(&square),
+            Some(5.)
+        );
+        assert_eq!(
+            Geometry::from_wkt("POINT(5 5)")?.distance(&square),
+            Some(0.)
+        );
+        assert_eq!(
+            Geometry::from_wkt("LINESTRING(0 0, 10 0)")?
+                .distance(&Geometry::from_wkt("LINESTRING(100 100, 10 1)")?),
+            Some(1.)
+        );
+        assert_eq!(
+            Geometry::from_wkt("LINESTRING(0 2, 5 2, 5 12)")?
+                .distance(&Geometry::from_wkt("LINESTRING(-3 -1, 8 -1)")?),
+            Some(3.)
+        );
+        assert_eq!(
+            square.distance(&Geometry::from_wkt("POLYGON((20 0, 20 10, 14 5, 20 0))")?),
+            Some(4.)
+        );
+        assert_eq!(
+            square.distance(&Geometry::from_wkt(
+                "POLYGON((-10 -10, 20 -10, 20 20, -10 20, -10 -10), (-1 -1, 11 -1, 11 11, -1 11, -1 -1))"
+            )?),
+            Some(1.)
+        );
+        assert_eq!(Geometry::from_wkt("POINT EMPTY")?.distance(&square), None);
+        let paris = Geometry::from_wkt("POINT(2.3522 48.8566)")?;
+        let london = Geometry::from_wkt("POINT(-0.1276 51.5072)")?;
+        let distance = paris.geodesic_distance(&london).unwrap();
+        assert!((distance - 343_500.).abs() < 1000., "{}", distance);
+        Ok(())
+    }
+
+    #[test]
+    fn constructions() -> Result<(), GeometryParseError> {
+        let line = Geometry::from_wkt("LINESTRING(0 0, 2 0, 1 1)")?;
+        assert_eq!(
+            line.envelope().unwrap().to_geometry(),
+            Geometry::from_wkt("POLYGON((0 0, 2 0, 2 1, 0 1, 0 0))")?
+        );
+        assert_eq!(
+            line.convex_hull().unwrap(),
+            Geometry::from_wkt("POLYGON((0 0, 2 0, 1 1, 0 0))")?
+        );
+        let point = Geometry::from_wkt("POINT(0 0)")?;
+        let buffer = point.buffer(1000.).unwrap();
+        assert!(Geometry::from_wkt("POINT(0.005 0)")?.is_within(&buffer));
+        assert!(!Geometry::from_wkt("POINT(0.01 0)")?.is_within(&buffer));
+        let points = Geometry::from_wkt("MULTIPOINT((0 0), (1 0))")?
+            .buffer(1000.)
+            .unwrap();
+        assert!(Geometry::from_wkt("POINT(0.995 0)")?.is_within(&points));
+        assert!(!Geometry::from_wkt("POINT(0.5 0)")?.is_within(&points));
+        let line = Geometry::from_wkt("LINESTRING(0 0, 1 0, 1 1)")?
+            .buffer(1000.)
+            .unwrap();
+        assert!(Geometry::from_wkt("POINT(0.5 0.005)")?.is_within(&line));
+        assert!(Geometry::from_wkt("LINESTRING(0.5 0.005, 0.995 0.5)")?.intersects(&line));
+        assert!(!Geometry::from_wkt("POINT(0.5 0.5)")?.intersects(&line));
+        let polygon = Geometry::from_wkt("POLYGON((0 0, 2 0, 2 2, 1 2, 1 1, 0 1, 0 0))")?
+            .buffer(1000.)
+            .unwrap();
+        assert!(Geometry::from_wkt("POINT(0.5 0.5)")?.is_within(&polygon));
+        assert!(Geometry::from_wkt("POINT(0.5 1.005)")?.is_within(&polygon));
+        assert!(!Geometry::from_wkt("POINT(0.5 1.5)")?.is_within(&polygon));
+        Ok(())
+    }
+}
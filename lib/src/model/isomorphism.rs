@@ -0,0 +1,737 @@
+//! [Isomorphism](https://www.w3.org/TR/rdf11-concepts/#dfn-dataset-isomorphism) checks and differences between sets of quads.
+//!
+//! Usage example:
+//! ```
+//! use oxigraph::model::*;
+//!
+//! let ex = NamedNode::new("http://example.com")?;
+//! let left = vec![Quad::new(BlankNode::new("a")?, ex.clone(), ex.clone(), None)];
+//! let right = vec![Quad::new(BlankNode::new("b")?, ex.clone(), ex.clone(), None)];
+//! assert!(are_isomorphic(left.clone(), right.clone()));
+//!
+//! let diff = dataset_diff(left, right);
+//! assert!(diff.is_empty());
+//! assert_eq!(diff.blank_node_mapping()[&BlankNode::new("a")?], BlankNode::new("b")?);
+//! # Result::<_,Box<dyn std::error::Error>>::Ok(())
+//! ```
+
+use crate::io::canonicalization::{canonical_blank_node_labels, quad_blank_nodes, write_quad};
+use crate::model::*;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+/// Checks if two sets of quads are [isomorphic](https://www.w3.org/TR/rdf11-concepts/#dfn-dataset-isomorphism).
+///
+/// Duplicated quads are ignored.
+/// It relies on the [RDFC-1.0](https://www.w3.org/TR/rdf-canon/) canonicalization:
+/// the worst-case complexity is exponential in the number of blank nodes
+/// that could not be distinguished from their neighborhood.
+pub fn are_isomorphic(
+    left: impl IntoIterator<Item = Quad>,
+    right: impl IntoIterator<Item = Quad>,
+) -> bool {
+    let left = dedup(left);
+    let right = dedup(right);
+    left.len() == right.len()
+        && canonical_lines(&left, &canonical_blank_node_labels(&left))
+            == canonical_lines(&right, &canonical_blank_node_labels(&right))
+}
+
+/// Computes the difference between two sets of quads, up to a renaming of their blank nodes.
+///
+/// If the two sets are [isomorphic](https://www.w3.org/TR/rdf11-concepts/#dfn-dataset-isomorphism),
+/// the difference is empty and the returned blank node mapping is an isomorphism between them.
+/// Otherwise, the returned blank node mapping is one that gives the smallest difference.
+///
+/// Duplicated quads are ignored.
+/// Like for [`are_isomorphic`], the worst-case complexity is exponential in the number of blank nodes.
+///
+/// Usage example:
+/// ```
+/// use oxigraph::model::*;
+///
+/// let ex = NamedNode::new("http://example.com")?;
+/// let left = vec![
+///     Quad::new(BlankNode::new("a")?, ex.clone(), Literal::from(1), None),
+///     Quad::new(BlankNode::new("a")?, ex.clone(), Literal::from(2), None),
+/// ];
+/// let right = vec![
+///     Quad::new(BlankNode::new("b")?, ex.clone(), Literal::from(1), None),
+///     Quad::new(BlankNode::new("b")?, ex.clone(), Literal::from(3), None),
+/// ];
+///
+/// let diff = dataset_diff(left, right);
+/// assert_eq!(diff.only_in_left(), &[Quad::new(BlankNode::new("a")?, ex.clone(), Literal::from(2), None)]);
+/// assert_eq!(diff.only_in_right(), &[Quad::new(BlankNode::new("b")?, ex, Literal::from(3), None)]);
+/// # Result::<_,Box<dyn std::error::Error>>::Ok(())
+/// ```
+pub fn dataset_diff(
+    left: impl IntoIterator<Item = Quad>,
+    right: impl IntoIterator<Item = Quad>,
+) -> DatasetDiff {
+    let left = dedup(left);
+    let right = dedup(right);
+
+    let mapping = if let Some(mapping) = isomorphism(&left, &right) {
+        mapping
+    } else {
+        minimal_mapping(&left, &right)
+    };
+
+    let right_set = right.iter().collect::<HashSet<_>>();
+    let mut mapped_left = HashSet::with_capacity(left.len());
+    let mut only_in_left = Vec::new();
+    for quad in &left {
+        match map_quad(quad, &mapping) {
+            Some(mapped) if right_set.contains(&mapped) => {
+                mapped_left.insert(mapped);
+            }
+            _ => only_in_left.push(quad.clone()),
+        }
+    }
+    let mut only_in_right = right
+        .iter()
+        .filter(|quad| !mapped_left.contains(*quad))
+        .cloned()
+        .collect::<Vec<_>>();
+    sort_quads(&mut only_in_left);
+    sort_quads(&mut only_in_right);
+
+    DatasetDiff {
+        only_in_left,
+        only_in_right,
+        blank_node_mapping: mapping
+            .into_iter()
+            .map(|(l, r)| (BlankNode::new_unchecked(l), BlankNode::new_unchecked(r)))
+            .collect(),
+    }
+}
+
+/// The difference between two sets of quads returned by [`dataset_diff`].
+///
+/// Its [`Display`](std::fmt::Display) implementation writes the quads only in the left set prefixed by `-`
+/// and the quads only in the right set prefixed by `+`, one per line.
+#[derive(Eq, PartialEq, Debug, Clone, Default)]
+pub struct DatasetDiff {
+    only_in_left: Vec<Quad>,
+    only_in_right: Vec<Quad>,
+    blank_node_mapping: HashMap<BlankNode, BlankNode>,
+}
+
+impl DatasetDiff {
+    /// The quads of the left set without counterpart in the right set, using their left set blank nodes
+    pub fn only_in_left(&self) -> &[Quad] {
+        &self.only_in_left
+    }
+
+    /// The quads of the right set without counterpart in the left set, using their right set blank nodes
+    pub fn only_in_right(&self) -> &[Quad] {
+        &self.only_in_right
+    }
+
+    /// The mapping from blank nodes of the left set to blank nodes of the right set used to compute the difference
+    pub fn blank_node_mapping(&self) -> &HashMap<BlankNode, BlankNode> {
+        &self.blank_node_mapping
+    }
+
+    /// Returns `true` if the two sets are isomorphic
+    pub fn is_empty(&self) -> bool {
+        self.only_in_left.is_empty() && self.only_in_right.is_empty()
+    }
+}
+
+impl fmt::Display for DatasetDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for quad in &self.only_in_left {
+            writeln!(f, "- {}", quad)?;
+        }
+        for quad in &self.only_in_right {
+            writeln!(f, "+ {}", quad)?;
+        }
+        Ok(())
+    }
+}
+
+/// Applies `f` to all the blank nodes of the quad, including the ones inside of quoted triples.
+pub(crate) fn map_blank_nodes(quad: &Quad, f: &impl Fn(&BlankNode) -> BlankNode) -> Quad {
+    Quad {
+        subject: map_subject(&quad.subject, f),
+        predicate: quad.predicate.clone(),
+        object: map_term(&quad.object, f),
+        graph_name: match &quad.graph_name {
+            GraphName::BlankNode(node) => f(node).into(),
+            graph_name => graph_name.clone(),
+        },
+    }
+}
+
+fn map_subject(subject: &Subject, f: &impl Fn(&BlankNode) -> BlankNode) -> Subject {
+    match subject {
+        Subject::BlankNode(node) => f(node).into(),
+        Subject::Triple(triple) => map_triple(triple, f).into(),
+        Subject::NamedNode(_) => subject.clone(),
+    }
+}
+
+fn map_term(term: &Term, f: &impl Fn(&BlankNode) -> BlankNode) -> Term {
+    match term {
+        Term::BlankNode(node) => f(node).into(),
+        Term::Triple(triple) => map_triple(triple, f).into(),
+        Term::NamedNode(_) | Term::Literal(_) => term.clone(),
+    }
+}
+
+fn map_triple(triple: &Triple, f: &impl Fn(&BlankNode) -> BlankNode) -> Triple {
+    Triple {
+        subject: map_subject(&triple.subject, f),
+        predicate: triple.predicate.clone(),
+        object: map_term(&triple.object, f),
+    }
+}
+
+/// Maps the blank nodes of the quad or returns `None` if one of them is not in the mapping
+fn map_quad(quad: &Quad, mapping: &HashMap<&str, &str>) -> Option<Quad> {
+    if quad_blank_nodes(quad)
+        .iter()
+        .all(|(node, _)| mapping.contains_key(node))
+    {
+        Some(map_blank_nodes(quad, &|node| {
+            BlankNode::new_unchecked(mapping[node.as_str()])
+        }))
+    } else {
+        None
+    }
+}
+
+fn dedup(quads: impl IntoIterator<Item = Quad>) -> Vec<Quad> {
+    quads
+        .into_iter()
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+fn sort_quads(quads: &mut Vec<Quad>) {
+    let mut keyed = quads
+        .drain(..)
+        .map(|quad| (quad.to_string(), quad))
+        .collect::<Vec<_>>();
+    keyed.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+    quads.extend(keyed.into_iter().map(|(_, quad)| quad));
+}
+
+/// The sorted canonical N-Quads lines of the quads given their canonical blank node labels
+fn canonical_lines(quads: &[Quad], labels: &HashMap<String, String>) -> Vec<String> {
+    let mut lines = quads
+        .iter()
+        .map(|quad| {
+            let mut line = String::new();
+            write_quad(&mut line, quad.as_ref(), &|id, out| {
+                out.push_str(labels.get(id).map_or(id, String::as_str))
+            });
+            line
+        })
+        .collect::<Vec<_>>();
+    lines.sort_unstable();
+    lines
+}
+
+/// Returns an isomorphism from the left blank nodes to the right ones if it exists
+fn isomorphism<'a>(left: &'a [Quad], right: &'a [Quad]) -> Option<HashMap<&'a str, &'a str>> {
+    if left.len() != right.len() {
+        return None;
+    }
+    let left_labels = canonical_blank_node_labels(left);
+    let right_labels = canonical_blank_node_labels(right);
+    if canonical_lines(left, &left_labels) != canonical_lines(right, &right_labels) {
+        return None;
+    }
+    let right_by_label = right_labels
+        .iter()
+        .map(|(node, label)| (label.as_str(), node.as_str()))
+        .collect::<HashMap<_, _>>();
+    let left_nodes = blank_nodes(left);
+    let right_nodes = blank_nodes(right);
+    Some(
+        left_labels
+            .iter()
+            .filter_map(|(node, label)| {
+                Some((
+                    *left_nodes.get(node.as_str())?,
+                    *right_nodes.get(right_by_label.get(label.as_str())?)?,
+                ))
+            })
+            .collect(),
+    )
+}
+
+/// The blank node labels of the quads, as references into the quads
+fn blank_nodes(quads: &[Quad]) -> HashMap<&str, &str> {
+    quads
+        .iter()
+        .flat_map(quad_blank_nodes)
+        .map(|(node, _)| (node, node))
+        .collect()
+}
+
+/// Matches the blank nodes of two non-isomorphic sets of quads such that the most left quads match a right quad.
+///
+/// It is a branch and bound search over the injective mappings, bounded at the start by the [`approximate_mapping`].
+/// The left blank nodes are assigned in a breadth-first order of their neighborhood
+/// so that the quads are decided as early as possible.
+fn minimal_mapping<'a>(left: &'a [Quad], right: &'a [Quad]) -> HashMap<&'a str, &'a str> {
+    let left_graph = BlankNodeGraph::new(left);
+    let order = left_graph.breadth_first_order();
+    let position = order
+        .iter()
+        .enumerate()
+        .map(|(i, node)| (*node, i))
+        .collect::<HashMap<_, _>>();
+    // Each quad is decided when its last blank node in the order is assigned
+    let decided = order
+        .iter()
+        .map(|node| {
+            left_graph.quads[node]
+                .iter()
+                .copied()
+                .filter(|quad| {
+                    quad_blank_nodes(quad)
+                        .iter()
+                        .all(|(other, _)| position[other] <= position[node])
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    let mut undecided = vec![0; order.len() + 1];
+    for i in (0..order.len()).rev() {
+        undecided[i] = undecided[i + 1] + decided[i].len();
+    }
+    let mut right_nodes = blank_nodes(right).into_keys().collect::<Vec<_>>();
+    right_nodes.sort_unstable();
+
+    let mut search = MappingSearch {
+        order,
+        decided,
+        undecided,
+        right_nodes,
+        right: right.iter().collect(),
+        mapping: HashMap::new(),
+        used: HashSet::new(),
+        best: HashMap::new(),
+        best_score: 0,
+    };
+    search.best = approximate_mapping(left, right);
+    search.best_score = search.score(&search.best);
+    if search.best_score < search.undecided[0] {
+        search.search(0, 0);
+    }
+    search.best
+}
+
+/// The state of the [`minimal_mapping`] search
+struct MappingSearch<'a> {
+    /// The left blank nodes in assignment order
+    order: Vec<&'a str>,
+    /// The quads decided by the assignment of each left blank node
+    decided: Vec<Vec<&'a Quad>>,
+    /// The number of quads decided by the assignment of the blank nodes from each position
+    undecided: Vec<usize>,
+    right_nodes: Vec<&'a str>,
+    right: HashSet<&'a Quad>,
+    mapping: HashMap<&'a str, &'a str>,
+    /// The right blank nodes already in the mapping
+    used: HashSet<&'a str>,
+    best: HashMap<&'a str, &'a str>,
+    /// The number of matching quads with the best mapping
+    best_score: usize,
+}
+
+impl<'a> MappingSearch<'a> {
+    fn score(&self, mapping: &HashMap<&str, &str>) -> usize {
+        self.decided
+            .iter()
+            .flatten()
+            .filter(|quad| self.matches(quad, mapping))
+            .count()
+    }
+
+    fn matches(&self, quad: &Quad, mapping: &HashMap<&str, &str>) -> bool {
+        matches!(map_quad(quad, mapping), Some(quad) if self.right.contains(&quad))
+    }
+
+    /// The number of quads decided at `position` that match with the current mapping
+    fn gain(&self, position: usize) -> usize {
+        self.decided[position]
+            .iter()
+            .filter(|quad| self.matches(quad, &self.mapping))
+            .count()
+    }
+
+    fn search(&mut self, position: usize, score: usize) {
+        if position == self.order.len() {
+            if score > self.best_score {
+                self.best_score = score;
+                self.best = self.mapping.clone();
+            }
+            return;
+        }
+        let node = self.order[position];
+
+        // We try first the candidates that match the most quads, the blank node might also stay unmapped
+        let mut candidates = Vec::new();
+        for candidate in &self.right_nodes {
+            if !self.used.contains(candidate) {
+                self.mapping.insert(node, candidate);
+                candidates.push((Some(*candidate), self.gain(position)));
+                self.mapping.remove(node);
+            }
+        }
+        candidates.push((None, 0));
+        candidates.sort_by(|(_, a), (_, b)| b.cmp(a));
+
+        for (candidate, gain) in candidates {
+            if score + gain + self.undecided[position + 1] <= self.best_score {
+                // The candidates are sorted so the next ones could not do better
+                return;
+            }
+            if let Some(candidate) = candidate {
+                self.mapping.insert(node, candidate);
+                self.used.insert(candidate);
+            }
+            self.search(position + 1, score + gain);
+            if let Some(candidate) = candidate {
+                self.mapping.remove(node);
+                self.used.remove(candidate);
+            }
+            if self.best_score == self.undecided[0] {
+                return;
+            }
+        }
+    }
+}
+
+/// Matches the blank nodes of two non-isomorphic sets of quads.
+///
+/// The blank nodes are first colored by refining their neighborhood on both sides at the same time,
+/// the blank nodes with the same color are paired and the remaining ones are greedily paired
+/// with the node that makes the most of their quads match.
+fn approximate_mapping<'a>(left: &'a [Quad], right: &'a [Quad]) -> HashMap<&'a str, &'a str> {
+    let left_graph = BlankNodeGraph::new(left);
+    let right_graph = BlankNodeGraph::new(right);
+    let mut left_colors = left_graph.initial_colors();
+    let mut right_colors = right_graph.initial_colors();
+    let max_rounds = left_colors.len().max(right_colors.len()) + 1;
+    for _ in 0..max_rounds {
+        let new_left_colors = left_graph.refine(&left_colors);
+        let new_right_colors = right_graph.refine(&right_colors);
+        let grown = color_count(&new_left_colors) > color_count(&left_colors)
+            || color_count(&new_right_colors) > color_count(&right_colors);
+        left_colors = new_left_colors;
+        right_colors = new_right_colors;
+        if !grown {
+            break;
+        }
+    }
+
+    // We pair the nodes with the same color
+    let mut right_by_color = BTreeMap::<u64, Vec<&str>>::new();
+    for (node, color) in &right_colors {
+        right_by_color.entry(*color).or_default().push(node);
+    }
+    for nodes in right_by_color.values_mut() {
+        nodes.sort_unstable_by(|a, b| b.cmp(a));
+    }
+    let mut left_nodes = left_colors.keys().copied().collect::<Vec<_>>();
+    left_nodes.sort_unstable();
+    let mut mapping = HashMap::new();
+    let mut unmatched_left = Vec::new();
+    for node in left_nodes {
+        if let Some(other) = right_by_color
+            .get_mut(&left_colors[node])
+            .and_then(Vec::pop)
+        {
+            mapping.insert(node, other);
+        } else {
+            unmatched_left.push(node);
+        }
+    }
+    let mut unmatched_right = right_by_color.into_values().flatten().collect::<Vec<_>>();
+    unmatched_right.sort_unstable();
+
+    // We greedily pair the remaining nodes
+    // Pairing a node might allow to match the quads of its neighbors so we loop until nothing changes
+    let right_set = right.iter().collect::<HashSet<_>>();
+    let mut changed = true;
+    while changed {
+        changed = false;
+        unmatched_left.retain(|node| {
+            let mut best: Option<(usize, usize)> = None;
+            for (i, candidate) in unmatched_right.iter().enumerate() {
+                mapping.insert(node, *candidate);
+                let score = left_graph.quads[node]
+                    .iter()
+                    .filter(|quad| matches!(map_quad(quad, &mapping), Some(quad) if right_set.contains(&quad)))
+                    .count();
+                mapping.remove(node);
+                if score > best.map_or(0, |(_, best_score)| best_score) {
+                    best = Some((i, score));
+                }
+            }
+            if let Some((i, _)) = best {
+                mapping.insert(node, unmatched_right.remove(i));
+                changed = true;
+                false
+            } else {
+                true
+            }
+        });
+    }
+    mapping
+}
+
+fn color_count(colors: &HashMap<&str, u64>) -> usize {
+    colors.values().collect::<HashSet<_>>().len()
+}
+
+struct BlankNodeGraph<'a> {
+    quads: HashMap<&'a str, Vec<&'a Quad>>,
+}
+
+impl<'a> BlankNodeGraph<'a> {
+    fn new(quads: &'a [Quad]) -> Self {
+        let mut quads_for_node = HashMap::<_, Vec<&Quad>>::new();
+        for quad in quads {
+            for (node, _) in quad_blank_nodes(quad) {
+                let node_quads = quads_for_node.entry(node).or_default();
+                // The same blank node might appear multiple times in the quad
+                if !matches!(node_quads.last(), Some(q) if std::ptr::eq(*q, quad)) {
+                    node_quads.push(quad);
+                }
+            }
+        }
+        Self {
+            quads: quads_for_node,
+        }
+    }
+
+    /// The blank nodes in a breadth-first order, starting from the ones with the most quads
+    fn breadth_first_order(&self) -> Vec<&'a str> {
+        let mut roots = self.quads.keys().copied().collect::<Vec<_>>();
+        roots.sort_unstable_by(|a, b| {
+            self.quads[b]
+                .len()
+                .cmp(&self.quads[a].len())
+                .then_with(|| a.cmp(b))
+        });
+        let mut order = Vec::with_capacity(roots.len());
+        let mut visited = HashSet::new();
+        for root in roots {
+            if !visited.insert(root) {
+                continue;
+            }
+            let start = order.len();
+            order.push(root);
+            let mut i = start;
+            while i < order.len() {
+                let mut neighbors = self.quads[order[i]]
+                    .iter()
+                    .flat_map(|quad| quad_blank_nodes(quad))
+                    .map(|(node, _)| node)
+                    .filter(|node| !visited.contains(node))
+                    .collect::<Vec<_>>();
+                neighbors.sort_unstable();
+                neighbors.dedup();
+                for neighbor in neighbors {
+                    visited.insert(neighbor);
+                    order.push(neighbor);
+                }
+                i += 1;
+            }
+        }
+        order
+    }
+
+    fn initial_colors(&self) -> HashMap<&'a str, u64> {
+        self.quads.keys().map(|node| (*node, 0)).collect()
+    }
+
+    /// Computes the new color of each node from its color and the colors of its neighborhood
+    fn refine(&self, colors: &HashMap<&'a str, u64>) -> HashMap<&'a str, u64> {
+        self.quads
+            .iter()
+            .map(|(node, quads)| {
+                let mut signatures = quads
+                    .iter()
+                    .map(|quad| {
+                        let mut signature = String::new();
+                        write_quad(&mut signature, quad.as_ref(), &|id, out| {
+                            if id == *node {
+                                out.push('@');
+                            } else {
+                                out.push_str(&colors[id].to_string());
+                            }
+                        });
+                        signature
+                    })
+                    .collect::<Vec<_>>();
+                signatures.sort_unstable();
+                let mut hasher = DefaultHasher::new();
+                colors[node].hash(&mut hasher);
+                signatures.hash(&mut hasher);
+                (*node, hasher.finish())
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quad(s: &str, p: &str, o: &str) -> Quad {
+        let p = NamedNode::new_unchecked(format!("http://example.com/{}", p));
+        let o = if let Some(o) = o.strip_prefix("_:") {
+            Term::from(BlankNode::new_unchecked(o))
+        } else {
+            Literal::new_simple_literal(o).into()
+        };
+        Quad::new(BlankNode::new_unchecked(&s[2..]), p, o, None)
+    }
+
+    #[test]
+    fn test_are_isomorphic() {
+        let left = vec![quad("_:a", "p", "_:b"), quad("_:b", "p", "_:a")];
+        let right = vec![quad("_:x", "p", "_:y"), quad("_:y", "p", "_:x")];
+        assert!(are_isomorphic(left.clone(), right));
+        let other = vec![quad("_:x", "p", "_:y"), quad("_:y", "p", "_:y")];
+        assert!(!are_isomorphic(left, other));
+    }
+
+    #[test]
+    fn test_isomorphic_diff() {
+        let left = vec![quad("_:a", "p", "_:b"), quad("_:b", "q", "foo")];
+        let right = vec![quad("_:y", "q", "foo"), quad("_:x", "p", "_:y")];
+        let diff = dataset_diff(left, right);
+        assert!(diff.is_empty());
+        assert_eq!(diff.to_string(), "");
+        assert_eq!(
+            diff.blank_node_mapping()[&BlankNode::new_unchecked("a")],
+            BlankNode::new_unchecked("x")
+        );
+        assert_eq!(
+            diff.blank_node_mapping()[&BlankNode::new_unchecked("b")],
+            BlankNode::new_unchecked("y")
+        );
+    }
+
+    #[test]
+    fn test_non_isomorphic_diff() {
+        let left = vec![
+            quad("_:a", "p", "_:b"),
+            quad("_:b", "q", "foo"),
+            quad("_:b", "q", "bar"),
+            quad("_:c", "r", "baz"),
+        ];
+        let right = vec![
+            quad("_:x", "p", "_:y"),
+            quad("_:y", "q", "foo"),
+            quad("_:y", "q", "baz"),
+            quad("_:z", "r", "baz"),
+        ];
+        let diff = dataset_diff(left, right);
+        assert_eq!(diff.only_in_left(), &[quad("_:b", "q", "bar")]);
+        assert_eq!(diff.only_in_right(), &[quad("_:y", "q", "baz")]);
+        assert_eq!(
+            diff.to_string(),
+            "- _:b <http://example.com/q> \"bar\" .\n+ _:y <http://example.com/q> \"baz\" .\n"
+        );
+        assert_eq!(
+            diff.blank_node_mapping()[&BlankNode::new_unchecked("c")],
+            BlankNode::new_unchecked("z")
+        );
+    }
+
+    /// The smallest difference size over all the injective mappings
+    fn brute_force_diff_size(left: &[Quad], right: &[Quad]) -> usize {
+        fn best_matches<'a>(
+            left_nodes: &[&'a str],
+            right_nodes: &[&'a str],
+            mapping: &mut HashMap<&'a str, &'a str>,
+            left: &[Quad],
+            right: &HashSet<&Quad>,
+        ) -> usize {
+            if let Some((node, rest)) = left_nodes.split_first() {
+                let mut best = best_matches(rest, right_nodes, mapping, left, right);
+                for candidate in right_nodes {
+                    if !mapping.values().any(|used| used == candidate) {
+                        mapping.insert(node, candidate);
+                        best = best.max(best_matches(rest, right_nodes, mapping, left, right));
+                        mapping.remove(node);
+                    }
+                }
+                best
+            } else {
+                left.iter()
+                    .filter(|quad| matches!(map_quad(quad, mapping), Some(quad) if right.contains(&quad)))
+                    .count()
+            }
+        }
+
+        let left_nodes = blank_nodes(left).into_keys().collect::<Vec<_>>();
+        let right_nodes = blank_nodes(right).into_keys().collect::<Vec<_>>();
+        let matches = best_matches(
+            &left_nodes,
+            &right_nodes,
+            &mut HashMap::new(),
+            left,
+            &right.iter().collect(),
+        );
+        left.len() + right.len() - 2 * matches
+    }
+
+    #[test]
+    fn test_minimal_diff() {
+        let cases = vec![
+            (
+                vec![
+                    quad("_:a", "p", "_:b"),
+                    quad("_:b", "p", "_:c"),
+                    quad("_:c", "p", "_:a"),
+                    quad("_:a", "q", "foo"),
+                ],
+                vec![
+                    quad("_:x", "p", "_:y"),
+                    quad("_:y", "p", "_:x"),
+                    quad("_:x", "q", "foo"),
+                    quad("_:z", "p", "_:z"),
+                ],
+            ),
+            // The greedy pairing of the approximate mapping does not give the smallest difference here
+            (
+                vec![
+                    quad("_:b", "q", "_:c"),
+                    quad("_:c", "q", "1"),
+                    quad("_:b", "q", "1"),
+                ],
+                vec![
+                    quad("_:x", "q", "0"),
+                    quad("_:z", "q", "_:x"),
+                    quad("_:y", "q", "0"),
+                    quad("_:y", "q", "_:z"),
+                    quad("_:z", "q", "0"),
+                ],
+            ),
+        ];
+        for (left, right) in cases {
+            let diff = dataset_diff(left.clone(), right.clone());
+            assert_eq!(
+                diff.only_in_left().len() + diff.only_in_right().len(),
+                brute_force_diff_size(&left, &right),
+                "{}",
+                diff
+            );
+        }
+    }
+}
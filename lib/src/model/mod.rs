@@ -3,6 +3,8 @@
 //! Inspired by [RDF/JS](https://rdf.js.org/data-model-spec/) and [Apache Commons RDF](http://commons.apache.org/proper/commons-rdf/)
 
 mod blank_node;
+pub mod geo;
+pub(crate) mod isomorphism;
 mod literal;
 mod named_node;
 mod parser;
@@ -13,12 +15,13 @@ pub mod vocab;
 pub(crate) mod xsd;
 
 pub use crate::model::blank_node::{BlankNode, BlankNodeIdParseError, BlankNodeRef};
+pub use crate::model::isomorphism::{are_isomorphic, dataset_diff, DatasetDiff};
 pub use crate::model::literal::{Literal, LiteralRef};
 pub use crate::model::named_node::{NamedNode, NamedNodeRef};
 pub use crate::model::parser::TermParseError;
 pub use crate::model::triple::{
-    GraphName, GraphNameRef, NamedOrBlankNode, NamedOrBlankNodeRef, Quad, QuadRef, Term, TermRef,
-    Triple, TripleRef,
+    GraphName, GraphNameRef, NamedOrBlankNode, NamedOrBlankNodeRef, Quad, QuadRef, Subject,
+    SubjectRef, Term, TermRef, Triple, TripleRef,
 };
 pub use oxilangtag::LanguageTagParseError;
 pub use oxiri::IriParseError;
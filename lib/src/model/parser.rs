@@ -1,7 +1,7 @@
 use crate::model::blank_node::{BlankNode, BlankNodeIdParseError};
 use crate::model::named_node::NamedNode;
 use crate::model::vocab::xsd;
-use crate::model::{Literal, Term};
+use crate::model::{Literal, Subject, Term, Triple};
 use crate::sparql::{Variable, VariableNameParseError};
 use oxilangtag::LanguageTagParseError;
 use oxiri::IriParseError;
@@ -229,14 +229,32 @@ impl FromStr for Term {
 
     /// Parses a term from its NTriples or Turtle serialization
     ///
+    /// RDF-star quoted triples are also supported.
+    ///
     /// ```
-    /// use oxigraph::model::{Literal, Term};
+    /// use oxigraph::model::{Literal, NamedNode, Term, Triple};
     /// use std::str::FromStr;
     ///
-    /// assert_eq!(Term::from_str("\"ex\"").unwrap(), Literal::new_simple_literal("ex").into())
+    /// assert_eq!(Term::from_str("\"ex\"").unwrap(), Literal::new_simple_literal("ex").into());
+    /// assert_eq!(
+    ///     Term::from_str("<< <http://example.com/s> <http://example.com/p> \"o\" >>").unwrap(),
+    ///     Triple::new(
+    ///         NamedNode::new("http://example.com/s").unwrap(),
+    ///         NamedNode::new("http://example.com/p").unwrap(),
+    ///         Literal::new_simple_literal("o")
+    ///     ).into()
+    /// );
     /// ```
     fn from_str(s: &str) -> Result<Self, TermParseError> {
-        Ok(if s.starts_with('<') {
+        Ok(if s.starts_with("<<") {
+            let (triple, remain) = read_quoted_triple(s)?;
+            if !remain.trim().is_empty() {
+                return Err(TermParseError::msg(
+                    "Unexpected characters after a quoted triple",
+                ));
+            }
+            triple.into()
+        } else if s.starts_with('<') {
             NamedNode::from_str(s)?.into()
         } else if s.starts_with('_') {
             BlankNode::from_str(s)?.into()
@@ -246,6 +264,77 @@ impl FromStr for Term {
     }
 }
 
+/// Reads a `<< s p o >>` quoted triple and returns the remaining input
+fn read_quoted_triple(s: &str) -> Result<(Triple, &str), TermParseError> {
+    let s = s
+        .strip_prefix("<<")
+        .ok_or_else(|| TermParseError::msg("Quoted triples should start with <<"))?;
+    let (subject, s) = read_term(s.trim_start())?;
+    let subject = match subject {
+        Term::NamedNode(node) => Subject::NamedNode(node),
+        Term::BlankNode(node) => Subject::BlankNode(node),
+        Term::Triple(triple) => Subject::Triple(triple),
+        Term::Literal(_) => {
+            return Err(TermParseError::msg(
+                "Literals are not allowed as triple subjects",
+            ))
+        }
+    };
+    let (predicate, s) = read_term(s.trim_start())?;
+    let predicate = if let Term::NamedNode(predicate) = predicate {
+        predicate
+    } else {
+        return Err(TermParseError::msg("Triple predicates should be IRIs"));
+    };
+    let (object, s) = read_term(s.trim_start())?;
+    let s = s
+        .trim_start()
+        .strip_prefix(">>")
+        .ok_or_else(|| TermParseError::msg("Quoted triples should end with >>"))?;
+    Ok((Triple::new(subject, predicate, object), s))
+}
+
+/// Reads the term at the beginning of the input and returns the remaining input
+fn read_term(s: &str) -> Result<(Term, &str), TermParseError> {
+    if s.starts_with("<<") {
+        let (triple, remain) = read_quoted_triple(s)?;
+        return Ok((triple.into(), remain));
+    }
+    let end = if s.starts_with('<') {
+        s.find('>')
+            .map(|i| i + 1)
+            .ok_or_else(|| TermParseError::msg("Named node serialization should end with >"))?
+    } else if s.starts_with('"') {
+        let mut chars = s.char_indices().skip(1);
+        let mut end = None;
+        while let Some((i, c)) = chars.next() {
+            if c == '\\' {
+                chars.next();
+            } else if c == '"' {
+                end = Some(i + 1);
+                break;
+            }
+        }
+        let end = end.ok_or_else(|| TermParseError::msg("Unexpected literal end"))?;
+        if s[end..].starts_with("^^<") {
+            s[end..]
+                .find('>')
+                .map(|i| end + i + 1)
+                .ok_or_else(|| TermParseError::msg("Named node serialization should end with >"))?
+        } else if s[end..].starts_with('@') {
+            s[end + 1..]
+                .find(|c: char| !c.is_ascii_alphanumeric() && c != '-')
+                .map_or(s.len(), |i| end + 1 + i)
+        } else {
+            end
+        }
+    } else {
+        s.find(|c: char| c.is_whitespace() || c == '>')
+            .unwrap_or(s.len())
+    };
+    Ok((Term::from_str(&s[..end])?, &s[end..]))
+}
+
 impl FromStr for Variable {
     type Err = TermParseError;
 
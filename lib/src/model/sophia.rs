@@ -256,6 +256,8 @@ impl<'a> TTerm for NamedOrBlankNodeRef<'a> {
     }
 }
 
+/// Sophia does not support RDF-star: the methods panic if the term is a quoted triple.
+/// The stores never expose quoted triples through the Sophia API, the quads containing them are skipped.
 impl TTerm for Term {
     fn kind(&self) -> TermKind {
         use Term::*;
@@ -263,6 +265,7 @@ impl TTerm for Term {
             NamedNode(_) => TermKind::Iri,
             BlankNode(_) => TermKind::BlankNode,
             Literal(_) => TermKind::Literal,
+            Triple(_) => quoted_triples_unsupported(),
         }
     }
 
@@ -272,6 +275,7 @@ impl TTerm for Term {
             NamedNode(n) => n.value_raw(),
             BlankNode(n) => n.value_raw(),
             Literal(l) => l.value_raw(),
+            Triple(_) => quoted_triples_unsupported(),
         }
     }
 
@@ -297,6 +301,7 @@ impl TTerm for Term {
             NamedNode(n) => n.as_dyn(),
             BlankNode(n) => n.as_dyn(),
             Literal(l) => l.as_dyn(),
+            Triple(_) => quoted_triples_unsupported(),
         }
     }
 }
@@ -317,6 +322,8 @@ impl TryCopyTerm for Term {
     }
 }
 
+/// Sophia does not support RDF-star: the methods panic if the term is a quoted triple.
+/// The stores never expose quoted triples through the Sophia API, the quads containing them are skipped.
 impl<'a> TTerm for TermRef<'a> {
     fn kind(&self) -> TermKind {
         use TermRef::*;
@@ -324,6 +331,7 @@ impl<'a> TTerm for TermRef<'a> {
             NamedNode(_) => TermKind::Iri,
             BlankNode(_) => TermKind::BlankNode,
             Literal(_) => TermKind::Literal,
+            Triple(_) => quoted_triples_unsupported(),
         }
     }
 
@@ -333,6 +341,7 @@ impl<'a> TTerm for TermRef<'a> {
             NamedNode(n) => n.value_raw(),
             BlankNode(n) => n.value_raw(),
             Literal(l) => l.value_raw(),
+            Triple(_) => quoted_triples_unsupported(),
         }
     }
 
@@ -358,6 +367,7 @@ impl<'a> TTerm for TermRef<'a> {
             NamedNode(n) => n.as_dyn(),
             BlankNode(n) => n.as_dyn(),
             Literal(l) => l.as_dyn(),
+            Triple(_) => quoted_triples_unsupported(),
         }
     }
 }
@@ -392,6 +402,10 @@ impl<'a> From<TripleRef<'a>> for [TermRef<'a>; 3] {
     }
 }
 
+fn quoted_triples_unsupported() -> ! {
+    panic!("Sophia does not support RDF-star quoted triples")
+}
+
 /// Error raised when trying to copy a [Sophia](sophia)
 /// term as an incompatible Oxigraph term
 /// (e.g. a literal into `NamedNode`).
@@ -108,6 +108,33 @@ pub mod rdfs {
         NamedNodeRef::new_unchecked("http://www.w3.org/2000/01/rdf-schema#subPropertyOf");
 }
 
+pub mod owl {
+    //! [OWL 2](https://www.w3.org/TR/owl2-syntax/) vocabulary subset used by the OWL 2 RL reasoner
+    use crate::model::named_node::NamedNodeRef;
+
+    /// The subject and object classes have the same instances.
+    pub const EQUIVALENT_CLASS: NamedNodeRef<'_> =
+        NamedNodeRef::new_unchecked("http://www.w3.org/2002/07/owl#equivalentClass");
+    /// The subject and object properties have the same extensions.
+    pub const EQUIVALENT_PROPERTY: NamedNodeRef<'_> =
+        NamedNodeRef::new_unchecked("http://www.w3.org/2002/07/owl#equivalentProperty");
+    /// The subject property is the inverse of the object property.
+    pub const INVERSE_OF: NamedNodeRef<'_> =
+        NamedNodeRef::new_unchecked("http://www.w3.org/2002/07/owl#inverseOf");
+    /// The subject property is implied by the chain of the properties of the object list.
+    pub const PROPERTY_CHAIN_AXIOM: NamedNodeRef<'_> =
+        NamedNodeRef::new_unchecked("http://www.w3.org/2002/07/owl#propertyChainAxiom");
+    /// The subject and object individuals are the same.
+    pub const SAME_AS: NamedNodeRef<'_> =
+        NamedNodeRef::new_unchecked("http://www.w3.org/2002/07/owl#sameAs");
+    /// The class of symmetric properties.
+    pub const SYMMETRIC_PROPERTY: NamedNodeRef<'_> =
+        NamedNodeRef::new_unchecked("http://www.w3.org/2002/07/owl#SymmetricProperty");
+    /// The class of transitive properties.
+    pub const TRANSITIVE_PROPERTY: NamedNodeRef<'_> =
+        NamedNodeRef::new_unchecked("http://www.w3.org/2002/07/owl#TransitiveProperty");
+}
+
 pub mod xsd {
     //! [RDF compatible XSD datatypes](https://www.w3.org/TR/rdf11-concepts/#dfn-rdf-compatible-xsd-types)
     use crate::model::named_node::NamedNodeRef;
@@ -230,3 +257,27 @@ pub mod xsd {
     pub const YEAR_MONTH_DURATION: NamedNodeRef<'_> =
         NamedNodeRef::new_unchecked("http://www.w3.org/2001/XMLSchema#yearMonthDuration");
 }
+
+pub mod geo {
+    //! [GeoSPARQL](https://www.ogc.org/standards/geosparql) vocabulary
+    use crate::model::named_node::NamedNodeRef;
+
+    /// The class of geometries.
+    pub const GEOMETRY: NamedNodeRef<'_> =
+        NamedNodeRef::new_unchecked("http://www.opengis.net/ont/geosparql#Geometry");
+    /// Links a feature to its default geometry.
+    pub const HAS_GEOMETRY: NamedNodeRef<'_> =
+        NamedNodeRef::new_unchecked("http://www.opengis.net/ont/geosparql#hasGeometry");
+    /// Links a geometry to its WKT serialization.
+    pub const AS_WKT: NamedNodeRef<'_> =
+        NamedNodeRef::new_unchecked("http://www.opengis.net/ont/geosparql#asWKT");
+    /// Links a geometry to its GeoJSON serialization.
+    pub const AS_GEO_JSON: NamedNodeRef<'_> =
+        NamedNodeRef::new_unchecked("http://www.opengis.net/ont/geosparql#asGeoJSON");
+    /// Geometries serialized using WKT.
+    pub const WKT_LITERAL: NamedNodeRef<'_> =
+        NamedNodeRef::new_unchecked("http://www.opengis.net/ont/geosparql#wktLiteral");
+    /// Geometries serialized using GeoJSON.
+    pub const GEO_JSON_LITERAL: NamedNodeRef<'_> =
+        NamedNodeRef::new_unchecked("http://www.opengis.net/ont/geosparql#geoJSONLiteral");
+}
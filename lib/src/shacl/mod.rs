@@ -0,0 +1,493 @@
+//! [SHACL](https://www.w3.org/TR/shacl/) validation.
+//!
+//! The [SHACL Core](https://www.w3.org/TR/shacl/#core-components) constraint components
+//! and the [SHACL-SPARQL](https://www.w3.org/TR/shacl/#sparql-constraints) `sh:sparql` constraints based on `sh:select` are supported.
+//!
+//! Usage example:
+//! ```
+//! use oxigraph::MemoryStore;
+//! use oxigraph::io::GraphFormat;
+//! use oxigraph::model::*;
+//! use oxigraph::shacl::ShaclValidator;
+//!
+//! let shapes = MemoryStore::new();
+//! shapes.load_graph(br#"
+//!     @prefix sh: <http://www.w3.org/ns/shacl#> .
+//!     @prefix ex: <http://example.com/> .
+//!     ex:PersonShape a sh:NodeShape ;
+//!         sh:targetClass ex:Person ;
+//!         sh:property [ sh:path ex:name ; sh:minCount 1 ] .
+//! "#.as_ref(), GraphFormat::Turtle, &GraphName::DefaultGraph, None)?;
+//! let validator = ShaclValidator::new(shapes.iter().map(Triple::from))?;
+//!
+//! let data = MemoryStore::new();
+//! data.load_graph(br#"
+//!     @prefix ex: <http://example.com/> .
+//!     ex:alice a ex:Person ; ex:name "Alice" .
+//!     ex:bob a ex:Person .
+//! "#.as_ref(), GraphFormat::Turtle, &GraphName::DefaultGraph, None)?;
+//!
+//! let report = validator.validate(data.iter().map(Triple::from))?;
+//! assert!(!report.conforms());
+//! assert_eq!(report.results()[0].focus_node(), &NamedNode::new("http://example.com/bob")?.into());
+//!
+//! // The report as a sh:ValidationReport graph
+//! let report_graph = report.to_graph();
+//! assert!(report_graph.iter().any(|t| t.object == Literal::from(false).into()));
+//! # Result::<_,Box<dyn std::error::Error>>::Ok(())
+//! ```
+
+mod report;
+mod shape;
+mod validation;
+
+use crate::model::*;
+pub use crate::shacl::report::{ValidationReport, ValidationResult};
+use crate::shacl::shape::Shapes;
+use crate::shacl::validation::Validator;
+use crate::sparql::EvaluationError;
+use crate::store::MemoryStore;
+use std::error::Error;
+use std::{fmt, io};
+
+/// A SHACL validator built from a shapes graph.
+///
+/// It is cheap to reuse for several validations.
+pub struct ShaclValidator {
+    shapes: Shapes,
+}
+
+impl ShaclValidator {
+    /// Builds a validator from the triples of a shapes graph.
+    pub fn new(shapes: impl IntoIterator<Item = Triple>) -> Result<Self, ShaclError> {
+        Ok(Self {
+            shapes: Shapes::new(&Graph::new(shapes))?,
+        })
+    }
+
+    /// Validates the triples of a data graph.
+    pub fn validate(
+        &self,
+        data: impl IntoIterator<Item = Triple>,
+    ) -> Result<ValidationReport, ShaclError> {
+        Validator::new(&self.shapes, Graph::new(data)).validate()
+    }
+
+    /// Validates the triples of a data graph and returns an error if it does not conform to the shapes.
+    pub(crate) fn check(&self, data: impl IntoIterator<Item = Triple>) -> Result<(), ShaclError> {
+        let report = self.validate(data)?;
+        if report.conforms() {
+            Ok(())
+        } else {
+            Err(ShaclError::NotConforming(Box::new(report)))
+        }
+    }
+}
+
+/// An error raised by the SHACL validation.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ShaclError {
+    /// The shapes graph is not well-formed
+    InvalidShapes(String),
+    /// A SHACL-SPARQL query evaluation failed
+    Evaluation(EvaluationError),
+    /// The data does not conform to the shapes, returned when a write is rejected
+    NotConforming(Box<ValidationReport>),
+}
+
+impl fmt::Display for ShaclError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidShapes(msg) => write!(f, "Invalid SHACL shapes graph: {}", msg),
+            Self::Evaluation(error) => error.fmt(f),
+            Self::NotConforming(report) => {
+                write!(
+                    f,
+                    "The data does not conform to the SHACL shapes: {}",
+                    report
+                )
+            }
+        }
+    }
+}
+
+impl Error for ShaclError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Evaluation(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<EvaluationError> for ShaclError {
+    fn from(error: EvaluationError) -> Self {
+        Self::Evaluation(error)
+    }
+}
+
+impl From<ShaclError> for io::Error {
+    fn from(error: ShaclError) -> Self {
+        match error {
+            ShaclError::Evaluation(EvaluationError::Io(error)) => error,
+            ShaclError::InvalidShapes(_) => Self::new(io::ErrorKind::InvalidInput, error),
+            _ => Self::new(io::ErrorKind::InvalidData, error),
+        }
+    }
+}
+
+/// A graph loaded in memory with the lookups used by the validation
+struct Graph {
+    store: MemoryStore,
+}
+
+impl Graph {
+    fn new(triples: impl IntoIterator<Item = Triple>) -> Self {
+        let store = MemoryStore::new();
+        for triple in triples {
+            store.insert(triple.in_graph(GraphName::DefaultGraph));
+        }
+        Self { store }
+    }
+
+    fn triples_for_pattern(
+        &self,
+        subject: Option<&Term>,
+        predicate: Option<NamedNodeRef<'_>>,
+        object: Option<&Term>,
+    ) -> impl Iterator<Item = Triple> {
+        let subject = match subject {
+            Some(subject) => match as_subject(subject) {
+                Some(subject) => Some(subject),
+                None => return Vec::new().into_iter(),
+            },
+            None => None,
+        };
+        self.store
+            .quads_for_pattern(
+                subject,
+                predicate,
+                object.map(Term::as_ref),
+                Some(GraphNameRef::DefaultGraph),
+            )
+            .map(Triple::from)
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    fn objects(&self, subject: &Term, predicate: NamedNodeRef<'_>) -> Vec<Term> {
+        self.triples_for_pattern(Some(subject), Some(predicate), None)
+            .map(|t| t.object)
+            .collect()
+    }
+
+    fn object(&self, subject: &Term, predicate: NamedNodeRef<'_>) -> Option<Term> {
+        self.triples_for_pattern(Some(subject), Some(predicate), None)
+            .map(|t| t.object)
+            .next()
+    }
+
+    fn subjects(&self, predicate: NamedNodeRef<'_>, object: &Term) -> Vec<Term> {
+        self.triples_for_pattern(None, Some(predicate), Some(object))
+            .map(|t| t.subject.into())
+            .collect()
+    }
+
+    fn contains(&self, subject: &Term, predicate: NamedNodeRef<'_>, object: &Term) -> bool {
+        self.triples_for_pattern(Some(subject), Some(predicate), Some(object))
+            .next()
+            .is_some()
+    }
+
+    /// Reads a RDF list, stopping at the first malformed node
+    fn list(&self, mut node: Term) -> Vec<Term> {
+        let mut elements = Vec::new();
+        let mut visited = Vec::new();
+        while node != vocab::rdf::NIL.into() && !visited.contains(&node) {
+            match (
+                self.object(&node, vocab::rdf::FIRST),
+                self.object(&node, vocab::rdf::REST),
+            ) {
+                (Some(first), Some(rest)) => {
+                    elements.push(first);
+                    visited.push(node);
+                    node = rest;
+                }
+                _ => break,
+            }
+        }
+        elements
+    }
+}
+
+fn as_subject(term: &Term) -> Option<SubjectRef<'_>> {
+    match term {
+        Term::NamedNode(node) => Some(node.into()),
+        Term::BlankNode(node) => Some(node.into()),
+        Term::Triple(triple) => Some(SubjectRef::Triple(triple)),
+        Term::Literal(_) => None,
+    }
+}
+
+/// The SHACL vocabulary
+mod sh {
+    use crate::model::NamedNodeRef;
+
+    macro_rules! sh_terms {
+        ($($name:ident = $local:literal,)*) => {
+            $(pub const $name: NamedNodeRef<'_> =
+                NamedNodeRef::new_unchecked(concat!("http://www.w3.org/ns/shacl#", $local));)*
+        };
+    }
+
+    sh_terms! {
+        ALTERNATIVE_PATH = "alternativePath",
+        AND = "and",
+        AND_CONSTRAINT_COMPONENT = "AndConstraintComponent",
+        BLANK_NODE = "BlankNode",
+        BLANK_NODE_OR_IRI = "BlankNodeOrIRI",
+        BLANK_NODE_OR_LITERAL = "BlankNodeOrLiteral",
+        CLASS = "class",
+        CLASS_CONSTRAINT_COMPONENT = "ClassConstraintComponent",
+        CLOSED = "closed",
+        CLOSED_CONSTRAINT_COMPONENT = "ClosedConstraintComponent",
+        CONFORMS = "conforms",
+        DATATYPE = "datatype",
+        DATATYPE_CONSTRAINT_COMPONENT = "DatatypeConstraintComponent",
+        DEACTIVATED = "deactivated",
+        DECLARE = "declare",
+        DISJOINT = "disjoint",
+        DISJOINT_CONSTRAINT_COMPONENT = "DisjointConstraintComponent",
+        EQUALS = "equals",
+        EQUALS_CONSTRAINT_COMPONENT = "EqualsConstraintComponent",
+        FOCUS_NODE = "focusNode",
+        FLAGS = "flags",
+        HAS_VALUE = "hasValue",
+        HAS_VALUE_CONSTRAINT_COMPONENT = "HasValueConstraintComponent",
+        IGNORED_PROPERTIES = "ignoredProperties",
+        IN = "in",
+        IN_CONSTRAINT_COMPONENT = "InConstraintComponent",
+        INVERSE_PATH = "inversePath",
+        IRI = "IRI",
+        IRI_OR_LITERAL = "IRIOrLiteral",
+        LANGUAGE_IN = "languageIn",
+        LANGUAGE_IN_CONSTRAINT_COMPONENT = "LanguageInConstraintComponent",
+        LESS_THAN = "lessThan",
+        LESS_THAN_CONSTRAINT_COMPONENT = "LessThanConstraintComponent",
+        LESS_THAN_OR_EQUALS = "lessThanOrEquals",
+        LESS_THAN_OR_EQUALS_CONSTRAINT_COMPONENT = "LessThanOrEqualsConstraintComponent",
+        LITERAL = "Literal",
+        MAX_COUNT = "maxCount",
+        MAX_COUNT_CONSTRAINT_COMPONENT = "MaxCountConstraintComponent",
+        MAX_EXCLUSIVE = "maxExclusive",
+        MAX_EXCLUSIVE_CONSTRAINT_COMPONENT = "MaxExclusiveConstraintComponent",
+        MAX_INCLUSIVE = "maxInclusive",
+        MAX_INCLUSIVE_CONSTRAINT_COMPONENT = "MaxInclusiveConstraintComponent",
+        MAX_LENGTH = "maxLength",
+        MAX_LENGTH_CONSTRAINT_COMPONENT = "MaxLengthConstraintComponent",
+        MESSAGE = "message",
+        MIN_COUNT = "minCount",
+        MIN_COUNT_CONSTRAINT_COMPONENT = "MinCountConstraintComponent",
+        MIN_EXCLUSIVE = "minExclusive",
+        MIN_EXCLUSIVE_CONSTRAINT_COMPONENT = "MinExclusiveConstraintComponent",
+        MIN_INCLUSIVE = "minInclusive",
+        MIN_INCLUSIVE_CONSTRAINT_COMPONENT = "MinInclusiveConstraintComponent",
+        MIN_LENGTH = "minLength",
+        MIN_LENGTH_CONSTRAINT_COMPONENT = "MinLengthConstraintComponent",
+        NAMESPACE = "namespace",
+        NODE = "node",
+        NODE_CONSTRAINT_COMPONENT = "NodeConstraintComponent",
+        NODE_KIND = "nodeKind",
+        NODE_KIND_CONSTRAINT_COMPONENT = "NodeKindConstraintComponent",
+        NODE_SHAPE = "NodeShape",
+        NOT = "not",
+        NOT_CONSTRAINT_COMPONENT = "NotConstraintComponent",
+        ONE_OR_MORE_PATH = "oneOrMorePath",
+        OR = "or",
+        OR_CONSTRAINT_COMPONENT = "OrConstraintComponent",
+        PATH = "path",
+        PATTERN = "pattern",
+        PATTERN_CONSTRAINT_COMPONENT = "PatternConstraintComponent",
+        PREFIX = "prefix",
+        PREFIXES = "prefixes",
+        PROPERTY = "property",
+        PROPERTY_SHAPE = "PropertyShape",
+        QUALIFIED_MAX_COUNT = "qualifiedMaxCount",
+        QUALIFIED_MAX_COUNT_CONSTRAINT_COMPONENT = "QualifiedMaxCountConstraintComponent",
+        QUALIFIED_MIN_COUNT = "qualifiedMinCount",
+        QUALIFIED_MIN_COUNT_CONSTRAINT_COMPONENT = "QualifiedMinCountConstraintComponent",
+        QUALIFIED_VALUE_SHAPE = "qualifiedValueShape",
+        RESULT = "result",
+        RESULT_MESSAGE = "resultMessage",
+        RESULT_PATH = "resultPath",
+        RESULT_SEVERITY = "resultSeverity",
+        SELECT = "select",
+        SEVERITY = "severity",
+        SOURCE_CONSTRAINT = "sourceConstraint",
+        SOURCE_CONSTRAINT_COMPONENT = "sourceConstraintComponent",
+        SOURCE_SHAPE = "sourceShape",
+        SPARQL = "sparql",
+        SPARQL_CONSTRAINT_COMPONENT = "SPARQLConstraintComponent",
+        TARGET_CLASS = "targetClass",
+        TARGET_NODE = "targetNode",
+        TARGET_OBJECTS_OF = "targetObjectsOf",
+        TARGET_SUBJECTS_OF = "targetSubjectsOf",
+        UNIQUE_LANG = "uniqueLang",
+        UNIQUE_LANG_CONSTRAINT_COMPONENT = "UniqueLangConstraintComponent",
+        VALIDATION_REPORT = "ValidationReport",
+        VALIDATION_RESULT = "ValidationResult",
+        VALUE = "value",
+        VIOLATION = "Violation",
+        XONE = "xone",
+        XONE_CONSTRAINT_COMPONENT = "XoneConstraintComponent",
+        ZERO_OR_MORE_PATH = "zeroOrMorePath",
+        ZERO_OR_ONE_PATH = "zeroOrOnePath",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::GraphFormat;
+    use crate::model::vocab::rdf;
+    use std::io::Cursor;
+
+    const SHAPES: &str = r#"
+    @prefix sh: <http://www.w3.org/ns/shacl#> .
+    @prefix schema: <http://schema.org/> .
+    @prefix xsd: <http://www.w3.org/2001/XMLSchema#> .
+
+    schema:CityShape a sh:NodeShape ;
+        sh:targetClass schema:City ;
+        sh:property [
+            sh:path schema:name ;
+            sh:minCount 1 ;
+            sh:languageIn ( "fr" "en" )
+        ] , [
+            sh:path schema:population ;
+            sh:maxCount 1 ;
+            sh:datatype xsd:integer ;
+            sh:minInclusive 0
+        ] , [
+            sh:path ( schema:country schema:name ) ;
+            sh:minCount 1 ;
+            sh:message "A city should be in a named country"@en
+        ] ;
+        sh:sparql [
+            sh:select """
+                SELECT $this ?value WHERE {
+                    $this <http://schema.org/name> ?value .
+                    FILTER(STRLEN(?value) > 20)
+                }
+            """ ;
+            sh:message "The name {?value} is too long"
+        ] .
+    "#;
+
+    const DATA: &str = r#"
+    @prefix schema: <http://schema.org/> .
+    @prefix wd: <http://www.wikidata.org/entity/> .
+
+    wd:Q90 a schema:City ;
+        schema:name "Paris"@fr ;
+        schema:country wd:Q142 ;
+        schema:population 2000000 .
+    "#;
+
+    fn validator() -> Result<ShaclValidator, Box<dyn Error>> {
+        let shapes = MemoryStore::new();
+        shapes.load_graph(
+            Cursor::new(SHAPES),
+            GraphFormat::Turtle,
+            GraphNameRef::DefaultGraph,
+            None,
+        )?;
+        Ok(ShaclValidator::new(shapes.iter().map(Triple::from))?)
+    }
+
+    #[test]
+    fn test_validate() -> Result<(), Box<dyn Error>> {
+        let validator = validator()?;
+        let store = MemoryStore::new();
+        store.load_graph(
+            Cursor::new(DATA),
+            GraphFormat::Turtle,
+            GraphNameRef::DefaultGraph,
+            None,
+        )?;
+        let report = validator.validate(store.iter().map(Triple::from))?;
+        let paris = NamedNode::new("http://www.wikidata.org/entity/Q90")?;
+        assert_eq!(report.results().len(), 1);
+        let result = &report.results()[0];
+        assert_eq!(result.focus_node(), &paris.clone().into());
+        assert_eq!(
+            result.source_constraint_component().as_str(),
+            "http://www.w3.org/ns/shacl#MinCountConstraintComponent"
+        );
+        assert_eq!(
+            result.messages(),
+            &[LiteralRef::new_language_tagged_literal_unchecked(
+                "A city should be in a named country",
+                "en"
+            )
+            .into_owned()
+            .into()]
+        );
+        assert!(report
+            .to_graph()
+            .iter()
+            .any(|t| t.predicate.as_str() == "http://www.w3.org/ns/shacl#resultPath"));
+
+        // A SHACL-SPARQL constraint violation
+        let report = validator.validate(vec![
+            Triple::new(
+                paris.clone(),
+                rdf::TYPE,
+                NamedNode::new("http://schema.org/City")?,
+            ),
+            Triple::new(
+                paris,
+                NamedNode::new("http://schema.org/name")?,
+                Literal::new_language_tagged_literal("Paris, capitale de la France", "fr")?,
+            ),
+        ])?;
+        assert!(report.results().iter().any(|r| r.messages()
+            == [
+                Literal::new_simple_literal("The name Paris, capitale de la France is too long")
+                    .into()
+            ]));
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_graph_with_validation() -> Result<(), Box<dyn Error>> {
+        let validator = validator()?;
+        let store = MemoryStore::new();
+        store.load_graph(
+            Cursor::new(DATA),
+            GraphFormat::Turtle,
+            GraphNameRef::DefaultGraph,
+            None,
+        )?;
+        let error = store
+            .load_graph_with_validation(
+                Cursor::new(
+                    r#"<http://www.wikidata.org/entity/Q90> <http://schema.org/population> "many" ."#,
+                ),
+                GraphFormat::NTriples,
+                GraphNameRef::DefaultGraph,
+                None,
+                &validator,
+            )
+            .unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert!(!store.contains(QuadRef::new(
+            NamedNodeRef::new("http://www.wikidata.org/entity/Q90")?,
+            NamedNodeRef::new("http://schema.org/population")?,
+            LiteralRef::new_simple_literal("many"),
+            GraphNameRef::DefaultGraph
+        )));
+        Ok(())
+    }
+}
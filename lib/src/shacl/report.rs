@@ -0,0 +1,182 @@
+use crate::model::vocab::rdf;
+use crate::model::*;
+use crate::shacl::sh;
+use std::fmt;
+
+/// A SHACL [validation report](https://www.w3.org/TR/shacl/#validation-report).
+#[derive(Debug, Clone)]
+pub struct ValidationReport {
+    results: Vec<ValidationResult>,
+}
+
+impl ValidationReport {
+    pub(super) fn new(results: Vec<ValidationResult>) -> Self {
+        Self { results }
+    }
+
+    /// Returns if the data graph conforms to the shapes graph i.e. if there are no validation results
+    pub fn conforms(&self) -> bool {
+        self.results.is_empty()
+    }
+
+    /// The validation results
+    pub fn results(&self) -> &[ValidationResult] {
+        &self.results
+    }
+
+    /// Returns the report as a graph using the SHACL validation report vocabulary
+    pub fn to_graph(&self) -> Vec<Triple> {
+        let mut triples = Vec::new();
+        let report = BlankNode::default();
+        triples.push(Triple::new(
+            report.clone(),
+            rdf::TYPE,
+            sh::VALIDATION_REPORT,
+        ));
+        triples.push(Triple::new(
+            report.clone(),
+            sh::CONFORMS,
+            Literal::from(self.conforms()),
+        ));
+        for result in &self.results {
+            let node = BlankNode::default();
+            triples.push(Triple::new(report.clone(), sh::RESULT, node.clone()));
+            triples.push(Triple::new(node.clone(), rdf::TYPE, sh::VALIDATION_RESULT));
+            triples.push(Triple::new(
+                node.clone(),
+                sh::FOCUS_NODE,
+                result.focus_node.clone(),
+            ));
+            if let Some(path) = &result.result_path {
+                triples.push(Triple::new(node.clone(), sh::RESULT_PATH, path.clone()));
+                triples.extend(result.path_triples.iter().cloned());
+            }
+            if let Some(value) = &result.value {
+                triples.push(Triple::new(node.clone(), sh::VALUE, value.clone()));
+            }
+            triples.push(Triple::new(
+                node.clone(),
+                sh::SOURCE_SHAPE,
+                result.source_shape.clone(),
+            ));
+            triples.push(Triple::new(
+                node.clone(),
+                sh::SOURCE_CONSTRAINT_COMPONENT,
+                result.source_constraint_component.clone(),
+            ));
+            if let Some(constraint) = &result.source_constraint {
+                triples.push(Triple::new(
+                    node.clone(),
+                    sh::SOURCE_CONSTRAINT,
+                    constraint.clone(),
+                ));
+            }
+            triples.push(Triple::new(
+                node.clone(),
+                sh::RESULT_SEVERITY,
+                result.severity.clone(),
+            ));
+            for message in &result.messages {
+                triples.push(Triple::new(
+                    node.clone(),
+                    sh::RESULT_MESSAGE,
+                    message.clone(),
+                ));
+            }
+        }
+        triples
+    }
+}
+
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.conforms() {
+            return write!(f, "the data conforms to the shapes");
+        }
+        for (i, result) in self.results.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            result.fmt(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// A SHACL [validation result](https://www.w3.org/TR/shacl/#results-validation-result).
+#[derive(Debug, Clone)]
+pub struct ValidationResult {
+    pub(super) focus_node: Term,
+    pub(super) result_path: Option<Term>,
+    pub(super) path_triples: Vec<Triple>,
+    pub(super) value: Option<Term>,
+    pub(super) source_shape: Term,
+    pub(super) source_constraint_component: NamedNode,
+    pub(super) source_constraint: Option<Term>,
+    pub(super) severity: NamedNode,
+    pub(super) messages: Vec<Term>,
+}
+
+impl ValidationResult {
+    /// The focus node that has caused the result (`sh:focusNode`)
+    pub fn focus_node(&self) -> &Term {
+        &self.focus_node
+    }
+
+    /// The path of the property shape that has caused the result (`sh:resultPath`)
+    ///
+    /// Complex paths are blank nodes described in the graph returned by [`ValidationReport::to_graph`].
+    pub fn result_path(&self) -> Option<&Term> {
+        self.result_path.as_ref()
+    }
+
+    /// The value node that has violated the constraint (`sh:value`)
+    pub fn value(&self) -> Option<&Term> {
+        self.value.as_ref()
+    }
+
+    /// The shape that the focus node was validated against (`sh:sourceShape`)
+    pub fn source_shape(&self) -> &Term {
+        &self.source_shape
+    }
+
+    /// The constraint component that has caused the result (`sh:sourceConstraintComponent`)
+    pub fn source_constraint_component(&self) -> NamedNodeRef<'_> {
+        self.source_constraint_component.as_ref()
+    }
+
+    /// The SHACL-SPARQL constraint that has caused the result if any (`sh:sourceConstraint`)
+    pub fn source_constraint(&self) -> Option<&Term> {
+        self.source_constraint.as_ref()
+    }
+
+    /// The severity of the result i.e. `sh:Violation`, `sh:Warning` or `sh:Info` (`sh:resultSeverity`)
+    pub fn severity(&self) -> NamedNodeRef<'_> {
+        self.severity.as_ref()
+    }
+
+    /// The messages of the result (`sh:resultMessage`)
+    pub fn messages(&self) -> &[Term] {
+        &self.messages
+    }
+}
+
+impl fmt::Display for ValidationResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} on focus node {} from shape {}: {}",
+            self.severity, self.focus_node, self.source_shape, self.source_constraint_component
+        )?;
+        if let Some(path) = &self.result_path {
+            write!(f, " with path {}", path)?;
+        }
+        if let Some(value) = &self.value {
+            write!(f, " with value {}", value)?;
+        }
+        for message in &self.messages {
+            write!(f, " ({})", message)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,679 @@
+use crate::model::vocab::{rdf, rdfs};
+use crate::model::*;
+use crate::shacl::{sh, Graph, ShaclError};
+use regex::{Regex, RegexBuilder};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+const REGEX_SIZE_LIMIT: usize = 1_000_000;
+
+/// All the shapes of a shapes graph
+pub(super) struct Shapes {
+    pub shapes: Vec<Shape>,
+}
+
+pub(super) struct Shape {
+    pub id: Term,
+    pub path: Option<Path>,
+    pub targets: Vec<Target>,
+    pub constraints: Vec<Constraint>,
+    pub deactivated: bool,
+    pub severity: NamedNode,
+    pub messages: Vec<Term>,
+}
+
+pub(super) enum Target {
+    Node(Term),
+    Class(Term),
+    SubjectsOf(NamedNode),
+    ObjectsOf(NamedNode),
+}
+
+/// A [SHACL property path](https://www.w3.org/TR/shacl/#property-paths)
+#[derive(Clone)]
+pub(super) enum Path {
+    Predicate(NamedNode),
+    Inverse(Box<Path>),
+    Sequence(Vec<Path>),
+    Alternative(Vec<Path>),
+    ZeroOrMore(Box<Path>),
+    OneOrMore(Box<Path>),
+    ZeroOrOne(Box<Path>),
+}
+
+impl Path {
+    /// Serializes the path as a SHACL path graph and returns its root node
+    pub fn to_graph(&self, triples: &mut Vec<Triple>) -> Term {
+        match self {
+            Self::Predicate(p) => p.clone().into(),
+            Self::Inverse(p) => Self::wrap(sh::INVERSE_PATH, p, triples),
+            Self::Sequence(elements) => Self::list(elements, triples),
+            Self::Alternative(elements) => {
+                let list = Self::list(elements, triples);
+                let node = BlankNode::default();
+                triples.push(Triple::new(node.clone(), sh::ALTERNATIVE_PATH, list));
+                node.into()
+            }
+            Self::ZeroOrMore(p) => Self::wrap(sh::ZERO_OR_MORE_PATH, p, triples),
+            Self::OneOrMore(p) => Self::wrap(sh::ONE_OR_MORE_PATH, p, triples),
+            Self::ZeroOrOne(p) => Self::wrap(sh::ZERO_OR_ONE_PATH, p, triples),
+        }
+    }
+
+    fn wrap(predicate: NamedNodeRef<'_>, path: &Self, triples: &mut Vec<Triple>) -> Term {
+        let object = path.to_graph(triples);
+        let node = BlankNode::default();
+        triples.push(Triple::new(node.clone(), predicate, object));
+        node.into()
+    }
+
+    fn list(elements: &[Self], triples: &mut Vec<Triple>) -> Term {
+        let mut list = Term::from(rdf::NIL);
+        for element in elements.iter().rev() {
+            let value = element.to_graph(triples);
+            let node = BlankNode::default();
+            triples.push(Triple::new(node.clone(), rdf::FIRST, value));
+            triples.push(Triple::new(node.clone(), rdf::REST, list));
+            list = node.into();
+        }
+        list
+    }
+}
+
+impl fmt::Display for Path {
+    /// Formats the path using the SPARQL property path syntax
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Predicate(p) => p.fmt(f),
+            Self::Inverse(p) => write!(f, "^({})", p),
+            Self::Sequence(elements) => {
+                write!(f, "(")?;
+                for (i, element) in elements.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " / ")?;
+                    }
+                    element.fmt(f)?;
+                }
+                write!(f, ")")
+            }
+            Self::Alternative(elements) => {
+                write!(f, "(")?;
+                for (i, element) in elements.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " | ")?;
+                    }
+                    element.fmt(f)?;
+                }
+                write!(f, ")")
+            }
+            Self::ZeroOrMore(p) => write!(f, "({})*", p),
+            Self::OneOrMore(p) => write!(f, "({})+", p),
+            Self::ZeroOrOne(p) => write!(f, "({})?", p),
+        }
+    }
+}
+
+pub(super) enum Constraint {
+    Class(Term),
+    Datatype(NamedNode),
+    NodeKind(NamedNode),
+    MinCount(usize),
+    MaxCount(usize),
+    /// A value range constraint: the SPARQL operator and if the value is on the left of it
+    Range {
+        bound: Term,
+        operator: &'static str,
+        value_first: bool,
+        component: NamedNodeRef<'static>,
+    },
+    MinLength(usize),
+    MaxLength(usize),
+    Pattern(Regex),
+    LanguageIn(Vec<String>),
+    UniqueLang,
+    Equals(NamedNode),
+    Disjoint(NamedNode),
+    LessThan(NamedNode),
+    LessThanOrEquals(NamedNode),
+    Not(usize),
+    And(Vec<usize>),
+    Or(Vec<usize>),
+    Xone(Vec<usize>),
+    Node(usize),
+    Property(usize),
+    QualifiedMinCount {
+        shape: usize,
+        count: usize,
+    },
+    QualifiedMaxCount {
+        shape: usize,
+        count: usize,
+    },
+    Closed(HashSet<NamedNode>),
+    HasValue(Term),
+    In(Vec<Term>),
+    Sparql(SparqlConstraint),
+}
+
+impl Constraint {
+    pub fn component(&self) -> NamedNodeRef<'static> {
+        match self {
+            Self::Class(_) => sh::CLASS_CONSTRAINT_COMPONENT,
+            Self::Datatype(_) => sh::DATATYPE_CONSTRAINT_COMPONENT,
+            Self::NodeKind(_) => sh::NODE_KIND_CONSTRAINT_COMPONENT,
+            Self::MinCount(_) => sh::MIN_COUNT_CONSTRAINT_COMPONENT,
+            Self::MaxCount(_) => sh::MAX_COUNT_CONSTRAINT_COMPONENT,
+            Self::Range { component, .. } => *component,
+            Self::MinLength(_) => sh::MIN_LENGTH_CONSTRAINT_COMPONENT,
+            Self::MaxLength(_) => sh::MAX_LENGTH_CONSTRAINT_COMPONENT,
+            Self::Pattern(_) => sh::PATTERN_CONSTRAINT_COMPONENT,
+            Self::LanguageIn(_) => sh::LANGUAGE_IN_CONSTRAINT_COMPONENT,
+            Self::UniqueLang => sh::UNIQUE_LANG_CONSTRAINT_COMPONENT,
+            Self::Equals(_) => sh::EQUALS_CONSTRAINT_COMPONENT,
+            Self::Disjoint(_) => sh::DISJOINT_CONSTRAINT_COMPONENT,
+            Self::LessThan(_) => sh::LESS_THAN_CONSTRAINT_COMPONENT,
+            Self::LessThanOrEquals(_) => sh::LESS_THAN_OR_EQUALS_CONSTRAINT_COMPONENT,
+            Self::Not(_) => sh::NOT_CONSTRAINT_COMPONENT,
+            Self::And(_) => sh::AND_CONSTRAINT_COMPONENT,
+            Self::Or(_) => sh::OR_CONSTRAINT_COMPONENT,
+            Self::Xone(_) => sh::XONE_CONSTRAINT_COMPONENT,
+            Self::Node(_) | Self::Property(_) => sh::NODE_CONSTRAINT_COMPONENT,
+            Self::QualifiedMinCount { .. } => sh::QUALIFIED_MIN_COUNT_CONSTRAINT_COMPONENT,
+            Self::QualifiedMaxCount { .. } => sh::QUALIFIED_MAX_COUNT_CONSTRAINT_COMPONENT,
+            Self::Closed(_) => sh::CLOSED_CONSTRAINT_COMPONENT,
+            Self::HasValue(_) => sh::HAS_VALUE_CONSTRAINT_COMPONENT,
+            Self::In(_) => sh::IN_CONSTRAINT_COMPONENT,
+            Self::Sparql(_) => sh::SPARQL_CONSTRAINT_COMPONENT,
+        }
+    }
+}
+
+/// A SHACL-SPARQL constraint based on a SELECT query
+pub(super) struct SparqlConstraint {
+    pub id: Term,
+    pub query: String,
+    pub messages: Vec<Term>,
+}
+
+impl Shapes {
+    pub fn new(graph: &Graph) -> Result<Self, ShaclError> {
+        let mut parser = ShapesParser {
+            graph,
+            shapes: Vec::new(),
+            ids: HashMap::new(),
+        };
+        for candidate in parser.candidates() {
+            parser.shape(&candidate)?;
+        }
+        Ok(Self {
+            shapes: parser
+                .shapes
+                .into_iter()
+                .map(|shape| shape.ok_or_else(|| invalid("unresolved shape")))
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+struct ShapesParser<'a> {
+    graph: &'a Graph,
+    shapes: Vec<Option<Shape>>,
+    ids: HashMap<Term, usize>,
+}
+
+impl ShapesParser<'_> {
+    /// The nodes that are shapes on their own
+    fn candidates(&self) -> Vec<Term> {
+        let mut candidates = Vec::new();
+        for class in &[sh::NODE_SHAPE, sh::PROPERTY_SHAPE] {
+            candidates.extend(self.graph.subjects(rdf::TYPE, &(*class).into()));
+        }
+        for predicate in &[
+            sh::TARGET_NODE,
+            sh::TARGET_CLASS,
+            sh::TARGET_SUBJECTS_OF,
+            sh::TARGET_OBJECTS_OF,
+        ] {
+            candidates.extend(
+                self.graph
+                    .triples_for_pattern(None, Some(*predicate), None)
+                    .map(|t| t.subject.into()),
+            );
+        }
+        let mut seen = HashSet::new();
+        candidates.retain(|c| seen.insert(c.clone()));
+        candidates
+    }
+
+    /// Returns the index of a shape, parsing it if needed
+    fn shape(&mut self, id: &Term) -> Result<usize, ShaclError> {
+        if let Some(index) = self.ids.get(id) {
+            return Ok(*index);
+        }
+        let index = self.shapes.len();
+        self.ids.insert(id.clone(), index);
+        self.shapes.push(None);
+        let shape = self.parse_shape(id)?;
+        self.shapes[index] = Some(shape);
+        Ok(index)
+    }
+
+    fn parse_shape(&mut self, id: &Term) -> Result<Shape, ShaclError> {
+        let graph = self.graph;
+        let path = if let Some(path) = graph.object(id, sh::PATH) {
+            Some(self.path(&path)?)
+        } else {
+            None
+        };
+
+        let mut targets = Vec::new();
+        for node in graph.objects(id, sh::TARGET_NODE) {
+            targets.push(Target::Node(node));
+        }
+        for class in graph.objects(id, sh::TARGET_CLASS) {
+            targets.push(Target::Class(class));
+        }
+        if graph.contains(id, rdf::TYPE, &rdfs::CLASS.into()) {
+            targets.push(Target::Class(id.clone()));
+        }
+        for predicate in graph.objects(id, sh::TARGET_SUBJECTS_OF) {
+            targets.push(Target::SubjectsOf(named_node(predicate)?));
+        }
+        for predicate in graph.objects(id, sh::TARGET_OBJECTS_OF) {
+            targets.push(Target::ObjectsOf(named_node(predicate)?));
+        }
+
+        let mut constraints = Vec::new();
+        for class in graph.objects(id, sh::CLASS) {
+            constraints.push(Constraint::Class(class));
+        }
+        for datatype in graph.objects(id, sh::DATATYPE) {
+            constraints.push(Constraint::Datatype(named_node(datatype)?));
+        }
+        for kind in graph.objects(id, sh::NODE_KIND) {
+            constraints.push(Constraint::NodeKind(named_node(kind)?));
+        }
+        for count in graph.objects(id, sh::MIN_COUNT) {
+            constraints.push(Constraint::MinCount(integer(&count)?));
+        }
+        for count in graph.objects(id, sh::MAX_COUNT) {
+            constraints.push(Constraint::MaxCount(integer(&count)?));
+        }
+        for (predicate, operator, value_first, component) in &[
+            (
+                sh::MIN_EXCLUSIVE,
+                "<",
+                false,
+                sh::MIN_EXCLUSIVE_CONSTRAINT_COMPONENT,
+            ),
+            (
+                sh::MIN_INCLUSIVE,
+                "<=",
+                false,
+                sh::MIN_INCLUSIVE_CONSTRAINT_COMPONENT,
+            ),
+            (
+                sh::MAX_EXCLUSIVE,
+                "<",
+                true,
+                sh::MAX_EXCLUSIVE_CONSTRAINT_COMPONENT,
+            ),
+            (
+                sh::MAX_INCLUSIVE,
+                "<=",
+                true,
+                sh::MAX_INCLUSIVE_CONSTRAINT_COMPONENT,
+            ),
+        ] {
+            for bound in graph.objects(id, *predicate) {
+                constraints.push(Constraint::Range {
+                    bound,
+                    operator,
+                    value_first: *value_first,
+                    component: *component,
+                });
+            }
+        }
+        for length in graph.objects(id, sh::MIN_LENGTH) {
+            constraints.push(Constraint::MinLength(integer(&length)?));
+        }
+        for length in graph.objects(id, sh::MAX_LENGTH) {
+            constraints.push(Constraint::MaxLength(integer(&length)?));
+        }
+        for pattern in graph.objects(id, sh::PATTERN) {
+            let flags = graph.object(id, sh::FLAGS);
+            constraints.push(Constraint::Pattern(regex(&pattern, flags.as_ref())?));
+        }
+        for list in graph.objects(id, sh::LANGUAGE_IN) {
+            constraints.push(Constraint::LanguageIn(
+                graph
+                    .list(list)
+                    .into_iter()
+                    .map(|tag| string(&tag))
+                    .collect::<Result<_, _>>()?,
+            ));
+        }
+        for unique in graph.objects(id, sh::UNIQUE_LANG) {
+            if boolean(&unique)? {
+                constraints.push(Constraint::UniqueLang);
+            }
+        }
+        for predicate in graph.objects(id, sh::EQUALS) {
+            constraints.push(Constraint::Equals(named_node(predicate)?));
+        }
+        for predicate in graph.objects(id, sh::DISJOINT) {
+            constraints.push(Constraint::Disjoint(named_node(predicate)?));
+        }
+        for predicate in graph.objects(id, sh::LESS_THAN) {
+            constraints.push(Constraint::LessThan(named_node(predicate)?));
+        }
+        for predicate in graph.objects(id, sh::LESS_THAN_OR_EQUALS) {
+            constraints.push(Constraint::LessThanOrEquals(named_node(predicate)?));
+        }
+        for shape in graph.objects(id, sh::NOT) {
+            constraints.push(Constraint::Not(self.shape(&shape)?));
+        }
+        for list in graph.objects(id, sh::AND) {
+            constraints.push(Constraint::And(self.shape_list(list)?));
+        }
+        for list in graph.objects(id, sh::OR) {
+            constraints.push(Constraint::Or(self.shape_list(list)?));
+        }
+        for list in graph.objects(id, sh::XONE) {
+            constraints.push(Constraint::Xone(self.shape_list(list)?));
+        }
+        for shape in graph.objects(id, sh::NODE) {
+            constraints.push(Constraint::Node(self.shape(&shape)?));
+        }
+        let properties = graph.objects(id, sh::PROPERTY);
+        for property in &properties {
+            constraints.push(Constraint::Property(self.shape(property)?));
+        }
+        for shape in graph.objects(id, sh::QUALIFIED_VALUE_SHAPE) {
+            let shape = self.shape(&shape)?;
+            if let Some(count) = graph.object(id, sh::QUALIFIED_MIN_COUNT) {
+                constraints.push(Constraint::QualifiedMinCount {
+                    shape,
+                    count: integer(&count)?,
+                });
+            }
+            if let Some(count) = graph.object(id, sh::QUALIFIED_MAX_COUNT) {
+                constraints.push(Constraint::QualifiedMaxCount {
+                    shape,
+                    count: integer(&count)?,
+                });
+            }
+        }
+        for closed in graph.objects(id, sh::CLOSED) {
+            if boolean(&closed)? {
+                let mut allowed = HashSet::new();
+                for property in &properties {
+                    if let Some(Term::NamedNode(predicate)) = graph.object(property, sh::PATH) {
+                        allowed.insert(predicate);
+                    }
+                }
+                for list in graph.objects(id, sh::IGNORED_PROPERTIES) {
+                    for predicate in graph.list(list) {
+                        allowed.insert(named_node(predicate)?);
+                    }
+                }
+                constraints.push(Constraint::Closed(allowed));
+            }
+        }
+        for value in graph.objects(id, sh::HAS_VALUE) {
+            constraints.push(Constraint::HasValue(value));
+        }
+        for list in graph.objects(id, sh::IN) {
+            constraints.push(Constraint::In(graph.list(list)));
+        }
+        for sparql in graph.objects(id, sh::SPARQL) {
+            if let Some(constraint) = self.sparql_constraint(sparql, path.as_ref())? {
+                constraints.push(Constraint::Sparql(constraint));
+            }
+        }
+
+        Ok(Shape {
+            id: id.clone(),
+            path,
+            targets,
+            constraints,
+            deactivated: match graph.object(id, sh::DEACTIVATED) {
+                Some(deactivated) => boolean(&deactivated)?,
+                None => false,
+            },
+            severity: match graph.object(id, sh::SEVERITY) {
+                Some(severity) => named_node(severity)?,
+                None => sh::VIOLATION.into_owned(),
+            },
+            messages: graph.objects(id, sh::MESSAGE),
+        })
+    }
+
+    fn shape_list(&mut self, list: Term) -> Result<Vec<usize>, ShaclError> {
+        self.graph
+            .list(list)
+            .iter()
+            .map(|shape| self.shape(shape))
+            .collect()
+    }
+
+    fn path(&self, node: &Term) -> Result<Path, ShaclError> {
+        let graph = self.graph;
+        Ok(match node {
+            Term::NamedNode(predicate) => Path::Predicate(predicate.clone()),
+            Term::BlankNode(_) => {
+                if graph.object(node, rdf::FIRST).is_some() {
+                    let elements = graph.list(node.clone());
+                    if elements.len() < 2 {
+                        return Err(invalid(format!(
+                            "the sequence path {} should have at least two elements",
+                            node
+                        )));
+                    }
+                    Path::Sequence(
+                        elements
+                            .iter()
+                            .map(|e| self.path(e))
+                            .collect::<Result<_, _>>()?,
+                    )
+                } else if let Some(list) = graph.object(node, sh::ALTERNATIVE_PATH) {
+                    Path::Alternative(
+                        graph
+                            .list(list)
+                            .iter()
+                            .map(|e| self.path(e))
+                            .collect::<Result<_, _>>()?,
+                    )
+                } else if let Some(path) = graph.object(node, sh::INVERSE_PATH) {
+                    Path::Inverse(Box::new(self.path(&path)?))
+                } else if let Some(path) = graph.object(node, sh::ZERO_OR_MORE_PATH) {
+                    Path::ZeroOrMore(Box::new(self.path(&path)?))
+                } else if let Some(path) = graph.object(node, sh::ONE_OR_MORE_PATH) {
+                    Path::OneOrMore(Box::new(self.path(&path)?))
+                } else if let Some(path) = graph.object(node, sh::ZERO_OR_ONE_PATH) {
+                    Path::ZeroOrOne(Box::new(self.path(&path)?))
+                } else {
+                    return Err(invalid(format!("{} is not a valid SHACL path", node)));
+                }
+            }
+            _ => return Err(invalid(format!("{} is not a valid SHACL path", node))),
+        })
+    }
+
+    fn sparql_constraint(
+        &self,
+        id: Term,
+        path: Option<&Path>,
+    ) -> Result<Option<SparqlConstraint>, ShaclError> {
+        let graph = self.graph;
+        if let Some(deactivated) = graph.object(&id, sh::DEACTIVATED) {
+            if boolean(&deactivated)? {
+                return Ok(None);
+            }
+        }
+        let select = graph
+            .object(&id, sh::SELECT)
+            .ok_or_else(|| invalid(format!("the SPARQL constraint {} has no sh:select", id)))?;
+        let mut query = String::new();
+        for prefixes in graph.objects(&id, sh::PREFIXES) {
+            for declaration in graph.objects(&prefixes, sh::DECLARE) {
+                let prefix = graph
+                    .object(&declaration, sh::PREFIX)
+                    .ok_or_else(|| invalid(format!("{} has no sh:prefix", declaration)))?;
+                let namespace = graph
+                    .object(&declaration, sh::NAMESPACE)
+                    .ok_or_else(|| invalid(format!("{} has no sh:namespace", declaration)))?;
+                query.push_str(&format!(
+                    "PREFIX {}: <{}>\n",
+                    string(&prefix)?,
+                    string(&namespace)?
+                ));
+            }
+        }
+        let select = string(&select)?;
+        if let Some(path) = path {
+            query.push_str(&select.replace("$PATH", &path.to_string()));
+        } else {
+            query.push_str(&select);
+        }
+        Ok(Some(SparqlConstraint {
+            messages: graph.objects(&id, sh::MESSAGE),
+            id,
+            query,
+        }))
+    }
+}
+
+fn invalid(msg: impl Into<String>) -> ShaclError {
+    ShaclError::InvalidShapes(msg.into())
+}
+
+fn named_node(term: Term) -> Result<NamedNode, ShaclError> {
+    if let Term::NamedNode(node) = term {
+        Ok(node)
+    } else {
+        Err(invalid(format!("{} should be an IRI", term)))
+    }
+}
+
+fn string(term: &Term) -> Result<String, ShaclError> {
+    match term {
+        Term::Literal(literal) => Ok(literal.value().to_owned()),
+        Term::NamedNode(node) => Ok(node.as_str().to_owned()),
+        _ => Err(invalid(format!("{} should be a literal", term))),
+    }
+}
+
+fn integer(term: &Term) -> Result<usize, ShaclError> {
+    if let Term::Literal(literal) = term {
+        if let Ok(value) = literal.value().parse() {
+            return Ok(value);
+        }
+    }
+    Err(invalid(format!(
+        "{} should be a non negative integer",
+        term
+    )))
+}
+
+fn boolean(term: &Term) -> Result<bool, ShaclError> {
+    if let Term::Literal(literal) = term {
+        match literal.value() {
+            "true" | "1" => return Ok(true),
+            "false" | "0" => return Ok(false),
+            _ => (),
+        }
+    }
+    Err(invalid(format!("{} should be a boolean", term)))
+}
+
+fn regex(pattern: &Term, flags: Option<&Term>) -> Result<Regex, ShaclError> {
+    let pattern = string(pattern)?;
+    let mut regex_builder = RegexBuilder::new(&pattern);
+    regex_builder.size_limit(REGEX_SIZE_LIMIT);
+    if let Some(flags) = flags {
+        for flag in string(flags)?.chars() {
+            match flag {
+                's' => {
+                    regex_builder.dot_matches_new_line(true);
+                }
+                'm' => {
+                    regex_builder.multi_line(true);
+                }
+                'i' => {
+                    regex_builder.case_insensitive(true);
+                }
+                'x' => {
+                    regex_builder.ignore_whitespace(true);
+                }
+                _ => (),
+            }
+        }
+    }
+    regex_builder
+        .build()
+        .map_err(|e| invalid(format!("invalid sh:pattern {}: {}", pattern, e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::{GraphFormat, GraphParser};
+    use std::io::Cursor;
+
+    fn parse(data: &str) -> Graph {
+        let data = format!(
+            "@prefix ex: <http://example.com/> .
+            @prefix sh: <http://www.w3.org/ns/shacl#> .
+            {}",
+            data
+        );
+        Graph::new(
+            GraphParser::from_format(GraphFormat::Turtle)
+                .read_triples(Cursor::new(data))
+                .unwrap()
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_invalid_shapes() {
+        for shape in &[
+            "ex:S sh:targetNode ex:n ; sh:minCount \"a\" .",
+            "ex:S sh:targetNode ex:n ; sh:minCount -1 .",
+            "ex:S sh:targetNode ex:n ; sh:path \"p\" .",
+            "ex:S sh:targetNode ex:n ; sh:path ( ex:p ) .",
+            "ex:S sh:targetNode ex:n ; sh:path [ ex:p ex:q ] .",
+            "ex:S sh:targetNode ex:n ; sh:sparql [ sh:message \"m\" ] .",
+            "ex:S sh:targetNode ex:n ; sh:pattern \"(\" .",
+            "ex:S sh:targetNode ex:n ; sh:datatype \"xsd:string\" .",
+            "ex:S sh:targetNode ex:n ; sh:deactivated \"maybe\" .",
+        ] {
+            assert!(Shapes::new(&parse(shape)).is_err(), "{} is valid", shape);
+        }
+    }
+
+    #[test]
+    fn test_path_serialization() {
+        let graph = parse(
+            "ex:S a sh:PropertyShape ; sh:path ( ex:p [ sh:inversePath ex:q ] [ sh:zeroOrMorePath [ sh:alternativePath ( ex:r ex:s ) ] ] ) .",
+        );
+        let shapes = Shapes::new(&graph).unwrap();
+        let path = shapes.shapes[0].path.as_ref().unwrap();
+        assert_eq!(
+            path.to_string(),
+            "(<http://example.com/p> / ^(<http://example.com/q>) / ((<http://example.com/r> | <http://example.com/s>))*)"
+        );
+
+        let mut triples = Vec::new();
+        let root = path.to_graph(&mut triples);
+        let graph = Graph::new(triples);
+        let parser = ShapesParser {
+            graph: &graph,
+            shapes: Vec::new(),
+            ids: HashMap::new(),
+        };
+        assert_eq!(parser.path(&root).unwrap().to_string(), path.to_string());
+    }
+}
@@ -0,0 +1,1041 @@
+use crate::error::UnwrapInfallible;
+use crate::model::vocab::{rdf, rdfs, xsd};
+use crate::model::*;
+use crate::shacl::report::{ValidationReport, ValidationResult};
+use crate::shacl::shape::{Constraint, Path, Shape, Shapes, SparqlConstraint, Target};
+use crate::shacl::{sh, Graph, ShaclError};
+use crate::sparql::{QueryResults, QuerySolution};
+use crate::store::numeric_encoder::WriteEncoder;
+use crate::store::MemoryStore;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+/// The XSD datatypes whose lexical forms are checked by `sh:datatype`
+const CHECKED_DATATYPES: &[NamedNodeRef<'static>] = &[
+    xsd::BOOLEAN,
+    xsd::FLOAT,
+    xsd::DOUBLE,
+    xsd::INTEGER,
+    xsd::BYTE,
+    xsd::SHORT,
+    xsd::INT,
+    xsd::LONG,
+    xsd::UNSIGNED_BYTE,
+    xsd::UNSIGNED_SHORT,
+    xsd::UNSIGNED_INT,
+    xsd::UNSIGNED_LONG,
+    xsd::POSITIVE_INTEGER,
+    xsd::NEGATIVE_INTEGER,
+    xsd::NON_POSITIVE_INTEGER,
+    xsd::NON_NEGATIVE_INTEGER,
+    xsd::DECIMAL,
+    xsd::DATE_TIME,
+    xsd::DATE_TIME_STAMP,
+    xsd::TIME,
+    xsd::DATE,
+    xsd::G_YEAR_MONTH,
+    xsd::G_YEAR,
+    xsd::G_MONTH_DAY,
+    xsd::G_DAY,
+    xsd::G_MONTH,
+    xsd::DURATION,
+    xsd::YEAR_MONTH_DURATION,
+    xsd::DAY_TIME_DURATION,
+];
+
+pub(super) struct Validator<'a> {
+    shapes: &'a Shapes,
+    data: Graph,
+    /// The (shape, focus node) pairs being validated, used to stop on recursive shapes
+    in_progress: RefCell<HashSet<(usize, Term)>>,
+    sparql_solutions: RefCell<HashMap<String, Rc<Vec<QuerySolution>>>>,
+    /// An empty store used to evaluate comparisons and check literals
+    scratch: MemoryStore,
+}
+
+impl<'a> Validator<'a> {
+    pub fn new(shapes: &'a Shapes, data: Graph) -> Self {
+        Self {
+            shapes,
+            data,
+            in_progress: RefCell::default(),
+            sparql_solutions: RefCell::default(),
+            scratch: MemoryStore::new(),
+        }
+    }
+
+    pub fn validate(&self) -> Result<ValidationReport, ShaclError> {
+        let mut results = Vec::new();
+        for (index, shape) in self.shapes.shapes.iter().enumerate() {
+            if shape.deactivated {
+                continue;
+            }
+            for focus in self.focus_nodes(shape) {
+                self.validate_shape(index, &focus, &mut results)?;
+            }
+        }
+        Ok(ValidationReport::new(results))
+    }
+
+    fn focus_nodes(&self, shape: &Shape) -> Vec<Term> {
+        let mut nodes = Vec::new();
+        for target in &shape.targets {
+            match target {
+                Target::Node(node) => nodes.push(node.clone()),
+                Target::Class(class) => {
+                    for class in self.sub_classes(class) {
+                        nodes.extend(self.data.subjects(rdf::TYPE, &class));
+                    }
+                }
+                Target::SubjectsOf(predicate) => nodes.extend(
+                    self.data
+                        .triples_for_pattern(None, Some(predicate.as_ref()), None)
+                        .map(|t| t.subject.into()),
+                ),
+                Target::ObjectsOf(predicate) => nodes.extend(
+                    self.data
+                        .triples_for_pattern(None, Some(predicate.as_ref()), None)
+                        .map(|t| t.object),
+                ),
+            }
+        }
+        dedup(nodes)
+    }
+
+    /// The class and all its sub classes in the data graph
+    fn sub_classes(&self, class: &Term) -> Vec<Term> {
+        let mut classes = vec![class.clone()];
+        let mut i = 0;
+        while i < classes.len() {
+            for sub_class in self.data.subjects(rdfs::SUB_CLASS_OF, &classes[i]) {
+                if !classes.contains(&sub_class) {
+                    classes.push(sub_class);
+                }
+            }
+            i += 1;
+        }
+        classes
+    }
+
+    fn is_instance_of(&self, node: &Term, class: &Term) -> bool {
+        let classes = self.sub_classes(class);
+        self.data
+            .objects(node, rdf::TYPE)
+            .iter()
+            .any(|t| classes.contains(t))
+    }
+
+    fn validate_shape(
+        &self,
+        index: usize,
+        focus: &Term,
+        results: &mut Vec<ValidationResult>,
+    ) -> Result<(), ShaclError> {
+        let shape = &self.shapes.shapes[index];
+        if shape.deactivated {
+            return Ok(());
+        }
+        let key = (index, focus.clone());
+        if !self.in_progress.borrow_mut().insert(key.clone()) {
+            // The shape is recursive, we consider that the nested validation conforms
+            return Ok(());
+        }
+        let values = if let Some(path) = &shape.path {
+            self.path_values(path, focus, false)
+        } else {
+            vec![focus.clone()]
+        };
+        let result = shape
+            .constraints
+            .iter()
+            .try_for_each(|constraint| self.check(shape, constraint, focus, &values, results));
+        self.in_progress.borrow_mut().remove(&key);
+        result
+    }
+
+    fn conforms(&self, index: usize, node: &Term) -> Result<bool, ShaclError> {
+        let mut results = Vec::new();
+        self.validate_shape(index, node, &mut results)?;
+        Ok(results.is_empty())
+    }
+
+    fn check(
+        &self,
+        shape: &Shape,
+        constraint: &Constraint,
+        focus: &Term,
+        values: &[Term],
+        results: &mut Vec<ValidationResult>,
+    ) -> Result<(), ShaclError> {
+        let result = |value: Option<&Term>| self.result(shape, constraint, focus, value);
+        match constraint {
+            Constraint::Class(class) => {
+                for value in values {
+                    if !self.is_instance_of(value, class) {
+                        results.push(result(Some(value)));
+                    }
+                }
+            }
+            Constraint::Datatype(datatype) => {
+                for value in values {
+                    if !self.has_datatype(value, datatype.as_ref()) {
+                        results.push(result(Some(value)));
+                    }
+                }
+            }
+            Constraint::NodeKind(kind) => {
+                for value in values {
+                    if !has_node_kind(value, kind.as_ref()) {
+                        results.push(result(Some(value)));
+                    }
+                }
+            }
+            Constraint::MinCount(count) => {
+                if values.len() < *count {
+                    results.push(result(None));
+                }
+            }
+            Constraint::MaxCount(count) => {
+                if values.len() > *count {
+                    results.push(result(None));
+                }
+            }
+            Constraint::Range {
+                bound,
+                operator,
+                value_first,
+                ..
+            } => {
+                for value in values {
+                    let valid = if *value_first {
+                        self.compare(value, operator, bound)?
+                    } else {
+                        self.compare(bound, operator, value)?
+                    };
+                    if !valid {
+                        results.push(result(Some(value)));
+                    }
+                }
+            }
+            Constraint::MinLength(length) => {
+                for value in values {
+                    if lexical_form(value).map_or(true, |v| v.chars().count() < *length) {
+                        results.push(result(Some(value)));
+                    }
+                }
+            }
+            Constraint::MaxLength(length) => {
+                for value in values {
+                    if lexical_form(value).map_or(true, |v| v.chars().count() > *length) {
+                        results.push(result(Some(value)));
+                    }
+                }
+            }
+            Constraint::Pattern(regex) => {
+                for value in values {
+                    if lexical_form(value).map_or(true, |v| !regex.is_match(v)) {
+                        results.push(result(Some(value)));
+                    }
+                }
+            }
+            Constraint::LanguageIn(ranges) => {
+                for value in values {
+                    let valid = if let Term::Literal(literal) = value {
+                        literal.language().map_or(false, |language| {
+                            ranges.iter().any(|range| lang_matches(language, range))
+                        })
+                    } else {
+                        false
+                    };
+                    if !valid {
+                        results.push(result(Some(value)));
+                    }
+                }
+            }
+            Constraint::UniqueLang => {
+                let mut languages = Vec::new();
+                let mut duplicates = Vec::new();
+                for value in values {
+                    if let Term::Literal(literal) = value {
+                        if let Some(language) = literal.language() {
+                            let language = language.to_ascii_lowercase();
+                            if languages.contains(&language) {
+                                if !duplicates.contains(&language) {
+                                    duplicates.push(language);
+                                }
+                            } else {
+                                languages.push(language);
+                            }
+                        }
+                    }
+                }
+                for _ in duplicates {
+                    results.push(result(None));
+                }
+            }
+            Constraint::Equals(predicate) => {
+                let others = self.data.objects(focus, predicate.as_ref());
+                for value in values {
+                    if !others.contains(value) {
+                        results.push(result(Some(value)));
+                    }
+                }
+                for other in &others {
+                    if !values.contains(other) {
+                        results.push(result(Some(other)));
+                    }
+                }
+            }
+            Constraint::Disjoint(predicate) => {
+                let others = self.data.objects(focus, predicate.as_ref());
+                for value in values {
+                    if others.contains(value) {
+                        results.push(result(Some(value)));
+                    }
+                }
+            }
+            Constraint::LessThan(predicate) | Constraint::LessThanOrEquals(predicate) => {
+                let operator = if let Constraint::LessThan(_) = constraint {
+                    "<"
+                } else {
+                    "<="
+                };
+                let others = self.data.objects(focus, predicate.as_ref());
+                for value in values {
+                    for other in &others {
+                        if !self.compare(value, operator, other)? {
+                            results.push(result(Some(value)));
+                        }
+                    }
+                }
+            }
+            Constraint::Not(shape) => {
+                for value in values {
+                    if self.conforms(*shape, value)? {
+                        results.push(result(Some(value)));
+                    }
+                }
+            }
+            Constraint::And(shapes) => {
+                for value in values {
+                    if self.conforming_count(shapes, value)? != shapes.len() {
+                        results.push(result(Some(value)));
+                    }
+                }
+            }
+            Constraint::Or(shapes) => {
+                for value in values {
+                    if self.conforming_count(shapes, value)? == 0 {
+                        results.push(result(Some(value)));
+                    }
+                }
+            }
+            Constraint::Xone(shapes) => {
+                for value in values {
+                    if self.conforming_count(shapes, value)? != 1 {
+                        results.push(result(Some(value)));
+                    }
+                }
+            }
+            Constraint::Node(shape) => {
+                for value in values {
+                    if !self.conforms(*shape, value)? {
+                        results.push(result(Some(value)));
+                    }
+                }
+            }
+            Constraint::Property(shape) => {
+                for value in values {
+                    self.validate_shape(*shape, value, results)?;
+                }
+            }
+            Constraint::QualifiedMinCount { shape, count } => {
+                if self.conforming_values(*shape, values)? < *count {
+                    results.push(result(None));
+                }
+            }
+            Constraint::QualifiedMaxCount { shape, count } => {
+                if self.conforming_values(*shape, values)? > *count {
+                    results.push(result(None));
+                }
+            }
+            Constraint::Closed(allowed) => {
+                for value in values {
+                    for triple in self.data.triples_for_pattern(Some(value), None, None) {
+                        if !allowed.contains(&triple.predicate) {
+                            let mut result = result(Some(&triple.object));
+                            result.result_path = Some(triple.predicate.into());
+                            result.path_triples.clear();
+                            results.push(result);
+                        }
+                    }
+                }
+            }
+            Constraint::HasValue(expected) => {
+                if !values.contains(expected) {
+                    results.push(result(None));
+                }
+            }
+            Constraint::In(allowed) => {
+                for value in values {
+                    if !allowed.contains(value) {
+                        results.push(result(Some(value)));
+                    }
+                }
+            }
+            Constraint::Sparql(sparql) => {
+                self.check_sparql(shape, constraint, sparql, focus, results)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn conforming_count(&self, shapes: &[usize], node: &Term) -> Result<usize, ShaclError> {
+        let mut count = 0;
+        for shape in shapes {
+            if self.conforms(*shape, node)? {
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+
+    fn conforming_values(&self, shape: usize, values: &[Term]) -> Result<usize, ShaclError> {
+        let mut count = 0;
+        for value in values {
+            if self.conforms(shape, value)? {
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+
+    /// Evaluates a SHACL-SPARQL constraint.
+    ///
+    /// The query is evaluated once and its solutions are joined with the focus node on `?this`.
+    fn check_sparql(
+        &self,
+        shape: &Shape,
+        constraint: &Constraint,
+        sparql: &SparqlConstraint,
+        focus: &Term,
+        results: &mut Vec<ValidationResult>,
+    ) -> Result<(), ShaclError> {
+        let solutions = self.sparql_solutions(&sparql.query)?;
+        for solution in solutions.iter() {
+            if solution.get("this") != Some(focus) {
+                continue;
+            }
+            let value = solution
+                .get("value")
+                .cloned()
+                .or_else(|| shape.path.as_ref().map_or(Some(focus.clone()), |_| None));
+            let mut result = self.result(shape, constraint, focus, value.as_ref());
+            if let Some(path) = solution.get("path") {
+                result.result_path = Some(path.clone());
+                result.path_triples.clear();
+            }
+            result.source_constraint = Some(sparql.id.clone());
+            let messages = if sparql.messages.is_empty() {
+                &shape.messages
+            } else {
+                &sparql.messages
+            };
+            result.messages = messages
+                .iter()
+                .map(|message| format_message(message, solution))
+                .collect();
+            results.push(result);
+        }
+        Ok(())
+    }
+
+    fn sparql_solutions(&self, query: &str) -> Result<Rc<Vec<QuerySolution>>, ShaclError> {
+        if let Some(solutions) = self.sparql_solutions.borrow().get(query) {
+            return Ok(solutions.clone());
+        }
+        let solutions = if let QueryResults::Solutions(solutions) = self.data.store.query(query)? {
+            Rc::new(solutions.collect::<Result<Vec<_>, _>>()?)
+        } else {
+            return Err(ShaclError::InvalidShapes(format!(
+                "the SHACL-SPARQL query {} is not a SELECT query",
+                query
+            )));
+        };
+        self.sparql_solutions
+            .borrow_mut()
+            .insert(query.to_owned(), solutions.clone());
+        Ok(solutions)
+    }
+
+    fn result(
+        &self,
+        shape: &Shape,
+        constraint: &Constraint,
+        focus: &Term,
+        value: Option<&Term>,
+    ) -> ValidationResult {
+        let mut path_triples = Vec::new();
+        let result_path = shape
+            .path
+            .as_ref()
+            .map(|path| path.to_graph(&mut path_triples));
+        ValidationResult {
+            focus_node: focus.clone(),
+            result_path,
+            path_triples,
+            value: value.cloned(),
+            source_shape: shape.id.clone(),
+            source_constraint_component: constraint.component().into_owned(),
+            source_constraint: None,
+            severity: shape.severity.clone(),
+            messages: shape.messages.clone(),
+        }
+    }
+
+    /// Evaluates the path from a node, going backward if `inverse` is set
+    fn path_values(&self, path: &Path, node: &Term, inverse: bool) -> Vec<Term> {
+        match path {
+            Path::Predicate(predicate) => {
+                if inverse {
+                    self.data.subjects(predicate.as_ref(), node)
+                } else {
+                    self.data.objects(node, predicate.as_ref())
+                }
+            }
+            Path::Inverse(path) => self.path_values(path, node, !inverse),
+            Path::Sequence(elements) => {
+                let mut nodes = vec![node.clone()];
+                let elements: Box<dyn Iterator<Item = &Path>> = if inverse {
+                    Box::new(elements.iter().rev())
+                } else {
+                    Box::new(elements.iter())
+                };
+                for element in elements {
+                    nodes = dedup(
+                        nodes
+                            .iter()
+                            .flat_map(|n| self.path_values(element, n, inverse))
+                            .collect(),
+                    );
+                }
+                nodes
+            }
+            Path::Alternative(elements) => dedup(
+                elements
+                    .iter()
+                    .flat_map(|e| self.path_values(e, node, inverse))
+                    .collect(),
+            ),
+            Path::ZeroOrMore(path) => self.closure(path, vec![node.clone()], inverse),
+            Path::OneOrMore(path) => {
+                self.closure(path, self.path_values(path, node, inverse), inverse)
+            }
+            Path::ZeroOrOne(path) => {
+                let mut nodes = vec![node.clone()];
+                nodes.extend(self.path_values(path, node, inverse));
+                dedup(nodes)
+            }
+        }
+    }
+
+    fn closure(&self, path: &Path, start: Vec<Term>, inverse: bool) -> Vec<Term> {
+        let mut nodes = dedup(start);
+        let mut i = 0;
+        while i < nodes.len() {
+            for next in self.path_values(path, &nodes[i].clone(), inverse) {
+                if !nodes.contains(&next) {
+                    nodes.push(next);
+                }
+            }
+            i += 1;
+        }
+        nodes
+    }
+
+    /// Compares two literals using the SPARQL operators semantic
+    fn compare(&self, left: &Term, operator: &str, right: &Term) -> Result<bool, ShaclError> {
+        if let (Term::Literal(left), Term::Literal(right)) = (left, right) {
+            Ok(matches!(
+                self.scratch
+                    .query(format!("ASK {{ FILTER({} {} {}) }}", left, operator, right).as_str())?,
+                QueryResults::Boolean(true)
+            ))
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn has_datatype(&self, value: &Term, datatype: NamedNodeRef<'_>) -> bool {
+        if let Term::Literal(literal) = value {
+            if literal.datatype() != datatype {
+                false
+            } else if datatype == rdf::LANG_STRING {
+                literal.language().is_some()
+            } else if CHECKED_DATATYPES.contains(&datatype) {
+                let mut scratch = &self.scratch;
+                !scratch
+                    .encode_literal(literal.as_ref())
+                    .unwrap_infallible()
+                    .is_unknown_typed_literal()
+            } else {
+                true
+            }
+        } else {
+            false
+        }
+    }
+}
+
+fn has_node_kind(value: &Term, kind: NamedNodeRef<'_>) -> bool {
+    match value {
+        Term::NamedNode(_) => {
+            kind == sh::IRI || kind == sh::BLANK_NODE_OR_IRI || kind == sh::IRI_OR_LITERAL
+        }
+        Term::BlankNode(_) => {
+            kind == sh::BLANK_NODE
+                || kind == sh::BLANK_NODE_OR_IRI
+                || kind == sh::BLANK_NODE_OR_LITERAL
+        }
+        Term::Literal(_) => {
+            kind == sh::LITERAL || kind == sh::BLANK_NODE_OR_LITERAL || kind == sh::IRI_OR_LITERAL
+        }
+        Term::Triple(_) => false,
+    }
+}
+
+/// The string used by `sh:minLength`, `sh:maxLength` and `sh:pattern`
+fn lexical_form(value: &Term) -> Option<&str> {
+    match value {
+        Term::NamedNode(node) => Some(node.as_str()),
+        Term::Literal(literal) => Some(literal.value()),
+        Term::BlankNode(_) | Term::Triple(_) => None,
+    }
+}
+
+/// The SPARQL [langMatches](https://www.w3.org/TR/sparql11-query/#func-langMatches) function
+fn lang_matches(language: &str, range: &str) -> bool {
+    if range == "*" {
+        return !language.is_empty();
+    }
+    match language.get(..range.len()) {
+        Some(prefix) => {
+            prefix.eq_ignore_ascii_case(range)
+                && (language.len() == range.len() || language[range.len()..].starts_with('-'))
+        }
+        None => false,
+    }
+}
+
+/// Substitutes the `{?var}` and `{$var}` placeholders of a SHACL-SPARQL message
+fn format_message(message: &Term, solution: &QuerySolution) -> Term {
+    if let Term::Literal(literal) = message {
+        let mut value = literal.value().to_owned();
+        for (variable, term) in solution.iter() {
+            let replacement = match term {
+                Term::NamedNode(node) => node.as_str().to_owned(),
+                Term::Literal(literal) => literal.value().to_owned(),
+                _ => term.to_string(),
+            };
+            for placeholder in &[
+                format!("{{?{}}}", variable.as_str()),
+                format!("{{${}}}", variable.as_str()),
+            ] {
+                value = value.replace(placeholder.as_str(), &replacement);
+            }
+        }
+        if let Some(language) = literal.language() {
+            Literal::new_language_tagged_literal_unchecked(value, language).into()
+        } else {
+            Literal::new_simple_literal(value).into()
+        }
+    } else {
+        message.clone()
+    }
+}
+
+fn dedup(nodes: Vec<Term>) -> Vec<Term> {
+    let mut seen = HashSet::new();
+    nodes
+        .into_iter()
+        .filter(|node| seen.insert(node.clone()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::{GraphFormat, GraphParser};
+    use std::io::Cursor;
+
+    type Violation = (String, String, Option<String>);
+
+    fn parse(data: &str) -> Vec<Triple> {
+        let data = format!(
+            "@prefix ex: <http://example.com/> .
+            @prefix sh: <http://www.w3.org/ns/shacl#> .
+            @prefix rdf: <http://www.w3.org/1999/02/22-rdf-syntax-ns#> .
+            @prefix rdfs: <http://www.w3.org/2000/01/rdf-schema#> .
+            @prefix xsd: <http://www.w3.org/2001/XMLSchema#> .
+            {}",
+            data
+        );
+        GraphParser::from_format(GraphFormat::Turtle)
+            .read_triples(Cursor::new(data))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap()
+    }
+
+    fn validate(shapes: &str, data: &str) -> ValidationReport {
+        let shapes = Shapes::new(&Graph::new(parse(shapes))).unwrap();
+        Validator::new(&shapes, Graph::new(parse(data)))
+            .validate()
+            .unwrap()
+    }
+
+    /// The IRI local names, the literal values and `_:` for the blank nodes
+    fn short(term: &Term) -> String {
+        match term {
+            Term::NamedNode(node) => node.as_str().rsplit(&['/', '#'][..]).next().unwrap().into(),
+            Term::Literal(literal) => literal.value().into(),
+            Term::BlankNode(_) => "_:".into(),
+            Term::Triple(triple) => triple.to_string(),
+        }
+    }
+
+    /// The sorted (focus node, constraint component, value) of the validation results
+    fn violations(shapes: &str, data: &str) -> Vec<Violation> {
+        let mut violations = validate(shapes, data)
+            .results()
+            .iter()
+            .map(|result| {
+                (
+                    short(result.focus_node()),
+                    short(&result.source_constraint_component().into_owned().into()),
+                    result.value().map(short),
+                )
+            })
+            .collect::<Vec<_>>();
+        violations.sort();
+        violations
+    }
+
+    fn violation(focus: &str, component: &str, value: Option<&str>) -> Violation {
+        (focus.into(), component.into(), value.map(Into::into))
+    }
+
+    #[test]
+    fn test_targets() {
+        let shapes = "ex:S sh:targetNode ex:n ;
+                sh:targetClass ex:C ;
+                sh:targetSubjectsOf ex:p ;
+                sh:targetObjectsOf ex:q ;
+                sh:nodeKind sh:BlankNode .
+            ex:D a rdfs:Class , sh:NodeShape ; sh:nodeKind sh:BlankNode .
+            ex:Off sh:targetNode ex:n ; sh:deactivated true ; sh:class ex:C .";
+        let data = "ex:a a ex:C . ex:b a ex:Sub . ex:Sub rdfs:subClassOf ex:C . _:f a ex:C .
+            ex:c ex:p ex:o . ex:o ex:q ex:d . ex:e a ex:D .";
+        assert_eq!(
+            violations(shapes, data),
+            vec![
+                violation("a", "NodeKindConstraintComponent", Some("a")),
+                violation("b", "NodeKindConstraintComponent", Some("b")),
+                violation("c", "NodeKindConstraintComponent", Some("c")),
+                violation("d", "NodeKindConstraintComponent", Some("d")),
+                violation("e", "NodeKindConstraintComponent", Some("e")),
+                violation("n", "NodeKindConstraintComponent", Some("n")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_value_type_components() {
+        let shapes = "ex:S sh:targetNode ex:n ;
+            sh:property [ sh:path ex:class ; sh:class ex:C ] ;
+            sh:property [ sh:path ex:datatype ; sh:datatype xsd:integer ] ;
+            sh:property [ sh:path ex:lang ; sh:datatype rdf:langString ] ;
+            sh:property [ sh:path ex:kind ; sh:nodeKind sh:IRIOrLiteral ] .";
+        let data = "ex:n ex:class ex:c1 , ex:c2 , ex:c3 , \"c4\" ;
+                ex:datatype 1 , \"a\"^^xsd:integer , \"1\" ;
+                ex:lang \"a\"@en , \"b\" ;
+                ex:kind ex:k1 , \"k2\" , _:k3 .
+            ex:c1 a ex:C . ex:c2 a ex:Sub . ex:Sub rdfs:subClassOf ex:C .";
+        assert_eq!(
+            violations(shapes, data),
+            vec![
+                violation("n", "ClassConstraintComponent", Some("c3")),
+                violation("n", "ClassConstraintComponent", Some("c4")),
+                violation("n", "DatatypeConstraintComponent", Some("1")),
+                violation("n", "DatatypeConstraintComponent", Some("a")),
+                violation("n", "DatatypeConstraintComponent", Some("b")),
+                violation("n", "NodeKindConstraintComponent", Some("_:")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_cardinality_components() {
+        let shapes = "ex:S sh:targetClass ex:C ;
+            sh:property [ sh:path ex:p ; sh:minCount 1 ; sh:maxCount 2 ] .";
+        let data = "ex:a a ex:C . ex:b a ex:C ; ex:p 1 , 2 . ex:c a ex:C ; ex:p 1 , 2 , 3 .";
+        assert_eq!(
+            violations(shapes, data),
+            vec![
+                violation("a", "MinCountConstraintComponent", None),
+                violation("c", "MaxCountConstraintComponent", None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_value_range_components() {
+        let shapes = "ex:S sh:targetNode ex:n ;
+            sh:property [ sh:path ex:p ; sh:minExclusive 0 ; sh:maxInclusive 10 ] ;
+            sh:property [
+                sh:path ex:q ;
+                sh:minInclusive \"2020-01-01\"^^xsd:date ;
+                sh:maxExclusive \"2021-01-01\"^^xsd:date
+            ] .";
+        let data = "ex:n ex:p 0 , 1 , 10 , 10.5 , \"a\" ;
+            ex:q \"2020-01-01\"^^xsd:date , \"2021-01-01\"^^xsd:date .";
+        assert_eq!(
+            violations(shapes, data),
+            vec![
+                violation("n", "MaxExclusiveConstraintComponent", Some("2021-01-01")),
+                violation("n", "MaxInclusiveConstraintComponent", Some("10.5")),
+                violation("n", "MaxInclusiveConstraintComponent", Some("a")),
+                violation("n", "MinExclusiveConstraintComponent", Some("0")),
+                violation("n", "MinExclusiveConstraintComponent", Some("a")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_string_components() {
+        let shapes = "ex:S sh:targetNode ex:n ;
+            sh:property [ sh:path ex:length ; sh:minLength 2 ; sh:maxLength 3 ] ;
+            sh:property [ sh:path ex:pattern ; sh:pattern \"^a.c$\" ; sh:flags \"i\" ] ;
+            sh:property [ sh:path ex:lang ; sh:languageIn ( \"en\" \"fr\" ) ; sh:uniqueLang true ] .";
+        let data = "ex:n ex:length \"a\" , \"ab\" , \"abcd\" , \"été\" , _:b ;
+            ex:pattern \"abc\" , \"AXC\" , \"abcd\" ;
+            ex:lang \"a\"@en , \"b\"@en-US , \"c\"@EN , \"d\"@de , \"e\" .";
+        assert_eq!(
+            violations(shapes, data),
+            vec![
+                violation("n", "LanguageInConstraintComponent", Some("d")),
+                violation("n", "LanguageInConstraintComponent", Some("e")),
+                violation("n", "MaxLengthConstraintComponent", Some("_:")),
+                violation("n", "MaxLengthConstraintComponent", Some("abcd")),
+                violation("n", "MinLengthConstraintComponent", Some("_:")),
+                violation("n", "MinLengthConstraintComponent", Some("a")),
+                violation("n", "PatternConstraintComponent", Some("abcd")),
+                violation("n", "UniqueLangConstraintComponent", None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_property_pair_components() {
+        let shapes = "ex:S sh:targetNode ex:n ;
+            sh:property [
+                sh:path ex:p ;
+                sh:equals ex:q ;
+                sh:disjoint ex:r ;
+                sh:lessThan ex:max ;
+                sh:lessThanOrEquals ex:maxOrEqual
+            ] .";
+        let data = "ex:n ex:p 1 , 2 ; ex:q 2 , 3 ; ex:r 1 ; ex:max 2 ; ex:maxOrEqual 2 .";
+        assert_eq!(
+            violations(shapes, data),
+            vec![
+                violation("n", "DisjointConstraintComponent", Some("1")),
+                violation("n", "EqualsConstraintComponent", Some("1")),
+                violation("n", "EqualsConstraintComponent", Some("3")),
+                violation("n", "LessThanConstraintComponent", Some("2")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_logical_components() {
+        let shapes = "ex:S sh:targetNode ex:a , ex:b , ex:c ;
+            sh:not [ sh:class ex:Forbidden ] ;
+            sh:and ( [ sh:nodeKind sh:IRI ] [ sh:property [ sh:path ex:p ; sh:minCount 1 ] ] ) ;
+            sh:or ( [ sh:class ex:A ] [ sh:class ex:B ] ) ;
+            sh:xone ( [ sh:class ex:A ] [ sh:class ex:B ] ) .";
+        let data = "ex:a a ex:A ; ex:p 1 .
+            ex:b a ex:A , ex:B , ex:Forbidden ; ex:p 1 .
+            ex:c ex:q 1 .";
+        assert_eq!(
+            violations(shapes, data),
+            vec![
+                violation("b", "NotConstraintComponent", Some("b")),
+                violation("b", "XoneConstraintComponent", Some("b")),
+                violation("c", "AndConstraintComponent", Some("c")),
+                violation("c", "OrConstraintComponent", Some("c")),
+                violation("c", "XoneConstraintComponent", Some("c")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_shape_based_components() {
+        let shapes = "ex:Person sh:targetClass ex:Person ;
+                sh:property [ sh:path ex:name ; sh:minCount 1 ] ;
+                sh:property [ sh:path ex:knows ; sh:node ex:Person ] .
+            ex:Parent sh:targetNode ex:p1 , ex:p2 , ex:p3 ;
+                sh:property [
+                    sh:path ex:child ;
+                    sh:qualifiedValueShape [ sh:class ex:Adult ] ;
+                    sh:qualifiedMinCount 1 ;
+                    sh:qualifiedMaxCount 1
+                ] .";
+        // alice and bob know each other: the recursion is stopped
+        let data = "ex:alice a ex:Person ; ex:name \"Alice\" ; ex:knows ex:bob .
+            ex:bob a ex:Person ; ex:name \"Bob\" ; ex:knows ex:alice .
+            ex:carol a ex:Person ; ex:knows ex:alice .
+            ex:dave a ex:Person ; ex:name \"Dave\" ; ex:knows ex:carol .
+            ex:p1 ex:child ex:adult1 , ex:minor .
+            ex:p2 ex:child ex:minor .
+            ex:p3 ex:child ex:adult1 , ex:adult2 .
+            ex:adult1 a ex:Adult . ex:adult2 a ex:Adult .";
+        assert_eq!(
+            violations(shapes, data),
+            vec![
+                violation("carol", "MinCountConstraintComponent", None),
+                violation("dave", "NodeConstraintComponent", Some("carol")),
+                violation("p2", "QualifiedMinCountConstraintComponent", None),
+                violation("p3", "QualifiedMaxCountConstraintComponent", None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_other_components() {
+        let shapes = "ex:S sh:targetNode ex:n ;
+            sh:closed true ;
+            sh:ignoredProperties ( rdf:type ) ;
+            sh:property [ sh:path ex:p ; sh:hasValue 1 ; sh:in ( 1 2 ) ] .";
+        let data = "ex:n a ex:C ; ex:p 2 , 3 ; ex:other \"x\" .";
+        assert_eq!(
+            violations(shapes, data),
+            vec![
+                violation("n", "ClosedConstraintComponent", Some("x")),
+                violation("n", "HasValueConstraintComponent", None),
+                violation("n", "InConstraintComponent", Some("3")),
+            ]
+        );
+        let report = validate(shapes, data);
+        let closed = report
+            .results()
+            .iter()
+            .find(|r| r.source_constraint_component() == sh::CLOSED_CONSTRAINT_COMPONENT)
+            .unwrap();
+        assert_eq!(
+            closed.result_path(),
+            Some(&NamedNode::new_unchecked("http://example.com/other").into())
+        );
+    }
+
+    #[test]
+    fn test_paths() {
+        let data = Graph::new(parse(
+            "ex:a ex:p ex:b . ex:b ex:p ex:c . ex:c ex:q ex:d . ex:z ex:p ex:a . ex:y ex:p ex:z .",
+        ));
+        for (path, expected) in &[
+            ("ex:p", vec!["b"]),
+            ("[ sh:inversePath ex:p ]", vec!["z"]),
+            ("( ex:p ex:p )", vec!["c"]),
+            ("[ sh:inversePath ( ex:p ex:p ) ]", vec!["y"]),
+            (
+                "[ sh:alternativePath ( ex:p [ sh:inversePath ex:p ] ) ]",
+                vec!["b", "z"],
+            ),
+            ("[ sh:zeroOrMorePath ex:p ]", vec!["a", "b", "c"]),
+            ("[ sh:oneOrMorePath ex:p ]", vec!["b", "c"]),
+            ("[ sh:zeroOrOnePath ex:p ]", vec!["a", "b"]),
+            ("( ex:p [ sh:oneOrMorePath ex:p ] ex:q )", vec!["d"]),
+        ] {
+            let shapes = Shapes::new(&Graph::new(parse(&format!(
+                "ex:S sh:targetNode ex:a ; sh:path {} .",
+                path
+            ))))
+            .unwrap();
+            let validator =
+                Validator::new(&shapes, Graph::new(data.store.iter().map(Triple::from)));
+            let mut values = validator
+                .path_values(
+                    shapes.shapes[0].path.as_ref().unwrap(),
+                    &NamedNode::new_unchecked("http://example.com/a").into(),
+                    false,
+                )
+                .iter()
+                .map(short)
+                .collect::<Vec<_>>();
+            values.sort();
+            assert_eq!(&values, expected, "for path {}", path);
+        }
+    }
+
+    #[test]
+    fn test_sparql_constraints() {
+        let shapes = "ex: sh:declare [ sh:prefix \"ex\" ; sh:namespace \"http://example.com/\"^^xsd:anyURI ] .
+            ex:S sh:targetClass ex:C ;
+                sh:sparql [
+                    sh:message \"{$this} has the too large value {?value}\" ;
+                    sh:prefixes ex: ;
+                    sh:select \"SELECT $this ?value WHERE { $this ex:p ?value . FILTER(?value > 10) }\"
+                ] ;
+                sh:property [
+                    sh:path ex:q ;
+                    sh:sparql [ sh:select \"SELECT $this ?value WHERE { $this $PATH ?value . FILTER(?value < 0) }\" ]
+                ] ;
+                sh:sparql [ sh:deactivated true ; sh:select \"SELECT $this WHERE { $this ?p ?o }\" ] .";
+        let data = "ex:a a ex:C ; ex:p 5 , 20 ; ex:q 1 , -1 . ex:b ex:p 30 .";
+        assert_eq!(
+            violations(shapes, data),
+            vec![
+                violation("a", "SPARQLConstraintComponent", Some("-1")),
+                violation("a", "SPARQLConstraintComponent", Some("20")),
+            ]
+        );
+        let report = validate(shapes, data);
+        let large = report
+            .results()
+            .iter()
+            .find(|r| r.value() == Some(&Literal::from(20).into()))
+            .unwrap();
+        assert_eq!(
+            large.messages(),
+            &[
+                Literal::new_simple_literal("http://example.com/a has the too large value 20")
+                    .into()
+            ]
+        );
+        assert!(large.source_constraint().is_some());
+    }
+
+    #[test]
+    fn test_severity_and_messages() {
+        let report = validate(
+            "ex:S sh:targetNode ex:n ; sh:class ex:C ; sh:severity sh:Warning ; sh:message \"not a C\"@en .",
+            "ex:n ex:p ex:o .",
+        );
+        assert!(!report.conforms());
+        let result = &report.results()[0];
+        assert_eq!(
+            result.severity(),
+            NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#Warning")
+        );
+        assert_eq!(
+            result.messages(),
+            &[Literal::new_language_tagged_literal_unchecked("not a C", "en").into()]
+        );
+        assert!(report
+            .to_graph()
+            .iter()
+            .any(|t| t.predicate == sh::CONFORMS && t.object == Literal::from(false).into()));
+    }
+}
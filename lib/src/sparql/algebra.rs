@@ -12,8 +12,8 @@ use rio_api::model as rio;
 use std::collections::BTreeSet;
 use std::convert::TryFrom;
 use std::fmt;
-use std::rc::Rc;
 use std::str::FromStr;
+use std::sync::Arc;
 
 /// A parsed [SPARQL query](https://www.w3.org/TR/sparql11-query/)
 ///
@@ -67,7 +67,7 @@ pub enum Query {
         /// The [query dataset specification](https://www.w3.org/TR/sparql11-query/#specifyingDataset)
         dataset: QueryDataset,
         /// The query selection graph pattern
-        pattern: Rc<GraphPattern>,
+        pattern: Arc<GraphPattern>,
         /// The query base IRI
         base_iri: Option<Iri<String>>,
     },
@@ -290,11 +290,24 @@ impl From<Variable> for NamedNodeOrVariable {
     }
 }
 
-/// The union of [`Term`]s and [`Variable`]s
+/// The union of [`Term`]s, [`Variable`]s and [RDF-star](https://w3c.github.io/rdf-star/cg-spec/) quoted triple patterns
 #[derive(Eq, PartialEq, Debug, Clone, Hash)]
 pub enum TermOrVariable {
     Term(Term),
     Variable(Variable),
+    Triple(Box<TriplePattern>),
+}
+
+impl TermOrVariable {
+    fn add_variables<'a>(&'a self, vars: &mut BTreeSet<&'a Variable>) {
+        match self {
+            TermOrVariable::Term(_) => (),
+            TermOrVariable::Variable(var) => {
+                vars.insert(var);
+            }
+            TermOrVariable::Triple(triple) => triple.add_variables(vars),
+        }
+    }
 }
 
 impl fmt::Display for TermOrVariable {
@@ -302,6 +315,11 @@ impl fmt::Display for TermOrVariable {
         match self {
             TermOrVariable::Term(term) => term.fmt(f),
             TermOrVariable::Variable(var) => var.fmt(f),
+            TermOrVariable::Triple(triple) => write!(
+                f,
+                "<< {} {} {} >>",
+                triple.subject, triple.predicate, triple.object
+            ),
         }
     }
 }
@@ -336,6 +354,12 @@ impl From<Term> for TermOrVariable {
     }
 }
 
+impl From<TriplePattern> for TermOrVariable {
+    fn from(triple: TriplePattern) -> Self {
+        TermOrVariable::Triple(Box::new(triple))
+    }
+}
+
 impl From<NamedNodeOrVariable> for TermOrVariable {
     fn from(element: NamedNodeOrVariable) -> Self {
         match element {
@@ -365,6 +389,14 @@ impl TriplePattern {
             object: object.into(),
         }
     }
+
+    fn add_variables<'a>(&'a self, vars: &mut BTreeSet<&'a Variable>) {
+        self.subject.add_variables(vars);
+        if let NamedNodeOrVariable::Variable(p) = &self.predicate {
+            vars.insert(p);
+        }
+        self.object.add_variables(vars);
+    }
 }
 
 impl fmt::Display for TriplePattern {
@@ -799,6 +831,11 @@ pub enum Function {
     IsLiteral,
     IsNumeric,
     Regex,
+    Triple,
+    Subject,
+    Predicate,
+    Object,
+    IsTriple,
     Custom(NamedNode),
 }
 
@@ -851,6 +888,11 @@ impl fmt::Display for Function {
             Function::IsLiteral => write!(f, "isLITERAL"),
             Function::IsNumeric => write!(f, "isNUMERIC"),
             Function::Regex => write!(f, "REGEX"),
+            Function::Triple => write!(f, "TRIPLE"),
+            Function::Subject => write!(f, "SUBJECT"),
+            Function::Predicate => write!(f, "PREDICATE"),
+            Function::Object => write!(f, "OBJECT"),
+            Function::IsTriple => write!(f, "isTRIPLE"),
             Function::Custom(iri) => iri.fmt(f),
         }
     }
@@ -1076,26 +1118,14 @@ impl GraphPattern {
         match self {
             GraphPattern::BGP(p) => {
                 for pattern in p {
-                    if let TermOrVariable::Variable(s) = &pattern.subject {
-                        vars.insert(s);
-                    }
-                    if let NamedNodeOrVariable::Variable(p) = &pattern.predicate {
-                        vars.insert(p);
-                    }
-                    if let TermOrVariable::Variable(o) = &pattern.object {
-                        vars.insert(o);
-                    }
+                    pattern.add_variables(vars);
                 }
             }
             GraphPattern::Path {
                 subject, object, ..
             } => {
-                if let TermOrVariable::Variable(s) = subject {
-                    vars.insert(s);
-                }
-                if let TermOrVariable::Variable(o) = object {
-                    vars.insert(o);
-                }
+                subject.add_variables(vars);
+                object.add_variables(vars);
             }
             GraphPattern::Join { left, right }
             | GraphPattern::LeftJoin { left, right, .. }
@@ -0,0 +1,842 @@
+//! A LRU cache of materialized query results shared by the clones of a store.
+//!
+//! The cache is invalidated as a whole after each write to the store.
+//! Each write increments a generation counter: the results of a query are only stored
+//! if no write has happened during their evaluation.
+//!
+//! Only the queries whose results depend only on the store content are cached:
+//! the queries using `SERVICE`, `RAND()`, `NOW()`, `UUID()`, `STRUUID()`, `BNODE()`
+//! or a CONSTRUCT template with blank nodes are always evaluated.
+
+use crate::model::{BlankNode, Term, Triple};
+use crate::sparql::algebra::{
+    AggregationFunction, Expression, Function, GraphPattern, NamedNodeOrVariable, OrderComparator,
+    TermOrVariable, TriplePattern,
+};
+use crate::sparql::model::QueryTripleIter;
+use crate::sparql::{
+    EvaluationError, Query, QueryOptions, QueryResults, QuerySolutionIter, Variable,
+};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// The minimal length of the variable names considered as generated by the query parser
+const GENERATED_IDENTIFIER_MIN_LEN: usize = 24;
+/// The maximal number of solutions or triples of the cached results
+const MAX_CACHED_RESULTS_LEN: usize = 10_000;
+
+#[derive(Default)]
+pub(crate) struct QueryCache {
+    state: Mutex<QueryCacheState>,
+}
+
+#[derive(Default)]
+struct QueryCacheState {
+    capacity: usize,
+    generation: u64,
+    entries: LruMap,
+}
+
+/// The query and the options that might change its results
+///
+/// The service handler is not part of the key because the queries using `SERVICE` are not cached.
+#[derive(Eq, PartialEq, Hash, Clone)]
+struct QueryCacheKey {
+    query: Query,
+    rdfs_entailment: bool,
+}
+
+#[derive(Clone)]
+enum CachedResults {
+    Solutions {
+        variables: Arc<Vec<Variable>>,
+        solutions: Arc<Vec<Vec<Option<Term>>>>,
+    },
+    Boolean(bool),
+    Graph(Arc<Vec<Triple>>),
+}
+
+impl QueryCache {
+    /// Sets the maximal number of cached results, `0` disables the cache
+    pub fn set_capacity(&self, capacity: usize) {
+        let mut state = self.state();
+        state.capacity = capacity;
+        while state.entries.len() > capacity {
+            state.entries.remove_least_recently_used();
+        }
+    }
+
+    /// Returns a guard invalidating the cache when dropped
+    ///
+    /// It should be kept alive until the end of the write operations.
+    #[cfg(any(feature = "rocksdb", feature = "sled"))]
+    pub fn invalidation(&self) -> QueryCacheInvalidation<'_> {
+        QueryCacheInvalidation { cache: self }
+    }
+
+    /// Drops all the cached results
+    pub fn invalidate(&self) {
+        let mut state = self.state();
+        state.generation += 1;
+        state.entries.clear();
+    }
+
+    /// Returns the cached results of the query or evaluates it using `evaluate` and caches its results
+    pub fn evaluate(
+        &self,
+        query: Query,
+        options: QueryOptions,
+        evaluate: impl FnOnce(Query, QueryOptions) -> Result<QueryResults, EvaluationError>,
+    ) -> Result<QueryResults, EvaluationError> {
+        if !options.query_cache || !is_cacheable(&query) {
+            return evaluate(query, options);
+        }
+        let generation = {
+            let state = self.state();
+            if state.capacity == 0 {
+                drop(state);
+                return evaluate(query, options);
+            }
+            state.generation
+        };
+        let key = QueryCacheKey {
+            query: normalize_query(&query),
+            rdfs_entailment: options.rdfs_entailment,
+        };
+        if let Some(results) = self.state().entries.get(&key) {
+            return Ok(results.into());
+        }
+        match CachedResults::materialize(evaluate(query, options)?)? {
+            Materialized::Cacheable(results) => {
+                let mut state = self.state();
+                if state.generation == generation && state.capacity > 0 {
+                    if state.entries.len() >= state.capacity {
+                        state.entries.remove_least_recently_used();
+                    }
+                    state.entries.insert(key, results.clone());
+                }
+                Ok(results.into())
+            }
+            Materialized::TooLarge(results) => Ok(results),
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, QueryCacheState> {
+        self.state.lock().unwrap()
+    }
+}
+
+/// Invalidates the [`QueryCache`] when dropped
+#[cfg(any(feature = "rocksdb", feature = "sled"))]
+pub(crate) struct QueryCacheInvalidation<'a> {
+    cache: &'a QueryCache,
+}
+
+#[cfg(any(feature = "rocksdb", feature = "sled"))]
+impl Drop for QueryCacheInvalidation<'_> {
+    fn drop(&mut self) {
+        self.cache.invalidate()
+    }
+}
+
+/// A map keeping its entries in a doubly linked list from the most to the least recently used.
+///
+/// The list links are indexes in the entries vector so the lookups, insertions and evictions are in O(1).
+#[derive(Default)]
+struct LruMap {
+    indexes: HashMap<QueryCacheKey, usize>,
+    nodes: Vec<LruNode>,
+    /// The most recently used entry
+    head: Option<usize>,
+    /// The least recently used entry
+    tail: Option<usize>,
+}
+
+struct LruNode {
+    key: QueryCacheKey,
+    results: CachedResults,
+    previous: Option<usize>,
+    next: Option<usize>,
+}
+
+impl LruMap {
+    fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    fn get(&mut self, key: &QueryCacheKey) -> Option<CachedResults> {
+        let index = *self.indexes.get(key)?;
+        self.unlink(index);
+        self.push_front(index);
+        Some(self.nodes[index].results.clone())
+    }
+
+    fn insert(&mut self, key: QueryCacheKey, results: CachedResults) {
+        let index = if let Some(index) = self.indexes.get(&key).copied() {
+            self.nodes[index].results = results;
+            self.unlink(index);
+            index
+        } else {
+            self.nodes.push(LruNode {
+                key: key.clone(),
+                results,
+                previous: None,
+                next: None,
+            });
+            self.indexes.insert(key, self.nodes.len() - 1);
+            self.nodes.len() - 1
+        };
+        self.push_front(index);
+    }
+
+    fn remove_least_recently_used(&mut self) {
+        if let Some(index) = self.tail {
+            self.unlink(index);
+            let node = self.nodes.swap_remove(index);
+            self.indexes.remove(&node.key);
+            if index < self.nodes.len() {
+                // The last node has been moved to the removed node place
+                let moved = &self.nodes[index];
+                let (previous, next) = (moved.previous, moved.next);
+                if let Some(moved_index) = self.indexes.get_mut(&moved.key) {
+                    *moved_index = index;
+                }
+                match previous {
+                    Some(previous) => self.nodes[previous].next = Some(index),
+                    None => self.head = Some(index),
+                }
+                match next {
+                    Some(next) => self.nodes[next].previous = Some(index),
+                    None => self.tail = Some(index),
+                }
+            }
+        }
+    }
+
+    fn unlink(&mut self, index: usize) {
+        let (previous, next) = (self.nodes[index].previous, self.nodes[index].next);
+        match previous {
+            Some(previous) => self.nodes[previous].next = next,
+            None => self.head = next,
+        }
+        match next {
+            Some(next) => self.nodes[next].previous = previous,
+            None => self.tail = previous,
+        }
+        self.nodes[index].previous = None;
+        self.nodes[index].next = None;
+    }
+
+    fn push_front(&mut self, index: usize) {
+        self.nodes[index].next = self.head;
+        match self.head {
+            Some(head) => self.nodes[head].previous = Some(index),
+            None => self.tail = Some(index),
+        }
+        self.head = Some(index);
+    }
+}
+
+enum Materialized {
+    Cacheable(CachedResults),
+    /// The results with more than [`MAX_CACHED_RESULTS_LEN`] solutions or triples, the already read ones included
+    TooLarge(QueryResults),
+}
+
+impl CachedResults {
+    fn materialize(results: QueryResults) -> Result<Materialized, EvaluationError> {
+        Ok(match results {
+            QueryResults::Solutions(mut solutions) => {
+                let variables = Arc::new(solutions.variables().to_vec());
+                let values = solutions
+                    .by_ref()
+                    .take(MAX_CACHED_RESULTS_LEN + 1)
+                    .map(|solution| Ok(solution?.values().map(|v| v.cloned()).collect()))
+                    .collect::<Result<Vec<_>, EvaluationError>>()?;
+                if values.len() > MAX_CACHED_RESULTS_LEN {
+                    Materialized::TooLarge(QueryResults::Solutions(QuerySolutionIter::new(
+                        variables,
+                        Box::new(values.into_iter().map(Ok).chain(
+                            solutions.map(|solution| {
+                                Ok(solution?.values().map(|v| v.cloned()).collect())
+                            }),
+                        )),
+                    )))
+                } else {
+                    Materialized::Cacheable(Self::Solutions {
+                        variables,
+                        solutions: Arc::new(values),
+                    })
+                }
+            }
+            QueryResults::Boolean(result) => Materialized::Cacheable(Self::Boolean(result)),
+            QueryResults::Graph(mut triples) => {
+                let values = triples
+                    .by_ref()
+                    .take(MAX_CACHED_RESULTS_LEN + 1)
+                    .collect::<Result<Vec<_>, _>>()?;
+                if values.len() > MAX_CACHED_RESULTS_LEN {
+                    Materialized::TooLarge(QueryResults::Graph(QueryTripleIter {
+                        iter: Box::new(values.into_iter().map(Ok).chain(triples)),
+                    }))
+                } else {
+                    Materialized::Cacheable(Self::Graph(Arc::new(values)))
+                }
+            }
+        })
+    }
+}
+
+impl From<CachedResults> for QueryResults {
+    fn from(results: CachedResults) -> Self {
+        match results {
+            CachedResults::Solutions {
+                variables,
+                solutions,
+            } => QueryResults::Solutions(QuerySolutionIter::new(
+                Arc::new(variables.as_ref().clone()),
+                Box::new((0..solutions.len()).map(move |i| Ok(solutions[i].clone()))),
+            )),
+            CachedResults::Boolean(result) => QueryResults::Boolean(result),
+            CachedResults::Graph(triples) => QueryResults::Graph(QueryTripleIter {
+                iter: Box::new((0..triples.len()).map(move |i| Ok(triples[i].clone()))),
+            }),
+        }
+    }
+}
+
+/// Checks if the query results only depend on the store content
+fn is_cacheable(query: &Query) -> bool {
+    match query {
+        Query::Construct {
+            template, pattern, ..
+        } => !template.iter().any(has_blank_node) && is_cacheable_pattern(pattern),
+        Query::Select { pattern, .. } | Query::Describe { pattern, .. } => {
+            is_cacheable_pattern(pattern)
+        }
+        Query::Ask { pattern, .. } => is_cacheable_pattern(pattern),
+    }
+}
+
+fn has_blank_node(triple: &TriplePattern) -> bool {
+    let is_blank_node = |term: &TermOrVariable| match term {
+        TermOrVariable::Term(Term::BlankNode(_)) => true,
+        TermOrVariable::Triple(triple) => has_blank_node(triple),
+        _ => false,
+    };
+    is_blank_node(&triple.subject) || is_blank_node(&triple.object)
+}
+
+fn is_cacheable_pattern(pattern: &GraphPattern) -> bool {
+    match pattern {
+        GraphPattern::BGP(_) | GraphPattern::Path { .. } | GraphPattern::Table { .. } => true,
+        GraphPattern::Join { left, right }
+        | GraphPattern::Union { left, right }
+        | GraphPattern::Minus { left, right } => {
+            is_cacheable_pattern(left) && is_cacheable_pattern(right)
+        }
+        GraphPattern::LeftJoin { left, right, expr } => {
+            is_cacheable_pattern(left)
+                && is_cacheable_pattern(right)
+                && expr.iter().all(is_cacheable_expression)
+        }
+        GraphPattern::Filter { expr, inner } | GraphPattern::Extend { inner, expr, .. } => {
+            is_cacheable_expression(expr) && is_cacheable_pattern(inner)
+        }
+        GraphPattern::OrderBy { inner, condition } => {
+            is_cacheable_pattern(inner)
+                && condition.iter().all(|condition| match condition {
+                    OrderComparator::Asc(expr) | OrderComparator::Desc(expr) => {
+                        is_cacheable_expression(expr)
+                    }
+                })
+        }
+        GraphPattern::Group {
+            inner, aggregates, ..
+        } => {
+            is_cacheable_pattern(inner)
+                && aggregates.iter().all(|(_, aggregate)| match aggregate {
+                    AggregationFunction::Count { expr, .. } => {
+                        expr.iter().all(|expr| is_cacheable_expression(expr))
+                    }
+                    AggregationFunction::Sum { expr, .. }
+                    | AggregationFunction::Avg { expr, .. }
+                    | AggregationFunction::Min { expr, .. }
+                    | AggregationFunction::Max { expr, .. }
+                    | AggregationFunction::GroupConcat { expr, .. }
+                    | AggregationFunction::Sample { expr, .. }
+                    | AggregationFunction::Custom { expr, .. } => is_cacheable_expression(expr),
+                })
+        }
+        GraphPattern::Graph { inner, .. }
+        | GraphPattern::Project { inner, .. }
+        | GraphPattern::Distinct { inner }
+        | GraphPattern::Reduced { inner }
+        | GraphPattern::Slice { inner, .. } => is_cacheable_pattern(inner),
+        // The SERVICE results do not depend on the store content
+        GraphPattern::Service { .. } => false,
+    }
+}
+
+fn is_cacheable_expression(expression: &Expression) -> bool {
+    match expression {
+        Expression::NamedNode(_)
+        | Expression::Literal(_)
+        | Expression::Variable(_)
+        | Expression::Bound(_) => true,
+        Expression::Or(a, b)
+        | Expression::And(a, b)
+        | Expression::Equal(a, b)
+        | Expression::SameTerm(a, b)
+        | Expression::Greater(a, b)
+        | Expression::GreaterOrEqual(a, b)
+        | Expression::Less(a, b)
+        | Expression::LessOrEqual(a, b)
+        | Expression::Add(a, b)
+        | Expression::Subtract(a, b)
+        | Expression::Multiply(a, b)
+        | Expression::Divide(a, b) => is_cacheable_expression(a) && is_cacheable_expression(b),
+        Expression::UnaryPlus(e) | Expression::UnaryMinus(e) | Expression::Not(e) => {
+            is_cacheable_expression(e)
+        }
+        Expression::In(a, list) => {
+            is_cacheable_expression(a) && list.iter().all(is_cacheable_expression)
+        }
+        Expression::Coalesce(list) => list.iter().all(is_cacheable_expression),
+        Expression::FunctionCall(function, arguments) => {
+            // These functions return a different value at each evaluation
+            !matches!(
+                function,
+                Function::Rand
+                    | Function::Now
+                    | Function::UUID
+                    | Function::StrUUID
+                    | Function::BNode
+            ) && arguments.iter().all(is_cacheable_expression)
+        }
+        Expression::If(a, b, c) => {
+            is_cacheable_expression(a) && is_cacheable_expression(b) && is_cacheable_expression(c)
+        }
+        Expression::Exists(pattern) => is_cacheable_pattern(pattern),
+    }
+}
+
+/// Returns a copy of the query with a canonical naming of the variables and blank nodes generated by the parser
+///
+/// The parser generates random identifiers for anonymous variables and blank nodes so parsing twice the same query
+/// does not return equal queries.
+/// They are renamed here in their order of appearance.
+fn normalize_query(query: &Query) -> Query {
+    let mut query = query.clone();
+    let mut normalizer = QueryNormalizer::default();
+    match &mut query {
+        Query::Select { pattern, .. } => {
+            normalizer.set_projection(pattern);
+            normalizer.pattern(pattern);
+        }
+        Query::Construct {
+            template, pattern, ..
+        } => {
+            for triple in template {
+                normalizer.triple(triple);
+            }
+            normalizer.pattern(pattern);
+        }
+        Query::Describe { pattern, .. } => normalizer.pattern(pattern),
+        Query::Ask { pattern, .. } => normalizer.pattern(Arc::make_mut(pattern)),
+    }
+    query
+}
+
+#[derive(Default)]
+struct QueryNormalizer {
+    /// The variables returned in the solutions: they are never renamed
+    projection: Vec<Variable>,
+    variables: HashMap<Variable, Variable>,
+    blank_nodes: HashMap<BlankNode, BlankNode>,
+}
+
+impl QueryNormalizer {
+    fn set_projection(&mut self, pattern: &GraphPattern) {
+        match pattern {
+            GraphPattern::Project { projection, .. } => self.projection = projection.clone(),
+            GraphPattern::Distinct { inner }
+            | GraphPattern::Reduced { inner }
+            | GraphPattern::Slice { inner, .. } => self.set_projection(inner),
+            _ => (),
+        }
+    }
+
+    fn variable(&mut self, variable: &mut Variable) {
+        let name = variable.as_str();
+        if name.len() >= GENERATED_IDENTIFIER_MIN_LEN
+            && name.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+            && !self.projection.contains(variable)
+        {
+            let next_id = self.variables.len();
+            // '#' is not allowed in variable names so the canonical names could not conflict with the other ones
+            *variable = self
+                .variables
+                .entry(variable.clone())
+                .or_insert_with(|| Variable::new_unchecked(format!("#{}", next_id)))
+                .clone();
+        }
+    }
+
+    fn blank_node(&mut self, node: &mut BlankNode) {
+        // The blank nodes with a label written in the query are not anonymous
+        if node.as_ref().id().is_some() {
+            let next_id = self.blank_nodes.len();
+            *node = self
+                .blank_nodes
+                .entry(node.clone())
+                .or_insert_with(|| BlankNode::new_unchecked(format!("#{}", next_id)))
+                .clone();
+        }
+    }
+
+    fn term(&mut self, term: &mut TermOrVariable) {
+        match term {
+            TermOrVariable::Term(Term::BlankNode(node)) => self.blank_node(node),
+            TermOrVariable::Term(_) => (),
+            TermOrVariable::Variable(variable) => self.variable(variable),
+            TermOrVariable::Triple(triple) => self.triple(triple),
+        }
+    }
+
+    fn named_node_or_variable(&mut self, term: &mut NamedNodeOrVariable) {
+        if let NamedNodeOrVariable::Variable(variable) = term {
+            self.variable(variable)
+        }
+    }
+
+    fn triple(&mut self, triple: &mut TriplePattern) {
+        self.term(&mut triple.subject);
+        self.named_node_or_variable(&mut triple.predicate);
+        self.term(&mut triple.object);
+    }
+
+    fn pattern(&mut self, pattern: &mut GraphPattern) {
+        match pattern {
+            GraphPattern::BGP(triples) => {
+                for triple in triples {
+                    self.triple(triple);
+                }
+            }
+            GraphPattern::Path {
+                subject, object, ..
+            } => {
+                self.term(subject);
+                self.term(object);
+            }
+            GraphPattern::Join { left, right }
+            | GraphPattern::Union { left, right }
+            | GraphPattern::Minus { left, right } => {
+                self.pattern(left);
+                self.pattern(right);
+            }
+            GraphPattern::LeftJoin { left, right, expr } => {
+                self.pattern(left);
+                self.pattern(right);
+                if let Some(expr) = expr {
+                    self.expression(expr);
+                }
+            }
+            GraphPattern::Filter { expr, inner } => {
+                self.pattern(inner);
+                self.expression(expr);
+            }
+            GraphPattern::Graph { graph_name, inner } => {
+                self.named_node_or_variable(graph_name);
+                self.pattern(inner);
+            }
+            GraphPattern::Extend { inner, var, expr } => {
+                self.pattern(inner);
+                self.variable(var);
+                self.expression(expr);
+            }
+            GraphPattern::Table { variables, .. } => {
+                for variable in variables {
+                    self.variable(variable);
+                }
+            }
+            GraphPattern::OrderBy { inner, condition } => {
+                self.pattern(inner);
+                for condition in condition {
+                    match condition {
+                        OrderComparator::Asc(expr) | OrderComparator::Desc(expr) => {
+                            self.expression(expr)
+                        }
+                    }
+                }
+            }
+            GraphPattern::Project { inner, projection } => {
+                self.pattern(inner);
+                for variable in projection {
+                    self.variable(variable);
+                }
+            }
+            GraphPattern::Distinct { inner }
+            | GraphPattern::Reduced { inner }
+            | GraphPattern::Slice { inner, .. } => self.pattern(inner),
+            GraphPattern::Group {
+                inner,
+                by,
+                aggregates,
+            } => {
+                self.pattern(inner);
+                for variable in by {
+                    self.variable(variable);
+                }
+                for (variable, aggregate) in aggregates {
+                    self.variable(variable);
+                    match aggregate {
+                        AggregationFunction::Count { expr, .. } => {
+                            if let Some(expr) = expr {
+                                self.expression(expr);
+                            }
+                        }
+                        AggregationFunction::Sum { expr, .. }
+                        | AggregationFunction::Avg { expr, .. }
+                        | AggregationFunction::Min { expr, .. }
+                        | AggregationFunction::Max { expr, .. }
+                        | AggregationFunction::GroupConcat { expr, .. }
+                        | AggregationFunction::Sample { expr, .. }
+                        | AggregationFunction::Custom { expr, .. } => self.expression(expr),
+                    }
+                }
+            }
+            GraphPattern::Service { name, pattern, .. } => {
+                self.named_node_or_variable(name);
+                self.pattern(pattern);
+            }
+        }
+    }
+
+    fn expression(&mut self, expression: &mut Expression) {
+        match expression {
+            Expression::NamedNode(_) | Expression::Literal(_) => (),
+            Expression::Variable(variable) | Expression::Bound(variable) => self.variable(variable),
+            Expression::Or(a, b)
+            | Expression::And(a, b)
+            | Expression::Equal(a, b)
+            | Expression::SameTerm(a, b)
+            | Expression::Greater(a, b)
+            | Expression::GreaterOrEqual(a, b)
+            | Expression::Less(a, b)
+            | Expression::LessOrEqual(a, b)
+            | Expression::Add(a, b)
+            | Expression::Subtract(a, b)
+            | Expression::Multiply(a, b)
+            | Expression::Divide(a, b) => {
+                self.expression(a);
+                self.expression(b);
+            }
+            Expression::UnaryPlus(e) | Expression::UnaryMinus(e) | Expression::Not(e) => {
+                self.expression(e)
+            }
+            Expression::In(a, list) => {
+                self.expression(a);
+                for e in list {
+                    self.expression(e);
+                }
+            }
+            Expression::Coalesce(list) | Expression::FunctionCall(_, list) => {
+                for e in list {
+                    self.expression(e);
+                }
+            }
+            Expression::If(a, b, c) => {
+                self.expression(a);
+                self.expression(b);
+                self.expression(c);
+            }
+            Expression::Exists(pattern) => self.pattern(pattern),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_query() -> Result<(), EvaluationError> {
+        for query in &[
+            "SELECT (COUNT(*) AS ?c) WHERE { ?s ?p [ ?p2 ?o ] } GROUP BY ?s",
+            "SELECT ?s WHERE { ?s <http://example.com/p>/<http://example.com/p> _:b }",
+            "CONSTRUCT { ?s ?p [] } WHERE { ?s ?p ?o }",
+        ] {
+            assert_eq!(
+                normalize_query(&Query::parse(query, None)?),
+                normalize_query(&Query::parse(query, None)?)
+            );
+        }
+        assert_ne!(
+            normalize_query(&Query::parse("SELECT ?a WHERE { ?a ?b ?c }", None)?),
+            normalize_query(&Query::parse("SELECT ?b WHERE { ?b ?a ?c }", None)?)
+        );
+        // The IRIs and the literals are not renamed even if they look like generated identifiers
+        for (a, b) in &[
+            (
+                "ASK { ?s ?p \"?0123456789abcdef0123456789abcdef\" }",
+                "ASK { ?s ?p \"?fedcba9876543210fedcba9876543210\" }",
+            ),
+            (
+                "ASK { ?s ?p <http://example.com/_:0123456789abcdef0123456789abcdef> }",
+                "ASK { ?s ?p <http://example.com/_:fedcba9876543210fedcba9876543210> }",
+            ),
+        ] {
+            assert_ne!(
+                normalize_query(&Query::parse(a, None)?),
+                normalize_query(&Query::parse(b, None)?)
+            );
+        }
+        // The projected variables are part of the results
+        assert_ne!(
+            normalize_query(&Query::parse(
+                "SELECT ?fedcba9876543210fedcba9876543210 WHERE { ?fedcba9876543210fedcba9876543210 ?p ?o }",
+                None
+            )?),
+            normalize_query(&Query::parse(
+                "SELECT ?0123456789abcdef0123456789abcdef WHERE { ?0123456789abcdef0123456789abcdef ?p ?o }",
+                None
+            )?)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_is_cacheable() -> Result<(), EvaluationError> {
+        for query in &[
+            "SELECT * WHERE { ?s ?p ?o FILTER(STRLEN(STR(?o)) > 2) }",
+            "SELECT (COUNT(*) AS ?c) WHERE { ?s ?p ?o } GROUP BY ?s",
+            "CONSTRUCT { ?s ?p ?o } WHERE { ?s ?p ?o }",
+            "ASK { ?s ?p ?o FILTER EXISTS { ?o ?p ?s } }",
+        ] {
+            assert!(is_cacheable(&Query::parse(query, None)?), "{}", query);
+        }
+        for query in &[
+            "SELECT * WHERE { SERVICE <http://example.com/s> { ?s ?p ?o } }",
+            "SELECT * WHERE { ?s ?p ?o BIND(RAND() AS ?r) }",
+            "SELECT ?s WHERE { ?s ?p ?o } ORDER BY (NOW())",
+            "SELECT (MAX(UUID()) AS ?u) WHERE { ?s ?p ?o }",
+            "SELECT * WHERE { ?s ?p ?o FILTER(STRUUID() != ?o) }",
+            "SELECT * WHERE { ?s ?p ?o OPTIONAL { ?o ?p ?s FILTER(BNODE() != ?o) } }",
+            "ASK { ?s ?p ?o FILTER EXISTS { SERVICE <http://example.com/s> { ?o ?p ?s } } }",
+            "CONSTRUCT { ?s ?p [] } WHERE { ?s ?p ?o }",
+        ] {
+            assert!(!is_cacheable(&Query::parse(query, None)?), "{}", query);
+        }
+        Ok(())
+    }
+
+    fn evaluate_counting(
+        cache: &QueryCache,
+        query: &str,
+        options: QueryOptions,
+        count: &mut usize,
+    ) -> Result<QueryResults, EvaluationError> {
+        cache.evaluate(Query::parse(query, None)?, options, |_, _| {
+            *count += 1;
+            Ok(QueryResults::Boolean(true))
+        })
+    }
+
+    #[test]
+    fn test_cache_eviction() -> Result<(), EvaluationError> {
+        let cache = QueryCache::default();
+        cache.set_capacity(2);
+        let mut count = 0;
+        let queries = ["ASK { ?s ?p 1 }", "ASK { ?s ?p 2 }", "ASK { ?s ?p 3 }"];
+        evaluate_counting(&cache, queries[0], QueryOptions::default(), &mut count)?;
+        evaluate_counting(&cache, queries[1], QueryOptions::default(), &mut count)?;
+        evaluate_counting(&cache, queries[0], QueryOptions::default(), &mut count)?;
+        assert_eq!(count, 2);
+        // The least recently used query is the second one
+        evaluate_counting(&cache, queries[2], QueryOptions::default(), &mut count)?;
+        evaluate_counting(&cache, queries[0], QueryOptions::default(), &mut count)?;
+        assert_eq!(count, 3);
+        evaluate_counting(&cache, queries[1], QueryOptions::default(), &mut count)?;
+        assert_eq!(count, 4);
+        evaluate_counting(&cache, queries[1], QueryOptions::default(), &mut count)?;
+        evaluate_counting(&cache, queries[0], QueryOptions::default(), &mut count)?;
+        assert_eq!(count, 4);
+        evaluate_counting(&cache, queries[2], QueryOptions::default(), &mut count)?;
+        assert_eq!(count, 5);
+        cache.set_capacity(1);
+        evaluate_counting(&cache, queries[2], QueryOptions::default(), &mut count)?;
+        assert_eq!(count, 5);
+        evaluate_counting(&cache, queries[0], QueryOptions::default(), &mut count)?;
+        assert_eq!(count, 6);
+        cache.invalidate();
+        evaluate_counting(&cache, queries[0], QueryOptions::default(), &mut count)?;
+        assert_eq!(count, 7);
+        Ok(())
+    }
+
+    #[test]
+    fn test_cache_key() -> Result<(), EvaluationError> {
+        let cache = QueryCache::default();
+        cache.set_capacity(10);
+        let mut count = 0;
+        let mut query = Query::parse("ASK { ?s ?p ?o }", None)?;
+        for _ in 0..2 {
+            cache.evaluate(query.clone(), QueryOptions::default(), |_, _| {
+                count += 1;
+                Ok(QueryResults::Boolean(false))
+            })?;
+        }
+        assert_eq!(count, 1);
+        query.dataset_mut().set_default_graph_as_union();
+        cache.evaluate(query, QueryOptions::default(), |_, _| {
+            count += 1;
+            Ok(QueryResults::Boolean(true))
+        })?;
+        assert_eq!(count, 2);
+        evaluate_counting(
+            &cache,
+            "ASK { ?s ?p ?o }",
+            QueryOptions::default().with_rdfs_entailment(),
+            &mut count,
+        )?;
+        assert_eq!(count, 3);
+        Ok(())
+    }
+
+    #[test]
+    fn test_results_size_cap() -> Result<(), EvaluationError> {
+        let cache = QueryCache::default();
+        cache.set_capacity(10);
+        let mut count = 0;
+        for len in &[MAX_CACHED_RESULTS_LEN, MAX_CACHED_RESULTS_LEN + 1] {
+            let query = format!("SELECT ?v WHERE {{ VALUES ?v {{ {} }} }}", len);
+            for _ in 0..2 {
+                let results = cache.evaluate(
+                    Query::parse(&query, None)?,
+                    QueryOptions::default(),
+                    |_, _| {
+                        count += 1;
+                        let variables = Arc::new(vec![Variable::new_unchecked("v")]);
+                        Ok(QueryResults::Solutions(QuerySolutionIter::new(
+                            variables,
+                            Box::new((0..*len).map(|_| Ok(vec![None]))),
+                        )))
+                    },
+                )?;
+                if let QueryResults::Solutions(solutions) = results {
+                    assert_eq!(solutions.count(), *len);
+                } else {
+                    panic!("solutions expected");
+                }
+            }
+        }
+        // The too large results are evaluated each time
+        assert_eq!(count, 3);
+        Ok(())
+    }
+}
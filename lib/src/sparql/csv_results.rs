@@ -5,8 +5,8 @@ use crate::model::{vocab::xsd, *};
 use crate::sparql::error::EvaluationError;
 use crate::sparql::model::*;
 use std::io::{self, BufRead, Write};
-use std::rc::Rc;
 use std::str::FromStr;
+use std::sync::Arc;
 
 pub fn write_csv_results(
     results: QueryResults,
@@ -67,6 +67,9 @@ fn write_csv_term<'a>(term: impl Into<TermRef<'a>>, mut sink: impl Write) -> io:
             sink.write_all(bnode.as_str().as_bytes())
         }
         TermRef::Literal(literal) => write_escaped_csv_string(literal.value(), &mut sink),
+        TermRef::Triple(triple) => {
+            write_escaped_csv_string(&TermRef::Triple(triple).to_string(), &mut sink)
+        }
     }
 }
 
@@ -158,10 +161,13 @@ fn write_tsv_term<'a>(term: impl Into<TermRef<'a>>, mut sink: impl Write) -> io:
             }
             _ => sink.write_all(literal.to_string().as_bytes()),
         },
+        TermRef::Triple(triple) => write!(sink, "{}", TermRef::Triple(triple)),
     }
 }
 
-pub fn read_tsv_results(mut source: impl BufRead + 'static) -> Result<QueryResults, io::Error> {
+pub fn read_tsv_results(
+    mut source: impl BufRead + Send + 'static,
+) -> Result<QueryResults, io::Error> {
     let mut buffer = String::new();
 
     // We read the header
@@ -178,7 +184,7 @@ pub fn read_tsv_results(mut source: impl BufRead + 'static) -> Result<QueryResul
         .collect::<Result<Vec<_>, io::Error>>()?;
 
     Ok(QueryResults::Solutions(QuerySolutionIter::new(
-        Rc::new(variables),
+        Arc::new(variables),
         Box::new(TsvResultsIterator { buffer, source }),
     )))
 }
@@ -221,12 +227,12 @@ impl<R: BufRead> TsvResultsIterator<R> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::rc::Rc;
     use std::str;
+    use std::sync::Arc;
 
     fn build_example() -> QueryResults {
         QuerySolutionIter::new(
-            Rc::new(vec![
+            Arc::new(vec![
                 Variable::new_unchecked("x"),
                 Variable::new_unchecked("literal"),
             ]),
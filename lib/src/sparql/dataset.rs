@@ -1,21 +1,33 @@
+use crate::model::vocab::{rdf, rdfs};
 use crate::sparql::algebra::QueryDataset;
+use crate::sparql::rdfs::{RdfsSchema, RdfsVocabulary};
 use crate::sparql::EvaluationError;
 use crate::store::numeric_encoder::{
-    EncodedQuad, EncodedTerm, ReadEncoder, StrContainer, StrEncodingAware, StrId, StrLookup,
+    EncodedQuad, EncodedRange, EncodedTerm, ReadEncoder, StrContainer, StrEncodingAware, StrId,
+    StrLookup, WriteEncoder,
 };
-use crate::store::ReadableEncodedStore;
+use crate::store::spatial::SearchBox;
+use crate::store::{BoxedEncodedQuadsIter, ReadableEncodedStore};
 use lasso::{Rodeo, Spur};
-use std::cell::RefCell;
 use std::iter::{empty, once, Once};
+use std::sync::{Arc, Mutex};
 
 pub(crate) struct DatasetView<S: ReadableEncodedStore> {
     store: S,
-    extra: RefCell<Rodeo>,
+    extra: Mutex<Rodeo>,
     dataset: EncodedDatasetSpec<S::StrId>,
+    rdfs: Option<Arc<RdfsSchema<DatasetStrId<S::StrId>>>>,
 }
 
 impl<S: ReadableEncodedStore> DatasetView<S> {
-    pub fn new(store: S, dataset: &QueryDataset) -> Result<Self, EvaluationError> {
+    /// Builds a view of the store restricted to the given dataset
+    ///
+    /// If `rdfs_entailment` is set, the view also contains the triples entailed under the RDFS entailment regime.
+    pub fn new(
+        store: S,
+        dataset: &QueryDataset,
+        rdfs_entailment: bool,
+    ) -> Result<Self, EvaluationError> {
         let dataset = EncodedDatasetSpec {
             default: dataset
                 .default_graph_graphs()
@@ -42,11 +54,57 @@ impl<S: ReadableEncodedStore> DatasetView<S> {
                 .transpose()
                 .map_err(|e| e.into())?,
         };
-        Ok(Self {
+        let mut view = Self {
             store,
-            extra: RefCell::new(Rodeo::default()),
+            extra: Mutex::new(Rodeo::default()),
             dataset,
-        })
+            rdfs: None,
+        };
+        if rdfs_entailment {
+            let mut encoder = &view;
+            let vocabulary = RdfsVocabulary {
+                type_: encoder.encode_named_node(rdf::TYPE)?,
+                sub_class_of: encoder.encode_named_node(rdfs::SUB_CLASS_OF)?,
+                sub_property_of: encoder.encode_named_node(rdfs::SUB_PROPERTY_OF)?,
+                domain: encoder.encode_named_node(rdfs::DOMAIN)?,
+                range: encoder.encode_named_node(rdfs::RANGE)?,
+            };
+            let schema = RdfsSchema::new(vocabulary, |predicate| {
+                view.encoded_quads_for_pattern(
+                    None,
+                    Some(predicate),
+                    None,
+                    Some(EncodedTerm::DefaultGraph),
+                )
+            })?;
+            view.rdfs = Some(Arc::new(schema));
+        }
+        Ok(view)
+    }
+
+    /// Returns if some strings not in the store have been encoded using this view
+    ///
+    /// The encoded terms built using this view are only valid for the other views of the same store if it is not the case.
+    pub fn has_temporary_strings(&self) -> bool {
+        !self.extra.lock().unwrap().is_empty()
+    }
+
+    /// Returns the quads of the dataset without entailment
+    fn encoded_asserted_quads_for_pattern(
+        &self,
+        subject: Option<EncodedTerm<DatasetStrId<S::StrId>>>,
+        predicate: Option<EncodedTerm<DatasetStrId<S::StrId>>>,
+        object: Option<EncodedTerm<DatasetStrId<S::StrId>>>,
+        graph_name: Option<EncodedTerm<DatasetStrId<S::StrId>>>,
+    ) -> Box<dyn Iterator<Item = Result<EncodedQuad<DatasetStrId<S::StrId>>, EvaluationError>> + Send>
+    {
+        if let Some((subject, predicate, object, graph_name)) =
+            try_map_quad_pattern(subject, predicate, object, graph_name)
+        {
+            self.encoded_quads_for_pattern_in_dataset(subject, predicate, object, graph_name)
+        } else {
+            Box::new(empty())
+        }
     }
 
     #[allow(clippy::needless_collect)]
@@ -56,7 +114,7 @@ impl<S: ReadableEncodedStore> DatasetView<S> {
         predicate: Option<EncodedTerm<S::StrId>>,
         object: Option<EncodedTerm<S::StrId>>,
         graph_name: Option<EncodedTerm<S::StrId>>,
-    ) -> Box<dyn Iterator<Item = Result<EncodedQuad<DatasetStrId<S::StrId>>, EvaluationError>>>
+    ) -> Box<dyn Iterator<Item = Result<EncodedQuad<DatasetStrId<S::StrId>>, EvaluationError>> + Send>
     {
         if let Some(graph_name) = graph_name {
             if graph_name.is_default_graph() {
@@ -149,6 +207,63 @@ impl<S: ReadableEncodedStore> DatasetView<S> {
             )
         }
     }
+
+    /// Applies the dataset to a store index lookup
+    ///
+    /// Returns `None` if the index can not be used, the generic lookup is then used as a fallback.
+    fn encoded_quads_for_index_lookup(
+        &self,
+        graph_name: Option<EncodedTerm<DatasetStrId<S::StrId>>>,
+        lookup: impl Fn(
+            Option<EncodedTerm<S::StrId>>,
+        ) -> Option<BoxedEncodedQuadsIter<S::StrId, S::Error>>,
+    ) -> Option<<Self as ReadableEncodedStore>::QuadsIter> {
+        if self.rdfs.is_some() {
+            // The indexes only contain the asserted quads
+            return None;
+        }
+        Some(if let Some(graph_name) = graph_name {
+            let graph_name = graph_name.try_map_id(unwrap_store_id).ok()?;
+            if graph_name.is_default_graph() {
+                if let Some(default_graph_graphs) = &self.dataset.default {
+                    if default_graph_graphs.len() != 1 {
+                        return None;
+                    }
+                    // Single graph optimization
+                    Box::new(
+                        map_iter(lookup(Some(default_graph_graphs[0]))?).map(|quad| {
+                            let quad = quad?;
+                            Ok(EncodedQuad::new(
+                                quad.subject,
+                                quad.predicate,
+                                quad.object,
+                                EncodedTerm::DefaultGraph,
+                            ))
+                        }),
+                    )
+                } else {
+                    Box::new(map_iter(lookup(None)?))
+                }
+            } else if self
+                .dataset
+                .named
+                .as_ref()
+                .map_or(true, |d| d.contains(&graph_name))
+            {
+                Box::new(map_iter(lookup(Some(graph_name))?))
+            } else {
+                Box::new(empty())
+            }
+        } else {
+            if self.dataset.named.is_some() {
+                return None;
+            }
+            Box::new(map_iter(lookup(None)?).filter(|quad| match quad {
+                Err(_) => true,
+                Ok(quad) => quad.graph_name != EncodedTerm::DefaultGraph,
+            }))
+        })
+    }
 }
 
 impl<S: ReadableEncodedStore> StrEncodingAware for DatasetView<S> {
@@ -160,14 +275,17 @@ impl<S: ReadableEncodedStore> StrLookup for DatasetView<S> {
     fn get_str(&self, id: DatasetStrId<S::StrId>) -> Result<Option<String>, EvaluationError> {
         match id {
             DatasetStrId::Store(id) => self.store.get_str(id).map_err(|e| e.into()),
-            DatasetStrId::Temporary(id) => {
-                Ok(self.extra.borrow().try_resolve(&id).map(|e| e.to_owned()))
-            }
+            DatasetStrId::Temporary(id) => Ok(self
+                .extra
+                .lock()
+                .unwrap()
+                .try_resolve(&id)
+                .map(|e| e.to_owned())),
         }
     }
 
     fn get_str_id(&self, value: &str) -> Result<Option<DatasetStrId<S::StrId>>, EvaluationError> {
-        if let Some(id) = self.extra.borrow().get(value) {
+        if let Some(id) = self.extra.lock().unwrap().get(value) {
             Ok(Some(DatasetStrId::Temporary(id)))
         } else {
             Ok(self
@@ -180,8 +298,9 @@ impl<S: ReadableEncodedStore> StrLookup for DatasetView<S> {
 }
 
 impl<S: ReadableEncodedStore> ReadableEncodedStore for DatasetView<S> {
-    type QuadsIter =
-        Box<dyn Iterator<Item = Result<EncodedQuad<DatasetStrId<S::StrId>>, EvaluationError>>>;
+    type QuadsIter = Box<
+        dyn Iterator<Item = Result<EncodedQuad<DatasetStrId<S::StrId>>, EvaluationError>> + Send,
+    >;
     type GraphsIter = Once<Result<EncodedTerm<DatasetStrId<S::StrId>>, EvaluationError>>;
 
     fn encoded_quads_for_pattern(
@@ -190,17 +309,60 @@ impl<S: ReadableEncodedStore> ReadableEncodedStore for DatasetView<S> {
         predicate: Option<EncodedTerm<Self::StrId>>,
         object: Option<EncodedTerm<Self::StrId>>,
         graph_name: Option<EncodedTerm<Self::StrId>>,
-    ) -> Box<dyn Iterator<Item = Result<EncodedQuad<DatasetStrId<S::StrId>>, EvaluationError>>>
+    ) -> Box<dyn Iterator<Item = Result<EncodedQuad<DatasetStrId<S::StrId>>, EvaluationError>> + Send>
     {
-        if let Some((subject, predicate, object, graph_name)) =
-            try_map_quad_pattern(subject, predicate, object, graph_name)
-        {
-            self.encoded_quads_for_pattern_in_dataset(subject, predicate, object, graph_name)
+        if let Some(rdfs) = &self.rdfs {
+            Box::new(rdfs.quads_for_pattern(
+                subject,
+                predicate,
+                object,
+                graph_name,
+                |s, p, o, g| self.encoded_asserted_quads_for_pattern(s, p, o, g),
+            ))
         } else {
-            Box::new(empty())
+            self.encoded_asserted_quads_for_pattern(subject, predicate, object, graph_name)
         }
     }
 
+    fn encoded_quads_for_predicate_object_range(
+        &self,
+        predicate: EncodedTerm<Self::StrId>,
+        range: EncodedRange,
+        graph_name: Option<EncodedTerm<Self::StrId>>,
+    ) -> Option<Self::QuadsIter> {
+        let predicate = predicate.try_map_id(unwrap_store_id).ok()?;
+        self.encoded_quads_for_index_lookup(graph_name, |graph_name| {
+            self.store
+                .encoded_quads_for_predicate_object_range(predicate, range, graph_name)
+        })
+    }
+
+    fn encoded_quads_for_predicate_object_token(
+        &self,
+        predicate: EncodedTerm<Self::StrId>,
+        token: &str,
+        graph_name: Option<EncodedTerm<Self::StrId>>,
+    ) -> Option<Self::QuadsIter> {
+        let predicate = predicate.try_map_id(unwrap_store_id).ok()?;
+        self.encoded_quads_for_index_lookup(graph_name, |graph_name| {
+            self.store
+                .encoded_quads_for_predicate_object_token(predicate, token, graph_name)
+        })
+    }
+
+    fn encoded_quads_for_predicate_object_box(
+        &self,
+        predicate: EncodedTerm<Self::StrId>,
+        search_box: SearchBox,
+        graph_name: Option<EncodedTerm<Self::StrId>>,
+    ) -> Option<Self::QuadsIter> {
+        let predicate = predicate.try_map_id(unwrap_store_id).ok()?;
+        self.encoded_quads_for_index_lookup(graph_name, |graph_name| {
+            self.store
+                .encoded_quads_for_predicate_object_box(predicate, search_box, graph_name)
+        })
+    }
+
     fn encoded_named_graphs(&self) -> Self::GraphsIter {
         once(Err(EvaluationError::msg(
             "Graphs lookup is not implemented by DatasetView",
@@ -273,7 +435,7 @@ impl<'a, S: ReadableEncodedStore> StrContainer for &'a DatasetView<S> {
             Ok(DatasetStrId::Store(id))
         } else {
             Ok(DatasetStrId::Temporary(
-                self.extra.borrow_mut().get_or_intern(value),
+                self.extra.lock().unwrap().get_or_intern(value),
             ))
         }
     }
@@ -0,0 +1,303 @@
+use crate::io::{DatasetFormat, DatasetSerializer, GraphFormat, GraphSerializer, RdfFormat};
+use crate::model::geo::GEOF;
+use crate::model::vocab::{rdf, xsd};
+use crate::model::*;
+use crate::sparql::model::QueryResultsFormat;
+use std::io;
+use std::io::Write;
+
+const SD: &str = "http://www.w3.org/ns/sparql-service-description#";
+const VOID_TRIPLES: &str = "http://rdfs.org/ns/void#triples";
+
+/// The extension functions implemented by the SPARQL evaluator
+const EXTENSION_FUNCTIONS: &[&str] = &[
+    "http://oxigraph.org/fn/textMatch",
+    "http://oxigraph.org/fn/textScore",
+];
+const GEO_FUNCTIONS: &[&str] = &[
+    "distance",
+    "buffer",
+    "sfIntersects",
+    "sfDisjoint",
+    "sfWithin",
+    "sfContains",
+    "envelope",
+    "convexHull",
+];
+
+/// A [SPARQL 1.1 Service Description](https://www.w3.org/TR/sparql11-service-description/) of the capabilities of a store.
+///
+/// It describes the supported query languages, results formats, features and extension functions
+/// and, if known, the dataset of the store.
+///
+/// Descriptions of the stores content are returned by their `service_description` method.
+///
+/// Usage example:
+/// ```
+/// use oxigraph::MemoryStore;
+/// use oxigraph::io::GraphFormat;
+/// use oxigraph::model::*;
+///
+/// let store = MemoryStore::new();
+/// let ex = NamedNode::new("http://example.com")?;
+/// store.insert(Quad::new(ex.clone(), ex.clone(), ex.clone(), ex.clone()));
+///
+/// let description = store.service_description().with_endpoint(NamedNode::new("http://example.com/query")?);
+/// let mut buffer = Vec::new();
+/// description.write(&mut buffer, GraphFormat::NTriples)?;
+/// assert!(String::from_utf8(buffer)?.contains("<http://www.w3.org/ns/sparql-service-description#endpoint> <http://example.com/query> ."));
+/// # Result::<_,Box<dyn std::error::Error>>::Ok(())
+/// ```
+#[derive(Eq, PartialEq, Debug, Clone)]
+pub struct ServiceDescription {
+    endpoint: Option<NamedNode>,
+    union_default_graph: bool,
+    extension_functions: Vec<NamedNode>,
+    dataset: Option<DatasetDescription>,
+}
+
+#[derive(Eq, PartialEq, Debug, Clone)]
+struct DatasetDescription {
+    triples: Option<usize>,
+    named_graphs: Vec<NamedNode>,
+}
+
+impl ServiceDescription {
+    /// Builds the description of a SPARQL endpoint based on this library without information about its dataset
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the IRI of the endpoint
+    pub fn with_endpoint(mut self, endpoint: impl Into<NamedNode>) -> Self {
+        self.endpoint = Some(endpoint.into());
+        self
+    }
+
+    /// States that the default graph of the queries is the union of all the graphs of the store
+    pub fn with_union_default_graph(mut self) -> Self {
+        self.union_default_graph = true;
+        self
+    }
+
+    /// Adds an extension function supported by the endpoint
+    pub fn with_extension_function(mut self, function: impl Into<NamedNode>) -> Self {
+        let function = function.into();
+        if !self.extension_functions.contains(&function) {
+            self.extension_functions.push(function);
+        }
+        self
+    }
+
+    /// Describes the dataset of the endpoint using the names of its named graphs
+    ///
+    /// The named graphs identified by blank nodes are not described.
+    pub fn with_dataset(
+        mut self,
+        named_graphs: impl IntoIterator<Item = NamedOrBlankNode>,
+    ) -> Self {
+        let named_graphs = named_graphs
+            .into_iter()
+            .filter_map(|graph| {
+                if let NamedOrBlankNode::NamedNode(graph) = graph {
+                    Some(graph)
+                } else {
+                    None
+                }
+            })
+            .collect();
+        if let Some(dataset) = &mut self.dataset {
+            dataset.named_graphs = named_graphs;
+        } else {
+            self.dataset = Some(DatasetDescription {
+                triples: None,
+                named_graphs,
+            });
+        }
+        self
+    }
+
+    /// Adds the total number of triples of the dataset of the endpoint using the `void:triples` property
+    pub fn with_triples_count(mut self, triples: usize) -> Self {
+        if let Some(dataset) = &mut self.dataset {
+            dataset.triples = Some(triples);
+        } else {
+            self.dataset = Some(DatasetDescription {
+                triples: Some(triples),
+                named_graphs: Vec::new(),
+            });
+        }
+        self
+    }
+
+    /// The IRI of the endpoint if set
+    pub fn endpoint(&self) -> Option<&NamedNode> {
+        self.endpoint.as_ref()
+    }
+
+    /// The supported extension functions
+    pub fn extension_functions(&self) -> &[NamedNode] {
+        &self.extension_functions
+    }
+
+    /// Returns the description as RDF triples
+    pub fn triples(&self) -> Vec<Triple> {
+        let mut triples = Vec::new();
+        let service = BlankNode::default();
+        add(&mut triples, service.clone(), rdf::TYPE, sd("Service"));
+        if let Some(endpoint) = &self.endpoint {
+            add(
+                &mut triples,
+                service.clone(),
+                sd("endpoint"),
+                endpoint.clone(),
+            );
+        }
+        for language in &["SPARQL10Query", "SPARQL11Query", "SPARQL11Update"] {
+            add(
+                &mut triples,
+                service.clone(),
+                sd("supportedLanguage"),
+                sd(language),
+            );
+        }
+        let result_formats = [
+            QueryResultsFormat::Xml.iri(),
+            QueryResultsFormat::Json.iri(),
+            QueryResultsFormat::Csv.iri(),
+            QueryResultsFormat::Tsv.iri(),
+            GraphFormat::NTriples.iri(),
+            GraphFormat::Turtle.iri(),
+            GraphFormat::RdfXml.iri(),
+            DatasetFormat::JsonLd.iri(),
+        ];
+        for format in &result_formats {
+            add(
+                &mut triples,
+                service.clone(),
+                sd("resultFormat"),
+                NamedNode::new_unchecked(*format),
+            );
+        }
+        add(
+            &mut triples,
+            service.clone(),
+            sd("feature"),
+            sd("BasicFederatedQuery"),
+        );
+        if self.union_default_graph {
+            add(
+                &mut triples,
+                service.clone(),
+                sd("feature"),
+                sd("UnionDefaultGraph"),
+            );
+        }
+        for function in &self.extension_functions {
+            add(
+                &mut triples,
+                service.clone(),
+                sd("extensionFunction"),
+                function.clone(),
+            );
+            add(&mut triples, function.clone(), rdf::TYPE, sd("Function"));
+        }
+        if let Some(dataset_description) = &self.dataset {
+            let dataset = BlankNode::default();
+            add(
+                &mut triples,
+                service.clone(),
+                sd("defaultDataset"),
+                dataset.clone(),
+            );
+            add(&mut triples, dataset.clone(), rdf::TYPE, sd("Dataset"));
+            if let Some(triples_count) = dataset_description.triples {
+                add(
+                    &mut triples,
+                    dataset.clone(),
+                    NamedNode::new_unchecked(VOID_TRIPLES),
+                    Literal::new_typed_literal(triples_count.to_string(), xsd::INTEGER),
+                );
+            }
+            let default_graph = BlankNode::default();
+            add(
+                &mut triples,
+                dataset.clone(),
+                sd("defaultGraph"),
+                default_graph.clone(),
+            );
+            add(&mut triples, default_graph, rdf::TYPE, sd("Graph"));
+            for graph_name in &dataset_description.named_graphs {
+                let named_graph = BlankNode::default();
+                add(
+                    &mut triples,
+                    dataset.clone(),
+                    sd("namedGraph"),
+                    named_graph.clone(),
+                );
+                add(
+                    &mut triples,
+                    named_graph.clone(),
+                    rdf::TYPE,
+                    sd("NamedGraph"),
+                );
+                add(&mut triples, named_graph, sd("name"), graph_name.clone());
+            }
+        }
+        triples
+    }
+
+    /// Writes the description into `writer` using the given format
+    ///
+    /// With a dataset format like [JSON-LD](crate::io::DatasetFormat::JsonLd) the description is written in the default graph.
+    pub fn write(&self, writer: impl Write, format: impl Into<RdfFormat>) -> Result<(), io::Error> {
+        match format.into() {
+            RdfFormat::Graph(format) => {
+                let mut writer = GraphSerializer::from_format(format).triple_writer(writer)?;
+                for triple in self.triples() {
+                    writer.write(&triple)?;
+                }
+                writer.finish()
+            }
+            RdfFormat::Dataset(format) => {
+                let mut writer = DatasetSerializer::from_format(format).quad_writer(writer)?;
+                for triple in self.triples() {
+                    writer.write(&triple.in_graph(GraphName::DefaultGraph))?;
+                }
+                writer.finish()
+            }
+        }
+    }
+}
+
+impl Default for ServiceDescription {
+    fn default() -> Self {
+        Self {
+            endpoint: None,
+            union_default_graph: false,
+            extension_functions: EXTENSION_FUNCTIONS
+                .iter()
+                .map(|function| NamedNode::new_unchecked(*function))
+                .chain(
+                    GEO_FUNCTIONS
+                        .iter()
+                        .map(|function| NamedNode::new_unchecked(format!("{}{}", GEOF, function))),
+                )
+                .collect(),
+            dataset: None,
+        }
+    }
+}
+
+fn sd(name: &str) -> NamedNode {
+    NamedNode::new_unchecked(format!("{}{}", SD, name))
+}
+
+fn add(
+    triples: &mut Vec<Triple>,
+    subject: impl Into<Subject>,
+    predicate: impl Into<NamedNode>,
+    object: impl Into<Term>,
+) {
+    triples.push(Triple::new(subject, predicate, object))
+}
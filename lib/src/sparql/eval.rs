@@ -1,50 +1,94 @@
+use crate::model::geo::{DistanceUnit, Geometry, EARTH_RADIUS};
 use crate::model::vocab::{rdf, xsd};
 use crate::model::xsd::*;
-use crate::model::Triple;
-use crate::model::{BlankNode, LiteralRef, NamedNodeRef};
+use crate::model::{BlankNode, Literal, LiteralRef, NamedNodeRef, Term, Triple};
 use crate::sparql::algebra::{GraphPattern, Query, QueryDataset};
 use crate::sparql::error::EvaluationError;
 use crate::sparql::model::*;
 use crate::sparql::plan::*;
-use crate::sparql::service::ServiceHandler;
+use crate::sparql::service::EvaluatorServiceHandler;
 use crate::store::numeric_encoder::*;
 use crate::store::small_string::SmallString;
+use crate::store::spatial::encoded_term_geometry;
+use crate::store::text::{text_match, text_score};
 use crate::store::ReadableEncodedStore;
 use digest::Digest;
+#[cfg(feature = "async_service")]
+use futures::executor::{block_on, block_on_stream};
+#[cfg(feature = "async_service")]
+use futures::future::{join_all, ready, BoxFuture, FutureExt};
+#[cfg(feature = "async_service")]
+use futures::stream::{self, StreamExt};
 use md5::Md5;
 use oxilangtag::LanguageTag;
 use oxiri::Iri;
 use rand::random;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 use regex::{Regex, RegexBuilder};
 use sha1::Sha1;
 use sha2::{Sha256, Sha384, Sha512};
 use std::cmp::Ordering;
+#[cfg(feature = "parallel")]
+use std::collections::hash_map::DefaultHasher;
 use std::collections::{HashMap, HashSet};
 use std::convert::{TryFrom, TryInto};
 use std::hash::Hash;
+#[cfg(feature = "parallel")]
+use std::hash::Hasher;
 use std::iter::Iterator;
 use std::iter::{empty, once};
-use std::rc::Rc;
 use std::str;
+use std::sync::Arc;
+#[cfg(feature = "async_service")]
+use std::sync::Mutex;
 
 const REGEX_SIZE_LIMIT: usize = 1_000_000;
-
-type EncodedTuplesIterator<I> = Box<dyn Iterator<Item = Result<EncodedTuple<I>, EvaluationError>>>;
-
-pub(crate) struct SimpleEvaluator<S> {
-    dataset: Rc<S>,
-    base_iri: Option<Rc<Iri<String>>>,
+/// The minimal number of tuples given to each task during parallel evaluation
+#[cfg(feature = "parallel")]
+const PARALLEL_BATCH_SIZE: usize = 256;
+/// The maximal number of pending SERVICE calls of a bind join
+#[cfg(feature = "async_service")]
+const MAX_CONCURRENT_SERVICE_CALLS: usize = 8;
+
+type EncodedTuplesIterator<I> =
+    Box<dyn Iterator<Item = Result<EncodedTuple<I>, EvaluationError>> + Send>;
+
+/// A pending SERVICE call
+#[cfg(feature = "async_service")]
+type ServiceCall = BoxFuture<'static, Result<QueryResults, EvaluationError>>;
+/// A SERVICE call, done when it is created if the `"async_service"` feature is disabled
+#[cfg(not(feature = "async_service"))]
+type ServiceCall = Result<QueryResults, EvaluationError>;
+
+/// The results of SERVICE calls identified by the id of the SERVICE plan node and its input tuple
+#[cfg(feature = "async_service")]
+type ServiceResults<I> = HashMap<(usize, EncodedTuple<I>), Result<QueryResults, EvaluationError>>;
+
+pub(crate) struct SimpleEvaluator<S: ReadableEncodedStore> {
+    dataset: Arc<S>,
+    base_iri: Option<Arc<Iri<String>>>,
     now: DateTime,
-    service_handler: Rc<dyn ServiceHandler<Error = EvaluationError>>,
+    service_handler: Arc<EvaluatorServiceHandler>,
+    #[cfg(feature = "async_service")]
+    service_results: Arc<Mutex<ServiceResults<S::StrId>>>,
+    parameters: Arc<EncodedTuple<S::StrId>>,
+    parallel: bool,
+    service_batch_size: usize,
 }
 
-impl<S> Clone for SimpleEvaluator<S> {
+impl<S: ReadableEncodedStore> Clone for SimpleEvaluator<S> {
     fn clone(&self) -> Self {
         Self {
             dataset: self.dataset.clone(),
             base_iri: self.base_iri.clone(),
             now: self.now,
             service_handler: self.service_handler.clone(),
+            #[cfg(feature = "async_service")]
+            service_results: self.service_results.clone(),
+            parameters: self.parameters.clone(),
+            parallel: self.parallel,
+            service_batch_size: self.service_batch_size,
         }
     }
 }
@@ -54,22 +98,56 @@ where
     for<'a> &'a S: StrContainer<StrId = S::StrId>,
 {
     pub fn new(
-        dataset: Rc<S>,
-        base_iri: Option<Rc<Iri<String>>>,
-        service_handler: Rc<dyn ServiceHandler<Error = EvaluationError>>,
+        dataset: Arc<S>,
+        base_iri: Option<Arc<Iri<String>>>,
+        service_handler: Arc<EvaluatorServiceHandler>,
     ) -> Self {
         Self {
             dataset,
             base_iri,
             now: DateTime::now().unwrap(),
             service_handler,
+            #[cfg(feature = "async_service")]
+            service_results: Arc::default(),
+            parameters: Arc::new(EncodedTuple::with_capacity(0)),
+            parallel: false,
+            service_batch_size: 0,
         }
     }
 
+    /// Sets the values of the variables bound by the [`PlanNode::Parameters`] nodes
+    pub fn with_parameters(mut self, parameters: EncodedTuple<S::StrId>) -> Self {
+        self.parameters = Arc::new(parameters);
+        self
+    }
+
+    /// Evaluates the UNION, basic graph pattern and GROUP BY operators using the rayon thread pool
+    ///
+    /// The evaluation stays sequential if the `"parallel"` feature is disabled.
+    pub fn with_parallel_evaluation(mut self) -> Self {
+        self.parallel = true;
+        self
+    }
+
+    /// Joins the SERVICE clauses with the rest of the query by sending the bindings of the join variables
+    /// by batches of `batch_size` tuples in a VALUES clause
+    ///
+    /// The SERVICE clauses are evaluated independently if `batch_size` is `0`.
+    pub fn with_service_batch_size(mut self, batch_size: usize) -> Self {
+        self.service_batch_size = batch_size;
+        self
+    }
+
+    fn without_parallel_evaluation(&self) -> Self {
+        let mut eval = self.clone();
+        eval.parallel = false;
+        eval
+    }
+
     pub fn evaluate_select_plan(
         &self,
         plan: &PlanNode<S::StrId>,
-        variables: Rc<Vec<Variable>>,
+        variables: Arc<Vec<Variable>>,
     ) -> Result<QueryResults, EvaluationError> {
         let iter = self.eval_plan(plan, EncodedTuple::with_capacity(variables.len()));
         Ok(QueryResults::Solutions(
@@ -82,7 +160,12 @@ where
         plan: &PlanNode<S::StrId>,
     ) -> Result<QueryResults, EvaluationError> {
         let from = EncodedTuple::with_capacity(plan.maybe_bound_variables().len());
-        match self.eval_plan(plan, from).next() {
+        // Only the first solution is needed so we do not evaluate in parallel
+        match self
+            .without_parallel_evaluation()
+            .eval_plan(plan, from)
+            .next()
+        {
             Some(Ok(_)) => Ok(QueryResults::Boolean(true)),
             Some(Err(error)) => Err(error),
             None => Ok(QueryResults::Boolean(false)),
@@ -125,17 +208,63 @@ where
         node: &PlanNode<S::StrId>,
         from: EncodedTuple<S::StrId>,
     ) -> EncodedTuplesIterator<S::StrId> {
+        #[cfg(feature = "async_service")]
+        if let PlanNode::Join { .. } | PlanNode::AntiJoin { .. } | PlanNode::Union { .. } = node {
+            let started = self.start_independent_services(node, &from);
+            if !started.is_empty() {
+                return Box::new(StartedServicesIterator {
+                    iter: self.eval_node(node, from),
+                    results: self.service_results.clone(),
+                    started,
+                });
+            }
+        }
+        self.eval_node(node, from)
+    }
+
+    fn eval_node(
+        &self,
+        node: &PlanNode<S::StrId>,
+        from: EncodedTuple<S::StrId>,
+    ) -> EncodedTuplesIterator<S::StrId> {
+        if self.parallel {
+            if let Some(iter) = self.eval_plan_in_parallel(node, &from) {
+                return iter;
+            }
+        }
         match node {
             PlanNode::Init => Box::new(once(Ok(from))),
+            PlanNode::Parameters { child } => {
+                let parameters = self.parameters.clone();
+                if let Some(from) = from.combine_with(&parameters) {
+                    // Some nodes like VALUES do not keep the input bindings so we check them again
+                    Box::new(
+                        self.eval_plan(child, from)
+                            .filter_map(move |tuple| match tuple {
+                                Ok(tuple) => tuple.combine_with(&parameters).map(Ok),
+                                Err(error) => Some(Err(error)),
+                            }),
+                    )
+                } else {
+                    Box::new(empty())
+                }
+            }
             PlanNode::StaticBindings { tuples } => Box::new(tuples.clone().into_iter().map(Ok)),
             PlanNode::Service {
+                id,
                 variables,
                 silent,
                 service_name,
                 graph_pattern,
                 ..
             } => {
-                match self.evaluate_service(service_name, graph_pattern, variables.clone(), &from) {
+                match self.evaluate_service(
+                    *id,
+                    service_name,
+                    graph_pattern,
+                    variables.clone(),
+                    &from,
+                ) {
                     Ok(result) => Box::new(result.flat_map(move |binding| {
                         binding
                             .map(|binding| binding.combine_with(&from))
@@ -156,20 +285,59 @@ where
                 predicate,
                 object,
                 graph_name,
+                object_range,
+                object_token,
+                object_box,
             } => {
                 let eval = self.clone();
                 let subject = *subject;
                 let predicate = *predicate;
                 let object = *object;
                 let graph_name = *graph_name;
+                let object_range = *object_range;
+                let object_token = object_token.clone();
+                let object_box = *object_box;
                 Box::new(self.eval_plan(child, from).flat_map_ok(move |tuple| {
-                    let mut iter: Box<dyn Iterator<Item = _>> =
-                        Box::new(eval.dataset.encoded_quads_for_pattern(
-                            get_pattern_value(&subject, &tuple),
-                            get_pattern_value(&predicate, &tuple),
-                            get_pattern_value(&object, &tuple),
-                            get_pattern_value(&graph_name, &tuple),
-                        ));
+                    let input_subject = get_pattern_value(&subject, &tuple);
+                    let input_predicate = get_pattern_value(&predicate, &tuple);
+                    let input_object = get_pattern_value(&object, &tuple);
+                    let input_graph_name = get_pattern_value(&graph_name, &tuple);
+                    let index_iter = match (input_subject, input_predicate, input_object) {
+                        (None, Some(input_predicate), None) => object_range
+                            .and_then(|range| {
+                                eval.dataset.encoded_quads_for_predicate_object_range(
+                                    input_predicate,
+                                    range,
+                                    input_graph_name,
+                                )
+                            })
+                            .or_else(|| {
+                                eval.dataset.encoded_quads_for_predicate_object_token(
+                                    input_predicate,
+                                    object_token.as_deref()?,
+                                    input_graph_name,
+                                )
+                            })
+                            .or_else(|| {
+                                eval.dataset.encoded_quads_for_predicate_object_box(
+                                    input_predicate,
+                                    object_box?,
+                                    input_graph_name,
+                                )
+                            }),
+                        _ => None,
+                    };
+                    let mut iter: Box<dyn Iterator<Item = _> + Send> =
+                        if let Some(iter) = index_iter {
+                            iter
+                        } else {
+                            Box::new(eval.dataset.encoded_quads_for_pattern(
+                                input_subject,
+                                input_predicate,
+                                input_object,
+                                input_graph_name,
+                            ))
+                        };
                     if subject.is_var() && subject == predicate {
                         iter = Box::new(iter.filter(|quad| match quad {
                             Err(_) => true,
@@ -220,6 +388,41 @@ where
                     iter
                 }))
             }
+            PlanNode::QuotedTriplePattern {
+                child,
+                triple,
+                subject,
+                predicate,
+                object,
+            } => {
+                let eval = self.clone();
+                let triple = *triple;
+                let components = [*subject, *predicate, *object];
+                Box::new(self.eval_plan(child, from).filter_map(move |tuple| {
+                    let mut tuple = match tuple {
+                        Ok(tuple) => tuple,
+                        Err(error) => return Some(Err(error)),
+                    };
+                    let input_triple = if let Some(input_triple) = get_pattern_value(&triple, &tuple) {
+                        input_triple
+                    } else {
+                        return Some(Err(EvaluationError::msg(
+                            "The quoted triple pattern should be evaluated after a pattern binding its triple",
+                        )));
+                    };
+                    let values = eval.triple_components(input_triple)?;
+                    for (component, value) in components.iter().zip(values.iter()) {
+                        if let Some(input_value) = get_pattern_value(component, &tuple) {
+                            if input_value != *value {
+                                return None;
+                            }
+                        } else {
+                            put_pattern_value(component, *value, &mut tuple);
+                        }
+                    }
+                    Some(Ok(tuple))
+                }))
+            }
             PlanNode::PathPatternJoin {
                 child,
                 subject,
@@ -288,6 +491,9 @@ where
                     }
                 }))
             }
+            PlanNode::Join { left, right } if self.is_service_bind_join(left, right) => {
+                self.eval_service_bind_join(left, right, from, false)
+            }
             PlanNode::Join { left, right } => {
                 //TODO: very dumb implementation
                 let mut errors = Vec::default();
@@ -318,6 +524,13 @@ where
                     right,
                 })
             }
+            PlanNode::LeftJoin {
+                left,
+                right,
+                possible_problem_vars,
+            } if possible_problem_vars.is_empty() && self.is_service_bind_join(left, right) => {
+                self.eval_service_bind_join(left, right, from, true)
+            }
             PlanNode::LeftJoin {
                 left,
                 right,
@@ -325,14 +538,14 @@ where
             } => {
                 if possible_problem_vars.is_empty() {
                     Box::new(LeftJoinIterator {
-                        eval: self.clone(),
+                        eval: self.without_parallel_evaluation(),
                         right_plan: right.clone(),
                         left_iter: self.eval_plan(left, from),
                         current_right: Box::new(empty()),
                     })
                 } else {
                     Box::new(BadLeftJoinIterator {
-                        eval: self.clone(),
+                        eval: self.without_parallel_evaluation(),
                         right_plan: right.clone(),
                         left_iter: self.eval_plan(left, from),
                         current_left: None,
@@ -342,7 +555,7 @@ where
                 }
             }
             PlanNode::Filter { child, expression } => {
-                let eval = self.clone();
+                let eval = self.without_parallel_evaluation();
                 let expression = expression.clone();
                 Box::new(self.eval_plan(child, from).filter(move |tuple| {
                     match tuple {
@@ -366,7 +579,7 @@ where
                 position,
                 expression,
             } => {
-                let eval = self.clone();
+                let eval = self.without_parallel_evaluation();
                 let position = *position;
                 let expression = expression.clone();
                 Box::new(self.eval_plan(child, from).map(move |tuple| {
@@ -416,7 +629,14 @@ where
                 Box::new(hash_deduplicate(self.eval_plan(child, from)))
             }
             PlanNode::Skip { child, count } => Box::new(self.eval_plan(child, from).skip(*count)),
-            PlanNode::Limit { child, count } => Box::new(self.eval_plan(child, from).take(*count)),
+            PlanNode::Limit { child, count } => {
+                // The parallel evaluation reads its input ahead, we keep LIMIT lazy
+                Box::new(
+                    self.without_parallel_evaluation()
+                        .eval_plan(child, from)
+                        .take(*count),
+                )
+            }
             PlanNode::Project { child, mapping } => {
                 //TODO: use from somewhere?
                 let mapping = mapping.clone();
@@ -510,24 +730,174 @@ where
         }
     }
 
+    /// Evaluates the node using the rayon thread pool
+    ///
+    /// Returns `None` if the node should be evaluated sequentially.
+    /// The pipelines only read their input a partition ahead for each thread,
+    /// the other nodes materialize their results.
+    #[cfg(feature = "parallel")]
+    fn eval_plan_in_parallel(
+        &self,
+        node: &PlanNode<S::StrId>,
+        from: &EncodedTuple<S::StrId>,
+    ) -> Option<EncodedTuplesIterator<S::StrId>> {
+        match node {
+            PlanNode::Union { children } => {
+                let results = self.eval_plans_in_parallel(
+                    children
+                        .iter()
+                        .map(|child| (child.clone(), from.clone()))
+                        .collect(),
+                );
+                Some(Box::new(results.into_iter().flatten()))
+            }
+            PlanNode::QuadPatternJoin { .. }
+            | PlanNode::PathPatternJoin { .. }
+            | PlanNode::QuotedTriplePattern { .. }
+            | PlanNode::Filter { .. }
+            | PlanNode::Extend { .. } => {
+                // We split the output of the deepest operator of the pipeline, usually a scan,
+                // in partitions read on demand and evaluate the other operators on each of them
+                let mut pipeline = vec![node];
+                while let Some(child) = pipeline_child(pipeline[pipeline.len() - 1]) {
+                    pipeline.push(child);
+                }
+                let first = pipeline.pop()?;
+                if pipeline.is_empty() {
+                    return None;
+                }
+                Some(Box::new(PipelineIterator {
+                    eval: self.without_parallel_evaluation(),
+                    input: self
+                        .without_parallel_evaluation()
+                        .eval_plan(first, from.clone()),
+                    pipeline: pipeline.into_iter().cloned().collect(),
+                    from: from.clone(),
+                    results: Vec::new().into_iter(),
+                }))
+            }
+            PlanNode::Aggregate {
+                child,
+                key_mapping,
+                aggregates,
+            } if !key_mapping.is_empty() => {
+                // Each group is computed by a single task
+                let (errors, tuples) = self.materialize(child, from.clone());
+                if tuples.len() < 2 * PARALLEL_BATCH_SIZE {
+                    let node = PlanNode::Aggregate {
+                        child: Arc::new(PlanNode::StaticBindings { tuples }),
+                        key_mapping: key_mapping.clone(),
+                        aggregates: aggregates.clone(),
+                    };
+                    let results = self
+                        .without_parallel_evaluation()
+                        .eval_plan(&node, from.clone());
+                    return Some(Box::new(errors.into_iter().map(Err).chain(results)));
+                }
+                let mut buckets = vec![Vec::new(); rayon::current_num_threads()];
+                for tuple in tuples {
+                    let mut hasher = DefaultHasher::new();
+                    for (v, _) in key_mapping.iter() {
+                        tuple.get(*v).hash(&mut hasher);
+                    }
+                    let bucket =
+                        usize::try_from(hasher.finish() % buckets.len() as u64).unwrap_or(0);
+                    buckets[bucket].push(tuple);
+                }
+                let results = self.eval_plans_in_parallel(
+                    buckets
+                        .into_iter()
+                        .filter(|tuples| !tuples.is_empty())
+                        .map(|tuples| {
+                            let node = PlanNode::Aggregate {
+                                child: Arc::new(PlanNode::StaticBindings { tuples }),
+                                key_mapping: key_mapping.clone(),
+                                aggregates: aggregates.clone(),
+                            };
+                            (Arc::new(node), from.clone())
+                        })
+                        .collect(),
+                );
+                Some(Box::new(
+                    errors
+                        .into_iter()
+                        .map(Err)
+                        .chain(results.into_iter().flatten()),
+                ))
+            }
+            _ => None,
+        }
+    }
+
+    /// Sequential fallback used when the `"parallel"` feature is disabled
+    #[cfg(not(feature = "parallel"))]
+    fn eval_plan_in_parallel(
+        &self,
+        _: &PlanNode<S::StrId>,
+        _: &EncodedTuple<S::StrId>,
+    ) -> Option<EncodedTuplesIterator<S::StrId>> {
+        None
+    }
+
+    /// Evaluates each plan in a rayon task and returns their results in the same order
+    #[cfg(feature = "parallel")]
+    fn eval_plans_in_parallel(
+        &self,
+        tasks: Vec<(Arc<PlanNode<S::StrId>>, EncodedTuple<S::StrId>)>,
+    ) -> Vec<Vec<Result<EncodedTuple<S::StrId>, EvaluationError>>> {
+        let eval = self.without_parallel_evaluation();
+        tasks
+            .into_par_iter()
+            .map(|(plan, from)| eval.eval_plan(&plan, from).collect())
+            .collect()
+    }
+
+    #[cfg(feature = "parallel")]
+    fn materialize(
+        &self,
+        node: &PlanNode<S::StrId>,
+        from: EncodedTuple<S::StrId>,
+    ) -> (Vec<EvaluationError>, Vec<EncodedTuple<S::StrId>>) {
+        let mut errors = Vec::default();
+        let tuples = self
+            .eval_plan(node, from)
+            .filter_map(|result| match result {
+                Ok(result) => Some(result),
+                Err(error) => {
+                    errors.push(error);
+                    None
+                }
+            })
+            .collect();
+        (errors, tuples)
+    }
+
+    #[cfg_attr(not(feature = "async_service"), allow(unused_variables))]
     fn evaluate_service(
         &self,
+        id: usize,
         service_name: &PatternValue<S::StrId>,
         graph_pattern: &GraphPattern,
-        variables: Rc<Vec<Variable>>,
+        variables: Arc<Vec<Variable>>,
         from: &EncodedTuple<S::StrId>,
     ) -> Result<EncodedTuplesIterator<S::StrId>, EvaluationError> {
-        if let QueryResults::Solutions(iter) = self.service_handler.handle(
-            self.dataset.decode_named_node(
-                get_pattern_value(service_name, from)
-                    .ok_or_else(|| EvaluationError::msg("The SERVICE name is not bound"))?,
-            )?,
-            Query::Select {
-                dataset: QueryDataset::default(),
-                pattern: graph_pattern.clone(),
-                base_iri: self.base_iri.as_ref().map(|iri| iri.as_ref().clone()),
-            },
-        )? {
+        #[cfg(feature = "async_service")]
+        let results = {
+            let started = self
+                .service_results
+                .lock()
+                .unwrap()
+                .remove(&(id, from.clone()));
+            if let Some(results) = started {
+                results
+            } else {
+                // The future is driven by this thread: the handler must not rely on an external runtime
+                block_on(self.call_service(service_name, graph_pattern, from))
+            }
+        };
+        #[cfg(not(feature = "async_service"))]
+        let results = self.call_service(service_name, graph_pattern, from);
+        if let QueryResults::Solutions(iter) = results? {
             Ok(self.encode_bindings(variables, iter))
         } else {
             Err(EvaluationError::msg(
@@ -536,6 +906,227 @@ where
         }
     }
 
+    fn call_service(
+        &self,
+        service_name: &PatternValue<S::StrId>,
+        graph_pattern: &GraphPattern,
+        from: &EncodedTuple<S::StrId>,
+    ) -> ServiceCall {
+        let service_name = match get_pattern_value(service_name, from)
+            .ok_or_else(|| EvaluationError::msg("The SERVICE name is not bound"))
+            .and_then(|service_name| Ok(self.dataset.decode_named_node(service_name)?))
+        {
+            Ok(service_name) => service_name,
+            Err(error) => return failed_service_call(error),
+        };
+        self.service_handler.handle(
+            service_name,
+            Query::Select {
+                dataset: QueryDataset::default(),
+                pattern: graph_pattern.clone(),
+                base_iri: self.base_iri.as_ref().map(|iri| iri.as_ref().clone()),
+            },
+        )
+    }
+
+    /// Sends concurrently the SERVICE calls evaluated with the same input tuple as the node
+    ///
+    /// Their results are kept until the evaluation of the SERVICE nodes.
+    /// The keys of the results are returned to remove the ones that are not read.
+    #[cfg(feature = "async_service")]
+    fn start_independent_services(
+        &self,
+        node: &PlanNode<S::StrId>,
+        from: &EncodedTuple<S::StrId>,
+    ) -> Vec<(usize, EncodedTuple<S::StrId>)> {
+        let mut services = Vec::new();
+        self.add_independent_services(node, &mut services);
+        let services = {
+            let results = self.service_results.lock().unwrap();
+            services
+                .into_iter()
+                .filter(|(id, _, _)| !results.contains_key(&(*id, from.clone())))
+                .collect::<Vec<_>>()
+        };
+        if services.len() < 2 {
+            return Vec::new(); // Nothing to do concurrently
+        }
+        let calls = services.iter().map(|(_, service_name, graph_pattern)| {
+            self.call_service(service_name, graph_pattern, from)
+        });
+        // The calls progress concurrently but are all polled by this thread, see AsyncServiceHandler
+        let results = block_on(join_all(calls));
+        let keys = services
+            .into_iter()
+            .map(|(id, _, _)| (id, from.clone()))
+            .collect::<Vec<_>>();
+        self.service_results
+            .lock()
+            .unwrap()
+            .extend(keys.iter().cloned().zip(results));
+        keys
+    }
+
+    /// Adds the id, the name and the pattern of the SERVICE nodes that are evaluated with the same input tuple as `node`
+    #[cfg(feature = "async_service")]
+    fn add_independent_services<'a>(
+        &self,
+        node: &'a PlanNode<S::StrId>,
+        services: &mut Vec<(usize, &'a PatternValue<S::StrId>, &'a GraphPattern)>,
+    ) {
+        match node {
+            PlanNode::Service {
+                id,
+                service_name,
+                graph_pattern,
+                ..
+            } => services.push((*id, service_name, graph_pattern)),
+            PlanNode::Join { left, right } => {
+                self.add_independent_services(left, services);
+                if !self.is_service_bind_join(left, right) {
+                    self.add_independent_services(right, services);
+                }
+            }
+            PlanNode::AntiJoin { left, right } => {
+                self.add_independent_services(left, services);
+                self.add_independent_services(right, services);
+            }
+            PlanNode::Union { children } => {
+                for child in children {
+                    self.add_independent_services(child, services);
+                }
+            }
+            // The right side of a left join is evaluated for each left tuple
+            PlanNode::LeftJoin { left, .. } => self.add_independent_services(left, services),
+            PlanNode::QuadPatternJoin { child, .. }
+            | PlanNode::PathPatternJoin { child, .. }
+            | PlanNode::QuotedTriplePattern { child, .. }
+            | PlanNode::Filter { child, .. }
+            | PlanNode::Extend { child, .. }
+            | PlanNode::Sort { child, .. }
+            | PlanNode::HashDeduplicate { child }
+            | PlanNode::Skip { child, .. }
+            | PlanNode::Limit { child, .. }
+            | PlanNode::Aggregate { child, .. } => self.add_independent_services(child, services),
+            // These nodes do not give their input tuple to their child
+            PlanNode::Init
+            | PlanNode::StaticBindings { .. }
+            | PlanNode::Project { .. }
+            | PlanNode::Parameters { .. } => (),
+        }
+    }
+
+    fn is_service_bind_join(&self, left: &PlanNode<S::StrId>, right: &PlanNode<S::StrId>) -> bool {
+        self.service_batch_size > 0 && !service_join_variables(left, right).is_empty()
+    }
+
+    /// Evaluates `left` and joins its tuples with the results of the SERVICE node `right`
+    ///
+    /// The values of the join variables are sent to the service by batches in a VALUES clause.
+    /// If a call fails and the SERVICE is SILENT, the tuples of the batch are returned unchanged.
+    fn eval_service_bind_join(
+        &self,
+        left: &PlanNode<S::StrId>,
+        right: &PlanNode<S::StrId>,
+        from: EncodedTuple<S::StrId>,
+        optional: bool,
+    ) -> EncodedTuplesIterator<S::StrId> {
+        let (service_name, variables, graph_pattern, silent) = if let PlanNode::Service {
+            service_name,
+            variables,
+            graph_pattern,
+            silent,
+            ..
+        } = right
+        {
+            (
+                *service_name,
+                variables.clone(),
+                graph_pattern.clone(),
+                *silent,
+            )
+        } else {
+            unreachable!("eval_service_bind_join called without a SERVICE node")
+        };
+        let join_variables = service_join_variables(left, right);
+
+        // We group the tuples by service name, the batches are built while the results are consumed
+        let batches = ServiceBatchIterator {
+            input: self.eval_plan(left, from),
+            service_name,
+            batch_size: self.service_batch_size,
+            open_batches: Vec::new(),
+        };
+        let table_variables = join_variables
+            .iter()
+            .map(|v| variables[*v].clone())
+            .collect::<Vec<_>>();
+        let eval = self.clone();
+        let calls = batches.map(move |batch| {
+            let batch = match batch {
+                Ok(batch) => batch,
+                Err(error) => return (Vec::new(), failed_service_call(error)),
+            };
+            let rows = batch
+                .iter()
+                .map(|tuple| {
+                    join_variables
+                        .iter()
+                        .map(|v| {
+                            tuple
+                                .get(*v)
+                                .filter(|value| !value.is_blank_node())
+                                .map(|value| eval.dataset.decode_term(value))
+                                .transpose()
+                        })
+                        .collect::<Result<Vec<_>, _>>()
+                })
+                .collect::<Result<HashSet<_>, _>>();
+            let call = match rows {
+                Ok(rows) => eval.call_service(
+                    &service_name,
+                    &GraphPattern::Join {
+                        left: Box::new(GraphPattern::Table {
+                            variables: table_variables.clone(),
+                            rows: rows.into_iter().collect(),
+                        }),
+                        right: Box::new(graph_pattern.as_ref().clone()),
+                    },
+                    &batch[0],
+                ),
+                Err(error) => failed_service_call(error.into()),
+            };
+            (batch, call)
+        });
+        // The batch calls are polled by the thread consuming the results, no runtime is needed
+        #[cfg(feature = "async_service")]
+        let calls = block_on_stream(
+            stream::iter(calls)
+                .map(|(batch, call)| call.map(move |results| (batch, results)))
+                .buffered(MAX_CONCURRENT_SERVICE_CALLS),
+        );
+
+        let eval = self.clone();
+        Box::new(calls.flat_map(move |(batch, results)| {
+            let results = results.and_then(|results| {
+                if let QueryResults::Solutions(iter) = results {
+                    eval.encode_bindings(variables.clone(), iter)
+                        .collect::<Result<Vec<_>, _>>()
+                } else {
+                    Err(EvaluationError::msg(
+                        "The service call has not returned a set of solutions",
+                    ))
+                }
+            });
+            match results {
+                Ok(results) => join_service_results(batch, &results, optional),
+                // The errors of the left operand have an empty batch and are not silenced
+                Err(_) if silent && !batch.is_empty() => batch.into_iter().map(Ok).collect(),
+                Err(error) => vec![Err(error)],
+            }
+        }))
+    }
+
     fn accumulator_for_aggregate(
         &self,
         function: &PlanAggregationFunction,
@@ -584,7 +1175,7 @@ where
         path: &PlanPropertyPath<S::StrId>,
         start: EncodedTerm<S::StrId>,
         graph_name: EncodedTerm<S::StrId>,
-    ) -> Box<dyn Iterator<Item = Result<EncodedTerm<S::StrId>, EvaluationError>>> {
+    ) -> Box<dyn Iterator<Item = Result<EncodedTerm<S::StrId>, EvaluationError>> + Send> {
         match path {
             PlanPropertyPath::Path(p) => Box::new(
                 self.dataset
@@ -647,7 +1238,7 @@ where
         path: &PlanPropertyPath<S::StrId>,
         end: EncodedTerm<S::StrId>,
         graph_name: EncodedTerm<S::StrId>,
-    ) -> Box<dyn Iterator<Item = Result<EncodedTerm<S::StrId>, EvaluationError>>> {
+    ) -> Box<dyn Iterator<Item = Result<EncodedTerm<S::StrId>, EvaluationError>> + Send> {
         match path {
             PlanPropertyPath::Path(p) => Box::new(
                 self.dataset
@@ -710,9 +1301,8 @@ where
         path: &PlanPropertyPath<S::StrId>,
         graph_name: EncodedTerm<S::StrId>,
     ) -> Box<
-        dyn Iterator<
-            Item = Result<(EncodedTerm<S::StrId>, EncodedTerm<S::StrId>), EvaluationError>,
-        >,
+        dyn Iterator<Item = Result<(EncodedTerm<S::StrId>, EncodedTerm<S::StrId>), EvaluationError>>
+            + Send,
     > {
         match path {
             PlanPropertyPath::Path(p) => Box::new(
@@ -1031,22 +1621,7 @@ where
                 let mut language_range =
                     self.to_simple_string(self.eval_expression(language_range, tuple)?)?;
                 language_range.make_ascii_lowercase();
-                Some(
-                    if &*language_range == "*" {
-                        !language_tag.is_empty()
-                    } else {
-                        !ZipLongest::new(language_range.split('-'), language_tag.split('-')).any(
-                            |parts| match parts {
-                                (Some(range_subtag), Some(language_subtag)) => {
-                                    range_subtag != language_subtag
-                                }
-                                (Some(_), None) => true,
-                                (None, _) => false,
-                            },
-                        )
-                    }
-                    .into(),
-                )
+                Some(language_matches(&language_tag, &language_range).into())
             }
             PlanExpression::Datatype(e) => self.datatype(self.eval_expression(e, tuple)?),
             PlanExpression::Bound(v) => Some(tuple.contains(*v).into()),
@@ -1410,6 +1985,21 @@ where
             PlanExpression::IsLiteral(e) => {
                 Some(self.eval_expression(e, tuple)?.is_literal().into())
             }
+            PlanExpression::IsTriple(e) => Some(self.eval_expression(e, tuple)?.is_triple().into()),
+            PlanExpression::Triple(subject, predicate, object) => self.build_triple(
+                self.eval_expression(subject, tuple)?,
+                self.eval_expression(predicate, tuple)?,
+                self.eval_expression(object, tuple)?,
+            ),
+            PlanExpression::Subject(e) => {
+                Some(self.triple_components(self.eval_expression(e, tuple)?)?[0])
+            }
+            PlanExpression::Predicate(e) => {
+                Some(self.triple_components(self.eval_expression(e, tuple)?)?[1])
+            }
+            PlanExpression::Object(e) => {
+                Some(self.triple_components(self.eval_expression(e, tuple)?)?[2])
+            }
             PlanExpression::IsNumeric(e) => Some(
                 matches!(
                     self.eval_expression(e, tuple)?,
@@ -1432,6 +2022,74 @@ where
                 let text = self.to_string(self.eval_expression(text, tuple)?)?;
                 Some(regex.is_match(&text).into())
             }
+            PlanExpression::TextMatch(text, query, language_range) => {
+                let text = self.eval_expression(text, tuple)?;
+                let query = self.to_string(self.eval_expression(query, tuple)?)?;
+                if let Some(language_range) = language_range {
+                    let mut language_range =
+                        self.to_simple_string(self.eval_expression(language_range, tuple)?)?;
+                    language_range.make_ascii_lowercase();
+                    if !language_matches(&self.to_language(text)?, &language_range) {
+                        return Some(false.into());
+                    }
+                }
+                Some(text_match(&self.to_string(text)?, &query)?.into())
+            }
+            PlanExpression::TextScore(text, query) => {
+                let text = self.to_string(self.eval_expression(text, tuple)?)?;
+                let query = self.to_string(self.eval_expression(query, tuple)?)?;
+                Some(text_score(&text, &query)?.into())
+            }
+            PlanExpression::GeoDistance(a, b, unit) => {
+                let a = self.to_geometry(self.eval_expression(a, tuple)?)?;
+                let b = self.to_geometry(self.eval_expression(b, tuple)?)?;
+                Some(
+                    match self.to_distance_unit(unit.as_deref(), tuple)? {
+                        DistanceUnit::Metre => a.geodesic_distance(&b)?,
+                        DistanceUnit::Degree => a.distance(&b)?,
+                        DistanceUnit::Radian => a.distance(&b)?.to_radians(),
+                    }
+                    .into(),
+                )
+            }
+            PlanExpression::GeoIntersects(a, b) => {
+                let a = self.to_geometry(self.eval_expression(a, tuple)?)?;
+                let b = self.to_geometry(self.eval_expression(b, tuple)?)?;
+                Some(a.intersects(&b).into())
+            }
+            PlanExpression::GeoDisjoint(a, b) => {
+                let a = self.to_geometry(self.eval_expression(a, tuple)?)?;
+                let b = self.to_geometry(self.eval_expression(b, tuple)?)?;
+                Some((!a.intersects(&b)).into())
+            }
+            PlanExpression::GeoWithin(a, b) => {
+                let a = self.to_geometry(self.eval_expression(a, tuple)?)?;
+                let b = self.to_geometry(self.eval_expression(b, tuple)?)?;
+                Some(a.is_within(&b).into())
+            }
+            PlanExpression::GeoContains(a, b) => {
+                let a = self.to_geometry(self.eval_expression(a, tuple)?)?;
+                let b = self.to_geometry(self.eval_expression(b, tuple)?)?;
+                Some(a.contains(&b).into())
+            }
+            PlanExpression::GeoBuffer(geometry, radius, unit) => {
+                let geometry = self.to_geometry(self.eval_expression(geometry, tuple)?)?;
+                let radius = self.to_double(self.eval_expression(radius, tuple)?)?;
+                let radius = match self.to_distance_unit(unit.as_deref(), tuple)? {
+                    DistanceUnit::Metre => radius,
+                    DistanceUnit::Degree => radius.to_radians() * EARTH_RADIUS,
+                    DistanceUnit::Radian => radius * EARTH_RADIUS,
+                };
+                self.build_geometry(geometry.buffer(radius)?)
+            }
+            PlanExpression::GeoEnvelope(e) => {
+                let geometry = self.to_geometry(self.eval_expression(e, tuple)?)?;
+                self.build_geometry(geometry.envelope()?.into())
+            }
+            PlanExpression::GeoConvexHull(e) => {
+                let geometry = self.to_geometry(self.eval_expression(e, tuple)?)?;
+                self.build_geometry(geometry.convex_hull()?)
+            }
             PlanExpression::BooleanCast(e) => match self.eval_expression(e, tuple)? {
                 EncodedTerm::BooleanLiteral(value) => Some(value.into()),
                 EncodedTerm::FloatLiteral(value) => Some((value != 0. && !value.is_nan()).into()),
@@ -1586,7 +2244,8 @@ where
             EncodedTerm::NamedNode { iri_id } => Some(iri_id.into()),
             EncodedTerm::NumericalBlankNode { .. }
             | EncodedTerm::SmallBlankNode { .. }
-            | EncodedTerm::BigBlankNode { .. } => None,
+            | EncodedTerm::BigBlankNode { .. }
+            | EncodedTerm::Triple { .. } => None,
             EncodedTerm::SmallStringLiteral(value)
             | EncodedTerm::SmallSmallLangStringLiteral { value, .. }
             | EncodedTerm::SmallBigLangStringLiteral { value, .. }
@@ -1651,6 +2310,58 @@ where
         }
     }
 
+    fn to_language(&self, term: EncodedTerm<S::StrId>) -> Option<String> {
+        match term {
+            EncodedTerm::SmallSmallLangStringLiteral { language, .. }
+            | EncodedTerm::BigSmallLangStringLiteral { language, .. } => Some(language.into()),
+            EncodedTerm::SmallBigLangStringLiteral { language_id, .. }
+            | EncodedTerm::BigBigLangStringLiteral { language_id, .. } => {
+                self.dataset.get_str(language_id).ok()?
+            }
+            e if e.is_literal() => Some(String::new()),
+            _ => None,
+        }
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn to_double(&self, term: EncodedTerm<S::StrId>) -> Option<f64> {
+        match term {
+            EncodedTerm::FloatLiteral(value) => Some(value.into()),
+            EncodedTerm::DoubleLiteral(value) => Some(value),
+            EncodedTerm::IntegerLiteral(value) => Some(value as f64),
+            EncodedTerm::DecimalLiteral(value) => Some(value.to_f64()),
+            _ => None,
+        }
+    }
+
+    fn to_geometry(&self, term: EncodedTerm<S::StrId>) -> Option<Geometry> {
+        encoded_term_geometry(&*self.dataset, term).ok()?
+    }
+
+    /// Evaluates an optional GeoSPARQL unit of measure, metres being the default
+    fn to_distance_unit(
+        &self,
+        unit: Option<&PlanExpression<S::StrId>>,
+        tuple: &EncodedTuple<S::StrId>,
+    ) -> Option<DistanceUnit> {
+        if let Some(unit) = unit {
+            if let EncodedTerm::NamedNode { iri_id } = self.eval_expression(unit, tuple)? {
+                DistanceUnit::from_iri(&self.dataset.get_str(iri_id).ok()??)
+            } else {
+                None
+            }
+        } else {
+            Some(DistanceUnit::Metre)
+        }
+    }
+
+    fn build_geometry(&self, geometry: Geometry) -> Option<EncodedTerm<S::StrId>> {
+        self.dataset
+            .as_ref()
+            .encode_literal(Literal::from(geometry).as_ref())
+            .ok()
+    }
+
     fn to_string_and_language(
         &self,
         term: EncodedTerm<S::StrId>,
@@ -1741,6 +2452,41 @@ where
         }
     }
 
+    fn build_triple(
+        &self,
+        subject: EncodedTerm<S::StrId>,
+        predicate: EncodedTerm<S::StrId>,
+        object: EncodedTerm<S::StrId>,
+    ) -> Option<EncodedTerm<S::StrId>> {
+        let triple = Triple::new(
+            self.dataset.decode_subject(subject).ok()?,
+            self.dataset.decode_named_node(predicate).ok()?,
+            self.dataset.decode_term(object).ok()?,
+        );
+        self.dataset.as_ref().encode_triple(&triple).ok()
+    }
+
+    /// Returns the subject, predicate and object of a quoted triple
+    fn triple_components(
+        &self,
+        triple: EncodedTerm<S::StrId>,
+    ) -> Option<[EncodedTerm<S::StrId>; 3]> {
+        if !triple.is_triple() {
+            return None;
+        }
+        let triple = if let Term::Triple(triple) = self.dataset.decode_term(triple).ok()? {
+            triple
+        } else {
+            return None;
+        };
+        let mut dataset = self.dataset.as_ref();
+        Some([
+            dataset.encode_subject(triple.subject.as_ref()).ok()?,
+            dataset.encode_named_node(triple.predicate.as_ref()).ok()?,
+            dataset.encode_term(triple.object.as_ref()).ok()?,
+        ])
+    }
+
     fn build_string_id(&self, value: &str) -> Option<SmallStringOrId<S::StrId>> {
         Some(if let Ok(value) = SmallString::try_from(value) {
             value.into()
@@ -1817,7 +2563,7 @@ where
     fn decode_bindings(
         &self,
         iter: EncodedTuplesIterator<S::StrId>,
-        variables: Rc<Vec<Variable>>,
+        variables: Arc<Vec<Variable>>,
     ) -> QuerySolutionIter {
         let eval = self.clone();
         let tuple_size = variables.len();
@@ -1838,7 +2584,7 @@ where
     // this is used to encode results from a BindingIterator into an EncodedTuplesIterator. This happens when SERVICE clauses are evaluated
     fn encode_bindings(
         &self,
-        variables: Rc<Vec<Variable>>,
+        variables: Arc<Vec<Variable>>,
         iter: QuerySolutionIter,
     ) -> EncodedTuplesIterator<S::StrId> {
         let eval = self.clone();
@@ -1872,7 +2618,8 @@ where
             | EncodedTerm::SmallSmallLangStringLiteral { .. }
             | EncodedTerm::SmallBigLangStringLiteral { .. }
             | EncodedTerm::BigSmallLangStringLiteral { .. }
-            | EncodedTerm::BigBigLangStringLiteral { .. } => Some(a == b),
+            | EncodedTerm::BigBigLangStringLiteral { .. }
+            | EncodedTerm::Triple { .. } => Some(a == b),
             EncodedTerm::SmallStringLiteral(a) => match b {
                 EncodedTerm::SmallStringLiteral(b) => Some(a == b),
                 EncodedTerm::SmallTypedLiteral { .. } | EncodedTerm::BigTypedLiteral { .. } => None,
@@ -2040,8 +2787,15 @@ where
                     _ if b.is_blank_node() => Ordering::Greater,
                     _ => Ordering::Less,
                 },
+                EncodedTerm::Triple { triple_id: a } => match b {
+                    EncodedTerm::Triple { triple_id: b } => {
+                        self.compare_str_ids(a, b).unwrap_or(Ordering::Equal)
+                    }
+                    _ => Ordering::Greater,
+                },
                 a => match b {
                     _ if b.is_named_node() || b.is_blank_node() => Ordering::Greater,
+                    _ if b.is_triple() => Ordering::Less,
                     b => self.partial_cmp_literals(a, b).unwrap_or(Ordering::Equal),
                 },
             },
@@ -2208,6 +2962,7 @@ where
             | EncodedTerm::SmallBlankNode { .. }
             | EncodedTerm::BigBlankNode { .. }
             | EncodedTerm::NumericalBlankNode { .. }
+            | EncodedTerm::Triple { .. }
             | EncodedTerm::DefaultGraph => None,
             EncodedTerm::SmallStringLiteral(_) | EncodedTerm::BigStringLiteral { .. } => {
                 self.build_named_node(xsd::STRING.as_str())
@@ -2387,6 +3142,154 @@ impl NumericBinaryOperands {
     }
 }
 
+/// Returns a SERVICE call that has failed before being sent
+#[cfg(feature = "async_service")]
+fn failed_service_call(error: EvaluationError) -> ServiceCall {
+    Box::pin(ready(Err(error)))
+}
+
+/// Returns a SERVICE call that has failed before being sent
+#[cfg(not(feature = "async_service"))]
+fn failed_service_call(error: EvaluationError) -> ServiceCall {
+    Err(error)
+}
+
+/// Returns the variables that might be bound by both `left` and the SERVICE node `right`
+fn service_join_variables<I: StrId>(left: &PlanNode<I>, right: &PlanNode<I>) -> Vec<usize> {
+    if let PlanNode::Service { child, .. } = right {
+        let left_variables = left.maybe_bound_variables();
+        child
+            .maybe_bound_variables()
+            .into_iter()
+            .filter(|v| left_variables.contains(v))
+            .collect()
+    } else {
+        Vec::new()
+    }
+}
+
+fn join_service_results<I: StrId>(
+    batch: Vec<EncodedTuple<I>>,
+    results: &[EncodedTuple<I>],
+    optional: bool,
+) -> Vec<Result<EncodedTuple<I>, EvaluationError>> {
+    let mut output = Vec::new();
+    for tuple in batch {
+        let len = output.len();
+        output.extend(
+            results
+                .iter()
+                .filter_map(|result| tuple.combine_with(result))
+                .map(Ok),
+        );
+        if optional && output.len() == len {
+            output.push(Ok(tuple));
+        }
+    }
+    output
+}
+
+/// Returns the child of the node if they could both be evaluated on partitions of the child input
+#[cfg(feature = "parallel")]
+fn pipeline_child<I: StrId>(node: &PlanNode<I>) -> Option<&PlanNode<I>> {
+    match node {
+        PlanNode::QuadPatternJoin { child, .. }
+        | PlanNode::PathPatternJoin { child, .. }
+        | PlanNode::QuotedTriplePattern { child, .. }
+        | PlanNode::Filter { child, .. }
+        | PlanNode::Extend { child, .. } => match child.as_ref() {
+            PlanNode::QuadPatternJoin { .. }
+            | PlanNode::PathPatternJoin { .. }
+            | PlanNode::QuotedTriplePattern { .. }
+            | PlanNode::Filter { .. }
+            | PlanNode::Extend { .. } => Some(child),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Returns a copy of a node returned by [`pipeline_child`] with a new child
+#[cfg(feature = "parallel")]
+fn with_pipeline_child<I: StrId>(node: &PlanNode<I>, child: Arc<PlanNode<I>>) -> PlanNode<I> {
+    match node {
+        PlanNode::QuadPatternJoin {
+            subject,
+            predicate,
+            object,
+            graph_name,
+            object_range,
+            object_token,
+            object_box,
+            ..
+        } => PlanNode::QuadPatternJoin {
+            child,
+            subject: *subject,
+            predicate: *predicate,
+            object: *object,
+            graph_name: *graph_name,
+            object_range: *object_range,
+            object_token: object_token.clone(),
+            object_box: *object_box,
+        },
+        PlanNode::PathPatternJoin {
+            subject,
+            path,
+            object,
+            graph_name,
+            ..
+        } => PlanNode::PathPatternJoin {
+            child,
+            subject: *subject,
+            path: path.clone(),
+            object: *object,
+            graph_name: *graph_name,
+        },
+        PlanNode::QuotedTriplePattern {
+            triple,
+            subject,
+            predicate,
+            object,
+            ..
+        } => PlanNode::QuotedTriplePattern {
+            child,
+            triple: *triple,
+            subject: *subject,
+            predicate: *predicate,
+            object: *object,
+        },
+        PlanNode::Filter { expression, .. } => PlanNode::Filter {
+            child,
+            expression: expression.clone(),
+        },
+        PlanNode::Extend {
+            position,
+            expression,
+            ..
+        } => PlanNode::Extend {
+            child,
+            position: *position,
+            expression: expression.clone(),
+        },
+        _ => unreachable!("with_pipeline_child called on a non pipeline node"),
+    }
+}
+
+/// Checks if a lowercase language tag matches a lowercase [basic language range](https://tools.ietf.org/html/rfc4647#section-3.3.1)
+fn language_matches(language_tag: &str, language_range: &str) -> bool {
+    if language_range == "*" {
+        !language_tag.is_empty()
+    } else {
+        !ZipLongest::new(language_range.split('-'), language_tag.split('-')).any(
+            |parts| match parts {
+                (Some(range_subtag), Some(language_subtag)) => range_subtag != language_subtag,
+                (Some(_), None) => true,
+                (None, _) => false,
+            },
+        )
+    }
+}
+
 fn get_pattern_value<I: StrId>(
     selector: &PatternValue<I>,
     tuple: &EncodedTuple<I>,
@@ -2517,7 +3420,7 @@ impl<I: StrId> Iterator for AntiJoinIterator<I> {
 
 struct LeftJoinIterator<S: ReadableEncodedStore + 'static> {
     eval: SimpleEvaluator<S>,
-    right_plan: Rc<PlanNode<S::StrId>>,
+    right_plan: Arc<PlanNode<S::StrId>>,
     left_iter: EncodedTuplesIterator<S::StrId>,
     current_right: EncodedTuplesIterator<S::StrId>,
 }
@@ -2548,11 +3451,11 @@ where
 
 struct BadLeftJoinIterator<S: ReadableEncodedStore + 'static> {
     eval: SimpleEvaluator<S>,
-    right_plan: Rc<PlanNode<S::StrId>>,
+    right_plan: Arc<PlanNode<S::StrId>>,
     left_iter: EncodedTuplesIterator<S::StrId>,
     current_left: Option<EncodedTuple<S::StrId>>,
     current_right: EncodedTuplesIterator<S::StrId>,
-    problem_vars: Rc<Vec<usize>>,
+    problem_vars: Arc<Vec<usize>>,
 }
 
 impl<S: ReadableEncodedStore<Error = EvaluationError> + 'static> Iterator for BadLeftJoinIterator<S>
@@ -2603,7 +3506,7 @@ where
 
 struct UnionIterator<S: ReadableEncodedStore + 'static> {
     eval: SimpleEvaluator<S>,
-    plans: Vec<Rc<PlanNode<S::StrId>>>,
+    plans: Vec<Arc<PlanNode<S::StrId>>>,
     input: EncodedTuple<S::StrId>,
     current_iterator: EncodedTuplesIterator<S::StrId>,
     current_plan: usize,
@@ -2631,6 +3534,149 @@ where
     }
 }
 
+/// Evaluates a pipeline of operators on partitions of its input using the rayon thread pool
+///
+/// At most a partition per thread is read ahead of the consumed results.
+#[cfg(feature = "parallel")]
+struct PipelineIterator<S: ReadableEncodedStore + 'static> {
+    eval: SimpleEvaluator<S>,
+    input: EncodedTuplesIterator<S::StrId>,
+    /// The operators from the top of the pipeline to the one reading `input`
+    pipeline: Vec<PlanNode<S::StrId>>,
+    from: EncodedTuple<S::StrId>,
+    results: std::vec::IntoIter<Result<EncodedTuple<S::StrId>, EvaluationError>>,
+}
+
+#[cfg(feature = "parallel")]
+impl<S: ReadableEncodedStore<Error = EvaluationError> + 'static> Iterator for PipelineIterator<S>
+where
+    for<'a> &'a S: StrContainer<StrId = S::StrId>,
+{
+    type Item = Result<EncodedTuple<S::StrId>, EvaluationError>;
+
+    fn next(&mut self) -> Option<Result<EncodedTuple<S::StrId>, EvaluationError>> {
+        loop {
+            if let Some(result) = self.results.next() {
+                return Some(result);
+            }
+            let mut errors = Vec::new();
+            let mut tasks = Vec::new();
+            while tasks.len() < rayon::current_num_threads() {
+                let mut tuples = Vec::with_capacity(PARALLEL_BATCH_SIZE);
+                for tuple in self.input.by_ref() {
+                    match tuple {
+                        Ok(tuple) => {
+                            tuples.push(tuple);
+                            if tuples.len() >= PARALLEL_BATCH_SIZE {
+                                break;
+                            }
+                        }
+                        Err(error) => errors.push(Err(error)),
+                    }
+                }
+                if tuples.is_empty() {
+                    break;
+                }
+                let plan = self
+                    .pipeline
+                    .iter()
+                    .rev()
+                    .fold(PlanNode::StaticBindings { tuples }, |child, node| {
+                        with_pipeline_child(node, Arc::new(child))
+                    });
+                tasks.push((Arc::new(plan), self.from.clone()));
+            }
+            if tasks.is_empty() && errors.is_empty() {
+                return None;
+            }
+            let mut results = errors;
+            if tasks.len() > 1 {
+                results.extend(
+                    self.eval
+                        .eval_plans_in_parallel(tasks)
+                        .into_iter()
+                        .flatten(),
+                );
+            } else {
+                for (plan, from) in tasks {
+                    results.extend(self.eval.eval_plan(&plan, from));
+                }
+            }
+            self.results = results.into_iter();
+        }
+    }
+}
+
+/// Drops the results of the SERVICE calls started for the iterator that have not been read when it is dropped
+#[cfg(feature = "async_service")]
+struct StartedServicesIterator<I: StrId> {
+    iter: EncodedTuplesIterator<I>,
+    results: Arc<Mutex<ServiceResults<I>>>,
+    started: Vec<(usize, EncodedTuple<I>)>,
+}
+
+#[cfg(feature = "async_service")]
+impl<I: StrId> Iterator for StartedServicesIterator<I> {
+    type Item = Result<EncodedTuple<I>, EvaluationError>;
+
+    fn next(&mut self) -> Option<Result<EncodedTuple<I>, EvaluationError>> {
+        self.iter.next()
+    }
+}
+
+#[cfg(feature = "async_service")]
+impl<I: StrId> Drop for StartedServicesIterator<I> {
+    fn drop(&mut self) {
+        if let Ok(mut results) = self.results.lock() {
+            for key in &self.started {
+                results.remove(key);
+            }
+        }
+    }
+}
+
+/// Groups the input tuples in batches of tuples with the same service name
+///
+/// A batch is returned as soon as it is full, the incomplete ones at the end of the input.
+struct ServiceBatchIterator<I: StrId> {
+    input: EncodedTuplesIterator<I>,
+    service_name: PatternValue<I>,
+    batch_size: usize,
+    /// The batches not full yet, in their creation order
+    open_batches: Vec<(Option<EncodedTerm<I>>, Vec<EncodedTuple<I>>)>,
+}
+
+impl<I: StrId> Iterator for ServiceBatchIterator<I> {
+    type Item = Result<Vec<EncodedTuple<I>>, EvaluationError>;
+
+    fn next(&mut self) -> Option<Result<Vec<EncodedTuple<I>>, EvaluationError>> {
+        for tuple in self.input.by_ref() {
+            let tuple = match tuple {
+                Ok(tuple) => tuple,
+                Err(error) => return Some(Err(error)),
+            };
+            let name = get_pattern_value(&self.service_name, &tuple);
+            let position =
+                if let Some(position) = self.open_batches.iter().position(|(n, _)| *n == name) {
+                    position
+                } else {
+                    self.open_batches
+                        .push((name, Vec::with_capacity(self.batch_size)));
+                    self.open_batches.len() - 1
+                };
+            self.open_batches[position].1.push(tuple);
+            if self.open_batches[position].1.len() >= self.batch_size {
+                return Some(Ok(self.open_batches.remove(position).1));
+            }
+        }
+        if self.open_batches.is_empty() {
+            None
+        } else {
+            Some(Ok(self.open_batches.remove(0).1))
+        }
+    }
+}
+
 struct ConstructIterator<S: ReadableEncodedStore + 'static> {
     eval: SimpleEvaluator<S>,
     iter: EncodedTuplesIterator<S::StrId>,
@@ -2639,7 +3685,10 @@ struct ConstructIterator<S: ReadableEncodedStore + 'static> {
     bnodes: Vec<EncodedTerm<S::StrId>>,
 }
 
-impl<S: ReadableEncodedStore<Error = EvaluationError> + 'static> Iterator for ConstructIterator<S> {
+impl<S: ReadableEncodedStore<Error = EvaluationError> + 'static> Iterator for ConstructIterator<S>
+where
+    for<'a> &'a S: StrContainer<StrId = S::StrId>,
+{
     type Item = Result<Triple, EvaluationError>;
 
     fn next(&mut self) -> Option<Result<Triple, EvaluationError>> {
@@ -2654,9 +3703,24 @@ impl<S: ReadableEncodedStore<Error = EvaluationError> + 'static> Iterator for Co
                 };
                 for template in &self.template {
                     if let (Some(subject), Some(predicate), Some(object)) = (
-                        get_triple_template_value(&template.subject, &tuple, &mut self.bnodes),
-                        get_triple_template_value(&template.predicate, &tuple, &mut self.bnodes),
-                        get_triple_template_value(&template.object, &tuple, &mut self.bnodes),
+                        get_triple_template_value(
+                            &self.eval,
+                            &template.subject,
+                            &tuple,
+                            &mut self.bnodes,
+                        ),
+                        get_triple_template_value(
+                            &self.eval,
+                            &template.predicate,
+                            &tuple,
+                            &mut self.bnodes,
+                        ),
+                        get_triple_template_value(
+                            &self.eval,
+                            &template.object,
+                            &tuple,
+                            &mut self.bnodes,
+                        ),
                     ) {
                         self.buffered_results.push(decode_triple(
                             &*self.eval.dataset,
@@ -2672,11 +3736,15 @@ impl<S: ReadableEncodedStore<Error = EvaluationError> + 'static> Iterator for Co
     }
 }
 
-fn get_triple_template_value<I: StrId>(
-    selector: &TripleTemplateValue<I>,
-    tuple: &EncodedTuple<I>,
-    bnodes: &mut Vec<EncodedTerm<I>>,
-) -> Option<EncodedTerm<I>> {
+fn get_triple_template_value<S: ReadableEncodedStore<Error = EvaluationError> + 'static>(
+    eval: &SimpleEvaluator<S>,
+    selector: &TripleTemplateValue<S::StrId>,
+    tuple: &EncodedTuple<S::StrId>,
+    bnodes: &mut Vec<EncodedTerm<S::StrId>>,
+) -> Option<EncodedTerm<S::StrId>>
+where
+    for<'a> &'a S: StrContainer<StrId = S::StrId>,
+{
     match selector {
         TripleTemplateValue::Constant(term) => Some(*term),
         TripleTemplateValue::Variable(v) => tuple.get(*v),
@@ -2686,6 +3754,12 @@ fn get_triple_template_value<I: StrId>(
             }
             Some(bnodes[*id])
         }
+        TripleTemplateValue::Triple(triple) => {
+            let subject = get_triple_template_value(eval, &triple.subject, tuple, bnodes)?;
+            let predicate = get_triple_template_value(eval, &triple.predicate, tuple, bnodes)?;
+            let object = get_triple_template_value(eval, &triple.object, tuple, bnodes)?;
+            eval.build_triple(subject, predicate, object)
+        }
     }
 }
 
@@ -2700,7 +3774,7 @@ fn decode_triple<D: Decoder>(
     object: EncodedTerm<D::StrId>,
 ) -> Result<Triple, EvaluationError> {
     Ok(Triple::new(
-        decoder.decode_named_or_blank_node(subject)?,
+        decoder.decode_subject(subject)?,
         decoder.decode_named_node(predicate)?,
         decoder.decode_term(object)?,
     ))
@@ -2709,7 +3783,7 @@ fn decode_triple<D: Decoder>(
 struct DescribeIterator<S: ReadableEncodedStore + 'static> {
     eval: SimpleEvaluator<S>,
     iter: EncodedTuplesIterator<S::StrId>,
-    quads: Box<dyn Iterator<Item = Result<EncodedQuad<S::StrId>, EvaluationError>>>,
+    quads: Box<dyn Iterator<Item = Result<EncodedQuad<S::StrId>, EvaluationError>> + Send>,
 }
 
 impl<S: ReadableEncodedStore<Error = EvaluationError> + 'static> Iterator for DescribeIterator<S> {
@@ -2894,7 +3968,7 @@ impl<
     }
 }
 
-trait Accumulator<I: StrId> {
+trait Accumulator<I: StrId>: Send {
     fn add(&mut self, element: Option<EncodedTerm<I>>);
 
     fn state(&self) -> Option<EncodedTerm<I>>;
@@ -3113,11 +4187,11 @@ struct GroupConcatAccumulator<S: ReadableEncodedStore + 'static> {
     eval: SimpleEvaluator<S>,
     concat: Option<String>,
     language: Option<Option<SmallStringOrId<S::StrId>>>,
-    separator: Rc<String>,
+    separator: Arc<String>,
 }
 
 impl<S: ReadableEncodedStore + 'static> GroupConcatAccumulator<S> {
-    fn new(eval: SimpleEvaluator<S>, separator: Rc<String>) -> Self {
+    fn new(eval: SimpleEvaluator<S>, separator: Arc<String>) -> Self {
         Self {
             eval,
             concat: Some("".to_owned()),
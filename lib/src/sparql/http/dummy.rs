@@ -5,6 +5,7 @@ use http::{Request, Response};
 use std::io;
 use std::io::BufRead;
 
+#[derive(Clone)]
 pub struct Client {}
 
 impl Client {
@@ -15,7 +16,7 @@ impl Client {
     pub fn request(
         &self,
         _request: &Request<Option<Vec<u8>>>,
-    ) -> io::Result<Response<Box<dyn BufRead>>> {
+    ) -> io::Result<Response<Box<dyn BufRead + Send>>> {
         Err(invalid_input_error(
             "HTTP client is not available. Enable the feature 'simple_http'",
         ))
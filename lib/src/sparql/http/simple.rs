@@ -11,6 +11,7 @@ use std::io;
 use std::io::{BufRead, BufReader, Read, Write};
 use std::net::TcpStream;
 
+#[derive(Clone)]
 pub struct Client {}
 
 impl Client {
@@ -21,7 +22,7 @@ impl Client {
     pub fn request(
         &self,
         request: &Request<Option<Vec<u8>>>,
-    ) -> io::Result<Response<Box<dyn BufRead>>> {
+    ) -> io::Result<Response<Box<dyn BufRead + Send>>> {
         let scheme = request
             .uri()
             .scheme_str()
@@ -136,7 +137,10 @@ impl Client {
         Ok(())
     }
 
-    fn decode<'a>(&self, reader: impl Read + 'a) -> io::Result<Response<Box<dyn BufRead + 'a>>> {
+    fn decode<'a>(
+        &self,
+        reader: impl Read + Send + 'a,
+    ) -> io::Result<Response<Box<dyn BufRead + Send + 'a>>> {
         let mut reader = BufReader::new(reader);
 
         // Let's read the headers
@@ -197,7 +201,7 @@ impl Client {
             ));
         }
 
-        let body: Box<dyn BufRead> = if let Some(content_length) = content_length {
+        let body: Box<dyn BufRead + Send> = if let Some(content_length) = content_length {
             let len = content_length
                 .to_str()
                 .map_err(invalid_data_error)?
@@ -3,8 +3,10 @@
 //! Stores execute SPARQL. See [`MemoryStore`](super::store::memory::MemoryStore::query()) for an example.
 
 pub mod algebra;
+mod cache;
 mod csv_results;
 mod dataset;
+mod description;
 mod error;
 mod eval;
 mod http;
@@ -13,12 +15,16 @@ mod model;
 mod parser;
 mod plan;
 mod plan_builder;
+mod prepared;
+mod rdfs;
 mod service;
 mod update;
 mod xml_results;
 
 pub use crate::sparql::algebra::{Query, Update};
+pub(crate) use crate::sparql::cache::QueryCache;
 use crate::sparql::dataset::DatasetView;
+pub use crate::sparql::description::ServiceDescription;
 pub use crate::sparql::error::EvaluationError;
 use crate::sparql::eval::SimpleEvaluator;
 pub use crate::sparql::model::QueryResults;
@@ -29,14 +35,23 @@ pub use crate::sparql::model::QueryTripleIter;
 pub use crate::sparql::model::{Variable, VariableNameParseError};
 pub use crate::sparql::parser::ParseError;
 use crate::sparql::plan_builder::PlanBuilder;
+pub(crate) use crate::sparql::prepared::prepare_query;
+pub use crate::sparql::prepared::PreparedQuery;
+#[cfg(feature = "async_service")]
+pub use crate::sparql::service::AsyncServiceHandler;
+#[cfg(not(feature = "async_service"))]
+use crate::sparql::service::ErrorConversionServiceHandler;
 pub use crate::sparql::service::ServiceHandler;
-use crate::sparql::service::{EmptyServiceHandler, ErrorConversionServiceHandler};
+#[cfg(feature = "async_service")]
+use crate::sparql::service::{AsyncErrorConversionServiceHandler, BlockingServiceHandler};
+use crate::sparql::service::{EmptyServiceHandler, EvaluatorServiceHandler};
 use crate::sparql::update::SimpleUpdateEvaluator;
 use crate::store::numeric_encoder::StrContainer;
 use crate::store::{ReadableEncodedStore, StoreOrParseError, WritableEncodedStore};
+use oxiri::Iri;
 use std::convert::TryInto;
 use std::io;
-use std::rc::Rc;
+use std::sync::Arc;
 
 pub(crate) fn evaluate_query<R: ReadableEncodedStore + 'static>(
     store: R,
@@ -49,28 +64,22 @@ pub(crate) fn evaluate_query<R: ReadableEncodedStore + 'static>(
             base_iri,
             dataset,
         } => {
-            let dataset = DatasetView::new(store, &dataset)?;
+            let dataset = DatasetView::new(store, &dataset, options.rdfs_entailment)?;
             let (plan, variables) = PlanBuilder::build(&dataset, &pattern)?;
-            SimpleEvaluator::new(
-                Rc::new(dataset),
-                base_iri.map(Rc::new),
-                options.service_handler,
-            )
-            .evaluate_select_plan(&plan, Rc::new(variables))
+            options
+                .evaluator(Arc::new(dataset), base_iri.map(Arc::new))
+                .evaluate_select_plan(&plan, Arc::new(variables))
         }
         Query::Ask {
             pattern,
             base_iri,
             dataset,
         } => {
-            let dataset = DatasetView::new(store, &dataset)?;
+            let dataset = DatasetView::new(store, &dataset, options.rdfs_entailment)?;
             let (plan, _) = PlanBuilder::build(&dataset, &pattern)?;
-            SimpleEvaluator::new(
-                Rc::new(dataset),
-                base_iri.map(Rc::new),
-                options.service_handler,
-            )
-            .evaluate_ask_plan(&plan)
+            options
+                .evaluator(Arc::new(dataset), base_iri.map(Arc::new))
+                .evaluate_ask_plan(&plan)
         }
         Query::Construct {
             template,
@@ -78,29 +87,23 @@ pub(crate) fn evaluate_query<R: ReadableEncodedStore + 'static>(
             base_iri,
             dataset,
         } => {
-            let dataset = DatasetView::new(store, &dataset)?;
+            let dataset = DatasetView::new(store, &dataset, options.rdfs_entailment)?;
             let (plan, variables) = PlanBuilder::build(&dataset, &pattern)?;
             let construct = PlanBuilder::build_graph_template(&dataset, &template, variables)?;
-            SimpleEvaluator::new(
-                Rc::new(dataset),
-                base_iri.map(Rc::new),
-                options.service_handler,
-            )
-            .evaluate_construct_plan(&plan, construct)
+            options
+                .evaluator(Arc::new(dataset), base_iri.map(Arc::new))
+                .evaluate_construct_plan(&plan, construct)
         }
         Query::Describe {
             pattern,
             base_iri,
             dataset,
         } => {
-            let dataset = DatasetView::new(store, &dataset)?;
+            let dataset = DatasetView::new(store, &dataset, options.rdfs_entailment)?;
             let (plan, _) = PlanBuilder::build(&dataset, &pattern)?;
-            SimpleEvaluator::new(
-                Rc::new(dataset),
-                base_iri.map(Rc::new),
-                options.service_handler,
-            )
-            .evaluate_describe_plan(&plan)
+            options
+                .evaluator(Arc::new(dataset), base_iri.map(Arc::new))
+                .evaluate_describe_plan(&plan)
         }
     }
 }
@@ -112,7 +115,11 @@ pub(crate) fn evaluate_query<R: ReadableEncodedStore + 'static>(
 /// a simple HTTP 1.1 client is used to execute [SPARQL 1.1 Federated Query](https://www.w3.org/TR/sparql11-federated-query/) SERVICE calls.
 #[derive(Clone)]
 pub struct QueryOptions {
-    pub(crate) service_handler: Rc<dyn ServiceHandler<Error = EvaluationError>>,
+    pub(crate) service_handler: Arc<EvaluatorServiceHandler>,
+    pub(crate) rdfs_entailment: bool,
+    pub(crate) query_cache: bool,
+    pub(crate) parallel_evaluation: bool,
+    pub(crate) service_batch_size: usize,
 }
 
 impl Default for QueryOptions {
@@ -120,28 +127,171 @@ impl Default for QueryOptions {
     fn default() -> Self {
         Self {
             service_handler: if cfg!(feature = "http_client") {
-                Rc::new(service::SimpleServiceHandler::new())
+                Arc::new(service::SimpleServiceHandler::new())
             } else {
-                Rc::new(EmptyServiceHandler)
+                Arc::new(EmptyServiceHandler)
             },
+            rdfs_entailment: false,
+            query_cache: true,
+            parallel_evaluation: false,
+            service_batch_size: 0,
         }
     }
 }
 
 impl QueryOptions {
     /// Use a given [`ServiceHandler`] to execute [SPARQL 1.1 Federated Query](https://www.w3.org/TR/sparql11-federated-query/) SERVICE calls.
+    ///
+    /// The calls are done sequentially. If the `"async_service"` feature is activated,
+    /// [`with_async_service_handler`](QueryOptions::with_async_service_handler()) allows to do them concurrently.
     #[inline]
     pub fn with_service_handler(mut self, service_handler: impl ServiceHandler + 'static) -> Self {
-        self.service_handler = Rc::new(ErrorConversionServiceHandler::wrap(service_handler));
+        #[cfg(feature = "async_service")]
+        {
+            self.service_handler = Arc::new(BlockingServiceHandler::wrap(service_handler));
+        }
+        #[cfg(not(feature = "async_service"))]
+        {
+            self.service_handler = Arc::new(ErrorConversionServiceHandler::wrap(service_handler));
+        }
+        self
+    }
+
+    /// Use a given [`AsyncServiceHandler`] to execute [SPARQL 1.1 Federated Query](https://www.w3.org/TR/sparql11-federated-query/) SERVICE calls.
+    ///
+    /// The SERVICE clauses that do not depend on each other are evaluated concurrently.
+    ///
+    /// It requires the `"async_service"` feature to be activated.
+    #[cfg(feature = "async_service")]
+    #[inline]
+    pub fn with_async_service_handler(
+        mut self,
+        service_handler: impl AsyncServiceHandler + 'static,
+    ) -> Self {
+        self.service_handler = Arc::new(AsyncErrorConversionServiceHandler::wrap(service_handler));
         self
     }
 
     /// Disables the `SERVICE` calls
     #[inline]
     pub fn without_service_handler(mut self) -> Self {
-        self.service_handler = Rc::new(EmptyServiceHandler);
+        self.service_handler = Arc::new(EmptyServiceHandler);
         self
     }
+
+    /// Evaluates the query under the [RDFS entailment regime](https://www.w3.org/TR/sparql11-entailment/#RDFSEntRegime).
+    ///
+    /// The `rdfs:subClassOf`, `rdfs:subPropertyOf`, `rdfs:domain` and `rdfs:range` triples of the default graph are used to
+    /// infer the `rdf:type`, `rdfs:subClassOf`, `rdfs:subPropertyOf` and sub-property triples in all graphs.
+    /// The RDFS axiomatic triples are not returned.
+    /// A triple entailed in several ways is returned once: the triples matching a pattern that could have entailed triples
+    /// are kept in memory during its evaluation, so scanning a large part of the store uses as much memory as a `SELECT DISTINCT`.
+    ///
+    /// Usage example:
+    /// ```
+    /// use oxigraph::MemoryStore;
+    /// use oxigraph::io::GraphFormat;
+    /// use oxigraph::model::GraphNameRef;
+    /// use oxigraph::sparql::{QueryOptions, QueryResults};
+    ///
+    /// let store = MemoryStore::new();
+    /// store.load_graph(
+    ///     "<http://example.com/Cat> <http://www.w3.org/2000/01/rdf-schema#subClassOf> <http://example.com/Animal> .
+    ///      <http://example.com/tom> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://example.com/Cat> .".as_bytes(),
+    ///     GraphFormat::NTriples,
+    ///     GraphNameRef::DefaultGraph,
+    ///     None,
+    /// )?;
+    /// if let QueryResults::Boolean(result) = store.query_opt(
+    ///     "ASK { <http://example.com/tom> a <http://example.com/Animal> }",
+    ///     QueryOptions::default().with_rdfs_entailment(),
+    /// )? {
+    ///     assert!(result);
+    /// }
+    /// # Result::<_,Box<dyn std::error::Error>>::Ok(())
+    /// ```
+    #[inline]
+    pub fn with_rdfs_entailment(mut self) -> Self {
+        self.rdfs_entailment = true;
+        self
+    }
+
+    /// Evaluates the query against the store even if its results are in the store query results cache
+    ///
+    /// The results are then not added to the cache.
+    /// See [`MemoryStore::set_query_cache_capacity`](super::MemoryStore::set_query_cache_capacity()) for the cache description.
+    #[inline]
+    pub fn without_query_cache(mut self) -> Self {
+        self.query_cache = false;
+        self
+    }
+
+    /// Evaluates the query using multiple threads
+    ///
+    /// The branches of UNION, the joins and filters following triple pattern scans and the GROUP BY aggregations
+    /// are distributed on the [rayon](https://docs.rs/rayon) global thread pool.
+    /// The scans are split in partitions of a few hundred tuples read on demand,
+    /// the UNION branches and the GROUP BY inputs are materialized.
+    /// The LIMIT operators and the ASK queries are evaluated sequentially to stop as soon as possible.
+    ///
+    /// It requires the `"parallel"` feature to be activated, the query is evaluated sequentially otherwise.
+    ///
+    /// Usage example:
+    /// ```
+    /// use oxigraph::MemoryStore;
+    /// use oxigraph::model::*;
+    /// use oxigraph::sparql::{QueryOptions, QueryResults};
+    ///
+    /// let store = MemoryStore::new();
+    /// let ex = NamedNode::new("http://example.com")?;
+    /// store.insert(QuadRef::new(&ex, &ex, &ex, GraphNameRef::DefaultGraph));
+    /// if let QueryResults::Solutions(solutions) = store.query_opt(
+    ///     "SELECT ?s WHERE { { ?s ?p ?o } UNION { ?o ?p ?s } }",
+    ///     QueryOptions::default().with_parallel_evaluation(),
+    /// )? {
+    ///     assert_eq!(solutions.count(), 2);
+    /// }
+    /// # Result::<_,Box<dyn std::error::Error>>::Ok(())
+    /// ```
+    #[inline]
+    pub fn with_parallel_evaluation(mut self) -> Self {
+        self.parallel_evaluation = true;
+        self
+    }
+
+    /// Sets the number of bindings sent at once to a SERVICE sharing variables with the rest of the query
+    ///
+    /// The bindings are sent in a `VALUES` clause, so a single request is done for `batch_size` solutions of the
+    /// rest of the query. If a request fails and the SERVICE is `SILENT`, only the solutions of its batch
+    /// are kept unchanged.
+    ///
+    /// The batches are built while the query results are consumed. If the `"async_service"` feature is activated,
+    /// at most 8 requests are pending at once, otherwise they are done one after the other.
+    ///
+    /// Batching is disabled by default, i.e. the batch size is `0`:
+    /// the SERVICE pattern is then evaluated without the bindings of the rest of the query.
+    #[inline]
+    pub fn with_service_batch_size(mut self, batch_size: usize) -> Self {
+        self.service_batch_size = batch_size;
+        self
+    }
+
+    pub(crate) fn evaluator<S: ReadableEncodedStore<Error = EvaluationError> + 'static>(
+        self,
+        dataset: Arc<S>,
+        base_iri: Option<Arc<Iri<String>>>,
+    ) -> SimpleEvaluator<S>
+    where
+        for<'a> &'a S: StrContainer<StrId = S::StrId>,
+    {
+        let evaluator = SimpleEvaluator::new(dataset, base_iri, self.service_handler)
+            .with_service_batch_size(self.service_batch_size);
+        if self.parallel_evaluation {
+            evaluator.with_parallel_evaluation()
+        } else {
+            evaluator
+        }
+    }
 }
 
 /// Options for SPARQL update evaluation
@@ -192,6 +342,6 @@ pub(crate) fn evaluate_update<
 where
     io::Error: From<StoreOrParseError<W::Error>>,
 {
-    SimpleUpdateEvaluator::new(read, write, update.base_iri.map(Rc::new), options)
+    SimpleUpdateEvaluator::new(read, write, update.base_iri.map(Arc::new), options)
         .eval_all(&update.operations)
 }
@@ -1,6 +1,5 @@
 use crate::error::invalid_input_error;
-use crate::io::GraphFormat;
-use crate::io::GraphSerializer;
+use crate::io::{DatasetSerializer, GraphSerializer, RdfFormat};
 use crate::model::*;
 use crate::sparql::csv_results::{read_tsv_results, write_csv_results, write_tsv_results};
 use crate::sparql::error::EvaluationError;
@@ -9,7 +8,7 @@ use crate::sparql::xml_results::{read_xml_results, write_xml_results};
 use rand::random;
 use std::error::Error;
 use std::io::{BufRead, Write};
-use std::rc::Rc;
+use std::sync::Arc;
 use std::{fmt, io};
 
 /// Results of a [SPARQL query](https://www.w3.org/TR/sparql11-query/)
@@ -25,7 +24,7 @@ pub enum QueryResults {
 impl QueryResults {
     /// Reads a SPARQL query results serialization
     pub fn read(
-        reader: impl BufRead + 'static,
+        reader: impl BufRead + Send + 'static,
         format: QueryResultsFormat,
     ) -> Result<Self, io::Error> {
         match format {
@@ -73,7 +72,8 @@ impl QueryResults {
 
     /// Writes the graph query results
     ///
-    /// This method fails if it is called on the `Solution` or `Boolean` results
+    /// This method fails if it is called on the `Solution` or `Boolean` results.
+    /// With a dataset format like [JSON-LD](crate::io::DatasetFormat::JsonLd) the triples are written in the default graph.
     ///
     /// ```
     /// use oxigraph::MemoryStore;
@@ -94,14 +94,25 @@ impl QueryResults {
     pub fn write_graph(
         self,
         write: impl Write,
-        format: GraphFormat,
+        format: impl Into<RdfFormat>,
     ) -> Result<(), EvaluationError> {
         if let QueryResults::Graph(triples) = self {
-            let mut writer = GraphSerializer::from_format(format).triple_writer(write)?;
-            for triple in triples {
-                writer.write(&triple?)?;
+            match format.into() {
+                RdfFormat::Graph(format) => {
+                    let mut writer = GraphSerializer::from_format(format).triple_writer(write)?;
+                    for triple in triples {
+                        writer.write(&triple?)?;
+                    }
+                    writer.finish()?;
+                }
+                RdfFormat::Dataset(format) => {
+                    let mut writer = DatasetSerializer::from_format(format).quad_writer(write)?;
+                    for triple in triples {
+                        writer.write(&triple?.in_graph(GraphName::DefaultGraph))?;
+                    }
+                    writer.finish()?;
+                }
             }
-            writer.finish()?;
             Ok(())
         } else {
             Err(
@@ -229,14 +240,14 @@ impl QueryResultsFormat {
 /// # Result::<_,Box<dyn std::error::Error>>::Ok(())
 /// ```
 pub struct QuerySolutionIter {
-    variables: Rc<Vec<Variable>>,
-    iter: Box<dyn Iterator<Item = Result<Vec<Option<Term>>, EvaluationError>>>,
+    variables: Arc<Vec<Variable>>,
+    iter: Box<dyn Iterator<Item = Result<Vec<Option<Term>>, EvaluationError>> + Send>,
 }
 
 impl QuerySolutionIter {
     pub fn new(
-        variables: Rc<Vec<Variable>>,
-        iter: Box<dyn Iterator<Item = Result<Vec<Option<Term>>, EvaluationError>>>,
+        variables: Arc<Vec<Variable>>,
+        iter: Box<dyn Iterator<Item = Result<Vec<Option<Term>>, EvaluationError>> + Send>,
     ) -> Self {
         Self { variables, iter }
     }
@@ -281,7 +292,7 @@ impl Iterator for QuerySolutionIter {
 /// It is the equivalent of a row in SQL.
 pub struct QuerySolution {
     values: Vec<Option<Term>>,
-    variables: Rc<Vec<Variable>>,
+    variables: Arc<Vec<Variable>>,
 }
 
 impl QuerySolution {
@@ -378,7 +389,7 @@ impl VariableSolutionIndex for Variable {
 /// # Result::<_,Box<dyn std::error::Error>>::Ok(())
 /// ```
 pub struct QueryTripleIter {
-    pub(crate) iter: Box<dyn Iterator<Item = Result<Triple, EvaluationError>>>,
+    pub(crate) iter: Box<dyn Iterator<Item = Result<Triple, EvaluationError>> + Send>,
 }
 
 impl Iterator for QueryTripleIter {
@@ -526,3 +537,54 @@ impl fmt::Display for VariableNameParseError {
 }
 
 impl Error for VariableNameParseError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sparql::QueryOptions;
+    use crate::store::MemoryStore;
+    use std::thread;
+
+    #[test]
+    fn test_send_query_results() -> Result<(), Box<dyn Error>> {
+        fn assert_send<T: Send>() {}
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send::<QueryResults>();
+        assert_send_sync::<QueryOptions>();
+        assert_send_sync::<MemoryStore>();
+        #[cfg(feature = "sled")]
+        assert_send_sync::<crate::SledStore>();
+
+        let store = MemoryStore::new();
+        for i in 0..3 {
+            let ex = NamedNode::new(format!("http://example.com/{}", i))?;
+            store.insert(QuadRef::new(&ex, &ex, &ex, GraphNameRef::DefaultGraph));
+        }
+        let options = QueryOptions::default().without_service_handler();
+        let results = store.query_opt("SELECT ?s WHERE { ?s ?p ?o }", options.clone())?;
+        let count = thread::spawn(move || {
+            if let QueryResults::Solutions(solutions) = results {
+                solutions.filter(Result::is_ok).count()
+            } else {
+                0
+            }
+        })
+        .join()
+        .unwrap();
+        assert_eq!(count, 3);
+
+        let results = thread::spawn(move || {
+            store
+                .query_opt("CONSTRUCT WHERE { ?s ?p ?o }", options)
+                .unwrap()
+        })
+        .join()
+        .unwrap();
+        if let QueryResults::Graph(triples) = results {
+            assert_eq!(triples.count(), 3);
+        } else {
+            return Err("CONSTRUCT results expected".into());
+        }
+        Ok(())
+    }
+}
@@ -9,9 +9,9 @@ use peg::str::LineCol;
 use std::borrow::Cow;
 use std::collections::{HashMap, HashSet};
 use std::error::Error;
-use std::rc::Rc;
 use std::str::Chars;
 use std::str::FromStr;
+use std::sync::Arc;
 use std::{char, fmt};
 
 /// Parses a SPARQL query with an optional base IRI to resolve relative IRIs in the query
@@ -173,6 +173,31 @@ fn add_to_triple_or_path_patterns(
     }
 }
 
+/// Converts a pattern without variables to a term
+fn ground_term(term: TermOrVariable) -> Option<Term> {
+    match term {
+        TermOrVariable::Term(term) => Some(term),
+        TermOrVariable::Variable(_) => None,
+        TermOrVariable::Triple(triple) => Some(
+            Triple::new(
+                match ground_term(triple.subject)? {
+                    Term::NamedNode(node) => Subject::from(node),
+                    Term::BlankNode(node) => node.into(),
+                    Term::Triple(triple) => triple.into(),
+                    Term::Literal(_) => return None,
+                },
+                if let NamedNodeOrVariable::NamedNode(predicate) = triple.predicate {
+                    predicate
+                } else {
+                    return None;
+                },
+                ground_term(triple.object)?,
+            )
+            .into(),
+        ),
+    }
+}
+
 fn build_bgp(patterns: Vec<TripleOrPathPattern>) -> GraphPattern {
     let mut bgp = Vec::with_capacity(patterns.len());
     let mut paths = Vec::with_capacity(patterns.len());
@@ -832,7 +857,7 @@ parser! {
         rule AskQuery() -> Query = i("ASK") _ d:DatasetClauses() w:WhereClause() _ g:GroupClause()? _ h:HavingClause()? _ o:OrderClause()? _ l:LimitOffsetClauses()? _ v:ValuesClause() {
             Query::Ask {
                 dataset: d,
-                pattern: Rc::new(build_select(Selection::default(), w, g, h, o, l, v, state)),
+                pattern: Arc::new(build_select(Selection::default(), w, g, h, o, l, v, state)),
                 base_iri: state.base_iri.clone()
             }
         }
@@ -1138,9 +1163,10 @@ parser! {
         //[49]
         rule QuadData() -> Vec<Quad> = "{" _ q:Quads() _ "}" {?
             q.into_iter().map(|q| Ok(Quad {
-                subject: match q.subject {
-                    TermOrVariable::Term(Term::NamedNode(t)) => t.into(),
-                    TermOrVariable::Term(Term::BlankNode(t)) => t.into(),
+                subject: match ground_term(q.subject) {
+                    Some(Term::NamedNode(t)) => t.into(),
+                    Some(Term::BlankNode(t)) => t.into(),
+                    Some(Term::Triple(t)) => t.into(),
                     _ => return Err(())
                 },
                 predicate: if let NamedNodeOrVariable::NamedNode(t) = q.predicate {
@@ -1148,11 +1174,7 @@ parser! {
                 } else {
                     return Err(())
                 },
-                object: if let TermOrVariable::Term(t) = q.object {
-                    t
-                } else {
-                    return Err(())
-                },
+                object: ground_term(q.object).ok_or(())?,
                 graph_name: match q.graph_name {
                     Some(NamedNodeOrVariable::NamedNode(t)) => t.into(),
                     None => GraphName::DefaultGraph,
@@ -1643,8 +1665,17 @@ parser! {
         //[106]
         rule VarOrTerm() -> TermOrVariable =
             v:Var() { v.into() } /
+            t:QuotedTP() { t.into() } /
             t:GraphTerm() { t.into() }
 
+        rule QuotedTP() -> TriplePattern = "<<" _ s:VarOrTerm() _ p:Verb() _ o:VarOrTerm() _ ">>" {?
+            if let TermOrVariable::Term(Term::Literal(_)) = s {
+                Err("Literals are not allowed as quoted triple subjects")
+            } else {
+                Ok(TriplePattern::new(s, p, o))
+            }
+        }
+
         //[107]
         rule VarOrIri() -> NamedNodeOrVariable =
             v:Var() { v.into() } /
@@ -1798,6 +1829,11 @@ parser! {
             i("isBLANK") "(" _ e:Expression() _ ")" { Expression::FunctionCall(Function::IsBlank, vec![e]) } /
             i("isLITERAL") "(" _ e:Expression() _ ")" { Expression::FunctionCall(Function::IsLiteral, vec![e]) } /
             i("isNUMERIC") "(" _ e:Expression() _ ")" { Expression::FunctionCall(Function::IsNumeric, vec![e]) } /
+            i("TRIPLE") _ "(" _ s:Expression() _ "," _ p:Expression() _ "," _ o:Expression() _ ")" { Expression::FunctionCall(Function::Triple, vec![s, p, o]) } /
+            i("SUBJECT") _ "(" _ e:Expression() _ ")" { Expression::FunctionCall(Function::Subject, vec![e]) } /
+            i("PREDICATE") _ "(" _ e:Expression() _ ")" { Expression::FunctionCall(Function::Predicate, vec![e]) } /
+            i("OBJECT") _ "(" _ e:Expression() _ ")" { Expression::FunctionCall(Function::Object, vec![e]) } /
+            i("isTRIPLE") _ "(" _ e:Expression() _ ")" { Expression::FunctionCall(Function::IsTriple, vec![e]) } /
             RegexExpression() /
             ExistsFunc() /
             NotExistsFunc()
@@ -1,8 +1,9 @@
 use crate::sparql::algebra::GraphPattern;
 use crate::sparql::model::Variable;
-use crate::store::numeric_encoder::{EncodedTerm, StrId};
+use crate::store::numeric_encoder::{EncodedRange, EncodedTerm, StrId};
+use crate::store::spatial::SearchBox;
 use std::collections::BTreeSet;
-use std::rc::Rc;
+use std::sync::Arc;
 
 #[derive(Eq, PartialEq, Debug, Clone, Hash)]
 pub enum PlanNode<I: StrId> {
@@ -11,75 +12,97 @@ pub enum PlanNode<I: StrId> {
         tuples: Vec<EncodedTuple<I>>,
     },
     Service {
+        /// Identifies the node among the SERVICE nodes of its plan
+        id: usize,
         service_name: PatternValue<I>,
-        variables: Rc<Vec<Variable>>,
-        child: Rc<PlanNode<I>>,
-        graph_pattern: Rc<GraphPattern>,
+        variables: Arc<Vec<Variable>>,
+        child: Arc<PlanNode<I>>,
+        graph_pattern: Arc<GraphPattern>,
         silent: bool,
     },
     QuadPatternJoin {
-        child: Rc<PlanNode<I>>,
+        child: Arc<PlanNode<I>>,
         subject: PatternValue<I>,
         predicate: PatternValue<I>,
         object: PatternValue<I>,
         graph_name: PatternValue<I>,
+        object_range: Option<EncodedRange>, // range of the object values allowed by a FILTER
+        object_token: Option<Arc<str>>, // token that the object text must contain to pass a FILTER
+        object_box: Option<SearchBox>, // box that the object geometry must intersect to pass a FILTER
+    },
+    /// Binds `subject`, `predicate` and `object` to the components of the quoted triple `triple`
+    /// and drops the tuples where they do not match.
+    ///
+    /// `triple` should be bound by the child, usually by a quad pattern.
+    /// The quoted triples with already bound components are built before the pattern using a [`PlanNode::Extend`].
+    QuotedTriplePattern {
+        child: Arc<PlanNode<I>>,
+        triple: PatternValue<I>,
+        subject: PatternValue<I>,
+        predicate: PatternValue<I>,
+        object: PatternValue<I>,
     },
     PathPatternJoin {
-        child: Rc<PlanNode<I>>,
+        child: Arc<PlanNode<I>>,
         subject: PatternValue<I>,
-        path: Rc<PlanPropertyPath<I>>,
+        path: Arc<PlanPropertyPath<I>>,
         object: PatternValue<I>,
         graph_name: PatternValue<I>,
     },
     Join {
-        left: Rc<PlanNode<I>>,
-        right: Rc<PlanNode<I>>,
+        left: Arc<PlanNode<I>>,
+        right: Arc<PlanNode<I>>,
     },
     AntiJoin {
-        left: Rc<PlanNode<I>>,
-        right: Rc<PlanNode<I>>,
+        left: Arc<PlanNode<I>>,
+        right: Arc<PlanNode<I>>,
     },
     Filter {
-        child: Rc<PlanNode<I>>,
-        expression: Rc<PlanExpression<I>>,
+        child: Arc<PlanNode<I>>,
+        expression: Arc<PlanExpression<I>>,
     },
     Union {
-        children: Vec<Rc<PlanNode<I>>>,
+        children: Vec<Arc<PlanNode<I>>>,
     },
     LeftJoin {
-        left: Rc<PlanNode<I>>,
-        right: Rc<PlanNode<I>>,
-        possible_problem_vars: Rc<Vec<usize>>, //Variables that should not be part of the entry of the left join
+        left: Arc<PlanNode<I>>,
+        right: Arc<PlanNode<I>>,
+        possible_problem_vars: Arc<Vec<usize>>, //Variables that should not be part of the entry of the left join
     },
     Extend {
-        child: Rc<PlanNode<I>>,
+        child: Arc<PlanNode<I>>,
         position: usize,
-        expression: Rc<PlanExpression<I>>,
+        expression: Arc<PlanExpression<I>>,
     },
     Sort {
-        child: Rc<PlanNode<I>>,
+        child: Arc<PlanNode<I>>,
         by: Vec<Comparator<I>>,
     },
     HashDeduplicate {
-        child: Rc<PlanNode<I>>,
+        child: Arc<PlanNode<I>>,
     },
     Skip {
-        child: Rc<PlanNode<I>>,
+        child: Arc<PlanNode<I>>,
         count: usize,
     },
     Limit {
-        child: Rc<PlanNode<I>>,
+        child: Arc<PlanNode<I>>,
         count: usize,
     },
     Project {
-        child: Rc<PlanNode<I>>,
-        mapping: Rc<Vec<(usize, usize)>>, // pairs of (variable key in child, variable key in output)
+        child: Arc<PlanNode<I>>,
+        mapping: Arc<Vec<(usize, usize)>>, // pairs of (variable key in child, variable key in output)
     },
     Aggregate {
         // By definition the group by key are the range 0..key_mapping.len()
-        child: Rc<PlanNode<I>>,
-        key_mapping: Rc<Vec<(usize, usize)>>, // aggregate key pairs of (variable key in child, variable key in output)
-        aggregates: Rc<Vec<(PlanAggregation<I>, usize)>>,
+        child: Arc<PlanNode<I>>,
+        key_mapping: Arc<Vec<(usize, usize)>>, // aggregate key pairs of (variable key in child, variable key in output)
+        aggregates: Arc<Vec<(PlanAggregation<I>, usize)>>,
+    },
+    /// Evaluates `child` with the variables bound to the parameters of a prepared query.
+    /// The returned tuples are only the ones compatible with the parameters.
+    Parameters {
+        child: Arc<PlanNode<I>>,
     },
 }
 
@@ -109,6 +132,7 @@ impl<I: StrId> PlanNode<I> {
                 predicate,
                 object,
                 graph_name,
+                ..
             } => {
                 if let PatternValue::Variable(var) = subject {
                     set.insert(*var);
@@ -124,6 +148,20 @@ impl<I: StrId> PlanNode<I> {
                 }
                 child.add_maybe_bound_variables(set);
             }
+            PlanNode::QuotedTriplePattern {
+                child,
+                triple,
+                subject,
+                predicate,
+                object,
+            } => {
+                for value in &[triple, subject, predicate, object] {
+                    if let PatternValue::Variable(var) = value {
+                        set.insert(*var);
+                    }
+                }
+                child.add_maybe_bound_variables(set);
+            }
             PlanNode::PathPatternJoin {
                 child,
                 subject,
@@ -170,7 +208,8 @@ impl<I: StrId> PlanNode<I> {
             | PlanNode::Sort { child, .. }
             | PlanNode::HashDeduplicate { child }
             | PlanNode::Skip { child, .. }
-            | PlanNode::Limit { child, .. } => child.add_maybe_bound_variables(set),
+            | PlanNode::Limit { child, .. }
+            | PlanNode::Parameters { child } => child.add_maybe_bound_variables(set),
             PlanNode::Project { mapping, child } => {
                 let child_bound = child.maybe_bound_variables();
                 for (child_i, output_i) in mapping.iter() {
@@ -212,7 +251,7 @@ impl<I: StrId> PatternValue<I> {
 pub enum PlanExpression<I: StrId> {
     Constant(EncodedTerm<I>),
     Variable(usize),
-    Exists(Rc<PlanNode<I>>),
+    Exists(Arc<PlanNode<I>>),
     Or(Box<PlanExpression<I>>, Box<PlanExpression<I>>),
     And(Box<PlanExpression<I>>, Box<PlanExpression<I>>),
     Equal(Box<PlanExpression<I>>, Box<PlanExpression<I>>),
@@ -290,11 +329,42 @@ pub enum PlanExpression<I: StrId> {
     IsBlank(Box<PlanExpression<I>>),
     IsLiteral(Box<PlanExpression<I>>),
     IsNumeric(Box<PlanExpression<I>>),
+    Triple(
+        Box<PlanExpression<I>>,
+        Box<PlanExpression<I>>,
+        Box<PlanExpression<I>>,
+    ),
+    Subject(Box<PlanExpression<I>>),
+    Predicate(Box<PlanExpression<I>>),
+    Object(Box<PlanExpression<I>>),
+    IsTriple(Box<PlanExpression<I>>),
     Regex(
         Box<PlanExpression<I>>,
         Box<PlanExpression<I>>,
         Option<Box<PlanExpression<I>>>,
     ),
+    TextMatch(
+        Box<PlanExpression<I>>,
+        Box<PlanExpression<I>>,
+        Option<Box<PlanExpression<I>>>,
+    ),
+    TextScore(Box<PlanExpression<I>>, Box<PlanExpression<I>>),
+    GeoDistance(
+        Box<PlanExpression<I>>,
+        Box<PlanExpression<I>>,
+        Option<Box<PlanExpression<I>>>,
+    ),
+    GeoIntersects(Box<PlanExpression<I>>, Box<PlanExpression<I>>),
+    GeoDisjoint(Box<PlanExpression<I>>, Box<PlanExpression<I>>),
+    GeoWithin(Box<PlanExpression<I>>, Box<PlanExpression<I>>),
+    GeoContains(Box<PlanExpression<I>>, Box<PlanExpression<I>>),
+    GeoBuffer(
+        Box<PlanExpression<I>>,
+        Box<PlanExpression<I>>,
+        Option<Box<PlanExpression<I>>>,
+    ),
+    GeoEnvelope(Box<PlanExpression<I>>),
+    GeoConvexHull(Box<PlanExpression<I>>),
     BooleanCast(Box<PlanExpression<I>>),
     DoubleCast(Box<PlanExpression<I>>),
     FloatCast(Box<PlanExpression<I>>),
@@ -354,6 +424,12 @@ impl<I: StrId> PlanExpression<I> {
             | PlanExpression::IsBlank(e)
             | PlanExpression::IsLiteral(e)
             | PlanExpression::IsNumeric(e)
+            | PlanExpression::Subject(e)
+            | PlanExpression::Predicate(e)
+            | PlanExpression::Object(e)
+            | PlanExpression::IsTriple(e)
+            | PlanExpression::GeoEnvelope(e)
+            | PlanExpression::GeoConvexHull(e)
             | PlanExpression::BooleanCast(e)
             | PlanExpression::DoubleCast(e)
             | PlanExpression::FloatCast(e)
@@ -387,13 +463,25 @@ impl<I: StrId> PlanExpression<I> {
             | PlanExpression::StrDT(a, b)
             | PlanExpression::SameTerm(a, b)
             | PlanExpression::SubStr(a, b, None)
-            | PlanExpression::Regex(a, b, None) => {
+            | PlanExpression::Regex(a, b, None)
+            | PlanExpression::TextMatch(a, b, None)
+            | PlanExpression::TextScore(a, b)
+            | PlanExpression::GeoDistance(a, b, None)
+            | PlanExpression::GeoIntersects(a, b)
+            | PlanExpression::GeoDisjoint(a, b)
+            | PlanExpression::GeoWithin(a, b)
+            | PlanExpression::GeoContains(a, b)
+            | PlanExpression::GeoBuffer(a, b, None) => {
                 a.add_maybe_bound_variables(set);
                 b.add_maybe_bound_variables(set);
             }
             PlanExpression::If(a, b, c)
+            | PlanExpression::Triple(a, b, c)
             | PlanExpression::SubStr(a, b, Some(c))
             | PlanExpression::Regex(a, b, Some(c))
+            | PlanExpression::TextMatch(a, b, Some(c))
+            | PlanExpression::GeoDistance(a, b, Some(c))
+            | PlanExpression::GeoBuffer(a, b, Some(c))
             | PlanExpression::Replace(a, b, c, None) => {
                 a.add_maybe_bound_variables(set);
                 b.add_maybe_bound_variables(set);
@@ -439,19 +527,19 @@ pub enum PlanAggregationFunction {
     Max,
     Avg,
     Sample,
-    GroupConcat { separator: Rc<String> },
+    GroupConcat { separator: Arc<String> },
 }
 
 #[derive(Eq, PartialEq, Debug, Clone, Hash)]
 pub enum PlanPropertyPath<I: StrId> {
     Path(EncodedTerm<I>),
-    Reverse(Rc<PlanPropertyPath<I>>),
-    Sequence(Rc<PlanPropertyPath<I>>, Rc<PlanPropertyPath<I>>),
-    Alternative(Rc<PlanPropertyPath<I>>, Rc<PlanPropertyPath<I>>),
-    ZeroOrMore(Rc<PlanPropertyPath<I>>),
-    OneOrMore(Rc<PlanPropertyPath<I>>),
-    ZeroOrOne(Rc<PlanPropertyPath<I>>),
-    NegatedPropertySet(Rc<Vec<EncodedTerm<I>>>),
+    Reverse(Arc<PlanPropertyPath<I>>),
+    Sequence(Arc<PlanPropertyPath<I>>, Arc<PlanPropertyPath<I>>),
+    Alternative(Arc<PlanPropertyPath<I>>, Arc<PlanPropertyPath<I>>),
+    ZeroOrMore(Arc<PlanPropertyPath<I>>),
+    OneOrMore(Arc<PlanPropertyPath<I>>),
+    ZeroOrOne(Arc<PlanPropertyPath<I>>),
+    NegatedPropertySet(Arc<Vec<EncodedTerm<I>>>),
 }
 
 #[derive(Eq, PartialEq, Debug, Clone, Hash)]
@@ -460,18 +548,19 @@ pub enum Comparator<I: StrId> {
     Desc(PlanExpression<I>),
 }
 
-#[derive(Eq, PartialEq, Debug, Clone, Copy, Hash)]
+#[derive(Eq, PartialEq, Debug, Clone, Hash)]
 pub struct TripleTemplate<I: StrId> {
     pub subject: TripleTemplateValue<I>,
     pub predicate: TripleTemplateValue<I>,
     pub object: TripleTemplateValue<I>,
 }
 
-#[derive(Eq, PartialEq, Debug, Clone, Copy, Hash)]
+#[derive(Eq, PartialEq, Debug, Clone, Hash)]
 pub enum TripleTemplateValue<I: StrId> {
     Constant(EncodedTerm<I>),
     BlankNode(usize),
     Variable(usize),
+    Triple(Box<TripleTemplate<I>>),
 }
 
 #[derive(Eq, PartialEq, Debug, Clone, Hash)]
@@ -1,14 +1,24 @@
+use crate::model::geo::{metres_to_degrees, DistanceUnit, Geometry, GEOF};
+use crate::model::vocab::xsd;
 use crate::model::{BlankNode, Literal, NamedNode, Term};
 use crate::sparql::algebra::*;
 use crate::sparql::error::EvaluationError;
 use crate::sparql::model::*;
 use crate::sparql::plan::*;
-use crate::store::numeric_encoder::{EncodedTerm, WriteEncoder};
-use std::collections::{BTreeSet, HashSet};
-use std::rc::Rc;
+use crate::store::numeric_encoder::{EncodedRange, EncodedTerm, StrId, WriteEncoder};
+use crate::store::spatial::SearchBox;
+use crate::store::text::lookup_token;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::sync::Arc;
 
 pub(crate) struct PlanBuilder<E: WriteEncoder> {
     encoder: E,
+    /// The pattern on which the parameters of a prepared query are bound
+    parameters_pattern: Option<*const GraphPattern>,
+    /// The variables of the scope of `parameters_pattern` in the order of their keys
+    parameters_variables: Vec<Variable>,
+    /// The number of SERVICE nodes built so far, used as their ids
+    service_count: usize,
 }
 
 impl<E: WriteEncoder<Error = EvaluationError>> PlanBuilder<E> {
@@ -17,7 +27,7 @@ impl<E: WriteEncoder<Error = EvaluationError>> PlanBuilder<E> {
         pattern: &GraphPattern,
     ) -> Result<(PlanNode<E::StrId>, Vec<Variable>), EvaluationError> {
         let mut variables = Vec::default();
-        let plan = PlanBuilder { encoder }.build_for_graph_pattern(
+        let plan = PlanBuilder::new(encoder).build_for_graph_pattern(
             pattern,
             &mut variables,
             PatternValue::Constant(EncodedTerm::DefaultGraph),
@@ -25,12 +35,40 @@ impl<E: WriteEncoder<Error = EvaluationError>> PlanBuilder<E> {
         Ok((plan, variables))
     }
 
+    /// Builds the plan of a prepared query.
+    ///
+    /// The parameters are bound on the pattern below the solution modifiers using a [`PlanNode::Parameters`] node.
+    /// The variables that could be bound by the parameters are returned in the order of their keys.
+    pub fn build_prepared(
+        encoder: E,
+        pattern: &GraphPattern,
+    ) -> Result<(PlanNode<E::StrId>, Vec<Variable>, Vec<Variable>), EvaluationError> {
+        let mut variables = Vec::default();
+        let mut builder = PlanBuilder::new(encoder);
+        builder.parameters_pattern = Some(parameters_pattern(pattern));
+        let plan = builder.build_for_graph_pattern(
+            pattern,
+            &mut variables,
+            PatternValue::Constant(EncodedTerm::DefaultGraph),
+        )?;
+        Ok((plan, variables, builder.parameters_variables))
+    }
+
     pub fn build_graph_template(
         encoder: E,
         template: &[TriplePattern],
         mut variables: Vec<Variable>,
     ) -> Result<Vec<TripleTemplate<E::StrId>>, EvaluationError> {
-        PlanBuilder { encoder }.build_for_graph_template(template, &mut variables)
+        PlanBuilder::new(encoder).build_for_graph_template(template, &mut variables)
+    }
+
+    fn new(encoder: E) -> Self {
+        Self {
+            encoder,
+            parameters_pattern: None,
+            parameters_variables: Vec::new(),
+            service_count: 0,
+        }
     }
 
     fn build_for_graph_pattern(
@@ -39,19 +77,46 @@ impl<E: WriteEncoder<Error = EvaluationError>> PlanBuilder<E> {
         variables: &mut Vec<Variable>,
         graph_name: PatternValue<E::StrId>,
     ) -> Result<PlanNode<E::StrId>, EvaluationError> {
+        if self.parameters_pattern.map_or(false, |parameters_pattern| {
+            std::ptr::eq(parameters_pattern, pattern)
+        }) {
+            self.parameters_pattern = None;
+            let child = self.build_for_graph_pattern(pattern, variables, graph_name)?;
+            self.parameters_variables = variables.clone();
+            return Ok(PlanNode::Parameters {
+                child: Arc::new(child),
+            });
+        }
         Ok(match pattern {
             GraphPattern::BGP(p) => self.build_for_bgp(p, variables, graph_name)?,
             GraphPattern::Path {
                 subject,
                 path,
                 object,
-            } => PlanNode::PathPatternJoin {
-                child: Rc::new(PlanNode::Init),
-                subject: self.pattern_value_from_term_or_variable(subject, variables)?,
-                path: Rc::new(self.build_for_path(path)?),
-                object: self.pattern_value_from_term_or_variable(object, variables)?,
-                graph_name,
-            },
+            } => {
+                let mut quoted_triples = Vec::new();
+                let subject = self.pattern_value_from_term_or_variable(
+                    subject,
+                    variables,
+                    &mut quoted_triples,
+                )?;
+                let object = self.pattern_value_from_term_or_variable(
+                    object,
+                    variables,
+                    &mut quoted_triples,
+                )?;
+                let plan = PlanNode::PathPatternJoin {
+                    child: Arc::new(add_quoted_triple_builds(
+                        PlanNode::Init,
+                        quoted_triples.iter(),
+                    )),
+                    subject,
+                    path: Arc::new(self.build_for_path(path)?),
+                    object,
+                    graph_name,
+                };
+                add_quoted_triple_patterns(plan, quoted_triples.iter().rev())
+            }
             GraphPattern::Join { left, right } => {
                 //TODO: improve
                 if let GraphPattern::Path {
@@ -61,17 +126,31 @@ impl<E: WriteEncoder<Error = EvaluationError>> PlanBuilder<E> {
                 } = right.as_ref()
                 {
                     let left = self.build_for_graph_pattern(left, variables, graph_name)?;
-                    PlanNode::PathPatternJoin {
-                        child: Rc::new(left),
-                        subject: self.pattern_value_from_term_or_variable(subject, variables)?,
-                        path: Rc::new(self.build_for_path(path)?),
-                        object: self.pattern_value_from_term_or_variable(object, variables)?,
+                    let mut quoted_triples = Vec::new();
+                    let subject = self.pattern_value_from_term_or_variable(
+                        subject,
+                        variables,
+                        &mut quoted_triples,
+                    )?;
+                    let object = self.pattern_value_from_term_or_variable(
+                        object,
+                        variables,
+                        &mut quoted_triples,
+                    )?;
+                    let plan = PlanNode::PathPatternJoin {
+                        child: Arc::new(add_quoted_triple_builds(left, quoted_triples.iter())),
+                        subject,
+                        path: Arc::new(self.build_for_path(path)?),
+                        object,
                         graph_name,
-                    }
+                    };
+                    add_quoted_triple_patterns(plan, quoted_triples.iter().rev())
                 } else {
                     PlanNode::Join {
-                        left: Rc::new(self.build_for_graph_pattern(left, variables, graph_name)?),
-                        right: Rc::new(self.build_for_graph_pattern(right, variables, graph_name)?),
+                        left: Arc::new(self.build_for_graph_pattern(left, variables, graph_name)?),
+                        right: Arc::new(
+                            self.build_for_graph_pattern(right, variables, graph_name)?,
+                        ),
                     }
                 }
             }
@@ -85,8 +164,8 @@ impl<E: WriteEncoder<Error = EvaluationError>> PlanBuilder<E> {
                 //We add the extra filter if needed
                 let right = if let Some(expr) = expr {
                     PlanNode::Filter {
-                        child: Rc::new(right),
-                        expression: Rc::new(
+                        child: Arc::new(right),
+                        expression: Arc::new(
                             self.build_for_expression(expr, variables, graph_name)?,
                         ),
                     }
@@ -95,15 +174,22 @@ impl<E: WriteEncoder<Error = EvaluationError>> PlanBuilder<E> {
                 };
 
                 PlanNode::LeftJoin {
-                    left: Rc::new(left),
-                    right: Rc::new(right),
-                    possible_problem_vars: Rc::new(possible_problem_vars.into_iter().collect()),
+                    left: Arc::new(left),
+                    right: Arc::new(right),
+                    possible_problem_vars: Arc::new(possible_problem_vars.into_iter().collect()),
+                }
+            }
+            GraphPattern::Filter { expr, inner } => {
+                let mut child = self.build_for_graph_pattern(inner, variables, graph_name)?;
+                let expression = self.build_for_expression(expr, variables, graph_name)?;
+                add_object_ranges(&mut child, &expression);
+                add_object_tokens(&mut child, expr, variables);
+                add_object_boxes(&mut child, expr, variables);
+                PlanNode::Filter {
+                    child: Arc::new(child),
+                    expression: Arc::new(expression),
                 }
             }
-            GraphPattern::Filter { expr, inner } => PlanNode::Filter {
-                child: Rc::new(self.build_for_graph_pattern(inner, variables, graph_name)?),
-                expression: Rc::new(self.build_for_expression(expr, variables, graph_name)?),
-            },
             GraphPattern::Union { left, right } => {
                 //We flatten the UNIONs
                 let mut stack: Vec<&GraphPattern> = vec![left, right];
@@ -115,7 +201,7 @@ impl<E: WriteEncoder<Error = EvaluationError>> PlanBuilder<E> {
                             stack.push(left);
                             stack.push(right);
                         }
-                        Some(p) => children.push(Rc::new(
+                        Some(p) => children.push(Arc::new(
                             self.build_for_graph_pattern(p, variables, graph_name)?,
                         )),
                     }
@@ -128,13 +214,13 @@ impl<E: WriteEncoder<Error = EvaluationError>> PlanBuilder<E> {
                 self.build_for_graph_pattern(inner, variables, graph_name)?
             }
             GraphPattern::Extend { inner, var, expr } => PlanNode::Extend {
-                child: Rc::new(self.build_for_graph_pattern(inner, variables, graph_name)?),
+                child: Arc::new(self.build_for_graph_pattern(inner, variables, graph_name)?),
                 position: variable_key(variables, var),
-                expression: Rc::new(self.build_for_expression(expr, variables, graph_name)?),
+                expression: Arc::new(self.build_for_expression(expr, variables, graph_name)?),
             },
             GraphPattern::Minus { left, right } => PlanNode::AntiJoin {
-                left: Rc::new(self.build_for_graph_pattern(left, variables, graph_name)?),
-                right: Rc::new(self.build_for_graph_pattern(right, variables, graph_name)?),
+                left: Arc::new(self.build_for_graph_pattern(left, variables, graph_name)?),
+                right: Arc::new(self.build_for_graph_pattern(right, variables, graph_name)?),
             },
             GraphPattern::Service {
                 name,
@@ -145,11 +231,13 @@ impl<E: WriteEncoder<Error = EvaluationError>> PlanBuilder<E> {
                 let child = self.build_for_graph_pattern(pattern, variables, graph_name)?;
                 let service_name =
                     self.pattern_value_from_named_node_or_variable(name, variables)?;
+                self.service_count += 1;
                 PlanNode::Service {
+                    id: self.service_count,
                     service_name,
-                    variables: Rc::new(variables.clone()),
-                    child: Rc::new(child),
-                    graph_pattern: Rc::new(*pattern.clone()),
+                    variables: Arc::new(variables.clone()),
+                    child: Arc::new(child),
+                    graph_pattern: Arc::new(*pattern.clone()),
                     silent: *silent,
                 }
             }
@@ -163,12 +251,12 @@ impl<E: WriteEncoder<Error = EvaluationError>> PlanBuilder<E> {
                     self.convert_pattern_value_id(graph_name, variables, &mut inner_variables);
 
                 PlanNode::Aggregate {
-                    child: Rc::new(self.build_for_graph_pattern(
+                    child: Arc::new(self.build_for_graph_pattern(
                         inner,
                         &mut inner_variables,
                         inner_graph_name,
                     )?),
-                    key_mapping: Rc::new(
+                    key_mapping: Arc::new(
                         by.iter()
                             .map(|k| {
                                 (
@@ -178,7 +266,7 @@ impl<E: WriteEncoder<Error = EvaluationError>> PlanBuilder<E> {
                             })
                             .collect(),
                     ),
-                    aggregates: Rc::new(
+                    aggregates: Arc::new(
                         aggregates
                             .iter()
                             .map(|(v, a)| {
@@ -210,7 +298,7 @@ impl<E: WriteEncoder<Error = EvaluationError>> PlanBuilder<E> {
                     })
                     .collect();
                 PlanNode::Sort {
-                    child: Rc::new(self.build_for_graph_pattern(inner, variables, graph_name)?),
+                    child: Arc::new(self.build_for_graph_pattern(inner, variables, graph_name)?),
                     by: condition?,
                 }
             }
@@ -219,12 +307,12 @@ impl<E: WriteEncoder<Error = EvaluationError>> PlanBuilder<E> {
                 let inner_graph_name =
                     self.convert_pattern_value_id(graph_name, variables, &mut inner_variables);
                 PlanNode::Project {
-                    child: Rc::new(self.build_for_graph_pattern(
+                    child: Arc::new(self.build_for_graph_pattern(
                         inner,
                         &mut inner_variables,
                         inner_graph_name,
                     )?),
-                    mapping: Rc::new(
+                    mapping: Arc::new(
                         projection
                             .iter()
                             .enumerate()
@@ -236,7 +324,7 @@ impl<E: WriteEncoder<Error = EvaluationError>> PlanBuilder<E> {
                 }
             }
             GraphPattern::Distinct { inner } => PlanNode::HashDeduplicate {
-                child: Rc::new(self.build_for_graph_pattern(inner, variables, graph_name)?),
+                child: Arc::new(self.build_for_graph_pattern(inner, variables, graph_name)?),
             },
             GraphPattern::Reduced { inner } => {
                 self.build_for_graph_pattern(inner, variables, graph_name)?
@@ -249,13 +337,13 @@ impl<E: WriteEncoder<Error = EvaluationError>> PlanBuilder<E> {
                 let mut plan = self.build_for_graph_pattern(inner, variables, graph_name)?;
                 if *start > 0 {
                     plan = PlanNode::Skip {
-                        child: Rc::new(plan),
+                        child: Arc::new(plan),
                         count: *start,
                     };
                 }
                 if let Some(length) = length {
                     plan = PlanNode::Limit {
-                        child: Rc::new(plan),
+                        child: Arc::new(plan),
                         count: *length,
                     };
                 }
@@ -272,14 +360,30 @@ impl<E: WriteEncoder<Error = EvaluationError>> PlanBuilder<E> {
     ) -> Result<PlanNode<E::StrId>, EvaluationError> {
         let mut plan = PlanNode::Init;
         for pattern in sort_bgp(p) {
+            let mut quoted_triples = Vec::new();
+            let subject = self.pattern_value_from_term_or_variable(
+                &pattern.subject,
+                variables,
+                &mut quoted_triples,
+            )?;
+            let predicate =
+                self.pattern_value_from_named_node_or_variable(&pattern.predicate, variables)?;
+            let object = self.pattern_value_from_term_or_variable(
+                &pattern.object,
+                variables,
+                &mut quoted_triples,
+            )?;
             plan = PlanNode::QuadPatternJoin {
-                child: Rc::new(plan),
-                subject: self.pattern_value_from_term_or_variable(&pattern.subject, variables)?,
-                predicate: self
-                    .pattern_value_from_named_node_or_variable(&pattern.predicate, variables)?,
-                object: self.pattern_value_from_term_or_variable(&pattern.object, variables)?,
+                child: Arc::new(add_quoted_triple_builds(plan, quoted_triples.iter())),
+                subject,
+                predicate,
+                object,
                 graph_name,
-            }
+                object_range: None,
+                object_token: None,
+                object_box: None,
+            };
+            plan = add_quoted_triple_patterns(plan, quoted_triples.iter().rev());
         }
         Ok(plan)
     }
@@ -293,27 +397,27 @@ impl<E: WriteEncoder<Error = EvaluationError>> PlanBuilder<E> {
                 PlanPropertyPath::Path(self.build_named_node(p)?)
             }
             PropertyPathExpression::Reverse(p) => {
-                PlanPropertyPath::Reverse(Rc::new(self.build_for_path(p)?))
+                PlanPropertyPath::Reverse(Arc::new(self.build_for_path(p)?))
             }
             PropertyPathExpression::Alternative(a, b) => PlanPropertyPath::Alternative(
-                Rc::new(self.build_for_path(a)?),
-                Rc::new(self.build_for_path(b)?),
+                Arc::new(self.build_for_path(a)?),
+                Arc::new(self.build_for_path(b)?),
             ),
             PropertyPathExpression::Sequence(a, b) => PlanPropertyPath::Sequence(
-                Rc::new(self.build_for_path(a)?),
-                Rc::new(self.build_for_path(b)?),
+                Arc::new(self.build_for_path(a)?),
+                Arc::new(self.build_for_path(b)?),
             ),
             PropertyPathExpression::ZeroOrMore(p) => {
-                PlanPropertyPath::ZeroOrMore(Rc::new(self.build_for_path(p)?))
+                PlanPropertyPath::ZeroOrMore(Arc::new(self.build_for_path(p)?))
             }
             PropertyPathExpression::OneOrMore(p) => {
-                PlanPropertyPath::OneOrMore(Rc::new(self.build_for_path(p)?))
+                PlanPropertyPath::OneOrMore(Arc::new(self.build_for_path(p)?))
             }
             PropertyPathExpression::ZeroOrOne(p) => {
-                PlanPropertyPath::ZeroOrOne(Rc::new(self.build_for_path(p)?))
+                PlanPropertyPath::ZeroOrOne(Arc::new(self.build_for_path(p)?))
             }
             PropertyPathExpression::NegatedPropertySet(p) => {
-                PlanPropertyPath::NegatedPropertySet(Rc::new(
+                PlanPropertyPath::NegatedPropertySet(Arc::new(
                     p.iter()
                         .map(|p| self.build_named_node(p))
                         .collect::<Result<Vec<_>, _>>()?,
@@ -595,6 +699,27 @@ impl<E: WriteEncoder<Error = EvaluationError>> PlanBuilder<E> {
                 Function::IsNumeric => PlanExpression::IsNumeric(Box::new(
                     self.build_for_expression(&parameters[0], variables, graph_name)?,
                 )),
+                Function::Triple => PlanExpression::Triple(
+                    Box::new(self.build_for_expression(&parameters[0], variables, graph_name)?),
+                    Box::new(self.build_for_expression(&parameters[1], variables, graph_name)?),
+                    Box::new(self.build_for_expression(&parameters[2], variables, graph_name)?),
+                ),
+                Function::Subject => PlanExpression::Subject(Box::new(self.build_for_expression(
+                    &parameters[0],
+                    variables,
+                    graph_name,
+                )?)),
+                Function::Predicate => PlanExpression::Predicate(Box::new(
+                    self.build_for_expression(&parameters[0], variables, graph_name)?,
+                )),
+                Function::Object => PlanExpression::Object(Box::new(self.build_for_expression(
+                    &parameters[0],
+                    variables,
+                    graph_name,
+                )?)),
+                Function::IsTriple => PlanExpression::IsTriple(Box::new(
+                    self.build_for_expression(&parameters[0], variables, graph_name)?,
+                )),
                 Function::Regex => PlanExpression::Regex(
                     Box::new(self.build_for_expression(&parameters[0], variables, graph_name)?),
                     Box::new(self.build_for_expression(&parameters[1], variables, graph_name)?),
@@ -702,6 +827,52 @@ impl<E: WriteEncoder<Error = EvaluationError>> PlanBuilder<E> {
                             graph_name,
                             "string",
                         )?
+                    } else if name == "http://oxigraph.org/fn/textMatch" {
+                        if parameters.len() != 2 && parameters.len() != 3 {
+                            return Err(EvaluationError::msg(
+                                "The textMatch function takes two or three parameters",
+                            ));
+                        }
+                        PlanExpression::TextMatch(
+                            Box::new(self.build_for_expression(
+                                &parameters[0],
+                                variables,
+                                graph_name,
+                            )?),
+                            Box::new(self.build_for_expression(
+                                &parameters[1],
+                                variables,
+                                graph_name,
+                            )?),
+                            match parameters.get(2) {
+                                Some(language_range) => Some(Box::new(self.build_for_expression(
+                                    language_range,
+                                    variables,
+                                    graph_name,
+                                )?)),
+                                None => None,
+                            },
+                        )
+                    } else if let Some(function) = name.as_str().strip_prefix(GEOF) {
+                        self.build_geo_function(function, parameters, variables, graph_name)?
+                    } else if name == "http://oxigraph.org/fn/textScore" {
+                        if parameters.len() != 2 {
+                            return Err(EvaluationError::msg(
+                                "The textScore function takes two parameters",
+                            ));
+                        }
+                        PlanExpression::TextScore(
+                            Box::new(self.build_for_expression(
+                                &parameters[0],
+                                variables,
+                                graph_name,
+                            )?),
+                            Box::new(self.build_for_expression(
+                                &parameters[1],
+                                variables,
+                                graph_name,
+                            )?),
+                        )
                     } else {
                         return Err(EvaluationError::msg(format!(
                             "Not supported custom function {}",
@@ -716,7 +887,7 @@ impl<E: WriteEncoder<Error = EvaluationError>> PlanBuilder<E> {
                 Box::new(self.build_for_expression(b, variables, graph_name)?),
                 Box::new(self.build_for_expression(c, variables, graph_name)?),
             ),
-            Expression::Exists(n) => PlanExpression::Exists(Rc::new(
+            Expression::Exists(n) => PlanExpression::Exists(Arc::new(
                 self.build_for_graph_pattern(n, variables, graph_name)?,
             )),
             Expression::Coalesce(parameters) => {
@@ -747,6 +918,57 @@ impl<E: WriteEncoder<Error = EvaluationError>> PlanBuilder<E> {
         }
     }
 
+    fn build_geo_function(
+        &mut self,
+        function: &str,
+        parameters: &[Expression],
+        variables: &mut Vec<Variable>,
+        graph_name: PatternValue<E::StrId>,
+    ) -> Result<PlanExpression<E::StrId>, EvaluationError> {
+        let arity_error = |arity: &str| {
+            Err(EvaluationError::msg(format!(
+                "The geof:{} function takes {}",
+                function, arity
+            )))
+        };
+        if parameters.len() > 3 {
+            return arity_error("at most three parameters");
+        }
+        let mut parameters = self
+            .expression_list(parameters, variables, graph_name)?
+            .into_iter()
+            .map(Box::new);
+        Ok(
+            match (
+                function,
+                parameters.next(),
+                parameters.next(),
+                parameters.next(),
+            ) {
+                ("distance", Some(a), Some(b), c) => PlanExpression::GeoDistance(a, b, c),
+                ("buffer", Some(a), Some(b), c) => PlanExpression::GeoBuffer(a, b, c),
+                ("sfIntersects", Some(a), Some(b), None) => PlanExpression::GeoIntersects(a, b),
+                ("sfDisjoint", Some(a), Some(b), None) => PlanExpression::GeoDisjoint(a, b),
+                ("sfWithin", Some(a), Some(b), None) => PlanExpression::GeoWithin(a, b),
+                ("sfContains", Some(a), Some(b), None) => PlanExpression::GeoContains(a, b),
+                ("envelope", Some(a), None, None) => PlanExpression::GeoEnvelope(a),
+                ("convexHull", Some(a), None, None) => PlanExpression::GeoConvexHull(a),
+                ("distance", ..) | ("buffer", ..) => return arity_error("two or three parameters"),
+                ("sfIntersects", ..)
+                | ("sfDisjoint", ..)
+                | ("sfWithin", ..)
+                | ("sfContains", ..) => return arity_error("two parameters"),
+                ("envelope", ..) | ("convexHull", ..) => return arity_error("one parameter"),
+                _ => {
+                    return Err(EvaluationError::msg(format!(
+                        "Not supported GeoSPARQL function geof:{}",
+                        function
+                    )))
+                }
+            },
+        )
+    }
+
     fn expression_list(
         &mut self,
         l: &[Expression],
@@ -758,10 +980,14 @@ impl<E: WriteEncoder<Error = EvaluationError>> PlanBuilder<E> {
             .collect()
     }
 
+    /// Quoted triple patterns are replaced by fresh variables.
+    /// The relations between these variables and the quoted triple components are pushed to `quoted_triples`,
+    /// the innermost quoted triples first.
     fn pattern_value_from_term_or_variable(
         &mut self,
         term_or_variable: &TermOrVariable,
         variables: &mut Vec<Variable>,
+        quoted_triples: &mut Vec<QuotedTriple<E::StrId>>,
     ) -> Result<PatternValue<E::StrId>, EvaluationError> {
         Ok(match term_or_variable {
             TermOrVariable::Variable(variable) => {
@@ -775,6 +1001,28 @@ impl<E: WriteEncoder<Error = EvaluationError>> PlanBuilder<E> {
                 //TODO: very bad hack to convert bnode to variable
             }
             TermOrVariable::Term(term) => PatternValue::Constant(self.build_term(term)?),
+            TermOrVariable::Triple(triple) => {
+                let subject = self.pattern_value_from_term_or_variable(
+                    &triple.subject,
+                    variables,
+                    quoted_triples,
+                )?;
+                let predicate =
+                    self.pattern_value_from_named_node_or_variable(&triple.predicate, variables)?;
+                let object = self.pattern_value_from_term_or_variable(
+                    &triple.object,
+                    variables,
+                    quoted_triples,
+                )?;
+                let triple = variable_key(variables, &Variable::new_random());
+                quoted_triples.push(QuotedTriple {
+                    triple,
+                    subject,
+                    predicate,
+                    object,
+                });
+                PatternValue::Variable(triple)
+            }
         })
     }
 
@@ -862,7 +1110,7 @@ impl<E: WriteEncoder<Error = EvaluationError>> PlanBuilder<E> {
                 separator,
             } => Ok(PlanAggregation {
                 function: PlanAggregationFunction::GroupConcat {
-                    separator: Rc::new(separator.clone().unwrap_or_else(|| " ".to_string())),
+                    separator: Arc::new(separator.clone().unwrap_or_else(|| " ".to_string())),
                 },
                 parameter: Some(self.build_for_expression(expr, variables, graph_name)?),
                 distinct: *distinct,
@@ -914,6 +1162,22 @@ impl<E: WriteEncoder<Error = EvaluationError>> PlanBuilder<E> {
                 TripleTemplateValue::BlankNode(bnode_key(bnodes, bnode))
             }
             TermOrVariable::Term(term) => TripleTemplateValue::Constant(self.build_term(term)?),
+            TermOrVariable::Triple(triple) => {
+                TripleTemplateValue::Triple(Box::new(TripleTemplate {
+                    subject: self.template_value_from_term_or_variable(
+                        &triple.subject,
+                        variables,
+                        bnodes,
+                    )?,
+                    predicate: self
+                        .template_value_from_named_node_or_variable(&triple.predicate, variables)?,
+                    object: self.template_value_from_term_or_variable(
+                        &triple.object,
+                        variables,
+                        bnodes,
+                    )?,
+                }))
+            }
         })
     }
 
@@ -975,6 +1239,7 @@ impl<E: WriteEncoder<Error = EvaluationError>> PlanBuilder<E> {
             PlanNode::Init
             | PlanNode::StaticBindings { .. }
             | PlanNode::QuadPatternJoin { .. }
+            | PlanNode::QuotedTriplePattern { .. }
             | PlanNode::PathPatternJoin { .. } => (),
             PlanNode::Filter { child, expression } => {
                 expression.add_maybe_bound_variables(set); //TODO: only if it is not already bound
@@ -1007,7 +1272,8 @@ impl<E: WriteEncoder<Error = EvaluationError>> PlanBuilder<E> {
             | PlanNode::Sort { child, .. }
             | PlanNode::HashDeduplicate { child }
             | PlanNode::Skip { child, .. }
-            | PlanNode::Limit { child, .. } => {
+            | PlanNode::Limit { child, .. }
+            | PlanNode::Parameters { child } => {
                 self.add_left_join_problematic_variables(&*child, set)
             }
             PlanNode::Project { mapping, child } => {
@@ -1052,6 +1318,57 @@ impl<E: WriteEncoder<Error = EvaluationError>> PlanBuilder<E> {
     }
 }
 
+/// A quoted triple pattern replaced by the variable `triple` with its components
+struct QuotedTriple<I: StrId> {
+    triple: usize,
+    subject: PatternValue<I>,
+    predicate: PatternValue<I>,
+    object: PatternValue<I>,
+}
+
+/// Binds the quoted triple variables if their components are already bound, the innermost quoted triples first
+fn add_quoted_triple_builds<'a, I: StrId + 'a>(
+    mut plan: PlanNode<I>,
+    quoted_triples: impl Iterator<Item = &'a QuotedTriple<I>>,
+) -> PlanNode<I> {
+    for quoted_triple in quoted_triples {
+        plan = PlanNode::Extend {
+            child: Arc::new(plan),
+            position: quoted_triple.triple,
+            expression: Arc::new(PlanExpression::Triple(
+                Box::new(pattern_value_expression(quoted_triple.subject)),
+                Box::new(pattern_value_expression(quoted_triple.predicate)),
+                Box::new(pattern_value_expression(quoted_triple.object)),
+            )),
+        };
+    }
+    plan
+}
+
+/// Matches the components of the quoted triple variables bound by a pattern, the outermost quoted triples first
+fn add_quoted_triple_patterns<'a, I: StrId + 'a>(
+    mut plan: PlanNode<I>,
+    quoted_triples: impl Iterator<Item = &'a QuotedTriple<I>>,
+) -> PlanNode<I> {
+    for quoted_triple in quoted_triples {
+        plan = PlanNode::QuotedTriplePattern {
+            child: Arc::new(plan),
+            triple: PatternValue::Variable(quoted_triple.triple),
+            subject: quoted_triple.subject,
+            predicate: quoted_triple.predicate,
+            object: quoted_triple.object,
+        };
+    }
+    plan
+}
+
+fn pattern_value_expression<I: StrId>(value: PatternValue<I>) -> PlanExpression<I> {
+    match value {
+        PatternValue::Constant(term) => PlanExpression::Constant(term),
+        PatternValue::Variable(variable) => PlanExpression::Variable(variable),
+    }
+}
+
 fn variable_key(variables: &mut Vec<Variable>, variable: &Variable) -> usize {
     match slice_key(variables, variable) {
         Some(key) => key,
@@ -1155,3 +1472,307 @@ fn add_pattern_variables<'a>(
         blank_nodes.insert(bnode);
     }
 }
+
+/// Sets the object ranges of the quad patterns binding variables constrained by a range FILTER
+fn add_object_ranges<I: StrId>(node: &mut PlanNode<I>, expression: &PlanExpression<I>) {
+    let mut bounds = BTreeMap::default();
+    add_expression_bounds(expression, &mut bounds);
+    for (variable, (lower, upper)) in bounds {
+        if let Some(range) = EncodedRange::new(lower, upper) {
+            add_object_range(node, variable, range);
+        }
+    }
+}
+
+type Bounds<I> = (Option<EncodedTerm<I>>, Option<EncodedTerm<I>>);
+
+fn add_expression_bounds<I: StrId>(
+    expression: &PlanExpression<I>,
+    bounds: &mut BTreeMap<usize, Bounds<I>>,
+) {
+    match expression {
+        PlanExpression::And(a, b) => {
+            add_expression_bounds(a, bounds);
+            add_expression_bounds(b, bounds);
+        }
+        PlanExpression::Equal(a, b) => {
+            if let Some((variable, value)) =
+                variable_and_constant(a, b).or_else(|| variable_and_constant(b, a))
+            {
+                bounds.insert(variable, (Some(value), Some(value)));
+            }
+        }
+        PlanExpression::Greater(a, b) | PlanExpression::GreaterOrEqual(a, b) => {
+            if let Some((variable, value)) = variable_and_constant(a, b) {
+                bounds.entry(variable).or_default().0 = Some(value);
+            } else if let Some((variable, value)) = variable_and_constant(b, a) {
+                bounds.entry(variable).or_default().1 = Some(value);
+            }
+        }
+        PlanExpression::Less(a, b) | PlanExpression::LessOrEqual(a, b) => {
+            if let Some((variable, value)) = variable_and_constant(a, b) {
+                bounds.entry(variable).or_default().1 = Some(value);
+            } else if let Some((variable, value)) = variable_and_constant(b, a) {
+                bounds.entry(variable).or_default().0 = Some(value);
+            }
+        }
+        _ => (),
+    }
+}
+
+fn variable_and_constant<I: StrId>(
+    a: &PlanExpression<I>,
+    b: &PlanExpression<I>,
+) -> Option<(usize, EncodedTerm<I>)> {
+    match (a, b) {
+        (PlanExpression::Variable(variable), PlanExpression::Constant(value)) => {
+            Some((*variable, *value))
+        }
+        _ => None,
+    }
+}
+
+/// Sets the object tokens of the quad patterns binding variables constrained by a textMatch FILTER
+fn add_object_tokens<I: StrId>(
+    node: &mut PlanNode<I>,
+    expression: &Expression,
+    variables: &[Variable],
+) {
+    match expression {
+        Expression::And(a, b) => {
+            add_object_tokens(node, a, variables);
+            add_object_tokens(node, b, variables);
+        }
+        Expression::FunctionCall(Function::Custom(name), parameters)
+            if name.as_str() == "http://oxigraph.org/fn/textMatch" =>
+        {
+            if let (Some(Expression::Variable(variable)), Some(Expression::Literal(query))) =
+                (parameters.first(), parameters.get(1))
+            {
+                if let (Some(variable), Some(token)) =
+                    (slice_key(variables, variable), lookup_token(query.value()))
+                {
+                    add_object_token(node, variable, &token.into());
+                }
+            }
+        }
+        _ => (),
+    }
+}
+
+/// Sets the object boxes of the quad patterns binding variables constrained by a GeoSPARQL FILTER
+fn add_object_boxes<I: StrId>(
+    node: &mut PlanNode<I>,
+    expression: &Expression,
+    variables: &[Variable],
+) {
+    match expression {
+        Expression::And(a, b) => {
+            add_object_boxes(node, a, variables);
+            add_object_boxes(node, b, variables);
+        }
+        Expression::FunctionCall(Function::Custom(name), parameters) => {
+            if let (
+                Some("sfWithin"),
+                [Expression::Variable(variable), Expression::Literal(geometry)],
+            )
+            | (
+                Some("sfIntersects"),
+                [Expression::Variable(variable), Expression::Literal(geometry)],
+            )
+            | (
+                Some("sfIntersects"),
+                [Expression::Literal(geometry), Expression::Variable(variable)],
+            )
+            | (
+                Some("sfContains"),
+                [Expression::Literal(geometry), Expression::Variable(variable)],
+            ) = (name.as_str().strip_prefix(GEOF), parameters.as_slice())
+            {
+                if let (Some(variable), Some(search_box)) = (
+                    slice_key(variables, variable),
+                    Geometry::from_literal(geometry.as_ref())
+                        .ok()
+                        .and_then(|geometry| SearchBox::new(geometry.envelope()?)),
+                ) {
+                    add_object_box(node, variable, search_box);
+                }
+            }
+        }
+        Expression::Less(distance, max)
+        | Expression::LessOrEqual(distance, max)
+        | Expression::Greater(max, distance)
+        | Expression::GreaterOrEqual(max, distance) => {
+            if let Some((variable, search_box)) = distance_search_box(distance, max, variables) {
+                add_object_box(node, variable, search_box);
+            }
+        }
+        _ => (),
+    }
+}
+
+/// Returns the box containing all the geometries at most `max` away from the constant geometry of a geof:distance call
+fn distance_search_box(
+    distance: &Expression,
+    max: &Expression,
+    variables: &[Variable],
+) -> Option<(usize, SearchBox)> {
+    let parameters = match distance {
+        Expression::FunctionCall(Function::Custom(name), parameters)
+            if name.as_str().strip_prefix(GEOF) == Some("distance") =>
+        {
+            parameters
+        }
+        _ => return None,
+    };
+    let (variable, geometry, unit) = match parameters.as_slice() {
+        [Expression::Variable(variable), Expression::Literal(geometry)]
+        | [Expression::Literal(geometry), Expression::Variable(variable)] => {
+            (variable, geometry, None)
+        }
+        [Expression::Variable(variable), Expression::Literal(geometry), Expression::NamedNode(unit)]
+        | [Expression::Literal(geometry), Expression::Variable(variable), Expression::NamedNode(unit)] => {
+            (variable, geometry, Some(unit.as_str()))
+        }
+        _ => return None,
+    };
+    let max = match max {
+        Expression::Literal(max)
+            if [xsd::INTEGER, xsd::DECIMAL, xsd::DOUBLE, xsd::FLOAT].contains(&max.datatype()) =>
+        {
+            max.value().parse::<f64>().ok()?
+        }
+        _ => return None,
+    };
+    if max.is_nan() || max < 0. {
+        return None;
+    }
+    let envelope = Geometry::from_literal(geometry.as_ref()).ok()?.envelope()?;
+    let (dx, dy) = match unit.map_or(Some(DistanceUnit::Metre), DistanceUnit::from_iri)? {
+        DistanceUnit::Metre => {
+            metres_to_degrees(max, envelope.min_y.abs().max(envelope.max_y.abs()))
+        }
+        DistanceUnit::Degree => (max, max),
+        DistanceUnit::Radian => (max.to_degrees(), max.to_degrees()),
+    };
+    let mut envelope = envelope.expand(dx, dy);
+    if envelope.min_x < -180. || envelope.max_x > 180. {
+        // The distances wrap around the antimeridian
+        envelope.min_x = f64::NEG_INFINITY;
+        envelope.max_x = f64::INFINITY;
+    }
+    Some((slice_key(variables, variable)?, SearchBox::new(envelope)?))
+}
+
+/// Sets the box on the first evaluated quad pattern binding the variable as object
+fn add_object_box<I: StrId>(
+    node: &mut PlanNode<I>,
+    variable: usize,
+    search_box: SearchBox,
+) -> bool {
+    set_on_object_pattern(node, variable, &mut |node| {
+        if let PlanNode::QuadPatternJoin { object_box, .. } = node {
+            *object_box = Some(search_box);
+        }
+    })
+}
+
+/// Sets the token on the first evaluated quad pattern binding the variable as object
+fn add_object_token<I: StrId>(node: &mut PlanNode<I>, variable: usize, token: &Arc<str>) -> bool {
+    set_on_object_pattern(node, variable, &mut |node| {
+        if let PlanNode::QuadPatternJoin { object_token, .. } = node {
+            *object_token = Some(token.clone());
+        }
+    })
+}
+
+/// Sets the range on the first evaluated quad pattern binding the variable as object
+fn add_object_range<I: StrId>(
+    node: &mut PlanNode<I>,
+    variable: usize,
+    range: EncodedRange,
+) -> bool {
+    set_on_object_pattern(node, variable, &mut |node| {
+        if let PlanNode::QuadPatternJoin { object_range, .. } = node {
+            *object_range = Some(range);
+        }
+    })
+}
+
+/// Calls `set` on the first evaluated quad pattern with a constant predicate binding the variable as object
+///
+/// Returns `false` if there is no such pattern or if the plan is shared and could not be modified.
+fn set_on_object_pattern<I: StrId>(
+    node: &mut PlanNode<I>,
+    variable: usize,
+    set: &mut impl FnMut(&mut PlanNode<I>),
+) -> bool {
+    let found = match node {
+        PlanNode::QuadPatternJoin {
+            child,
+            predicate,
+            object,
+            ..
+        } => {
+            if set_on_shared_object_pattern(child, variable, set) {
+                return true;
+            }
+            !predicate.is_var() && *object == PatternValue::Variable(variable)
+        }
+        PlanNode::QuotedTriplePattern { child, .. } => {
+            return set_on_shared_object_pattern(child, variable, set)
+        }
+        PlanNode::Join { left, right } => {
+            return set_on_shared_object_pattern(left, variable, set)
+                || set_on_shared_object_pattern(right, variable, set)
+        }
+        _ => false,
+    };
+    if found {
+        set(node);
+    }
+    found
+}
+
+fn set_on_shared_object_pattern<I: StrId>(
+    node: &mut Arc<PlanNode<I>>,
+    variable: usize,
+    set: &mut impl FnMut(&mut PlanNode<I>),
+) -> bool {
+    if let Some(node) = Arc::get_mut(node) {
+        set_on_object_pattern(node, variable, set)
+    } else {
+        false
+    }
+}
+
+/// Returns the pattern below the solution modifiers on which the parameters of a prepared query are bound
+fn parameters_pattern(pattern: &GraphPattern) -> &GraphPattern {
+    match pattern {
+        GraphPattern::Slice { inner, .. }
+        | GraphPattern::Distinct { inner }
+        | GraphPattern::Reduced { inner }
+        | GraphPattern::Project { inner, .. }
+        | GraphPattern::OrderBy { inner, .. }
+        | GraphPattern::Group { inner, .. } => parameters_pattern(inner),
+        // The HAVING filters, the SELECT expressions and the final VALUES above a GROUP BY
+        GraphPattern::Filter { inner, .. }
+        | GraphPattern::Extend { inner, .. }
+        | GraphPattern::Join { left: inner, .. }
+            if has_group(inner) =>
+        {
+            parameters_pattern(inner)
+        }
+        _ => pattern,
+    }
+}
+
+fn has_group(pattern: &GraphPattern) -> bool {
+    match pattern {
+        GraphPattern::Group { .. } => true,
+        GraphPattern::Filter { inner, .. }
+        | GraphPattern::Extend { inner, .. }
+        | GraphPattern::Join { left: inner, .. } => has_group(inner),
+        _ => false,
+    }
+}
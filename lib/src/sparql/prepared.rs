@@ -0,0 +1,233 @@
+use crate::model::Term;
+use crate::sparql::algebra::{GraphPattern, Query, QueryDataset};
+use crate::sparql::dataset::{DatasetStrId, DatasetView};
+use crate::sparql::error::EvaluationError;
+use crate::sparql::model::{QueryResults, Variable};
+use crate::sparql::plan::{EncodedTuple, PlanNode, TripleTemplate};
+use crate::sparql::plan_builder::PlanBuilder;
+use crate::sparql::QueryOptions;
+use crate::store::numeric_encoder::{StrId, WriteEncoder};
+use crate::store::ReadableEncodedStore;
+use oxiri::Iri;
+use std::convert::TryInto;
+use std::sync::{Arc, RwLock};
+
+/// A [SPARQL 1.1 query](https://www.w3.org/TR/sparql11-query/) parsed and planned once to be executed many times against a store.
+///
+/// Values are given to the query variables using [`bind`](PreparedQuery::bind).
+/// They behave like a one row `VALUES` clause at the beginning of the query `WHERE` clause:
+/// the solution modifiers (`ORDER BY`, `GROUP BY`, `LIMIT`...) are applied after the binding.
+/// Binding terms avoids to build queries by string concatenation and so SPARQL injections.
+///
+/// Prepared queries are created using the `prepare_query` method of the stores.
+///
+/// Usage example:
+/// ```
+/// use oxigraph::MemoryStore;
+/// use oxigraph::model::*;
+/// use oxigraph::sparql::QueryResults;
+///
+/// let store = MemoryStore::new();
+/// let ex = NamedNode::new("http://example.com")?;
+/// store.insert(Quad::new(ex.clone(), ex.clone(), Literal::from(1), None));
+///
+/// let mut query = store.prepare_query("SELECT ?o WHERE { ?s ?p ?o }")?;
+/// query.bind("s", ex.clone())?;
+/// if let QueryResults::Solutions(mut solutions) = query.exec()? {
+///     assert_eq!(solutions.next().unwrap()?.get("o"), Some(&Literal::from(1).into()));
+/// }
+///
+/// query.bind("s", NamedNode::new("http://example.com/other")?)?;
+/// if let QueryResults::Solutions(mut solutions) = query.exec()? {
+///     assert!(solutions.next().is_none());
+/// }
+/// # Result::<_,Box<dyn std::error::Error>>::Ok(())
+/// ```
+pub struct PreparedQuery {
+    evaluator: Arc<dyn PreparedEvaluator>,
+    parameters: Arc<Vec<Variable>>,
+    bindings: Vec<(usize, Term)>,
+}
+
+impl PreparedQuery {
+    /// Binds the variable `variable` to `value` for the next executions of the query.
+    ///
+    /// A previous binding of the same variable is replaced.
+    /// An error is returned if the variable is not in the scope of the query `WHERE` clause.
+    pub fn bind(
+        &mut self,
+        variable: &str,
+        value: impl Into<Term>,
+    ) -> Result<&mut Self, EvaluationError> {
+        let variable = Variable::new(variable.strip_prefix('?').unwrap_or(variable))
+            .map_err(|e| EvaluationError::msg(e.to_string()))?;
+        let index = self
+            .parameters
+            .iter()
+            .position(|v| *v == variable)
+            .ok_or_else(|| {
+                EvaluationError::msg(format!(
+                    "The variable {} is not in the scope of the query WHERE clause",
+                    variable
+                ))
+            })?;
+        let value = value.into();
+        if let Some(binding) = self.bindings.iter_mut().find(|(i, _)| *i == index) {
+            binding.1 = value;
+        } else {
+            self.bindings.push((index, value));
+        }
+        Ok(self)
+    }
+
+    /// Removes all the variable bindings
+    pub fn clear_bindings(&mut self) {
+        self.bindings.clear()
+    }
+
+    /// The variables that could be bound using [`bind`](PreparedQuery::bind)
+    pub fn variables(&self) -> &[Variable] {
+        &self.parameters
+    }
+
+    /// Executes the query with the current bindings against the current state of the store
+    pub fn exec(&self) -> Result<QueryResults, EvaluationError> {
+        self.evaluator.evaluate(&self.bindings)
+    }
+}
+
+pub(crate) fn prepare_query<S: ReadableEncodedStore + Clone + 'static>(
+    store: S,
+    query: impl TryInto<Query, Error = impl Into<EvaluationError>>,
+    options: QueryOptions,
+) -> Result<PreparedQuery, EvaluationError> {
+    let evaluator = SimplePreparedEvaluator {
+        store,
+        query: query.try_into().map_err(|e| e.into())?,
+        options,
+        plan: RwLock::new(None),
+    };
+    // We build the plan a first time to validate the query and to get the parameters variables
+    let (_, plan) = evaluator.plan()?;
+    Ok(PreparedQuery {
+        parameters: Arc::new(plan.parameters.clone()),
+        evaluator: Arc::new(evaluator),
+        bindings: Vec::new(),
+    })
+}
+
+trait PreparedEvaluator: Send + Sync {
+    fn evaluate(&self, bindings: &[(usize, Term)]) -> Result<QueryResults, EvaluationError>;
+}
+
+struct SimplePreparedEvaluator<S: ReadableEncodedStore> {
+    store: S,
+    query: Query,
+    options: QueryOptions,
+    plan: RwLock<Option<Arc<PreparedPlan<DatasetStrId<S::StrId>>>>>,
+}
+
+struct PreparedPlan<I: StrId> {
+    node: PlanNode<I>,
+    variables: Arc<Vec<Variable>>,
+    parameters: Vec<Variable>,
+    template: Option<Vec<TripleTemplate<I>>>,
+}
+
+impl<S: ReadableEncodedStore + Clone + 'static> SimplePreparedEvaluator<S> {
+    /// Returns a new view of the store and the query plan for this view
+    ///
+    /// The plan is cached if it does not contain strings that are only encoded in the view.
+    #[allow(clippy::type_complexity, clippy::expect_used)]
+    fn plan(
+        &self,
+    ) -> Result<(DatasetView<S>, Arc<PreparedPlan<DatasetStrId<S::StrId>>>), EvaluationError> {
+        let (pattern, dataset, _) = query_parts(&self.query);
+        let view = DatasetView::new(self.store.clone(), dataset, self.options.rdfs_entailment)?;
+        if let Some(plan) = &*self
+            .plan
+            .read()
+            .expect("the prepared query plan lock has been poisoned because of a panic")
+        {
+            return Ok((view, plan.clone()));
+        }
+        let (node, variables, parameters) = PlanBuilder::build_prepared(&view, pattern)?;
+        let template = if let Query::Construct { template, .. } = &self.query {
+            Some(PlanBuilder::build_graph_template(
+                &view,
+                template,
+                variables.clone(),
+            )?)
+        } else {
+            None
+        };
+        let plan = Arc::new(PreparedPlan {
+            node,
+            variables: Arc::new(variables),
+            parameters,
+            template,
+        });
+        if !view.has_temporary_strings() {
+            *self
+                .plan
+                .write()
+                .expect("the prepared query plan lock has been poisoned because of a panic") =
+                Some(plan.clone());
+        }
+        Ok((view, plan))
+    }
+}
+
+impl<S: ReadableEncodedStore + Clone + 'static> PreparedEvaluator for SimplePreparedEvaluator<S> {
+    fn evaluate(&self, bindings: &[(usize, Term)]) -> Result<QueryResults, EvaluationError> {
+        let (view, plan) = self.plan()?;
+        let mut parameters = EncodedTuple::with_capacity(plan.parameters.len());
+        {
+            let mut encoder = &view;
+            for (index, value) in bindings {
+                parameters.set(*index, encoder.encode_term(value.as_ref())?);
+            }
+        }
+        let (_, _, base_iri) = query_parts(&self.query);
+        let evaluator = self
+            .options
+            .clone()
+            .evaluator(Arc::new(view), base_iri.cloned().map(Arc::new))
+            .with_parameters(parameters);
+        match &self.query {
+            Query::Select { .. } => {
+                evaluator.evaluate_select_plan(&plan.node, plan.variables.clone())
+            }
+            Query::Ask { .. } => evaluator.evaluate_ask_plan(&plan.node),
+            Query::Construct { .. } => evaluator
+                .evaluate_construct_plan(&plan.node, plan.template.clone().unwrap_or_default()),
+            Query::Describe { .. } => evaluator.evaluate_describe_plan(&plan.node),
+        }
+    }
+}
+
+fn query_parts(query: &Query) -> (&GraphPattern, &QueryDataset, Option<&Iri<String>>) {
+    match query {
+        Query::Select {
+            pattern,
+            dataset,
+            base_iri,
+        }
+        | Query::Construct {
+            pattern,
+            dataset,
+            base_iri,
+            ..
+        }
+        | Query::Describe {
+            pattern,
+            dataset,
+            base_iri,
+        } => (pattern, dataset, base_iri.as_ref()),
+        Query::Ask {
+            pattern,
+            dataset,
+            base_iri,
+        } => (pattern, dataset, base_iri.as_ref()),
+    }
+}
@@ -0,0 +1,401 @@
+//! [RDFS entailment regime](https://www.w3.org/TR/sparql11-entailment/#RDFSEntRegime) support.
+//!
+//! The schema (`rdfs:subClassOf`, `rdfs:subPropertyOf`, `rdfs:domain` and `rdfs:range` triples) is read once from the default graph
+//! and closed transitively.
+//! The quad pattern lookups then return the asserted quads and the quads entailed from them using the rules
+//! rdfs2, rdfs3, rdfs5, rdfs7, rdfs9 and rdfs11.
+//! Entailed quads are in the graph of the quad they are derived from.
+
+use crate::sparql::EvaluationError;
+use crate::store::numeric_encoder::{EncodedQuad, EncodedTerm, StrId};
+use std::collections::{HashMap, HashSet};
+use std::iter::Flatten;
+use std::sync::Arc;
+use std::{slice, vec};
+
+/// The encoded RDF and RDFS vocabulary terms used by the entailment rules
+#[derive(Clone, Copy)]
+pub(crate) struct RdfsVocabulary<I: StrId> {
+    pub type_: EncodedTerm<I>,
+    pub sub_class_of: EncodedTerm<I>,
+    pub sub_property_of: EncodedTerm<I>,
+    pub domain: EncodedTerm<I>,
+    pub range: EncodedTerm<I>,
+}
+
+/// The transitive closure of the RDFS schema of a dataset
+pub(crate) struct RdfsSchema<I: StrId> {
+    vocabulary: RdfsVocabulary<I>,
+    /// The super classes of each class, including the class itself
+    super_classes: HashMap<EncodedTerm<I>, Vec<EncodedTerm<I>>>,
+    /// The sub classes of each class, including the class itself
+    sub_classes: HashMap<EncodedTerm<I>, Vec<EncodedTerm<I>>>,
+    /// The super properties of each property, including the property itself
+    super_properties: HashMap<EncodedTerm<I>, Vec<EncodedTerm<I>>>,
+    /// The sub properties of each property, including the property itself
+    sub_properties: HashMap<EncodedTerm<I>, Vec<EncodedTerm<I>>>,
+    /// The classes of the subjects of each property
+    domains: HashMap<EncodedTerm<I>, Vec<EncodedTerm<I>>>,
+    /// The classes of the objects of each property
+    ranges: HashMap<EncodedTerm<I>, Vec<EncodedTerm<I>>>,
+}
+
+impl<I: StrId> RdfsSchema<I> {
+    /// Builds the schema from the default graph quads returned by `lookup` for a given predicate
+    pub fn new<Q: Iterator<Item = Result<EncodedQuad<I>, EvaluationError>>>(
+        vocabulary: RdfsVocabulary<I>,
+        lookup: impl Fn(EncodedTerm<I>) -> Q,
+    ) -> Result<Self, EvaluationError> {
+        let edges = |predicate| {
+            let mut edges = HashMap::<_, Vec<_>>::new();
+            for quad in lookup(predicate) {
+                let quad = quad?;
+                edges.entry(quad.subject).or_default().push(quad.object);
+            }
+            Ok::<_, EvaluationError>(edges)
+        };
+        let super_classes = transitive_closure(&edges(vocabulary.sub_class_of)?);
+        let super_properties = transitive_closure(&edges(vocabulary.sub_property_of)?);
+        let direct_domains = edges(vocabulary.domain)?;
+        let direct_ranges = edges(vocabulary.range)?;
+
+        let mut schema = Self {
+            vocabulary,
+            sub_classes: inverse(&super_classes),
+            sub_properties: inverse(&super_properties),
+            super_classes,
+            super_properties,
+            domains: HashMap::new(),
+            ranges: HashMap::new(),
+        };
+        schema.domains = schema.property_classes(&direct_domains);
+        schema.ranges = schema.property_classes(&direct_ranges);
+        Ok(schema)
+    }
+
+    /// Returns the asserted and the entailed quads matching the pattern
+    ///
+    /// `lookup` returns the asserted quads matching a pattern.
+    /// The lookups are done when this method is called but their results are only consumed lazily.
+    pub fn quads_for_pattern<
+        Q: Iterator<Item = Result<EncodedQuad<I>, EvaluationError>> + Send + 'static,
+    >(
+        self: &Arc<Self>,
+        subject: Option<EncodedTerm<I>>,
+        predicate: Option<EncodedTerm<I>>,
+        object: Option<EncodedTerm<I>>,
+        graph_name: Option<EncodedTerm<I>>,
+        lookup: impl Fn(
+            Option<EncodedTerm<I>>,
+            Option<EncodedTerm<I>>,
+            Option<EncodedTerm<I>>,
+            Option<EncodedTerm<I>>,
+        ) -> Q,
+    ) -> EntailedQuadsIter<I>
+    where
+        I: 'static,
+    {
+        let mut iters: Vec<QuadsIter<I>> = Vec::new();
+        if let Some(predicate) = predicate {
+            self.add_quads_for_predicate(
+                subject, predicate, object, graph_name, &lookup, &mut iters,
+            );
+        } else {
+            iters.push(Box::new(lookup(subject, None, object, graph_name)));
+            let mut predicates = vec![
+                self.vocabulary.type_,
+                self.vocabulary.sub_class_of,
+                self.vocabulary.sub_property_of,
+            ];
+            predicates.extend(
+                self.sub_properties
+                    .iter()
+                    .filter(|(_, sub_properties)| sub_properties.len() > 1)
+                    .map(|(property, _)| *property),
+            );
+            for predicate in predicates {
+                self.add_quads_for_predicate(
+                    subject, predicate, object, graph_name, &lookup, &mut iters,
+                );
+            }
+        }
+        EntailedQuadsIter {
+            // The store lookups never return the same quad twice, only the entailed quads should be deduplicated
+            seen: if iters.len() > 1 {
+                Some(HashSet::new())
+            } else {
+                None
+            },
+            iter: iters.into_iter().flatten(),
+        }
+    }
+
+    fn add_quads_for_predicate<
+        Q: Iterator<Item = Result<EncodedQuad<I>, EvaluationError>> + Send + 'static,
+    >(
+        self: &Arc<Self>,
+        subject: Option<EncodedTerm<I>>,
+        predicate: EncodedTerm<I>,
+        object: Option<EncodedTerm<I>>,
+        graph_name: Option<EncodedTerm<I>>,
+        lookup: &impl Fn(
+            Option<EncodedTerm<I>>,
+            Option<EncodedTerm<I>>,
+            Option<EncodedTerm<I>>,
+            Option<EncodedTerm<I>>,
+        ) -> Q,
+        iters: &mut Vec<QuadsIter<I>>,
+    ) where
+        I: 'static,
+    {
+        // rdfs7: the sub properties
+        for sub_property in related(&self.sub_properties, &predicate) {
+            iters.push(Box::new(
+                lookup(subject, Some(*sub_property), object, graph_name).map(move |quad| {
+                    let quad = quad?;
+                    Ok(EncodedQuad::new(
+                        quad.subject,
+                        predicate,
+                        quad.object,
+                        quad.graph_name,
+                    ))
+                }),
+            ));
+        }
+
+        if predicate == self.vocabulary.type_ {
+            let type_ = self.vocabulary.type_;
+            // rdfs9: the sub classes
+            for type_property in related(&self.sub_properties, &type_) {
+                if let Some(object) = object {
+                    for sub_class in related(&self.sub_classes, &object) {
+                        iters.push(Box::new(
+                            lookup(subject, Some(*type_property), Some(*sub_class), graph_name)
+                                .map(move |quad| {
+                                    let quad = quad?;
+                                    Ok(EncodedQuad::new(
+                                        quad.subject,
+                                        type_,
+                                        object,
+                                        quad.graph_name,
+                                    ))
+                                }),
+                        ));
+                    }
+                } else {
+                    let schema = Arc::clone(self);
+                    iters.push(Box::new(
+                        lookup(subject, Some(*type_property), None, graph_name).flat_map(
+                            move |quad| {
+                                entailed_types(quad, |quad| {
+                                    related(&schema.super_classes, &quad.object)
+                                        .iter()
+                                        .map(|class| {
+                                            EncodedQuad::new(
+                                                quad.subject,
+                                                type_,
+                                                *class,
+                                                quad.graph_name,
+                                            )
+                                        })
+                                        .collect()
+                                })
+                            },
+                        ),
+                    ));
+                }
+            }
+            // rdfs2: the property domains
+            for (property, classes) in &self.domains {
+                if object.map_or(false, |object| !classes.contains(&object)) {
+                    continue;
+                }
+                let classes = matching_classes(classes, object);
+                iters.push(Box::new(
+                    lookup(subject, Some(*property), None, graph_name).flat_map(move |quad| {
+                        entailed_types(quad, |quad| {
+                            classes
+                                .iter()
+                                .map(|class| {
+                                    EncodedQuad::new(quad.subject, type_, *class, quad.graph_name)
+                                })
+                                .collect()
+                        })
+                    }),
+                ));
+            }
+            // rdfs3: the property ranges
+            for (property, classes) in &self.ranges {
+                if object.map_or(false, |object| !classes.contains(&object)) {
+                    continue;
+                }
+                let classes = matching_classes(classes, object);
+                iters.push(Box::new(
+                    lookup(None, Some(*property), subject, graph_name).flat_map(move |quad| {
+                        entailed_types(quad, |quad| {
+                            if quad.object.is_literal() {
+                                return Vec::new();
+                            }
+                            classes
+                                .iter()
+                                .map(|class| {
+                                    EncodedQuad::new(quad.object, type_, *class, quad.graph_name)
+                                })
+                                .collect()
+                        })
+                    }),
+                ));
+            }
+        }
+
+        // rdfs5 and rdfs11: the schema closure, stored in the default graph
+        if graph_name.map_or(false, |graph_name| graph_name.is_default_graph()) {
+            let closure = if predicate == self.vocabulary.sub_class_of {
+                Some(&self.super_classes)
+            } else if predicate == self.vocabulary.sub_property_of {
+                Some(&self.super_properties)
+            } else {
+                None
+            };
+            if let Some(closure) = closure {
+                let mut quads = Vec::new();
+                for (start, ends) in closure {
+                    if subject.map_or(false, |subject| subject != *start) {
+                        continue;
+                    }
+                    for end in ends {
+                        if end != start && object.map_or(true, |object| object == *end) {
+                            quads.push(Ok(EncodedQuad::new(
+                                *start,
+                                predicate,
+                                *end,
+                                EncodedTerm::DefaultGraph,
+                            )));
+                        }
+                    }
+                }
+                iters.push(Box::new(quads.into_iter()));
+            }
+        }
+    }
+
+    /// Maps each property to the closed set of classes given by `direct` for itself and its super properties
+    fn property_classes(
+        &self,
+        direct: &HashMap<EncodedTerm<I>, Vec<EncodedTerm<I>>>,
+    ) -> HashMap<EncodedTerm<I>, Vec<EncodedTerm<I>>> {
+        let mut properties = HashMap::<_, Vec<_>>::new();
+        for (property, classes) in direct {
+            for sub_property in related(&self.sub_properties, property) {
+                let property_classes = properties.entry(*sub_property).or_default();
+                for class in classes {
+                    for super_class in related(&self.super_classes, class) {
+                        if !property_classes.contains(super_class) {
+                            property_classes.push(*super_class);
+                        }
+                    }
+                }
+            }
+        }
+        properties
+    }
+}
+
+type QuadsIter<I> = Box<dyn Iterator<Item = Result<EncodedQuad<I>, EvaluationError>> + Send>;
+
+/// The asserted and the entailed quads, without duplicates
+///
+/// The same quad could be entailed from different asserted quads and by different rules.
+/// The returned quads are kept in memory to skip these duplicates,
+/// so the memory used grows with the number of results like for a `SELECT DISTINCT`.
+/// Nothing is kept if no quad could be entailed for the pattern.
+pub(crate) struct EntailedQuadsIter<I: StrId> {
+    iter: Flatten<vec::IntoIter<QuadsIter<I>>>,
+    seen: Option<HashSet<EncodedQuad<I>>>,
+}
+
+impl<I: StrId> Iterator for EntailedQuadsIter<I> {
+    type Item = Result<EncodedQuad<I>, EvaluationError>;
+
+    fn next(&mut self) -> Option<Result<EncodedQuad<I>, EvaluationError>> {
+        loop {
+            match self.iter.next()? {
+                Ok(quad) => {
+                    if self.seen.as_mut().map_or(true, |seen| seen.insert(quad)) {
+                        return Some(Ok(quad));
+                    }
+                }
+                Err(error) => return Some(Err(error)),
+            }
+        }
+    }
+}
+
+/// Maps an asserted quad to the `rdf:type` quads entailed from it
+fn entailed_types<I: StrId>(
+    quad: Result<EncodedQuad<I>, EvaluationError>,
+    entail: impl FnOnce(EncodedQuad<I>) -> Vec<EncodedQuad<I>>,
+) -> Vec<Result<EncodedQuad<I>, EvaluationError>> {
+    match quad {
+        Ok(quad) => entail(quad).into_iter().map(Ok).collect(),
+        Err(error) => vec![Err(error)],
+    }
+}
+
+/// The classes that are equal to `object` if it is set
+fn matching_classes<I: StrId>(
+    classes: &[EncodedTerm<I>],
+    object: Option<EncodedTerm<I>>,
+) -> Vec<EncodedTerm<I>> {
+    classes
+        .iter()
+        .copied()
+        .filter(|class| object.map_or(true, |object| object == *class))
+        .collect()
+}
+
+/// Returns the terms related to `term` in a reflexive closure, `term` itself if it has no entry
+fn related<'a, I: StrId>(
+    closure: &'a HashMap<EncodedTerm<I>, Vec<EncodedTerm<I>>>,
+    term: &'a EncodedTerm<I>,
+) -> &'a [EncodedTerm<I>] {
+    closure
+        .get(term)
+        .map_or(slice::from_ref(term), Vec::as_slice)
+}
+
+/// Computes the reflexive transitive closure of a relation
+fn transitive_closure<I: StrId>(
+    edges: &HashMap<EncodedTerm<I>, Vec<EncodedTerm<I>>>,
+) -> HashMap<EncodedTerm<I>, Vec<EncodedTerm<I>>> {
+    let mut closure = HashMap::new();
+    for start in edges.keys().chain(edges.values().flatten()) {
+        if closure.contains_key(start) {
+            continue;
+        }
+        let mut reached = vec![*start];
+        let mut stack = vec![*start];
+        while let Some(current) = stack.pop() {
+            for next in edges.get(&current).into_iter().flatten() {
+                if !reached.contains(next) {
+                    reached.push(*next);
+                    stack.push(*next);
+                }
+            }
+        }
+        closure.insert(*start, reached);
+    }
+    closure
+}
+
+fn inverse<I: StrId>(
+    relation: &HashMap<EncodedTerm<I>, Vec<EncodedTerm<I>>>,
+) -> HashMap<EncodedTerm<I>, Vec<EncodedTerm<I>>> {
+    let mut inverse = HashMap::<_, Vec<_>>::new();
+    for (start, ends) in relation {
+        for end in ends {
+            inverse.entry(*end).or_default().push(*start);
+        }
+    }
+    inverse
+}
@@ -5,9 +5,23 @@ use crate::sparql::error::EvaluationError;
 use crate::sparql::http::Client;
 use crate::sparql::model::QueryResults;
 use crate::sparql::QueryResultsFormat;
+#[cfg(feature = "async_service")]
+use futures::channel::oneshot;
+#[cfg(feature = "async_service")]
+use futures::future::{ready, BoxFuture};
+#[cfg(feature = "async_service")]
+use futures::FutureExt;
 use http::header::{ACCEPT, CONTENT_TYPE, USER_AGENT};
 use http::{Method, Request, StatusCode};
+#[cfg(feature = "async_service")]
+use std::collections::VecDeque;
 use std::error::Error;
+#[cfg(feature = "async_service")]
+use std::panic::{self, AssertUnwindSafe};
+#[cfg(feature = "async_service")]
+use std::sync::{Arc, Mutex};
+#[cfg(feature = "async_service")]
+use std::thread;
 
 /// Handler for [SPARQL 1.1 Federated Query](https://www.w3.org/TR/sparql11-federated-query/) SERVICE.
 ///
@@ -49,13 +63,83 @@ use std::error::Error;
 /// }
 /// # Result::<_,Box<dyn std::error::Error>>::Ok(())
 /// ```
-pub trait ServiceHandler {
+pub trait ServiceHandler: Send + Sync {
     type Error: Error + Send + Sync + 'static;
 
     /// Evaluates a [`Query`] against a given service identified by a [`NamedNode`](crate::model::NamedNode).
     fn handle(&self, service_name: NamedNode, query: Query) -> Result<QueryResults, Self::Error>;
 }
 
+/// Asynchronous handler for [SPARQL 1.1 Federated Query](https://www.w3.org/TR/sparql11-federated-query/) SERVICE.
+///
+/// It requires the `"async_service"` feature to be activated.
+///
+/// Should be given to [`QueryOptions`](super::QueryOptions::with_async_service_handler())
+/// before evaluating a SPARQL query that uses SERVICE calls.
+///
+/// The evaluator calls [`handle`](AsyncServiceHandler::handle) for all the SERVICE clauses that do not depend on each other
+/// (the operands of a join or of a UNION for example) before awaiting the returned futures.
+/// The sub-queries are so sent concurrently to the services.
+/// The futures are driven by the thread evaluating the query with [`block_on`](futures::executor::block_on):
+/// they should not require a specific runtime like the [Tokio](https://tokio.rs/) one.
+/// This thread is blocked while the calls are pending, so the queries using SERVICE should not be evaluated
+/// from an asynchronous task, but for example from a [`spawn_blocking`](https://docs.rs/tokio/1/tokio/task/fn.spawn_blocking.html) one.
+///
+/// ```
+/// use futures::future::{ready, BoxFuture};
+/// use oxigraph::MemoryStore;
+/// use oxigraph::model::*;
+/// use oxigraph::sparql::{AsyncServiceHandler, QueryOptions, QueryResults, Query, EvaluationError};
+///
+/// #[derive(Default)]
+/// struct TestServiceHandler {
+///     store: MemoryStore
+/// }
+///
+/// impl AsyncServiceHandler for TestServiceHandler {
+///     type Error = EvaluationError;
+///
+///     fn handle(&self, service_name: NamedNode, query: Query) -> BoxFuture<'static, Result<QueryResults,EvaluationError>> {
+///         if service_name == "http://example.com/service" {
+///             Box::pin(ready(self.store.query(query)))
+///         } else {
+///             panic!()
+///         }
+///     }
+/// }
+///
+/// let store = MemoryStore::new();
+/// let service = TestServiceHandler::default();
+/// let ex = NamedNode::new("http://example.com")?;
+/// service.store.insert(Quad::new(ex.clone(), ex.clone(), ex.clone(), None));
+///
+/// if let QueryResults::Solutions(mut solutions) = store.query_opt(
+///     "SELECT ?s WHERE { SERVICE <http://example.com/service> { ?s ?p ?o } }",
+///     QueryOptions::default().with_async_service_handler(service)
+/// )? {
+///     assert_eq!(solutions.next().unwrap()?.get("s"), Some(&ex.into()));
+/// }
+/// # Result::<_,Box<dyn std::error::Error>>::Ok(())
+/// ```
+#[cfg(feature = "async_service")]
+pub trait AsyncServiceHandler: Send + Sync {
+    type Error: Error + Send + Sync + 'static;
+
+    /// Starts the evaluation of a [`Query`] against a given service identified by a [`NamedNode`](crate::model::NamedNode).
+    fn handle(
+        &self,
+        service_name: NamedNode,
+        query: Query,
+    ) -> BoxFuture<'static, Result<QueryResults, Self::Error>>;
+}
+
+/// The service handler used by the query evaluator
+#[cfg(feature = "async_service")]
+pub(crate) type EvaluatorServiceHandler = dyn AsyncServiceHandler<Error = EvaluationError>;
+/// The service handler used by the query evaluator
+#[cfg(not(feature = "async_service"))]
+pub(crate) type EvaluatorServiceHandler = dyn ServiceHandler<Error = EvaluationError>;
+
 pub struct EmptyServiceHandler;
 
 impl ServiceHandler for EmptyServiceHandler {
@@ -68,16 +152,32 @@ impl ServiceHandler for EmptyServiceHandler {
     }
 }
 
+#[cfg(feature = "async_service")]
+impl AsyncServiceHandler for EmptyServiceHandler {
+    type Error = EvaluationError;
+
+    fn handle(
+        &self,
+        service_name: NamedNode,
+        query: Query,
+    ) -> BoxFuture<'static, Result<QueryResults, EvaluationError>> {
+        Box::pin(ready(ServiceHandler::handle(self, service_name, query)))
+    }
+}
+
+#[cfg(not(feature = "async_service"))]
 pub struct ErrorConversionServiceHandler<S: ServiceHandler> {
     handler: S,
 }
 
+#[cfg(not(feature = "async_service"))]
 impl<S: ServiceHandler> ErrorConversionServiceHandler<S> {
     pub fn wrap(handler: S) -> Self {
         Self { handler }
     }
 }
 
+#[cfg(not(feature = "async_service"))]
 impl<S: ServiceHandler> ServiceHandler for ErrorConversionServiceHandler<S> {
     type Error = EvaluationError;
 
@@ -92,8 +192,84 @@ impl<S: ServiceHandler> ServiceHandler for ErrorConversionServiceHandler<S> {
     }
 }
 
+/// Wraps a synchronous [`ServiceHandler`]: the calls are done when the returned futures are created
+#[cfg(feature = "async_service")]
+pub struct BlockingServiceHandler<S: ServiceHandler> {
+    handler: S,
+}
+
+#[cfg(feature = "async_service")]
+impl<S: ServiceHandler> BlockingServiceHandler<S> {
+    pub fn wrap(handler: S) -> Self {
+        Self { handler }
+    }
+}
+
+#[cfg(feature = "async_service")]
+impl<S: ServiceHandler> AsyncServiceHandler for BlockingServiceHandler<S> {
+    type Error = EvaluationError;
+
+    fn handle(
+        &self,
+        service_name: NamedNode,
+        query: Query,
+    ) -> BoxFuture<'static, Result<QueryResults, EvaluationError>> {
+        Box::pin(ready(
+            self.handler
+                .handle(service_name, query)
+                .map_err(EvaluationError::wrap),
+        ))
+    }
+}
+
+#[cfg(feature = "async_service")]
+pub struct AsyncErrorConversionServiceHandler<S: AsyncServiceHandler> {
+    handler: S,
+}
+
+#[cfg(feature = "async_service")]
+impl<S: AsyncServiceHandler> AsyncErrorConversionServiceHandler<S> {
+    pub fn wrap(handler: S) -> Self {
+        Self { handler }
+    }
+}
+
+#[cfg(feature = "async_service")]
+impl<S: AsyncServiceHandler> AsyncServiceHandler for AsyncErrorConversionServiceHandler<S> {
+    type Error = EvaluationError;
+
+    fn handle(
+        &self,
+        service_name: NamedNode,
+        query: Query,
+    ) -> BoxFuture<'static, Result<QueryResults, EvaluationError>> {
+        Box::pin(
+            self.handler
+                .handle(service_name, query)
+                .map(|result| result.map_err(EvaluationError::wrap)),
+        )
+    }
+}
+
+/// The maximal number of threads calling the services of a [`SimpleServiceHandler`]
+#[cfg(feature = "async_service")]
+const MAX_CALLING_THREADS: usize = 8;
+
+#[cfg(feature = "async_service")]
+type ServiceCall = Box<dyn FnOnce() + Send>;
+
 pub struct SimpleServiceHandler {
     client: Client,
+    #[cfg(feature = "async_service")]
+    pool: Arc<Mutex<CallingThreads>>,
+}
+
+/// The pending calls and the number of threads doing them
+#[cfg(feature = "async_service")]
+#[derive(Default)]
+struct CallingThreads {
+    running: usize,
+    pending: VecDeque<ServiceCall>,
 }
 
 impl SimpleServiceHandler {
@@ -101,17 +277,41 @@ impl SimpleServiceHandler {
     pub fn new() -> Self {
         Self {
             client: Client::new(),
+            #[cfg(feature = "async_service")]
+            pool: Arc::default(),
         }
     }
-}
 
-impl ServiceHandler for SimpleServiceHandler {
-    type Error = EvaluationError;
+    /// Runs the call on a calling thread, starting a new one if there are less than [`MAX_CALLING_THREADS`]
+    #[cfg(feature = "async_service")]
+    fn spawn(&self, call: ServiceCall) {
+        let mut pool = self.pool.lock().unwrap();
+        pool.pending.push_back(call);
+        if pool.running >= MAX_CALLING_THREADS {
+            return; // A running thread will do the call
+        }
+        pool.running += 1;
+        drop(pool);
+        let pool = self.pool.clone();
+        thread::spawn(move || loop {
+            let call = {
+                let mut pool = pool.lock().unwrap();
+                if let Some(call) = pool.pending.pop_front() {
+                    call
+                } else {
+                    pool.running -= 1;
+                    return;
+                }
+            };
+            // The sender of a panicking call is dropped so the evaluation gets an error
+            let _ = panic::catch_unwind(AssertUnwindSafe(call));
+        });
+    }
 
-    fn handle(
-        &self,
-        service_name: NamedNode,
-        query: Query,
+    fn call(
+        client: &Client,
+        service_name: &NamedNode,
+        query: &Query,
     ) -> Result<QueryResults, EvaluationError> {
         let request = Request::builder()
             .method(Method::POST)
@@ -121,7 +321,7 @@ impl ServiceHandler for SimpleServiceHandler {
             .header(USER_AGENT, concat!("Oxigraph/", env!("CARGO_PKG_VERSION")))
             .body(Some(query.to_string().into_bytes()))
             .map_err(invalid_input_error)?;
-        let response = self.client.request(&request)?;
+        let response = client.request(&request)?;
         if response.status() != StatusCode::OK {
             return Err(EvaluationError::msg(format!(
                 "HTTP error code {} returned when querying service {}",
@@ -149,3 +349,403 @@ impl ServiceHandler for SimpleServiceHandler {
         Ok(QueryResults::read(response.into_body(), format)?)
     }
 }
+
+impl ServiceHandler for SimpleServiceHandler {
+    type Error = EvaluationError;
+
+    fn handle(
+        &self,
+        service_name: NamedNode,
+        query: Query,
+    ) -> Result<QueryResults, EvaluationError> {
+        Self::call(&self.client, &service_name, &query)
+    }
+}
+
+/// The calls are done by at most [`MAX_CALLING_THREADS`] threads using the blocking HTTP client.
+///
+/// The threads are started on demand and stop when there are no more pending calls.
+#[cfg(feature = "async_service")]
+impl AsyncServiceHandler for SimpleServiceHandler {
+    type Error = EvaluationError;
+
+    fn handle(
+        &self,
+        service_name: NamedNode,
+        query: Query,
+    ) -> BoxFuture<'static, Result<QueryResults, EvaluationError>> {
+        let (sender, receiver) = oneshot::channel();
+        let client = self.client.clone();
+        self.spawn(Box::new(move || {
+            // The receiver might have been dropped if the query evaluation has been stopped
+            let _ = sender.send(Self::call(&client, &service_name, &query));
+        }));
+        Box::pin(receiver.map(|result| {
+            result.unwrap_or_else(|_| {
+                Err(EvaluationError::msg(
+                    "The thread calling the service has stopped",
+                ))
+            })
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Literal, Quad};
+    use crate::sparql::QueryOptions;
+    use crate::store::MemoryStore;
+    #[cfg(feature = "async_service")]
+    use futures::future::poll_fn;
+    use std::io;
+    #[cfg(feature = "async_service")]
+    use std::sync::atomic::{AtomicUsize, Ordering::SeqCst};
+    #[cfg(feature = "async_service")]
+    use std::sync::mpsc;
+    use std::sync::{Arc, Mutex};
+    #[cfg(feature = "async_service")]
+    use std::task::Poll;
+    #[cfg(feature = "async_service")]
+    use std::time::Duration;
+
+    #[cfg(feature = "async_service")]
+    #[test]
+    fn test_calling_threads_are_bounded() {
+        let handler = SimpleServiceHandler::new();
+        let running = Arc::new(Mutex::new((0, 0)));
+        let (sender, receiver) = mpsc::channel();
+        for i in 0..4 * MAX_CALLING_THREADS {
+            let running = running.clone();
+            let sender = sender.clone();
+            handler.spawn(Box::new(move || {
+                {
+                    let mut running = running.lock().unwrap();
+                    running.0 += 1;
+                    running.1 = running.1.max(running.0);
+                }
+                thread::sleep(Duration::from_millis(10));
+                running.lock().unwrap().0 -= 1;
+                if i == 0 {
+                    panic!("The other calls should still be done");
+                }
+                sender.send(()).unwrap();
+            }));
+        }
+        for _ in 1..4 * MAX_CALLING_THREADS {
+            receiver.recv_timeout(Duration::from_secs(10)).unwrap();
+        }
+        assert!(running.lock().unwrap().1 <= MAX_CALLING_THREADS);
+    }
+
+    /// Returns the same results for all the services once all of them have been called
+    #[cfg(feature = "async_service")]
+    struct ConcurrentServiceHandler {
+        store: MemoryStore,
+        calls: Arc<AtomicUsize>,
+        expected_calls: usize,
+    }
+
+    #[cfg(feature = "async_service")]
+    impl AsyncServiceHandler for ConcurrentServiceHandler {
+        type Error = EvaluationError;
+
+        fn handle(
+            &self,
+            _: NamedNode,
+            query: Query,
+        ) -> BoxFuture<'static, Result<QueryResults, EvaluationError>> {
+            self.calls.fetch_add(1, SeqCst);
+            let mut results = Some(self.store.query(query));
+            let calls = self.calls.clone();
+            let expected_calls = self.expected_calls;
+            let mut polls = 0;
+            Box::pin(poll_fn(move |context| {
+                polls += 1;
+                if calls.load(SeqCst) >= expected_calls {
+                    Poll::Ready(results.take().unwrap())
+                } else if polls > 1000 {
+                    Poll::Ready(Err(io::Error::new(
+                        io::ErrorKind::Other,
+                        "The SERVICE calls are not concurrent",
+                    )
+                    .into()))
+                } else {
+                    context.waker().wake_by_ref();
+                    Poll::Pending
+                }
+            }))
+        }
+    }
+
+    #[cfg(feature = "async_service")]
+    #[test]
+    fn test_concurrent_service_calls() -> Result<(), Box<dyn Error>> {
+        let store = MemoryStore::new();
+        let service_store = MemoryStore::new();
+        let ex = NamedNode::new("http://example.com")?;
+        service_store.insert(Quad::new(ex.clone(), ex.clone(), ex.clone(), None));
+        for (query, count) in &[
+            ("SELECT * WHERE { { SERVICE <http://example.com/s1> { ?s ?p ?o } } UNION { SERVICE <http://example.com/s2> { ?s ?p ?o } } }", 2),
+            ("SELECT * WHERE { SERVICE <http://example.com/s1> { ?s ?p ?o } SERVICE <http://example.com/s2> { ?s ?p ?o2 } }", 1),
+            ("SELECT * WHERE { { SERVICE <http://example.com/s1> { ?s ?p ?o } SERVICE <http://example.com/s2> { ?s ?p ?o2 } } UNION { SERVICE <http://example.com/s3> { ?s ?p ?o } } }", 2),
+        ] {
+            let handler = ConcurrentServiceHandler {
+                store: service_store.clone(),
+                calls: Arc::default(),
+                expected_calls: query.matches("SERVICE").count(),
+            };
+            if let QueryResults::Solutions(solutions) = store.query_opt(
+                *query,
+                QueryOptions::default()
+                    .with_async_service_handler(handler)
+                    .with_service_batch_size(0),
+            )? {
+                let solutions = solutions.collect::<Result<Vec<_>, _>>()?;
+                assert_eq!(solutions.len(), *count);
+                // The failed SERVICE calls would produce solutions without ?o
+                assert!(solutions.iter().all(|solution| solution.get("o").is_some()));
+            } else {
+                return Err("SELECT query expected".into());
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg(all(feature = "async_service", feature = "http_client"))]
+    #[test]
+    fn test_concurrent_http_service_calls() -> Result<(), Box<dyn Error>> {
+        use std::io::{self, BufRead, BufReader, Read, Write};
+        use std::net::TcpListener;
+        use std::sync::Condvar;
+        use std::time::Duration;
+
+        // A stub SPARQL endpoint answering to each request only once it has received two of them
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let address = listener.local_addr()?;
+        let received = Arc::new((Mutex::new(0), Condvar::new()));
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let received = received.clone();
+                thread::spawn(move || -> io::Result<()> {
+                    let mut stream = stream?;
+                    let mut reader = BufReader::new(stream.try_clone()?);
+                    let mut path = String::new();
+                    let mut content_length = 0;
+                    loop {
+                        let mut line = String::new();
+                        reader.read_line(&mut line)?;
+                        let line = line.trim_end();
+                        if line.is_empty() {
+                            break;
+                        } else if let Some(value) =
+                            line.to_ascii_lowercase().strip_prefix("content-length:")
+                        {
+                            content_length = value.trim().parse().unwrap();
+                        } else if path.is_empty() {
+                            path = line.split(' ').nth(1).unwrap_or_default().to_owned();
+                        }
+                    }
+                    reader.take(content_length).read_to_end(&mut Vec::new())?;
+
+                    let (count, condition) = &*received;
+                    let mut count = count.lock().unwrap();
+                    *count += 1;
+                    condition.notify_all();
+                    let (count, _) = condition
+                        .wait_timeout_while(count, Duration::from_secs(10), |count| *count < 2)
+                        .unwrap();
+                    let body = format!("<?xml version=\"1.0\"?><sparql xmlns=\"http://www.w3.org/2005/sparql-results#\"><head><variable name=\"s\"/></head><results><result><binding name=\"s\"><uri>http://example.com{}</uri></binding></result></results></sparql>", path);
+                    let status = if *count >= 2 {
+                        "200 OK"
+                    } else {
+                        "503 Service Unavailable"
+                    };
+                    write!(
+                        stream,
+                        "HTTP/1.1 {}\r\nContent-Type: application/sparql-results+xml\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        status,
+                        body.len(),
+                        body
+                    )
+                });
+            }
+        });
+
+        let store = MemoryStore::new();
+        let query = format!(
+            "SELECT ?s WHERE {{ {{ SERVICE <http://{0}/a> {{ ?s ?p ?o }} }} UNION {{ SERVICE <http://{0}/b> {{ ?s ?p ?o }} }} }}",
+            address
+        );
+        if let QueryResults::Solutions(solutions) = store.query(query.as_str())? {
+            let mut solutions = solutions
+                .map(|solution| Ok(solution?.get("s").map(|s| s.to_string())))
+                .collect::<Result<Vec<_>, EvaluationError>>()?;
+            solutions.sort();
+            assert_eq!(
+                solutions,
+                vec![
+                    Some("<http://example.com/a>".to_owned()),
+                    Some("<http://example.com/b>".to_owned())
+                ]
+            );
+        } else {
+            return Err("SELECT query expected".into());
+        }
+        Ok(())
+    }
+
+    /// Records the queries sent to the service and fails on the ones about `failing`
+    struct RecordingServiceHandler {
+        store: MemoryStore,
+        queries: Arc<Mutex<Vec<String>>>,
+        failing: Option<NamedNode>,
+    }
+
+    impl ServiceHandler for RecordingServiceHandler {
+        type Error = EvaluationError;
+
+        fn handle(&self, _: NamedNode, query: Query) -> Result<QueryResults, EvaluationError> {
+            let query = query.to_string();
+            self.queries.lock().unwrap().push(query.clone());
+            match &self.failing {
+                Some(failing) if query.contains(&failing.to_string()) => {
+                    Err(io::Error::new(io::ErrorKind::Other, "The service is not available").into())
+                }
+                _ => self.store.query(query.as_str()),
+            }
+        }
+    }
+
+    #[test]
+    fn test_service_bind_join() -> Result<(), Box<dyn Error>> {
+        let store = MemoryStore::new();
+        let service_store = MemoryStore::new();
+        let p = NamedNode::new("http://example.com/p")?;
+        let q = NamedNode::new("http://example.com/q")?;
+        for i in 0..5 {
+            let o = NamedNode::new(format!("http://example.com/o{}", i))?;
+            store.insert(Quad::new(
+                NamedNode::new(format!("http://example.com/s{}", i))?,
+                p.clone(),
+                o.clone(),
+                None,
+            ));
+            if i < 4 {
+                service_store.insert(Quad::new(o, q.clone(), Literal::from(i), None));
+            }
+        }
+
+        for (query, count) in &[
+            ("SELECT * WHERE { ?s <http://example.com/p> ?o SERVICE <http://example.com/service> { ?o <http://example.com/q> ?v } }", 4),
+            ("SELECT * WHERE { ?s <http://example.com/p> ?o OPTIONAL { SERVICE <http://example.com/service> { ?o <http://example.com/q> ?v } } }", 5),
+        ] {
+            let queries = Arc::new(Mutex::new(Vec::new()));
+            let handler = RecordingServiceHandler {
+                store: service_store.clone(),
+                queries: queries.clone(),
+                failing: None,
+            };
+            if let QueryResults::Solutions(solutions) = store.query_opt(
+                *query,
+                QueryOptions::default()
+                    .with_service_handler(handler)
+                    .with_service_batch_size(2),
+            )? {
+                let solutions = solutions.collect::<Result<Vec<_>, _>>()?;
+                assert_eq!(solutions.len(), *count);
+                assert_eq!(
+                    solutions
+                        .iter()
+                        .filter(|solution| solution.get("v").is_some())
+                        .count(),
+                    4
+                );
+            } else {
+                return Err("SELECT query expected".into());
+            }
+            let queries = queries.lock().unwrap();
+            assert_eq!(queries.len(), 3);
+            assert!(queries.iter().all(|query| query.contains("VALUES")));
+        }
+
+        // Only the solutions of the failing batch are kept unchanged
+        let handler = RecordingServiceHandler {
+            store: service_store.clone(),
+            queries: Arc::default(),
+            failing: Some(NamedNode::new("http://example.com/o0")?),
+        };
+        if let QueryResults::Solutions(solutions) = store.query_opt(
+            "SELECT * WHERE { ?s <http://example.com/p> ?o SERVICE SILENT <http://example.com/service> { ?o <http://example.com/q> ?v } }",
+            QueryOptions::default()
+                .with_service_handler(handler)
+                .with_service_batch_size(1),
+        )? {
+            let solutions = solutions.collect::<Result<Vec<_>, _>>()?;
+            assert_eq!(solutions.len(), 4);
+            assert_eq!(
+                solutions
+                    .iter()
+                    .filter(|solution| solution.get("v").is_none())
+                    .map(|solution| solution.get("o").cloned())
+                    .collect::<Vec<_>>(),
+                vec![Some(NamedNode::new("http://example.com/o0")?.into())]
+            );
+        } else {
+            return Err("SELECT query expected".into());
+        }
+
+        // With OPTIONAL, the solutions of the failing batch are kept unchanged as the ones without matches
+        let handler = RecordingServiceHandler {
+            store: service_store.clone(),
+            queries: Arc::default(),
+            failing: Some(NamedNode::new("http://example.com/o0")?),
+        };
+        if let QueryResults::Solutions(solutions) = store.query_opt(
+            "SELECT * WHERE { ?s <http://example.com/p> ?o OPTIONAL { SERVICE SILENT <http://example.com/service> { ?o <http://example.com/q> ?v } } }",
+            QueryOptions::default()
+                .with_service_handler(handler)
+                .with_service_batch_size(1),
+        )? {
+            let solutions = solutions.collect::<Result<Vec<_>, _>>()?;
+            assert_eq!(solutions.len(), 5);
+            let mut without_value = solutions
+                .iter()
+                .filter(|solution| solution.get("v").is_none())
+                .map(|solution| solution.get("o").map(|o| o.to_string()))
+                .collect::<Vec<_>>();
+            without_value.sort();
+            assert_eq!(
+                without_value,
+                vec![
+                    Some("<http://example.com/o0>".to_owned()),
+                    Some("<http://example.com/o4>".to_owned())
+                ]
+            );
+        } else {
+            return Err("SELECT query expected".into());
+        }
+
+        // The batching is disabled by default
+        let queries = Arc::new(Mutex::new(Vec::new()));
+        let handler = RecordingServiceHandler {
+            store: service_store,
+            queries: queries.clone(),
+            failing: None,
+        };
+        if let QueryResults::Solutions(solutions) = store.query_opt(
+            "SELECT * WHERE { ?s <http://example.com/p> ?o SERVICE <http://example.com/service> { ?o <http://example.com/q> ?v } }",
+            QueryOptions::default().with_service_handler(handler),
+        )? {
+            assert_eq!(solutions.collect::<Result<Vec<_>, _>>()?.len(), 4);
+        } else {
+            return Err("SELECT query expected".into());
+        }
+        assert!(queries
+            .lock()
+            .unwrap()
+            .iter()
+            .all(|query| !query.contains("VALUES")));
+        Ok(())
+    }
+}
@@ -1,18 +1,17 @@
 use crate::error::{invalid_data_error, invalid_input_error};
 use crate::io::GraphFormat;
-use crate::model::{BlankNode, GraphNameRef, NamedNode, NamedOrBlankNode, Quad, Term};
+use crate::model::{BlankNode, GraphNameRef, NamedNode, Quad, Subject, Term, Triple};
 use crate::sparql::algebra::{
     GraphPattern, GraphTarget, GraphUpdateOperation, NamedNodeOrVariable, QuadPattern,
-    QueryDataset, TermOrVariable,
+    QueryDataset, TermOrVariable, TriplePattern,
 };
 use crate::sparql::dataset::{DatasetStrId, DatasetView};
-use crate::sparql::eval::SimpleEvaluator;
 use crate::sparql::http::Client;
 use crate::sparql::plan::EncodedTuple;
 use crate::sparql::plan_builder::PlanBuilder;
 use crate::sparql::{EvaluationError, UpdateOptions, Variable};
 use crate::store::numeric_encoder::{
-    EncodedQuad, EncodedTerm, ReadEncoder, StrContainer, StrLookup, WriteEncoder,
+    Decoder, EncodedQuad, EncodedTerm, ReadEncoder, StrContainer, StrLookup, WriteEncoder,
 };
 use crate::store::{load_graph, ReadableEncodedStore, StoreOrParseError, WritableEncodedStore};
 use http::header::{ACCEPT, CONTENT_TYPE, USER_AGENT};
@@ -20,12 +19,12 @@ use http::{Method, Request, StatusCode};
 use oxiri::Iri;
 use std::collections::HashMap;
 use std::io;
-use std::rc::Rc;
+use std::sync::Arc;
 
 pub(crate) struct SimpleUpdateEvaluator<'a, R, W> {
     read: R,
     write: &'a mut W,
-    base_iri: Option<Rc<Iri<String>>>,
+    base_iri: Option<Arc<Iri<String>>>,
     options: UpdateOptions,
     client: Client,
 }
@@ -41,7 +40,7 @@ where
     pub fn new(
         read: R,
         write: &'a mut W,
-        base_iri: Option<Rc<Iri<String>>>,
+        base_iri: Option<Arc<Iri<String>>>,
         options: UpdateOptions,
     ) -> Self {
         Self {
@@ -113,17 +112,36 @@ where
         using: &QueryDataset,
         algebra: &GraphPattern,
     ) -> Result<(), EvaluationError> {
-        let dataset = Rc::new(DatasetView::new(self.read.clone(), using)?);
+        let dataset = Arc::new(DatasetView::new(
+            self.read.clone(),
+            using,
+            self.options.query_options.rdfs_entailment,
+        )?);
         let (plan, variables) = PlanBuilder::build(dataset.as_ref(), algebra)?;
-        let evaluator = SimpleEvaluator::<DatasetView<R>>::new(
-            dataset.clone(),
-            self.base_iri.clone(),
-            self.options.query_options.service_handler.clone(),
-        );
+        let evaluator = self
+            .options
+            .query_options
+            .clone()
+            .evaluator(dataset.clone(), self.base_iri.clone());
         let mut bnodes = HashMap::new();
+        let has_quoted_triples = delete.iter().chain(insert).any(|quad| {
+            matches!(quad.subject, TermOrVariable::Triple(_))
+                || matches!(quad.object, TermOrVariable::Triple(_))
+        });
         for tuple in evaluator.eval_plan(&plan, EncodedTuple::with_capacity(variables.len())) {
+            let tuple = tuple?;
+            // Quoted triples are built from the decoded terms
+            let terms = if has_quoted_triples {
+                tuple
+                    .iter()
+                    .map(|t| t.map(|t| dataset.decode_term(t)).transpose())
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(to_eval_error)?
+            } else {
+                Vec::new()
+            };
             // We map the tuple to only get store strings
-            let tuple = tuple?
+            let tuple = tuple
                 .into_iter()
                 .map(|t| {
                     Ok(if let Some(t) = t {
@@ -157,15 +175,19 @@ where
 
             for quad in delete {
                 if let Some(quad) =
-                    self.encode_quad_pattern_for_deletion(quad, &variables, &tuple)?
+                    self.encode_quad_pattern_for_deletion(quad, &variables, &tuple, &terms)?
                 {
                     self.write.remove_encoded(&quad).map_err(to_eval_error)?;
                 }
             }
             for quad in insert {
-                if let Some(quad) =
-                    self.encode_quad_pattern_for_insertion(quad, &variables, &tuple, &mut bnodes)?
-                {
+                if let Some(quad) = self.encode_quad_pattern_for_insertion(
+                    quad,
+                    &variables,
+                    &tuple,
+                    &terms,
+                    &mut bnodes,
+                )? {
                     self.write.insert_encoded(&quad).map_err(to_eval_error)?;
                 }
             }
@@ -219,7 +241,7 @@ where
         load_graph(
             self.write,
             response.into_body(),
-            format,
+            format.into(),
             to_graph_name,
             Some(from.as_str()),
         )
@@ -359,12 +381,13 @@ where
     ) -> Result<Option<EncodedQuad<R::StrId>>, EvaluationError> {
         Ok(Some(EncodedQuad {
             subject: match &quad.subject {
-                NamedOrBlankNode::NamedNode(subject) => {
-                    self.write.encode_named_node(subject.as_ref())
-                }
-                NamedOrBlankNode::BlankNode(subject) => self
+                Subject::NamedNode(subject) => self.write.encode_named_node(subject.as_ref()),
+                Subject::BlankNode(subject) => self
                     .write
                     .encode_blank_node(bnodes.entry(subject.clone()).or_default().as_ref()),
+                Subject::Triple(subject) => self
+                    .write
+                    .encode_triple(&triple_with_fresh_blank_nodes(subject, bnodes)),
             }
             .map_err(to_eval_error)?,
             predicate: self
@@ -377,6 +400,9 @@ where
                     .write
                     .encode_blank_node(bnodes.entry(object.clone()).or_default().as_ref()),
                 Term::Literal(object) => self.write.encode_literal(object.as_ref()),
+                Term::Triple(object) => self
+                    .write
+                    .encode_triple(&triple_with_fresh_blank_nodes(object, bnodes)),
             }
             .map_err(to_eval_error)?,
             graph_name: self
@@ -391,13 +417,18 @@ where
         quad: &QuadPattern,
         variables: &[Variable],
         values: &[Option<EncodedTerm<R::StrId>>],
+        terms: &[Option<Term>],
         bnodes: &mut HashMap<BlankNode, BlankNode>,
     ) -> Result<Option<EncodedQuad<R::StrId>>, EvaluationError> {
         Ok(Some(EncodedQuad {
-            subject: if let Some(subject) =
-                self.encode_term_for_insertion(&quad.subject, variables, values, bnodes, |t| {
-                    t.is_named_node() || t.is_blank_node()
-                })? {
+            subject: if let Some(subject) = self.encode_term_for_insertion(
+                &quad.subject,
+                variables,
+                values,
+                terms,
+                bnodes,
+                |t| t.is_named_node() || t.is_blank_node() || t.is_triple(),
+            )? {
                 subject
             } else {
                 return Ok(None);
@@ -409,10 +440,14 @@ where
             } else {
                 return Ok(None);
             },
-            object: if let Some(object) =
-                self.encode_term_for_insertion(&quad.object, variables, values, bnodes, |t| {
-                    !t.is_default_graph()
-                })? {
+            object: if let Some(object) = self.encode_term_for_insertion(
+                &quad.object,
+                variables,
+                values,
+                terms,
+                bnodes,
+                |t| !t.is_default_graph(),
+            )? {
                 object
             } else {
                 return Ok(None);
@@ -436,6 +471,7 @@ where
         term: &TermOrVariable,
         variables: &[Variable],
         values: &[Option<EncodedTerm<R::StrId>>],
+        terms: &[Option<Term>],
         bnodes: &mut HashMap<BlankNode, BlankNode>,
         validate: impl FnOnce(&EncodedTerm<R::StrId>) -> bool,
     ) -> Result<Option<EncodedTerm<R::StrId>>, EvaluationError> {
@@ -464,6 +500,13 @@ where
                     None
                 }
             }
+            TermOrVariable::Triple(triple) => {
+                if let Some(triple) = quoted_triple_from_pattern(triple, variables, terms, bnodes) {
+                    Some(self.write.encode_triple(&triple).map_err(to_eval_error)?)
+                } else {
+                    None
+                }
+            }
         })
     }
 
@@ -504,7 +547,7 @@ where
         Ok(Some(EncodedQuad {
             subject: if let Some(subject) = self
                 .read
-                .get_encoded_named_or_blank_node(quad.subject.as_ref())
+                .get_encoded_subject(quad.subject.as_ref())
                 .map_err(to_eval_error)?
             {
                 subject
@@ -546,10 +589,11 @@ where
         quad: &QuadPattern,
         variables: &[Variable],
         values: &[Option<EncodedTerm<R::StrId>>],
+        terms: &[Option<Term>],
     ) -> Result<Option<EncodedQuad<R::StrId>>, EvaluationError> {
         Ok(Some(EncodedQuad {
             subject: if let Some(subject) =
-                self.encode_term_for_deletion(&quad.subject, variables, values)?
+                self.encode_term_for_deletion(&quad.subject, variables, values, terms)?
             {
                 subject
             } else {
@@ -563,7 +607,7 @@ where
                 return Ok(None);
             },
             object: if let Some(object) =
-                self.encode_term_for_deletion(&quad.object, variables, values)?
+                self.encode_term_for_deletion(&quad.object, variables, values, terms)?
             {
                 object
             } else {
@@ -588,6 +632,7 @@ where
         term: &TermOrVariable,
         variables: &[Variable],
         values: &[Option<EncodedTerm<R::StrId>>],
+        terms: &[Option<Term>],
     ) -> Result<Option<EncodedTerm<R::StrId>>, EvaluationError> {
         match term {
             TermOrVariable::Term(term) => {
@@ -612,6 +657,18 @@ where
                     None
                 },
             ),
+            TermOrVariable::Triple(triple) => {
+                // Blank nodes are not allowed in deletion patterns so the map stays empty
+                if let Some(triple) =
+                    quoted_triple_from_pattern(triple, variables, terms, &mut HashMap::new())
+                {
+                    self.read
+                        .get_encoded_term(Term::from(triple).as_ref())
+                        .map_err(to_eval_error)
+                } else {
+                    Ok(None)
+                }
+            }
         }
     }
 
@@ -645,6 +702,88 @@ where
     }
 }
 
+/// Replaces the blank nodes of a quoted triple by the blank nodes that are inserted instead of them
+fn triple_with_fresh_blank_nodes(
+    triple: &Triple,
+    bnodes: &mut HashMap<BlankNode, BlankNode>,
+) -> Triple {
+    Triple {
+        subject: match &triple.subject {
+            Subject::BlankNode(subject) => {
+                bnodes.entry(subject.clone()).or_default().clone().into()
+            }
+            Subject::Triple(subject) => triple_with_fresh_blank_nodes(subject, bnodes).into(),
+            subject => subject.clone(),
+        },
+        predicate: triple.predicate.clone(),
+        object: match &triple.object {
+            Term::BlankNode(object) => bnodes.entry(object.clone()).or_default().clone().into(),
+            Term::Triple(object) => triple_with_fresh_blank_nodes(object, bnodes).into(),
+            object => object.clone(),
+        },
+    }
+}
+
+/// Builds the quoted triple described by a pattern from the solution terms
+///
+/// Returns `None` if a variable is not bound or if the triple would not be valid.
+fn quoted_triple_from_pattern(
+    pattern: &TriplePattern,
+    variables: &[Variable],
+    terms: &[Option<Term>],
+    bnodes: &mut HashMap<BlankNode, BlankNode>,
+) -> Option<Triple> {
+    let subject = match term_from_pattern(&pattern.subject, variables, terms, bnodes)? {
+        Term::NamedNode(subject) => subject.into(),
+        Term::BlankNode(subject) => subject.into(),
+        Term::Triple(subject) => Subject::Triple(subject),
+        Term::Literal(_) => return None,
+    };
+    let predicate = match &pattern.predicate {
+        NamedNodeOrVariable::NamedNode(predicate) => predicate.clone(),
+        NamedNodeOrVariable::Variable(v) => {
+            if let Term::NamedNode(predicate) = variable_term(v, variables, terms)? {
+                predicate
+            } else {
+                return None;
+            }
+        }
+    };
+    let object = term_from_pattern(&pattern.object, variables, terms, bnodes)?;
+    Some(Triple::new(subject, predicate, object))
+}
+
+fn term_from_pattern(
+    pattern: &TermOrVariable,
+    variables: &[Variable],
+    terms: &[Option<Term>],
+    bnodes: &mut HashMap<BlankNode, BlankNode>,
+) -> Option<Term> {
+    match pattern {
+        TermOrVariable::Term(Term::BlankNode(bnode)) => {
+            Some(bnodes.entry(bnode.clone()).or_default().clone().into())
+        }
+        TermOrVariable::Term(term) => Some(term.clone()),
+        TermOrVariable::Variable(v) => variable_term(v, variables, terms),
+        TermOrVariable::Triple(triple) => {
+            Some(quoted_triple_from_pattern(triple, variables, terms, bnodes)?.into())
+        }
+    }
+}
+
+fn variable_term(
+    variable: &Variable,
+    variables: &[Variable],
+    terms: &[Option<Term>],
+) -> Option<Term> {
+    variables
+        .iter()
+        .position(|v| v == variable)
+        .and_then(|i| terms.get(i))
+        .cloned()
+        .flatten()
+}
+
 fn to_eval_error(e: impl Into<EvaluationError>) -> EvaluationError {
     e.into()
 }
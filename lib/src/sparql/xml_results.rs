@@ -16,7 +16,7 @@ use std::io;
 use std::io::BufRead;
 use std::io::Write;
 use std::iter::empty;
-use std::rc::Rc;
+use std::sync::Arc;
 
 pub fn write_xml_results(results: QueryResults, sink: impl Write) -> Result<(), EvaluationError> {
     match results {
@@ -88,47 +88,7 @@ fn write_solutions(solutions: QuerySolutionIter, sink: impl Write) -> Result<(),
             writer
                 .write_event(Event::Start(binding_tag))
                 .map_err(map_xml_error)?;
-            match value {
-                Term::NamedNode(uri) => {
-                    writer
-                        .write_event(Event::Start(BytesStart::borrowed_name(b"uri")))
-                        .map_err(map_xml_error)?;
-                    writer
-                        .write_event(Event::Text(BytesText::from_plain_str(uri.as_str())))
-                        .map_err(map_xml_error)?;
-                    writer
-                        .write_event(Event::End(BytesEnd::borrowed(b"uri")))
-                        .map_err(map_xml_error)?;
-                }
-                Term::BlankNode(bnode) => {
-                    writer
-                        .write_event(Event::Start(BytesStart::borrowed_name(b"bnode")))
-                        .map_err(map_xml_error)?;
-                    writer
-                        .write_event(Event::Text(BytesText::from_plain_str(bnode.as_str())))
-                        .map_err(map_xml_error)?;
-                    writer
-                        .write_event(Event::End(BytesEnd::borrowed(b"bnode")))
-                        .map_err(map_xml_error)?;
-                }
-                Term::Literal(literal) => {
-                    let mut literal_tag = BytesStart::borrowed_name(b"literal");
-                    if let Some(language) = literal.language() {
-                        literal_tag.push_attribute(("xml:lang", language));
-                    } else if !literal.is_plain() {
-                        literal_tag.push_attribute(("datatype", literal.datatype().as_str()));
-                    }
-                    writer
-                        .write_event(Event::Start(literal_tag))
-                        .map_err(map_xml_error)?;
-                    writer
-                        .write_event(Event::Text(BytesText::from_plain_str(literal.value())))
-                        .map_err(map_xml_error)?;
-                    writer
-                        .write_event(Event::End(BytesEnd::borrowed(b"literal")))
-                        .map_err(map_xml_error)?;
-                }
-            }
+            write_xml_term(value.as_ref(), &mut writer).map_err(map_xml_error)?;
             writer
                 .write_event(Event::End(BytesEnd::borrowed(b"binding")))
                 .map_err(map_xml_error)?;
@@ -146,7 +106,50 @@ fn write_solutions(solutions: QuerySolutionIter, sink: impl Write) -> Result<(),
     Ok(())
 }
 
-pub fn read_xml_results(source: impl BufRead + 'static) -> Result<QueryResults, io::Error> {
+fn write_xml_term(
+    term: TermRef<'_>,
+    writer: &mut Writer<impl Write>,
+) -> Result<(), quick_xml::Error> {
+    match term {
+        TermRef::NamedNode(uri) => {
+            writer.write_event(Event::Start(BytesStart::borrowed_name(b"uri")))?;
+            writer.write_event(Event::Text(BytesText::from_plain_str(uri.as_str())))?;
+            writer.write_event(Event::End(BytesEnd::borrowed(b"uri")))?;
+        }
+        TermRef::BlankNode(bnode) => {
+            writer.write_event(Event::Start(BytesStart::borrowed_name(b"bnode")))?;
+            writer.write_event(Event::Text(BytesText::from_plain_str(bnode.as_str())))?;
+            writer.write_event(Event::End(BytesEnd::borrowed(b"bnode")))?;
+        }
+        TermRef::Literal(literal) => {
+            let mut literal_tag = BytesStart::borrowed_name(b"literal");
+            if let Some(language) = literal.language() {
+                literal_tag.push_attribute(("xml:lang", language));
+            } else if !literal.is_plain() {
+                literal_tag.push_attribute(("datatype", literal.datatype().as_str()));
+            }
+            writer.write_event(Event::Start(literal_tag))?;
+            writer.write_event(Event::Text(BytesText::from_plain_str(literal.value())))?;
+            writer.write_event(Event::End(BytesEnd::borrowed(b"literal")))?;
+        }
+        TermRef::Triple(triple) => {
+            writer.write_event(Event::Start(BytesStart::borrowed_name(b"triple")))?;
+            writer.write_event(Event::Start(BytesStart::borrowed_name(b"subject")))?;
+            write_xml_term(triple.subject.as_ref().into(), writer)?;
+            writer.write_event(Event::End(BytesEnd::borrowed(b"subject")))?;
+            writer.write_event(Event::Start(BytesStart::borrowed_name(b"predicate")))?;
+            write_xml_term(triple.predicate.as_ref().into(), writer)?;
+            writer.write_event(Event::End(BytesEnd::borrowed(b"predicate")))?;
+            writer.write_event(Event::Start(BytesStart::borrowed_name(b"object")))?;
+            write_xml_term(triple.object.as_ref(), writer)?;
+            writer.write_event(Event::End(BytesEnd::borrowed(b"object")))?;
+            writer.write_event(Event::End(BytesEnd::borrowed(b"triple")))?;
+        }
+    }
+    Ok(())
+}
+
+pub fn read_xml_results(source: impl BufRead + Send + 'static) -> Result<QueryResults, io::Error> {
     enum State {
         Start,
         Sparql,
@@ -217,7 +220,7 @@ pub fn read_xml_results(source: impl BufRead + 'static) -> Result<QueryResults,
                             mapping.insert(var.as_bytes().to_vec(), i);
                         }
                         return Ok(QueryResults::Solutions(QuerySolutionIter::new(
-                            Rc::new(variables.into_iter().map(Variable::new).collect::<Result<Vec<_>,_>>().map_err(invalid_data_error)?),
+                            Arc::new(variables.into_iter().map(Variable::new).collect::<Result<Vec<_>,_>>().map_err(invalid_data_error)?),
                             Box::new(ResultsIterator {
                                 reader,
                                 buffer: Vec::default(),
@@ -255,7 +258,7 @@ pub fn read_xml_results(source: impl BufRead + 'static) -> Result<QueryResults,
                 State::AfterHead => {
                     return if event.name() == b"results" {
                         Ok(QueryResults::Solutions(QuerySolutionIter::new(
-                            Rc::new(variables.into_iter().map(Variable::new).collect::<Result<Vec<_>,_>>().map_err(invalid_data_error)?),
+                            Arc::new(variables.into_iter().map(Variable::new).collect::<Result<Vec<_>,_>>().map_err(invalid_data_error)?),
                             Box::new(empty()),
                         )))
                     } else {
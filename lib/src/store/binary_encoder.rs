@@ -1,6 +1,6 @@
 use crate::error::invalid_data_error;
 use crate::model::xsd::*;
-use crate::store::numeric_encoder::StrId;
+use crate::store::numeric_encoder::{RangeKey, StrId};
 use crate::store::small_string::SmallString;
 use siphasher::sip128::{Hasher128, SipHasher24};
 use std::hash::Hasher;
@@ -11,14 +11,16 @@ use std::mem::size_of;
 type EncodedTerm = crate::store::numeric_encoder::EncodedTerm<StrHash>;
 type EncodedQuad = crate::store::numeric_encoder::EncodedQuad<StrHash>;
 
-pub const LATEST_STORAGE_VERSION: u64 = 1;
+pub const LATEST_STORAGE_VERSION: u64 = 2;
 pub const WRITTEN_TERM_MAX_SIZE: usize = size_of::<u8>() + 2 * size_of::<StrHash>();
+pub const WRITTEN_RANGE_KEY_SIZE: usize = 17;
 
 // Encoded term type blocks
 // 1-7: usual named nodes (except prefixes c.f. later)
 // 8-15: blank nodes
 // 16-47: literals
-// 48-64: future use
+// 48: quoted triples
+// 49-64: future use
 // 64-127: default named node prefixes
 // 128-255: custom named node prefixes
 const TYPE_NAMED_NODE_ID: u8 = 1;
@@ -50,6 +52,7 @@ const TYPE_G_MONTH_LITERAL: u8 = 41;
 const TYPE_DURATION_LITERAL: u8 = 42;
 const TYPE_YEAR_MONTH_DURATION_LITERAL: u8 = 43;
 const TYPE_DAY_TIME_DURATION_LITERAL: u8 = 44;
+const TYPE_TRIPLE: u8 = 48;
 
 #[derive(Eq, PartialEq, Debug, Copy, Clone, Hash)]
 #[repr(transparent)]
@@ -92,6 +95,8 @@ pub enum QuadEncoding {
     DSPO,
     DPOS,
     DOSP,
+    PVOSG,
+    PTOSG,
 }
 
 impl QuadEncoding {
@@ -107,6 +112,46 @@ impl QuadEncoding {
             QuadEncoding::DSPO => cursor.read_dspo_quad(),
             QuadEncoding::DPOS => cursor.read_dpos_quad(),
             QuadEncoding::DOSP => cursor.read_dosp_quad(),
+            QuadEncoding::PVOSG => {
+                let predicate = cursor.read_term()?;
+                cursor.set_position(cursor.position() + WRITTEN_RANGE_KEY_SIZE as u64);
+                let object = cursor.read_term()?;
+                let subject = cursor.read_term()?;
+                let graph_name = if cursor.position() < buffer.len() as u64 {
+                    cursor.read_term()?
+                } else {
+                    EncodedTerm::DefaultGraph
+                };
+                Ok(EncodedQuad {
+                    subject,
+                    predicate,
+                    object,
+                    graph_name,
+                })
+            }
+            QuadEncoding::PTOSG => {
+                let predicate = cursor.read_term()?;
+                let mut byte = [0];
+                loop {
+                    cursor.read_exact(&mut byte)?;
+                    if byte[0] == 0 {
+                        break; // end of the token
+                    }
+                }
+                let object = cursor.read_term()?;
+                let subject = cursor.read_term()?;
+                let graph_name = if cursor.position() < buffer.len() as u64 {
+                    cursor.read_term()?
+                } else {
+                    EncodedTerm::DefaultGraph
+                };
+                Ok(EncodedQuad {
+                    subject,
+                    predicate,
+                    object,
+                    graph_name,
+                })
+            }
         }
     }
 }
@@ -342,6 +387,13 @@ impl<R: Read> TermReader for R {
                     value_id: StrHash::from_be_bytes(buffer),
                 })
             }
+            TYPE_TRIPLE => {
+                let mut buffer = [0; 16];
+                self.read_exact(&mut buffer)?;
+                Ok(EncodedTerm::Triple {
+                    triple_id: StrHash::from_be_bytes(buffer),
+                })
+            }
             TYPE_BOOLEAN_LITERAL_TRUE => Ok(EncodedTerm::BooleanLiteral(true)),
             TYPE_BOOLEAN_LITERAL_FALSE => Ok(EncodedTerm::BooleanLiteral(false)),
             TYPE_FLOAT_LITERAL => {
@@ -496,6 +548,53 @@ pub fn write_osp_quad(sink: &mut Vec<u8>, quad: &EncodedQuad) {
     write_term(sink, quad.predicate);
 }
 
+/// Writes the key of the range index: the predicate, the object value key, then the object, subject and graph name
+pub fn write_pvosg_quad(sink: &mut Vec<u8>, key: RangeKey, quad: &EncodedQuad) {
+    write_term(sink, quad.predicate);
+    sink.extend_from_slice(&key.to_be_bytes());
+    write_term(sink, quad.object);
+    write_term(sink, quad.subject);
+    write_term(sink, quad.graph_name);
+}
+
+/// Encodes a predicate and an object value key as a range index key prefix
+pub fn encode_term_range_key(predicate: EncodedTerm, key: RangeKey) -> Vec<u8> {
+    let mut vec = Vec::with_capacity(WRITTEN_TERM_MAX_SIZE + WRITTEN_RANGE_KEY_SIZE);
+    write_term(&mut vec, predicate);
+    vec.extend_from_slice(&key.to_be_bytes());
+    vec
+}
+
+/// Writes the key of the text index: the predicate, the null-terminated token, then the object, subject and graph name
+pub fn write_ptosg_quad(sink: &mut Vec<u8>, token: &str, quad: &EncodedQuad) {
+    write_term(sink, quad.predicate);
+    sink.extend_from_slice(token.as_bytes());
+    sink.push(0);
+    write_term(sink, quad.object);
+    write_term(sink, quad.subject);
+    write_term(sink, quad.graph_name);
+}
+
+/// Encodes a predicate and a token as a text index key prefix
+pub fn encode_term_token(predicate: EncodedTerm, token: &str) -> Vec<u8> {
+    let mut vec = Vec::with_capacity(WRITTEN_TERM_MAX_SIZE + token.len() + 1);
+    write_term(&mut vec, predicate);
+    vec.extend_from_slice(token.as_bytes());
+    vec.push(0);
+    vec
+}
+
+/// Returns the smallest key greater than all the keys having the given prefix
+pub fn prefix_successor(mut prefix: Vec<u8>) -> Option<Vec<u8>> {
+    while let Some(last) = prefix.pop() {
+        if last < u8::MAX {
+            prefix.push(last + 1);
+            return Some(prefix);
+        }
+    }
+    None
+}
+
 pub fn encode_term(t: EncodedTerm) -> Vec<u8> {
     let mut vec = Vec::with_capacity(WRITTEN_TERM_MAX_SIZE);
     write_term(&mut vec, t);
@@ -594,6 +693,10 @@ pub fn write_term(sink: &mut Vec<u8>, term: EncodedTerm) {
             sink.extend_from_slice(&datatype_id.to_be_bytes());
             sink.extend_from_slice(&value_id.to_be_bytes());
         }
+        EncodedTerm::Triple { triple_id } => {
+            sink.push(TYPE_TRIPLE);
+            sink.extend_from_slice(&triple_id.to_be_bytes());
+        }
         EncodedTerm::BooleanLiteral(true) => sink.push(TYPE_BOOLEAN_LITERAL_TRUE),
         EncodedTerm::BooleanLiteral(false) => sink.push(TYPE_BOOLEAN_LITERAL_FALSE),
         EncodedTerm::FloatLiteral(value) => {
@@ -0,0 +1,511 @@
+//! Read-only store based on an [HDT](https://www.rdfhdt.org/) file.
+
+use crate::io::hdt::{hdt_term_serializations, parse_hdt_term, Hdt, HdtTripleIds, TripleRole};
+use crate::io::{DatasetSerializer, GraphSerializer};
+use crate::model::*;
+use crate::sparql::{
+    evaluate_query, prepare_query, EvaluationError, PreparedQuery, Query, QueryCache, QueryOptions,
+    QueryResults,
+};
+use crate::store::numeric_encoder::{
+    Decoder, StrContainer, StrEncodingAware, StrLookup, WriteEncoder,
+};
+use crate::store::{
+    dump_canonical_dataset, dump_dataset, dump_graph, get_encoded_quad_pattern,
+    ReadableEncodedStore,
+};
+use lasso::{LargeSpur, ThreadedRodeo};
+use std::convert::TryInto;
+use std::io;
+use std::io::Write;
+use std::iter::{empty, once, Empty, Once};
+use std::path::Path;
+use std::sync::Arc;
+
+/// Read-only store based on an [HDT](https://www.rdfhdt.org/) file.
+///
+/// The file is mapped into memory and its triples are exposed in the default graph.
+/// It should not be modified while the store is opened.
+/// The store has no named graphs.
+///
+/// Only the triples patterns with a bound subject use the HDT index.
+/// The other ones are evaluated with a scan of the file triples.
+///
+/// To load an HDT file into a writable store, use for example [`RocksDbStore::load_hdt`](super::rocksdb::RocksDbStore::load_hdt()).
+///
+/// Usage example:
+/// ```no_run
+/// use oxigraph::HdtStore;
+/// use oxigraph::sparql::QueryResults;
+///
+/// let store = HdtStore::open("example.hdt")?;
+///
+/// // SPARQL query
+/// if let QueryResults::Solutions(mut solutions) = store.query("SELECT ?s WHERE { ?s ?p ?o }")? {
+///     println!("{}", solutions.next().unwrap()?.get("s").unwrap());
+/// }
+/// # Result::<_,Box<dyn std::error::Error>>::Ok(())
+/// ```
+#[derive(Clone)]
+pub struct HdtStore {
+    hdt: Arc<Hdt>,
+    strings: Arc<ThreadedRodeo<LargeSpur>>,
+    query_cache: Arc<QueryCache>,
+}
+
+type EncodedTerm = crate::store::numeric_encoder::EncodedTerm<LargeSpur>;
+type EncodedQuad = crate::store::numeric_encoder::EncodedQuad<LargeSpur>;
+
+impl HdtStore {
+    /// Opens an HDT file.
+    ///
+    /// The file is mapped into memory: modifying or truncating it while the store is opened leads to undefined results.
+    /// Only the HDT files with a four sections dictionary and bitmap triples, the default of the HDT tools, are supported.
+    ///
+    /// Errors related to an invalid or unsupported file use the [`InvalidData`](std::io::ErrorKind::InvalidData) or [`UnexpectedEof`](std::io::ErrorKind::UnexpectedEof) error kinds.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, io::Error> {
+        Ok(Self::from_hdt(Hdt::open(path.as_ref())?))
+    }
+
+    fn from_hdt(hdt: Hdt) -> Self {
+        Self {
+            hdt: Arc::new(hdt),
+            strings: Arc::new(ThreadedRodeo::new()),
+            query_cache: Arc::default(),
+        }
+    }
+
+    /// Executes a [SPARQL 1.1 query](https://www.w3.org/TR/sparql11-query/).
+    ///
+    /// See [`MemoryStore`](super::memory::MemoryStore::query()) for a usage example.
+    pub fn query(
+        &self,
+        query: impl TryInto<Query, Error = impl Into<EvaluationError>>,
+    ) -> Result<QueryResults, EvaluationError> {
+        self.query_opt(query, QueryOptions::default())
+    }
+
+    /// Executes a [SPARQL 1.1 query](https://www.w3.org/TR/sparql11-query/) with some options.
+    pub fn query_opt(
+        &self,
+        query: impl TryInto<Query, Error = impl Into<EvaluationError>>,
+        options: QueryOptions,
+    ) -> Result<QueryResults, EvaluationError> {
+        self.query_cache.evaluate(
+            query.try_into().map_err(|e| e.into())?,
+            options,
+            |query, options| evaluate_query(self.clone(), query, options),
+        )
+    }
+
+    /// Prepares a [SPARQL 1.1 query](https://www.w3.org/TR/sparql11-query/) to be executed many times with different variable bindings.
+    ///
+    /// See [`MemoryStore`](super::memory::MemoryStore::prepare_query()) for a usage example.
+    pub fn prepare_query(
+        &self,
+        query: impl TryInto<Query, Error = impl Into<EvaluationError>>,
+    ) -> Result<PreparedQuery, EvaluationError> {
+        self.prepare_query_opt(query, QueryOptions::default())
+    }
+
+    /// Prepares a [SPARQL 1.1 query](https://www.w3.org/TR/sparql11-query/) with some options.
+    pub fn prepare_query_opt(
+        &self,
+        query: impl TryInto<Query, Error = impl Into<EvaluationError>>,
+        options: QueryOptions,
+    ) -> Result<PreparedQuery, EvaluationError> {
+        prepare_query(self.clone(), query, options)
+    }
+
+    /// Sets the maximal number of query results kept in the store query results cache.
+    ///
+    /// See [`MemoryStore`](super::memory::MemoryStore::set_query_cache_capacity()) for more details.
+    /// The store being read-only, the cached results are never invalidated.
+    pub fn set_query_cache_capacity(&self, capacity: usize) {
+        self.query_cache.set_capacity(capacity)
+    }
+
+    /// Retrieves quads with a filter on each quad component
+    pub fn quads_for_pattern(
+        &self,
+        subject: Option<SubjectRef<'_>>,
+        predicate: Option<NamedNodeRef<'_>>,
+        object: Option<TermRef<'_>>,
+        graph_name: Option<GraphNameRef<'_>>,
+    ) -> HdtQuadIter {
+        HdtQuadIter {
+            iter: match get_encoded_quad_pattern(self, subject, predicate, object, graph_name) {
+                Ok(Some((subject, predicate, object, graph_name))) => {
+                    self.encoded_quads_for_pattern(subject, predicate, object, graph_name)
+                }
+                Ok(None) => EncodedQuadsIter::Empty,
+                Err(error) => EncodedQuadsIter::Error(once(error)),
+            },
+            store: self.clone(),
+        }
+    }
+
+    /// Returns all the quads contained in the store
+    pub fn iter(&self) -> HdtQuadIter {
+        self.quads_for_pattern(None, None, None, None)
+    }
+
+    /// Checks if this store contains a given quad
+    pub fn contains<'a>(&self, quad: impl Into<QuadRef<'a>>) -> Result<bool, io::Error> {
+        let quad = quad.into();
+        Ok(self
+            .quads_for_pattern(
+                Some(quad.subject),
+                Some(quad.predicate),
+                Some(quad.object),
+                Some(quad.graph_name),
+            )
+            .next()
+            .transpose()?
+            .is_some())
+    }
+
+    /// Returns the number of quads in the store
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn len(&self) -> usize {
+        self.hdt.len() as usize
+    }
+
+    /// Returns if the store is empty
+    pub fn is_empty(&self) -> bool {
+        self.hdt.len() == 0
+    }
+
+    /// Dumps a store graph into a file.
+    ///
+    /// Only the default graph is not empty.
+    pub fn dump_graph<'a>(
+        &self,
+        writer: impl Write,
+        format: impl Into<GraphSerializer>,
+        from_graph_name: impl Into<GraphNameRef<'a>>,
+    ) -> Result<(), io::Error> {
+        dump_graph(
+            self.quads_for_pattern(None, None, None, Some(from_graph_name.into()))
+                .map(|q| Ok(q?.into())),
+            writer,
+            &format.into(),
+        )
+    }
+
+    /// Dumps the store into a file.
+    pub fn dump_dataset(
+        &self,
+        writer: impl Write,
+        format: impl Into<DatasetSerializer>,
+    ) -> Result<(), io::Error> {
+        dump_dataset(self.iter(), writer, &format.into())
+    }
+
+    /// Dumps the store into a file as [canonical N-Quads](crate::io::write_canonical_n_quads()).
+    ///
+    /// See [`MemoryStore`](super::memory::MemoryStore::dump_canonical_dataset()) for a usage example.
+    pub fn dump_canonical_dataset(&self, writer: impl Write) -> Result<(), io::Error> {
+        dump_canonical_dataset(self.iter(), writer)
+    }
+
+    /// Returns the HDT id of an encoded term
+    fn hdt_id(&self, term: EncodedTerm, role: TripleRole) -> Result<Option<u64>, io::Error> {
+        let term = self.decode_term(term)?;
+        for serialization in hdt_term_serializations(term.as_ref()) {
+            if let Some(id) = self.hdt.locate(role, &serialization)? {
+                return Ok(Some(id));
+            }
+        }
+        Ok(None)
+    }
+
+    fn encode_hdt_term(&self, role: TripleRole, id: u64) -> Result<EncodedTerm, io::Error> {
+        let term = parse_hdt_term(&self.hdt.extract(role, id)?)?;
+        let mut this = self;
+        this.encode_term(term.as_ref())
+    }
+}
+
+impl StrEncodingAware for HdtStore {
+    type Error = io::Error;
+    type StrId = LargeSpur;
+}
+
+impl StrLookup for HdtStore {
+    fn get_str(&self, id: LargeSpur) -> Result<Option<String>, io::Error> {
+        Ok(self.strings.try_resolve(&id).map(|e| e.to_owned()))
+    }
+
+    fn get_str_id(&self, value: &str) -> Result<Option<LargeSpur>, io::Error> {
+        // The strings are interned lazily, the lookup in the HDT dictionary is done when a pattern is evaluated
+        Ok(Some(self.strings.get_or_intern(value)))
+    }
+}
+
+impl StrContainer for &HdtStore {
+    fn insert_str(&mut self, value: &str) -> Result<LargeSpur, io::Error> {
+        Ok(self.strings.get_or_intern(value))
+    }
+}
+
+impl ReadableEncodedStore for HdtStore {
+    type QuadsIter = EncodedQuadsIter;
+    type GraphsIter = Empty<Result<EncodedTerm, io::Error>>;
+
+    fn encoded_quads_for_pattern(
+        &self,
+        subject: Option<EncodedTerm>,
+        predicate: Option<EncodedTerm>,
+        object: Option<EncodedTerm>,
+        graph_name: Option<EncodedTerm>,
+    ) -> EncodedQuadsIter {
+        if matches!(graph_name, Some(g) if !g.is_default_graph()) {
+            return EncodedQuadsIter::Empty;
+        }
+        let mut ids = [None; 3];
+        for (i, (term, role)) in [
+            (subject, TripleRole::Subject),
+            (predicate, TripleRole::Predicate),
+            (object, TripleRole::Object),
+        ]
+        .iter()
+        .enumerate()
+        {
+            if let Some(term) = term {
+                match self.hdt_id(*term, *role) {
+                    Ok(Some(id)) => ids[i] = Some(id),
+                    Ok(None) => return EncodedQuadsIter::Empty,
+                    Err(error) => return EncodedQuadsIter::Error(once(error)),
+                }
+            }
+        }
+        EncodedQuadsIter::Triples(Box::new(DecodingTriples {
+            ids: self.hdt.triple_ids(ids[0], ids[1], ids[2]),
+            store: self.clone(),
+            subject: None,
+            predicate: None,
+        }))
+    }
+
+    fn encoded_named_graphs(&self) -> Self::GraphsIter {
+        empty()
+    }
+
+    fn contains_encoded_named_graph(&self, _graph_name: EncodedTerm) -> Result<bool, io::Error> {
+        Ok(false)
+    }
+}
+
+pub(crate) enum EncodedQuadsIter {
+    Triples(Box<DecodingTriples>),
+    Error(Once<io::Error>),
+    Empty,
+}
+
+impl Iterator for EncodedQuadsIter {
+    type Item = Result<EncodedQuad, io::Error>;
+
+    fn next(&mut self) -> Option<Result<EncodedQuad, io::Error>> {
+        match self {
+            EncodedQuadsIter::Triples(triples) => {
+                let ids = triples.ids.next()?;
+                Some(triples.decode(ids))
+            }
+            EncodedQuadsIter::Error(iter) => iter.next().map(Err),
+            EncodedQuadsIter::Empty => None,
+        }
+    }
+}
+
+/// Encodes the triples returned by the HDT file.
+///
+/// Consecutive triples often share their subject and their predicate so the last ones are cached.
+pub(crate) struct DecodingTriples {
+    ids: HdtTripleIds,
+    store: HdtStore,
+    subject: Option<(u64, EncodedTerm)>,
+    predicate: Option<(u64, EncodedTerm)>,
+}
+
+impl DecodingTriples {
+    fn decode(
+        &mut self,
+        [subject_id, predicate_id, object_id]: [u64; 3],
+    ) -> Result<EncodedQuad, io::Error> {
+        let subject = cached_hdt_term(
+            &self.store,
+            &mut self.subject,
+            TripleRole::Subject,
+            subject_id,
+        )?;
+        let predicate = cached_hdt_term(
+            &self.store,
+            &mut self.predicate,
+            TripleRole::Predicate,
+            predicate_id,
+        )?;
+        let object = self.store.encode_hdt_term(TripleRole::Object, object_id)?;
+        Ok(EncodedQuad::new(
+            subject,
+            predicate,
+            object,
+            EncodedTerm::DefaultGraph,
+        ))
+    }
+}
+
+fn cached_hdt_term(
+    store: &HdtStore,
+    cache: &mut Option<(u64, EncodedTerm)>,
+    role: TripleRole,
+    id: u64,
+) -> Result<EncodedTerm, io::Error> {
+    match cache {
+        Some((cached_id, term)) if *cached_id == id => Ok(*term),
+        _ => {
+            let term = store.encode_hdt_term(role, id)?;
+            *cache = Some((id, term));
+            Ok(term)
+        }
+    }
+}
+
+/// An iterator returning the quads contained in a [`HdtStore`].
+pub struct HdtQuadIter {
+    iter: EncodedQuadsIter,
+    store: HdtStore,
+}
+
+impl Iterator for HdtQuadIter {
+    type Item = Result<Quad, io::Error>;
+
+    fn next(&mut self) -> Option<Result<Quad, io::Error>> {
+        Some(match self.iter.next()? {
+            Ok(quad) => self.store.decode_quad(&quad).map_err(|e| e.into()),
+            Err(error) => Err(error),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::hdt::write_hdt;
+    use crate::model::vocab::xsd;
+    use crate::store::MemoryStore;
+    use std::fs;
+
+    fn example() -> Vec<Triple> {
+        let ex = |name: &str| NamedNode::new_unchecked(format!("http://example.com/{}", name));
+        vec![
+            Triple::new(ex("a"), ex("knows"), ex("b")),
+            Triple::new(ex("b"), ex("knows"), ex("c")),
+            Triple::new(ex("a"), ex("name"), Literal::new_simple_literal("A")),
+            Triple::new(
+                ex("b"),
+                ex("name"),
+                Literal::new_language_tagged_literal_unchecked("Bé", "fr"),
+            ),
+            Triple::new(ex("b"), ex("age"), Literal::from(12)),
+            Triple::new(BlankNode::new_unchecked("c"), ex("knows"), ex("a")),
+        ]
+    }
+
+    fn write_example_file(name: &str) -> io::Result<std::path::PathBuf> {
+        let file = std::env::temp_dir().join(name);
+        fs::write(&file, write_hdt(&example(), 2))?;
+        Ok(file)
+    }
+
+    #[test]
+    fn test_hdt_store() -> Result<(), Box<dyn std::error::Error>> {
+        let file = write_example_file("oxigraph_hdt_store_test.hdt")?;
+        let store = HdtStore::open(&file)?;
+        fs::remove_file(&file)?;
+
+        assert_eq!(store.len(), 6);
+        assert!(!store.is_empty());
+        let mut expected = example()
+            .into_iter()
+            .map(|t| t.in_graph(GraphName::DefaultGraph))
+            .collect::<Vec<_>>();
+        expected.sort_by_key(|q| q.to_string());
+        let mut quads = store.iter().collect::<Result<Vec<_>, _>>()?;
+        quads.sort_by_key(|q| q.to_string());
+        assert_eq!(quads, expected);
+
+        let a = NamedNodeRef::new("http://example.com/a")?;
+        let knows = NamedNodeRef::new("http://example.com/knows")?;
+        assert_eq!(
+            store
+                .quads_for_pattern(None, None, Some(a.into()), None)
+                .count(),
+            1
+        );
+        assert_eq!(
+            store
+                .quads_for_pattern(Some(a.into()), Some(knows), None, None)
+                .count(),
+            1
+        );
+        assert_eq!(
+            store
+                .quads_for_pattern(Some(a.into()), None, None, Some(a.into()))
+                .count(),
+            0
+        );
+        assert!(store.contains(QuadRef::new(
+            NamedNodeRef::new("http://example.com/b")?,
+            NamedNodeRef::new("http://example.com/age")?,
+            &Literal::from(12),
+            GraphNameRef::DefaultGraph
+        ))?);
+        assert!(!store.contains(QuadRef::new(a, knows, a, GraphNameRef::DefaultGraph))?);
+
+        if let QueryResults::Solutions(solutions) = store.query(
+            "SELECT ?name WHERE { ?s <http://example.com/knows>/<http://example.com/name> ?name }",
+        )? {
+            let mut names = solutions
+                .map(|s| Ok(s?.get("name").cloned()))
+                .collect::<Result<Vec<_>, EvaluationError>>()?;
+            names.sort_by_key(|n| n.as_ref().map(|n| n.to_string()));
+            assert_eq!(
+                names,
+                vec![
+                    Some(Literal::new_simple_literal("A").into()),
+                    Some(Literal::new_language_tagged_literal_unchecked("Bé", "fr").into())
+                ]
+            );
+        } else {
+            panic!("SELECT query should return solutions")
+        }
+        assert!(matches!(
+            store.query(&format!(
+                "ASK {{ ?s ?p \"12\"^^<{}> ; ?p2 \"Bé\"@fr }}",
+                xsd::INTEGER.as_str()
+            ))?,
+            QueryResults::Boolean(true)
+        ));
+        assert!(matches!(
+            store.query("ASK { GRAPH ?g { ?s ?p ?o } }")?,
+            QueryResults::Boolean(false)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_hdt() -> Result<(), Box<dyn std::error::Error>> {
+        let file = write_example_file("oxigraph_load_hdt_test.hdt")?;
+        let store = MemoryStore::new();
+        store.load_hdt(&file, GraphNameRef::DefaultGraph)?;
+        fs::remove_file(&file)?;
+
+        let expected = example()
+            .into_iter()
+            .map(|t| t.in_graph(GraphName::DefaultGraph))
+            .collect::<MemoryStore>();
+        assert!(store.is_isomorphic(&expected));
+        Ok(())
+    }
+}
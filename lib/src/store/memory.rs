@@ -1,26 +1,37 @@
 //! In-memory store.
 
 use crate::error::{invalid_input_error, UnwrapInfallible};
-use crate::io::{DatasetFormat, DatasetParser, GraphFormat, GraphParser};
+use crate::io::detection::open_rdf_file;
+use crate::io::{
+    DatasetParser, DatasetSerializer, GraphParser, GraphSerializer, PrefixMap, RdfFormat,
+};
+use crate::model::geo::Envelope;
 use crate::model::*;
+use crate::shacl::{ShaclError, ShaclValidator};
 use crate::sparql::{
-    evaluate_query, evaluate_update, EvaluationError, Query, QueryOptions, QueryResults, Update,
-    UpdateOptions,
+    evaluate_query, evaluate_update, prepare_query, EvaluationError, PreparedQuery, Query,
+    QueryCache, QueryOptions, QueryResults, ServiceDescription, Update, UpdateOptions,
 };
 use crate::store::numeric_encoder::{
-    Decoder, ReadEncoder, StrContainer, StrEncodingAware, StrId, StrLookup, WriteEncoder,
+    Decoder, EncodedRange, RangeKey, ReadEncoder, StrContainer, StrEncodingAware, StrId, StrLookup,
+    WriteEncoder,
 };
+use crate::store::reasoning::{EncodedTriple, InferenceStore, Owl2RlReasoner};
+use crate::store::rules::{refresh_view, refresh_views, Rule, RuleChanges, RuleStore};
+use crate::store::spatial::{encoded_term_geometry, RTree, SearchBox};
+use crate::store::text::encoded_term_tokens;
 use crate::store::{
-    dump_dataset, dump_graph, get_encoded_quad_pattern, load_dataset, load_graph,
-    ReadableEncodedStore, WritableEncodedStore,
+    dump_canonical_dataset, dump_dataset, dump_graph, get_encoded_quad_pattern, load_dataset,
+    load_graph, load_hdt, read_validated_graph, ReadableEncodedStore, StoreOptions,
+    WritableEncodedStore,
 };
 use lasso::{LargeSpur, ThreadedRodeo};
-use std::collections::hash_map::DefaultHasher;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::convert::{Infallible, TryInto};
-use std::hash::{Hash, Hasher};
+use std::hash::Hash;
 use std::io::{BufRead, Write};
 use std::iter::FromIterator;
+use std::path::Path;
 use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
 use std::vec::IntoIter;
 use std::{fmt, io};
@@ -56,10 +67,14 @@ use std::{fmt, io};
 pub struct MemoryStore {
     indexes: Arc<RwLock<MemoryStoreIndexes>>,
     strings: Arc<ThreadedRodeo<LargeSpur>>,
+    query_cache: Arc<QueryCache>,
 }
 
 type TripleMap<T> = HashMap<T, HashMap<T, HashSet<T>>>;
 type QuadMap<T> = HashMap<T, TripleMap<T>>;
+type RangeMap<T, Q> = HashMap<T, BTreeMap<RangeKey, HashSet<Q>>>;
+type TokenMap<T, Q> = HashMap<T, BTreeMap<String, HashSet<Q>>>;
+type BoxMap<T, Q> = HashMap<T, RTree<Q>>;
 type EncodedTerm = crate::store::numeric_encoder::EncodedTerm<LargeSpur>;
 type EncodedQuad = crate::store::numeric_encoder::EncodedQuad<LargeSpur>;
 
@@ -74,6 +89,20 @@ struct MemoryStoreIndexes {
     default_spo: TripleMap<EncodedTerm>,
     default_pos: TripleMap<EncodedTerm>,
     default_osp: TripleMap<EncodedTerm>,
+    range: Option<RangeMap<EncodedTerm, EncodedQuad>>,
+    text: Option<MemoryLiteralIndex<TokenMap<EncodedTerm, EncodedQuad>>>,
+    spatial: Option<MemoryLiteralIndex<BoxMap<EncodedTerm, EncodedQuad>>>,
+    reasoner: Option<Arc<Owl2RlReasoner<LargeSpur>>>,
+    rules: Arc<Vec<Rule>>,
+    rule_changes: Option<RuleChanges<LargeSpur>>,
+}
+
+/// Index of the literal objects by predicate that needs to read the literal values
+///
+/// The full-text index maps the string literals tokens and the spatial index the geometry literals bounding boxes.
+struct MemoryLiteralIndex<M> {
+    strings: Arc<ThreadedRodeo<LargeSpur>>,
+    entries: M,
 }
 
 impl Default for MemoryStore {
@@ -85,10 +114,56 @@ impl Default for MemoryStore {
 impl MemoryStore {
     /// Constructs a new [`MemoryStore`]()
     pub fn new() -> Self {
-        Self {
-            indexes: Arc::new(RwLock::default()),
-            strings: Arc::new(ThreadedRodeo::new()),
+        Self::new_with_options(StoreOptions::default())
+    }
+
+    /// Constructs a new [`MemoryStore`]() maintaining the optional indexes enabled in `options`.
+    ///
+    /// Usage example:
+    /// ```
+    /// use oxigraph::MemoryStore;
+    /// use oxigraph::store::StoreOptions;
+    ///
+    /// let store = MemoryStore::new_with_options(StoreOptions::default().with_range_index());
+    /// assert!(store.is_empty());
+    /// ```
+    pub fn new_with_options(options: StoreOptions) -> Self {
+        let strings = Arc::new(ThreadedRodeo::new());
+        let store = Self {
+            indexes: Arc::new(RwLock::new(MemoryStoreIndexes {
+                range: if options.range_index {
+                    Some(HashMap::default())
+                } else {
+                    None
+                },
+                text: if options.text_index {
+                    Some(MemoryLiteralIndex {
+                        strings: strings.clone(),
+                        entries: HashMap::default(),
+                    })
+                } else {
+                    None
+                },
+                spatial: if options.spatial_index {
+                    Some(MemoryLiteralIndex {
+                        strings: strings.clone(),
+                        entries: HashMap::default(),
+                    })
+                } else {
+                    None
+                },
+                ..MemoryStoreIndexes::default()
+            })),
+            strings,
+            query_cache: Arc::default(),
+        };
+        if options.owl2rl_reasoning {
+            let mut encoder = &store;
+            let reasoner =
+                Owl2RlReasoner::new(|node| encoder.encode_named_node(node)).unwrap_infallible();
+            store.indexes_mut().reasoner = Some(Arc::new(reasoner));
         }
+        store
     }
 
     /// Executes a [SPARQL 1.1 query](https://www.w3.org/TR/sparql11-query/).
@@ -126,7 +201,103 @@ impl MemoryStore {
         query: impl TryInto<Query, Error = impl Into<EvaluationError>>,
         options: QueryOptions,
     ) -> Result<QueryResults, EvaluationError> {
-        evaluate_query(self.clone(), query, options)
+        self.query_cache.evaluate(
+            query.try_into().map_err(|e| e.into())?,
+            options,
+            |query, options| evaluate_query(self.clone(), query, options),
+        )
+    }
+
+    /// Prepares a [SPARQL 1.1 query](https://www.w3.org/TR/sparql11-query/) to be executed many times with different variable bindings.
+    ///
+    /// Usage example:
+    /// ```
+    /// use oxigraph::MemoryStore;
+    /// use oxigraph::model::*;
+    /// use oxigraph::sparql::QueryResults;
+    ///
+    /// let store = MemoryStore::new();
+    /// let ex = NamedNode::new("http://example.com")?;
+    /// let query = store.prepare_query("ASK { ?s ?p ?o }")?;
+    ///
+    /// store.insert(Quad::new(ex.clone(), ex.clone(), ex.clone(), None));
+    /// if let QueryResults::Boolean(result) = query.exec()? {
+    ///     assert!(result);
+    /// }
+    /// # Result::<_,Box<dyn std::error::Error>>::Ok(())
+    /// ```
+    pub fn prepare_query(
+        &self,
+        query: impl TryInto<Query, Error = impl Into<EvaluationError>>,
+    ) -> Result<PreparedQuery, EvaluationError> {
+        self.prepare_query_opt(query, QueryOptions::default())
+    }
+
+    /// Prepares a [SPARQL 1.1 query](https://www.w3.org/TR/sparql11-query/) with some options.
+    pub fn prepare_query_opt(
+        &self,
+        query: impl TryInto<Query, Error = impl Into<EvaluationError>>,
+        options: QueryOptions,
+    ) -> Result<PreparedQuery, EvaluationError> {
+        prepare_query(self.clone(), query, options)
+    }
+
+    /// Sets the maximal number of query results kept in the store query results cache.
+    ///
+    /// The cache is disabled by default, i.e. its capacity is `0`.
+    /// It keeps the fully materialized results of the most recently used queries
+    /// and is shared by all the clones of the store.
+    /// The queries are identified by their normalized serialization, their dataset and their evaluation options.
+    /// All the cached results are dropped after each write to the store.
+    /// The queries using `SERVICE`, the non deterministic functions like `RAND()`, `NOW()` or `BNODE()`
+    /// or a CONSTRUCT template with blank nodes are never cached,
+    /// nor the results with more than 10,000 solutions or triples.
+    /// Use [`QueryOptions::without_query_cache`] to bypass the cache for a given query.
+    ///
+    /// Usage example:
+    /// ```
+    /// use oxigraph::MemoryStore;
+    /// use oxigraph::model::*;
+    /// use oxigraph::sparql::QueryResults;
+    ///
+    /// let store = MemoryStore::new();
+    /// store.set_query_cache_capacity(100);
+    /// let ex = NamedNode::new("http://example.com")?;
+    ///
+    /// // The results are cached
+    /// assert!(matches!(store.query("ASK { ?s ?p ?o }")?, QueryResults::Boolean(false)));
+    /// assert!(matches!(store.query("ASK { ?s ?p ?o }")?, QueryResults::Boolean(false)));
+    ///
+    /// // The cache is invalidated by writes
+    /// store.insert(Quad::new(ex.clone(), ex.clone(), ex.clone(), None));
+    /// assert!(matches!(store.query("ASK { ?s ?p ?o }")?, QueryResults::Boolean(true)));
+    /// # Result::<_,Box<dyn std::error::Error>>::Ok(())
+    /// ```
+    pub fn set_query_cache_capacity(&self, capacity: usize) {
+        self.query_cache.set_capacity(capacity)
+    }
+
+    /// Returns a [SPARQL 1.1 Service Description](https://www.w3.org/TR/sparql11-service-description/) of the store.
+    ///
+    /// It describes the SPARQL features and the extension functions supported by the store
+    /// and the named graphs of its dataset.
+    /// The number of quads might be added with [`ServiceDescription::with_triples_count`].
+    ///
+    /// Usage example:
+    /// ```
+    /// use oxigraph::MemoryStore;
+    /// use oxigraph::model::*;
+    ///
+    /// let store = MemoryStore::new();
+    /// let ex = NamedNode::new("http://example.com")?;
+    /// store.insert(Quad::new(ex.clone(), ex.clone(), ex.clone(), ex.clone()));
+    ///
+    /// let description = store.service_description();
+    /// assert!(description.triples().iter().any(|t| t.object == ex.clone().into()));
+    /// # Result::<_,Box<dyn std::error::Error>>::Ok(())
+    /// ```
+    pub fn service_description(&self) -> ServiceDescription {
+        ServiceDescription::default().with_dataset(self.named_graphs())
     }
 
     /// Retrieves quads with a filter on each quad component
@@ -150,7 +321,7 @@ impl MemoryStore {
     /// ```
     pub fn quads_for_pattern(
         &self,
-        subject: Option<NamedOrBlankNodeRef<'_>>,
+        subject: Option<SubjectRef<'_>>,
         predicate: Option<NamedNodeRef<'_>>,
         object: Option<TermRef<'_>>,
         graph_name: Option<GraphNameRef<'_>>,
@@ -245,12 +416,14 @@ impl MemoryStore {
         update: impl TryInto<Update, Error = impl Into<EvaluationError>>,
         options: UpdateOptions,
     ) -> Result<(), EvaluationError> {
-        evaluate_update(
+        let result = evaluate_update(
             self.clone(),
             &mut &*self,
             update.try_into().map_err(|e| e.into())?,
             options,
-        )
+        );
+        self.refresh_rules();
+        result
     }
 
     /// Executes an ACID transaction.
@@ -284,9 +457,95 @@ impl MemoryStore {
     ) -> Result<(), E> {
         let mut transaction = MemoryTransaction { ops: Vec::new() };
         f(&mut transaction)?;
+        self.apply_transaction(transaction, self.indexes_mut());
+        Ok(())
+    }
+
+    /// Executes an ACID transaction that is only applied if the graphs it modifies conform to SHACL shapes.
+    ///
+    /// Each graph touched by the transaction is validated with its content after the transaction.
+    /// The transaction is rollbacked with a [`ShaclError::NotConforming`] error containing the validation report if one of them does not conform.
+    ///
+    /// Usage example:
+    /// ```
+    /// use oxigraph::MemoryStore;
+    /// use oxigraph::model::*;
+    /// use oxigraph::shacl::{ShaclError, ShaclValidator};
+    ///
+    /// let ex = NamedNode::new("http://example.com/s")?;
+    /// let p = NamedNode::new("http://example.com/p")?;
+    /// let sh_target_node = NamedNode::new("http://www.w3.org/ns/shacl#targetNode")?;
+    /// let sh_max_count = NamedNode::new("http://www.w3.org/ns/shacl#maxCount")?;
+    /// let sh_property = NamedNode::new("http://www.w3.org/ns/shacl#property")?;
+    /// let sh_path = NamedNode::new("http://www.w3.org/ns/shacl#path")?;
+    /// let shape = NamedNode::new("http://example.com/shape")?;
+    /// let property = BlankNode::default();
+    /// let validator = ShaclValidator::new(vec![
+    ///     Triple::new(shape.clone(), sh_target_node, ex.clone()),
+    ///     Triple::new(shape, sh_property, property.clone()),
+    ///     Triple::new(property.clone(), sh_path, p.clone()),
+    ///     Triple::new(property, sh_max_count, Literal::from(1)),
+    /// ])?;
+    ///
+    /// let store = MemoryStore::new();
+    /// let result = store.transaction_with_validation(&validator, |transaction| {
+    ///     transaction.insert(Quad::new(ex.clone(), p.clone(), Literal::from(1), None));
+    ///     transaction.insert(Quad::new(ex.clone(), p.clone(), Literal::from(2), None));
+    ///     Ok(()) as Result<(), ShaclError>
+    /// });
+    /// assert!(matches!(result, Err(ShaclError::NotConforming(_))));
+    /// assert!(store.is_empty());
+    /// # Result::<_,Box<dyn std::error::Error>>::Ok(())
+    /// ```
+    pub fn transaction_with_validation<E: From<ShaclError>>(
+        &self,
+        validator: &ShaclValidator,
+        f: impl FnOnce(&mut MemoryTransaction) -> Result<(), E>,
+    ) -> Result<(), E> {
+        let mut transaction = MemoryTransaction { ops: Vec::new() };
+        f(&mut transaction)?;
+
+        let mut this = self;
+        let indexes = self.indexes_mut();
+        // The state of the quads written by the transaction once it is applied
+        let mut written = HashMap::new();
+        let mut graph_names = Vec::new();
+        for op in &transaction.ops {
+            let (quad, inserted) = match op {
+                TransactionOp::Insert(quad) => (quad, true),
+                TransactionOp::Delete(quad) => (quad, false),
+            };
+            let quad = this.encode_quad(quad.as_ref()).unwrap_infallible();
+            if !graph_names.contains(&quad.graph_name) {
+                graph_names.push(quad.graph_name);
+            }
+            written.insert(quad, inserted);
+        }
+        for graph_name in graph_names {
+            let kept = indexes
+                .graph_quads(graph_name)
+                .filter(|quad| written.get(quad) != Some(&false));
+            let added = written
+                .iter()
+                .filter(|(quad, inserted)| {
+                    **inserted && quad.graph_name == graph_name && !indexes.contains(quad)
+                })
+                .map(|(quad, _)| *quad);
+            validator.check(
+                kept.chain(added)
+                    .map(|quad| Triple::from(self.decode_quad(&quad).unwrap())),
+            )?;
+        }
+        self.apply_transaction(transaction, indexes);
+        Ok(())
+    }
 
+    fn apply_transaction(
+        &self,
+        transaction: MemoryTransaction,
+        mut indexes: RwLockWriteGuard<'_, MemoryStoreIndexes>,
+    ) {
         let mut this = self;
-        let mut indexes = self.indexes_mut();
         for op in transaction.ops {
             match op {
                 TransactionOp::Insert(quad) => {
@@ -299,7 +558,8 @@ impl MemoryStore {
                 }
             }
         }
-        Ok(())
+        drop(indexes);
+        self.refresh_rules();
     }
 
     /// Loads a graph file (i.e. triples) into the store.
@@ -326,17 +586,124 @@ impl MemoryStore {
     /// If the parsing fails in the middle of the file, the triples read before stay in the store.
     /// Use a (memory greedy) [transaction](MemoryStore::transaction()) if you do not want that.
     ///
+    /// A [`GraphParser`](crate::io::GraphParser) could be given instead of a format, for example to skip the invalid statements
+    /// using its [lenient mode](crate::io::GraphParser::lenient()).
+    /// The base IRI given to this method overrides the one set on the parser.
+    ///
+    /// N-Triples files are parsed [in parallel](crate::io::GraphParser::read_triples_in_parallel()).
+    ///
     /// Errors related to parameter validation like the base IRI use the [`InvalidInput`](std::io::ErrorKind::InvalidInput) error kind.
     /// Errors related to a bad syntax in the loaded file use the [`InvalidData`](std::io::ErrorKind::InvalidData) or [`UnexpectedEof`](std::io::ErrorKind::UnexpectedEof) error kinds.
     pub fn load_graph<'a>(
         &self,
         reader: impl BufRead,
-        format: GraphFormat,
+        format: impl Into<GraphParser>,
         to_graph_name: impl Into<GraphNameRef<'a>>,
         base_iri: Option<&str>,
     ) -> Result<(), io::Error> {
+        self.load_graph_with_prefixes(reader, format, to_graph_name, base_iri)?;
+        Ok(())
+    }
+
+    /// Loads a graph file (i.e. triples) into the store like [`load_graph`](MemoryStore::load_graph())
+    /// and returns the prefixes and the base IRI declared in the file.
+    ///
+    /// Usage example:
+    /// ```
+    /// use oxigraph::MemoryStore;
+    /// use oxigraph::io::{GraphFormat, GraphSerializer};
+    /// use oxigraph::model::*;
+    ///
+    /// let store = MemoryStore::new();
+    /// let file = b"@prefix ex: <http://example.com/> .\nex:s ex:p ex:o .";
+    /// let prefixes = store.load_graph_with_prefixes(file.as_ref(), GraphFormat::Turtle, &GraphName::DefaultGraph, None)?;
+    /// assert_eq!(prefixes.get("ex"), Some("http://example.com/"));
+    ///
+    /// // the prefixes could be used to write back the file
+    /// let mut buffer = Vec::new();
+    /// store.dump_graph(&mut buffer, GraphSerializer::from_format(GraphFormat::Turtle).with_prefix_map(&prefixes), &GraphName::DefaultGraph)?;
+    /// assert_eq!(buffer, b"@prefix ex: <http://example.com/> .\n\nex:s ex:p ex:o .\n");
+    /// # Result::<_,Box<dyn std::error::Error>>::Ok(())
+    /// ```
+    pub fn load_graph_with_prefixes<'a>(
+        &self,
+        reader: impl BufRead,
+        format: impl Into<GraphParser>,
+        to_graph_name: impl Into<GraphNameRef<'a>>,
+        base_iri: Option<&str>,
+    ) -> Result<PrefixMap, io::Error> {
         let mut store = self;
-        load_graph(&mut store, reader, format, to_graph_name.into(), base_iri)?;
+        let result = load_graph(
+            &mut store,
+            reader,
+            format.into(),
+            to_graph_name.into(),
+            base_iri,
+        );
+        self.refresh_rules();
+        Ok(result?)
+    }
+
+    /// Loads a graph file (i.e. triples) into the store if the resulting graph conforms to SHACL shapes.
+    ///
+    /// The existing content of the target graph and the loaded triples are validated together.
+    /// Nothing is written if the validation fails.
+    ///
+    /// Usage example:
+    /// ```
+    /// use oxigraph::MemoryStore;
+    /// use oxigraph::io::GraphFormat;
+    /// use oxigraph::model::*;
+    /// use oxigraph::shacl::ShaclValidator;
+    ///
+    /// let shapes = MemoryStore::new();
+    /// shapes.load_graph(br#"
+    ///     @prefix sh: <http://www.w3.org/ns/shacl#> .
+    ///     @prefix xsd: <http://www.w3.org/2001/XMLSchema#> .
+    ///     [] sh:targetSubjectsOf <http://example.com/age> ;
+    ///         sh:property [ sh:path <http://example.com/age> ; sh:datatype xsd:integer ] .
+    /// "#.as_ref(), GraphFormat::Turtle, &GraphName::DefaultGraph, None)?;
+    /// let validator = ShaclValidator::new(shapes.iter().map(Triple::from))?;
+    ///
+    /// let store = MemoryStore::new();
+    /// let file = b"<http://example.com/s> <http://example.com/age> \"old\" .";
+    /// assert!(store.load_graph_with_validation(file.as_ref(), GraphFormat::NTriples, &GraphName::DefaultGraph, None, &validator).is_err());
+    /// assert!(store.is_empty());
+    /// # Result::<_,Box<dyn std::error::Error>>::Ok(())
+    /// ```
+    ///
+    /// Errors related to parameter validation like the base IRI use the [`InvalidInput`](std::io::ErrorKind::InvalidInput) error kind.
+    /// Errors related to a bad syntax in the loaded file or to a non conforming graph use the [`InvalidData`](std::io::ErrorKind::InvalidData) or [`UnexpectedEof`](std::io::ErrorKind::UnexpectedEof) error kinds.
+    pub fn load_graph_with_validation<'a>(
+        &self,
+        reader: impl BufRead,
+        format: impl Into<GraphParser>,
+        to_graph_name: impl Into<GraphNameRef<'a>>,
+        base_iri: Option<&str>,
+        validator: &ShaclValidator,
+    ) -> Result<(), io::Error> {
+        let to_graph_name = to_graph_name.into();
+        let mut this = self;
+        let indexes = self.indexes_mut();
+        let encoded_graph_name = this.encode_graph_name(to_graph_name).unwrap_infallible();
+        let triples = read_validated_graph(
+            indexes
+                .graph_quads(encoded_graph_name)
+                .map(|quad| Triple::from(self.decode_quad(&quad).unwrap())),
+            reader,
+            format.into(),
+            base_iri,
+            validator,
+        )?;
+        self.apply_transaction(
+            MemoryTransaction {
+                ops: triples
+                    .into_iter()
+                    .map(|t| TransactionOp::Insert(t.in_graph(to_graph_name.into_owned())))
+                    .collect(),
+            },
+            indexes,
+        );
         Ok(())
     }
 
@@ -364,16 +731,96 @@ impl MemoryStore {
     /// If the parsing fails in the middle of the file, the quads read before stay in the store.
     /// Use a (memory greedy) [transaction](MemoryStore::transaction()) if you do not want that.
     ///
+    /// A [`DatasetParser`](crate::io::DatasetParser) could be given instead of a format, for example to skip the invalid statements
+    /// using its [lenient mode](crate::io::DatasetParser::lenient()).
+    /// The base IRI given to this method overrides the one set on the parser.
+    ///
+    /// N-Quads files are parsed [in parallel](crate::io::DatasetParser::read_quads_in_parallel()).
+    ///
     /// Errors related to parameter validation like the base IRI use the [`InvalidInput`](std::io::ErrorKind::InvalidInput) error kind.
     /// Errors related to a bad syntax in the loaded file use the [`InvalidData`](std::io::ErrorKind::InvalidData) or [`UnexpectedEof`](std::io::ErrorKind::UnexpectedEof) error kinds.
     pub fn load_dataset(
         &self,
         reader: impl BufRead,
-        format: DatasetFormat,
+        format: impl Into<DatasetParser>,
+        base_iri: Option<&str>,
+    ) -> Result<(), io::Error> {
+        let mut store = self;
+        let result = load_dataset(&mut store, reader, format.into(), base_iri);
+        self.refresh_rules();
+        result?;
+        Ok(())
+    }
+
+    /// Loads a RDF file into the store finding its format and its compression from its name or its content.
+    ///
+    /// The file format is looked for from the file extension (`.ttl`, `.nq.gz`...)
+    /// and, if it is unknown, [guessed from the file content](crate::io::RdfFormat::from_content()).
+    /// If the content has markers specific to a format the extension syntax does not allow,
+    /// like a JSON-LD `@context` in a `.ttl` file, the format guessed from the content is used.
+    /// The gzip, bzip2 and Zstandard compressed files are decompressed.
+    /// The triples of the graph formats are loaded into the default graph.
+    ///
+    /// Usage example:
+    /// ```
+    /// use oxigraph::MemoryStore;
+    /// use oxigraph::model::*;
+    /// use std::fs;
+    ///
+    /// let file = std::env::temp_dir().join("oxigraph_load_from_path_example.data");
+    /// fs::write(&file, "<http://example.com> <http://example.com> <http://example.com> <http://example.com> .")?;
+    ///
+    /// let store = MemoryStore::new();
+    /// store.load_from_path(&file, None)?;
+    /// # fs::remove_file(&file)?;
+    ///
+    /// let ex = NamedNodeRef::new("http://example.com")?;
+    /// assert!(store.contains(QuadRef::new(ex, ex, ex, ex)));
+    /// # Result::<_,Box<dyn std::error::Error>>::Ok(())
+    /// ```
+    ///
+    /// Errors related to parameter validation like the base IRI or an undetectable format use the [`InvalidInput`](std::io::ErrorKind::InvalidInput) error kind.
+    /// Errors related to a bad syntax in the loaded file use the [`InvalidData`](std::io::ErrorKind::InvalidData) or [`UnexpectedEof`](std::io::ErrorKind::UnexpectedEof) error kinds.
+    pub fn load_from_path(
+        &self,
+        path: impl AsRef<Path>,
         base_iri: Option<&str>,
+    ) -> Result<(), io::Error> {
+        let (reader, format) = open_rdf_file(path.as_ref())?;
+        match format {
+            RdfFormat::Graph(format) => {
+                self.load_graph(reader, format, GraphNameRef::DefaultGraph, base_iri)
+            }
+            RdfFormat::Dataset(format) => self.load_dataset(reader, format, base_iri),
+        }
+    }
+
+    /// Loads an [HDT](https://www.rdfhdt.org/) file into the store.
+    ///
+    /// The file is mapped into memory while its triples are loaded.
+    /// The terms are read directly from the HDT dictionary, without going through a textual RDF syntax.
+    /// Use [`HdtStore`](super::hdt::HdtStore) to query an HDT file without loading it.
+    ///
+    /// Usage example:
+    /// ```no_run
+    /// use oxigraph::MemoryStore;
+    /// use oxigraph::model::GraphNameRef;
+    ///
+    /// let store = MemoryStore::new();
+    /// store.load_hdt("example.hdt", GraphNameRef::DefaultGraph)?;
+    /// # std::io::Result::Ok(())
+    /// ```
+    ///
+    /// Errors related to an invalid or unsupported HDT file use the [`InvalidData`](std::io::ErrorKind::InvalidData) or [`UnexpectedEof`](std::io::ErrorKind::UnexpectedEof) error kinds.
+    pub fn load_hdt<'a>(
+        &self,
+        path: impl AsRef<Path>,
+        to_graph_name: impl Into<GraphNameRef<'a>>,
     ) -> Result<(), io::Error> {
         let mut store = self;
-        load_dataset(&mut store, reader, format, base_iri)?;
+        let result = load_hdt(&mut store, path.as_ref(), to_graph_name.into());
+        self.refresh_rules();
+        result?;
         Ok(())
     }
 
@@ -383,6 +830,7 @@ impl MemoryStore {
         let mut this = self;
         let quad = this.encode_quad(quad.into().as_ref()).unwrap_infallible();
         this.insert_encoded(&quad).unwrap_infallible();
+        self.refresh_rules();
     }
 
     /// Removes a quad from this store.
@@ -390,21 +838,24 @@ impl MemoryStore {
         if let Some(quad) = self.get_encoded_quad(quad.into()).unwrap_infallible() {
             let mut this = self;
             this.remove_encoded(&quad).unwrap_infallible();
+            self.refresh_rules();
         }
     }
 
     /// Returns if the current dataset is [isomorphic](https://www.w3.org/TR/rdf11-concepts/#dfn-dataset-isomorphism) with another one.
     ///
-    /// It is implemented using the canonicalization approach presented in
-    /// [Canonical Forms for Isomorphic and Equivalent RDF Graphs: Algorithms for Leaning and Labelling Blank Nodes, Aidan Hogan, 2017](http://aidanhogan.com/docs/rdf-canonicalisation.pdf)
+    /// It relies on [`are_isomorphic`] and its [RDFC-1.0](https://www.w3.org/TR/rdf-canon/) canonicalization.
     ///
-    /// Warning: This implementation worst-case complexity is in O(b!) with b the number of blank nodes in the input graphs.
+    /// Warning: This implementation worst-case complexity is exponential in the number of blank nodes in the input graphs.
     pub fn is_isomorphic(&self, other: &Self) -> bool {
-        iso_canonicalize(self) == iso_canonicalize(other)
+        are_isomorphic(self.iter(), other.iter())
     }
 
     /// Dumps a store graph into a file.
     ///
+    /// A [`GraphSerializer`](crate::io::GraphSerializer) could be given instead of the format to configure the output,
+    /// for example to write a [pretty](crate::io::GraphSerializer::pretty()) Turtle file with prefixes.
+    ///
     /// Usage example:
     /// ```
     /// use oxigraph::MemoryStore;
@@ -424,19 +875,21 @@ impl MemoryStore {
     pub fn dump_graph<'a>(
         &self,
         writer: impl Write,
-        format: GraphFormat,
+        format: impl Into<GraphSerializer>,
         from_graph_name: impl Into<GraphNameRef<'a>>,
     ) -> Result<(), io::Error> {
         dump_graph(
             self.quads_for_pattern(None, None, None, Some(from_graph_name.into()))
                 .map(|q| Ok(q.into())),
             writer,
-            format,
+            &format.into(),
         )
     }
 
     /// Dumps the store into a file.
     ///
+    /// A [`DatasetSerializer`](crate::io::DatasetSerializer) could be given instead of the format to configure the output.
+    ///
     /// Usage example:
     /// ```
     /// use oxigraph::MemoryStore;
@@ -452,8 +905,35 @@ impl MemoryStore {
     /// assert_eq!(file, buffer.as_slice());
     /// # std::io::Result::Ok(())
     /// ```
-    pub fn dump_dataset(&self, writer: impl Write, format: DatasetFormat) -> Result<(), io::Error> {
-        dump_dataset(self.iter().map(Ok), writer, format)
+    pub fn dump_dataset(
+        &self,
+        writer: impl Write,
+        format: impl Into<DatasetSerializer>,
+    ) -> Result<(), io::Error> {
+        dump_dataset(self.iter().map(Ok), writer, &format.into())
+    }
+
+    /// Dumps the store into a file as [canonical N-Quads](crate::io::write_canonical_n_quads()).
+    ///
+    /// The blank nodes are relabeled using the [RDFC-1.0](https://www.w3.org/TR/rdf-canon/) algorithm
+    /// so two isomorphic stores give the same output.
+    ///
+    /// Usage example:
+    /// ```
+    /// use oxigraph::MemoryStore;
+    /// use oxigraph::model::*;
+    ///
+    /// let ex = NamedNode::new("http://example.com")?;
+    /// let store = MemoryStore::new();
+    /// store.insert(Quad::new(BlankNode::default(), ex.clone(), ex.clone(), None));
+    ///
+    /// let mut buffer = Vec::new();
+    /// store.dump_canonical_dataset(&mut buffer)?;
+    /// assert_eq!(buffer.as_slice(), "_:c14n0 <http://example.com> <http://example.com> .\n".as_bytes());
+    /// # Result::<_,Box<dyn std::error::Error>>::Ok(())
+    /// ```
+    pub fn dump_canonical_dataset(&self, writer: impl Write) -> Result<(), io::Error> {
+        dump_canonical_dataset(self.iter().map(Ok), writer)
     }
 
     /// Returns all the store named graphs
@@ -548,7 +1028,8 @@ impl MemoryStore {
             .unwrap_infallible()
         {
             let mut this = self;
-            this.clear_encoded_graph(graph_name).unwrap_infallible()
+            this.clear_encoded_graph(graph_name).unwrap_infallible();
+            self.refresh_rules();
         }
     }
 
@@ -577,7 +1058,8 @@ impl MemoryStore {
         {
             let mut this = self;
             this.remove_encoded_named_graph(graph_name)
-                .unwrap_infallible()
+                .unwrap_infallible();
+            self.refresh_rules();
         }
     }
 
@@ -599,7 +1081,112 @@ impl MemoryStore {
     /// # Result::<_,Box<dyn std::error::Error>>::Ok(())
     /// ```
     pub fn clear(&self) {
-        self.indexes_mut().clear().unwrap_infallible()
+        self.indexes_mut().clear().unwrap_infallible();
+        self.refresh_rules();
+    }
+
+    /// Adds a rule materializing the results of a SPARQL CONSTRUCT query into a named graph.
+    ///
+    /// The target graph content is replaced by the query results and is kept up to date when the store data changes.
+    /// It should not be modified directly: it is recomputed from the rules targeting it.
+    /// A rule with the same name is replaced.
+    /// The CONSTRUCT template should not contain blank nodes and the query should not use `SERVICE`.
+    ///
+    /// The writes do not return errors so the views are left as they are if the rules do not reach a fixpoint after a write.
+    ///
+    /// Usage example:
+    /// ```
+    /// use oxigraph::MemoryStore;
+    /// use oxigraph::model::*;
+    ///
+    /// let store = MemoryStore::new();
+    /// let view = NamedNode::new("http://example.com/grandparents")?;
+    /// store.add_rule(
+    ///     "grandparents",
+    ///     "PREFIX ex: <http://example.com/>
+    ///      CONSTRUCT { ?a ex:grandparent ?c } WHERE { ?a ex:parent ?b . ?b ex:parent ?c }",
+    ///     view.clone(),
+    /// )?;
+    ///
+    /// let ex = |name| NamedNode::new(format!("http://example.com/{}", name));
+    /// store.insert(Quad::new(ex("a")?, ex("parent")?, ex("b")?, None));
+    /// store.insert(Quad::new(ex("b")?, ex("parent")?, ex("c")?, None));
+    /// let result = Quad::new(ex("a")?, ex("grandparent")?, ex("c")?, view.clone());
+    /// assert!(store.contains(&result));
+    ///
+    /// // The view is updated when the data changes
+    /// store.remove(&Quad::new(ex("b")?, ex("parent")?, ex("c")?, None));
+    /// assert!(!store.contains(&result));
+    /// # Result::<_,Box<dyn std::error::Error>>::Ok(())
+    /// ```
+    pub fn add_rule(
+        &self,
+        name: impl Into<String>,
+        query: impl TryInto<Query, Error = impl Into<EvaluationError>>,
+        target_graph: impl Into<NamedNode>,
+    ) -> Result<(), EvaluationError> {
+        let rule = Rule::new(name, query, target_graph)?;
+        let target_graph = rule.target_graph().into_owned();
+        let (rules, old_target_graph) = {
+            let mut indexes = self.indexes_mut();
+            let mut rules = indexes.rules.as_ref().clone();
+            let old_target_graph = rules
+                .iter()
+                .position(|r| r.name() == rule.name())
+                .map(|i| rules.remove(i).target_graph().into_owned());
+            rules.push(rule);
+            indexes.rules = Arc::new(rules);
+            indexes
+                .rule_changes
+                .get_or_insert_with(RuleChanges::default);
+            (indexes.rules.clone(), old_target_graph)
+        };
+        refresh_view(self, &rules, &target_graph)?;
+        if let Some(old_target_graph) = old_target_graph {
+            refresh_view(self, &rules, &old_target_graph)?;
+        }
+        refresh_views(self, &rules)
+    }
+
+    /// Removes a rule added with [`add_rule`](MemoryStore::add_rule()) and returns if it existed.
+    ///
+    /// The rule results are removed from its target graph.
+    pub fn remove_rule(&self, name: &str) -> bool {
+        let (rules, removed) = {
+            let mut indexes = self.indexes_mut();
+            let mut rules = indexes.rules.as_ref().clone();
+            let removed = rules
+                .iter()
+                .position(|r| r.name() == name)
+                .map(|i| rules.remove(i));
+            if rules.is_empty() {
+                indexes.rule_changes = None;
+            }
+            indexes.rules = Arc::new(rules);
+            (indexes.rules.clone(), removed)
+        };
+        if let Some(removed) = removed {
+            // The view is recomputed from the remaining rules targeting it
+            let _ = refresh_view(self, &rules, &removed.target_graph().into_owned());
+            self.refresh_rules();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Returns the rules added with [`add_rule`](MemoryStore::add_rule()).
+    pub fn rules(&self) -> Vec<Rule> {
+        self.indexes().rules.as_ref().clone()
+    }
+
+    fn refresh_rules(&self) {
+        let rules = self.indexes().rules.clone();
+        if !rules.is_empty() {
+            // The memory store writes can't fail: the views are left as they are if their refresh fails,
+            // i.e. if the rules do not reach a fixpoint
+            let _ = refresh_views(self, &rules);
+        }
     }
 
     #[allow(clippy::expect_used)]
@@ -611,26 +1198,17 @@ impl MemoryStore {
 
     #[allow(clippy::expect_used)]
     fn indexes_mut(&self) -> RwLockWriteGuard<'_, MemoryStoreIndexes> {
-        self.indexes
+        let indexes = self
+            .indexes
             .write()
-            .expect("the Memory store mutex has been poisoned because of a panic")
+            .expect("the Memory store mutex has been poisoned because of a panic");
+        // The queries evaluated concurrently will only read the indexes once the write lock is released
+        self.query_cache.invalidate();
+        indexes
     }
 
     fn contains_encoded(&self, quad: &EncodedQuad) -> bool {
-        let indexes = self.indexes();
-        if quad.graph_name.is_default_graph() {
-            indexes.default_spo.get(&quad.subject).map_or(false, |po| {
-                po.get(&quad.predicate)
-                    .map_or(false, |o| o.contains(&quad.object))
-            })
-        } else {
-            indexes.spog.get(&quad.subject).map_or(false, |pog| {
-                pog.get(&quad.predicate).map_or(false, |og| {
-                    og.get(&quad.object)
-                        .map_or(false, |g| g.contains(&quad.graph_name))
-                })
-            })
-        }
+        self.indexes().contains(quad)
     }
 
     fn encoded_quads_for_pattern_inner(
@@ -1004,6 +1582,63 @@ impl<'a> ReadableEncodedStore for MemoryStore {
         }
     }
 
+    fn encoded_quads_for_predicate_object_range(
+        &self,
+        predicate: EncodedTerm,
+        range: EncodedRange,
+        graph_name: Option<EncodedTerm>,
+    ) -> Option<Box<dyn Iterator<Item = Result<EncodedQuad, Infallible>> + Send>> {
+        let indexes = self.indexes();
+        let quads = option_range_flatten(indexes.range.as_ref()?.get(&predicate), range)
+            .filter(|quad| graph_name.map_or(true, |graph_name| quad.graph_name == graph_name))
+            .map(Ok)
+            .collect::<Vec<_>>();
+        Some(Box::new(quads.into_iter()))
+    }
+
+    fn encoded_quads_for_predicate_object_token(
+        &self,
+        predicate: EncodedTerm,
+        token: &str,
+        graph_name: Option<EncodedTerm>,
+    ) -> Option<Box<dyn Iterator<Item = Result<EncodedQuad, Infallible>> + Send>> {
+        let indexes = self.indexes();
+        let quads = option_set_flatten(
+            indexes
+                .text
+                .as_ref()?
+                .entries
+                .get(&predicate)
+                .and_then(|quads_by_token| quads_by_token.get(token)),
+        )
+        .filter(|quad| graph_name.map_or(true, |graph_name| quad.graph_name == graph_name))
+        .map(Ok)
+        .collect::<Vec<_>>();
+        Some(Box::new(quads.into_iter()))
+    }
+
+    fn encoded_quads_for_predicate_object_box(
+        &self,
+        predicate: EncodedTerm,
+        search_box: SearchBox,
+        graph_name: Option<EncodedTerm>,
+    ) -> Option<Box<dyn Iterator<Item = Result<EncodedQuad, Infallible>> + Send>> {
+        let indexes = self.indexes();
+        let quads = indexes
+            .spatial
+            .as_ref()?
+            .entries
+            .get(&predicate)
+            .map(|tree| tree.search(&search_box.envelope()))
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|quad| graph_name.map_or(true, |graph_name| quad.graph_name == graph_name))
+            .copied()
+            .map(Ok)
+            .collect::<Vec<_>>();
+        Some(Box::new(quads.into_iter()))
+    }
+
     fn encoded_named_graphs(&self) -> Self::GraphsIter {
         EncodedGraphsIter {
             iter: self
@@ -1047,6 +1682,29 @@ impl<'a> WritableEncodedStore for &'a MemoryStore {
     }
 }
 
+impl RuleStore for MemoryStore {
+    fn take_rule_changes(&self) -> RuleChanges<LargeSpur> {
+        self.indexes_mut()
+            .rule_changes
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
+
+    fn write_view_changes(&self, inserted: &[Quad], removed: &[Quad]) -> Result<(), Infallible> {
+        let mut this = self;
+        for quad in removed {
+            let quad = this.encode_quad(quad.as_ref())?;
+            this.remove_encoded(&quad)?;
+        }
+        for quad in inserted {
+            let quad = this.encode_quad(quad.as_ref())?;
+            this.insert_encoded(&quad)?;
+        }
+        Ok(())
+    }
+}
+
 impl StrEncodingAware for MemoryStoreIndexes {
     type Error = Infallible;
     type StrId = LargeSpur;
@@ -1054,6 +1712,237 @@ impl StrEncodingAware for MemoryStoreIndexes {
 
 impl WritableEncodedStore for MemoryStoreIndexes {
     fn insert_encoded(&mut self, quad: &EncodedQuad) -> Result<(), Infallible> {
+        if let Some(reasoner) = self.reasoner.clone() {
+            if quad.graph_name.is_default_graph() && !self.contains(quad) {
+                self.insert_quad(quad);
+                return reasoner.on_insert(self, [quad.subject, quad.predicate, quad.object]);
+            }
+        }
+        self.insert_quad(quad);
+        Ok(())
+    }
+
+    fn remove_encoded(&mut self, quad: &EncodedQuad) -> Result<(), Infallible> {
+        if let Some(reasoner) = self.reasoner.clone() {
+            if quad.graph_name.is_default_graph() && self.contains(quad) {
+                self.remove_quad(quad);
+                return reasoner.on_remove(self, [quad.subject, quad.predicate, quad.object]);
+            }
+        }
+        self.remove_quad(quad);
+        Ok(())
+    }
+
+    fn insert_encoded_named_graph(&mut self, graph_name: EncodedTerm) -> Result<(), Infallible> {
+        self.gspo.entry(graph_name).or_default();
+        self.gpos.entry(graph_name).or_default();
+        self.gosp.entry(graph_name).or_default();
+        Ok(())
+    }
+
+    fn clear_encoded_graph(&mut self, graph_name: EncodedTerm) -> Result<(), Infallible> {
+        if let Some(rule_changes) = &mut self.rule_changes {
+            rule_changes.record_all();
+        }
+        if graph_name.is_default_graph() {
+            self.default_spo.clear();
+            self.default_pos.clear();
+            self.default_osp.clear();
+            if let Some(reasoner) = self.reasoner.clone() {
+                // All the inferred triples are derived from the default graph
+                self.clear_encoded_graph(reasoner.inferred_graph())?;
+            }
+        } else {
+            if let Some(spo) = self.gspo.get(&graph_name) {
+                for (s, po) in spo {
+                    for (p, os) in po {
+                        for o in os {
+                            remove_from_quad_map(&mut self.spog, s, p, o, &graph_name);
+                            remove_from_quad_map(&mut self.posg, p, o, s, &graph_name);
+                            remove_from_quad_map(&mut self.ospg, o, s, p, &graph_name);
+                        }
+                    }
+                }
+            }
+            if let Some(spo) = self.gspo.get_mut(&graph_name) {
+                spo.clear();
+            }
+            if let Some(pos) = self.gpos.get_mut(&graph_name) {
+                pos.clear();
+            }
+            if let Some(osp) = self.gosp.get_mut(&graph_name) {
+                osp.clear();
+            }
+        }
+        self.remove_graph_from_value_indexes(graph_name);
+        Ok(())
+    }
+
+    fn remove_encoded_named_graph(&mut self, graph_name: EncodedTerm) -> Result<(), Infallible> {
+        if let Some(rule_changes) = &mut self.rule_changes {
+            rule_changes.record_all();
+        }
+        if let Some(spo) = self.gspo.get(&graph_name) {
+            for (s, po) in spo {
+                for (p, os) in po {
+                    for o in os {
+                        remove_from_quad_map(&mut self.spog, s, p, o, &graph_name);
+                        remove_from_quad_map(&mut self.posg, p, o, s, &graph_name);
+                        remove_from_quad_map(&mut self.ospg, o, s, p, &graph_name);
+                    }
+                }
+            }
+        }
+        self.gspo.remove(&graph_name);
+        self.gpos.remove(&graph_name);
+        self.gosp.remove(&graph_name);
+        self.remove_graph_from_value_indexes(graph_name);
+        Ok(())
+    }
+
+    fn clear(&mut self) -> Result<(), Infallible> {
+        *self = MemoryStoreIndexes {
+            range: self.range.as_ref().map(|_| HashMap::default()),
+            text: self.text.take().map(MemoryLiteralIndex::cleared),
+            spatial: self.spatial.take().map(MemoryLiteralIndex::cleared),
+            reasoner: self.reasoner.take(),
+            rules: self.rules.clone(),
+            rule_changes: self.rule_changes.take().map(|mut changes| {
+                changes.record_all();
+                changes
+            }),
+            ..MemoryStoreIndexes::default()
+        };
+        Ok(())
+    }
+}
+
+impl InferenceStore for MemoryStoreIndexes {
+    fn closure_triples_for_pattern(
+        &self,
+        subject: Option<EncodedTerm>,
+        predicate: Option<EncodedTerm>,
+        object: Option<EncodedTerm>,
+    ) -> Result<Vec<EncodedTriple<LargeSpur>>, Infallible> {
+        let mut triples = triple_maps_pattern(
+            &self.default_spo,
+            &self.default_pos,
+            &self.default_osp,
+            subject,
+            predicate,
+            object,
+        );
+        if let Some(reasoner) = &self.reasoner {
+            let inferred_graph = reasoner.inferred_graph();
+            if let (Some(spo), Some(pos), Some(osp)) = (
+                self.gspo.get(&inferred_graph),
+                self.gpos.get(&inferred_graph),
+                self.gosp.get(&inferred_graph),
+            ) {
+                triples.extend(triple_maps_pattern(
+                    spo, pos, osp, subject, predicate, object,
+                ));
+            }
+        }
+        Ok(triples)
+    }
+
+    fn contains_asserted(&self, triple: &EncodedTriple<LargeSpur>) -> Result<bool, Infallible> {
+        let [subject, predicate, object] = *triple;
+        Ok(self.contains(&EncodedQuad::new(
+            subject,
+            predicate,
+            object,
+            EncodedTerm::DefaultGraph,
+        )))
+    }
+
+    fn contains_inferred(&self, triple: &EncodedTriple<LargeSpur>) -> Result<bool, Infallible> {
+        Ok(self.reasoner.as_ref().map_or(false, |reasoner| {
+            let [subject, predicate, object] = *triple;
+            self.contains(&EncodedQuad::new(
+                subject,
+                predicate,
+                object,
+                reasoner.inferred_graph(),
+            ))
+        }))
+    }
+
+    fn insert_inferred(&mut self, triple: &EncodedTriple<LargeSpur>) -> Result<(), Infallible> {
+        if let Some(reasoner) = &self.reasoner {
+            let [subject, predicate, object] = *triple;
+            let quad = EncodedQuad::new(subject, predicate, object, reasoner.inferred_graph());
+            self.insert_quad(&quad);
+        }
+        Ok(())
+    }
+
+    fn remove_inferred(&mut self, triple: &EncodedTriple<LargeSpur>) -> Result<(), Infallible> {
+        if let Some(reasoner) = &self.reasoner {
+            let [subject, predicate, object] = *triple;
+            let quad = EncodedQuad::new(subject, predicate, object, reasoner.inferred_graph());
+            self.remove_quad(&quad);
+        }
+        Ok(())
+    }
+}
+
+impl<M: Default> MemoryLiteralIndex<M> {
+    fn cleared(self) -> Self {
+        Self {
+            strings: self.strings,
+            entries: M::default(),
+        }
+    }
+}
+
+impl<M> StrEncodingAware for MemoryLiteralIndex<M> {
+    type Error = Infallible;
+    type StrId = LargeSpur;
+}
+
+impl<M> StrLookup for MemoryLiteralIndex<M> {
+    fn get_str(&self, id: LargeSpur) -> Result<Option<String>, Infallible> {
+        Ok(self.strings.try_resolve(&id).map(|e| e.to_owned()))
+    }
+
+    fn get_str_id(&self, value: &str) -> Result<Option<LargeSpur>, Infallible> {
+        Ok(self.strings.get(value))
+    }
+}
+
+impl MemoryStoreIndexes {
+    /// Returns the quads of a graph without taking the store lock again
+    fn graph_quads(&self, graph_name: EncodedTerm) -> impl Iterator<Item = EncodedQuad> + '_ {
+        option_triple_map_flatten(if graph_name.is_default_graph() {
+            Some(&self.default_spo)
+        } else {
+            self.gspo.get(&graph_name)
+        })
+        .map(move |(s, p, o)| EncodedQuad::new(s, p, o, graph_name))
+    }
+
+    fn contains(&self, quad: &EncodedQuad) -> bool {
+        if quad.graph_name.is_default_graph() {
+            self.default_spo.get(&quad.subject).map_or(false, |po| {
+                po.get(&quad.predicate)
+                    .map_or(false, |o| o.contains(&quad.object))
+            })
+        } else {
+            self.spog.get(&quad.subject).map_or(false, |pog| {
+                pog.get(&quad.predicate).map_or(false, |og| {
+                    og.get(&quad.object)
+                        .map_or(false, |g| g.contains(&quad.graph_name))
+                })
+            })
+        }
+    }
+
+    fn insert_quad(&mut self, quad: &EncodedQuad) {
+        if let Some(rule_changes) = &mut self.rule_changes {
+            rule_changes.record(quad, true);
+        }
         if quad.graph_name.is_default_graph() {
             insert_into_triple_map(
                 &mut self.default_spo,
@@ -1117,10 +2006,41 @@ impl WritableEncodedStore for MemoryStoreIndexes {
                 quad.graph_name,
             );
         }
-        Ok(())
+        if let Some(range) = &mut self.range {
+            if let Some(key) = RangeKey::new(quad.object) {
+                range
+                    .entry(quad.predicate)
+                    .or_default()
+                    .entry(key)
+                    .or_default()
+                    .insert(*quad);
+            }
+        }
+        if let Some(text) = &mut self.text {
+            for token in encoded_term_tokens(&*text, quad.object).unwrap_infallible() {
+                text.entries
+                    .entry(quad.predicate)
+                    .or_default()
+                    .entry(token)
+                    .or_default()
+                    .insert(*quad);
+            }
+        }
+        if let Some(spatial) = &mut self.spatial {
+            if let Some(envelope) = encoded_term_envelope(&*spatial, quad.object) {
+                spatial
+                    .entries
+                    .entry(quad.predicate)
+                    .or_default()
+                    .insert(envelope, *quad);
+            }
+        }
     }
 
-    fn remove_encoded(&mut self, quad: &EncodedQuad) -> Result<(), Infallible> {
+    fn remove_quad(&mut self, quad: &EncodedQuad) {
+        if let Some(rule_changes) = &mut self.rule_changes {
+            rule_changes.record(quad, false);
+        }
         if quad.graph_name.is_default_graph() {
             remove_from_triple_map(
                 &mut self.default_spo,
@@ -1172,67 +2092,81 @@ impl WritableEncodedStore for MemoryStoreIndexes {
                 &quad.graph_name,
             );
         }
-        Ok(())
-    }
-
-    fn insert_encoded_named_graph(&mut self, graph_name: EncodedTerm) -> Result<(), Infallible> {
-        self.gspo.entry(graph_name).or_default();
-        self.gpos.entry(graph_name).or_default();
-        self.gosp.entry(graph_name).or_default();
-        Ok(())
-    }
-
-    fn clear_encoded_graph(&mut self, graph_name: EncodedTerm) -> Result<(), Infallible> {
-        if graph_name.is_default_graph() {
-            self.default_spo.clear();
-            self.default_pos.clear();
-            self.default_osp.clear();
-        } else {
-            if let Some(spo) = self.gspo.get(&graph_name) {
-                for (s, po) in spo {
-                    for (p, os) in po {
-                        for o in os {
-                            remove_from_quad_map(&mut self.spog, s, p, o, &graph_name);
-                            remove_from_quad_map(&mut self.posg, p, o, s, &graph_name);
-                            remove_from_quad_map(&mut self.ospg, o, s, p, &graph_name);
-                        }
-                    }
-                }
+        if let Some(range) = &mut self.range {
+            if let Some(key) = RangeKey::new(quad.object) {
+                remove_from_sorted_map(range, &quad.predicate, &key, quad);
             }
-            if let Some(spo) = self.gspo.get_mut(&graph_name) {
-                spo.clear();
-            }
-            if let Some(pos) = self.gpos.get_mut(&graph_name) {
-                pos.clear();
+        }
+        if let Some(text) = &mut self.text {
+            for token in encoded_term_tokens(&*text, quad.object).unwrap_infallible() {
+                remove_from_sorted_map(&mut text.entries, &quad.predicate, &token, quad);
             }
-            if let Some(osp) = self.gosp.get_mut(&graph_name) {
-                osp.clear();
+        }
+        if let Some(spatial) = &mut self.spatial {
+            if let Some(envelope) = encoded_term_envelope(&*spatial, quad.object) {
+                if let Some(tree) = spatial.entries.get_mut(&quad.predicate) {
+                    tree.remove(&envelope, quad);
+                    if tree.is_empty() {
+                        spatial.entries.remove(&quad.predicate);
+                    }
+                }
             }
         }
-        Ok(())
     }
 
-    fn remove_encoded_named_graph(&mut self, graph_name: EncodedTerm) -> Result<(), Infallible> {
-        if let Some(spo) = self.gspo.get(&graph_name) {
-            for (s, po) in spo {
-                for (p, os) in po {
-                    for o in os {
-                        remove_from_quad_map(&mut self.spog, s, p, o, &graph_name);
-                        remove_from_quad_map(&mut self.posg, p, o, s, &graph_name);
-                        remove_from_quad_map(&mut self.ospg, o, s, p, &graph_name);
-                    }
-                }
+    fn remove_graph_from_value_indexes(&mut self, graph_name: EncodedTerm) {
+        if let Some(range) = &mut self.range {
+            remove_graph_from_sorted_map(range, graph_name);
+        }
+        if let Some(text) = &mut self.text {
+            remove_graph_from_sorted_map(&mut text.entries, graph_name);
+        }
+        if let Some(spatial) = &mut self.spatial {
+            for tree in spatial.entries.values_mut() {
+                tree.retain(|quad| quad.graph_name != graph_name);
             }
+            spatial.entries.retain(|_, tree| !tree.is_empty());
         }
-        self.gspo.remove(&graph_name);
-        self.gpos.remove(&graph_name);
-        self.gosp.remove(&graph_name);
-        Ok(())
     }
+}
 
-    fn clear(&mut self) -> Result<(), Infallible> {
-        *self = MemoryStoreIndexes::default();
-        Ok(())
+fn encoded_term_envelope(
+    strings: &impl StrLookup<StrId = LargeSpur, Error = Infallible>,
+    term: EncodedTerm,
+) -> Option<Envelope> {
+    encoded_term_geometry(strings, term)
+        .unwrap_infallible()?
+        .envelope()
+}
+
+/// Returns the triples matching a pattern using the three orderings of a graph
+fn triple_maps_pattern<T: Copy + Eq + Hash>(
+    spo: &TripleMap<T>,
+    pos: &TripleMap<T>,
+    osp: &TripleMap<T>,
+    subject: Option<T>,
+    predicate: Option<T>,
+    object: Option<T>,
+) -> Vec<[T; 3]> {
+    if let Some(subject) = subject {
+        option_pair_map_flatten(spo.get(&subject))
+            .filter(|(p, o)| {
+                predicate.map_or(true, |predicate| predicate == *p)
+                    && object.map_or(true, |object| object == *o)
+            })
+            .map(|(p, o)| [subject, p, o])
+            .collect()
+    } else if let Some(predicate) = predicate {
+        option_pair_map_flatten(pos.get(&predicate))
+            .filter(|(o, _)| object.map_or(true, |object| object == *o))
+            .map(|(o, s)| [s, predicate, o])
+            .collect()
+    } else if let Some(object) = object {
+        option_pair_map_flatten(osp.get(&object))
+            .map(|(s, p)| [s, p, object])
+            .collect()
+    } else {
+        triple_map_flatten(spo).map(|(s, p, o)| [s, p, o]).collect()
     }
 }
 
@@ -1273,6 +2207,51 @@ fn remove_from_quad_map<T: Eq + Hash>(quad_map: &mut QuadMap<T>, e1: &T, e2: &T,
     }
 }
 
+fn remove_from_sorted_map<T: Eq + Hash, K: Ord, Q: Eq + Hash>(
+    sorted_map: &mut HashMap<T, BTreeMap<K, HashSet<Q>>>,
+    e: &T,
+    key: &K,
+    quad: &Q,
+) {
+    let mut quads_by_key_empty = false;
+    if let Some(quads_by_key) = sorted_map.get_mut(e) {
+        let mut quads_empty = false;
+        if let Some(quads) = quads_by_key.get_mut(key) {
+            quads.remove(quad);
+            quads_empty = quads.is_empty();
+        }
+        if quads_empty {
+            quads_by_key.remove(key);
+        }
+        quads_by_key_empty = quads_by_key.is_empty();
+    }
+    if quads_by_key_empty {
+        sorted_map.remove(e);
+    }
+}
+
+fn remove_graph_from_sorted_map<T: Eq + Hash, K: Ord>(
+    sorted_map: &mut HashMap<T, BTreeMap<K, HashSet<EncodedQuad>>>,
+    graph_name: EncodedTerm,
+) {
+    for quads_by_key in sorted_map.values_mut() {
+        for quads in quads_by_key.values_mut() {
+            quads.retain(|quad| quad.graph_name != graph_name);
+        }
+        quads_by_key.retain(|_, quads| !quads.is_empty());
+    }
+    sorted_map.retain(|_, quads_by_key| !quads_by_key.is_empty());
+}
+
+fn option_range_flatten<Q: Copy>(
+    i: Option<&BTreeMap<RangeKey, HashSet<Q>>>,
+    range: EncodedRange,
+) -> impl Iterator<Item = Q> + '_ {
+    i.into_iter()
+        .flat_map(move |quads_by_key| quads_by_key.range(range.start..=range.end))
+        .flat_map(|(_, quads)| quads.iter().copied())
+}
+
 fn option_set_flatten<T: Clone>(i: Option<&HashSet<T>>) -> impl Iterator<Item = T> + '_ {
     i.into_iter().flat_map(|s| s.iter().cloned())
 }
@@ -1367,12 +2346,12 @@ impl MemoryTransaction {
     pub fn load_graph<'a>(
         &mut self,
         reader: impl BufRead,
-        format: GraphFormat,
+        format: impl Into<GraphParser>,
         to_graph_name: impl Into<GraphNameRef<'a>>,
         base_iri: Option<&str>,
     ) -> Result<(), io::Error> {
         let to_graph_name = to_graph_name.into();
-        let mut parser = GraphParser::from_format(format);
+        let mut parser = format.into();
         if let Some(base_iri) = base_iri {
             parser = parser
                 .with_base_iri(base_iri)
@@ -1416,10 +2395,10 @@ impl MemoryTransaction {
     pub fn load_dataset(
         &mut self,
         reader: impl BufRead,
-        format: DatasetFormat,
+        format: impl Into<DatasetParser>,
         base_iri: Option<&str>,
     ) -> Result<(), io::Error> {
-        let mut parser = DatasetParser::from_format(format);
+        let mut parser = format.into();
         if let Some(base_iri) = base_iri {
             parser = parser
                 .with_base_iri(base_iri)
@@ -1582,158 +2561,70 @@ impl Iterator for MemoryGraphNameIter {
 
 impl StrId for LargeSpur {}
 
-// Isomorphism implementation
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::error::Error;
 
-fn iso_canonicalize(g: &MemoryStore) -> Vec<String> {
-    let bnodes = bnodes(g);
-    let (hash, partition) = hash_bnodes(g, bnodes.into_iter().map(|bnode| (bnode, 0)).collect());
-    distinguish(g, &hash, &partition)
-}
-
-fn distinguish(
-    g: &MemoryStore,
-    hash: &HashMap<EncodedTerm, u64>,
-    partition: &[(u64, Vec<EncodedTerm>)],
-) -> Vec<String> {
-    let b_prime = partition
-        .iter()
-        .find_map(|(_, b)| if b.len() > 1 { Some(b) } else { None });
-    if let Some(b_prime) = b_prime {
-        b_prime
-            .iter()
-            .map(|b| {
-                let mut hash_prime = hash.clone();
-                hash_prime.insert(*b, hash_tuple((hash_prime[b], 22)));
-                let (hash_prime_prime, partition_prime) = hash_bnodes(g, hash_prime);
-                distinguish(g, &hash_prime_prime, &partition_prime)
-            })
-            .fold(None, |a, b| {
-                Some(if let Some(a) = a {
-                    if a <= b {
-                        a
-                    } else {
-                        b
-                    }
-                } else {
-                    b
-                })
-            })
-            .unwrap_or_else(Vec::new)
-    } else {
-        label(g, hash)
-    }
-}
-
-fn hash_bnodes(
-    g: &MemoryStore,
-    mut hashes: HashMap<EncodedTerm, u64>,
-) -> (HashMap<EncodedTerm, u64>, Vec<(u64, Vec<EncodedTerm>)>) {
-    let mut to_hash = Vec::new();
-    let mut partition: HashMap<u64, Vec<EncodedTerm>> = HashMap::new();
-    let mut partition_len = 0;
-    loop {
-        //TODO: improve termination
-        let mut new_hashes = HashMap::new();
-        for (bnode, old_hash) in &hashes {
-            for q in g.encoded_quads_for_subject(*bnode) {
-                to_hash.push((
-                    hash_term(q.predicate, &hashes, g),
-                    hash_term(q.object, &hashes, g),
-                    hash_term(q.graph_name, &hashes, g),
-                    0,
-                ));
-            }
-            for q in g.encoded_quads_for_object(*bnode) {
-                to_hash.push((
-                    hash_term(q.subject, &hashes, g),
-                    hash_term(q.predicate, &hashes, g),
-                    hash_term(q.graph_name, &hashes, g),
-                    1,
-                ));
-            }
-            for q in g.encoded_quads_for_graph(*bnode) {
-                to_hash.push((
-                    hash_term(q.subject, &hashes, g),
-                    hash_term(q.predicate, &hashes, g),
-                    hash_term(q.object, &hashes, g),
-                    2,
-                ));
-            }
-            to_hash.sort_unstable();
-            let hash = hash_tuple((old_hash, &to_hash));
-            to_hash.clear();
-            new_hashes.insert(*bnode, hash);
-            partition.entry(hash).or_default().push(*bnode);
-        }
-        if partition.len() == partition_len {
-            let mut partition: Vec<_> = partition.into_iter().collect();
-            partition.sort_by(|(h1, b1), (h2, b2)| (b1.len(), h1).cmp(&(b2.len(), h2)));
-            return (hashes, partition);
-        }
-        hashes = new_hashes;
-        partition_len = partition.len();
-        partition.clear();
-    }
-}
-
-fn bnodes(g: &MemoryStore) -> HashSet<EncodedTerm> {
-    let mut bnodes = HashSet::new();
-    for q in g.encoded_quads() {
-        if q.subject.is_blank_node() {
-            bnodes.insert(q.subject);
-        }
-        if q.object.is_blank_node() {
-            bnodes.insert(q.object);
-        }
-        if q.graph_name.is_blank_node() {
-            bnodes.insert(q.graph_name);
-        }
-    }
-    bnodes
-}
-
-fn label(g: &MemoryStore, hashes: &HashMap<EncodedTerm, u64>) -> Vec<String> {
-    //TODO: better representation?
-    let mut data: Vec<_> = g
-        .encoded_quads()
-        .into_iter()
-        .map(|q| {
-            g.decode_quad(&EncodedQuad {
-                subject: map_term(q.subject, hashes),
-                predicate: map_term(q.predicate, hashes),
-                object: map_term(q.object, hashes),
-                graph_name: map_term(q.graph_name, hashes),
-            })
-            .unwrap()
-            .to_string()
-        })
-        .collect();
-    data.sort();
-    data
-}
-
-fn map_term(term: EncodedTerm, bnodes_hash: &HashMap<EncodedTerm, u64>) -> EncodedTerm {
-    if term.is_blank_node() {
-        EncodedTerm::NumericalBlankNode {
-            id: (*bnodes_hash.get(&term).unwrap()).into(),
+    fn count(
+        store: &MemoryStore,
+        query: &str,
+        options: QueryOptions,
+    ) -> Result<usize, Box<dyn Error>> {
+        if let QueryResults::Solutions(solutions) = store.query_opt(query, options)? {
+            Ok(solutions.collect::<Result<Vec<_>, _>>()?.len())
+        } else {
+            Err("SELECT query expected".into())
         }
-    } else {
-        term
     }
-}
 
-fn hash_term(term: EncodedTerm, bnodes_hash: &HashMap<EncodedTerm, u64>, g: &MemoryStore) -> u64 {
-    if term.is_blank_node() {
-        *bnodes_hash.get(&term).unwrap()
-    } else if let Ok(term) = g.decode_term(term) {
-        hash_tuple(term)
-    } else {
-        0
+    #[test]
+    fn test_query_cache() -> Result<(), Box<dyn Error>> {
+        let ex = |name: &str| NamedNode::new(format!("http://example.com/{}", name));
+        let store = MemoryStore::new();
+        store.set_query_cache_capacity(2);
+        let all = "SELECT * WHERE { ?s ?p ?o }";
+        let named = "SELECT * WHERE { GRAPH ?g { ?s ?p ?o } }";
+        let aggregate = "SELECT (COUNT(*) AS ?c) WHERE { ?s ?p [] } GROUP BY ?s";
+        assert_eq!(count(&store, all, QueryOptions::default())?, 0);
+        assert_eq!(count(&store, all, QueryOptions::default())?, 0);
+
+        // The cached results are dropped after each write
+        store.insert(Quad::new(ex("a")?, ex("p")?, ex("b")?, None));
+        assert_eq!(count(&store, all, QueryOptions::default())?, 1);
+        assert_eq!(count(&store, all, QueryOptions::default())?, 1);
+        assert_eq!(count(&store, aggregate, QueryOptions::default())?, 1);
+        assert_eq!(count(&store, aggregate, QueryOptions::default())?, 1);
+        store.update(
+            "INSERT DATA { <http://example.com/b> <http://example.com/p> <http://example.com/c> }",
+        )?;
+        assert_eq!(count(&store, all, QueryOptions::default())?, 2);
+        assert_eq!(count(&store, aggregate, QueryOptions::default())?, 2);
+        assert_eq!(count(&store, named, QueryOptions::default())?, 0);
+        let quad = Quad::new(ex("c")?, ex("p")?, ex("d")?, ex("g")?);
+        store.transaction(|transaction| {
+            transaction.insert(quad);
+            Result::<_, Infallible>::Ok(())
+        })?;
+        assert_eq!(count(&store, named, QueryOptions::default())?, 1);
+        store.remove_named_graph(&ex("g")?);
+        assert_eq!(count(&store, named, QueryOptions::default())?, 0);
+        store.clear();
+        assert_eq!(count(&store, all, QueryOptions::default())?, 0);
+        assert_eq!(
+            count(&store, all, QueryOptions::default().without_query_cache())?,
+            0
+        );
+
+        // The cache is shared by the clones of the store
+        let clone = store.clone();
+        count(&store, all, QueryOptions::default())?;
+        clone.insert(Quad::new(ex("a")?, ex("p")?, ex("b")?, None));
+        assert_eq!(count(&store, all, QueryOptions::default())?, 1);
+
+        // Disabling the cache drops the cached results
+        store.set_query_cache_capacity(0);
+        assert_eq!(count(&store, all, QueryOptions::default())?, 1);
+        Ok(())
     }
 }
-
-fn hash_tuple(v: impl Hash) -> u64 {
-    let mut hasher = DefaultHasher::new();
-    v.hash(&mut hasher);
-    hasher.finish()
-}
@@ -2,39 +2,61 @@
 
 #[cfg(any(feature = "rocksdb", feature = "sled"))]
 mod binary_encoder;
+pub mod hdt;
 pub mod memory;
 pub(crate) mod numeric_encoder;
+mod options;
+mod reasoning;
 #[cfg(feature = "rocksdb")]
 pub mod rocksdb;
+mod rules;
 #[cfg(feature = "sled")]
 pub mod sled;
 pub(crate) mod small_string;
 #[cfg(feature = "sophia")]
 mod sophia;
+pub(crate) mod spatial;
+pub(crate) mod text;
 
+pub use crate::store::hdt::HdtStore;
 pub use crate::store::memory::MemoryStore;
+pub use crate::store::options::StoreOptions;
+pub use crate::store::reasoning::INFERRED_GRAPH;
 #[cfg(feature = "rocksdb")]
 pub use crate::store::rocksdb::RocksDbStore;
+pub use crate::store::rules::Rule;
 #[cfg(feature = "sled")]
 pub use crate::store::sled::SledStore;
 
 use crate::error::invalid_input_error;
-use crate::io::{DatasetFormat, DatasetSerializer, GraphFormat, GraphSerializer};
+use crate::io::hdt::{parse_hdt_term, Hdt, TripleRole};
+use crate::io::jsonld::JsonLdParser;
+use crate::io::turtle::{ParallelTurtleParser, TurtleParser, TurtleSyntax};
+use crate::io::{
+    write_canonical_n_quads, DatasetFormat, DatasetParser, DatasetSerializer, GraphFormat,
+    GraphParser, GraphSerializer, PrefixMap,
+};
 use crate::model::*;
+use crate::shacl::ShaclValidator;
 use crate::store::numeric_encoder::*;
+use crate::store::spatial::SearchBox;
 use oxiri::Iri;
-use rio_api::parser::{QuadsParser, TriplesParser};
-use rio_turtle::{NQuadsParser, NTriplesParser, TriGParser, TurtleError, TurtleParser};
+use rio_api::parser::TriplesParser;
 use rio_xml::{RdfXmlError, RdfXmlParser};
 use std::collections::HashMap;
 use std::convert::Infallible;
 use std::io;
 use std::io::{BufRead, Write};
 use std::iter::Iterator;
+use std::path::Path;
+use std::sync::Arc;
 
-pub(crate) trait ReadableEncodedStore: StrLookup {
-    type QuadsIter: Iterator<Item = Result<EncodedQuad<Self::StrId>, Self::Error>> + 'static;
-    type GraphsIter: Iterator<Item = Result<EncodedTerm<Self::StrId>, Self::Error>> + 'static;
+pub(crate) type BoxedEncodedQuadsIter<I, E> =
+    Box<dyn Iterator<Item = Result<EncodedQuad<I>, E>> + Send>;
+
+pub(crate) trait ReadableEncodedStore: StrLookup + Send + Sync {
+    type QuadsIter: Iterator<Item = Result<EncodedQuad<Self::StrId>, Self::Error>> + Send + 'static;
+    type GraphsIter: Iterator<Item = Result<EncodedTerm<Self::StrId>, Self::Error>> + Send + 'static;
 
     fn encoded_quads_for_pattern(
         &self,
@@ -44,6 +66,44 @@ pub(crate) trait ReadableEncodedStore: StrLookup {
         graph_name: Option<EncodedTerm<Self::StrId>>,
     ) -> Self::QuadsIter;
 
+    /// Returns the quads with the given predicate and an object literal in the given range.
+    ///
+    /// Returns `None` if the store does not maintain a range index.
+    /// The returned quads might not all be in the range, the caller should filter them.
+    fn encoded_quads_for_predicate_object_range(
+        &self,
+        _predicate: EncodedTerm<Self::StrId>,
+        _range: EncodedRange,
+        _graph_name: Option<EncodedTerm<Self::StrId>>,
+    ) -> Option<BoxedEncodedQuadsIter<Self::StrId, Self::Error>> {
+        None
+    }
+
+    /// Returns the quads with the given predicate and a string literal object containing the given token.
+    ///
+    /// Returns `None` if the store does not maintain a full-text index.
+    fn encoded_quads_for_predicate_object_token(
+        &self,
+        _predicate: EncodedTerm<Self::StrId>,
+        _token: &str,
+        _graph_name: Option<EncodedTerm<Self::StrId>>,
+    ) -> Option<BoxedEncodedQuadsIter<Self::StrId, Self::Error>> {
+        None
+    }
+
+    /// Returns the quads with the given predicate and a geometry literal object intersecting the given box.
+    ///
+    /// Returns `None` if the store does not maintain a spatial index.
+    /// The returned quads might not all satisfy the spatial FILTER, the caller should filter them.
+    fn encoded_quads_for_predicate_object_box(
+        &self,
+        _predicate: EncodedTerm<Self::StrId>,
+        _search_box: SearchBox,
+        _graph_name: Option<EncodedTerm<Self::StrId>>,
+    ) -> Option<BoxedEncodedQuadsIter<Self::StrId, Self::Error>> {
+        None
+    }
+
     fn encoded_named_graphs(&self) -> Self::GraphsIter;
 
     fn contains_encoded_named_graph(
@@ -78,28 +138,60 @@ pub(crate) trait WritableEncodedStore: StrEncodingAware {
 pub(crate) fn load_graph<S: WritableEncodedStore + StrContainer>(
     store: &mut S,
     reader: impl BufRead,
-    format: GraphFormat,
+    parser: GraphParser,
     to_graph_name: GraphNameRef<'_>,
     base_iri: Option<&str>,
-) -> Result<(), StoreOrParseError<S::Error>> {
+) -> Result<PrefixMap, StoreOrParseError<S::Error>> {
     let base_iri = if let Some(base_iri) = base_iri {
         Some(Iri::parse(base_iri.into()).map_err(invalid_input_error)?)
     } else {
-        None
+        parser.base_iri
     };
-    match format {
+    match parser.format {
         GraphFormat::NTriples => {
-            load_from_triple_parser(store, NTriplesParser::new(reader), to_graph_name)
+            load_from_quad_parser(
+                store,
+                ParallelTurtleParser::new(reader, TurtleSyntax::NTriples, parser.on_error),
+                Some(to_graph_name),
+            )?;
+            Ok(PrefixMap::default())
         }
         GraphFormat::Turtle => {
-            load_from_triple_parser(store, TurtleParser::new(reader, base_iri), to_graph_name)
+            let mut parser = TurtleParser::new(reader, TurtleSyntax::Turtle, base_iri)
+                .with_error_handler(parser.on_error);
+            load_from_quad_parser(store, &mut parser, Some(to_graph_name))?;
+            Ok(parser.prefixes().clone())
         }
         GraphFormat::RdfXml => {
-            load_from_triple_parser(store, RdfXmlParser::new(reader, base_iri), to_graph_name)
+            let prefixes = PrefixMap::with_base_iri(base_iri.clone());
+            load_from_triple_parser(store, RdfXmlParser::new(reader, base_iri), to_graph_name)?;
+            Ok(prefixes)
         }
     }
 }
 
+/// Parses a graph file and validates the union of the existing triples and of the parsed ones against SHACL shapes.
+///
+/// Returns the parsed triples if the union conforms to the shapes.
+pub(crate) fn read_validated_graph(
+    existing: impl IntoIterator<Item = Triple>,
+    reader: impl BufRead,
+    mut parser: GraphParser,
+    base_iri: Option<&str>,
+    validator: &ShaclValidator,
+) -> Result<Vec<Triple>, io::Error> {
+    if let Some(base_iri) = base_iri {
+        parser = parser
+            .with_base_iri(base_iri)
+            .map_err(invalid_input_error)?;
+    }
+    let triples = parser
+        .read_triples(reader)?
+        .collect::<Result<Vec<_>, _>>()?;
+    validator.check(existing.into_iter().chain(triples.iter().cloned()))?;
+    Ok(triples)
+}
+
 fn load_from_triple_parser<S: WritableEncodedStore + StrContainer, P: TriplesParser>(
     store: &mut S,
     mut parser: P,
@@ -126,9 +218,9 @@ where
 fn dump_graph(
     triples: impl Iterator<Item = Result<Triple, io::Error>>,
     writer: impl Write,
-    format: GraphFormat,
+    serializer: &GraphSerializer,
 ) -> Result<(), io::Error> {
-    let mut writer = GraphSerializer::from_format(format).triple_writer(writer)?;
+    let mut writer = serializer.triple_writer(writer)?;
     for triple in triples {
         writer.write(&triple?)?;
     }
@@ -138,62 +230,157 @@ fn dump_graph(
 fn load_dataset<S: WritableEncodedStore + StrContainer>(
     store: &mut S,
     reader: impl BufRead,
-    format: DatasetFormat,
+    parser: DatasetParser,
     base_iri: Option<&str>,
 ) -> Result<(), StoreOrParseError<S::Error>> {
     let base_iri = if let Some(base_iri) = base_iri {
         Some(Iri::parse(base_iri.into()).map_err(invalid_input_error)?)
     } else {
-        None
+        parser.base_iri
     };
-    match format {
-        DatasetFormat::NQuads => load_from_quad_parser(store, NQuadsParser::new(reader)),
-        DatasetFormat::TriG => load_from_quad_parser(store, TriGParser::new(reader, base_iri)),
+    match parser.format {
+        DatasetFormat::NQuads => load_from_quad_parser(
+            store,
+            ParallelTurtleParser::new(reader, TurtleSyntax::NQuads, parser.on_error),
+            None,
+        ),
+        DatasetFormat::TriG => load_from_quad_parser(
+            store,
+            TurtleParser::new(reader, TurtleSyntax::TriG, base_iri)
+                .with_error_handler(parser.on_error),
+            None,
+        ),
+        DatasetFormat::JsonLd => load_from_quad_parser(
+            store,
+            JsonLdParser::new(reader, base_iri, parser.json_ld_contexts),
+            None,
+        ),
     }
 }
 
-fn load_from_quad_parser<S: WritableEncodedStore + StrContainer, P: QuadsParser>(
+/// Loads the quads returned by a Turtle family or JSON-LD parser.
+///
+/// If `to_graph_name` is set, all the quads are loaded into this graph.
+/// Blank nodes are already fresh for each parsing so they do not need to be remapped.
+fn load_from_quad_parser<S: WritableEncodedStore + StrContainer>(
     store: &mut S,
-    mut parser: P,
-) -> Result<(), StoreOrParseError<S::Error>>
-where
-    StoreOrParseError<S::Error>: From<P::Error>,
-{
-    let mut bnode_map = HashMap::default();
-    parser.parse_all(&mut move |q| {
-        let quad = store
-            .encode_rio_quad(q, &mut bnode_map)
-            .map_err(StoreOrParseError::Store)?;
+    parser: impl Iterator<Item = Result<Quad, io::Error>>,
+    to_graph_name: Option<GraphNameRef<'_>>,
+) -> Result<(), StoreOrParseError<S::Error>> {
+    let to_graph_name = to_graph_name
+        .map(|g| store.encode_graph_name(g))
+        .transpose()
+        .map_err(StoreOrParseError::Store)?;
+    for quad in parser {
+        let quad = quad?;
+        let quad = if let Some(to_graph_name) = to_graph_name {
+            store.encode_triple_in_graph(
+                TripleRef::new(&quad.subject, &quad.predicate, &quad.object),
+                to_graph_name,
+            )
+        } else {
+            store.encode_quad(quad.as_ref())
+        }
+        .map_err(StoreOrParseError::Store)?;
         store
             .insert_encoded(&quad)
             .map_err(StoreOrParseError::Store)?;
-        Ok(())
-    })
+    }
+    Ok(())
+}
+
+/// Loads the triples of an HDT file.
+///
+/// The terms are read from the HDT dictionary without going through an RDF syntax.
+/// Blank nodes are remapped to fresh ones like for the other formats.
+pub(crate) fn load_hdt<S: WritableEncodedStore + StrContainer>(
+    store: &mut S,
+    path: &Path,
+    to_graph_name: GraphNameRef<'_>,
+) -> Result<(), StoreOrParseError<S::Error>> {
+    let hdt = Arc::new(Hdt::open(path)?);
+    let to_graph_name = store
+        .encode_graph_name(to_graph_name)
+        .map_err(StoreOrParseError::Store)?;
+    let mut bnode_map = HashMap::new();
+    let mut predicates = HashMap::new();
+    let mut current_subject = None;
+    for [subject_id, predicate_id, object_id] in hdt.triple_ids(None, None, None) {
+        // The triples are sorted by subject so we encode each subject only once
+        let subject = match current_subject {
+            Some((id, subject)) if id == subject_id => subject,
+            _ => {
+                let subject =
+                    encode_hdt_term(store, &hdt, TripleRole::Subject, subject_id, &mut bnode_map)?;
+                current_subject = Some((subject_id, subject));
+                subject
+            }
+        };
+        let predicate = if let Some(predicate) = predicates.get(&predicate_id) {
+            *predicate
+        } else {
+            let predicate = encode_hdt_term(
+                store,
+                &hdt,
+                TripleRole::Predicate,
+                predicate_id,
+                &mut bnode_map,
+            )?;
+            predicates.insert(predicate_id, predicate);
+            predicate
+        };
+        let object = encode_hdt_term(store, &hdt, TripleRole::Object, object_id, &mut bnode_map)?;
+        store
+            .insert_encoded(&EncodedQuad::new(subject, predicate, object, to_graph_name))
+            .map_err(StoreOrParseError::Store)?;
+    }
+    Ok(())
+}
+
+fn encode_hdt_term<S: StrContainer>(
+    store: &mut S,
+    hdt: &Hdt,
+    role: TripleRole,
+    id: u64,
+    bnode_map: &mut HashMap<String, BlankNode>,
+) -> Result<EncodedTerm<S::StrId>, StoreOrParseError<S::Error>> {
+    let term = match parse_hdt_term(&hdt.extract(role, id)?)? {
+        Term::BlankNode(node) => bnode_map
+            .entry(node.into_string())
+            .or_default()
+            .clone()
+            .into(),
+        term => term,
+    };
+    store
+        .encode_term(term.as_ref())
+        .map_err(StoreOrParseError::Store)
 }
 
 fn dump_dataset(
     quads: impl Iterator<Item = Result<Quad, io::Error>>,
     writer: impl Write,
-    format: DatasetFormat,
+    serializer: &DatasetSerializer,
 ) -> Result<(), io::Error> {
-    let mut writer = DatasetSerializer::from_format(format).quad_writer(writer)?;
+    let mut writer = serializer.quad_writer(writer)?;
     for quad in quads {
         writer.write(&quad?)?;
     }
     writer.finish()
 }
 
+fn dump_canonical_dataset(
+    quads: impl Iterator<Item = Result<Quad, io::Error>>,
+    writer: impl Write,
+) -> Result<(), io::Error> {
+    write_canonical_n_quads(quads.collect::<Result<Vec<_>, _>>()?, writer)
+}
+
 pub(crate) enum StoreOrParseError<S> {
     Store(S),
     Parse(io::Error),
 }
 
-impl<S> From<TurtleError> for StoreOrParseError<S> {
-    fn from(error: TurtleError) -> Self {
-        Self::Parse(error.into())
-    }
-}
-
 impl<S> From<RdfXmlError> for StoreOrParseError<S> {
     fn from(error: RdfXmlError) -> Self {
         Self::Parse(error.into())
@@ -233,7 +420,7 @@ type QuadPattern<I> = (
 
 fn get_encoded_quad_pattern<E: ReadEncoder>(
     encoder: &E,
-    subject: Option<NamedOrBlankNodeRef<'_>>,
+    subject: Option<SubjectRef<'_>>,
     predicate: Option<NamedNodeRef<'_>>,
     object: Option<TermRef<'_>>,
     graph_name: Option<GraphNameRef<'_>>,
@@ -241,7 +428,7 @@ fn get_encoded_quad_pattern<E: ReadEncoder>(
     Ok(Some((
         if let Some(subject) = transpose(
             subject
-                .map(|t| encoder.get_encoded_named_or_blank_node(t))
+                .map(|t| encoder.get_encoded_subject(t))
                 .transpose()?,
         ) {
             subject
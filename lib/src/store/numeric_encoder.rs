@@ -13,9 +13,10 @@ use std::error::Error;
 use std::fmt::Debug;
 use std::hash::Hash;
 use std::hash::Hasher;
+use std::str::FromStr;
 use std::{fmt, io, str};
 
-pub trait StrId: Eq + Debug + Copy + Hash {}
+pub trait StrId: Eq + Debug + Copy + Hash + Send + Sync {}
 
 #[derive(Debug, Clone, Copy)]
 pub enum EncodedTerm<I: StrId> {
@@ -74,6 +75,10 @@ pub enum EncodedTerm<I: StrId> {
     DurationLiteral(Duration),
     YearMonthDurationLiteral(YearMonthDuration),
     DayTimeDurationLiteral(DayTimeDuration),
+    /// An RDF-star quoted triple stored using its N-Triples-star serialization
+    Triple {
+        triple_id: I,
+    },
 }
 
 impl<I: StrId> PartialEq for EncodedTerm<I> {
@@ -187,6 +192,14 @@ impl<I: StrId> PartialEq for EncodedTerm<I> {
             (Self::DurationLiteral(a), Self::DurationLiteral(b)) => a == b,
             (Self::YearMonthDurationLiteral(a), Self::YearMonthDurationLiteral(b)) => a == b,
             (Self::DayTimeDurationLiteral(a), Self::DayTimeDurationLiteral(b)) => a == b,
+            (
+                Self::Triple {
+                    triple_id: triple_id_a,
+                },
+                Self::Triple {
+                    triple_id: triple_id_b,
+                },
+            ) => triple_id_a == triple_id_b,
             (_, _) => false,
         }
     }
@@ -250,6 +263,7 @@ impl<I: StrId> Hash for EncodedTerm<I> {
             Self::DurationLiteral(value) => value.hash(state),
             Self::YearMonthDurationLiteral(value) => value.hash(state),
             Self::DayTimeDurationLiteral(value) => value.hash(state),
+            Self::Triple { triple_id } => triple_id.hash(state),
         }
     }
 }
@@ -298,6 +312,10 @@ impl<I: StrId> EncodedTerm<I> {
         )
     }
 
+    pub fn is_triple(&self) -> bool {
+        matches!(self, Self::Triple { .. })
+    }
+
     pub fn is_unknown_typed_literal(&self) -> bool {
         matches!(
             self,
@@ -373,6 +391,9 @@ impl<I: StrId> EncodedTerm<I> {
             Self::DurationLiteral(value) => EncodedTerm::DurationLiteral(value),
             Self::YearMonthDurationLiteral(value) => EncodedTerm::YearMonthDurationLiteral(value),
             Self::DayTimeDurationLiteral(value) => EncodedTerm::DayTimeDurationLiteral(value),
+            Self::Triple { triple_id } => EncodedTerm::Triple {
+                triple_id: mapping(triple_id),
+            },
         }
     }
 
@@ -443,6 +464,9 @@ impl<I: StrId> EncodedTerm<I> {
             Self::DurationLiteral(value) => EncodedTerm::DurationLiteral(value),
             Self::YearMonthDurationLiteral(value) => EncodedTerm::YearMonthDurationLiteral(value),
             Self::DayTimeDurationLiteral(value) => EncodedTerm::DayTimeDurationLiteral(value),
+            Self::Triple { triple_id } => EncodedTerm::Triple {
+                triple_id: mapping(triple_id)?,
+            },
         })
     }
 }
@@ -554,6 +578,145 @@ impl<I: StrId> EncodedQuad<I> {
     }
 }
 
+const RANGE_KIND_NUMERIC: u8 = 1;
+const RANGE_KIND_DATE_TIME: u8 = 2;
+const RANGE_KIND_DATE: u8 = 3;
+const RANGE_KIND_TIME: u8 = 4;
+// Numeric values are compared with various precisions so we widen the ranges by a few f32 ulps
+const NUMERIC_RANGE_MARGIN: u128 = 2;
+// Values without timezone may be shifted by up to 14 hours when compared with values with a timezone
+const TIMEZONE_RANGE_MARGIN: u128 = 14 * 3600 * 1_000_000_000_000_000_000;
+
+/// Key of the value-ordered index on literal objects.
+///
+/// Numeric literals are keyed by their `xsd:float` approximation and temporal literals by their position on the time line.
+/// A range of keys is only a superset of the literals actually matching a FILTER, the FILTER should still be evaluated on them.
+#[derive(Eq, PartialEq, Ord, PartialOrd, Debug, Clone, Copy, Hash)]
+pub struct RangeKey {
+    kind: u8,
+    value: u128,
+}
+
+impl RangeKey {
+    #[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+    pub fn new<I: StrId>(term: EncodedTerm<I>) -> Option<Self> {
+        match term {
+            EncodedTerm::FloatLiteral(value) => Self::numeric(value),
+            EncodedTerm::DoubleLiteral(value) => Self::numeric(value as f32),
+            EncodedTerm::IntegerLiteral(value) => Self::numeric(value as f32),
+            EncodedTerm::DecimalLiteral(value) => Self::numeric(value.to_f32()),
+            EncodedTerm::DateTimeLiteral(value) => {
+                Some(Self::temporal(RANGE_KIND_DATE_TIME, value.to_be_bytes()))
+            }
+            EncodedTerm::DateLiteral(value) => {
+                Some(Self::temporal(RANGE_KIND_DATE, value.to_be_bytes()))
+            }
+            EncodedTerm::TimeLiteral(value) => {
+                Some(Self::temporal(RANGE_KIND_TIME, value.to_be_bytes()))
+            }
+            _ => None,
+        }
+    }
+
+    fn numeric(value: f32) -> Option<Self> {
+        if value.is_nan() {
+            return None;
+        }
+        let bits = if value == 0. { 0 } else { value.to_bits() }; // -0 == +0
+        Some(Self {
+            kind: RANGE_KIND_NUMERIC,
+            value: u128::from(if bits >> 31 == 0 {
+                bits | (1 << 31)
+            } else {
+                !bits
+            }),
+        })
+    }
+
+    #[allow(clippy::cast_sign_loss)]
+    fn temporal(kind: u8, bytes: [u8; 18]) -> Self {
+        let mut value = [0; 16];
+        value.copy_from_slice(&bytes[..16]);
+        Self {
+            kind,
+            value: (i128::from_be_bytes(value) as u128) ^ (1 << 127),
+        }
+    }
+
+    fn margin(self) -> u128 {
+        if self.kind == RANGE_KIND_NUMERIC {
+            NUMERIC_RANGE_MARGIN
+        } else {
+            TIMEZONE_RANGE_MARGIN
+        }
+    }
+
+    fn widen_down(self) -> Self {
+        Self {
+            kind: self.kind,
+            value: self.value.saturating_sub(self.margin()),
+        }
+    }
+
+    fn widen_up(self) -> Self {
+        Self {
+            kind: self.kind,
+            value: self.value.saturating_add(self.margin()),
+        }
+    }
+
+    #[cfg(any(feature = "rocksdb", feature = "sled"))]
+    pub fn to_be_bytes(self) -> [u8; 17] {
+        let mut bytes = [self.kind; 17];
+        bytes[1..].copy_from_slice(&self.value.to_be_bytes());
+        bytes
+    }
+}
+
+/// An inclusive range of [`RangeKey`] containing at least all the literals between some bounds.
+#[derive(Eq, PartialEq, Debug, Clone, Copy, Hash)]
+pub struct EncodedRange {
+    pub start: RangeKey,
+    pub end: RangeKey,
+}
+
+impl EncodedRange {
+    /// Builds the range from inclusive bounds. Returns `None` if the bounds are not comparable.
+    pub fn new<I: StrId>(
+        lower: Option<EncodedTerm<I>>,
+        upper: Option<EncodedTerm<I>>,
+    ) -> Option<Self> {
+        let lower = lower.map(RangeKey::new);
+        let upper = upper.map(RangeKey::new);
+        Some(match (lower, upper) {
+            (Some(Some(lower)), Some(Some(upper))) => {
+                if lower.kind != upper.kind {
+                    return None;
+                }
+                Self {
+                    start: lower.widen_down(),
+                    end: upper.widen_up(),
+                }
+            }
+            (Some(Some(lower)), None) => Self {
+                start: lower.widen_down(),
+                end: RangeKey {
+                    kind: lower.kind,
+                    value: u128::MAX,
+                },
+            },
+            (None, Some(Some(upper))) => Self {
+                start: RangeKey {
+                    kind: upper.kind,
+                    value: 0,
+                },
+                end: upper.widen_up(),
+            },
+            _ => return None,
+        })
+    }
+}
+
 pub(crate) trait StrEncodingAware {
     //TODO: rename
     type Error: Error + Into<EvaluationError> + 'static;
@@ -759,6 +922,32 @@ pub(crate) trait ReadEncoder: StrEncodingAware {
         }
     }
 
+    fn get_encoded_triple(
+        &self,
+        triple: &Triple,
+    ) -> Result<Option<EncodedTerm<Self::StrId>>, Self::Error> {
+        Ok(Some(EncodedTerm::Triple {
+            triple_id: if let Some(triple_id) =
+                self.get_encoded_str(&TermRef::Triple(triple).to_string())?
+            {
+                triple_id
+            } else {
+                return Ok(None);
+            },
+        }))
+    }
+
+    fn get_encoded_subject(
+        &self,
+        term: SubjectRef<'_>,
+    ) -> Result<Option<EncodedTerm<Self::StrId>>, Self::Error> {
+        match term {
+            SubjectRef::NamedNode(named_node) => self.get_encoded_named_node(named_node),
+            SubjectRef::BlankNode(blank_node) => self.get_encoded_blank_node(blank_node),
+            SubjectRef::Triple(triple) => self.get_encoded_triple(triple),
+        }
+    }
+
     fn get_encoded_term(
         &self,
         term: TermRef<'_>,
@@ -767,6 +956,7 @@ pub(crate) trait ReadEncoder: StrEncodingAware {
             TermRef::NamedNode(named_node) => self.get_encoded_named_node(named_node),
             TermRef::BlankNode(blank_node) => self.get_encoded_blank_node(blank_node),
             TermRef::Literal(literal) => self.get_encoded_literal(literal),
+            TermRef::Triple(triple) => self.get_encoded_triple(triple),
         }
     }
 
@@ -786,7 +976,7 @@ pub(crate) trait ReadEncoder: StrEncodingAware {
         quad: QuadRef<'_>,
     ) -> Result<Option<EncodedQuad<Self::StrId>>, Self::Error> {
         Ok(Some(EncodedQuad {
-            subject: if let Some(subject) = self.get_encoded_named_or_blank_node(quad.subject)? {
+            subject: if let Some(subject) = self.get_encoded_subject(quad.subject)? {
                 subject
             } else {
                 return Ok(None);
@@ -862,11 +1052,29 @@ pub(crate) trait WriteEncoder: StrEncodingAware {
         }
     }
 
+    fn encode_triple(&mut self, triple: &Triple) -> Result<EncodedTerm<Self::StrId>, Self::Error> {
+        Ok(EncodedTerm::Triple {
+            triple_id: self.encode_str(&TermRef::Triple(triple).to_string())?,
+        })
+    }
+
+    fn encode_subject(
+        &mut self,
+        term: SubjectRef<'_>,
+    ) -> Result<EncodedTerm<Self::StrId>, Self::Error> {
+        match term {
+            SubjectRef::NamedNode(named_node) => self.encode_named_node(named_node),
+            SubjectRef::BlankNode(blank_node) => self.encode_blank_node(blank_node),
+            SubjectRef::Triple(triple) => self.encode_triple(triple),
+        }
+    }
+
     fn encode_term(&mut self, term: TermRef<'_>) -> Result<EncodedTerm<Self::StrId>, Self::Error> {
         match term {
             TermRef::NamedNode(named_node) => self.encode_named_node(named_node),
             TermRef::BlankNode(blank_node) => self.encode_blank_node(blank_node),
             TermRef::Literal(literal) => self.encode_literal(literal),
+            TermRef::Triple(triple) => self.encode_triple(triple),
         }
     }
 
@@ -883,7 +1091,7 @@ pub(crate) trait WriteEncoder: StrEncodingAware {
 
     fn encode_quad(&mut self, quad: QuadRef<'_>) -> Result<EncodedQuad<Self::StrId>, Self::Error> {
         Ok(EncodedQuad {
-            subject: self.encode_named_or_blank_node(quad.subject)?,
+            subject: self.encode_subject(quad.subject)?,
             predicate: self.encode_named_node(quad.predicate)?,
             object: self.encode_term(quad.object)?,
             graph_name: self.encode_graph_name(quad.graph_name)?,
@@ -896,7 +1104,7 @@ pub(crate) trait WriteEncoder: StrEncodingAware {
         graph_name: EncodedTerm<Self::StrId>,
     ) -> Result<EncodedQuad<Self::StrId>, Self::Error> {
         Ok(EncodedQuad {
-            subject: self.encode_named_or_blank_node(triple.subject)?,
+            subject: self.encode_subject(triple.subject)?,
             predicate: self.encode_named_node(triple.predicate)?,
             object: self.encode_term(triple.object)?,
             graph_name,
@@ -1030,16 +1238,19 @@ pub(crate) trait WriteEncoder: StrEncodingAware {
         })
     }
 
-    fn encode_rio_named_or_blank_node(
+    fn encode_rio_subject(
         &mut self,
-        term: rio::NamedOrBlankNode<'_>,
+        term: rio::Subject<'_>,
         bnodes_map: &mut HashMap<String, u128>,
     ) -> Result<EncodedTerm<Self::StrId>, Self::Error> {
         match term {
-            rio::NamedOrBlankNode::NamedNode(named_node) => self.encode_rio_named_node(named_node),
-            rio::NamedOrBlankNode::BlankNode(blank_node) => {
+            rio::Subject::NamedNode(named_node) => self.encode_rio_named_node(named_node),
+            rio::Subject::BlankNode(blank_node) => {
                 self.encode_rio_blank_node(blank_node, bnodes_map)
             }
+            rio::Subject::Triple(triple) => {
+                self.encode_triple(&quoted_rio_triple(triple, bnodes_map))
+            }
         }
     }
 
@@ -1052,25 +1263,10 @@ pub(crate) trait WriteEncoder: StrEncodingAware {
             rio::Term::NamedNode(named_node) => self.encode_rio_named_node(named_node),
             rio::Term::BlankNode(blank_node) => self.encode_rio_blank_node(blank_node, bnodes_map),
             rio::Term::Literal(literal) => self.encode_rio_literal(literal),
+            rio::Term::Triple(triple) => self.encode_triple(&quoted_rio_triple(triple, bnodes_map)),
         }
     }
 
-    fn encode_rio_quad(
-        &mut self,
-        quad: rio::Quad<'_>,
-        bnodes_map: &mut HashMap<String, u128>,
-    ) -> Result<EncodedQuad<Self::StrId>, Self::Error> {
-        Ok(EncodedQuad {
-            subject: self.encode_rio_named_or_blank_node(quad.subject, bnodes_map)?,
-            predicate: self.encode_rio_named_node(quad.predicate)?,
-            object: self.encode_rio_term(quad.object, bnodes_map)?,
-            graph_name: match quad.graph_name {
-                Some(graph_name) => self.encode_rio_named_or_blank_node(graph_name, bnodes_map)?,
-                None => EncodedTerm::DefaultGraph,
-            },
-        })
-    }
-
     fn encode_rio_triple_in_graph(
         &mut self,
         triple: rio::Triple<'_>,
@@ -1078,7 +1274,7 @@ pub(crate) trait WriteEncoder: StrEncodingAware {
         bnodes_map: &mut HashMap<String, u128>,
     ) -> Result<EncodedQuad<Self::StrId>, Self::Error> {
         Ok(EncodedQuad {
-            subject: self.encode_rio_named_or_blank_node(triple.subject, bnodes_map)?,
+            subject: self.encode_rio_subject(triple.subject, bnodes_map)?,
             predicate: self.encode_rio_named_node(triple.predicate)?,
             object: self.encode_rio_term(triple.object, bnodes_map)?,
             graph_name,
@@ -1088,6 +1284,49 @@ pub(crate) trait WriteEncoder: StrEncodingAware {
     fn encode_str(&mut self, value: &str) -> Result<Self::StrId, Self::Error>;
 }
 
+/// Converts a quoted triple returned by Rio using the same blank nodes as [`WriteEncoder::encode_rio_blank_node`]
+fn quoted_rio_triple(triple: &rio::Triple<'_>, bnodes_map: &mut HashMap<String, u128>) -> Triple {
+    let subject = match triple.subject {
+        rio::Subject::NamedNode(node) => NamedNode::new_unchecked(node.iri).into(),
+        rio::Subject::BlankNode(node) => quoted_rio_blank_node(node, bnodes_map).into(),
+        rio::Subject::Triple(triple) => quoted_rio_triple(triple, bnodes_map).into(),
+    };
+    let object = match triple.object {
+        rio::Term::NamedNode(node) => NamedNode::new_unchecked(node.iri).into(),
+        rio::Term::BlankNode(node) => quoted_rio_blank_node(node, bnodes_map).into(),
+        rio::Term::Literal(literal) => rio_literal(literal).into(),
+        rio::Term::Triple(triple) => quoted_rio_triple(triple, bnodes_map).into(),
+    };
+    Triple::new(
+        subject,
+        NamedNode::new_unchecked(triple.predicate.iri),
+        object,
+    )
+}
+
+fn quoted_rio_blank_node(
+    node: rio::BlankNode<'_>,
+    bnodes_map: &mut HashMap<String, u128>,
+) -> BlankNode {
+    BlankNode::new_from_unique_id(
+        *bnodes_map
+            .entry(node.id.to_owned())
+            .or_insert_with(random::<u128>),
+    )
+}
+
+fn rio_literal(literal: rio::Literal<'_>) -> Literal {
+    match literal {
+        rio::Literal::Simple { value } => Literal::new_simple_literal(value),
+        rio::Literal::LanguageTaggedString { value, language } => {
+            Literal::new_language_tagged_literal_unchecked(value, language)
+        }
+        rio::Literal::Typed { value, datatype } => {
+            Literal::new_typed_literal(value, NamedNode::new_unchecked(datatype.iri))
+        }
+    }
+}
+
 impl<S: StrContainer> WriteEncoder for S {
     fn encode_str(&mut self, value: &str) -> Result<Self::StrId, Self::Error> {
         self.insert_str(value)
@@ -1171,6 +1410,20 @@ pub(crate) trait Decoder: StrLookup {
         encoded: EncodedTerm<Self::StrId>,
     ) -> Result<Term, DecoderError<Self::Error>>;
 
+    fn decode_subject(
+        &self,
+        encoded: EncodedTerm<Self::StrId>,
+    ) -> Result<Subject, DecoderError<Self::Error>> {
+        match self.decode_term(encoded)? {
+            Term::NamedNode(named_node) => Ok(named_node.into()),
+            Term::BlankNode(blank_node) => Ok(blank_node.into()),
+            Term::Literal(_) => Err(DecoderError::Decoder {
+                msg: "A literal has ben found instead of a named node".to_owned(),
+            }),
+            Term::Triple(triple) => Ok(Subject::Triple(triple)),
+        }
+    }
+
     fn decode_named_or_blank_node(
         &self,
         encoded: EncodedTerm<Self::StrId>,
@@ -1181,6 +1434,9 @@ pub(crate) trait Decoder: StrLookup {
             Term::Literal(_) => Err(DecoderError::Decoder {
                 msg: "A literal has ben found instead of a named node".to_owned(),
             }),
+            Term::Triple(_) => Err(DecoderError::Decoder {
+                msg: "A quoted triple has been found instead of a named node".to_owned(),
+            }),
         }
     }
 
@@ -1196,6 +1452,9 @@ pub(crate) trait Decoder: StrLookup {
             Term::Literal(_) => Err(DecoderError::Decoder {
                 msg: "A literal has ben found instead of a named node".to_owned(),
             }),
+            Term::Triple(_) => Err(DecoderError::Decoder {
+                msg: "A quoted triple has been found instead of a named node".to_owned(),
+            }),
         }
     }
 
@@ -1204,7 +1463,7 @@ pub(crate) trait Decoder: StrLookup {
         encoded: &EncodedQuad<Self::StrId>,
     ) -> Result<Triple, DecoderError<Self::Error>> {
         Ok(Triple::new(
-            self.decode_named_or_blank_node(encoded.subject)?,
+            self.decode_subject(encoded.subject)?,
             self.decode_named_node(encoded.predicate)?,
             self.decode_term(encoded.object)?,
         ))
@@ -1215,7 +1474,7 @@ pub(crate) trait Decoder: StrLookup {
         encoded: &EncodedQuad<Self::StrId>,
     ) -> Result<Quad, DecoderError<Self::Error>> {
         Ok(Quad::new(
-            self.decode_named_or_blank_node(encoded.subject)?,
+            self.decode_subject(encoded.subject)?,
             self.decode_named_node(encoded.predicate)?,
             self.decode_term(encoded.object)?,
             match encoded.graph_name {
@@ -1303,6 +1562,15 @@ impl<S: StrLookup> Decoder for S {
             EncodedTerm::DurationLiteral(value) => Ok(Literal::from(value).into()),
             EncodedTerm::YearMonthDurationLiteral(value) => Ok(Literal::from(value).into()),
             EncodedTerm::DayTimeDurationLiteral(value) => Ok(Literal::from(value).into()),
+            EncodedTerm::Triple { triple_id } => {
+                let serialization = get_required_str(self, triple_id)?;
+                match Term::from_str(&serialization) {
+                    Ok(term) if term.is_triple() => Ok(term),
+                    _ => Err(DecoderError::Decoder {
+                        msg: format!("Invalid quoted triple serialization {}", serialization),
+                    }),
+                }
+            }
         }
     }
 }
@@ -0,0 +1,178 @@
+//! Options shared by the store implementations.
+
+/// The optional indexes and inferences maintained by a store.
+///
+/// The options could be combined and are given to [`MemoryStore::new_with_options()`](super::memory::MemoryStore::new_with_options()),
+/// [`SledStore::open_with_options()`](super::sled::SledStore::open_with_options()) or [`RocksDbStore::open_with_options()`](super::rocksdb::RocksDbStore::open_with_options()).
+///
+/// The persistent stores build the enabled indexes and inferences from their content if it is not done yet.
+/// Once enabled, they are kept up to date even if the store is opened later without them.
+///
+/// Usage example:
+/// ```
+/// use oxigraph::MemoryStore;
+/// use oxigraph::store::StoreOptions;
+///
+/// let store = MemoryStore::new_with_options(StoreOptions::default().with_range_index().with_text_index());
+/// assert!(store.is_empty());
+/// ```
+#[derive(Default, Clone, Copy, Debug, Eq, PartialEq)]
+pub struct StoreOptions {
+    pub(crate) range_index: bool,
+    pub(crate) text_index: bool,
+    pub(crate) spatial_index: bool,
+    pub(crate) owl2rl_reasoning: bool,
+}
+
+impl StoreOptions {
+    /// Maintains a value-ordered index of the numeric and temporal literals.
+    ///
+    /// SPARQL queries are then able to evaluate FILTERs like `FILTER(?date > "2020-01-01"^^xsd:date)` using range lookups.
+    ///
+    /// Usage example:
+    /// ```
+    /// use oxigraph::MemoryStore;
+    /// use oxigraph::model::*;
+    /// use oxigraph::sparql::QueryResults;
+    /// use oxigraph::store::StoreOptions;
+    ///
+    /// let store = MemoryStore::new_with_options(StoreOptions::default().with_range_index());
+    ///
+    /// // insertions
+    /// let ex = NamedNodeRef::new("http://example.com")?;
+    /// store.insert(QuadRef::new(ex, ex, &Literal::from(1), GraphNameRef::DefaultGraph));
+    /// store.insert(QuadRef::new(ex, ex, &Literal::from(10), GraphNameRef::DefaultGraph));
+    ///
+    /// // SPARQL query
+    /// if let QueryResults::Solutions(mut solutions) = store.query("SELECT ?o WHERE { ?s <http://example.com> ?o FILTER(?o > 5) }")? {
+    ///     assert_eq!(solutions.next().unwrap()?.get("o"), Some(&Literal::from(10).into()));
+    ///     assert!(solutions.next().is_none());
+    /// }
+    /// # Result::<_,Box<dyn std::error::Error>>::Ok(())
+    /// ```
+    #[inline]
+    pub fn with_range_index(mut self) -> Self {
+        self.range_index = true;
+        self
+    }
+
+    /// Maintains a full-text index of the string literals.
+    ///
+    /// The literals are split into case-folded alphanumeric tokens.
+    /// SPARQL queries are then able to evaluate FILTERs using the `<http://oxigraph.org/fn/textMatch>(?text, "query")` function with index lookups.
+    /// This function returns true if the text contains all the tokens of the query.
+    /// An optional third parameter restricts the match to the texts with a language tag matching a language range like in `langMatches`.
+    ///
+    /// The `<http://oxigraph.org/fn/textScore>(?text, "query")` function returns a relevance score between 0 and 1.
+    /// It is the proportion of the query tokens found in the text multiplied by the proportion of the text tokens found in the query.
+    ///
+    /// Usage example:
+    /// ```
+    /// use oxigraph::MemoryStore;
+    /// use oxigraph::model::*;
+    /// use oxigraph::sparql::QueryResults;
+    /// use oxigraph::store::StoreOptions;
+    ///
+    /// let store = MemoryStore::new_with_options(StoreOptions::default().with_text_index());
+    ///
+    /// // insertions
+    /// let ex = NamedNodeRef::new("http://example.com")?;
+    /// store.insert(QuadRef::new(ex, ex, LiteralRef::new_language_tagged_literal_unchecked("The Eiffel Tower", "en"), GraphNameRef::DefaultGraph));
+    /// store.insert(QuadRef::new(ex, ex, LiteralRef::new_language_tagged_literal_unchecked("La tour Eiffel", "fr"), GraphNameRef::DefaultGraph));
+    ///
+    /// // SPARQL query
+    /// if let QueryResults::Solutions(mut solutions) = store.query("SELECT ?o WHERE { ?s <http://example.com> ?o FILTER(<http://oxigraph.org/fn/textMatch>(?o, \"eiffel tower\", \"en\")) }")? {
+    ///     assert_eq!(solutions.next().unwrap()?.get("o"), Some(&LiteralRef::new_language_tagged_literal_unchecked("The Eiffel Tower", "en").into_owned().into()));
+    ///     assert!(solutions.next().is_none());
+    /// }
+    /// # Result::<_,Box<dyn std::error::Error>>::Ok(())
+    /// ```
+    #[inline]
+    pub fn with_text_index(mut self) -> Self {
+        self.text_index = true;
+        self
+    }
+
+    /// Maintains a R-tree index of the bounding boxes of the `geo:wktLiteral` and `geo:geoJSONLiteral` literals.
+    ///
+    /// SPARQL queries are then able to evaluate FILTERs using the [GeoSPARQL](https://www.ogc.org/standards/geosparql) functions
+    /// `geof:sfWithin(?geometry, "...")`, `geof:sfIntersects(?geometry, "...")`, `geof:sfContains("...", ?geometry)`
+    /// and `geof:distance(?geometry, "...", uom:metre) < ...` with index lookups.
+    ///
+    /// This index is only supported by [`MemoryStore`](super::memory::MemoryStore).
+    /// The persistent stores return an [`InvalidInput`](std::io::ErrorKind::InvalidInput) error if it is enabled.
+    ///
+    /// Usage example:
+    /// ```
+    /// use oxigraph::MemoryStore;
+    /// use oxigraph::model::*;
+    /// use oxigraph::model::vocab::geo;
+    /// use oxigraph::sparql::QueryResults;
+    /// use oxigraph::store::StoreOptions;
+    ///
+    /// let store = MemoryStore::new_with_options(StoreOptions::default().with_spatial_index());
+    ///
+    /// // insertions
+    /// let paris = NamedNodeRef::new("http://example.com/paris")?;
+    /// let london = NamedNodeRef::new("http://example.com/london")?;
+    /// store.insert(QuadRef::new(paris, geo::AS_WKT, LiteralRef::new_typed_literal("POINT(2.35 48.85)", geo::WKT_LITERAL), GraphNameRef::DefaultGraph));
+    /// store.insert(QuadRef::new(london, geo::AS_WKT, LiteralRef::new_typed_literal("POINT(-0.13 51.51)", geo::WKT_LITERAL), GraphNameRef::DefaultGraph));
+    ///
+    /// // SPARQL query
+    /// if let QueryResults::Solutions(mut solutions) = store.query("PREFIX geo: <http://www.opengis.net/ont/geosparql#>
+    ///     PREFIX geof: <http://www.opengis.net/def/function/geosparql/>
+    ///     SELECT ?s WHERE { ?s geo:asWKT ?wkt FILTER(geof:sfWithin(?wkt, \"POLYGON((-5 42, 8 42, 8 51, -5 51, -5 42))\"^^geo:wktLiteral)) }")? {
+    ///     assert_eq!(solutions.next().unwrap()?.get("s"), Some(&paris.into_owned().into()));
+    ///     assert!(solutions.next().is_none());
+    /// }
+    /// # Result::<_,Box<dyn std::error::Error>>::Ok(())
+    /// ```
+    #[inline]
+    pub fn with_spatial_index(mut self) -> Self {
+        self.spatial_index = true;
+        self
+    }
+
+    /// Materializes the [OWL 2 RL](https://www.w3.org/TR/owl2-profiles/#OWL_2_RL) consequences of the default graph.
+    ///
+    /// The inferred triples are stored in the [`INFERRED_GRAPH`](super::INFERRED_GRAPH) named graph
+    /// and kept up to date when quads are inserted into or removed from the default graph, including during transactions.
+    /// This graph should not be modified directly.
+    ///
+    /// The supported rules are the ones about `owl:sameAs`, `owl:inverseOf`, `owl:SymmetricProperty`, `owl:TransitiveProperty`,
+    /// `owl:propertyChainAxiom`, `owl:equivalentClass`, `owl:equivalentProperty`
+    /// and the RDFS `rdfs:subClassOf`, `rdfs:subPropertyOf`, `rdfs:domain` and `rdfs:range` properties.
+    ///
+    /// Usage example:
+    /// ```
+    /// use oxigraph::MemoryStore;
+    /// use oxigraph::model::*;
+    /// use oxigraph::store::{StoreOptions, INFERRED_GRAPH};
+    ///
+    /// let store = MemoryStore::new_with_options(StoreOptions::default().with_owl2rl_reasoning());
+    /// store.update("PREFIX owl: <http://www.w3.org/2002/07/owl#>
+    ///     INSERT DATA {
+    ///         <http://example.com/ancestor> a owl:TransitiveProperty .
+    ///         <http://example.com/a> <http://example.com/ancestor> <http://example.com/b> .
+    ///         <http://example.com/b> <http://example.com/ancestor> <http://example.com/c> .
+    ///     }")?;
+    ///
+    /// let inferred = QuadRef::new(
+    ///     NamedNodeRef::new("http://example.com/a")?,
+    ///     NamedNodeRef::new("http://example.com/ancestor")?,
+    ///     NamedNodeRef::new("http://example.com/c")?,
+    ///     INFERRED_GRAPH,
+    /// );
+    /// assert!(store.contains(inferred));
+    ///
+    /// // The inferences are removed with the triples they are derived from
+    /// store.update("DELETE DATA { <http://example.com/b> <http://example.com/ancestor> <http://example.com/c> }")?;
+    /// assert!(!store.contains(inferred));
+    /// # Result::<_,Box<dyn std::error::Error>>::Ok(())
+    /// ```
+    #[inline]
+    pub fn with_owl2rl_reasoning(mut self) -> Self {
+        self.owl2rl_reasoning = true;
+        self
+    }
+}
@@ -0,0 +1,522 @@
+//! Forward-chaining materialization of a subset of the [OWL 2 RL](https://www.w3.org/TR/owl2-profiles/#Reasoning_in_OWL_2_RL_and_RDF_Graphs_using_Rules) rules.
+//!
+//! The rules are applied to the triples of the default graph and their consequences are stored in the [`INFERRED_GRAPH`] named graph.
+//! The supported rules are the equality rules `eq-sym`, `eq-trans` and `eq-rep-*`,
+//! the property rules `prp-dom`, `prp-rng`, `prp-symp`, `prp-trp`, `prp-spo1`, `prp-spo2`, `prp-eqp1`, `prp-eqp2`, `prp-inv1` and `prp-inv2`,
+//! the class rules `cax-sco`, `cax-eqc1` and `cax-eqc2`
+//! and the schema rules `scm-sco`, `scm-spo`, `scm-eqc1` and `scm-eqp1`.
+//!
+//! The materialization is maintained incrementally:
+//! the consequences of an inserted triple are propagated using a semi-naive evaluation
+//! and a removed triple is handled using the Delete and Rederive (DRed) algorithm.
+
+use crate::model::vocab::{owl, rdf, rdfs};
+use crate::model::NamedNodeRef;
+use crate::store::numeric_encoder::{EncodedTerm, StrEncodingAware, StrId};
+use std::collections::HashSet;
+
+/// The named graph in which the triples inferred by the OWL 2 RL reasoner are stored.
+pub const INFERRED_GRAPH: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://oxigraph.org/graph/inferred");
+
+/// An encoded triple, in the subject, predicate, object order
+pub(crate) type EncodedTriple<I> = [EncodedTerm<I>; 3];
+
+/// The store operations used by the reasoner
+///
+/// The asserted triples are the ones of the default graph and the inferred triples the ones of [`INFERRED_GRAPH`].
+pub(crate) trait InferenceStore: StrEncodingAware {
+    /// Returns the asserted and inferred triples matching the pattern
+    fn closure_triples_for_pattern(
+        &self,
+        subject: Option<EncodedTerm<Self::StrId>>,
+        predicate: Option<EncodedTerm<Self::StrId>>,
+        object: Option<EncodedTerm<Self::StrId>>,
+    ) -> Result<Vec<EncodedTriple<Self::StrId>>, Self::Error>;
+
+    fn contains_asserted(&self, triple: &EncodedTriple<Self::StrId>) -> Result<bool, Self::Error>;
+
+    fn contains_inferred(&self, triple: &EncodedTriple<Self::StrId>) -> Result<bool, Self::Error>;
+
+    fn insert_inferred(&mut self, triple: &EncodedTriple<Self::StrId>) -> Result<(), Self::Error>;
+
+    fn remove_inferred(&mut self, triple: &EncodedTriple<Self::StrId>) -> Result<(), Self::Error>;
+}
+
+/// The maximal number of variables in a rule
+const MAX_VARIABLES: usize = 4;
+
+type Bindings<I> = [Option<EncodedTerm<I>>; MAX_VARIABLES];
+
+#[derive(Clone, Copy)]
+enum Slot<I: StrId> {
+    Variable(usize),
+    Constant(EncodedTerm<I>),
+}
+
+type Atom<I> = [Slot<I>; 3];
+
+struct Rule<I: StrId> {
+    body: Vec<Atom<I>>,
+    head: Vec<Atom<I>>,
+}
+
+/// The encoded vocabulary terms used by the property chain rule
+#[derive(Clone, Copy)]
+struct ListVocabulary<I: StrId> {
+    property_chain_axiom: EncodedTerm<I>,
+    first: EncodedTerm<I>,
+    rest: EncodedTerm<I>,
+    nil: EncodedTerm<I>,
+}
+
+/// An OWL 2 RL reasoner over the encoded terms of a store
+pub(crate) struct Owl2RlReasoner<I: StrId> {
+    rules: Vec<Rule<I>>,
+    lists: ListVocabulary<I>,
+    inferred_graph: EncodedTerm<I>,
+}
+
+impl<I: StrId> Owl2RlReasoner<I> {
+    /// Builds the reasoner, `encode` being used to encode the vocabulary
+    pub fn new<E>(
+        mut encode: impl FnMut(NamedNodeRef<'_>) -> Result<EncodedTerm<I>, E>,
+    ) -> Result<Self, E> {
+        let mut constant = |node| Ok(Slot::Constant(encode(node)?));
+        let type_ = constant(rdf::TYPE)?;
+        let sub_class_of = constant(rdfs::SUB_CLASS_OF)?;
+        let sub_property_of = constant(rdfs::SUB_PROPERTY_OF)?;
+        let domain = constant(rdfs::DOMAIN)?;
+        let range = constant(rdfs::RANGE)?;
+        let same_as = constant(owl::SAME_AS)?;
+        let equivalent_class = constant(owl::EQUIVALENT_CLASS)?;
+        let equivalent_property = constant(owl::EQUIVALENT_PROPERTY)?;
+        let inverse_of = constant(owl::INVERSE_OF)?;
+        let symmetric_property = constant(owl::SYMMETRIC_PROPERTY)?;
+        let transitive_property = constant(owl::TRANSITIVE_PROPERTY)?;
+        let lists = ListVocabulary {
+            property_chain_axiom: encode(owl::PROPERTY_CHAIN_AXIOM)?,
+            first: encode(rdf::FIRST)?,
+            rest: encode(rdf::REST)?,
+            nil: encode(rdf::NIL)?,
+        };
+        let inferred_graph = encode(INFERRED_GRAPH)?;
+
+        let (a, b, c, d) = (
+            Slot::Variable(0),
+            Slot::Variable(1),
+            Slot::Variable(2),
+            Slot::Variable(3),
+        );
+        let rule = |body: Vec<Atom<I>>, head: Vec<Atom<I>>| Rule { body, head };
+        let rules = vec![
+            // eq-sym
+            rule(vec![[a, same_as, b]], vec![[b, same_as, a]]),
+            // eq-trans
+            rule(
+                vec![[a, same_as, b], [b, same_as, c]],
+                vec![[a, same_as, c]],
+            ),
+            // eq-rep-s
+            rule(vec![[a, same_as, b], [a, c, d]], vec![[b, c, d]]),
+            // eq-rep-p
+            rule(vec![[a, same_as, b], [c, a, d]], vec![[c, b, d]]),
+            // eq-rep-o
+            rule(vec![[a, same_as, b], [c, d, a]], vec![[c, d, b]]),
+            // prp-dom
+            rule(vec![[a, domain, b], [c, a, d]], vec![[c, type_, b]]),
+            // prp-rng
+            rule(vec![[a, range, b], [c, a, d]], vec![[d, type_, b]]),
+            // prp-symp
+            rule(
+                vec![[a, type_, symmetric_property], [b, a, c]],
+                vec![[c, a, b]],
+            ),
+            // prp-trp
+            rule(
+                vec![[a, type_, transitive_property], [b, a, c], [c, a, d]],
+                vec![[b, a, d]],
+            ),
+            // prp-spo1
+            rule(vec![[a, sub_property_of, b], [c, a, d]], vec![[c, b, d]]),
+            // prp-eqp1
+            rule(
+                vec![[a, equivalent_property, b], [c, a, d]],
+                vec![[c, b, d]],
+            ),
+            // prp-eqp2
+            rule(
+                vec![[a, equivalent_property, b], [c, b, d]],
+                vec![[c, a, d]],
+            ),
+            // prp-inv1
+            rule(vec![[a, inverse_of, b], [c, a, d]], vec![[d, b, c]]),
+            // prp-inv2
+            rule(vec![[a, inverse_of, b], [c, b, d]], vec![[d, a, c]]),
+            // cax-sco
+            rule(
+                vec![[a, sub_class_of, b], [c, type_, a]],
+                vec![[c, type_, b]],
+            ),
+            // cax-eqc1
+            rule(
+                vec![[a, equivalent_class, b], [c, type_, a]],
+                vec![[c, type_, b]],
+            ),
+            // cax-eqc2
+            rule(
+                vec![[a, equivalent_class, b], [c, type_, b]],
+                vec![[c, type_, a]],
+            ),
+            // scm-sco
+            rule(
+                vec![[a, sub_class_of, b], [b, sub_class_of, c]],
+                vec![[a, sub_class_of, c]],
+            ),
+            // scm-spo
+            rule(
+                vec![[a, sub_property_of, b], [b, sub_property_of, c]],
+                vec![[a, sub_property_of, c]],
+            ),
+            // scm-eqc1
+            rule(
+                vec![[a, equivalent_class, b]],
+                vec![[a, sub_class_of, b], [b, sub_class_of, a]],
+            ),
+            // scm-eqp1
+            rule(
+                vec![[a, equivalent_property, b]],
+                vec![[a, sub_property_of, b], [b, sub_property_of, a]],
+            ),
+        ];
+        Ok(Self {
+            rules,
+            lists,
+            inferred_graph,
+        })
+    }
+
+    /// The encoded [`INFERRED_GRAPH`] name
+    pub fn inferred_graph(&self) -> EncodedTerm<I> {
+        self.inferred_graph
+    }
+
+    /// Materializes the consequences of all the asserted triples
+    #[cfg(any(feature = "rocksdb", feature = "sled"))]
+    pub fn materialize<S: InferenceStore<StrId = I>>(
+        &self,
+        store: &mut S,
+        asserted: Vec<EncodedTriple<I>>,
+    ) -> Result<(), S::Error> {
+        self.propagate(store, asserted)
+    }
+
+    /// Updates the materialization after the insertion of a triple in the default graph
+    pub fn on_insert<S: InferenceStore<StrId = I>>(
+        &self,
+        store: &mut S,
+        triple: EncodedTriple<I>,
+    ) -> Result<(), S::Error> {
+        if store.contains_inferred(&triple)? {
+            // Its consequences are already materialized
+            return store.remove_inferred(&triple);
+        }
+        self.propagate(store, vec![triple])
+    }
+
+    /// Updates the materialization after the removal of a triple from the default graph
+    pub fn on_remove<S: InferenceStore<StrId = I>>(
+        &self,
+        store: &mut S,
+        triple: EncodedTriple<I>,
+    ) -> Result<(), S::Error> {
+        // We over delete all the inferred triples that might depend on the removed one
+        let mut deleted = vec![triple];
+        let mut seen = HashSet::new();
+        seen.insert(triple);
+        let mut i = 0;
+        while i < deleted.len() {
+            for consequence in self.consequences(store, deleted[i])? {
+                if !seen.contains(&consequence) && store.contains_inferred(&consequence)? {
+                    seen.insert(consequence);
+                    deleted.push(consequence);
+                }
+            }
+            i += 1;
+        }
+        for triple in &deleted[1..] {
+            store.remove_inferred(triple)?;
+        }
+
+        // We rederive the ones that are still derivable
+        let mut rederived = Vec::new();
+        for triple in deleted {
+            if !store.contains_asserted(&triple)?
+                && !store.contains_inferred(&triple)?
+                && self.is_derivable(store, triple)?
+            {
+                store.insert_inferred(&triple)?;
+                rederived.push(triple);
+            }
+        }
+        self.propagate(store, rederived)
+    }
+
+    /// Adds the consequences of the given triples until a fix point is reached
+    fn propagate<S: InferenceStore<StrId = I>>(
+        &self,
+        store: &mut S,
+        mut queue: Vec<EncodedTriple<I>>,
+    ) -> Result<(), S::Error> {
+        while let Some(triple) = queue.pop() {
+            for consequence in self.consequences(store, triple)? {
+                if !store.contains_asserted(&consequence)?
+                    && !store.contains_inferred(&consequence)?
+                {
+                    store.insert_inferred(&consequence)?;
+                    queue.push(consequence);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the triples derived in one step using the given triple and the current closure
+    fn consequences<S: InferenceStore<StrId = I>>(
+        &self,
+        store: &S,
+        triple: EncodedTriple<I>,
+    ) -> Result<Vec<EncodedTriple<I>>, S::Error> {
+        let mut results = Vec::new();
+        for rule in &self.rules {
+            for (i, atom) in rule.body.iter().enumerate() {
+                if let Some(bindings) = unify(atom, &triple, [None; MAX_VARIABLES]) {
+                    let others = rule
+                        .body
+                        .iter()
+                        .enumerate()
+                        .filter(|(j, _)| *j != i)
+                        .map(|(_, atom)| atom)
+                        .collect::<Vec<_>>();
+                    for solution in solve(store, &others, bindings)? {
+                        results.extend(rule.head.iter().filter_map(|h| instantiate(h, &solution)));
+                    }
+                }
+            }
+        }
+        self.add_chain_consequences(store, triple, &mut results)?;
+        Ok(results)
+    }
+
+    /// Returns if the triple is derivable in one step from the current closure
+    fn is_derivable<S: InferenceStore<StrId = I>>(
+        &self,
+        store: &S,
+        triple: EncodedTriple<I>,
+    ) -> Result<bool, S::Error> {
+        for rule in &self.rules {
+            for head in &rule.head {
+                if let Some(bindings) = unify(head, &triple, [None; MAX_VARIABLES]) {
+                    let body = rule.body.iter().collect::<Vec<_>>();
+                    if !solve(store, &body, bindings)?.is_empty() {
+                        return Ok(true);
+                    }
+                }
+            }
+        }
+        let [subject, predicate, object] = triple;
+        for (property, chain) in self.chains(store)? {
+            if property == predicate && walk(store, subject, &chain, true)?.contains(&object) {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// prp-spo2: the property chains
+    fn add_chain_consequences<S: InferenceStore<StrId = I>>(
+        &self,
+        store: &S,
+        triple: EncodedTriple<I>,
+        results: &mut Vec<EncodedTriple<I>>,
+    ) -> Result<(), S::Error> {
+        let [subject, predicate, object] = triple;
+        if predicate == self.lists.property_chain_axiom {
+            // A new chain: all its instances
+            let chain = self.list(store, object)?;
+            add_chain_instances(store, subject, &chain, results)?;
+        } else if predicate == self.lists.first || predicate == self.lists.rest {
+            // The chains might have changed
+            for (property, chain) in self.chains(store)? {
+                add_chain_instances(store, property, &chain, results)?;
+            }
+        } else {
+            for (property, chain) in self.chains(store)? {
+                for (i, link) in chain.iter().enumerate() {
+                    if *link != predicate {
+                        continue;
+                    }
+                    let ends = walk(store, object, &chain[i + 1..], true)?;
+                    for start in walk(store, subject, &chain[..i], false)? {
+                        for end in &ends {
+                            results.extend(valid_triple([start, property, *end]));
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the declared property chains
+    fn chains<S: InferenceStore<StrId = I>>(
+        &self,
+        store: &S,
+    ) -> Result<Vec<(EncodedTerm<I>, Vec<EncodedTerm<I>>)>, S::Error> {
+        store
+            .closure_triples_for_pattern(None, Some(self.lists.property_chain_axiom), None)?
+            .into_iter()
+            .map(|[property, _, list]| Ok((property, self.list(store, list)?)))
+            .filter(|chain| chain.as_ref().map_or(true, |(_, chain)| !chain.is_empty()))
+            .collect()
+    }
+
+    /// Reads a RDF list, stopping at the first malformed node
+    fn list<S: InferenceStore<StrId = I>>(
+        &self,
+        store: &S,
+        mut node: EncodedTerm<I>,
+    ) -> Result<Vec<EncodedTerm<I>>, S::Error> {
+        let mut elements = Vec::new();
+        let mut visited = HashSet::new();
+        while node != self.lists.nil && visited.insert(node) {
+            let first =
+                store.closure_triples_for_pattern(Some(node), Some(self.lists.first), None)?;
+            let rest =
+                store.closure_triples_for_pattern(Some(node), Some(self.lists.rest), None)?;
+            match (first.as_slice(), rest.as_slice()) {
+                ([[_, _, first]], [[_, _, rest]]) => {
+                    elements.push(*first);
+                    node = *rest;
+                }
+                _ => return Ok(Vec::new()),
+            }
+        }
+        Ok(elements)
+    }
+}
+
+/// Adds all the `(start, property, end)` triples such that `end` is reachable from `start` following the chain
+fn add_chain_instances<S: InferenceStore>(
+    store: &S,
+    property: EncodedTerm<S::StrId>,
+    chain: &[EncodedTerm<S::StrId>],
+    results: &mut Vec<EncodedTriple<S::StrId>>,
+) -> Result<(), S::Error> {
+    if let Some((link, others)) = chain.split_first() {
+        for [start, _, next] in store.closure_triples_for_pattern(None, Some(*link), None)? {
+            for end in walk(store, next, others, true)? {
+                results.extend(valid_triple([start, property, end]));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Returns the nodes reachable from `start` following the chain of properties, backward if `forward` is false
+fn walk<S: InferenceStore>(
+    store: &S,
+    start: EncodedTerm<S::StrId>,
+    chain: &[EncodedTerm<S::StrId>],
+    forward: bool,
+) -> Result<Vec<EncodedTerm<S::StrId>>, S::Error> {
+    let mut current = vec![start];
+    let mut links = chain.to_vec();
+    if !forward {
+        links.reverse();
+    }
+    for link in links {
+        let mut next = Vec::new();
+        for node in current {
+            let triples = if forward {
+                store.closure_triples_for_pattern(Some(node), Some(link), None)?
+            } else {
+                store.closure_triples_for_pattern(None, Some(link), Some(node))?
+            };
+            for [subject, _, object] in triples {
+                let node = if forward { object } else { subject };
+                if !next.contains(&node) {
+                    next.push(node);
+                }
+            }
+        }
+        current = next;
+    }
+    Ok(current)
+}
+
+/// Returns all the bindings extending `bindings` such that all the atoms are in the closure
+fn solve<S: InferenceStore>(
+    store: &S,
+    atoms: &[&Atom<S::StrId>],
+    bindings: Bindings<S::StrId>,
+) -> Result<Vec<Bindings<S::StrId>>, S::Error> {
+    let (atom, others) = if let Some(split) = atoms.split_first() {
+        split
+    } else {
+        return Ok(vec![bindings]);
+    };
+    let value = |slot: &Slot<S::StrId>| match slot {
+        Slot::Variable(i) => bindings[*i],
+        Slot::Constant(term) => Some(*term),
+    };
+    let mut solutions = Vec::new();
+    for triple in
+        store.closure_triples_for_pattern(value(&atom[0]), value(&atom[1]), value(&atom[2]))?
+    {
+        if let Some(bindings) = unify(atom, &triple, bindings) {
+            solutions.extend(solve(store, others, bindings)?);
+        }
+    }
+    Ok(solutions)
+}
+
+fn unify<I: StrId>(
+    atom: &Atom<I>,
+    triple: &EncodedTriple<I>,
+    mut bindings: Bindings<I>,
+) -> Option<Bindings<I>> {
+    for (slot, term) in atom.iter().zip(triple) {
+        match slot {
+            Slot::Variable(i) => match bindings[*i] {
+                Some(value) if value != *term => return None,
+                Some(_) => (),
+                None => bindings[*i] = Some(*term),
+            },
+            Slot::Constant(value) => {
+                if value != term {
+                    return None;
+                }
+            }
+        }
+    }
+    Some(bindings)
+}
+
+fn instantiate<I: StrId>(atom: &Atom<I>, bindings: &Bindings<I>) -> Option<EncodedTriple<I>> {
+    let value = |slot: &Slot<I>| match slot {
+        Slot::Variable(i) => bindings[*i],
+        Slot::Constant(term) => Some(*term),
+    };
+    valid_triple([value(&atom[0])?, value(&atom[1])?, value(&atom[2])?])
+}
+
+/// Filters out the generalized triples like the ones with a literal subject
+fn valid_triple<I: StrId>(triple: EncodedTriple<I>) -> Option<EncodedTriple<I>> {
+    let [subject, predicate, _] = triple;
+    if subject.is_literal() || !predicate.is_named_node() {
+        None
+    } else {
+        Some(triple)
+    }
+}
@@ -1,19 +1,30 @@
 //! Store based on the [RocksDB](https://rocksdb.org/) key-value database.
 
-use crate::error::invalid_data_error;
-use crate::io::{DatasetFormat, GraphFormat};
+use crate::error::{invalid_data_error, invalid_input_error};
+use crate::io::detection::open_rdf_file;
+use crate::io::{
+    DatasetParser, DatasetSerializer, GraphParser, GraphSerializer, PrefixMap, RdfFormat,
+};
 use crate::model::*;
+use crate::shacl::ShaclValidator;
 use crate::sparql::{
-    evaluate_query, evaluate_update, EvaluationError, Query, QueryOptions, QueryResults, Update,
-    UpdateOptions,
+    evaluate_query, evaluate_update, prepare_query, EvaluationError, PreparedQuery, Query,
+    QueryCache, QueryOptions, QueryResults, ServiceDescription, Update, UpdateOptions,
 };
 use crate::store::binary_encoder::*;
 use crate::store::numeric_encoder::{
-    Decoder, ReadEncoder, StrContainer, StrEncodingAware, StrLookup, WriteEncoder,
+    Decoder, EncodedRange, RangeKey, ReadEncoder, StrContainer, StrEncodingAware, StrLookup,
+    WriteEncoder,
+};
+use crate::store::reasoning::{EncodedTriple, InferenceStore, Owl2RlReasoner};
+use crate::store::rules::{
+    into_io_error, refresh_view, refresh_views, Rule, RuleChanges, RuleStore,
 };
+use crate::store::text::encoded_term_tokens;
 use crate::store::{
-    dump_dataset, dump_graph, get_encoded_quad_pattern, load_dataset, load_graph,
-    ReadableEncodedStore, WritableEncodedStore,
+    dump_canonical_dataset, dump_dataset, dump_graph, get_encoded_quad_pattern, load_dataset,
+    load_graph, load_hdt, read_validated_graph, ReadableEncodedStore, StoreOptions,
+    WritableEncodedStore,
 };
 use rocksdb::*;
 use std::collections::HashMap;
@@ -23,7 +34,7 @@ use std::io::{BufRead, Write};
 use std::iter::{once, Once};
 use std::mem::{take, transmute};
 use std::path::Path;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, RwLock};
 use std::{fmt, str};
 
 /// Store based on the [RocksDB](https://rocksdb.org/) key-value database.
@@ -62,6 +73,12 @@ use std::{fmt, str};
 #[derive(Clone)]
 pub struct RocksDbStore {
     db: Arc<DB>,
+    range_index: bool,
+    text_index: bool,
+    reasoner: Option<Arc<Owl2RlReasoner<StrHash>>>,
+    rules: Arc<RwLock<Vec<Rule>>>,
+    rule_changes: Arc<Mutex<Option<RuleChanges<StrHash>>>>,
+    query_cache: Arc<QueryCache>,
 }
 
 type EncodedTerm = crate::store::numeric_encoder::EncodedTerm<StrHash>;
@@ -78,24 +95,59 @@ const DSPO_CF: &str = "dspo";
 const DPOS_CF: &str = "dpos";
 const DOSP_CF: &str = "dosp";
 const GRAPHS_CF: &str = "graphs";
+const RANGE_CF: &str = "range";
+const TEXT_CF: &str = "text";
+const RULES_CF: &str = "rules";
 
-const COLUMN_FAMILIES: [&str; 11] = [
+const COLUMN_FAMILIES: [&str; 14] = [
     ID2STR_CF, SPOG_CF, POSG_CF, OSPG_CF, GSPO_CF, GPOS_CF, GOSP_CF, DSPO_CF, DPOS_CF, DOSP_CF,
-    GRAPHS_CF,
+    GRAPHS_CF, RANGE_CF, TEXT_CF, RULES_CF,
 ];
 
 const MAX_TRANSACTION_SIZE: usize = 1024;
 
 impl RocksDbStore {
     /// Opens a [`RocksDbStore`]()
+    ///
+    /// The optional indexes and the OWL 2 RL materialization are maintained if they have been enabled previously using [`RocksDbStore::open_with_options()`].
     pub fn open(path: impl AsRef<Path>) -> Result<Self, io::Error> {
-        let mut options = Options::default();
-        options.create_if_missing(true);
-        options.create_missing_column_families(true);
-        options.set_compaction_style(DBCompactionStyle::Universal);
+        Self::open_with_options(path, StoreOptions::default())
+    }
 
-        let this = Self {
-            db: Arc::new(DB::open_cf(&options, path, &COLUMN_FAMILIES).map_err(map_err)?),
+    /// Opens a [`RocksDbStore`]() maintaining the optional indexes and inferences enabled in `options`.
+    ///
+    /// If the store already exists, the newly enabled indexes and inferences are built from its content.
+    /// Once enabled they are kept up to date even if the store is opened using [`RocksDbStore::open()`].
+    /// The OWL 2 RL inferences are updated when the writes to the default graph are applied.
+    ///
+    /// See [`SledStore`](super::sled::SledStore::open_with_options()) for a usage example.
+    pub fn open_with_options(
+        path: impl AsRef<Path>,
+        options: StoreOptions,
+    ) -> Result<Self, io::Error> {
+        Self::do_open(path, options)
+    }
+
+    fn do_open(path: impl AsRef<Path>, options: StoreOptions) -> Result<Self, io::Error> {
+        if options.spatial_index {
+            return Err(invalid_input_error(
+                "The spatial index is only supported by MemoryStore",
+            ));
+        }
+        let mut db_options = Options::default();
+        db_options.create_if_missing(true);
+        db_options.create_missing_column_families(true);
+        db_options.set_compaction_style(DBCompactionStyle::Universal);
+
+        let db = DB::open_cf(&db_options, path, &COLUMN_FAMILIES).map_err(map_err)?;
+        let mut this = Self {
+            range_index: db.get("oxrangeindex").map_err(map_err)?.is_some(),
+            text_index: db.get("oxtextindex").map_err(map_err)?.is_some(),
+            reasoner: None,
+            rules: Arc::default(),
+            rule_changes: Arc::default(),
+            query_cache: Arc::default(),
+            db: Arc::new(db),
         };
 
         let mut version = this.ensure_version()?;
@@ -114,17 +166,38 @@ impl RocksDbStore {
             this.flush()?;
         }
 
+        if version == 1 {
+            // We migrate to v2: the quoted triples are a new term type, the existing terms are not changed
+            version = 2;
+            this.set_version(version)?;
+            this.flush()?;
+        }
+
         match version {
-            _ if version < LATEST_STORAGE_VERSION => Err(invalid_data_error(format!(
+            _ if version < LATEST_STORAGE_VERSION => return Err(invalid_data_error(format!(
                 "The RocksDB database is using the outdated encoding version {}. Automated migration is not supported, please dump the store dataset using a compatible Oxigraph version and load it again using the current version",
                 version
             ))),
-            LATEST_STORAGE_VERSION => Ok(this),
-            _ => Err(invalid_data_error(format!(
+            LATEST_STORAGE_VERSION => (),
+            _ => return Err(invalid_data_error(format!(
                 "The RocksDB database is using the too recent version {}. Upgrade to the latest Oxigraph version to load this database",
                 version
             )))
         }
+
+        this.load_rules()?;
+
+        if options.range_index && !this.range_index {
+            this.build_range_index()?;
+        }
+        if options.text_index && !this.text_index {
+            this.build_text_index()?;
+        }
+        if options.owl2rl_reasoning || this.db.get("oxowl2rl").map_err(map_err)?.is_some() {
+            this.enable_owl2rl_reasoning()?;
+        }
+
+        Ok(this)
     }
 
     pub fn open_readonly(path: impl AsRef<Path>) -> Result<Self, io::Error> {
@@ -133,8 +206,16 @@ impl RocksDbStore {
         options.create_missing_column_families(true);
         options.set_compaction_style(DBCompactionStyle::Universal);
 
+        let db =
+            DB::open_cf_for_read_only(&options, path, &COLUMN_FAMILIES, false).map_err(map_err)?;
         let this = Self {
-            db: Arc::new(DB::open_cf_for_read_only(&options, path, &COLUMN_FAMILIES, false).map_err(map_err)?),
+            range_index: db.get("oxrangeindex").map_err(map_err)?.is_some(),
+            text_index: db.get("oxtextindex").map_err(map_err)?.is_some(),
+            reasoner: None,
+            rules: Arc::default(),
+            rule_changes: Arc::default(),
+            query_cache: Arc::default(),
+            db: Arc::new(db),
         };
 
         let mut version = this.ensure_version()?;
@@ -153,6 +234,13 @@ impl RocksDbStore {
             this.flush()?;
         }
 
+        if version == 1 {
+            // We migrate to v2: the quoted triples are a new term type, the existing terms are not changed
+            version = 2;
+            this.set_version(version)?;
+            this.flush()?;
+        }
+
         match version {
             _ if version < LATEST_STORAGE_VERSION => Err(invalid_data_error(format!(
                 "The RocksDB database is using the outdated encoding version {}. Automated migration is not supported, please dump the store dataset using a compatible Oxigraph version and load it again using the current version",
@@ -185,6 +273,100 @@ impl RocksDbStore {
             .map_err(map_err)
     }
 
+    fn build_range_index(&mut self) -> Result<(), io::Error> {
+        self.range_index = true;
+        let mut transaction = self.auto_batch_writer();
+        for quad in self.encoded_quads_for_pattern(None, None, None, None) {
+            let quad = quad?;
+            if let Some(key) = RangeKey::new(quad.object) {
+                write_pvosg_quad(&mut transaction.buffer, key, &quad);
+                transaction
+                    .batch
+                    .put_cf(self.range_cf(), &transaction.buffer, &[]);
+                transaction.buffer.clear();
+                transaction.apply_if_big()?;
+            }
+        }
+        transaction.apply()?;
+        self.db.put("oxrangeindex", &[]).map_err(map_err)
+    }
+
+    fn build_text_index(&mut self) -> Result<(), io::Error> {
+        self.text_index = true;
+        let mut transaction = self.auto_batch_writer();
+        for quad in self.encoded_quads_for_pattern(None, None, None, None) {
+            let quad = quad?;
+            for token in encoded_term_tokens(self, quad.object)? {
+                write_ptosg_quad(&mut transaction.buffer, &token, &quad);
+                transaction
+                    .batch
+                    .put_cf(self.text_cf(), &transaction.buffer, &[]);
+                transaction.buffer.clear();
+            }
+            transaction.apply_if_big()?;
+        }
+        transaction.apply()?;
+        self.db.put("oxtextindex", &[]).map_err(map_err)
+    }
+
+    fn enable_owl2rl_reasoning(&mut self) -> Result<(), io::Error> {
+        let mut writer = self.auto_batch_writer();
+        let reasoner = Arc::new(Owl2RlReasoner::new(|node| writer.encode_named_node(node))?);
+        writer.apply()?;
+        self.reasoner = Some(reasoner.clone());
+        if self.db.get("oxowl2rl").map_err(map_err)?.is_none() {
+            let asserted = self
+                .quads_for_graph(EncodedTerm::DefaultGraph)
+                .map(|quad| quad.map(|quad| [quad.subject, quad.predicate, quad.object]))
+                .collect::<Result<Vec<_>, _>>()?;
+            let mut writer = self.auto_batch_writer();
+            writer.clear_encoded_graph(reasoner.inferred_graph())?;
+            writer.apply()?;
+            let mut this = &*self;
+            reasoner.materialize(&mut this, asserted)?;
+            self.db.put("oxowl2rl", &[]).map_err(map_err)?;
+        }
+        Ok(())
+    }
+
+    /// Updates the OWL 2 RL materialization after the insertions (`true`) and removals (`false`) of default graph quads have been written
+    fn update_reasoning(&self, changes: Vec<(EncodedQuad, bool)>) -> Result<(), io::Error> {
+        if let Some(reasoner) = &self.reasoner {
+            let mut this = self;
+            for (quad, inserted) in changes {
+                let triple = [quad.subject, quad.predicate, quad.object];
+                let asserted = this.contains_asserted(&triple)?;
+                if inserted && asserted {
+                    reasoner.on_insert(&mut this, triple)?;
+                } else if !inserted && !asserted {
+                    reasoner.on_remove(&mut this, triple)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn load_rules(&mut self) -> Result<(), io::Error> {
+        let mut rules = Vec::new();
+        for (name, value) in self
+            .db
+            .full_iterator_cf(self.rules_cf(), IteratorMode::Start)
+        {
+            let name = str::from_utf8(&name).map_err(invalid_data_error)?;
+            let value = str::from_utf8(&value).map_err(invalid_data_error)?;
+            let (target_graph, query) = value
+                .split_once('\n')
+                .ok_or_else(|| invalid_data_error(format!("Invalid stored rule {}", name)))?;
+            let target_graph = NamedNode::new(target_graph).map_err(invalid_data_error)?;
+            rules.push(Rule::new(name, query, target_graph).map_err(invalid_data_error)?);
+        }
+        if !rules.is_empty() {
+            self.rules = Arc::new(RwLock::new(rules));
+            self.rule_changes = Arc::new(Mutex::new(Some(RuleChanges::default())));
+        }
+        Ok(())
+    }
+
     fn flush(&self) -> Result<(), io::Error> {
         let mut options = FlushOptions::new();
         options.set_wait(true);
@@ -208,7 +390,51 @@ impl RocksDbStore {
         query: impl TryInto<Query, Error = impl Into<EvaluationError>>,
         options: QueryOptions,
     ) -> Result<QueryResults, EvaluationError> {
-        evaluate_query(self.clone(), query, options)
+        self.query_cache.evaluate(
+            query.try_into().map_err(|e| e.into())?,
+            options,
+            |query, options| evaluate_query(self.clone(), query, options),
+        )
+    }
+
+    /// Prepares a [SPARQL 1.1 query](https://www.w3.org/TR/sparql11-query/) to be executed many times with different variable bindings.
+    ///
+    /// See [`MemoryStore`](super::memory::MemoryStore::prepare_query()) for a usage example.
+    pub fn prepare_query(
+        &self,
+        query: impl TryInto<Query, Error = impl Into<EvaluationError>>,
+    ) -> Result<PreparedQuery, EvaluationError> {
+        self.prepare_query_opt(query, QueryOptions::default())
+    }
+
+    /// Prepares a [SPARQL 1.1 query](https://www.w3.org/TR/sparql11-query/) with some options.
+    pub fn prepare_query_opt(
+        &self,
+        query: impl TryInto<Query, Error = impl Into<EvaluationError>>,
+        options: QueryOptions,
+    ) -> Result<PreparedQuery, EvaluationError> {
+        prepare_query(self.clone(), query, options)
+    }
+
+    /// Sets the maximal number of query results kept in the store query results cache.
+    ///
+    /// The cache is disabled by default and is shared by all the clones of the store.
+    /// All the cached results are dropped after each write to the store.
+    ///
+    /// See [`MemoryStore`](super::memory::MemoryStore::set_query_cache_capacity()) for a description of the cache and a usage example.
+    pub fn set_query_cache_capacity(&self, capacity: usize) {
+        self.query_cache.set_capacity(capacity)
+    }
+
+    /// Returns a [SPARQL 1.1 Service Description](https://www.w3.org/TR/sparql11-service-description/) of the store.
+    ///
+    /// The number of quads is not described because counting them requires a full scan.
+    /// It might be added with [`ServiceDescription::with_triples_count`].
+    ///
+    /// See [`MemoryStore`](super::memory::MemoryStore::service_description()) for a usage example.
+    pub fn service_description(&self) -> Result<ServiceDescription, io::Error> {
+        Ok(ServiceDescription::default()
+            .with_dataset(self.named_graphs().collect::<Result<Vec<_>, _>>()?))
     }
 
     /// Retrieves quads with a filter on each quad component
@@ -216,7 +442,7 @@ impl RocksDbStore {
     /// See [`MemoryStore`](super::memory::MemoryStore::quads_for_pattern()) for a usage example.
     pub fn quads_for_pattern(
         &self,
-        subject: Option<NamedOrBlankNodeRef<'_>>,
+        subject: Option<SubjectRef<'_>>,
         predicate: Option<NamedNodeRef<'_>>,
         object: Option<TermRef<'_>>,
         graph_name: Option<GraphNameRef<'_>>,
@@ -296,6 +522,7 @@ impl RocksDbStore {
         update: impl TryInto<Update, Error = impl Into<EvaluationError>>,
         options: UpdateOptions,
     ) -> Result<(), EvaluationError> {
+        let _invalidation = self.query_cache.invalidation();
         let mut writer = self.auto_batch_writer();
         evaluate_update(
             self.clone(),
@@ -303,7 +530,8 @@ impl RocksDbStore {
             update.try_into().map_err(|e| e.into())?,
             options,
         )?;
-        Ok(writer.apply()?)
+        writer.apply()?;
+        Ok(self.refresh_rules()?)
     }
 
     /// Executes an ACID transaction.
@@ -318,14 +546,17 @@ impl RocksDbStore {
         &'a self,
         f: impl FnOnce(&mut RocksDbTransaction<'a>) -> Result<(), E>,
     ) -> Result<(), E> {
+        let _invalidation = self.query_cache.invalidation();
         let mut transaction = RocksDbTransaction {
             store: self,
             batch: WriteBatch::default(),
             buffer: Vec::new(),
             new_strings: HashMap::new(),
+            reasoning_changes: Vec::new(),
         };
         f(&mut transaction)?;
-        Ok(transaction.apply()?)
+        transaction.apply()?;
+        Ok(self.refresh_rules()?)
     }
 
     /// Loads a graph file (i.e. triples) into the store
@@ -341,19 +572,74 @@ impl RocksDbStore {
     pub fn load_graph<'a>(
         &self,
         reader: impl BufRead,
-        format: GraphFormat,
+        format: impl Into<GraphParser>,
         to_graph_name: impl Into<GraphNameRef<'a>>,
         base_iri: Option<&str>,
     ) -> Result<(), io::Error> {
+        self.load_graph_with_prefixes(reader, format, to_graph_name, base_iri)?;
+        Ok(())
+    }
+
+    /// Loads a graph file (i.e. triples) into the store like [`load_graph`](RocksDbStore::load_graph())
+    /// and returns the prefixes and the base IRI declared in the file.
+    ///
+    /// See [`MemoryStore`](super::memory::MemoryStore::load_graph_with_prefixes()) for a usage example.
+    pub fn load_graph_with_prefixes<'a>(
+        &self,
+        reader: impl BufRead,
+        format: impl Into<GraphParser>,
+        to_graph_name: impl Into<GraphNameRef<'a>>,
+        base_iri: Option<&str>,
+    ) -> Result<PrefixMap, io::Error> {
+        let _invalidation = self.query_cache.invalidation();
         let mut transaction = self.auto_batch_writer();
-        load_graph(
+        let prefixes = load_graph(
             &mut transaction,
             reader,
-            format,
+            format.into(),
             to_graph_name.into(),
             base_iri,
         )?;
-        Ok(transaction.apply()?)
+        transaction.apply()?;
+        self.refresh_rules()?;
+        Ok(prefixes)
+    }
+
+    /// Loads a graph file (i.e. triples) into the store if the resulting graph conforms to SHACL shapes.
+    ///
+    /// The existing content of the target graph and the loaded triples are validated together
+    /// and the triples are then written in a single batch.
+    /// Nothing is written if the validation fails.
+    ///
+    /// Errors related to parameter validation like the base IRI use the [`InvalidInput`](std::io::ErrorKind::InvalidInput) error kind.
+    /// Errors related to a bad syntax in the loaded file or to a non conforming graph use the [`InvalidData`](std::io::ErrorKind::InvalidData) or [`UnexpectedEof`](std::io::ErrorKind::UnexpectedEof) error kinds.
+    /// Errors related to data loading into the store use the other error kinds.
+    pub fn load_graph_with_validation<'a>(
+        &self,
+        reader: impl BufRead,
+        format: impl Into<GraphParser>,
+        to_graph_name: impl Into<GraphNameRef<'a>>,
+        base_iri: Option<&str>,
+        validator: &ShaclValidator,
+    ) -> Result<(), io::Error> {
+        let _invalidation = self.query_cache.invalidation();
+        let to_graph_name = to_graph_name.into();
+        let triples = read_validated_graph(
+            self.quads_for_pattern(None, None, None, Some(to_graph_name))
+                .map(|q| q.map(Triple::from))
+                .collect::<Result<Vec<_>, _>>()?,
+            reader,
+            format.into(),
+            base_iri,
+            validator,
+        )?;
+        let mut transaction = self.auto_batch_writer();
+        for triple in &triples {
+            let quad = transaction.encode_quad(triple.as_ref().in_graph(to_graph_name))?;
+            transaction.insert_encoded(&quad)?;
+        }
+        transaction.apply()?;
+        self.refresh_rules()
     }
 
     /// Loads a dataset file (i.e. quads) into the store.
@@ -369,30 +655,80 @@ impl RocksDbStore {
     pub fn load_dataset(
         &self,
         reader: impl BufRead,
-        format: DatasetFormat,
+        format: impl Into<DatasetParser>,
+        base_iri: Option<&str>,
+    ) -> Result<(), io::Error> {
+        let _invalidation = self.query_cache.invalidation();
+        let mut transaction = self.auto_batch_writer();
+        load_dataset(&mut transaction, reader, format.into(), base_iri)?;
+        transaction.apply()?;
+        self.refresh_rules()
+    }
+
+    /// Loads a RDF file into the store finding its format and its compression from its name or its content.
+    ///
+    /// See [`MemoryStore`](super::memory::MemoryStore::load_from_path()) for a usage example.
+    ///
+    /// Errors related to parameter validation like the base IRI or an undetectable format use the [`InvalidInput`](std::io::ErrorKind::InvalidInput) error kind.
+    /// Errors related to a bad syntax in the loaded file use the [`InvalidData`](std::io::ErrorKind::InvalidData) or [`UnexpectedEof`](std::io::ErrorKind::UnexpectedEof) error kinds.
+    /// Errors related to data loading into the store use the other error kinds.
+    pub fn load_from_path(
+        &self,
+        path: impl AsRef<Path>,
         base_iri: Option<&str>,
     ) -> Result<(), io::Error> {
+        let (reader, format) = open_rdf_file(path.as_ref())?;
+        match format {
+            RdfFormat::Graph(format) => {
+                self.load_graph(reader, format, GraphNameRef::DefaultGraph, base_iri)
+            }
+            RdfFormat::Dataset(format) => self.load_dataset(reader, format, base_iri),
+        }
+    }
+
+    /// Loads an [HDT](https://www.rdfhdt.org/) file into the store.
+    ///
+    /// The file is mapped into memory while its triples are loaded.
+    /// The terms are read directly from the HDT dictionary, without going through a textual RDF syntax.
+    /// See [`MemoryStore`](super::memory::MemoryStore::load_hdt()) for a usage example.
+    ///
+    /// Warning: This functions saves the triples in batch. If the parsing fails in the middle of the file,
+    /// only a part of it may be written. Use a (memory greedy) [transaction](RocksDbStore::transaction()) if you do not want that.
+    ///
+    /// Errors related to an invalid or unsupported HDT file use the [`InvalidData`](std::io::ErrorKind::InvalidData) or [`UnexpectedEof`](std::io::ErrorKind::UnexpectedEof) error kinds.
+    /// Errors related to data loading into the store use the other error kinds.
+    pub fn load_hdt<'a>(
+        &self,
+        path: impl AsRef<Path>,
+        to_graph_name: impl Into<GraphNameRef<'a>>,
+    ) -> Result<(), io::Error> {
+        let _invalidation = self.query_cache.invalidation();
         let mut transaction = self.auto_batch_writer();
-        load_dataset(&mut transaction, reader, format, base_iri)?;
-        Ok(transaction.apply()?)
+        load_hdt(&mut transaction, path.as_ref(), to_graph_name.into())?;
+        transaction.apply()?;
+        self.refresh_rules()
     }
 
     /// Adds a quad to this store.
     /// This operation is atomic and could not leave the store in a bad state.
     pub fn insert<'a>(&self, quad: impl Into<QuadRef<'a>>) -> Result<(), io::Error> {
+        let _invalidation = self.query_cache.invalidation();
         let mut transaction = self.auto_batch_writer();
         let quad = transaction.encode_quad(quad.into())?;
         transaction.insert_encoded(&quad)?;
-        transaction.apply()
+        transaction.apply()?;
+        self.refresh_rules()
     }
 
     /// Removes a quad from this store.
     /// This operation is atomic and could not leave the store in a bad state.
     pub fn remove<'a>(&self, quad: impl Into<QuadRef<'a>>) -> Result<(), io::Error> {
+        let _invalidation = self.query_cache.invalidation();
         if let Some(quad) = self.get_encoded_quad(quad.into())? {
             let mut transaction = self.auto_batch_writer();
             transaction.remove_encoded(&quad)?;
-            transaction.apply()
+            transaction.apply()?;
+            self.refresh_rules()
         } else {
             Ok(())
         }
@@ -404,22 +740,33 @@ impl RocksDbStore {
     pub fn dump_graph<'a>(
         &self,
         writer: impl Write,
-        format: GraphFormat,
+        format: impl Into<GraphSerializer>,
         from_graph_name: impl Into<GraphNameRef<'a>>,
     ) -> Result<(), io::Error> {
         dump_graph(
             self.quads_for_pattern(None, None, None, Some(from_graph_name.into()))
                 .map(|q| Ok(q?.into())),
             writer,
-            format,
+            &format.into(),
         )
     }
 
     /// Dumps the store into a file.
     ///    
     /// See [`MemoryStore`](super::memory::MemoryStore::dump_dataset()) for a usage example.
-    pub fn dump_dataset(&self, writer: impl Write, syntax: DatasetFormat) -> Result<(), io::Error> {
-        dump_dataset(self.iter(), writer, syntax)
+    pub fn dump_dataset(
+        &self,
+        writer: impl Write,
+        format: impl Into<DatasetSerializer>,
+    ) -> Result<(), io::Error> {
+        dump_dataset(self.iter(), writer, &format.into())
+    }
+
+    /// Dumps the store into a file as [canonical N-Quads](crate::io::write_canonical_n_quads()).
+    ///
+    /// See [`MemoryStore`](super::memory::MemoryStore::dump_canonical_dataset()) for a usage example.
+    pub fn dump_canonical_dataset(&self, writer: impl Write) -> Result<(), io::Error> {
+        dump_canonical_dataset(self.iter(), writer)
     }
 
     /// Returns all the store named graphs
@@ -452,6 +799,7 @@ impl RocksDbStore {
         &self,
         graph_name: impl Into<NamedOrBlankNodeRef<'a>>,
     ) -> Result<(), io::Error> {
+        let _invalidation = self.query_cache.invalidation();
         let mut transaction = self.auto_batch_writer();
         let graph_name = transaction.encode_named_or_blank_node(graph_name.into())?;
         transaction.insert_encoded_named_graph(graph_name)?;
@@ -465,10 +813,12 @@ impl RocksDbStore {
         &self,
         graph_name: impl Into<GraphNameRef<'a>>,
     ) -> Result<(), io::Error> {
+        let _invalidation = self.query_cache.invalidation();
         if let Some(graph_name) = self.get_encoded_graph_name(graph_name.into())? {
             let mut transaction = self.auto_batch_writer();
             transaction.clear_encoded_graph(graph_name)?;
-            transaction.apply()
+            transaction.apply()?;
+            self.refresh_rules()
         } else {
             Ok(())
         }
@@ -481,10 +831,12 @@ impl RocksDbStore {
         &self,
         graph_name: impl Into<NamedOrBlankNodeRef<'a>>,
     ) -> Result<(), io::Error> {
+        let _invalidation = self.query_cache.invalidation();
         if let Some(graph_name) = self.get_encoded_named_or_blank_node(graph_name.into())? {
             let mut transaction = self.auto_batch_writer();
             transaction.remove_encoded_named_graph(graph_name)?;
-            transaction.apply()
+            transaction.apply()?;
+            self.refresh_rules()
         } else {
             Ok(())
         }
@@ -494,9 +846,107 @@ impl RocksDbStore {
     ///
     /// See [`MemoryStore`](super::memory::MemoryStore::clear()) for a usage example.
     pub fn clear(&self) -> Result<(), io::Error> {
+        let _invalidation = self.query_cache.invalidation();
         let mut transaction = self.auto_batch_writer();
         transaction.clear()?;
-        transaction.apply()
+        transaction.apply()?;
+        self.refresh_rules()
+    }
+
+    /// Adds a rule materializing the results of a SPARQL CONSTRUCT query into a named graph.
+    ///
+    /// The rule is persisted in the database.
+    /// The target graph content is replaced by the query results and is kept up to date when the store data changes.
+    /// It should not be modified directly: it is recomputed from the rules targeting it.
+    /// A rule with the same name is replaced.
+    ///
+    /// See [`MemoryStore`](super::memory::MemoryStore::add_rule()) for a usage example.
+    pub fn add_rule(
+        &self,
+        name: impl Into<String>,
+        query: impl TryInto<Query, Error = impl Into<EvaluationError>>,
+        target_graph: impl Into<NamedNode>,
+    ) -> Result<(), EvaluationError> {
+        let _invalidation = self.query_cache.invalidation();
+        let rule = Rule::new(name, query, target_graph)?;
+        let target_graph = rule.target_graph().into_owned();
+        self.db
+            .put_cf(
+                self.rules_cf(),
+                rule.name(),
+                format!("{}\n{}", rule.target_graph().as_str(), rule.query()),
+            )
+            .map_err(map_err)?;
+        let (rules, old_target_graph) = {
+            let mut rules = self.rules.write().unwrap();
+            let old_target_graph = rules
+                .iter()
+                .position(|r| r.name() == rule.name())
+                .map(|i| rules.remove(i).target_graph().into_owned());
+            rules.push(rule);
+            self.rule_changes
+                .lock()
+                .unwrap()
+                .get_or_insert_with(RuleChanges::default);
+            (rules.clone(), old_target_graph)
+        };
+        refresh_view(self, &rules, &target_graph)?;
+        if let Some(old_target_graph) = old_target_graph {
+            refresh_view(self, &rules, &old_target_graph)?;
+        }
+        refresh_views(self, &rules)
+    }
+
+    /// Removes a rule added with [`add_rule`](RocksDbStore::add_rule()) and returns if it existed.
+    ///
+    /// The rule results are removed from its target graph.
+    pub fn remove_rule(&self, name: &str) -> Result<bool, io::Error> {
+        let _invalidation = self.query_cache.invalidation();
+        self.db.delete_cf(self.rules_cf(), name).map_err(map_err)?;
+        let (rules, removed) = {
+            let mut rules = self.rules.write().unwrap();
+            let removed = rules
+                .iter()
+                .position(|r| r.name() == name)
+                .map(|i| rules.remove(i));
+            if rules.is_empty() {
+                *self.rule_changes.lock().unwrap() = None;
+            }
+            (rules.clone(), removed)
+        };
+        if let Some(removed) = removed {
+            // The view is recomputed from the remaining rules targeting it
+            refresh_view(self, &rules, &removed.target_graph().into_owned())
+                .map_err(into_io_error)?;
+            self.refresh_rules()?;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    /// Returns the rules added with [`add_rule`](RocksDbStore::add_rule()).
+    pub fn rules(&self) -> Vec<Rule> {
+        self.rules.read().unwrap().clone()
+    }
+
+    fn refresh_rules(&self) -> Result<(), io::Error> {
+        if self.rules.read().unwrap().is_empty() {
+            return Ok(());
+        }
+        let rules = self.rules();
+        refresh_views(self, &rules).map_err(into_io_error)
+    }
+
+    /// Records an inserted or removed quad, `None` for a change that might affect any rule
+    fn record_rule_change(&self, change: Option<(&EncodedQuad, bool)>) {
+        if let Some(changes) = self.rule_changes.lock().unwrap().as_mut() {
+            if let Some((quad, inserted)) = change {
+                changes.record(quad, inserted);
+            } else {
+                changes.record_all();
+            }
+        }
     }
 
     fn id2str_cf(&self) -> &ColumnFamily {
@@ -542,11 +992,26 @@ impl RocksDbStore {
     fn graphs_cf(&self) -> &ColumnFamily {
         get_cf(&self.db, GRAPHS_CF)
     }
+
+    fn range_cf(&self) -> &ColumnFamily {
+        get_cf(&self.db, RANGE_CF)
+    }
+
+    fn text_cf(&self) -> &ColumnFamily {
+        get_cf(&self.db, TEXT_CF)
+    }
+
+    fn rules_cf(&self) -> &ColumnFamily {
+        get_cf(&self.db, RULES_CF)
+    }
+
     fn auto_batch_writer(&self) -> AutoBatchWriter<'_> {
         AutoBatchWriter {
             store: self,
             batch: WriteBatch::default(),
             buffer: Vec::default(),
+            new_strings: HashMap::default(),
+            reasoning_changes: Vec::default(),
         }
     }
 
@@ -906,6 +1371,56 @@ impl ReadableEncodedStore for RocksDbStore {
         }
     }
 
+    fn encoded_quads_for_predicate_object_range(
+        &self,
+        predicate: EncodedTerm,
+        range: EncodedRange,
+        graph_name: Option<EncodedTerm>,
+    ) -> Option<Box<dyn Iterator<Item = Result<EncodedQuad, io::Error>> + Send>> {
+        if !self.range_index {
+            return None;
+        }
+        let mut iter = self.db_iter(self.range_cf());
+        iter.iter
+            .seek(&encode_term_range_key(predicate, range.start));
+        let iter = DecodingRangeIterator {
+            iter,
+            end: prefix_successor(encode_term_range_key(predicate, range.end)),
+        };
+        Some(if let Some(graph_name) = graph_name {
+            Box::new(iter.filter(move |quad| match quad {
+                Err(_) => true,
+                Ok(quad) => quad.graph_name == graph_name,
+            }))
+        } else {
+            Box::new(iter)
+        })
+    }
+
+    fn encoded_quads_for_predicate_object_token(
+        &self,
+        predicate: EncodedTerm,
+        token: &str,
+        graph_name: Option<EncodedTerm>,
+    ) -> Option<Box<dyn Iterator<Item = Result<EncodedQuad, io::Error>> + Send>> {
+        if !self.text_index {
+            return None;
+        }
+        let iter = self.inner_quads(
+            self.text_cf(),
+            encode_term_token(predicate, token),
+            QuadEncoding::PTOSG,
+        );
+        Some(if let Some(graph_name) = graph_name {
+            Box::new(iter.filter(move |quad| match quad {
+                Err(_) => true,
+                Ok(quad) => quad.graph_name == graph_name,
+            }))
+        } else {
+            Box::new(iter)
+        })
+    }
+
     fn encoded_named_graphs(&self) -> DecodingGraphIterator {
         let mut iter = self.db_iter(self.graphs_cf());
         iter.iter.seek_to_first();
@@ -925,11 +1440,14 @@ struct AutoBatchWriter<'a> {
     store: &'a RocksDbStore,
     batch: WriteBatch,
     buffer: Vec<u8>,
+    new_strings: HashMap<StrHash, String>, // strings not written yet, only kept for the text index
+    reasoning_changes: Vec<(EncodedQuad, bool)>, // default graph changes not processed yet by the reasoner
 }
 
 impl AutoBatchWriter<'_> {
     fn apply(self) -> Result<(), io::Error> {
-        self.store.db.write(self.batch).map_err(map_err)
+        self.store.db.write(self.batch).map_err(map_err)?;
+        self.store.update_reasoning(self.reasoning_changes)
     }
 
     fn apply_if_big(&mut self) -> Result<(), io::Error> {
@@ -938,6 +1456,7 @@ impl AutoBatchWriter<'_> {
                 .db
                 .write(take(&mut self.batch))
                 .map_err(map_err)?;
+            self.new_strings.clear();
         }
         Ok(())
     }
@@ -974,18 +1493,44 @@ impl StrEncodingAware for AutoBatchWriter<'_> {
     type StrId = StrHash;
 }
 
+impl StrLookup for AutoBatchWriter<'_> {
+    fn get_str(&self, id: StrHash) -> Result<Option<String>, io::Error> {
+        if let Some(str) = self.new_strings.get(&id) {
+            Ok(Some(str.clone()))
+        } else {
+            self.store.get_str(id)
+        }
+    }
+
+    fn get_str_id(&self, value: &str) -> Result<Option<StrHash>, io::Error> {
+        let id = StrHash::new(value);
+        Ok(if self.new_strings.contains_key(&id) {
+            Some(id)
+        } else {
+            self.store.get_str_id(value)?
+        })
+    }
+}
+
 impl StrContainer for AutoBatchWriter<'_> {
     fn insert_str(&mut self, value: &str) -> Result<StrHash, io::Error> {
         let key = StrHash::new(value);
         self.batch
             .put_cf(self.store.id2str_cf(), &key.to_be_bytes(), value);
+        if self.store.text_index {
+            self.new_strings.insert(key, value.to_owned());
+        }
         Ok(key)
     }
 }
 
 impl WritableEncodedStore for AutoBatchWriter<'_> {
     fn insert_encoded(&mut self, quad: &EncodedQuad) -> Result<(), io::Error> {
+        self.store.record_rule_change(Some((quad, true)));
         if quad.graph_name.is_default_graph() {
+            if self.store.reasoner.is_some() {
+                self.reasoning_changes.push((*quad, true));
+            }
             write_spo_quad(&mut self.buffer, quad);
             self.batch.put_cf(self.store.dspo_cf(), &self.buffer, &[]);
             self.buffer.clear();
@@ -1027,11 +1572,31 @@ impl WritableEncodedStore for AutoBatchWriter<'_> {
             self.buffer.clear();
         }
 
+        if self.store.range_index {
+            if let Some(key) = RangeKey::new(quad.object) {
+                write_pvosg_quad(&mut self.buffer, key, quad);
+                self.batch.put_cf(self.store.range_cf(), &self.buffer, &[]);
+                self.buffer.clear();
+            }
+        }
+
+        if self.store.text_index {
+            for token in encoded_term_tokens(self, quad.object)? {
+                write_ptosg_quad(&mut self.buffer, &token, quad);
+                self.batch.put_cf(self.store.text_cf(), &self.buffer, &[]);
+                self.buffer.clear();
+            }
+        }
+
         self.apply_if_big()
     }
 
     fn remove_encoded(&mut self, quad: &EncodedQuad) -> Result<(), io::Error> {
+        self.store.record_rule_change(Some((quad, false)));
         if quad.graph_name.is_default_graph() {
+            if self.store.reasoner.is_some() {
+                self.reasoning_changes.push((*quad, false));
+            }
             write_spo_quad(&mut self.buffer, quad);
             self.batch.delete_cf(self.store.dspo_cf(), &self.buffer);
             self.buffer.clear();
@@ -1069,6 +1634,22 @@ impl WritableEncodedStore for AutoBatchWriter<'_> {
             self.buffer.clear();
         }
 
+        if self.store.range_index {
+            if let Some(key) = RangeKey::new(quad.object) {
+                write_pvosg_quad(&mut self.buffer, key, quad);
+                self.batch.delete_cf(self.store.range_cf(), &self.buffer);
+                self.buffer.clear();
+            }
+        }
+
+        if self.store.text_index {
+            for token in encoded_term_tokens(self, quad.object)? {
+                write_ptosg_quad(&mut self.buffer, &token, quad);
+                self.batch.delete_cf(self.store.text_cf(), &self.buffer);
+                self.buffer.clear();
+            }
+        }
+
         self.apply_if_big()
     }
 
@@ -1080,9 +1661,34 @@ impl WritableEncodedStore for AutoBatchWriter<'_> {
 
     fn clear_encoded_graph(&mut self, graph_name: EncodedTerm) -> Result<(), io::Error> {
         if graph_name.is_default_graph() {
+            if self.store.range_index {
+                for quad in self.store.quads_for_graph(graph_name) {
+                    let quad = quad?;
+                    if let Some(key) = RangeKey::new(quad.object) {
+                        write_pvosg_quad(&mut self.buffer, key, &quad);
+                        self.batch.delete_cf(self.store.range_cf(), &self.buffer);
+                        self.buffer.clear();
+                    }
+                }
+            }
+            if self.store.text_index {
+                for quad in self.store.quads_for_graph(graph_name) {
+                    let quad = quad?;
+                    for token in encoded_term_tokens(self.store, quad.object)? {
+                        write_ptosg_quad(&mut self.buffer, &token, &quad);
+                        self.batch.delete_cf(self.store.text_cf(), &self.buffer);
+                        self.buffer.clear();
+                    }
+                }
+            }
             self.clear_cf(self.store.dspo_cf());
             self.clear_cf(self.store.dpos_cf());
             self.clear_cf(self.store.dosp_cf());
+            self.store.record_rule_change(None);
+            if let Some(reasoner) = &self.store.reasoner {
+                // All the inferred triples are derived from the default graph
+                self.clear_encoded_graph(reasoner.inferred_graph())?;
+            }
         } else {
             for quad in self.store.quads_for_graph(graph_name) {
                 self.remove_encoded(&quad?)?;
@@ -1111,17 +1717,120 @@ impl WritableEncodedStore for AutoBatchWriter<'_> {
         self.clear_cf(self.store.dpos_cf());
         self.clear_cf(self.store.dosp_cf());
         self.clear_cf(self.store.graphs_cf());
+        self.clear_cf(self.store.range_cf());
+        self.clear_cf(self.store.text_cf());
         self.clear_cf(self.store.id2str_cf());
+        self.store.record_rule_change(None);
         self.apply_if_big()
     }
 }
 
+impl RuleStore for RocksDbStore {
+    fn take_rule_changes(&self) -> RuleChanges<StrHash> {
+        self.rule_changes
+            .lock()
+            .unwrap()
+            .as_mut()
+            .map(take)
+            .unwrap_or_default()
+    }
+
+    fn write_view_changes(&self, inserted: &[Quad], removed: &[Quad]) -> Result<(), io::Error> {
+        let mut writer = self.auto_batch_writer();
+        for quad in removed {
+            let quad = writer.encode_quad(quad.as_ref())?;
+            writer.remove_encoded(&quad)?;
+        }
+        for quad in inserted {
+            let quad = writer.encode_quad(quad.as_ref())?;
+            writer.insert_encoded(&quad)?;
+        }
+        writer.apply()
+    }
+}
+
+impl InferenceStore for &RocksDbStore {
+    fn closure_triples_for_pattern(
+        &self,
+        subject: Option<EncodedTerm>,
+        predicate: Option<EncodedTerm>,
+        object: Option<EncodedTerm>,
+    ) -> Result<Vec<EncodedTriple<StrHash>>, io::Error> {
+        let mut triples = Vec::new();
+        let mut graph_names = vec![EncodedTerm::DefaultGraph];
+        graph_names.extend(self.reasoner.as_ref().map(|r| r.inferred_graph()));
+        for graph_name in graph_names {
+            for quad in self.encoded_quads_for_pattern(subject, predicate, object, Some(graph_name))
+            {
+                let quad = quad?;
+                triples.push([quad.subject, quad.predicate, quad.object]);
+            }
+        }
+        Ok(triples)
+    }
+
+    fn contains_asserted(&self, triple: &EncodedTriple<StrHash>) -> Result<bool, io::Error> {
+        let [subject, predicate, object] = *triple;
+        self.contains_encoded(&EncodedQuad::new(
+            subject,
+            predicate,
+            object,
+            EncodedTerm::DefaultGraph,
+        ))
+    }
+
+    fn contains_inferred(&self, triple: &EncodedTriple<StrHash>) -> Result<bool, io::Error> {
+        if let Some(reasoner) = &self.reasoner {
+            let [subject, predicate, object] = *triple;
+            self.contains_encoded(&EncodedQuad::new(
+                subject,
+                predicate,
+                object,
+                reasoner.inferred_graph(),
+            ))
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn insert_inferred(&mut self, triple: &EncodedTriple<StrHash>) -> Result<(), io::Error> {
+        if let Some(reasoner) = &self.reasoner {
+            let [subject, predicate, object] = *triple;
+            let mut writer = self.auto_batch_writer();
+            writer.insert_encoded(&EncodedQuad::new(
+                subject,
+                predicate,
+                object,
+                reasoner.inferred_graph(),
+            ))?;
+            writer.apply()?;
+        }
+        Ok(())
+    }
+
+    fn remove_inferred(&mut self, triple: &EncodedTriple<StrHash>) -> Result<(), io::Error> {
+        if let Some(reasoner) = &self.reasoner {
+            let [subject, predicate, object] = *triple;
+            let mut writer = self.auto_batch_writer();
+            writer.remove_encoded(&EncodedQuad::new(
+                subject,
+                predicate,
+                object,
+                reasoner.inferred_graph(),
+            ))?;
+            writer.apply()?;
+        }
+        Ok(())
+    }
+}
+
 /// Allows inserting and deleting quads during an ACID transaction with the [`RocksDbStore`].
 pub struct RocksDbTransaction<'a> {
     store: &'a RocksDbStore,
     batch: WriteBatch,
     buffer: Vec<u8>,
     new_strings: HashMap<StrHash, String>,
+    reasoning_changes: Vec<(EncodedQuad, bool)>,
 }
 
 impl RocksDbTransaction<'_> {
@@ -1142,11 +1851,11 @@ impl RocksDbTransaction<'_> {
     pub fn load_graph<'a>(
         &mut self,
         reader: impl BufRead,
-        syntax: GraphFormat,
+        format: impl Into<GraphParser>,
         to_graph_name: impl Into<GraphNameRef<'a>>,
         base_iri: Option<&str>,
     ) -> Result<(), io::Error> {
-        load_graph(self, reader, syntax, to_graph_name.into(), base_iri)?;
+        load_graph(self, reader, format.into(), to_graph_name.into(), base_iri)?;
         Ok(())
     }
 
@@ -1167,10 +1876,10 @@ impl RocksDbTransaction<'_> {
     pub fn load_dataset(
         &mut self,
         reader: impl BufRead,
-        format: DatasetFormat,
+        format: impl Into<DatasetParser>,
         base_iri: Option<&str>,
     ) -> Result<(), io::Error> {
-        load_dataset(self, reader, format, base_iri)?;
+        load_dataset(self, reader, format.into(), base_iri)?;
         Ok(())
     }
 
@@ -1191,7 +1900,8 @@ impl RocksDbTransaction<'_> {
     }
 
     fn apply(self) -> Result<(), io::Error> {
-        self.store.db.write(self.batch).map_err(map_err)
+        self.store.db.write(self.batch).map_err(map_err)?;
+        self.store.update_reasoning(self.reasoning_changes)
     }
 }
 
@@ -1240,7 +1950,11 @@ impl StrContainer for RocksDbTransaction<'_> {
 
 impl WritableEncodedStore for RocksDbTransaction<'_> {
     fn insert_encoded(&mut self, quad: &EncodedQuad) -> Result<(), io::Error> {
+        self.store.record_rule_change(Some((quad, true)));
         if quad.graph_name.is_default_graph() {
+            if self.store.reasoner.is_some() {
+                self.reasoning_changes.push((*quad, true));
+            }
             write_spo_quad(&mut self.buffer, quad);
             self.batch.put_cf(self.store.dspo_cf(), &self.buffer, &[]);
             self.buffer.clear();
@@ -1282,11 +1996,31 @@ impl WritableEncodedStore for RocksDbTransaction<'_> {
             self.buffer.clear();
         }
 
+        if self.store.range_index {
+            if let Some(key) = RangeKey::new(quad.object) {
+                write_pvosg_quad(&mut self.buffer, key, quad);
+                self.batch.put_cf(self.store.range_cf(), &self.buffer, &[]);
+                self.buffer.clear();
+            }
+        }
+
+        if self.store.text_index {
+            for token in encoded_term_tokens(self, quad.object)? {
+                write_ptosg_quad(&mut self.buffer, &token, quad);
+                self.batch.put_cf(self.store.text_cf(), &self.buffer, &[]);
+                self.buffer.clear();
+            }
+        }
+
         Ok(())
     }
 
     fn remove_encoded(&mut self, quad: &EncodedQuad) -> Result<(), io::Error> {
+        self.store.record_rule_change(Some((quad, false)));
         if quad.graph_name.is_default_graph() {
+            if self.store.reasoner.is_some() {
+                self.reasoning_changes.push((*quad, false));
+            }
             write_spo_quad(&mut self.buffer, quad);
             self.batch.delete_cf(self.store.dspo_cf(), &self.buffer);
             self.buffer.clear();
@@ -1324,6 +2058,22 @@ impl WritableEncodedStore for RocksDbTransaction<'_> {
             self.buffer.clear();
         }
 
+        if self.store.range_index {
+            if let Some(key) = RangeKey::new(quad.object) {
+                write_pvosg_quad(&mut self.buffer, key, quad);
+                self.batch.delete_cf(self.store.range_cf(), &self.buffer);
+                self.buffer.clear();
+            }
+        }
+
+        if self.store.text_index {
+            for token in encoded_term_tokens(self, quad.object)? {
+                write_ptosg_quad(&mut self.buffer, &token, quad);
+                self.batch.delete_cf(self.store.text_cf(), &self.buffer);
+                self.buffer.clear();
+            }
+        }
+
         Ok(())
     }
 
@@ -1448,6 +2198,26 @@ impl Iterator for DecodingIndexIterator {
     }
 }
 
+struct DecodingRangeIterator {
+    iter: StaticDBRowIterator,
+    end: Option<Vec<u8>>,
+}
+
+impl Iterator for DecodingRangeIterator {
+    type Item = Result<EncodedQuad, io::Error>;
+
+    fn next(&mut self) -> Option<Result<EncodedQuad, io::Error>> {
+        let key = self.iter.key()?;
+        if self.end.as_ref().map_or(true, |end| key < end.as_slice()) {
+            let result = QuadEncoding::PVOSG.decode(key);
+            self.iter.next();
+            Some(result)
+        } else {
+            None
+        }
+    }
+}
+
 fn map_err(e: Error) -> io::Error {
     io::Error::new(io::ErrorKind::Other, e)
 }
@@ -1505,7 +2275,7 @@ fn store() -> Result<(), io::Error> {
     use std::env::temp_dir;
     use std::fs::remove_dir_all;
 
-    let main_s = NamedOrBlankNode::from(BlankNode::default());
+    let main_s = Subject::from(BlankNode::default());
     let main_p = NamedNode::new("http://example.com").unwrap();
     let main_o = Term::from(Literal::from(1));
     let main_g = GraphName::from(BlankNode::default());
@@ -0,0 +1,1106 @@
+//! User-defined rules: SPARQL CONSTRUCT queries materialized into named graphs.
+//!
+//! Each rule is a CONSTRUCT query whose results are stored in a target named graph, its view.
+//! A view is owned by the rules targeting it: after each write, the views of the rules that might be affected
+//! by the changed quads are updated.
+//! A rule is affected by a change if the predicate of a changed quad is used in its `WHERE` clause
+//! or if the rule uses a variable predicate or a negated property set.
+//!
+//! The views of the monotonic rules (basic graph patterns, `UNION`, `FILTER` without `EXISTS`, `BIND`...)
+//! are maintained from the changed quads: the rules are evaluated with the variables bound by the triple patterns
+//! matching the inserted quads and the triples that might have been derived from the removed quads are checked again.
+//! The other views, the views read by their own rules and the views affected by large writes
+//! are recomputed and only the difference with their current content is written.
+//! The views are refreshed until a fixpoint is reached, so rules can read the views of other rules using `GRAPH`.
+
+use crate::model::*;
+use crate::sparql::algebra::{
+    AggregationFunction, Expression, GraphPattern, NamedNodeOrVariable, OrderComparator,
+    PropertyPathExpression, TermOrVariable, TriplePattern,
+};
+use crate::sparql::{
+    evaluate_query, prepare_query, EvaluationError, PreparedQuery, Query, QueryOptions,
+    QueryResults, Variable,
+};
+use crate::store::numeric_encoder::{Decoder, EncodedQuad, EncodedTerm, ReadEncoder, StrId};
+use crate::store::ReadableEncodedStore;
+use std::collections::HashSet;
+use std::convert::TryInto;
+#[cfg(any(feature = "rocksdb", feature = "sled"))]
+use std::io;
+
+/// The maximal number of refresh rounds done after a write before giving up reaching a fixpoint
+const MAX_REFRESH_ROUNDS: usize = 64;
+/// The maximal number of changed quads from which the views are updated, the affected views are recomputed above
+const MAX_INCREMENTAL_CHANGES: usize = 1_000;
+
+/// A rule materializing the results of a SPARQL CONSTRUCT query into a named graph.
+///
+/// See [`MemoryStore::add_rule`](super::MemoryStore::add_rule()) for an example.
+#[derive(Eq, PartialEq, Debug, Clone)]
+pub struct Rule {
+    name: String,
+    query: Query,
+    target_graph: NamedNode,
+    /// The predicates the rule depends on, `None` if it might depend on any predicate
+    dependencies: Option<HashSet<NamedNode>>,
+}
+
+impl Rule {
+    pub(crate) fn new(
+        name: impl Into<String>,
+        query: impl TryInto<Query, Error = impl Into<EvaluationError>>,
+        target_graph: impl Into<NamedNode>,
+    ) -> Result<Self, EvaluationError> {
+        let query = query.try_into().map_err(|e| e.into())?;
+        let mut dependencies = Some(HashSet::new());
+        if let Query::Construct {
+            template, pattern, ..
+        } = &query
+        {
+            if template.iter().any(has_blank_node) {
+                return Err(EvaluationError::msg(
+                    "The CONSTRUCT template of a rule should not contain blank nodes",
+                ));
+            }
+            add_pattern_dependencies(pattern, &mut dependencies)?;
+        } else {
+            return Err(EvaluationError::msg("A rule should be a CONSTRUCT query"));
+        }
+        Ok(Self {
+            name: name.into(),
+            query,
+            target_graph: target_graph.into(),
+            dependencies,
+        })
+    }
+
+    /// The rule name
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The rule CONSTRUCT query
+    pub fn query(&self) -> &Query {
+        &self.query
+    }
+
+    /// The named graph in which the results of the rule are materialized
+    pub fn target_graph(&self) -> NamedNodeRef<'_> {
+        self.target_graph.as_ref()
+    }
+
+    fn depends_on(&self, predicate: &NamedNode) -> bool {
+        self.dependencies
+            .as_ref()
+            .map_or(true, |dependencies| dependencies.contains(predicate))
+    }
+}
+
+fn has_blank_node(triple: &TriplePattern) -> bool {
+    let is_blank_node = |term: &TermOrVariable| match term {
+        TermOrVariable::Term(Term::BlankNode(_)) => true,
+        TermOrVariable::Triple(triple) => has_blank_node(triple),
+        _ => false,
+    };
+    is_blank_node(&triple.subject) || is_blank_node(&triple.object)
+}
+
+fn add_pattern_dependencies(
+    pattern: &GraphPattern,
+    dependencies: &mut Option<HashSet<NamedNode>>,
+) -> Result<(), EvaluationError> {
+    match pattern {
+        GraphPattern::BGP(triples) => {
+            for triple in triples {
+                match &triple.predicate {
+                    NamedNodeOrVariable::NamedNode(predicate) => {
+                        if let Some(dependencies) = dependencies {
+                            dependencies.insert(predicate.clone());
+                        }
+                    }
+                    NamedNodeOrVariable::Variable(_) => *dependencies = None,
+                }
+            }
+        }
+        GraphPattern::Path { path, .. } => add_path_dependencies(path, dependencies),
+        GraphPattern::Join { left, right }
+        | GraphPattern::Union { left, right }
+        | GraphPattern::Minus { left, right } => {
+            add_pattern_dependencies(left, dependencies)?;
+            add_pattern_dependencies(right, dependencies)?;
+        }
+        GraphPattern::LeftJoin { left, right, expr } => {
+            add_pattern_dependencies(left, dependencies)?;
+            add_pattern_dependencies(right, dependencies)?;
+            if let Some(expr) = expr {
+                add_expression_dependencies(expr, dependencies)?;
+            }
+        }
+        GraphPattern::Filter { expr, inner } | GraphPattern::Extend { inner, expr, .. } => {
+            add_pattern_dependencies(inner, dependencies)?;
+            add_expression_dependencies(expr, dependencies)?;
+        }
+        GraphPattern::OrderBy { inner, condition } => {
+            add_pattern_dependencies(inner, dependencies)?;
+            for condition in condition {
+                match condition {
+                    OrderComparator::Asc(expr) | OrderComparator::Desc(expr) => {
+                        add_expression_dependencies(expr, dependencies)?
+                    }
+                }
+            }
+        }
+        GraphPattern::Group {
+            inner, aggregates, ..
+        } => {
+            add_pattern_dependencies(inner, dependencies)?;
+            for (_, aggregate) in aggregates {
+                match aggregate {
+                    AggregationFunction::Count { expr, .. } => {
+                        if let Some(expr) = expr {
+                            add_expression_dependencies(expr, dependencies)?;
+                        }
+                    }
+                    AggregationFunction::Sum { expr, .. }
+                    | AggregationFunction::Avg { expr, .. }
+                    | AggregationFunction::Min { expr, .. }
+                    | AggregationFunction::Max { expr, .. }
+                    | AggregationFunction::GroupConcat { expr, .. }
+                    | AggregationFunction::Sample { expr, .. }
+                    | AggregationFunction::Custom { expr, .. } => {
+                        add_expression_dependencies(expr, dependencies)?
+                    }
+                }
+            }
+        }
+        GraphPattern::Graph { inner, .. }
+        | GraphPattern::Project { inner, .. }
+        | GraphPattern::Distinct { inner }
+        | GraphPattern::Reduced { inner }
+        | GraphPattern::Slice { inner, .. } => add_pattern_dependencies(inner, dependencies)?,
+        GraphPattern::Table { .. } => (),
+        GraphPattern::Service { .. } => {
+            return Err(EvaluationError::msg(
+                "The results of a rule using SERVICE could not be kept up to date",
+            ))
+        }
+    }
+    Ok(())
+}
+
+fn add_path_dependencies(
+    path: &PropertyPathExpression,
+    dependencies: &mut Option<HashSet<NamedNode>>,
+) {
+    match path {
+        PropertyPathExpression::NamedNode(predicate) => {
+            if let Some(dependencies) = dependencies {
+                dependencies.insert(predicate.clone());
+            }
+        }
+        PropertyPathExpression::Reverse(path)
+        | PropertyPathExpression::ZeroOrMore(path)
+        | PropertyPathExpression::OneOrMore(path)
+        | PropertyPathExpression::ZeroOrOne(path) => add_path_dependencies(path, dependencies),
+        PropertyPathExpression::Sequence(a, b) | PropertyPathExpression::Alternative(a, b) => {
+            add_path_dependencies(a, dependencies);
+            add_path_dependencies(b, dependencies);
+        }
+        PropertyPathExpression::NegatedPropertySet(_) => *dependencies = None,
+    }
+}
+
+fn add_expression_dependencies(
+    expression: &Expression,
+    dependencies: &mut Option<HashSet<NamedNode>>,
+) -> Result<(), EvaluationError> {
+    match expression {
+        Expression::NamedNode(_)
+        | Expression::Literal(_)
+        | Expression::Variable(_)
+        | Expression::Bound(_) => (),
+        Expression::Or(a, b)
+        | Expression::And(a, b)
+        | Expression::Equal(a, b)
+        | Expression::SameTerm(a, b)
+        | Expression::Greater(a, b)
+        | Expression::GreaterOrEqual(a, b)
+        | Expression::Less(a, b)
+        | Expression::LessOrEqual(a, b)
+        | Expression::Add(a, b)
+        | Expression::Subtract(a, b)
+        | Expression::Multiply(a, b)
+        | Expression::Divide(a, b) => {
+            add_expression_dependencies(a, dependencies)?;
+            add_expression_dependencies(b, dependencies)?;
+        }
+        Expression::UnaryPlus(e) | Expression::UnaryMinus(e) | Expression::Not(e) => {
+            add_expression_dependencies(e, dependencies)?
+        }
+        Expression::In(a, list) => {
+            add_expression_dependencies(a, dependencies)?;
+            for e in list {
+                add_expression_dependencies(e, dependencies)?;
+            }
+        }
+        Expression::Coalesce(list) | Expression::FunctionCall(_, list) => {
+            for e in list {
+                add_expression_dependencies(e, dependencies)?;
+            }
+        }
+        Expression::If(a, b, c) => {
+            add_expression_dependencies(a, dependencies)?;
+            add_expression_dependencies(b, dependencies)?;
+            add_expression_dependencies(c, dependencies)?;
+        }
+        Expression::Exists(pattern) => add_pattern_dependencies(pattern, dependencies)?,
+    }
+    Ok(())
+}
+
+/// The changes done to a store since the last refresh of the views
+pub(crate) struct RuleChanges<I: StrId> {
+    predicates: HashSet<EncodedTerm<I>>,
+    /// The changed quads with `true` if they have been inserted,
+    /// `None` if there are too many of them for the views to be maintained incrementally
+    quads: Option<Vec<(EncodedQuad<I>, bool)>>,
+    all: bool,
+}
+
+impl<I: StrId> Default for RuleChanges<I> {
+    fn default() -> Self {
+        Self {
+            predicates: HashSet::new(),
+            quads: Some(Vec::new()),
+            all: false,
+        }
+    }
+}
+
+impl<I: StrId> RuleChanges<I> {
+    /// Records that a quad has been inserted or removed
+    pub fn record(&mut self, quad: &EncodedQuad<I>, inserted: bool) {
+        if self.all {
+            return;
+        }
+        self.predicates.insert(quad.predicate);
+        if matches!(&self.quads, Some(quads) if quads.len() >= MAX_INCREMENTAL_CHANGES) {
+            self.quads = None;
+        } else if let Some(quads) = &mut self.quads {
+            quads.push((*quad, inserted));
+        }
+    }
+
+    /// Records a change that might affect any rule like a graph clear
+    pub fn record_all(&mut self) {
+        self.all = true;
+        self.predicates.clear();
+        self.quads = None;
+    }
+
+    /// Adds the changes recorded somewhere else like in a transaction
+    #[cfg(feature = "sled")]
+    pub fn extend(&mut self, other: Self) {
+        if other.all {
+            self.record_all();
+        } else if let Some(quads) = other.quads {
+            for (quad, inserted) in quads {
+                self.record(&quad, inserted);
+            }
+        } else if !self.all {
+            self.predicates.extend(other.predicates);
+            self.quads = None;
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        !self.all && self.predicates.is_empty()
+    }
+}
+
+/// The store operations used to maintain the rules views
+pub(crate) trait RuleStore: ReadableEncodedStore + Decoder + Clone + 'static {
+    /// Returns the changes done since the last call and resets them
+    fn take_rule_changes(&self) -> RuleChanges<Self::StrId>;
+
+    /// Writes the changes of a view
+    fn write_view_changes(&self, inserted: &[Quad], removed: &[Quad]) -> Result<(), Self::Error>;
+}
+
+/// Refreshes the views of the rules affected by the changes done to the store until a fixpoint is reached.
+///
+/// The views are maintained from the changed quads if possible and fully recomputed else.
+/// An error is returned if the fixpoint is not reached after [`MAX_REFRESH_ROUNDS`] rounds.
+pub(crate) fn refresh_views<S: RuleStore>(store: &S, rules: &[Rule]) -> Result<(), EvaluationError>
+where
+    EvaluationError: From<S::Error>,
+{
+    for _ in 0..MAX_REFRESH_ROUNDS {
+        let changes = store.take_rule_changes();
+        if changes.is_empty() || rules.is_empty() {
+            return Ok(());
+        }
+        let mut targets = Vec::new();
+        if changes.all {
+            targets.extend(rules.iter().map(|r| r.target_graph.clone()));
+        } else {
+            let predicates = changes
+                .predicates
+                .into_iter()
+                .map(|predicate| store.decode_named_node(predicate))
+                .collect::<Result<Vec<_>, _>>()?;
+            for rule in rules {
+                if predicates.iter().any(|p| rule.depends_on(p)) {
+                    targets.push(rule.target_graph.clone());
+                }
+            }
+        }
+        let mut seen = HashSet::new();
+        targets.retain(|target| seen.insert(target.clone()));
+        let delta = if changes.all {
+            None
+        } else {
+            changes
+                .quads
+                .map(|quads| Delta::new(store, quads))
+                .transpose()?
+        };
+        for target in targets {
+            if let Some(delta) = &delta {
+                refresh_view_incrementally(store, rules, &target, delta)?;
+            } else {
+                refresh_view(store, rules, &target)?;
+            }
+        }
+    }
+    Err(EvaluationError::msg(format!(
+        "The rules views have not reached a fixpoint after {} refresh rounds",
+        MAX_REFRESH_ROUNDS
+    )))
+}
+
+/// Recomputes a view and writes the difference with its current content
+pub(crate) fn refresh_view<S: RuleStore>(
+    store: &S,
+    rules: &[Rule],
+    target: &NamedNode,
+) -> Result<(), EvaluationError>
+where
+    EvaluationError: From<S::Error>,
+{
+    let expected = view_content(store, rules, target)?;
+    let any = TriplePattern::new(
+        Variable::new_unchecked("s"),
+        Variable::new_unchecked("p"),
+        Variable::new_unchecked("o"),
+    );
+    let current = view_triples(store, target, &any, &[])?
+        .into_iter()
+        .collect::<HashSet<_>>();
+    let inserted = expected
+        .difference(&current)
+        .map(|t| t.clone().in_graph(target.clone()))
+        .collect::<Vec<_>>();
+    let removed = current
+        .difference(&expected)
+        .map(|t| t.clone().in_graph(target.clone()))
+        .collect::<Vec<_>>();
+    if !inserted.is_empty() || !removed.is_empty() {
+        store.write_view_changes(&inserted, &removed)?;
+    }
+    Ok(())
+}
+
+/// Updates a view from the quads changed since its last refresh.
+///
+/// The new triples are the results of the rules evaluated with the bindings of the triple patterns matching the inserted quads.
+/// The view triples that might have been derived from the removed quads are removed if the rules do not derive them anymore.
+fn refresh_view_incrementally<S: RuleStore>(
+    store: &S,
+    rules: &[Rule],
+    target: &NamedNode,
+    delta: &Delta,
+) -> Result<(), EvaluationError>
+where
+    EvaluationError: From<S::Error>,
+{
+    let mut view_rules = Vec::new();
+    for rule in rules.iter().filter(|r| &r.target_graph == target) {
+        if let Some(rule) = IncrementalRule::new(store, rule)? {
+            view_rules.push(rule);
+        } else {
+            return refresh_view(store, rules, target);
+        }
+    }
+
+    let mut derived = HashSet::new();
+    let mut candidates = HashSet::new();
+    for rule in &mut view_rules {
+        let mut all_bindings = HashSet::new();
+        for quad in &delta.inserted {
+            for bindings in rule.pattern_bindings(quad) {
+                all_bindings.insert(rule.restrict(bindings));
+            }
+        }
+        for bindings in all_bindings {
+            derived.extend(rule.evaluate(&bindings)?);
+        }
+        for quad in &delta.removed {
+            for bindings in rule.pattern_bindings(quad) {
+                for template in &rule.template {
+                    candidates.extend(view_triples(store, target, template, &bindings)?);
+                }
+            }
+        }
+    }
+
+    let mut removed = Vec::new();
+    for candidate in candidates {
+        if derived.contains(&candidate) {
+            continue;
+        }
+        let mut is_derived = false;
+        for rule in &mut view_rules {
+            for bindings in rule.template_bindings(&candidate).collect::<Vec<_>>() {
+                if rule.evaluate(&bindings)?.contains(&candidate) {
+                    is_derived = true;
+                    break;
+                }
+            }
+            if is_derived {
+                break;
+            }
+        }
+        if !is_derived {
+            removed.push(candidate.in_graph(target.clone()));
+        }
+    }
+    let mut inserted = Vec::new();
+    for triple in derived {
+        let quad = triple.in_graph(target.clone());
+        if !contains(store, &quad)? {
+            inserted.push(quad);
+        }
+    }
+    if !inserted.is_empty() || !removed.is_empty() {
+        store.write_view_changes(&inserted, &removed)?;
+    }
+    Ok(())
+}
+
+/// The quads inserted and removed since the last refresh that are still in this state in the store
+struct Delta {
+    inserted: HashSet<Quad>,
+    removed: HashSet<Quad>,
+}
+
+impl Delta {
+    fn new<S: RuleStore>(
+        store: &S,
+        quads: Vec<(EncodedQuad<S::StrId>, bool)>,
+    ) -> Result<Self, EvaluationError>
+    where
+        EvaluationError: From<S::Error>,
+    {
+        let mut delta = Self {
+            inserted: HashSet::new(),
+            removed: HashSet::new(),
+        };
+        for (quad, inserted) in quads {
+            // A quad inserted then removed is only considered removed and conversely
+            if contains_encoded(store, &quad)? == inserted {
+                let quad = store.decode_quad(&quad)?;
+                if inserted {
+                    delta.inserted.insert(quad);
+                } else {
+                    delta.removed.insert(quad);
+                }
+            }
+        }
+        Ok(delta)
+    }
+}
+
+type Bindings = Vec<(Variable, Term)>;
+
+/// A rule whose view could be maintained from the changed quads
+struct IncrementalRule {
+    query: PreparedQuery,
+    template: Vec<TriplePattern>,
+    /// The triple patterns of the `WHERE` clause with the graph they are matched against, `None` for the default graph
+    patterns: Vec<(TriplePattern, Option<NamedNodeOrVariable>)>,
+    /// The variables bound in all the solutions of the `WHERE` clause
+    certain_variables: HashSet<Variable>,
+}
+
+impl IncrementalRule {
+    /// Returns `None` if the rule is not monotonic or might read its own view
+    fn new<S: RuleStore>(store: &S, rule: &Rule) -> Result<Option<Self>, EvaluationError> {
+        let (template, patterns, certain_variables) = if let Query::Construct {
+            template,
+            dataset,
+            pattern,
+            ..
+        } = &rule.query
+        {
+            let mut patterns = Vec::new();
+            if !dataset.is_default_dataset()
+                || !add_incremental_patterns(pattern, None, &rule.target_graph, &mut patterns)
+            {
+                return Ok(None);
+            }
+            (template.clone(), patterns, certain_variables(pattern))
+        } else {
+            return Ok(None);
+        };
+        Ok(Some(Self {
+            query: prepare_query(
+                store.clone(),
+                rule.query.clone(),
+                QueryOptions::default().without_service_handler(),
+            )?,
+            template,
+            patterns,
+            certain_variables,
+        }))
+    }
+
+    /// The bindings of the `WHERE` clause triple patterns matching the quad
+    fn pattern_bindings<'a>(&'a self, quad: &'a Quad) -> impl Iterator<Item = Bindings> + 'a {
+        self.patterns
+            .iter()
+            .filter_map(move |(pattern, graph_name)| match_quad(pattern, graph_name.as_ref(), quad))
+    }
+
+    /// The bindings of the CONSTRUCT template triples matching the triple restricted to the certain variables
+    fn template_bindings<'a>(&'a self, triple: &'a Triple) -> impl Iterator<Item = Bindings> + 'a {
+        self.template.iter().filter_map(move |pattern| {
+            let mut bindings = Vec::new();
+            if match_triple(pattern, triple, &mut bindings) {
+                Some(self.restrict(bindings))
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Keeps only the variables bound in all the solutions: binding the other ones could add solutions
+    fn restrict(&self, bindings: Bindings) -> Bindings {
+        let mut bindings = bindings
+            .into_iter()
+            .filter(|(variable, _)| self.certain_variables.contains(variable))
+            .collect::<Vec<_>>();
+        bindings.sort_by(|(a, _), (b, _)| a.cmp(b));
+        bindings
+    }
+
+    fn evaluate(
+        &mut self,
+        bindings: &[(Variable, Term)],
+    ) -> Result<HashSet<Triple>, EvaluationError> {
+        self.query.clear_bindings();
+        for (variable, value) in bindings {
+            self.query.bind(variable.as_str(), value.clone())?;
+        }
+        if let QueryResults::Graph(triples) = self.query.exec()? {
+            triples.collect()
+        } else {
+            Err(EvaluationError::msg("A CONSTRUCT query was expected"))
+        }
+    }
+}
+
+/// Adds the triple patterns of a monotonic pattern and returns `false` if the pattern is not supported
+fn add_incremental_patterns(
+    pattern: &GraphPattern,
+    graph_name: Option<&NamedNodeOrVariable>,
+    target: &NamedNode,
+    patterns: &mut Vec<(TriplePattern, Option<NamedNodeOrVariable>)>,
+) -> bool {
+    match pattern {
+        GraphPattern::BGP(triples) => {
+            patterns.extend(triples.iter().map(|t| (t.clone(), graph_name.cloned())));
+            true
+        }
+        GraphPattern::Join { left, right } | GraphPattern::Union { left, right } => {
+            add_incremental_patterns(left, graph_name, target, patterns)
+                && add_incremental_patterns(right, graph_name, target, patterns)
+        }
+        GraphPattern::Filter { expr, inner } | GraphPattern::Extend { inner, expr, .. } => {
+            !has_exists(expr) && add_incremental_patterns(inner, graph_name, target, patterns)
+        }
+        GraphPattern::Graph { graph_name, inner } => match graph_name {
+            NamedNodeOrVariable::NamedNode(name) if name != target => {
+                add_incremental_patterns(inner, Some(graph_name), target, patterns)
+            }
+            // The rule might read its own view
+            _ => false,
+        },
+        GraphPattern::Project { inner, .. }
+        | GraphPattern::Distinct { inner }
+        | GraphPattern::Reduced { inner }
+        | GraphPattern::OrderBy { inner, .. } => {
+            add_incremental_patterns(inner, graph_name, target, patterns)
+        }
+        GraphPattern::Table { .. } => true,
+        // Property paths are not matched against the quads and the other operators are not monotonic
+        _ => false,
+    }
+}
+
+fn has_exists(expression: &Expression) -> bool {
+    match expression {
+        Expression::NamedNode(_)
+        | Expression::Literal(_)
+        | Expression::Variable(_)
+        | Expression::Bound(_) => false,
+        Expression::Or(a, b)
+        | Expression::And(a, b)
+        | Expression::Equal(a, b)
+        | Expression::SameTerm(a, b)
+        | Expression::Greater(a, b)
+        | Expression::GreaterOrEqual(a, b)
+        | Expression::Less(a, b)
+        | Expression::LessOrEqual(a, b)
+        | Expression::Add(a, b)
+        | Expression::Subtract(a, b)
+        | Expression::Multiply(a, b)
+        | Expression::Divide(a, b) => has_exists(a) || has_exists(b),
+        Expression::UnaryPlus(e) | Expression::UnaryMinus(e) | Expression::Not(e) => has_exists(e),
+        Expression::In(a, list) => has_exists(a) || list.iter().any(has_exists),
+        Expression::Coalesce(list) | Expression::FunctionCall(_, list) => {
+            list.iter().any(has_exists)
+        }
+        Expression::If(a, b, c) => has_exists(a) || has_exists(b) || has_exists(c),
+        Expression::Exists(_) => true,
+    }
+}
+
+/// The variables bound in all the solutions of a monotonic pattern
+fn certain_variables(pattern: &GraphPattern) -> HashSet<Variable> {
+    match pattern {
+        GraphPattern::BGP(triples) => {
+            let mut variables = HashSet::new();
+            for triple in triples {
+                add_triple_variables(triple, &mut variables);
+            }
+            variables
+        }
+        GraphPattern::Join { left, right } => {
+            let mut variables = certain_variables(left);
+            variables.extend(certain_variables(right));
+            variables
+        }
+        GraphPattern::Union { left, right } => {
+            let right = certain_variables(right);
+            certain_variables(left)
+                .into_iter()
+                .filter(|v| right.contains(v))
+                .collect()
+        }
+        GraphPattern::Graph { graph_name, inner } => {
+            let mut variables = certain_variables(inner);
+            if let NamedNodeOrVariable::Variable(variable) = graph_name {
+                variables.insert(variable.clone());
+            }
+            variables
+        }
+        GraphPattern::Project { inner, projection } => certain_variables(inner)
+            .into_iter()
+            .filter(|v| projection.contains(v))
+            .collect(),
+        GraphPattern::Table { variables, rows } => variables
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| rows.iter().all(|row| matches!(row.get(*i), Some(Some(_)))))
+            .map(|(_, v)| v.clone())
+            .collect(),
+        GraphPattern::Filter { inner, .. }
+        | GraphPattern::Extend { inner, .. }
+        | GraphPattern::Distinct { inner }
+        | GraphPattern::Reduced { inner }
+        | GraphPattern::OrderBy { inner, .. } => certain_variables(inner),
+        _ => HashSet::new(),
+    }
+}
+
+fn add_triple_variables(triple: &TriplePattern, variables: &mut HashSet<Variable>) {
+    for term in &[&triple.subject, &triple.object] {
+        match term {
+            TermOrVariable::Variable(variable) => {
+                variables.insert(variable.clone());
+            }
+            TermOrVariable::Triple(triple) => add_triple_variables(triple, variables),
+            TermOrVariable::Term(_) => (),
+        }
+    }
+    if let NamedNodeOrVariable::Variable(variable) = &triple.predicate {
+        variables.insert(variable.clone());
+    }
+}
+
+/// Returns the bindings of the pattern variables if the quad matches the pattern in the given graph
+fn match_quad(
+    pattern: &TriplePattern,
+    graph_name: Option<&NamedNodeOrVariable>,
+    quad: &Quad,
+) -> Option<Bindings> {
+    let mut bindings = Vec::new();
+    match (graph_name, &quad.graph_name) {
+        (None, GraphName::DefaultGraph) => (),
+        (Some(NamedNodeOrVariable::NamedNode(expected)), GraphName::NamedNode(actual))
+            if expected == actual => {}
+        (Some(NamedNodeOrVariable::Variable(variable)), GraphName::NamedNode(actual)) => {
+            bindings.push((variable.clone(), actual.clone().into()))
+        }
+        (Some(NamedNodeOrVariable::Variable(variable)), GraphName::BlankNode(actual)) => {
+            bindings.push((variable.clone(), actual.clone().into()))
+        }
+        _ => return None,
+    }
+    if match_triple(pattern, &quad.clone().into(), &mut bindings) {
+        Some(bindings)
+    } else {
+        None
+    }
+}
+
+fn match_triple(pattern: &TriplePattern, triple: &Triple, bindings: &mut Bindings) -> bool {
+    match_term(&pattern.subject, &triple.subject.clone().into(), bindings)
+        && match &pattern.predicate {
+            NamedNodeOrVariable::NamedNode(predicate) => *predicate == triple.predicate,
+            NamedNodeOrVariable::Variable(variable) => {
+                bind(variable, &triple.predicate.clone().into(), bindings)
+            }
+        }
+        && match_term(&pattern.object, &triple.object, bindings)
+}
+
+fn match_term(pattern: &TermOrVariable, term: &Term, bindings: &mut Bindings) -> bool {
+    match pattern {
+        // The blank nodes of the WHERE clause behave like variables
+        TermOrVariable::Term(Term::BlankNode(_)) => true,
+        TermOrVariable::Term(expected) => expected == term,
+        TermOrVariable::Variable(variable) => bind(variable, term, bindings),
+        TermOrVariable::Triple(pattern) => {
+            if let Term::Triple(triple) = term {
+                match_triple(pattern, triple, bindings)
+            } else {
+                false
+            }
+        }
+    }
+}
+
+fn bind(variable: &Variable, term: &Term, bindings: &mut Bindings) -> bool {
+    if let Some((_, value)) = bindings.iter().find(|(v, _)| v == variable) {
+        value == term
+    } else {
+        bindings.push((variable.clone(), term.clone()));
+        true
+    }
+}
+
+fn bound_term(pattern: &TermOrVariable, bindings: &[(Variable, Term)]) -> Option<Term> {
+    match pattern {
+        TermOrVariable::Term(term) => Some(term.clone()),
+        TermOrVariable::Variable(variable) => bindings
+            .iter()
+            .find(|(v, _)| v == variable)
+            .map(|(_, value)| value.clone()),
+        TermOrVariable::Triple(_) => None,
+    }
+}
+
+/// Returns the view triples matching a CONSTRUCT template triple with the given bindings
+fn view_triples<S: RuleStore>(
+    store: &S,
+    target: &NamedNode,
+    template: &TriplePattern,
+    bindings: &[(Variable, Term)],
+) -> Result<Vec<Triple>, EvaluationError>
+where
+    EvaluationError: From<S::Error>,
+{
+    let graph_name = if let Some(graph_name) = store.get_encoded_named_node(target.as_ref())? {
+        graph_name
+    } else {
+        return Ok(Vec::new());
+    };
+    let predicate = match &template.predicate {
+        NamedNodeOrVariable::NamedNode(predicate) => Some(predicate.clone().into()),
+        NamedNodeOrVariable::Variable(variable) => {
+            bound_term(&TermOrVariable::Variable(variable.clone()), bindings)
+        }
+    };
+    let mut encoded = Vec::with_capacity(3);
+    for term in &[
+        bound_term(&template.subject, bindings),
+        predicate,
+        bound_term(&template.object, bindings),
+    ] {
+        encoded.push(if let Some(term) = term {
+            if let Some(term) = store.get_encoded_term(term.as_ref())? {
+                Some(term)
+            } else {
+                // The term is not in the store
+                return Ok(Vec::new());
+            }
+        } else {
+            None
+        });
+    }
+    let mut triples = Vec::new();
+    for quad in
+        store.encoded_quads_for_pattern(encoded[0], encoded[1], encoded[2], Some(graph_name))
+    {
+        let triple = store.decode_triple(&quad?)?;
+        if match_triple(template, &triple, &mut bindings.to_vec()) {
+            triples.push(triple);
+        }
+    }
+    Ok(triples)
+}
+
+fn contains<S: RuleStore>(store: &S, quad: &Quad) -> Result<bool, EvaluationError>
+where
+    EvaluationError: From<S::Error>,
+{
+    Ok(if let Some(quad) = store.get_encoded_quad(quad.as_ref())? {
+        contains_encoded(store, &quad)?
+    } else {
+        false
+    })
+}
+
+fn contains_encoded<S: RuleStore>(
+    store: &S,
+    quad: &EncodedQuad<S::StrId>,
+) -> Result<bool, S::Error> {
+    Ok(store
+        .encoded_quads_for_pattern(
+            Some(quad.subject),
+            Some(quad.predicate),
+            Some(quad.object),
+            Some(quad.graph_name),
+        )
+        .next()
+        .transpose()?
+        .is_some())
+}
+
+fn view_content<S: RuleStore>(
+    store: &S,
+    rules: &[Rule],
+    target: &NamedNode,
+) -> Result<HashSet<Triple>, EvaluationError> {
+    let mut triples = HashSet::new();
+    for rule in rules {
+        if &rule.target_graph == target {
+            triples.extend(evaluate_triples(store, rule.query.clone())?);
+        }
+    }
+    Ok(triples)
+}
+
+fn evaluate_triples<S: RuleStore>(
+    store: &S,
+    query: Query,
+) -> Result<HashSet<Triple>, EvaluationError> {
+    if let QueryResults::Graph(triples) = evaluate_query(
+        store.clone(),
+        query,
+        QueryOptions::default().without_service_handler(),
+    )? {
+        triples.collect()
+    } else {
+        Err(EvaluationError::msg("A CONSTRUCT query was expected"))
+    }
+}
+
+/// Converts the errors of the views refresh into the errors of the on-disk stores
+#[cfg(any(feature = "rocksdb", feature = "sled"))]
+pub(crate) fn into_io_error(error: EvaluationError) -> io::Error {
+    match error {
+        EvaluationError::Io(error) => error,
+        error => io::Error::new(io::ErrorKind::Other, error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MemoryStore;
+
+    const PREFIX: &str = "PREFIX ex: <http://example.com/> ";
+
+    fn ex(name: &str) -> NamedNode {
+        NamedNode::new_unchecked(format!("http://example.com/{}", name))
+    }
+
+    /// Checks that the views are equal to the rules results evaluated from scratch
+    fn assert_views(store: &MemoryStore) {
+        for rule in store.rules() {
+            let expected = view_content(store, &store.rules(), &rule.target_graph).unwrap();
+            let actual = store
+                .quads_for_pattern(None, None, None, Some(rule.target_graph().into()))
+                .map(Triple::from)
+                .collect::<HashSet<_>>();
+            assert_eq!(expected, actual, "for the view of {}", rule.name());
+        }
+    }
+
+    #[test]
+    fn test_incremental_support() {
+        let target = ex("view");
+        for (pattern, is_incremental) in &[
+            ("?a ex:p ?b . ?b ex:p ?c", true),
+            (
+                "{ ?a ex:p ?b } UNION { GRAPH ex:other { ?a ex:q ?b } }",
+                true,
+            ),
+            ("?a ex:p ?b FILTER(?b > 2) BIND(?b + 1 AS ?c)", true),
+            ("{ SELECT DISTINCT ?a ?b WHERE { ?a ex:p ?b } }", true),
+            ("?a ex:p+ ?b", false),
+            ("?a ex:p ?b OPTIONAL { ?b ex:q ?c }", false),
+            ("?a ex:p ?b MINUS { ?b ex:q ?c }", false),
+            ("?a ex:p ?b FILTER NOT EXISTS { ?b ex:q ?c }", false),
+            ("GRAPH ex:view { ?a ex:p ?b }", false),
+            ("GRAPH ?g { ?a ex:p ?b }", false),
+        ] {
+            let query = Query::parse(
+                &format!(
+                    "{} CONSTRUCT {{ ?a ex:r ?b }} WHERE {{ {} }}",
+                    PREFIX, pattern
+                ),
+                None,
+            )
+            .unwrap();
+            if let Query::Construct { pattern: p, .. } = &query {
+                assert_eq!(
+                    add_incremental_patterns(p, None, &target, &mut Vec::new()),
+                    *is_incremental,
+                    "for {}",
+                    pattern
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_incremental_views() -> Result<(), EvaluationError> {
+        let store = MemoryStore::new();
+        store.add_rule(
+            "grandparents",
+            &format!(
+                "{} CONSTRUCT {{ ?a ex:grandparent ?c }} WHERE {{ ?a ex:parent ?b . ?b ex:parent ?c }}",
+                PREFIX
+            ),
+            ex("grandparents"),
+        )?;
+        store.add_rule(
+            "names",
+            &format!(
+                "{} CONSTRUCT {{ ?a ex:name ?n . ?a ex:nick ?m }} WHERE {{ {{ ?a ex:firstName ?n }} UNION {{ ?a ex:nickname ?m }} UNION {{ GRAPH ex:imported {{ ?a ex:firstName ?n }} }} }}",
+                PREFIX
+            ),
+            ex("names"),
+        )?;
+        // A rule reading the view of an other rule
+        store.add_rule(
+            "named grandparents",
+            &format!(
+                "{} CONSTRUCT {{ ?a ex:grandparentName ?n }} WHERE {{ GRAPH ex:grandparents {{ ?a ex:grandparent ?c }} GRAPH ex:names {{ ?c ex:name ?n }} }}",
+                PREFIX
+            ),
+            ex("namedGrandparents"),
+        )?;
+
+        let parent = |a: &str, b: &str| Quad::new(ex(a), ex("parent"), ex(b), None);
+        let bnode = BlankNode::default();
+        for quad in &[
+            parent("a", "b"),
+            parent("b", "c"),
+            parent("a", "d"),
+            parent("d", "c"),
+            Quad::new(bnode.clone(), ex("parent"), ex("a"), None),
+            Quad::new(ex("c"), ex("firstName"), Literal::from("C"), None),
+            Quad::new(ex("b"), ex("nickname"), Literal::from("B"), None),
+            Quad::new(
+                ex("c"),
+                ex("firstName"),
+                Literal::from("Cé"),
+                ex("imported"),
+            ),
+        ] {
+            store.insert(quad.clone());
+            assert_views(&store);
+        }
+        assert!(store.contains(&Quad::new(
+            bnode.clone(),
+            ex("grandparent"),
+            ex("b"),
+            ex("grandparents")
+        )));
+        assert!(store.contains(&Quad::new(
+            ex("a"),
+            ex("grandparentName"),
+            Literal::from("Cé"),
+            ex("namedGrandparents")
+        )));
+
+        // a is still a grandparent of c through d
+        store.remove(&parent("b", "c"));
+        assert_views(&store);
+        assert!(store.contains(&Quad::new(
+            ex("a"),
+            ex("grandparent"),
+            ex("c"),
+            ex("grandparents")
+        )));
+        store.remove(&parent("d", "c"));
+        assert_views(&store);
+        assert!(!store.contains(&Quad::new(
+            ex("a"),
+            ex("grandparent"),
+            ex("c"),
+            ex("grandparents")
+        )));
+        store.remove(&Quad::new(bnode, ex("parent"), ex("a"), None));
+        store.remove(&Quad::new(
+            ex("c"),
+            ex("firstName"),
+            Literal::from("Cé"),
+            ex("imported"),
+        ));
+        assert_views(&store);
+
+        // Updates with inserted and removed quads and clears
+        store.update(
+            "PREFIX ex: <http://example.com/> DELETE { ?a ex:parent ?b } INSERT { ?b ex:parent ?a } WHERE { ?a ex:parent ?b }",
+        )?;
+        assert_views(&store);
+        store.update("PREFIX ex: <http://example.com/> INSERT DATA { ex:c ex:parent ex:e . ex:e ex:parent ex:f }")?;
+        assert_views(&store);
+        store.clear();
+        assert_views(&store);
+        Ok(())
+    }
+
+    #[test]
+    fn test_no_fixpoint() {
+        let store = MemoryStore::new();
+        store.insert(Quad::new(ex("a"), ex("p"), Literal::from(0), None));
+        assert!(store
+            .add_rule(
+                "counter",
+                &format!(
+                    "{} CONSTRUCT {{ ex:a ex:p ?n }} WHERE {{ {{ ex:a ex:p ?o }} UNION {{ GRAPH ex:counter {{ ex:a ex:p ?o }} }} BIND(?o + 1 AS ?n) }}",
+                    PREFIX
+                ),
+                ex("counter"),
+            )
+            .is_err());
+    }
+}
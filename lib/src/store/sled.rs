@@ -1,30 +1,43 @@
 //! Store based on the [Sled](https://sled.rs/) key-value database.
 
-use crate::error::invalid_data_error;
-use crate::io::{DatasetFormat, GraphFormat};
+use crate::error::{invalid_data_error, invalid_input_error};
+use crate::io::detection::open_rdf_file;
+use crate::io::{
+    DatasetParser, DatasetSerializer, GraphParser, GraphSerializer, PrefixMap, RdfFormat,
+};
 use crate::model::*;
+use crate::shacl::ShaclValidator;
 use crate::sparql::{
-    evaluate_query, evaluate_update, EvaluationError, Query, QueryOptions, QueryResults, Update,
-    UpdateOptions,
+    evaluate_query, evaluate_update, prepare_query, EvaluationError, PreparedQuery, Query,
+    QueryCache, QueryOptions, QueryResults, ServiceDescription, Update, UpdateOptions,
 };
 use crate::store::binary_encoder::*;
 use crate::store::numeric_encoder::{
-    Decoder, ReadEncoder, StrContainer, StrEncodingAware, StrLookup, WriteEncoder,
+    Decoder, EncodedRange, RangeKey, ReadEncoder, StrContainer, StrEncodingAware, StrLookup,
+    WriteEncoder,
+};
+use crate::store::reasoning::{EncodedTriple, InferenceStore, Owl2RlReasoner};
+use crate::store::rules::{
+    into_io_error, refresh_view, refresh_views, Rule, RuleChanges, RuleStore,
 };
+use crate::store::text::encoded_term_tokens;
 use crate::store::{
-    dump_dataset, dump_graph, get_encoded_quad_pattern, load_dataset, load_graph,
-    ReadableEncodedStore, StoreOrParseError, WritableEncodedStore,
+    dump_canonical_dataset, dump_dataset, dump_graph, get_encoded_quad_pattern, load_dataset,
+    load_graph, load_hdt, read_validated_graph, ReadableEncodedStore, StoreOptions,
+    StoreOrParseError, WritableEncodedStore,
 };
 use sled::transaction::{
     ConflictableTransactionError, TransactionError, Transactional, TransactionalTree,
     UnabortableTransactionError,
 };
 use sled::{Config, Db, Iter, Tree};
+use std::cell::RefCell;
 use std::convert::TryInto;
 use std::error::Error;
 use std::io::{BufRead, Write};
 use std::iter::{once, Once};
 use std::path::Path;
+use std::sync::{Arc, Mutex, RwLock};
 use std::{fmt, io, str};
 
 /// Store based on the [Sled](https://sled.rs/) key-value database.
@@ -76,6 +89,15 @@ pub struct SledStore {
     dpos: Tree,
     dosp: Tree,
     graphs: Tree,
+    range: Tree,
+    range_index: bool,
+    text: Tree,
+    text_index: bool,
+    reasoner: Option<Arc<Owl2RlReasoner<StrHash>>>,
+    rules_tree: Tree,
+    rules: Arc<RwLock<Vec<Rule>>>,
+    rule_changes: Arc<Mutex<Option<RuleChanges<StrHash>>>>,
+    query_cache: Arc<QueryCache>,
 }
 
 type EncodedTerm = crate::store::numeric_encoder::EncodedTerm<StrHash>;
@@ -86,17 +108,73 @@ type EncodedQuad = crate::store::numeric_encoder::EncodedQuad<StrHash>;
 impl SledStore {
     /// Creates a temporary [`SledStore`]() that will be deleted after drop.
     pub fn new() -> Result<Self, io::Error> {
-        Self::do_open(&Config::new().temporary(true))
+        Self::new_with_options(StoreOptions::default())
+    }
+
+    /// Creates a temporary [`SledStore`]() maintaining the optional indexes and inferences enabled in `options`.
+    ///
+    /// See [`SledStore::open_with_options()`].
+    pub fn new_with_options(options: StoreOptions) -> Result<Self, io::Error> {
+        Self::do_open(&Config::new().temporary(true), options)
     }
 
     /// Opens a [`SledStore`]() and creates it if it does not exist yet.
+    ///
+    /// The optional indexes and the OWL 2 RL materialization are maintained if they have been enabled previously using [`SledStore::open_with_options()`].
     pub fn open(path: impl AsRef<Path>) -> Result<Self, io::Error> {
-        Self::do_open(&Config::new().path(path))
+        Self::open_with_options(path, StoreOptions::default())
     }
 
-    fn do_open(config: &Config) -> Result<Self, io::Error> {
+    /// Opens a [`SledStore`]() maintaining the optional indexes and inferences enabled in `options` and creates it if it does not exist yet.
+    ///
+    /// If the store already exists, the newly enabled indexes and inferences are built from its content.
+    /// Once enabled they are kept up to date even if the store is opened using [`SledStore::open()`].
+    ///
+    /// Usage example:
+    /// ```
+    /// use oxigraph::SledStore;
+    /// use oxigraph::model::*;
+    /// use oxigraph::sparql::QueryResults;
+    /// use oxigraph::store::StoreOptions;
+    /// # use std::fs::remove_dir_all;
+    ///
+    /// # {
+    /// let store = SledStore::open_with_options("example_indexes.db", StoreOptions::default().with_range_index().with_text_index())?;
+    ///
+    /// // insertions
+    /// let ex = NamedNodeRef::new("http://example.com")?;
+    /// store.insert(QuadRef::new(ex, ex, &Literal::from(1), GraphNameRef::DefaultGraph))?;
+    /// store.insert(QuadRef::new(ex, ex, &Literal::from(10), GraphNameRef::DefaultGraph))?;
+    /// store.insert(QuadRef::new(ex, ex, LiteralRef::new_simple_literal("The Eiffel Tower"), GraphNameRef::DefaultGraph))?;
+    ///
+    /// // SPARQL queries
+    /// if let QueryResults::Solutions(mut solutions) = store.query("SELECT ?o WHERE { ?s <http://example.com> ?o FILTER(?o > 5) }")? {
+    ///     assert_eq!(solutions.next().unwrap()?.get("o"), Some(&Literal::from(10).into()));
+    ///     assert!(solutions.next().is_none());
+    /// }
+    /// if let QueryResults::Solutions(mut solutions) = store.query("SELECT ?o WHERE { ?s <http://example.com> ?o FILTER(<http://oxigraph.org/fn/textMatch>(?o, \"eiffel\")) }")? {
+    ///     assert_eq!(solutions.next().unwrap()?.get("o"), Some(&LiteralRef::new_simple_literal("The Eiffel Tower").into_owned().into()));
+    ///     assert!(solutions.next().is_none());
+    /// }
+    /// # };
+    /// # remove_dir_all("example_indexes.db")?;
+    /// # Result::<_,Box<dyn std::error::Error>>::Ok(())
+    /// ```
+    pub fn open_with_options(
+        path: impl AsRef<Path>,
+        options: StoreOptions,
+    ) -> Result<Self, io::Error> {
+        Self::do_open(&Config::new().path(path), options)
+    }
+
+    fn do_open(config: &Config, options: StoreOptions) -> Result<Self, io::Error> {
+        if options.spatial_index {
+            return Err(invalid_input_error(
+                "The spatial index is only supported by MemoryStore",
+            ));
+        }
         let db = config.open()?;
-        let this = Self {
+        let mut this = Self {
             default: db.clone(),
             id2str: db.open_tree("id2str")?,
             spog: db.open_tree("spog")?,
@@ -109,6 +187,15 @@ impl SledStore {
             dpos: db.open_tree("dpos")?,
             dosp: db.open_tree("dosp")?,
             graphs: db.open_tree("graphs")?,
+            range: db.open_tree("range")?,
+            range_index: db.contains_key("oxrangeindex")?,
+            text: db.open_tree("text")?,
+            text_index: db.contains_key("oxtextindex")?,
+            reasoner: None,
+            rules_tree: db.open_tree("rules")?,
+            rules: Arc::default(),
+            rule_changes: Arc::default(),
+            query_cache: Arc::default(),
         };
 
         let mut version = this.ensure_version()?;
@@ -126,17 +213,38 @@ impl SledStore {
             this.graphs.flush()?;
         }
 
+        if version == 1 {
+            // We migrate to v2: the quoted triples are a new term type, the existing terms are not changed
+            version = 2;
+            this.set_version(version)?;
+            this.default.flush()?;
+        }
+
         match version {
-            _ if version < LATEST_STORAGE_VERSION => Err(invalid_data_error(format!(
+            _ if version < LATEST_STORAGE_VERSION => return Err(invalid_data_error(format!(
                 "The Sled database is using the outdated encoding version {}. Automated migration is not supported, please dump the store dataset using a compatible Oxigraph version and load it again using the current version",
                 version
             ))),
-            LATEST_STORAGE_VERSION => Ok(this),
-            _ => Err(invalid_data_error(format!(
+            LATEST_STORAGE_VERSION => (),
+            _ => return Err(invalid_data_error(format!(
                 "The Sled database is using the too recent version {}. Upgrade to the latest Oxigraph version to load this database",
                 version
             )))
         }
+
+        this.load_rules()?;
+
+        if options.range_index && !this.range_index {
+            this.build_range_index()?;
+        }
+        if options.text_index && !this.text_index {
+            this.build_text_index()?;
+        }
+        if options.owl2rl_reasoning || this.default.contains_key("oxowl2rl")? {
+            this.enable_owl2rl_reasoning()?;
+        }
+
+        Ok(this)
     }
 
     fn ensure_version(&self) -> Result<u64, io::Error> {
@@ -155,6 +263,72 @@ impl SledStore {
         Ok(())
     }
 
+    fn build_range_index(&mut self) -> Result<(), io::Error> {
+        let mut buffer = Vec::with_capacity(4 * WRITTEN_TERM_MAX_SIZE + WRITTEN_RANGE_KEY_SIZE);
+        for quad in self.encoded_quads_for_pattern(None, None, None, None) {
+            let quad = quad?;
+            if let Some(key) = RangeKey::new(quad.object) {
+                write_pvosg_quad(&mut buffer, key, &quad);
+                self.range.insert(buffer.as_slice(), &[])?;
+                buffer.clear();
+            }
+        }
+        self.default.insert("oxrangeindex", &[])?;
+        self.range_index = true;
+        Ok(())
+    }
+
+    fn build_text_index(&mut self) -> Result<(), io::Error> {
+        let mut buffer = Vec::with_capacity(5 * WRITTEN_TERM_MAX_SIZE);
+        for quad in self.encoded_quads_for_pattern(None, None, None, None) {
+            let quad = quad?;
+            for token in encoded_term_tokens(self, quad.object)? {
+                write_ptosg_quad(&mut buffer, &token, &quad);
+                self.text.insert(buffer.as_slice(), &[])?;
+                buffer.clear();
+            }
+        }
+        self.default.insert("oxtextindex", &[])?;
+        self.text_index = true;
+        Ok(())
+    }
+
+    fn enable_owl2rl_reasoning(&mut self) -> Result<(), io::Error> {
+        let mut encoder = &*self;
+        let reasoner = Arc::new(Owl2RlReasoner::new(|node| encoder.encode_named_node(node))?);
+        self.reasoner = Some(reasoner.clone());
+        if !self.default.contains_key("oxowl2rl")? {
+            let asserted = self
+                .quads_for_graph(EncodedTerm::DefaultGraph)
+                .map(|quad| quad.map(|quad| [quad.subject, quad.predicate, quad.object]))
+                .collect::<Result<Vec<_>, _>>()?;
+            let mut this = &*self;
+            this.clear_encoded_graph(reasoner.inferred_graph())?;
+            reasoner.materialize(&mut this, asserted)?;
+            self.default.insert("oxowl2rl", &[])?;
+        }
+        Ok(())
+    }
+
+    fn load_rules(&mut self) -> Result<(), io::Error> {
+        let mut rules = Vec::new();
+        for entry in &self.rules_tree {
+            let (name, value) = entry?;
+            let name = str::from_utf8(&name).map_err(invalid_data_error)?;
+            let value = str::from_utf8(&value).map_err(invalid_data_error)?;
+            let (target_graph, query) = value
+                .split_once('\n')
+                .ok_or_else(|| invalid_data_error(format!("Invalid stored rule {}", name)))?;
+            let target_graph = NamedNode::new(target_graph).map_err(invalid_data_error)?;
+            rules.push(Rule::new(name, query, target_graph).map_err(invalid_data_error)?);
+        }
+        if !rules.is_empty() {
+            self.rules = Arc::new(RwLock::new(rules));
+            self.rule_changes = Arc::new(Mutex::new(Some(RuleChanges::default())));
+        }
+        Ok(())
+    }
+
     /// Executes a [SPARQL 1.1 query](https://www.w3.org/TR/sparql11-query/).
     ///
     /// Usage example:
@@ -188,7 +362,67 @@ impl SledStore {
         query: impl TryInto<Query, Error = impl Into<EvaluationError>>,
         options: QueryOptions,
     ) -> Result<QueryResults, EvaluationError> {
-        evaluate_query(self.clone(), query, options)
+        self.query_cache.evaluate(
+            query.try_into().map_err(|e| e.into())?,
+            options,
+            |query, options| evaluate_query(self.clone(), query, options),
+        )
+    }
+
+    /// Prepares a [SPARQL 1.1 query](https://www.w3.org/TR/sparql11-query/) to be executed many times with different variable bindings.
+    ///
+    /// Usage example:
+    /// ```
+    /// use oxigraph::SledStore;
+    /// use oxigraph::model::*;
+    /// use oxigraph::sparql::QueryResults;
+    ///
+    /// let store = SledStore::new()?;
+    /// let ex = NamedNodeRef::new("http://example.com")?;
+    /// store.insert(QuadRef::new(ex, ex, ex, None))?;
+    ///
+    /// let mut query = store.prepare_query("SELECT ?o WHERE { ?s ?p ?o }")?;
+    /// query.bind("s", ex)?;
+    /// if let QueryResults::Solutions(mut solutions) = query.exec()? {
+    ///     assert_eq!(solutions.next().unwrap()?.get("o"), Some(&ex.into_owned().into()));
+    /// }
+    /// # Result::<_,Box<dyn std::error::Error>>::Ok(())
+    /// ```
+    pub fn prepare_query(
+        &self,
+        query: impl TryInto<Query, Error = impl Into<EvaluationError>>,
+    ) -> Result<PreparedQuery, EvaluationError> {
+        self.prepare_query_opt(query, QueryOptions::default())
+    }
+
+    /// Prepares a [SPARQL 1.1 query](https://www.w3.org/TR/sparql11-query/) with some options.
+    pub fn prepare_query_opt(
+        &self,
+        query: impl TryInto<Query, Error = impl Into<EvaluationError>>,
+        options: QueryOptions,
+    ) -> Result<PreparedQuery, EvaluationError> {
+        prepare_query(self.clone(), query, options)
+    }
+
+    /// Sets the maximal number of query results kept in the store query results cache.
+    ///
+    /// The cache is disabled by default and is shared by all the clones of the store.
+    /// All the cached results are dropped after each write to the store.
+    ///
+    /// See [`MemoryStore`](super::memory::MemoryStore::set_query_cache_capacity()) for a description of the cache and a usage example.
+    pub fn set_query_cache_capacity(&self, capacity: usize) {
+        self.query_cache.set_capacity(capacity)
+    }
+
+    /// Returns a [SPARQL 1.1 Service Description](https://www.w3.org/TR/sparql11-service-description/) of the store.
+    ///
+    /// The number of quads is not described because counting them requires a full scan.
+    /// It might be added with [`ServiceDescription::with_triples_count`].
+    ///
+    /// See [`MemoryStore`](super::memory::MemoryStore::service_description()) for a usage example.
+    pub fn service_description(&self) -> Result<ServiceDescription, io::Error> {
+        Ok(ServiceDescription::default()
+            .with_dataset(self.named_graphs().collect::<Result<Vec<_>, _>>()?))
     }
 
     /// Retrieves quads with a filter on each quad component
@@ -212,7 +446,7 @@ impl SledStore {
     /// ```
     pub fn quads_for_pattern(
         &self,
-        subject: Option<NamedOrBlankNodeRef<'_>>,
+        subject: Option<SubjectRef<'_>>,
         predicate: Option<NamedNodeRef<'_>>,
         object: Option<TermRef<'_>>,
         graph_name: Option<GraphNameRef<'_>>,
@@ -288,12 +522,16 @@ impl SledStore {
         update: impl TryInto<Update, Error = impl Into<EvaluationError>>,
         options: UpdateOptions,
     ) -> Result<(), EvaluationError> {
-        evaluate_update(
+        let _invalidation = self.query_cache.invalidation();
+        let result = evaluate_update(
             self.clone(),
             &mut &*self,
             update.try_into().map_err(|e| e.into())?,
             options,
-        )
+        );
+        let refreshed = self.refresh_rules();
+        result?;
+        Ok(refreshed?)
     }
 
     /// Executes an ACID transaction.
@@ -327,7 +565,13 @@ impl SledStore {
         &self,
         f: impl Fn(SledTransaction<'_>) -> Result<T, SledConflictableTransactionError<E>>,
     ) -> Result<T, SledTransactionError<E>> {
-        Ok((
+        let _invalidation = self.query_cache.invalidation();
+        let reasoning_changes = RefCell::new(Vec::new());
+        let changes = &reasoning_changes;
+        let transaction_rule_changes = RefCell::new(RuleChanges::default());
+        let rule_changes = &transaction_rule_changes;
+        let has_rules = self.has_rules();
+        let result = (
             &self.id2str,
             &self.spog,
             &self.posg,
@@ -339,9 +583,27 @@ impl SledStore {
             &self.dpos,
             &self.dosp,
             &self.graphs,
+            &self.range,
+            &self.text,
         )
             .transaction(
-                move |(id2str, spog, posg, ospg, gspo, gpos, gosp, dspo, dpos, dosp, graphs)| {
+                move |(
+                    id2str,
+                    spog,
+                    posg,
+                    ospg,
+                    gspo,
+                    gpos,
+                    gosp,
+                    dspo,
+                    dpos,
+                    dosp,
+                    graphs,
+                    range,
+                    text,
+                )| {
+                    changes.borrow_mut().clear();
+                    *rule_changes.borrow_mut() = RuleChanges::default();
                     Ok(f(SledTransaction {
                         id2str,
                         spog,
@@ -354,9 +616,41 @@ impl SledStore {
                         dpos,
                         dosp,
                         graphs,
+                        range,
+                        range_index: self.range_index,
+                        text,
+                        text_index: self.text_index,
+                        reasoning_changes: self.reasoner.as_ref().map(|_| changes),
+                        rule_changes: if has_rules { Some(rule_changes) } else { None },
                     })?)
                 },
-            )?)
+            )?;
+        if let Some(reasoner) = &self.reasoner {
+            // We update the materialization once the transaction is committed
+            let mut this = self;
+            for (quad, inserted) in reasoning_changes.into_inner() {
+                let triple = [quad.subject, quad.predicate, quad.object];
+                let asserted = this
+                    .contains_asserted(&triple)
+                    .map_err(SledTransactionError::Storage)?;
+                if inserted && asserted {
+                    reasoner.on_insert(&mut this, triple)
+                } else if !inserted && !asserted {
+                    reasoner.on_remove(&mut this, triple)
+                } else {
+                    Ok(())
+                }
+                .map_err(SledTransactionError::Storage)?;
+            }
+        }
+        if has_rules {
+            if let Some(changes) = self.rule_changes.lock().unwrap().as_mut() {
+                changes.extend(transaction_rule_changes.into_inner());
+            }
+            self.refresh_rules()
+                .map_err(SledTransactionError::Storage)?;
+        }
+        Ok(result)
     }
 
     /// Loads a graph file (i.e. triples) into the store
@@ -390,12 +684,99 @@ impl SledStore {
     pub fn load_graph<'a>(
         &self,
         reader: impl BufRead,
-        format: GraphFormat,
+        format: impl Into<GraphParser>,
         to_graph_name: impl Into<GraphNameRef<'a>>,
         base_iri: Option<&str>,
     ) -> Result<(), io::Error> {
+        self.load_graph_with_prefixes(reader, format, to_graph_name, base_iri)?;
+        Ok(())
+    }
+
+    /// Loads a graph file (i.e. triples) into the store like [`load_graph`](SledStore::load_graph())
+    /// and returns the prefixes and the base IRI declared in the file.
+    ///
+    /// See [`MemoryStore`](super::memory::MemoryStore::load_graph_with_prefixes()) for a usage example.
+    pub fn load_graph_with_prefixes<'a>(
+        &self,
+        reader: impl BufRead,
+        format: impl Into<GraphParser>,
+        to_graph_name: impl Into<GraphNameRef<'a>>,
+        base_iri: Option<&str>,
+    ) -> Result<PrefixMap, io::Error> {
+        let _invalidation = self.query_cache.invalidation();
         let mut this = self;
-        load_graph(&mut this, reader, format, to_graph_name.into(), base_iri)?;
+        let result = load_graph(
+            &mut this,
+            reader,
+            format.into(),
+            to_graph_name.into(),
+            base_iri,
+        );
+        let refreshed = self.refresh_rules();
+        let prefixes = result?;
+        refreshed?;
+        Ok(prefixes)
+    }
+
+    /// Loads a graph file (i.e. triples) into the store if the resulting graph conforms to SHACL shapes.
+    ///
+    /// The existing content of the target graph and the loaded triples are validated together
+    /// and the triples are then written in a single transaction.
+    /// Nothing is written if the validation fails.
+    ///
+    /// Usage example:
+    /// ```
+    /// use oxigraph::SledStore;
+    /// use oxigraph::io::GraphFormat;
+    /// use oxigraph::model::*;
+    /// use oxigraph::shacl::ShaclValidator;
+    ///
+    /// let validator = ShaclValidator::new(vec![Triple::new(
+    ///     NamedNode::new("http://example.com/shape")?,
+    ///     NamedNode::new("http://www.w3.org/ns/shacl#targetSubjectsOf")?,
+    ///     NamedNode::new("http://example.com/p")?,
+    /// ), Triple::new(
+    ///     NamedNode::new("http://example.com/shape")?,
+    ///     NamedNode::new("http://www.w3.org/ns/shacl#nodeKind")?,
+    ///     NamedNode::new("http://www.w3.org/ns/shacl#IRI")?,
+    /// )])?;
+    ///
+    /// let store = SledStore::new()?;
+    /// let file = b"_:s <http://example.com/p> <http://example.com/o> .";
+    /// assert!(store.load_graph_with_validation(file.as_ref(), GraphFormat::NTriples, &GraphName::DefaultGraph, None, &validator).is_err());
+    /// assert!(store.is_empty());
+    /// # Result::<_,Box<dyn std::error::Error>>::Ok(())
+    /// ```
+    ///
+    /// Errors related to parameter validation like the base IRI use the [`InvalidInput`](std::io::ErrorKind::InvalidInput) error kind.
+    /// Errors related to a bad syntax in the loaded file or to a non conforming graph use the [`InvalidData`](std::io::ErrorKind::InvalidData) or [`UnexpectedEof`](std::io::ErrorKind::UnexpectedEof) error kinds.
+    /// Errors related to data loading into the store use the other error kinds.
+    pub fn load_graph_with_validation<'a>(
+        &self,
+        reader: impl BufRead,
+        format: impl Into<GraphParser>,
+        to_graph_name: impl Into<GraphNameRef<'a>>,
+        base_iri: Option<&str>,
+        validator: &ShaclValidator,
+    ) -> Result<(), io::Error> {
+        let to_graph_name = to_graph_name.into();
+        let triples = read_validated_graph(
+            self.quads_for_pattern(None, None, None, Some(to_graph_name))
+                .map(|q| q.map(Triple::from))
+                .collect::<Result<Vec<_>, _>>()?,
+            reader,
+            format.into(),
+            base_iri,
+            validator,
+        )?;
+        self.transaction(
+            |transaction| -> Result<(), SledConflictableTransactionError<io::Error>> {
+                for triple in &triples {
+                    transaction.insert(triple.as_ref().in_graph(to_graph_name))?;
+                }
+                Ok(())
+            },
+        )?;
         Ok(())
     }
 
@@ -430,12 +811,58 @@ impl SledStore {
     pub fn load_dataset(
         &self,
         reader: impl BufRead,
-        format: DatasetFormat,
+        format: impl Into<DatasetParser>,
         base_iri: Option<&str>,
     ) -> Result<(), io::Error> {
+        let _invalidation = self.query_cache.invalidation();
         let mut this = self;
-        load_dataset(&mut this, reader, format, base_iri)?;
-        Ok(())
+        let result = load_dataset(&mut this, reader, format.into(), base_iri);
+        let refreshed = self.refresh_rules();
+        result?;
+        refreshed
+    }
+
+    /// Loads a RDF file into the store finding its format and its compression from its name or its content.
+    ///
+    /// See [`MemoryStore`](super::memory::MemoryStore::load_from_path()) for a usage example.
+    ///
+    /// Errors related to parameter validation like the base IRI or an undetectable format use the [`InvalidInput`](std::io::ErrorKind::InvalidInput) error kind.
+    /// Errors related to a bad syntax in the loaded file use the [`InvalidData`](std::io::ErrorKind::InvalidData) or [`UnexpectedEof`](std::io::ErrorKind::UnexpectedEof) error kinds.
+    /// Errors related to data loading into the store use the other error kinds.
+    pub fn load_from_path(
+        &self,
+        path: impl AsRef<Path>,
+        base_iri: Option<&str>,
+    ) -> Result<(), io::Error> {
+        let (reader, format) = open_rdf_file(path.as_ref())?;
+        match format {
+            RdfFormat::Graph(format) => {
+                self.load_graph(reader, format, GraphNameRef::DefaultGraph, base_iri)
+            }
+            RdfFormat::Dataset(format) => self.load_dataset(reader, format, base_iri),
+        }
+    }
+
+    /// Loads an [HDT](https://www.rdfhdt.org/) file into the store.
+    ///
+    /// The file is mapped into memory while its triples are loaded.
+    /// See [`MemoryStore`](super::memory::MemoryStore::load_hdt()) for a usage example.
+    ///
+    /// This method is not atomic: if the loading fails in the middle of the file, the triples read before stay in the store.
+    ///
+    /// Errors related to an invalid or unsupported HDT file use the [`InvalidData`](std::io::ErrorKind::InvalidData) or [`UnexpectedEof`](std::io::ErrorKind::UnexpectedEof) error kinds.
+    /// Errors related to data loading into the store use the other error kinds.
+    pub fn load_hdt<'a>(
+        &self,
+        path: impl AsRef<Path>,
+        to_graph_name: impl Into<GraphNameRef<'a>>,
+    ) -> Result<(), io::Error> {
+        let _invalidation = self.query_cache.invalidation();
+        let mut this = self;
+        let result = load_hdt(&mut this, path.as_ref(), to_graph_name.into());
+        let refreshed = self.refresh_rules();
+        result?;
+        refreshed
     }
 
     /// Adds a quad to this store.
@@ -444,9 +871,11 @@ impl SledStore {
     /// It might leave the store in a bad state if a crash happens during the insertion.
     /// Use a (memory greedy) [transaction](SledStore::transaction()) if you do not want that.
     pub fn insert<'a>(&self, quad: impl Into<QuadRef<'a>>) -> Result<(), io::Error> {
+        let _invalidation = self.query_cache.invalidation();
         let mut this = self;
         let quad = this.encode_quad(quad.into())?;
-        this.insert_encoded(&quad)
+        this.insert_encoded(&quad)?;
+        self.refresh_rules()
     }
 
     /// Removes a quad from this store.
@@ -455,9 +884,11 @@ impl SledStore {
     /// It might leave the store in a bad state if a crash happens during the removal.
     /// Use a (memory greedy) [transaction](SledStore::transaction()) if you do not want that.
     pub fn remove<'a>(&self, quad: impl Into<QuadRef<'a>>) -> Result<(), io::Error> {
+        let _invalidation = self.query_cache.invalidation();
         if let Some(quad) = self.get_encoded_quad(quad.into())? {
             let mut this = self;
-            this.remove_encoded(&quad)
+            this.remove_encoded(&quad)?;
+            self.refresh_rules()
         } else {
             Ok(())
         }
@@ -484,14 +915,14 @@ impl SledStore {
     pub fn dump_graph<'a>(
         &self,
         writer: impl Write,
-        format: GraphFormat,
+        format: impl Into<GraphSerializer>,
         from_graph_name: impl Into<GraphNameRef<'a>>,
     ) -> Result<(), io::Error> {
         dump_graph(
             self.quads_for_pattern(None, None, None, Some(from_graph_name.into()))
                 .map(|q| Ok(q?.into())),
             writer,
-            format,
+            &format.into(),
         )
     }
 
@@ -511,8 +942,19 @@ impl SledStore {
     /// assert_eq!(file, buffer.as_slice());
     /// # std::io::Result::Ok(())
     /// ```
-    pub fn dump_dataset(&self, writer: impl Write, format: DatasetFormat) -> Result<(), io::Error> {
-        dump_dataset(self.iter(), writer, format)
+    pub fn dump_dataset(
+        &self,
+        writer: impl Write,
+        format: impl Into<DatasetSerializer>,
+    ) -> Result<(), io::Error> {
+        dump_dataset(self.iter(), writer, &format.into())
+    }
+
+    /// Dumps the store into a file as [canonical N-Quads](crate::io::write_canonical_n_quads()).
+    ///
+    /// See [`MemoryStore`](super::memory::MemoryStore::dump_canonical_dataset()) for a usage example.
+    pub fn dump_canonical_dataset(&self, writer: impl Write) -> Result<(), io::Error> {
+        dump_canonical_dataset(self.iter(), writer)
     }
 
     /// Returns all the store named graphs
@@ -577,6 +1019,7 @@ impl SledStore {
         &self,
         graph_name: impl Into<NamedOrBlankNodeRef<'a>>,
     ) -> Result<(), io::Error> {
+        let _invalidation = self.query_cache.invalidation();
         let mut this = self;
         let graph_name = this.encode_named_or_blank_node(graph_name.into())?;
         this.insert_encoded_named_graph(graph_name)
@@ -604,9 +1047,11 @@ impl SledStore {
         &self,
         graph_name: impl Into<GraphNameRef<'a>>,
     ) -> Result<(), io::Error> {
+        let _invalidation = self.query_cache.invalidation();
         if let Some(graph_name) = self.get_encoded_graph_name(graph_name.into())? {
             let mut this = self;
-            this.clear_encoded_graph(graph_name)
+            this.clear_encoded_graph(graph_name)?;
+            self.refresh_rules()
         } else {
             Ok(())
         }
@@ -634,9 +1079,11 @@ impl SledStore {
         &self,
         graph_name: impl Into<NamedOrBlankNodeRef<'a>>,
     ) -> Result<(), io::Error> {
+        let _invalidation = self.query_cache.invalidation();
         if let Some(graph_name) = self.get_encoded_named_or_blank_node(graph_name.into())? {
             let mut this = self;
-            this.remove_encoded_named_graph(graph_name)
+            this.remove_encoded_named_graph(graph_name)?;
+            self.refresh_rules()
         } else {
             Ok(())
         }
@@ -660,8 +1107,255 @@ impl SledStore {
     /// # Result::<_,Box<dyn std::error::Error>>::Ok(())
     /// ```
     pub fn clear(&self) -> Result<(), io::Error> {
+        let _invalidation = self.query_cache.invalidation();
         let mut this = self;
-        (&mut this).clear()
+        (&mut this).clear()?;
+        self.refresh_rules()
+    }
+
+    /// Adds a rule materializing the results of a SPARQL CONSTRUCT query into a named graph.
+    ///
+    /// The rule is persisted in the database.
+    /// The target graph content is replaced by the query results and is kept up to date when the store data changes.
+    /// It should not be modified directly: it is recomputed from the rules targeting it.
+    /// A rule with the same name is replaced.
+    /// The CONSTRUCT template should not contain blank nodes and the query should not use `SERVICE`.
+    ///
+    /// Usage example:
+    /// ```
+    /// use oxigraph::SledStore;
+    /// use oxigraph::model::*;
+    ///
+    /// let store = SledStore::new()?;
+    /// let view = NamedNode::new("http://example.com/grandparents")?;
+    /// store.add_rule(
+    ///     "grandparents",
+    ///     "PREFIX ex: <http://example.com/>
+    ///      CONSTRUCT { ?a ex:grandparent ?c } WHERE { ?a ex:parent ?b . ?b ex:parent ?c }",
+    ///     view.clone(),
+    /// )?;
+    ///
+    /// let ex = |name| NamedNode::new(format!("http://example.com/{}", name));
+    /// store.insert(&Quad::new(ex("a")?, ex("parent")?, ex("b")?, None))?;
+    /// store.insert(&Quad::new(ex("b")?, ex("parent")?, ex("c")?, None))?;
+    /// assert!(store.contains(&Quad::new(ex("a")?, ex("grandparent")?, ex("c")?, view))?);
+    /// # Result::<_,Box<dyn std::error::Error>>::Ok(())
+    /// ```
+    pub fn add_rule(
+        &self,
+        name: impl Into<String>,
+        query: impl TryInto<Query, Error = impl Into<EvaluationError>>,
+        target_graph: impl Into<NamedNode>,
+    ) -> Result<(), EvaluationError> {
+        let _invalidation = self.query_cache.invalidation();
+        let rule = Rule::new(name, query, target_graph)?;
+        let target_graph = rule.target_graph().into_owned();
+        self.rules_tree
+            .insert(
+                rule.name(),
+                format!("{}\n{}", rule.target_graph().as_str(), rule.query()).as_str(),
+            )
+            .map_err(io::Error::from)?;
+        let (rules, old_target_graph) = {
+            let mut rules = self.rules.write().unwrap();
+            let old_target_graph = rules
+                .iter()
+                .position(|r| r.name() == rule.name())
+                .map(|i| rules.remove(i).target_graph().into_owned());
+            rules.push(rule);
+            self.rule_changes
+                .lock()
+                .unwrap()
+                .get_or_insert_with(RuleChanges::default);
+            (rules.clone(), old_target_graph)
+        };
+        refresh_view(self, &rules, &target_graph)?;
+        if let Some(old_target_graph) = old_target_graph {
+            refresh_view(self, &rules, &old_target_graph)?;
+        }
+        refresh_views(self, &rules)
+    }
+
+    /// Removes a rule added with [`add_rule`](SledStore::add_rule()) and returns if it existed.
+    ///
+    /// The rule results are removed from its target graph.
+    pub fn remove_rule(&self, name: &str) -> Result<bool, io::Error> {
+        let _invalidation = self.query_cache.invalidation();
+        self.rules_tree.remove(name)?;
+        let (rules, removed) = {
+            let mut rules = self.rules.write().unwrap();
+            let removed = rules
+                .iter()
+                .position(|r| r.name() == name)
+                .map(|i| rules.remove(i));
+            if rules.is_empty() {
+                *self.rule_changes.lock().unwrap() = None;
+            }
+            (rules.clone(), removed)
+        };
+        if let Some(removed) = removed {
+            // The view is recomputed from the remaining rules targeting it
+            refresh_view(self, &rules, &removed.target_graph().into_owned())
+                .map_err(into_io_error)?;
+            self.refresh_rules()?;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    /// Returns the rules added with [`add_rule`](SledStore::add_rule()).
+    pub fn rules(&self) -> Vec<Rule> {
+        self.rules.read().unwrap().clone()
+    }
+
+    fn has_rules(&self) -> bool {
+        !self.rules.read().unwrap().is_empty()
+    }
+
+    fn refresh_rules(&self) -> Result<(), io::Error> {
+        if !self.has_rules() {
+            return Ok(());
+        }
+        let rules = self.rules();
+        refresh_views(self, &rules).map_err(into_io_error)
+    }
+
+    /// Records an inserted or removed quad, `None` for a change that might affect any rule
+    fn record_rule_change(&self, change: Option<(&EncodedQuad, bool)>) {
+        if let Some(changes) = self.rule_changes.lock().unwrap().as_mut() {
+            if let Some((quad, inserted)) = change {
+                changes.record(quad, inserted);
+            } else {
+                changes.record_all();
+            }
+        }
+    }
+
+    fn insert_quad(&self, quad: &EncodedQuad) -> Result<(), io::Error> {
+        self.record_rule_change(Some((quad, true)));
+        let mut buffer = Vec::with_capacity(4 * WRITTEN_TERM_MAX_SIZE + 1);
+
+        if quad.graph_name.is_default_graph() {
+            write_spo_quad(&mut buffer, quad);
+            self.dspo.insert(buffer.as_slice(), &[])?;
+            buffer.clear();
+
+            write_pos_quad(&mut buffer, quad);
+            self.dpos.insert(buffer.as_slice(), &[])?;
+            buffer.clear();
+
+            write_osp_quad(&mut buffer, quad);
+            self.dosp.insert(buffer.as_slice(), &[])?;
+            buffer.clear();
+        } else {
+            write_spog_quad(&mut buffer, quad);
+            self.spog.insert(buffer.as_slice(), &[])?;
+            buffer.clear();
+
+            write_posg_quad(&mut buffer, quad);
+            self.posg.insert(buffer.as_slice(), &[])?;
+            buffer.clear();
+
+            write_ospg_quad(&mut buffer, quad);
+            self.ospg.insert(buffer.as_slice(), &[])?;
+            buffer.clear();
+
+            write_gspo_quad(&mut buffer, quad);
+            self.gspo.insert(buffer.as_slice(), &[])?;
+            buffer.clear();
+
+            write_gpos_quad(&mut buffer, quad);
+            self.gpos.insert(buffer.as_slice(), &[])?;
+            buffer.clear();
+
+            write_gosp_quad(&mut buffer, quad);
+            self.gosp.insert(buffer.as_slice(), &[])?;
+            buffer.clear();
+
+            write_term(&mut buffer, quad.graph_name);
+            self.graphs.insert(&buffer, &[])?;
+            buffer.clear();
+        }
+
+        if self.range_index {
+            if let Some(key) = RangeKey::new(quad.object) {
+                write_pvosg_quad(&mut buffer, key, quad);
+                self.range.insert(buffer.as_slice(), &[])?;
+                buffer.clear();
+            }
+        }
+
+        if self.text_index {
+            for token in encoded_term_tokens(self, quad.object)? {
+                write_ptosg_quad(&mut buffer, &token, quad);
+                self.text.insert(buffer.as_slice(), &[])?;
+                buffer.clear();
+            }
+        }
+
+        Ok(())
+    }
+
+    fn remove_quad(&self, quad: &EncodedQuad) -> Result<(), io::Error> {
+        self.record_rule_change(Some((quad, false)));
+        let mut buffer = Vec::with_capacity(4 * WRITTEN_TERM_MAX_SIZE + 1);
+
+        if quad.graph_name.is_default_graph() {
+            write_spo_quad(&mut buffer, quad);
+            self.dspo.remove(buffer.as_slice())?;
+            buffer.clear();
+
+            write_pos_quad(&mut buffer, quad);
+            self.dpos.remove(buffer.as_slice())?;
+            buffer.clear();
+
+            write_osp_quad(&mut buffer, quad);
+            self.dosp.remove(buffer.as_slice())?;
+            buffer.clear();
+        } else {
+            write_spog_quad(&mut buffer, quad);
+            self.spog.remove(buffer.as_slice())?;
+            buffer.clear();
+
+            write_posg_quad(&mut buffer, quad);
+            self.posg.remove(buffer.as_slice())?;
+            buffer.clear();
+
+            write_ospg_quad(&mut buffer, quad);
+            self.ospg.remove(buffer.as_slice())?;
+            buffer.clear();
+
+            write_gspo_quad(&mut buffer, quad);
+            self.gspo.remove(buffer.as_slice())?;
+            buffer.clear();
+
+            write_gpos_quad(&mut buffer, quad);
+            self.gpos.remove(buffer.as_slice())?;
+            buffer.clear();
+
+            write_gosp_quad(&mut buffer, quad);
+            self.gosp.remove(buffer.as_slice())?;
+            buffer.clear();
+        }
+
+        if self.range_index {
+            if let Some(key) = RangeKey::new(quad.object) {
+                write_pvosg_quad(&mut buffer, key, quad);
+                self.range.remove(buffer.as_slice())?;
+                buffer.clear();
+            }
+        }
+
+        if self.text_index {
+            for token in encoded_term_tokens(self, quad.object)? {
+                write_ptosg_quad(&mut buffer, &token, quad);
+                self.text.remove(buffer.as_slice())?;
+                buffer.clear();
+            }
+        }
+
+        Ok(())
     }
 
     fn contains_encoded(&self, quad: &EncodedQuad) -> Result<bool, io::Error> {
@@ -993,6 +1687,57 @@ impl ReadableEncodedStore for SledStore {
         }
     }
 
+    fn encoded_quads_for_predicate_object_range(
+        &self,
+        predicate: EncodedTerm,
+        range: EncodedRange,
+        graph_name: Option<EncodedTerm>,
+    ) -> Option<Box<dyn Iterator<Item = Result<EncodedQuad, io::Error>> + Send>> {
+        if !self.range_index {
+            return None;
+        }
+        let start = encode_term_range_key(predicate, range.start);
+        let iter = DecodingQuadIterator {
+            iter: if let Some(end) = prefix_successor(encode_term_range_key(predicate, range.end)) {
+                self.range.range(start..end)
+            } else {
+                self.range.range(start..)
+            },
+            encoding: QuadEncoding::PVOSG,
+        };
+        Some(if let Some(graph_name) = graph_name {
+            Box::new(iter.filter(move |quad| match quad {
+                Err(_) => true,
+                Ok(quad) => quad.graph_name == graph_name,
+            }))
+        } else {
+            Box::new(iter)
+        })
+    }
+
+    fn encoded_quads_for_predicate_object_token(
+        &self,
+        predicate: EncodedTerm,
+        token: &str,
+        graph_name: Option<EncodedTerm>,
+    ) -> Option<Box<dyn Iterator<Item = Result<EncodedQuad, io::Error>> + Send>> {
+        if !self.text_index {
+            return None;
+        }
+        let iter = DecodingQuadIterator {
+            iter: self.text.scan_prefix(encode_term_token(predicate, token)),
+            encoding: QuadEncoding::PTOSG,
+        };
+        Some(if let Some(graph_name) = graph_name {
+            Box::new(iter.filter(move |quad| match quad {
+                Err(_) => true,
+                Ok(quad) => quad.graph_name == graph_name,
+            }))
+        } else {
+            Box::new(iter)
+        })
+    }
+
     fn encoded_named_graphs(&self) -> DecodingGraphIterator {
         DecodingGraphIterator {
             iter: self.graphs.iter(),
@@ -1014,95 +1759,25 @@ impl<'a> StrContainer for &'a SledStore {
 
 impl<'a> WritableEncodedStore for &'a SledStore {
     fn insert_encoded(&mut self, quad: &EncodedQuad) -> Result<(), io::Error> {
-        let mut buffer = Vec::with_capacity(4 * WRITTEN_TERM_MAX_SIZE + 1);
-
-        if quad.graph_name.is_default_graph() {
-            write_spo_quad(&mut buffer, quad);
-            self.dspo.insert(buffer.as_slice(), &[])?;
-            buffer.clear();
-
-            write_pos_quad(&mut buffer, quad);
-            self.dpos.insert(buffer.as_slice(), &[])?;
-            buffer.clear();
-
-            write_osp_quad(&mut buffer, quad);
-            self.dosp.insert(buffer.as_slice(), &[])?;
-            buffer.clear();
-        } else {
-            write_spog_quad(&mut buffer, quad);
-            self.spog.insert(buffer.as_slice(), &[])?;
-            buffer.clear();
-
-            write_posg_quad(&mut buffer, quad);
-            self.posg.insert(buffer.as_slice(), &[])?;
-            buffer.clear();
-
-            write_ospg_quad(&mut buffer, quad);
-            self.ospg.insert(buffer.as_slice(), &[])?;
-            buffer.clear();
-
-            write_gspo_quad(&mut buffer, quad);
-            self.gspo.insert(buffer.as_slice(), &[])?;
-            buffer.clear();
-
-            write_gpos_quad(&mut buffer, quad);
-            self.gpos.insert(buffer.as_slice(), &[])?;
-            buffer.clear();
-
-            write_gosp_quad(&mut buffer, quad);
-            self.gosp.insert(buffer.as_slice(), &[])?;
-            buffer.clear();
-
-            write_term(&mut buffer, quad.graph_name);
-            self.graphs.insert(&buffer, &[])?;
-            buffer.clear();
+        let store: &SledStore = self;
+        if let Some(reasoner) = &store.reasoner {
+            if quad.graph_name.is_default_graph() && !store.contains_encoded(quad)? {
+                store.insert_quad(quad)?;
+                return reasoner.on_insert(self, [quad.subject, quad.predicate, quad.object]);
+            }
         }
-
-        Ok(())
+        store.insert_quad(quad)
     }
 
     fn remove_encoded(&mut self, quad: &EncodedQuad) -> Result<(), io::Error> {
-        let mut buffer = Vec::with_capacity(4 * WRITTEN_TERM_MAX_SIZE + 1);
-
-        if quad.graph_name.is_default_graph() {
-            write_spo_quad(&mut buffer, quad);
-            self.dspo.remove(buffer.as_slice())?;
-            buffer.clear();
-
-            write_pos_quad(&mut buffer, quad);
-            self.dpos.remove(buffer.as_slice())?;
-            buffer.clear();
-
-            write_osp_quad(&mut buffer, quad);
-            self.dosp.remove(buffer.as_slice())?;
-            buffer.clear();
-        } else {
-            write_spog_quad(&mut buffer, quad);
-            self.spog.remove(buffer.as_slice())?;
-            buffer.clear();
-
-            write_posg_quad(&mut buffer, quad);
-            self.posg.remove(buffer.as_slice())?;
-            buffer.clear();
-
-            write_ospg_quad(&mut buffer, quad);
-            self.ospg.remove(buffer.as_slice())?;
-            buffer.clear();
-
-            write_gspo_quad(&mut buffer, quad);
-            self.gspo.remove(buffer.as_slice())?;
-            buffer.clear();
-
-            write_gpos_quad(&mut buffer, quad);
-            self.gpos.remove(buffer.as_slice())?;
-            buffer.clear();
-
-            write_gosp_quad(&mut buffer, quad);
-            self.gosp.remove(buffer.as_slice())?;
-            buffer.clear();
+        let store: &SledStore = self;
+        if let Some(reasoner) = &store.reasoner {
+            if quad.graph_name.is_default_graph() && store.contains_encoded(quad)? {
+                store.remove_quad(quad)?;
+                return reasoner.on_remove(self, [quad.subject, quad.predicate, quad.object]);
+            }
         }
-
-        Ok(())
+        store.remove_quad(quad)
     }
 
     fn insert_encoded_named_graph(&mut self, graph_name: EncodedTerm) -> Result<(), io::Error> {
@@ -1112,9 +1787,38 @@ impl<'a> WritableEncodedStore for &'a SledStore {
 
     fn clear_encoded_graph(&mut self, graph_name: EncodedTerm) -> Result<(), io::Error> {
         if graph_name.is_default_graph() {
+            if self.range_index {
+                let mut buffer =
+                    Vec::with_capacity(4 * WRITTEN_TERM_MAX_SIZE + WRITTEN_RANGE_KEY_SIZE);
+                for quad in self.quads_for_graph(graph_name) {
+                    let quad = quad?;
+                    if let Some(key) = RangeKey::new(quad.object) {
+                        write_pvosg_quad(&mut buffer, key, &quad);
+                        self.range.remove(buffer.as_slice())?;
+                        buffer.clear();
+                    }
+                }
+            }
+            if self.text_index {
+                let mut buffer = Vec::with_capacity(5 * WRITTEN_TERM_MAX_SIZE);
+                for quad in self.quads_for_graph(graph_name) {
+                    let quad = quad?;
+                    for token in encoded_term_tokens(*self, quad.object)? {
+                        write_ptosg_quad(&mut buffer, &token, &quad);
+                        self.text.remove(buffer.as_slice())?;
+                        buffer.clear();
+                    }
+                }
+            }
             self.dspo.clear()?;
             self.dpos.clear()?;
             self.dosp.clear()?;
+            self.record_rule_change(None);
+            let store: &SledStore = self;
+            if let Some(reasoner) = &store.reasoner {
+                // All the inferred triples are derived from the default graph
+                self.clear_encoded_graph(reasoner.inferred_graph())?;
+            }
         } else {
             for quad in self.quads_for_graph(graph_name) {
                 self.remove_encoded(&quad?)?;
@@ -1142,7 +1846,105 @@ impl<'a> WritableEncodedStore for &'a SledStore {
         self.posg.clear()?;
         self.ospg.clear()?;
         self.graphs.clear()?;
+        self.range.clear()?;
+        self.text.clear()?;
         self.id2str.clear()?;
+        self.record_rule_change(None);
+        Ok(())
+    }
+}
+
+impl RuleStore for SledStore {
+    fn take_rule_changes(&self) -> RuleChanges<StrHash> {
+        self.rule_changes
+            .lock()
+            .unwrap()
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
+
+    fn write_view_changes(&self, inserted: &[Quad], removed: &[Quad]) -> Result<(), io::Error> {
+        let mut this = self;
+        for quad in removed {
+            let quad = this.encode_quad(quad.as_ref())?;
+            this.remove_encoded(&quad)?;
+        }
+        for quad in inserted {
+            let quad = this.encode_quad(quad.as_ref())?;
+            this.insert_encoded(&quad)?;
+        }
+        Ok(())
+    }
+}
+
+impl InferenceStore for &SledStore {
+    fn closure_triples_for_pattern(
+        &self,
+        subject: Option<EncodedTerm>,
+        predicate: Option<EncodedTerm>,
+        object: Option<EncodedTerm>,
+    ) -> Result<Vec<EncodedTriple<StrHash>>, io::Error> {
+        let mut triples = Vec::new();
+        let mut graph_names = vec![EncodedTerm::DefaultGraph];
+        graph_names.extend(self.reasoner.as_ref().map(|r| r.inferred_graph()));
+        for graph_name in graph_names {
+            for quad in self.encoded_quads_for_pattern(subject, predicate, object, Some(graph_name))
+            {
+                let quad = quad?;
+                triples.push([quad.subject, quad.predicate, quad.object]);
+            }
+        }
+        Ok(triples)
+    }
+
+    fn contains_asserted(&self, triple: &EncodedTriple<StrHash>) -> Result<bool, io::Error> {
+        let [subject, predicate, object] = *triple;
+        self.contains_encoded(&EncodedQuad::new(
+            subject,
+            predicate,
+            object,
+            EncodedTerm::DefaultGraph,
+        ))
+    }
+
+    fn contains_inferred(&self, triple: &EncodedTriple<StrHash>) -> Result<bool, io::Error> {
+        if let Some(reasoner) = &self.reasoner {
+            let [subject, predicate, object] = *triple;
+            self.contains_encoded(&EncodedQuad::new(
+                subject,
+                predicate,
+                object,
+                reasoner.inferred_graph(),
+            ))
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn insert_inferred(&mut self, triple: &EncodedTriple<StrHash>) -> Result<(), io::Error> {
+        if let Some(reasoner) = &self.reasoner {
+            let [subject, predicate, object] = *triple;
+            self.insert_quad(&EncodedQuad::new(
+                subject,
+                predicate,
+                object,
+                reasoner.inferred_graph(),
+            ))?;
+        }
+        Ok(())
+    }
+
+    fn remove_inferred(&mut self, triple: &EncodedTriple<StrHash>) -> Result<(), io::Error> {
+        if let Some(reasoner) = &self.reasoner {
+            let [subject, predicate, object] = *triple;
+            self.remove_quad(&EncodedQuad::new(
+                subject,
+                predicate,
+                object,
+                reasoner.inferred_graph(),
+            ))?;
+        }
         Ok(())
     }
 }
@@ -1160,6 +1962,12 @@ pub struct SledTransaction<'a> {
     dpos: &'a TransactionalTree,
     dosp: &'a TransactionalTree,
     graphs: &'a TransactionalTree,
+    range: &'a TransactionalTree,
+    range_index: bool,
+    text: &'a TransactionalTree,
+    text_index: bool,
+    reasoning_changes: Option<&'a RefCell<Vec<(EncodedQuad, bool)>>>,
+    rule_changes: Option<&'a RefCell<RuleChanges<StrHash>>>,
 }
 
 impl SledTransaction<'_> {
@@ -1201,12 +2009,18 @@ impl SledTransaction<'_> {
     pub fn load_graph<'a>(
         &self,
         reader: impl BufRead,
-        format: GraphFormat,
+        format: impl Into<GraphParser>,
         to_graph_name: impl Into<GraphNameRef<'a>>,
         base_iri: Option<&str>,
     ) -> Result<(), SledUnabortableTransactionError> {
         let mut this = self;
-        load_graph(&mut this, reader, format, to_graph_name.into(), base_iri)?;
+        load_graph(
+            &mut this,
+            reader,
+            format.into(),
+            to_graph_name.into(),
+            base_iri,
+        )?;
         Ok(())
     }
 
@@ -1248,11 +2062,11 @@ impl SledTransaction<'_> {
     pub fn load_dataset(
         &self,
         reader: impl BufRead,
-        format: DatasetFormat,
+        format: impl Into<DatasetParser>,
         base_iri: Option<&str>,
     ) -> Result<(), SledUnabortableTransactionError> {
         let mut this = self;
-        load_dataset(&mut this, reader, format, base_iri)?;
+        load_dataset(&mut this, reader, format.into(), base_iri)?;
         Ok(())
     }
 
@@ -1317,10 +2131,18 @@ impl<'a> WritableEncodedStore for &'a SledTransaction<'a> {
         &mut self,
         quad: &EncodedQuad,
     ) -> Result<(), SledUnabortableTransactionError> {
+        if let Some(changes) = self.rule_changes {
+            changes.borrow_mut().record(quad, true);
+        }
         let mut buffer = Vec::with_capacity(4 * WRITTEN_TERM_MAX_SIZE + 1);
 
         if quad.graph_name.is_default_graph() {
             write_spo_quad(&mut buffer, quad);
+            if let Some(changes) = self.reasoning_changes {
+                if self.dspo.get(buffer.as_slice())?.is_none() {
+                    changes.borrow_mut().push((*quad, true));
+                }
+            }
             self.dspo.insert(buffer.as_slice(), &[])?;
             buffer.clear();
 
@@ -1361,6 +2183,22 @@ impl<'a> WritableEncodedStore for &'a SledTransaction<'a> {
             buffer.clear();
         }
 
+        if self.range_index {
+            if let Some(key) = RangeKey::new(quad.object) {
+                write_pvosg_quad(&mut buffer, key, quad);
+                self.range.insert(buffer.as_slice(), &[])?;
+                buffer.clear();
+            }
+        }
+
+        if self.text_index {
+            for token in encoded_term_tokens(self, quad.object)? {
+                write_ptosg_quad(&mut buffer, &token, quad);
+                self.text.insert(buffer.as_slice(), &[])?;
+                buffer.clear();
+            }
+        }
+
         Ok(())
     }
 
@@ -1368,11 +2206,18 @@ impl<'a> WritableEncodedStore for &'a SledTransaction<'a> {
         &mut self,
         quad: &EncodedQuad,
     ) -> Result<(), SledUnabortableTransactionError> {
+        if let Some(changes) = self.rule_changes {
+            changes.borrow_mut().record(quad, false);
+        }
         let mut buffer = Vec::with_capacity(4 * WRITTEN_TERM_MAX_SIZE + 1);
 
         if quad.graph_name.is_default_graph() {
             write_spo_quad(&mut buffer, quad);
-            self.dspo.remove(buffer.as_slice())?;
+            if self.dspo.remove(buffer.as_slice())?.is_some() {
+                if let Some(changes) = self.reasoning_changes {
+                    changes.borrow_mut().push((*quad, false));
+                }
+            }
             buffer.clear();
 
             write_pos_quad(&mut buffer, quad);
@@ -1408,6 +2253,22 @@ impl<'a> WritableEncodedStore for &'a SledTransaction<'a> {
             buffer.clear();
         }
 
+        if self.range_index {
+            if let Some(key) = RangeKey::new(quad.object) {
+                write_pvosg_quad(&mut buffer, key, quad);
+                self.range.remove(buffer.as_slice())?;
+                buffer.clear();
+            }
+        }
+
+        if self.text_index {
+            for token in encoded_term_tokens(self, quad.object)? {
+                write_ptosg_quad(&mut buffer, &token, quad);
+                self.text.remove(buffer.as_slice())?;
+                buffer.clear();
+            }
+        }
+
         Ok(())
     }
 
@@ -1720,7 +2581,7 @@ impl Iterator for SledGraphNameIter {
 fn store() -> Result<(), io::Error> {
     use crate::model::*;
 
-    let main_s = NamedOrBlankNode::from(BlankNode::default());
+    let main_s = Subject::from(BlankNode::default());
     let main_p = NamedNode::new("http://example.com").unwrap();
     let main_o = Term::from(Literal::from(1));
     let main_g = GraphName::from(BlankNode::default());
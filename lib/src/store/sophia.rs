@@ -44,7 +44,7 @@ macro_rules! impl_dataset {
             fn quads(&self) -> DQuadSource<'_, Self> {
                 Box::new(
                     self.quads_for_pattern(None, None, None, None)
-                        .map($quad_map),
+                        .filter_map($quad_map),
                 )
             }
             fn quads_with_s<'s, TS>(&'s self, s: &'s TS) -> DQuadSource<'s, Self>
@@ -56,7 +56,7 @@ macro_rules! impl_dataset {
                 if s.is_none() {
                     Box::new(empty())
                 } else {
-                    Box::new(self.quads_for_pattern(s, None, None, None).map($quad_map))
+                    Box::new(self.quads_for_pattern(s, None, None, None).filter_map($quad_map))
                 }
             }
             fn quads_with_p<'s, TP>(&'s self, p: &'s TP) -> DQuadSource<'s, Self>
@@ -68,7 +68,7 @@ macro_rules! impl_dataset {
                 if p.is_none() {
                     Box::new(empty())
                 } else {
-                    Box::new(self.quads_for_pattern(None, p, None, None).map($quad_map))
+                    Box::new(self.quads_for_pattern(None, p, None, None).filter_map($quad_map))
                 }
             }
             fn quads_with_o<'s, TS>(&'s self, o: &'s TS) -> DQuadSource<'s, Self>
@@ -80,7 +80,7 @@ macro_rules! impl_dataset {
                 if o.is_none() {
                     Box::new(empty())
                 } else {
-                    Box::new(self.quads_for_pattern(None, None, o, None).map($quad_map))
+                    Box::new(self.quads_for_pattern(None, None, o, None).filter_map($quad_map))
                 }
             }
             fn quads_with_g<'s, TS>(&'s self, g: Option<&'s TS>) -> DQuadSource<'s, Self>
@@ -92,7 +92,7 @@ macro_rules! impl_dataset {
                 if g.is_none() {
                     Box::new(empty())
                 } else {
-                    Box::new(self.quads_for_pattern(None, None, None, g).map($quad_map))
+                    Box::new(self.quads_for_pattern(None, None, None, g).filter_map($quad_map))
                 }
             }
             fn quads_with_sp<'s, TS, TP>(&'s self, s: &'s TS, p: &'s TP) -> DQuadSource<'s, Self>
@@ -107,7 +107,7 @@ macro_rules! impl_dataset {
                 if s.is_none() || p.is_none() {
                     Box::new(empty())
                 } else {
-                    Box::new(self.quads_for_pattern(s, p, None, None).map($quad_map))
+                    Box::new(self.quads_for_pattern(s, p, None, None).filter_map($quad_map))
                 }
             }
             fn quads_with_so<'s, TS, TO>(&'s self, s: &'s TS, o: &'s TO) -> DQuadSource<'s, Self>
@@ -122,7 +122,7 @@ macro_rules! impl_dataset {
                 if s.is_none() || o.is_none() {
                     Box::new(empty())
                 } else {
-                    Box::new(self.quads_for_pattern(s, None, o, None).map($quad_map))
+                    Box::new(self.quads_for_pattern(s, None, o, None).filter_map($quad_map))
                 }
             }
             fn quads_with_sg<'s, TS, TG>(
@@ -141,7 +141,7 @@ macro_rules! impl_dataset {
                 if s.is_none() || g.is_none() {
                     Box::new(empty())
                 } else {
-                    Box::new(self.quads_for_pattern(s, None, None, g).map($quad_map))
+                    Box::new(self.quads_for_pattern(s, None, None, g).filter_map($quad_map))
                 }
             }
             fn quads_with_po<'s, TP, TO>(&'s self, p: &'s TP, o: &'s TO) -> DQuadSource<'s, Self>
@@ -156,7 +156,7 @@ macro_rules! impl_dataset {
                 if p.is_none() || o.is_none() {
                     Box::new(empty())
                 } else {
-                    Box::new(self.quads_for_pattern(None, p, o, None).map($quad_map))
+                    Box::new(self.quads_for_pattern(None, p, o, None).filter_map($quad_map))
                 }
             }
             fn quads_with_pg<'s, TP, TG>(
@@ -175,7 +175,7 @@ macro_rules! impl_dataset {
                 if p.is_none() || g.is_none() {
                     Box::new(empty())
                 } else {
-                    Box::new(self.quads_for_pattern(None, p, None, g).map($quad_map))
+                    Box::new(self.quads_for_pattern(None, p, None, g).filter_map($quad_map))
                 }
             }
             fn quads_with_og<'s, TO, TG>(
@@ -194,7 +194,7 @@ macro_rules! impl_dataset {
                 if o.is_none() || g.is_none() {
                     Box::new(empty())
                 } else {
-                    Box::new(self.quads_for_pattern(None, None, o, g).map($quad_map))
+                    Box::new(self.quads_for_pattern(None, None, o, g).filter_map($quad_map))
                 }
             }
             fn quads_with_spo<'s, TS, TP, TO>(
@@ -217,7 +217,7 @@ macro_rules! impl_dataset {
                 if s.is_none() || p.is_none() || o.is_none() {
                     Box::new(empty())
                 } else {
-                    Box::new(self.quads_for_pattern(s, p, o, None).map($quad_map))
+                    Box::new(self.quads_for_pattern(s, p, o, None).filter_map($quad_map))
                 }
             }
             fn quads_with_spg<'s, TS, TP, TG>(
@@ -240,7 +240,7 @@ macro_rules! impl_dataset {
                 if s.is_none() || p.is_none() || g.is_none() {
                     Box::new(empty())
                 } else {
-                    Box::new(self.quads_for_pattern(s, p, None, g).map($quad_map))
+                    Box::new(self.quads_for_pattern(s, p, None, g).filter_map($quad_map))
                 }
             }
             fn quads_with_sog<'s, TS, TO, TG>(
@@ -263,7 +263,7 @@ macro_rules! impl_dataset {
                 if s.is_none() || o.is_none() || g.is_none() {
                     Box::new(empty())
                 } else {
-                    Box::new(self.quads_for_pattern(s, None, o, g).map($quad_map))
+                    Box::new(self.quads_for_pattern(s, None, o, g).filter_map($quad_map))
                 }
             }
             fn quads_with_pog<'s, TP, TO, TG>(
@@ -286,7 +286,7 @@ macro_rules! impl_dataset {
                 if p.is_none() || o.is_none() || g.is_none() {
                     Box::new(empty())
                 } else {
-                    Box::new(self.quads_for_pattern(None, p, o, g).map($quad_map))
+                    Box::new(self.quads_for_pattern(None, p, o, g).filter_map($quad_map))
                 }
             }
             fn quads_with_spog<'s, TS, TP, TO, TG>(
@@ -313,7 +313,7 @@ macro_rules! impl_dataset {
                 if s.is_none() || p.is_none() || o.is_none() || g.is_none() {
                     Box::new(empty())
                 } else {
-                    Box::new(self.quads_for_pattern(s, p, o, g).map($quad_map))
+                    Box::new(self.quads_for_pattern(s, p, o, g).filter_map($quad_map))
                 }
             }
             fn subjects(&self) -> DResultTermSet<Self>
@@ -323,7 +323,7 @@ macro_rules! impl_dataset {
                 sparql_to_hashset!(
                     self,
                     $err_map,
-                    "SELECT DISTINCT ?s {{?s ?p ?o} UNION { GRAPH ?g {?s ?p ?o}}}"
+                    "SELECT DISTINCT ?s {{?s ?p ?o} UNION { GRAPH ?g {?s ?p ?o}} FILTER(!isTRIPLE(?s))}"
                 )
             }
             fn predicates(&self) -> DResultTermSet<Self>
@@ -343,7 +343,7 @@ macro_rules! impl_dataset {
                 sparql_to_hashset!(
                     self,
                     $err_map,
-                    "SELECT DISTINCT ?o {{?s ?p ?o} UNION { GRAPH ?g {?s ?p ?o}}}"
+                    "SELECT DISTINCT ?o {{?s ?p ?o} UNION { GRAPH ?g {?s ?p ?o}} FILTER(!isTRIPLE(?o))}"
                 )
             }
             fn graph_names(&self) -> DResultTermSet<Self>
@@ -484,6 +484,22 @@ mod memory {
 
     #[cfg(test)]
     sophia_api::test_dataset_impl!(test, MemoryStore, false, false);
+
+    #[test]
+    fn test_quoted_triples_are_skipped() {
+        let ex = NamedNodeRef::new_unchecked("http://example.com");
+        let store = MemoryStore::new();
+        store.insert(QuadRef::new(ex, ex, ex, GraphNameRef::DefaultGraph));
+        store.insert(Quad::new(
+            Triple::new(ex, ex, ex),
+            ex,
+            Triple::new(ex, ex, ex),
+            GraphName::DefaultGraph,
+        ));
+        assert_eq!(Dataset::quads(&store).count(), 1);
+        assert_eq!(store.subjects().unwrap().len(), 1);
+        assert_eq!(store.objects().unwrap().len(), 1);
+    }
 }
 
 #[cfg(feature = "sled")]
@@ -620,10 +636,19 @@ mod rocksdb {
 }
 
 // helper functions
-#[allow(clippy::unnecessary_wraps)]
-fn infallible_quad_map<'a>(q: Quad) -> Result<StreamedSophiaQuad<'a>, Infallible> {
-    let q: SophiaQuad = q.into();
-    Ok(StreamedQuad::by_value(q))
+
+/// Sophia does not support RDF-star: the quads containing quoted triples are not visible through its API
+fn is_sophia_quad(quad: &Quad) -> bool {
+    !quad.subject.is_triple() && !quad.object.is_triple()
+}
+
+fn infallible_quad_map<'a>(q: Quad) -> Option<Result<StreamedSophiaQuad<'a>, Infallible>> {
+    if is_sophia_quad(&q) {
+        let q: SophiaQuad = q.into();
+        Some(Ok(StreamedQuad::by_value(q)))
+    } else {
+        None
+    }
 }
 
 fn infallible_err_map(_: EvaluationError) -> Infallible {
@@ -633,11 +658,18 @@ fn infallible_err_map(_: EvaluationError) -> Infallible {
 #[cfg(any(feature = "rocksdb", feature = "sled"))]
 fn io_quad_map<'a>(
     res: Result<Quad, std::io::Error>,
-) -> Result<StreamedSophiaQuad<'a>, std::io::Error> {
-    res.map(|q| {
-        let q: SophiaQuad = q.into();
-        StreamedQuad::by_value(q)
-    })
+) -> Option<Result<StreamedSophiaQuad<'a>, std::io::Error>> {
+    match res {
+        Ok(q) => {
+            if is_sophia_quad(&q) {
+                let q: SophiaQuad = q.into();
+                Some(Ok(StreamedQuad::by_value(q)))
+            } else {
+                None
+            }
+        }
+        Err(e) => Some(Err(e)),
+    }
 }
 
 #[cfg(any(feature = "rocksdb", feature = "sled"))]
@@ -648,7 +680,7 @@ fn io_err_map(err: EvaluationError) -> std::io::Error {
     }
 }
 
-fn convert_subject<'a, T>(term: &'a T, buffer: &'a mut String) -> Option<NamedOrBlankNodeRef<'a>>
+fn convert_subject<'a, T>(term: &'a T, buffer: &'a mut String) -> Option<SubjectRef<'a>>
 where
     T: TTerm + ?Sized + 'a,
 {
@@ -0,0 +1,311 @@
+//! Geometry decoding and R-tree used by the spatial index and the GeoSPARQL functions.
+
+use crate::model::geo::{Envelope, Geometry};
+use crate::model::{LiteralRef, NamedNodeRef};
+use crate::store::numeric_encoder::{EncodedTerm, StrLookup};
+use std::mem::replace;
+
+const MAX_CHILDREN: usize = 16;
+
+/// A bounding box that the object geometries must intersect to pass a spatial FILTER
+///
+/// The coordinates are stored as bits in order to be hashable.
+#[derive(Eq, PartialEq, Debug, Clone, Copy, Hash)]
+pub struct SearchBox {
+    min_x: u64,
+    min_y: u64,
+    max_x: u64,
+    max_y: u64,
+}
+
+impl SearchBox {
+    pub fn new(envelope: Envelope) -> Option<Self> {
+        if envelope.min_x <= envelope.max_x && envelope.min_y <= envelope.max_y {
+            Some(Self {
+                min_x: envelope.min_x.to_bits(),
+                min_y: envelope.min_y.to_bits(),
+                max_x: envelope.max_x.to_bits(),
+                max_y: envelope.max_y.to_bits(),
+            })
+        } else {
+            None // Also excludes NaN
+        }
+    }
+
+    pub fn envelope(self) -> Envelope {
+        Envelope {
+            min_x: f64::from_bits(self.min_x),
+            min_y: f64::from_bits(self.min_y),
+            max_x: f64::from_bits(self.max_x),
+            max_y: f64::from_bits(self.max_y),
+        }
+    }
+}
+
+/// Returns the geometry of a `geo:wktLiteral` or a `geo:geoJSONLiteral` or nothing if the term is not a valid geometry literal
+pub fn encoded_term_geometry<S: StrLookup>(
+    store: &S,
+    term: EncodedTerm<S::StrId>,
+) -> Result<Option<Geometry>, S::Error> {
+    let (value, datatype_id) = match term {
+        EncodedTerm::SmallTypedLiteral { value, datatype_id } => (value.into(), datatype_id),
+        EncodedTerm::BigTypedLiteral {
+            value_id,
+            datatype_id,
+        } => {
+            if let Some(value) = store.get_str(value_id)? {
+                (value, datatype_id)
+            } else {
+                return Ok(None);
+            }
+        }
+        _ => return Ok(None),
+    };
+    let datatype: String = if let Some(datatype) = store.get_str(datatype_id)? {
+        datatype
+    } else {
+        return Ok(None);
+    };
+    Ok(Geometry::from_literal(LiteralRef::new_typed_literal(
+        &value,
+        NamedNodeRef::new_unchecked(&datatype),
+    ))
+    .ok())
+}
+
+/// A R-tree storing values by bounding box
+///
+/// Nodes are split along their widest axis when they have more than `MAX_CHILDREN` children.
+/// Removals do not rebalance the tree.
+pub struct RTree<T> {
+    root: Node<T>,
+}
+
+enum Node<T> {
+    Leaf(Vec<(Envelope, T)>),
+    Internal(Vec<(Envelope, Node<T>)>),
+}
+
+impl<T> Default for RTree<T> {
+    fn default() -> Self {
+        Self {
+            root: Node::Leaf(Vec::new()),
+        }
+    }
+}
+
+impl<T: PartialEq> RTree<T> {
+    /// Inserts the value if it is not already stored with the same box
+    pub fn insert(&mut self, envelope: Envelope, value: T) {
+        if self.contains(&envelope, &value) {
+            return;
+        }
+        if let Some(sibling) = self.root.insert(envelope, value) {
+            let old_root = replace(&mut self.root, Node::Leaf(Vec::new()));
+            if let Some(old_envelope) = old_root.envelope() {
+                self.root = Node::Internal(vec![(old_envelope, old_root), sibling]);
+            }
+        }
+    }
+
+    pub fn remove(&mut self, envelope: &Envelope, value: &T) -> bool {
+        let removed = self.root.remove(envelope, value);
+        if let Node::Internal(children) = &mut self.root {
+            if children.is_empty() {
+                self.root = Node::Leaf(Vec::new());
+            } else if children.len() == 1 {
+                self.root = children.pop().unwrap().1;
+            }
+        }
+        removed
+    }
+
+    pub fn contains(&self, envelope: &Envelope, value: &T) -> bool {
+        let mut stack = vec![&self.root];
+        while let Some(node) = stack.pop() {
+            match node {
+                Node::Leaf(entries) => {
+                    if entries.iter().any(|(e, v)| e == envelope && v == value) {
+                        return true;
+                    }
+                }
+                Node::Internal(children) => stack.extend(
+                    children
+                        .iter()
+                        .filter(|(e, _)| e.intersects(envelope))
+                        .map(|(_, c)| c),
+                ),
+            }
+        }
+        false
+    }
+
+    /// Returns the values whose bounding box intersects the given box
+    pub fn search(&self, envelope: &Envelope) -> Vec<&T> {
+        let mut results = Vec::new();
+        let mut stack = vec![&self.root];
+        while let Some(node) = stack.pop() {
+            match node {
+                Node::Leaf(entries) => results.extend(
+                    entries
+                        .iter()
+                        .filter(|(e, _)| e.intersects(envelope))
+                        .map(|(_, v)| v),
+                ),
+                Node::Internal(children) => stack.extend(
+                    children
+                        .iter()
+                        .filter(|(e, _)| e.intersects(envelope))
+                        .map(|(_, c)| c),
+                ),
+            }
+        }
+        results
+    }
+
+    /// Removes all the values not matching the predicate
+    pub fn retain(&mut self, mut f: impl FnMut(&T) -> bool) {
+        let mut entries = Vec::new();
+        replace(&mut self.root, Node::Leaf(Vec::new())).into_entries(&mut entries);
+        for (envelope, value) in entries {
+            if f(&value) {
+                self.insert(envelope, value);
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        matches!(&self.root, Node::Leaf(entries) if entries.is_empty())
+    }
+}
+
+impl<T: PartialEq> Node<T> {
+    fn envelope(&self) -> Option<Envelope> {
+        match self {
+            Node::Leaf(entries) => entries_envelope(entries),
+            Node::Internal(children) => entries_envelope(children),
+        }
+    }
+
+    /// Inserts the value and returns the new sibling node if the node has been split
+    fn insert(&mut self, envelope: Envelope, value: T) -> Option<(Envelope, Node<T>)> {
+        match self {
+            Node::Leaf(entries) => {
+                entries.push((envelope, value));
+                if entries.len() > MAX_CHILDREN {
+                    let (sibling_envelope, sibling) = split(entries);
+                    Some((sibling_envelope, Node::Leaf(sibling)))
+                } else {
+                    None
+                }
+            }
+            Node::Internal(children) => {
+                // We choose the child needing the smallest enlargement
+                let (position, _) =
+                    children
+                        .iter()
+                        .enumerate()
+                        .min_by(|(_, (a, _)), (_, (b, _))| {
+                            let a_enlargement = a.union(&envelope).area() - a.area();
+                            let b_enlargement = b.union(&envelope).area() - b.area();
+                            a_enlargement
+                                .partial_cmp(&b_enlargement)
+                                .unwrap_or(std::cmp::Ordering::Equal)
+                                .then_with(|| {
+                                    a.area()
+                                        .partial_cmp(&b.area())
+                                        .unwrap_or(std::cmp::Ordering::Equal)
+                                })
+                        })?;
+                let (child_envelope, child) = &mut children[position];
+                let new_sibling = child.insert(envelope, value);
+                if let Some(new_envelope) = child.envelope() {
+                    *child_envelope = new_envelope;
+                }
+                if let Some(sibling) = new_sibling {
+                    children.push(sibling);
+                    if children.len() > MAX_CHILDREN {
+                        let (sibling_envelope, sibling) = split(children);
+                        return Some((sibling_envelope, Node::Internal(sibling)));
+                    }
+                }
+                None
+            }
+        }
+    }
+
+    fn remove(&mut self, envelope: &Envelope, value: &T) -> bool {
+        match self {
+            Node::Leaf(entries) => {
+                if let Some(position) = entries
+                    .iter()
+                    .position(|(e, v)| e == envelope && v == value)
+                {
+                    entries.swap_remove(position);
+                    true
+                } else {
+                    false
+                }
+            }
+            Node::Internal(children) => {
+                for i in 0..children.len() {
+                    if children[i].0.intersects(envelope) && children[i].1.remove(envelope, value) {
+                        if let Some(new_envelope) = children[i].1.envelope() {
+                            children[i].0 = new_envelope;
+                        } else {
+                            children.swap_remove(i);
+                        }
+                        return true;
+                    }
+                }
+                false
+            }
+        }
+    }
+
+    fn into_entries(self, output: &mut Vec<(Envelope, T)>) {
+        match self {
+            Node::Leaf(entries) => output.extend(entries),
+            Node::Internal(children) => {
+                for (_, child) in children {
+                    child.into_entries(output)
+                }
+            }
+        }
+    }
+}
+
+fn entries_envelope<V>(entries: &[(Envelope, V)]) -> Option<Envelope> {
+    let mut envelopes = entries.iter().map(|(e, _)| *e);
+    let first = envelopes.next()?;
+    Some(envelopes.fold(first, |a, b| a.union(&b)))
+}
+
+/// Splits the entries in two halves along the axis where their centers are the most spread
+///
+/// Returns the second half and its envelope
+fn split<V>(entries: &mut Vec<(Envelope, V)>) -> (Envelope, Vec<(Envelope, V)>) {
+    let center_x = |e: &Envelope| e.min_x + e.max_x;
+    let center_y = |e: &Envelope| e.min_y + e.max_y;
+    let spread = |center: &dyn Fn(&Envelope) -> f64| {
+        let (min, max) = entries
+            .iter()
+            .fold((f64::MAX, f64::MIN), |(min, max), (e, _)| {
+                (min.min(center(e)), max.max(center(e)))
+            });
+        max - min
+    };
+    let center: &dyn Fn(&Envelope) -> f64 = if spread(&center_x) >= spread(&center_y) {
+        &center_x
+    } else {
+        &center_y
+    };
+    entries.sort_by(|(a, _), (b, _)| {
+        center(a)
+            .partial_cmp(&center(b))
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    let other = entries.split_off(entries.len() / 2);
+    let other_envelope = entries_envelope(&other).unwrap();
+    (other_envelope, other)
+}
@@ -0,0 +1,75 @@
+//! Tokenization and scoring used by the full-text index and the text search SPARQL functions.
+
+use crate::store::numeric_encoder::{EncodedTerm, StrLookup};
+use std::collections::BTreeSet;
+
+/// Splits a text into case-folded alphanumeric tokens
+pub fn tokenize(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|token| !token.is_empty())
+        .map(|token| token.to_lowercase())
+}
+
+/// Returns the distinct tokens of a string literal lexical form or nothing if the term is not a string literal
+pub fn encoded_term_tokens<S: StrLookup>(
+    store: &S,
+    term: EncodedTerm<S::StrId>,
+) -> Result<BTreeSet<String>, S::Error> {
+    Ok(match term {
+        EncodedTerm::SmallStringLiteral(value)
+        | EncodedTerm::SmallSmallLangStringLiteral { value, .. }
+        | EncodedTerm::SmallBigLangStringLiteral { value, .. } => tokenize(&value).collect(),
+        EncodedTerm::BigStringLiteral { value_id }
+        | EncodedTerm::BigSmallLangStringLiteral { value_id, .. }
+        | EncodedTerm::BigBigLangStringLiteral { value_id, .. } => {
+            if let Some(value) = store.get_str(value_id)? {
+                tokenize(&value).collect()
+            } else {
+                BTreeSet::default()
+            }
+        }
+        _ => BTreeSet::default(),
+    })
+}
+
+/// Returns the query token that is the most selective one for an index lookup
+///
+/// We assume that the longest tokens are the rarest ones.
+pub fn lookup_token(query: &str) -> Option<String> {
+    tokenize(query).max_by_key(|token| token.chars().count())
+}
+
+/// Scores how well a text matches a query.
+///
+/// The score is the proportion of the query tokens found in the text
+/// multiplied by the proportion of the text tokens found in the query.
+/// It is between 0 (no match) and 1 (same tokens).
+///
+/// Returns `None` if the query does not contain any token.
+#[allow(clippy::cast_precision_loss)]
+pub fn text_score(text: &str, query: &str) -> Option<f64> {
+    let query = tokenize(query).collect::<BTreeSet<_>>();
+    if query.is_empty() {
+        return None;
+    }
+    let text = tokenize(text).collect::<Vec<_>>();
+    if text.is_empty() {
+        return Some(0.);
+    }
+    let found_query_tokens = query.iter().filter(|token| text.contains(token)).count();
+    let found_text_tokens = text.iter().filter(|token| query.contains(*token)).count();
+    Some(
+        (found_query_tokens as f64 / query.len() as f64)
+            * (found_text_tokens as f64 / text.len() as f64),
+    )
+}
+
+/// Checks if a text contains all the tokens of a query.
+///
+/// Returns `None` if the query does not contain any token.
+pub fn text_match(text: &str, query: &str) -> Option<bool> {
+    let text = tokenize(text).collect::<BTreeSet<_>>();
+    let mut query = tokenize(query).peekable();
+    query.peek()?;
+    Some(query.all(|token| text.contains(&token)))
+}
@@ -1,7 +1,14 @@
+use oxigraph::io::GraphFormat;
 use oxigraph::model::vocab::{rdf, xsd};
 use oxigraph::model::*;
+use oxigraph::store::StoreOptions;
 use oxigraph::RocksDbStore;
+use rand::random;
+use std::env::temp_dir;
+use std::error::Error;
+use std::fs::remove_dir_all;
 use std::io;
+use std::io::Cursor;
 use std::process::Command;
 
 fn quads(graph_name: impl Into<GraphNameRef<'static>>) -> Vec<QuadRef<'static>> {
@@ -79,6 +86,136 @@ fn test_backward_compatibility() -> io::Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_owl2rl_reasoning() -> Result<(), Box<dyn Error>> {
+    let mut repo_path = temp_dir();
+    repo_path.push(random::<u128>().to_string());
+    {
+        let store = RocksDbStore::open_with_options(
+            &repo_path,
+            StoreOptions::default().with_owl2rl_reasoning(),
+        )?;
+        store.load_graph(
+            Cursor::new(
+                r#"
+@prefix ex: <http://example.com/> .
+@prefix owl: <http://www.w3.org/2002/07/owl#> .
+@prefix rdfs: <http://www.w3.org/2000/01/rdf-schema#> .
+
+ex:ancestor a owl:TransitiveProperty .
+ex:parent rdfs:subPropertyOf ex:ancestor ;
+    owl:inverseOf ex:child .
+ex:uncle owl:propertyChainAxiom ( ex:parent ex:brother ) .
+ex:Man rdfs:subClassOf ex:Person .
+ex:brother rdfs:range ex:Man .
+ex:a ex:parent ex:b .
+ex:b ex:parent ex:c ;
+    ex:brother ex:d .
+ex:c owl:sameAs ex:e .
+"#,
+            ),
+            GraphFormat::Turtle,
+            &GraphName::DefaultGraph,
+            None,
+        )?;
+
+        let ex = |name: &str| NamedNode::new(format!("http://example.com/{}", name));
+        let inferred = |s: &str, p: &str, o: &str| -> Result<bool, Box<dyn Error>> {
+            Ok(store.contains(QuadRef::new(
+                &ex(s)?,
+                &ex(p)?,
+                &ex(o)?,
+                oxigraph::store::INFERRED_GRAPH,
+            ))?)
+        };
+        assert!(inferred("a", "ancestor", "c")?);
+        assert!(inferred("a", "ancestor", "e")?);
+        assert!(inferred("c", "child", "b")?);
+        assert!(inferred("a", "uncle", "d")?);
+        assert!(store.contains(QuadRef::new(
+            &ex("d")?,
+            rdf::TYPE,
+            &ex("Person")?,
+            oxigraph::store::INFERRED_GRAPH
+        ))?);
+        assert!(
+            !inferred("a", "parent", "b")?,
+            "asserted triples are not duplicated"
+        );
+
+        // Removal with SPARQL UPDATE
+        store.update(
+            "DELETE DATA { <http://example.com/b> <http://example.com/parent> <http://example.com/c> }",
+        )?;
+        assert!(!inferred("a", "ancestor", "c")?);
+        assert!(!inferred("c", "child", "b")?);
+        assert!(inferred("a", "ancestor", "b")?);
+        assert!(inferred("a", "uncle", "d")?);
+
+        // A triple derivable in two ways is kept
+        store.insert(QuadRef::new(
+            &ex("a")?,
+            &ex("brother")?,
+            &ex("f")?,
+            GraphNameRef::DefaultGraph,
+        ))?;
+        store.insert(QuadRef::new(
+            &ex("f")?,
+            rdf::TYPE,
+            &ex("Man")?,
+            GraphNameRef::DefaultGraph,
+        ))?;
+        store.transaction(|transaction| {
+            transaction.remove(QuadRef::new(
+                &ex("a").unwrap(),
+                &ex("brother").unwrap(),
+                &ex("f").unwrap(),
+                GraphNameRef::DefaultGraph,
+            ))
+        })?;
+        assert!(store.contains(QuadRef::new(
+            &ex("f")?,
+            rdf::TYPE,
+            &ex("Person")?,
+            oxigraph::store::INFERRED_GRAPH
+        ))?);
+
+        // Asserting an inferred triple moves it out of the inferred graph
+        store.transaction(|transaction| {
+            transaction.insert(QuadRef::new(
+                &ex("a").unwrap(),
+                &ex("ancestor").unwrap(),
+                &ex("b").unwrap(),
+                GraphNameRef::DefaultGraph,
+            ))
+        })?;
+        assert!(!inferred("a", "ancestor", "b")?);
+    }
+
+    // The materialization is kept when the store is reopened
+    {
+        let store = RocksDbStore::open(&repo_path)?;
+        store.remove(QuadRef::new(
+            NamedNodeRef::new("http://example.com/a")?,
+            NamedNodeRef::new("http://example.com/parent")?,
+            NamedNodeRef::new("http://example.com/b")?,
+            GraphNameRef::DefaultGraph,
+        ))?;
+        assert!(!store.contains(QuadRef::new(
+            NamedNodeRef::new("http://example.com/b")?,
+            NamedNodeRef::new("http://example.com/child")?,
+            NamedNodeRef::new("http://example.com/a")?,
+            oxigraph::store::INFERRED_GRAPH
+        ))?);
+
+        // Clearing the default graph clears the inferred graph
+        store.clear_graph(GraphNameRef::DefaultGraph)?;
+        assert!(store.is_empty());
+    }
+    remove_dir_all(&repo_path)?;
+    Ok(())
+}
+
 fn reset_dir(dir: &str) -> io::Result<()> {
     assert!(Command::new("git")
         .args(&["clean", "-fX", dir])
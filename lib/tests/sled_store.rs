@@ -1,11 +1,17 @@
-use oxigraph::io::{DatasetFormat, GraphFormat};
+use oxigraph::io::{DatasetFormat, GraphFormat, GraphParser, GraphSerializer, SyntaxError};
 use oxigraph::model::vocab::{rdf, xsd};
 use oxigraph::model::*;
+use oxigraph::sparql::{QueryOptions, QueryResults};
 use oxigraph::store::sled::SledConflictableTransactionError;
-use oxigraph::SledStore;
+use oxigraph::store::StoreOptions;
+use oxigraph::{MemoryStore, SledStore};
+use std::convert::Infallible;
+use std::error::Error;
+use std::fs::{create_dir_all, remove_dir_all, write};
 use std::io;
 use std::io::Cursor;
 use std::process::Command;
+use std::sync::{Arc, Mutex};
 
 const DATA: &str = r#"
 @prefix schema: <http://schema.org/> .
@@ -85,6 +91,121 @@ fn test_load_graph() -> io::Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_load_graph_with_prefixes() -> Result<(), Box<dyn Error>> {
+    let store = SledStore::new()?;
+    let prefixes = store.load_graph_with_prefixes(
+        Cursor::new(
+            "@base <http://example.com/> .\n@prefix ex: <ns#> .\n@prefix : <http://example.org/> .\n<s> ex:p :o .",
+        ),
+        GraphFormat::Turtle,
+        None,
+        None,
+    )?;
+    assert_eq!(prefixes.base_iri(), Some("http://example.com/"));
+    assert_eq!(prefixes.get("ex"), Some("http://example.com/ns#"));
+    assert_eq!(prefixes.get(""), Some("http://example.org/"));
+    assert_eq!(prefixes.len(), 2);
+    assert_eq!(
+        prefixes.to_string(),
+        "BASE <http://example.com/>\nPREFIX ex: <http://example.com/ns#>\nPREFIX : <http://example.org/>\n"
+    );
+
+    // The prefixes can be used as a SPARQL prologue
+    if let QueryResults::Boolean(result) =
+        store.query(format!("{}ASK {{ <s> ex:p :o }}", prefixes).as_str())?
+    {
+        assert!(result);
+    } else {
+        return Err("ASK query expected".into());
+    }
+
+    let mut buffer = Vec::new();
+    store.dump_graph(
+        &mut buffer,
+        GraphSerializer::from_format(GraphFormat::Turtle).with_prefix_map(&prefixes),
+        None,
+    )?;
+    let output = String::from_utf8(buffer)?;
+    assert!(output.contains("ex:p :o"), "{}", output);
+    Ok(())
+}
+
+#[test]
+fn test_load_graph_lenient() -> io::Result<()> {
+    let file = "<http://example.com/s> <http://example.com/p> <http://example.com/o1> .\n\
+        <http://example.com/s> <http://example.com/p> <http://example.com/o2 .\n\
+        <http://example.com/s> <http://example.com/p> <http://example.com/o3> .\n";
+    let store = SledStore::new()?;
+
+    let error = store
+        .load_graph(Cursor::new(file), GraphFormat::NTriples, None, None)
+        .unwrap_err();
+    let error = error
+        .get_ref()
+        .and_then(|e| e.downcast_ref::<SyntaxError>())
+        .unwrap();
+    assert_eq!(error.position().line, 2);
+    assert_eq!(error.position().column, 70);
+    assert_eq!(store.len(), 1);
+
+    let errors = Arc::new(Mutex::new(Vec::new()));
+    let handler_errors = errors.clone();
+    store.load_graph(
+        Cursor::new(file),
+        GraphParser::from_format(GraphFormat::NTriples)
+            .lenient(move |e| handler_errors.lock().unwrap().push(e.position().line)),
+        None,
+        None,
+    )?;
+    assert_eq!(store.len(), 2);
+    assert_eq!(*errors.lock().unwrap(), vec![2]);
+    Ok(())
+}
+
+#[test]
+fn test_load_from_path() -> io::Result<()> {
+    let dir = std::env::temp_dir().join("oxigraph_load_from_path_test");
+    if dir.exists() {
+        remove_dir_all(&dir)?;
+    }
+    create_dir_all(&dir)?;
+    let turtle = dir.join("data.ttl");
+    write(&turtle, DATA)?;
+    let unnamed = dir.join("data");
+    write(
+        &unnamed,
+        "<http://example.com/s> <http://example.com/p> <http://example.com/o> <http://example.com/g> .\n",
+    )?;
+    let empty = dir.join("empty");
+    write(&empty, "")?;
+
+    let store = SledStore::new()?;
+    store.load_from_path(&turtle, None)?;
+    assert!(!store.contains_named_graph(NamedNodeRef::new_unchecked("http://example.com/g"))?);
+    store.load_from_path(&unnamed, None)?;
+    assert!(store.contains_named_graph(NamedNodeRef::new_unchecked("http://example.com/g"))?);
+    assert_eq!(
+        store.load_from_path(&empty, None).unwrap_err().kind(),
+        io::ErrorKind::InvalidInput
+    );
+
+    let expected = MemoryStore::new();
+    expected.load_graph(
+        Cursor::new(DATA),
+        GraphFormat::Turtle,
+        &GraphName::DefaultGraph,
+        None,
+    )?;
+    assert_eq!(
+        store
+            .quads_for_pattern(None, None, None, Some(GraphNameRef::DefaultGraph))
+            .count(),
+        expected.len()
+    );
+    remove_dir_all(&dir)
+}
+
 #[test]
 fn test_load_dataset() -> io::Result<()> {
     let store = SledStore::new()?;
@@ -153,6 +274,119 @@ fn test_transaction_load_dataset() -> io::Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_range_index() -> Result<(), Box<dyn Error>> {
+    let store = SledStore::new_with_options(StoreOptions::default().with_range_index())?;
+    store.load_dataset(
+        Cursor::new(
+            r#"
+@prefix ex: <http://example.com/> .
+@prefix xsd: <http://www.w3.org/2001/XMLSchema#> .
+
+ex:a ex:value 1 ; ex:date "2020-01-01"^^xsd:date .
+ex:b ex:value 5.5 ; ex:date "2020-06-01Z"^^xsd:date .
+ex:c ex:value 1e1 ; ex:date "2021-01-01+02:00"^^xsd:date .
+ex:d ex:value "10"^^xsd:float ; ex:date "foo" .
+ex:g { ex:e ex:value -3 ; ex:date "2019-01-01"^^xsd:date . }
+"#,
+        ),
+        DatasetFormat::TriG,
+        None,
+    )?;
+    store.remove(QuadRef::new(
+        NamedNodeRef::new("http://example.com/a")?,
+        NamedNodeRef::new("http://example.com/value")?,
+        &Literal::from(1),
+        GraphNameRef::DefaultGraph,
+    ))?;
+
+    for (query, expected) in &[
+        (
+            "SELECT ?s WHERE { ?s <http://example.com/value> ?o FILTER(?o > 0 && ?o <= 10) }",
+            vec!["b", "c", "d"],
+        ),
+        (
+            "SELECT ?s WHERE { ?s <http://example.com/value> ?o FILTER(10 <= ?o) }",
+            vec!["c", "d"],
+        ),
+        (
+            "SELECT ?s WHERE { ?s <http://example.com/value> ?o FILTER(?o = 10) }",
+            vec!["c", "d"],
+        ),
+        (
+            "SELECT ?s WHERE { GRAPH ?g { ?s <http://example.com/value> ?o } FILTER(?o < 0) }",
+            vec!["e"],
+        ),
+        (
+            "SELECT ?s WHERE { ?s <http://example.com/date> ?o FILTER(?o >= \"2020-06-01Z\"^^<http://www.w3.org/2001/XMLSchema#date>) }",
+            vec!["b", "c"],
+        ),
+    ] {
+        assert_subjects(&store, query, QueryOptions::default(), expected)?;
+    }
+    Ok(())
+}
+
+#[test]
+fn test_text_index() -> Result<(), Box<dyn Error>> {
+    let store = SledStore::new_with_options(StoreOptions::default().with_text_index())?;
+    store.load_dataset(
+        Cursor::new(
+            r#"
+@prefix ex: <http://example.com/> .
+
+ex:a ex:label "The Eiffel Tower"@en , "La tour Eiffel"@fr .
+ex:b ex:label "The Tower of London, a historic castle on the north bank of the River Thames"@en .
+ex:c ex:label "Eiffel" ; ex:comment "Tower" .
+ex:g { ex:d ex:label "Tokyo Tower" . }
+"#,
+        ),
+        DatasetFormat::TriG,
+        None,
+    )?;
+    store.remove(QuadRef::new(
+        NamedNodeRef::new("http://example.com/c")?,
+        NamedNodeRef::new("http://example.com/label")?,
+        LiteralRef::new_simple_literal("Eiffel"),
+        GraphNameRef::DefaultGraph,
+    ))?;
+
+    for (query, expected) in &[
+        (
+            "SELECT ?s WHERE { ?s <http://example.com/label> ?o FILTER(<http://oxigraph.org/fn/textMatch>(?o, \"TOWER\")) }",
+            vec!["a", "b"],
+        ),
+        (
+            "SELECT ?s WHERE { ?s <http://example.com/label> ?o FILTER(<http://oxigraph.org/fn/textMatch>(?o, \"eiffel tower\")) }",
+            vec!["a"],
+        ),
+        (
+            "SELECT ?s WHERE { ?s <http://example.com/label> ?o FILTER(<http://oxigraph.org/fn/textMatch>(?o, \"eiffel\", \"fr\")) }",
+            vec!["a"],
+        ),
+        (
+            "SELECT ?s WHERE { ?s <http://example.com/label> ?o FILTER(<http://oxigraph.org/fn/textMatch>(?o, \"thames\")) }",
+            vec!["b"],
+        ),
+        (
+            "SELECT ?s WHERE { GRAPH ?g { ?s <http://example.com/label> ?o } FILTER(<http://oxigraph.org/fn/textMatch>(?o, \"tower\")) }",
+            vec!["d"],
+        ),
+    ] {
+        assert_subjects(&store, query, QueryOptions::default(), expected)?;
+    }
+
+    if let QueryResults::Solutions(solutions) = store.query("SELECT ?o WHERE { ?s <http://example.com/label> ?o FILTER(<http://oxigraph.org/fn/textMatch>(?o, \"tower\")) } ORDER BY DESC(<http://oxigraph.org/fn/textScore>(?o, \"the tower\")) LIMIT 1")? {
+        let solutions = solutions.collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(solutions.len(), 1);
+        assert_eq!(
+            solutions[0].get("o"),
+            Some(&LiteralRef::new_language_tagged_literal_unchecked("The Eiffel Tower", "en").into_owned().into())
+        );
+    }
+    Ok(())
+}
+
 #[test]
 fn test_backward_compatibility() -> io::Result<()> {
     {
@@ -175,6 +409,654 @@ fn test_backward_compatibility() -> io::Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_quoted_triples() -> Result<(), Box<dyn Error>> {
+    let store = SledStore::new()?;
+    store.load_graph(
+        Cursor::new(
+            "@prefix ex: <http://example.com/> .\n<< ex:s ex:p \"o\" >> ex:certainty 0.9 .\n",
+        ),
+        GraphFormat::Turtle,
+        GraphNameRef::DefaultGraph,
+        None,
+    )?;
+    let quoted = Triple::new(
+        NamedNode::new("http://example.com/s")?,
+        NamedNode::new("http://example.com/p")?,
+        Literal::new_simple_literal("o"),
+    );
+    let certainty = NamedNode::new("http://example.com/certainty")?;
+    assert!(store.contains(&Quad::new(
+        quoted.clone(),
+        certainty.clone(),
+        Literal::new_typed_literal("0.9", xsd::DECIMAL),
+        GraphName::DefaultGraph,
+    ))?);
+
+    store.update(
+        "PREFIX ex: <http://example.com/> INSERT { << ?s ?p ?o >> ex:checked true } WHERE { << ?s ?p ?o >> ex:certainty ?c }",
+    )?;
+    assert!(store.contains(&Quad::new(
+        quoted.clone(),
+        NamedNode::new("http://example.com/checked")?,
+        Literal::from(true),
+        GraphName::DefaultGraph,
+    ))?);
+
+    if let QueryResults::Solutions(solutions) =
+        store.query("SELECT ?t WHERE { ?t <http://example.com/checked> true }")?
+    {
+        let solutions = solutions.collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(solutions.len(), 1);
+        assert_eq!(solutions[0].get("t"), Some(&Term::from(quoted.clone())));
+    }
+    if let QueryResults::Solutions(solutions) = store.query(
+        "SELECT ?o WHERE { << <http://example.com/s> ?p ?o >> <http://example.com/certainty>+ ?c }",
+    )? {
+        let solutions = solutions.collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(solutions.len(), 1);
+        assert_eq!(
+            solutions[0].get("o"),
+            Some(&Literal::new_simple_literal("o").into())
+        );
+    }
+
+    let mut buffer = Vec::new();
+    store.dump_graph(
+        &mut buffer,
+        GraphFormat::NTriples,
+        GraphNameRef::DefaultGraph,
+    )?;
+    let other = SledStore::new()?;
+    other.load_graph(
+        Cursor::new(buffer),
+        GraphFormat::NTriples,
+        GraphNameRef::DefaultGraph,
+        None,
+    )?;
+    assert_eq!(other.len(), 2);
+    assert!(other.contains(&Quad::new(
+        quoted,
+        certainty,
+        Literal::new_typed_literal("0.9", xsd::DECIMAL),
+        GraphName::DefaultGraph,
+    ))?);
+    Ok(())
+}
+
+#[test]
+fn test_rdfs_entailment() -> Result<(), Box<dyn Error>> {
+    let store = SledStore::new()?;
+    store.load_dataset(
+        Cursor::new(
+            r#"
+@prefix ex: <http://example.com/> .
+@prefix rdfs: <http://www.w3.org/2000/01/rdf-schema#> .
+
+ex:Cat rdfs:subClassOf ex:Mammal .
+ex:Mammal rdfs:subClassOf ex:Animal .
+ex:hasPet rdfs:subPropertyOf ex:knows ;
+    rdfs:domain ex:Person ;
+    rdfs:range ex:Animal .
+ex:tom a ex:Cat .
+ex:alice ex:hasPet ex:felix .
+ex:g { ex:bob ex:hasPet ex:rex . }
+"#,
+        ),
+        DatasetFormat::TriG,
+        None,
+    )?;
+
+    for (query, expected) in &[
+        (
+            "SELECT ?s WHERE { ?s a <http://example.com/Animal> }",
+            vec!["felix", "tom"],
+        ),
+        (
+            "SELECT ?s WHERE { ?s a <http://example.com/Person> }",
+            vec!["alice"],
+        ),
+        (
+            "SELECT ?s WHERE { ?s <http://example.com/knows> ?o }",
+            vec!["alice"],
+        ),
+        (
+            "SELECT ?s WHERE { ?s <http://www.w3.org/2000/01/rdf-schema#subClassOf> <http://example.com/Animal> }",
+            vec!["Cat", "Mammal"],
+        ),
+        (
+            "SELECT ?s WHERE { GRAPH <http://example.com/g> { ?s a <http://example.com/Person> } }",
+            vec!["bob"],
+        ),
+        (
+            "SELECT DISTINCT ?s WHERE { ?s ?p <http://example.com/Mammal> }",
+            vec!["Cat", "tom"],
+        ),
+    ] {
+        assert_subjects(
+            &store,
+            query,
+            QueryOptions::default().with_rdfs_entailment(),
+            expected,
+        )?;
+    }
+
+    if let QueryResults::Boolean(result) =
+        store.query("ASK { <http://example.com/tom> a <http://example.com/Animal> }")?
+    {
+        assert!(!result, "entailment should be opt-in");
+    }
+    Ok(())
+}
+
+#[test]
+fn test_rdfs_entailment_large_scan() -> Result<(), Box<dyn Error>> {
+    let mut data = String::from(
+        "@prefix ex: <http://example.com/> .\n@prefix rdfs: <http://www.w3.org/2000/01/rdf-schema#> .\nex:Cat rdfs:subClassOf ex:Mammal .\nex:Mammal rdfs:subClassOf ex:Animal .\n",
+    );
+    for i in 0..10_000 {
+        data.push_str(&format!("ex:cat{} a ex:Cat .\n", i));
+    }
+    let store = SledStore::new()?;
+    store.load_graph(
+        Cursor::new(data),
+        GraphFormat::Turtle,
+        GraphNameRef::DefaultGraph,
+        None,
+    )?;
+
+    for (query, expected) in &[
+        ("SELECT (COUNT(*) AS ?c) WHERE { ?s ?p ?o }", 30_003),
+        ("SELECT (COUNT(*) AS ?c) WHERE { ?s a ?o }", 30_000),
+        (
+            "SELECT (COUNT(*) AS ?c) WHERE { ?s a <http://example.com/Animal> }",
+            10_000,
+        ),
+    ] {
+        if let QueryResults::Solutions(mut solutions) =
+            store.query_opt(*query, QueryOptions::default().with_rdfs_entailment())?
+        {
+            assert_eq!(
+                solutions.next().unwrap()?.get("c"),
+                Some(&Literal::from(*expected).into()),
+                "for query {}",
+                query
+            );
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_owl2rl_reasoning() -> Result<(), Box<dyn Error>> {
+    let store = SledStore::new_with_options(StoreOptions::default().with_owl2rl_reasoning())?;
+    store.load_graph(
+        Cursor::new(
+            r#"
+@prefix ex: <http://example.com/> .
+@prefix owl: <http://www.w3.org/2002/07/owl#> .
+@prefix rdfs: <http://www.w3.org/2000/01/rdf-schema#> .
+
+ex:ancestor a owl:TransitiveProperty .
+ex:parent rdfs:subPropertyOf ex:ancestor ;
+    owl:inverseOf ex:child .
+ex:uncle owl:propertyChainAxiom ( ex:parent ex:brother ) .
+ex:Man rdfs:subClassOf ex:Person .
+ex:brother rdfs:range ex:Man .
+ex:a ex:parent ex:b .
+ex:b ex:parent ex:c ;
+    ex:brother ex:d .
+ex:c owl:sameAs ex:e .
+"#,
+        ),
+        GraphFormat::Turtle,
+        &GraphName::DefaultGraph,
+        None,
+    )?;
+
+    let ex = |name: &str| NamedNode::new(format!("http://example.com/{}", name));
+    let inferred = |s: &str, p: &str, o: &str| -> Result<bool, Box<dyn Error>> {
+        Ok(store.contains(QuadRef::new(
+            &ex(s)?,
+            &ex(p)?,
+            &ex(o)?,
+            oxigraph::store::INFERRED_GRAPH,
+        ))?)
+    };
+    assert!(inferred("a", "ancestor", "c")?);
+    assert!(inferred("a", "ancestor", "e")?);
+    assert!(inferred("c", "child", "b")?);
+    assert!(inferred("a", "uncle", "d")?);
+    assert!(store.contains(QuadRef::new(
+        &ex("d")?,
+        rdf::TYPE,
+        &ex("Person")?,
+        oxigraph::store::INFERRED_GRAPH
+    ))?);
+    assert!(
+        !inferred("a", "parent", "b")?,
+        "asserted triples are not duplicated"
+    );
+
+    // Removal with SPARQL UPDATE
+    store.update(
+        "DELETE DATA { <http://example.com/b> <http://example.com/parent> <http://example.com/c> }",
+    )?;
+    assert!(!inferred("a", "ancestor", "c")?);
+    assert!(!inferred("c", "child", "b")?);
+    assert!(inferred("a", "ancestor", "b")?);
+    assert!(inferred("a", "uncle", "d")?);
+
+    // A triple derivable in two ways is kept
+    store.insert(QuadRef::new(
+        &ex("a")?,
+        &ex("brother")?,
+        &ex("f")?,
+        GraphNameRef::DefaultGraph,
+    ))?;
+    store.insert(QuadRef::new(
+        &ex("f")?,
+        rdf::TYPE,
+        &ex("Man")?,
+        GraphNameRef::DefaultGraph,
+    ))?;
+    store.transaction(|transaction| {
+        transaction.remove(QuadRef::new(
+            &ex("a").unwrap(),
+            &ex("brother").unwrap(),
+            &ex("f").unwrap(),
+            GraphNameRef::DefaultGraph,
+        ))?;
+        Ok(()) as Result<(), SledConflictableTransactionError<Infallible>>
+    })?;
+    assert!(store.contains(QuadRef::new(
+        &ex("f")?,
+        rdf::TYPE,
+        &ex("Person")?,
+        oxigraph::store::INFERRED_GRAPH
+    ))?);
+
+    // Asserting an inferred triple moves it out of the inferred graph
+    store.transaction(|transaction| {
+        transaction.insert(QuadRef::new(
+            &ex("a").unwrap(),
+            &ex("ancestor").unwrap(),
+            &ex("b").unwrap(),
+            GraphNameRef::DefaultGraph,
+        ))?;
+        Ok(()) as Result<(), SledConflictableTransactionError<Infallible>>
+    })?;
+    assert!(!inferred("a", "ancestor", "b")?);
+
+    // Clearing the default graph clears the inferred graph
+    store.clear_graph(GraphNameRef::DefaultGraph)?;
+    assert!(store.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_rules() -> Result<(), Box<dyn Error>> {
+    let path = std::env::temp_dir().join("oxigraph_sled_rules_test");
+    if path.exists() {
+        remove_dir_all(&path)?;
+    }
+    let ex = |name: &str| NamedNode::new(format!("http://example.com/{}", name));
+    let ancestors = ex("ancestors")?;
+    let grandparents = ex("grandparents")?;
+    {
+        let store = SledStore::open(&path)?;
+        store.load_graph(
+            Cursor::new(
+                r#"
+@prefix ex: <http://example.com/> .
+ex:a ex:parent ex:b .
+ex:b ex:parent ex:c .
+"#,
+            ),
+            GraphFormat::Turtle,
+            GraphNameRef::DefaultGraph,
+            None,
+        )?;
+        store.add_rule(
+            "ancestors",
+            "PREFIX ex: <http://example.com/> CONSTRUCT { ?a ex:ancestor ?b } WHERE { ?a ex:parent+ ?b }",
+            ancestors.clone(),
+        )?;
+        // A rule reading the view of another rule
+        store.add_rule(
+            "grandparents",
+            "PREFIX ex: <http://example.com/> CONSTRUCT { ?a ex:grandparent ?c } WHERE { ?a ex:parent ?b . GRAPH ex:ancestors { ?b ex:ancestor ?c } }",
+            grandparents.clone(),
+        )?;
+        assert!(store.contains(&Quad::new(
+            ex("a")?,
+            ex("ancestor")?,
+            ex("c")?,
+            ancestors.clone()
+        ))?);
+        assert!(store.contains(&Quad::new(
+            ex("a")?,
+            ex("grandparent")?,
+            ex("c")?,
+            grandparents.clone()
+        ))?);
+
+        // Rules should be CONSTRUCT queries without blank nodes in their template
+        assert!(store
+            .add_rule("bad", "SELECT * WHERE { ?s ?p ?o }", ex("bad")?)
+            .is_err());
+        assert!(store
+            .add_rule(
+                "bad",
+                "CONSTRUCT { [] ?p ?o } WHERE { ?s ?p ?o }",
+                ex("bad")?
+            )
+            .is_err());
+        assert_eq!(store.rules().len(), 2);
+
+        // Transactions and updates
+        let quad = Quad::new(ex("c")?, ex("parent")?, ex("d")?, None);
+        store.transaction(|transaction| {
+            transaction.insert(&quad)?;
+            Ok(()) as Result<(), SledConflictableTransactionError<Infallible>>
+        })?;
+        assert!(store.contains(&Quad::new(
+            ex("a")?,
+            ex("ancestor")?,
+            ex("d")?,
+            ancestors.clone()
+        ))?);
+        assert!(store.contains(&Quad::new(
+            ex("b")?,
+            ex("grandparent")?,
+            ex("d")?,
+            grandparents.clone()
+        ))?);
+        store.update("DELETE DATA { <http://example.com/b> <http://example.com/parent> <http://example.com/c> }")?;
+        assert!(!store.contains(&Quad::new(
+            ex("a")?,
+            ex("ancestor")?,
+            ex("d")?,
+            ancestors.clone()
+        ))?);
+        assert!(!store.contains(&Quad::new(
+            ex("a")?,
+            ex("grandparent")?,
+            ex("c")?,
+            grandparents.clone()
+        ))?);
+        assert!(store.contains(&Quad::new(
+            ex("a")?,
+            ex("ancestor")?,
+            ex("b")?,
+            ancestors.clone()
+        ))?);
+    }
+    {
+        // The rules are persisted
+        let store = SledStore::open(&path)?;
+        assert_eq!(store.rules().len(), 2);
+        store.insert(&Quad::new(ex("b")?, ex("parent")?, ex("c")?, None))?;
+        assert!(store.contains(&Quad::new(
+            ex("a")?,
+            ex("ancestor")?,
+            ex("d")?,
+            ancestors.clone()
+        ))?);
+
+        assert!(store.remove_rule("ancestors")?);
+        assert!(!store.remove_rule("ancestors")?);
+        assert!(store
+            .quads_for_pattern(None, None, None, Some(ancestors.as_ref().into()))
+            .next()
+            .is_none());
+        assert!(store
+            .quads_for_pattern(None, None, None, Some(grandparents.as_ref().into()))
+            .next()
+            .is_none());
+    }
+    remove_dir_all(&path)?;
+    Ok(())
+}
+
+#[test]
+fn test_prepared_query() -> Result<(), Box<dyn Error>> {
+    let ex = |name: &str| NamedNode::new(format!("http://example.com/{}", name));
+    let store = SledStore::new()?;
+    let mut query = store.prepare_query(
+        "SELECT ?o (COUNT(*) AS ?c) WHERE { ?s ?p ?o } GROUP BY ?o ORDER BY ?o LIMIT 2",
+    )?;
+    assert!(query.bind("c", ex("a")?).is_err());
+    assert!(query.bind("unknown", ex("a")?).is_err());
+
+    // The query is executed against the current state of the store
+    store.insert(&Quad::new(ex("a")?, ex("p")?, Literal::from(1), None))?;
+    store.insert(&Quad::new(ex("a")?, ex("q")?, Literal::from(1), None))?;
+    store.insert(&Quad::new(ex("a")?, ex("p")?, Literal::from(2), None))?;
+    store.insert(&Quad::new(ex("a")?, ex("p")?, Literal::from(3), None))?;
+    store.insert(&Quad::new(ex("b")?, ex("p")?, Literal::from(4), None))?;
+    let solutions =
+        |query: &oxigraph::sparql::PreparedQuery| -> Result<Vec<(Term, Term)>, Box<dyn Error>> {
+            if let QueryResults::Solutions(solutions) = query.exec()? {
+                Ok(solutions
+                    .map(|s| {
+                        let s = s?;
+                        Ok((s.get("o").unwrap().clone(), s.get("c").unwrap().clone()))
+                    })
+                    .collect::<Result<_, Box<dyn Error>>>()?)
+            } else {
+                Err("SELECT query expected".into())
+            }
+        };
+    assert_eq!(
+        solutions(&query)?,
+        vec![
+            (Literal::from(1).into(), Literal::from(2).into()),
+            (Literal::from(2).into(), Literal::from(1).into())
+        ]
+    );
+
+    // The bindings are applied before the solution modifiers
+    query.bind("?p", ex("p")?)?;
+    assert_eq!(
+        solutions(&query)?,
+        vec![
+            (Literal::from(1).into(), Literal::from(1).into()),
+            (Literal::from(2).into(), Literal::from(1).into())
+        ]
+    );
+    query.bind("s", ex("b")?)?;
+    assert_eq!(
+        solutions(&query)?,
+        vec![(Literal::from(4).into(), Literal::from(1).into())]
+    );
+
+    query.clear_bindings();
+    assert_eq!(solutions(&query)?.len(), 2);
+
+    // The bindings are not interpreted as SPARQL
+    let mut query = store.prepare_query("ASK { ?s ?p ?o }")?;
+    query.bind("o", Literal::new_simple_literal("1 } UNION { ?s ?p ?o"))?;
+    assert!(matches!(query.exec()?, QueryResults::Boolean(false)));
+    query.bind("o", Literal::from(4))?;
+    assert!(matches!(query.exec()?, QueryResults::Boolean(true)));
+
+    // Terms that are not in the store are bound as usual
+    query.bind("s", ex("c")?)?;
+    assert!(matches!(query.exec()?, QueryResults::Boolean(false)));
+
+    let mut query =
+        store.prepare_query("CONSTRUCT { ?s <http://example.com/r> ?o } WHERE { ?s ?p ?o }")?;
+    query.bind("o", Literal::from(4))?;
+    if let QueryResults::Graph(triples) = query.exec()? {
+        assert_eq!(
+            triples.collect::<Result<Vec<_>, _>>()?,
+            vec![Triple::new(ex("b")?, ex("r")?, Literal::from(4))]
+        );
+    } else {
+        panic!("CONSTRUCT query expected")
+    }
+    Ok(())
+}
+
+#[test]
+fn test_query_cache_invalidation() -> Result<(), Box<dyn Error>> {
+    let ex = |name: &str| NamedNode::new(format!("http://example.com/{}", name));
+    let store = SledStore::new()?;
+    store.set_query_cache_capacity(2);
+    let count = |query: &str| -> Result<usize, Box<dyn Error>> {
+        if let QueryResults::Solutions(solutions) = store.query(query)? {
+            Ok(solutions.collect::<Result<Vec<_>, _>>()?.len())
+        } else {
+            Err("SELECT query expected".into())
+        }
+    };
+    let all = "SELECT * WHERE { ?s ?p ?o }";
+    let named = "SELECT * WHERE { GRAPH ?g { ?s ?p ?o } }";
+    assert_eq!(count(all)?, 0);
+
+    // Each write path drops the cached results
+    store.insert(&Quad::new(ex("a")?, ex("p")?, ex("b")?, None))?;
+    assert_eq!(count(all)?, 1);
+    store.update(
+        "INSERT DATA { <http://example.com/b> <http://example.com/p> <http://example.com/c> }",
+    )?;
+    assert_eq!(count(all)?, 2);
+    assert_eq!(count(named)?, 0);
+    let quad = Quad::new(ex("c")?, ex("p")?, ex("d")?, ex("g")?);
+    store.transaction(|transaction| {
+        transaction.insert(&quad)?;
+        Ok(()) as Result<_, SledConflictableTransactionError<Infallible>>
+    })?;
+    assert_eq!(count(named)?, 1);
+    store.remove_named_graph(&ex("g")?)?;
+    assert_eq!(count(named)?, 0);
+    store.clear()?;
+    assert_eq!(count(all)?, 0);
+
+    // The cache is shared by the clones of the store
+    let clone = store.clone();
+    clone.insert(&Quad::new(ex("a")?, ex("p")?, ex("b")?, None))?;
+    assert_eq!(count(all)?, 1);
+    Ok(())
+}
+
+#[test]
+fn test_parallel_evaluation() -> Result<(), Box<dyn Error>> {
+    let ex = |name: String| NamedNode::new(format!("http://example.com/{}", name));
+    let store = SledStore::new()?;
+    for i in 0..2000 {
+        store.insert(&Quad::new(
+            ex(format!("s{}", i))?,
+            ex("p".to_owned())?,
+            ex(format!("o{}", i % 10))?,
+            None,
+        ))?;
+    }
+    for i in 0..10 {
+        store.insert(&Quad::new(
+            ex(format!("o{}", i))?,
+            ex("label".to_owned())?,
+            Literal::from(i),
+            None,
+        ))?;
+    }
+    let solutions = |query: &str, options: QueryOptions| -> Result<Vec<String>, Box<dyn Error>> {
+        if let QueryResults::Solutions(solutions) = store.query_opt(query, options)? {
+            let mut solutions = solutions
+                .map(|solution| {
+                    Ok(solution?
+                        .iter()
+                        .map(|(v, t)| format!("{}={}", v, t))
+                        .collect::<Vec<_>>()
+                        .join(" "))
+                })
+                .collect::<Result<Vec<_>, Box<dyn Error>>>()?;
+            solutions.sort();
+            Ok(solutions)
+        } else {
+            Err("SELECT query expected".into())
+        }
+    };
+    for (query, count) in &[
+        ("SELECT ?s WHERE { { ?s <http://example.com/p> ?o } UNION { ?o <http://example.com/label> ?s } }", 2010),
+        ("SELECT ?s ?l WHERE { ?s <http://example.com/p> ?o . ?o <http://example.com/label> ?l FILTER(?l > 3) BIND(?l + 1 AS ?l2) }", 1200),
+        ("SELECT ?o (COUNT(?s) AS ?c) (SAMPLE(?l) AS ?sl) WHERE { ?s <http://example.com/p> ?o OPTIONAL { ?o <http://example.com/label> ?l } } GROUP BY ?o", 10),
+        ("SELECT (COUNT(*) AS ?c) WHERE { ?s <http://example.com/p> ?o . ?s2 <http://example.com/p> ?o FILTER(?s2 != ?s) }", 1),
+    ] {
+        let sequential = solutions(query, QueryOptions::default().without_query_cache())?;
+        assert_eq!(sequential.len(), *count);
+        assert_eq!(
+            solutions(
+                query,
+                QueryOptions::default()
+                    .without_query_cache()
+                    .with_parallel_evaluation()
+            )?,
+            sequential
+        );
+    }
+    let parallel = QueryOptions::default()
+        .without_query_cache()
+        .with_parallel_evaluation();
+    assert_eq!(
+        solutions(
+            "SELECT ?s WHERE { ?s <http://example.com/p> ?o . ?o <http://example.com/label> ?l } LIMIT 3",
+            parallel.clone()
+        )?
+        .len(),
+        3
+    );
+    assert!(matches!(
+        store.query_opt(
+            "ASK { ?s <http://example.com/p> ?o . ?o <http://example.com/label> 9 }",
+            parallel
+        )?,
+        QueryResults::Boolean(true)
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_service_description() -> Result<(), Box<dyn Error>> {
+    let store = SledStore::new()?;
+    let ex = NamedNode::new("http://example.com")?;
+    store.insert(QuadRef::new(&ex, &ex, &ex, &ex))?;
+    store.insert(QuadRef::new(&ex, &ex, &ex, None))?;
+    let description = store
+        .service_description()?
+        .with_endpoint(NamedNode::new("http://example.com/query")?);
+    // The quads are not counted by default
+    assert!(!description
+        .triples()
+        .iter()
+        .any(|t| t.predicate.as_str() == "http://rdfs.org/ns/void#triples"));
+    let description = description.with_triples_count(store.len());
+
+    let description_store = MemoryStore::new();
+    for triple in description.triples() {
+        description_store.insert(triple.in_graph(GraphName::DefaultGraph));
+    }
+    let query = "PREFIX sd: <http://www.w3.org/ns/sparql-service-description#>
+        PREFIX void: <http://rdfs.org/ns/void#>
+        ASK {
+            ?service a sd:Service ;
+                sd:endpoint <http://example.com/query> ;
+                sd:supportedLanguage sd:SPARQL11Query, sd:SPARQL11Update ;
+                sd:resultFormat <http://www.w3.org/ns/formats/SPARQL_Results_JSON> ;
+                sd:extensionFunction <http://oxigraph.org/fn/textMatch> ;
+                sd:defaultDataset ?dataset .
+            ?dataset void:triples 2 ;
+                sd:namedGraph/sd:name <http://example.com> .
+        }";
+    if let QueryResults::Boolean(result) = description_store.query(query)? {
+        assert!(result);
+    } else {
+        return Err("ASK query expected".into());
+    }
+    Ok(())
+}
+
 fn reset_dir(dir: &str) -> io::Result<()> {
     assert!(Command::new("git")
         .args(&["clean", "-fX", dir])
@@ -186,3 +1068,22 @@ fn reset_dir(dir: &str) -> io::Result<()> {
         .success());
     Ok(())
 }
+
+fn assert_subjects(
+    store: &SledStore,
+    query: &str,
+    options: QueryOptions,
+    expected: &[&str],
+) -> Result<(), Box<dyn Error>> {
+    let mut subjects = Vec::new();
+    if let QueryResults::Solutions(solutions) = store.query_opt(query, options)? {
+        for solution in solutions {
+            if let Some(Term::NamedNode(s)) = solution?.get("s") {
+                subjects.push(s.as_str()["http://example.com/".len()..].to_owned());
+            }
+        }
+    }
+    subjects.sort();
+    assert_eq!(subjects, expected, "for query {}", query);
+    Ok(())
+}
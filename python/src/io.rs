@@ -2,7 +2,8 @@ use crate::model::{PyQuad, PyTriple};
 use crate::store_utils::map_io_err;
 use oxigraph::io::read::{QuadReader, TripleReader};
 use oxigraph::io::{
-    DatasetFormat, DatasetParser, DatasetSerializer, GraphFormat, GraphParser, GraphSerializer,
+    DatasetFormat, DatasetParser, DatasetSerializer, DecompressingReader, GraphFormat, GraphParser,
+    GraphSerializer,
 };
 use pyo3::exceptions::PyValueError;
 use pyo3::prelude::*;
@@ -25,12 +26,15 @@ pub fn add_to_module(module: &PyModule) -> PyResult<()> {
 /// * `N-Quads <https://www.w3.org/TR/n-quads/>`_ (``application/n-quads``)
 /// * `Turtle <https://www.w3.org/TR/turtle/>`_ (``text/turtle``)
 /// * `TriG <https://www.w3.org/TR/trig/>`_ (``application/trig``)
+/// * `JSON-LD 1.1 <https://www.w3.org/TR/json-ld11/>`_ (``application/ld+json``)
 /// * `RDF/XML <https://www.w3.org/TR/rdf-syntax-grammar/>`_ (``application/rdf+xml``)
 ///
 /// It supports also some MIME type aliases.
 /// For example ``application/turtle`` could also be used for `Turtle <https://www.w3.org/TR/turtle/>`_
 /// and ``application/xml`` for `RDF/XML <https://www.w3.org/TR/rdf-syntax-grammar/>`_.
 ///
+/// gzip, bzip2 and Zstandard compressed inputs are detected and decompressed automatically.
+///
 /// :param input: The binary I/O object to read from. For example, it could be a file opened in binary mode with ``open('my_file.ttl', 'rb')``.
 /// :type input: io.RawIOBase or io.BufferedIOBase
 /// :param mime_type: the MIME type of the RDF serialization
@@ -53,7 +57,8 @@ pub fn parse(
     base_iri: Option<&str>,
     py: Python<'_>,
 ) -> PyResult<PyObject> {
-    let input = BufReader::new(PyFileLike::new(input));
+    let input =
+        DecompressingReader::new(BufReader::new(PyFileLike::new(input))).map_err(map_io_err)?;
     if let Some(graph_format) = GraphFormat::from_media_type(mime_type) {
         let mut parser = GraphParser::from_format(graph_format);
         if let Some(base_iri) = base_iri {
@@ -92,12 +97,15 @@ pub fn parse(
 /// * `N-Quads <https://www.w3.org/TR/n-quads/>`_ (``application/n-quads``)
 /// * `Turtle <https://www.w3.org/TR/turtle/>`_ (``text/turtle``)
 /// * `TriG <https://www.w3.org/TR/trig/>`_ (``application/trig``)
+/// * `JSON-LD 1.1 <https://www.w3.org/TR/json-ld11/>`_ (``application/ld+json``)
 /// * `RDF/XML <https://www.w3.org/TR/rdf-syntax-grammar/>`_ (``application/rdf+xml``)
 ///
 /// It supports also some MIME type aliases.
 /// For example ``application/turtle`` could also be used for `Turtle <https://www.w3.org/TR/turtle/>`_
 /// and ``application/xml`` for `RDF/XML <https://www.w3.org/TR/rdf-syntax-grammar/>`_.
 ///
+/// gzip, bzip2 and Zstandard compressed inputs are detected and decompressed automatically.
+///
 /// :param input: the RDF triples and quads to serialize
 /// :type input: iter(Triple) or iter(Quad)
 /// :param output: The binary I/O object to write to. For example, it could be a file opened in binary mode with ``open('my_file.ttl', 'wb')``.
@@ -147,7 +155,7 @@ pub fn serialize(input: &PyAny, output: PyObject, mime_type: &str) -> PyResult<(
 
 #[pyclass(name = "TripleReader", module = "oxigraph")]
 pub struct PyTripleReader {
-    inner: TripleReader<BufReader<PyFileLike>>,
+    inner: TripleReader<DecompressingReader<BufReader<PyFileLike>>>,
 }
 
 #[pyproto]
@@ -166,7 +174,7 @@ impl PyIterProtocol for PyTripleReader {
 
 #[pyclass(name = "QuadReader", module = "oxigraph")]
 pub struct PyQuadReader {
-    inner: QuadReader<BufReader<PyFileLike>>,
+    inner: QuadReader<DecompressingReader<BufReader<PyFileLike>>>,
 }
 
 #[pyproto]
@@ -2,7 +2,7 @@ use crate::io::PyFileLike;
 use crate::model::*;
 use crate::sparql::*;
 use crate::store_utils::*;
-use oxigraph::io::{DatasetFormat, GraphFormat};
+use oxigraph::io::{DatasetFormat, DecompressingReader, GraphFormat};
 use oxigraph::model::GraphNameRef;
 use oxigraph::store::memory::*;
 use pyo3::basic::CompareOp;
@@ -210,12 +210,15 @@ impl PyMemoryStore {
     /// * `N-Quads <https://www.w3.org/TR/n-quads/>`_ (``application/n-quads``)
     /// * `Turtle <https://www.w3.org/TR/turtle/>`_ (``text/turtle``)
     /// * `TriG <https://www.w3.org/TR/trig/>`_ (``application/trig``)
+    /// * `JSON-LD 1.1 <https://www.w3.org/TR/json-ld11/>`_ (``application/ld+json``)
     /// * `RDF/XML <https://www.w3.org/TR/rdf-syntax-grammar/>`_ (``application/rdf+xml``)
     ///
     /// It supports also some MIME type aliases.
     /// For example ``application/turtle`` could also be used for `Turtle <https://www.w3.org/TR/turtle/>`_
     /// and ``application/xml`` for `RDF/XML <https://www.w3.org/TR/rdf-syntax-grammar/>`_.
     ///
+    /// gzip, bzip2 and Zstandard compressed inputs are detected and decompressed automatically.
+    ///
     /// :param input: The binary I/O object to read from. For example, it could be a file opened in binary mode with ``open('my_file.ttl', 'rb')``.
     /// :type input: io.RawIOBase or io.BufferedIOBase
     /// :param mime_type: the MIME type of the RDF serialization
@@ -245,7 +248,8 @@ impl PyMemoryStore {
         } else {
             None
         };
-        let input = BufReader::new(PyFileLike::new(input));
+        let input =
+            DecompressingReader::new(BufReader::new(PyFileLike::new(input))).map_err(map_io_err)?;
         if let Some(graph_format) = GraphFormat::from_media_type(mime_type) {
             self.inner
                 .load_graph(
@@ -280,6 +284,7 @@ impl PyMemoryStore {
     /// * `N-Quads <https://www.w3.org/TR/n-quads/>`_ (``application/n-quads``)
     /// * `Turtle <https://www.w3.org/TR/turtle/>`_ (``text/turtle``)
     /// * `TriG <https://www.w3.org/TR/trig/>`_ (``application/trig``)
+    /// * `JSON-LD 1.1 <https://www.w3.org/TR/json-ld11/>`_ (``application/ld+json``)
     /// * `RDF/XML <https://www.w3.org/TR/rdf-syntax-grammar/>`_ (``application/rdf+xml``)
     ///
     /// It supports also some MIME type aliases.
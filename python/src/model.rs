@@ -45,6 +45,12 @@ impl From<PyNamedNode> for NamedOrBlankNode {
     }
 }
 
+impl From<PyNamedNode> for Subject {
+    fn from(node: PyNamedNode) -> Self {
+        node.inner.into()
+    }
+}
+
 impl From<PyNamedNode> for Term {
     fn from(node: PyNamedNode) -> Self {
         node.inner.into()
@@ -144,6 +150,12 @@ impl From<PyBlankNode> for NamedOrBlankNode {
     }
 }
 
+impl From<PyBlankNode> for Subject {
+    fn from(node: PyBlankNode) -> Self {
+        node.inner.into()
+    }
+}
+
 impl From<PyBlankNode> for Term {
     fn from(node: PyBlankNode) -> Self {
         node.inner.into()
@@ -437,11 +449,49 @@ impl IntoPy<PyObject> for PyNamedOrBlankNode {
     }
 }
 
+#[derive(FromPyObject)]
+pub enum PySubject {
+    NamedNode(PyNamedNode),
+    BlankNode(PyBlankNode),
+    Triple(PyTriple),
+}
+
+impl From<PySubject> for Subject {
+    fn from(node: PySubject) -> Self {
+        match node {
+            PySubject::NamedNode(node) => node.into(),
+            PySubject::BlankNode(node) => node.into(),
+            PySubject::Triple(triple) => Triple::from(triple).into(),
+        }
+    }
+}
+
+impl From<Subject> for PySubject {
+    fn from(node: Subject) -> Self {
+        match node {
+            Subject::NamedNode(node) => PySubject::NamedNode(node.into()),
+            Subject::BlankNode(node) => PySubject::BlankNode(node.into()),
+            Subject::Triple(triple) => PySubject::Triple((*triple).into()),
+        }
+    }
+}
+
+impl IntoPy<PyObject> for PySubject {
+    fn into_py(self, py: Python<'_>) -> PyObject {
+        match self {
+            PySubject::NamedNode(node) => node.into_py(py),
+            PySubject::BlankNode(node) => node.into_py(py),
+            PySubject::Triple(triple) => triple.into_py(py),
+        }
+    }
+}
+
 #[derive(FromPyObject)]
 pub enum PyTerm {
     NamedNode(PyNamedNode),
     BlankNode(PyBlankNode),
     Literal(PyLiteral),
+    Triple(PyTriple),
 }
 
 impl From<PyTerm> for Term {
@@ -450,6 +500,7 @@ impl From<PyTerm> for Term {
             PyTerm::NamedNode(node) => node.into(),
             PyTerm::BlankNode(node) => node.into(),
             PyTerm::Literal(literal) => literal.into(),
+            PyTerm::Triple(triple) => Triple::from(triple).into(),
         }
     }
 }
@@ -460,6 +511,7 @@ impl From<Term> for PyTerm {
             Term::NamedNode(node) => PyTerm::NamedNode(node.into()),
             Term::BlankNode(node) => PyTerm::BlankNode(node.into()),
             Term::Literal(literal) => PyTerm::Literal(literal.into()),
+            Term::Triple(triple) => PyTerm::Triple((*triple).into()),
         }
     }
 }
@@ -470,6 +522,7 @@ impl IntoPy<PyObject> for PyTerm {
             PyTerm::NamedNode(node) => node.into_py(py),
             PyTerm::BlankNode(node) => node.into_py(py),
             PyTerm::Literal(literal) => literal.into_py(py),
+            PyTerm::Triple(triple) => triple.into_py(py),
         }
     }
 }
@@ -477,11 +530,11 @@ impl IntoPy<PyObject> for PyTerm {
 /// An RDF `triple <https://www.w3.org/TR/rdf11-concepts/#dfn-rdf-triple>`_
 ///
 /// :param subject: the triple subject
-/// :type subject: NamedNode or BlankNode
+/// :type subject: NamedNode or BlankNode or Triple
 /// :param predicate: the triple predicate
 /// :type predicate: NamedNode
 /// :param object: the triple object
-/// :type object: NamedNode or BlankNode or Literal
+/// :type object: NamedNode or BlankNode or Literal or Triple
 ///
 /// The :py:func:`str` function provides a serialization compatible with NTriples, Turtle and SPARQL:
 ///
@@ -491,6 +544,11 @@ impl IntoPy<PyObject> for PyTerm {
 /// A triple could also be easily destructed into its components:
 ///
 /// >>> (s, p, o) = Triple(NamedNode('http://example.com'), NamedNode('http://example.com/p'), Literal('1'))
+///
+/// A triple could be used as subject or object of an other triple (`RDF-star <https://w3c.github.io/rdf-star/cg-spec/>`_):
+///
+/// >>> str(Triple(Triple(NamedNode('http://example.com'), NamedNode('http://example.com/p'), Literal('1')), NamedNode('http://example.com/certainty'), Literal('0.9')))
+/// '<< <http://example.com> <http://example.com/p> "1" >> <http://example.com/certainty> "0.9" .'
 #[pyclass(name = "Triple", module = "oxigraph")]
 #[derive(Eq, PartialEq, Debug, Clone, Hash)]
 #[text_signature = "(subject, predicate, object)"]
@@ -519,17 +577,17 @@ impl<'a> From<&'a PyTriple> for TripleRef<'a> {
 #[pymethods]
 impl PyTriple {
     #[new]
-    fn new(subject: PyNamedOrBlankNode, predicate: PyNamedNode, object: PyTerm) -> Self {
+    fn new(subject: PySubject, predicate: PyNamedNode, object: PyTerm) -> Self {
         Triple::new(subject, predicate, object).into()
     }
 
     /// :return: the triple subject
-    /// :rtype: NamedNode or BlankNode
+    /// :rtype: NamedNode or BlankNode or Triple
     ///
     /// >>> Triple(NamedNode('http://example.com'), NamedNode('http://example.com/p'), Literal('1')).subject
     /// <NamedNode value=http://example.com>
     #[getter]
-    fn subject(&self) -> PyNamedOrBlankNode {
+    fn subject(&self) -> PySubject {
         self.inner.subject.clone().into()
     }
 
@@ -544,7 +602,7 @@ impl PyTriple {
     }
 
     /// :return: the triple object
-    /// :rtype: NamedNode or BlankNode or Literal
+    /// :rtype: NamedNode or BlankNode or Literal or Triple
     ///
     /// >>> Triple(NamedNode('http://example.com'), NamedNode('http://example.com/p'), Literal('1')).object
     /// <Literal value=1 datatype=<NamedNode value=http://www.w3.org/2001/XMLSchema#string>>
@@ -562,13 +620,7 @@ impl PyObjectProtocol for PyTriple {
 
     fn __repr__(&self) -> String {
         let mut buffer = String::new();
-        buffer.push_str("<Triple subject=");
-        term_repr(self.inner.subject.as_ref().into(), &mut buffer);
-        buffer.push_str(" predicate=");
-        named_node_repr(self.inner.predicate.as_ref(), &mut buffer);
-        buffer.push_str(" object=");
-        term_repr(self.inner.object.as_ref(), &mut buffer);
-        buffer.push('>');
+        triple_repr(&self.inner, &mut buffer);
         buffer
     }
 
@@ -652,11 +704,11 @@ impl IntoPy<PyObject> for PyGraphName {
 /// in a `RDF dataset <https://www.w3.org/TR/rdf11-concepts/#dfn-rdf-dataset>`_
 ///
 /// :param subject: the quad subject
-/// :type subject: NamedNode or BlankNode
+/// :type subject: NamedNode or BlankNode or Triple
 /// :param predicate: the quad predicate
 /// :type predicate: NamedNode
 /// :param object: the quad object
-/// :type object: NamedNode or BlankNode or Literal
+/// :type object: NamedNode or BlankNode or Literal or Triple
 /// :param graph: the quad graph name. If not present, the default graph is assumed.
 /// :type graph: NamedNode or BlankNode or DefaultGraph or None, optional
 ///
@@ -700,7 +752,7 @@ impl<'a> From<&'a PyQuad> for QuadRef<'a> {
 impl PyQuad {
     #[new]
     fn new(
-        subject: PyNamedOrBlankNode,
+        subject: PySubject,
         predicate: PyNamedNode,
         object: PyTerm,
         graph_name: Option<PyGraphName>,
@@ -715,12 +767,12 @@ impl PyQuad {
     }
 
     /// :return: the quad subject
-    /// :rtype: NamedNode or BlankNode
+    /// :rtype: NamedNode or BlankNode or Triple
     ///
     /// >>> Quad(NamedNode('http://example.com'), NamedNode('http://example.com/p'), Literal('1'), NamedNode('http://example.com/g')).subject
     /// <NamedNode value=http://example.com>
     #[getter]
-    fn subject(&self) -> PyNamedOrBlankNode {
+    fn subject(&self) -> PySubject {
         self.inner.subject.clone().into()
     }
 
@@ -735,7 +787,7 @@ impl PyQuad {
     }
 
     /// :return: the quad object
-    /// :rtype: NamedNode or BlankNode or Literal
+    /// :rtype: NamedNode or BlankNode or Literal or Triple
     ///
     /// >>> Quad(NamedNode('http://example.com'), NamedNode('http://example.com/p'), Literal('1'), NamedNode('http://example.com/g')).object
     /// <Literal value=1 datatype=<NamedNode value=http://www.w3.org/2001/XMLSchema#string>>
@@ -804,7 +856,7 @@ impl PyMappingProtocol<'p> for PyQuad {
         let gil = Python::acquire_gil();
         let py = gil.python();
         match input {
-            0 => Ok(PyNamedOrBlankNode::from(self.inner.subject.clone()).into_py(py)),
+            0 => Ok(PySubject::from(self.inner.subject.clone()).into_py(py)),
             1 => Ok(PyNamedNode::from(self.inner.predicate.clone()).into_py(py)),
             2 => Ok(PyTerm::from(self.inner.object.clone()).into_py(py)),
             3 => Ok(PyGraphName::from(self.inner.graph_name.clone()).into_py(py)),
@@ -960,10 +1012,46 @@ impl<'a> TryFrom<&'a PyAny> for PyNamedOrBlankNodeRef<'a> {
     }
 }
 
+pub enum PySubjectRef<'a> {
+    NamedNode(PyRef<'a, PyNamedNode>),
+    BlankNode(PyRef<'a, PyBlankNode>),
+    Triple(PyRef<'a, PyTriple>),
+}
+
+impl<'a> From<&'a PySubjectRef<'a>> for SubjectRef<'a> {
+    fn from(value: &'a PySubjectRef<'a>) -> Self {
+        match value {
+            PySubjectRef::NamedNode(value) => value.inner.as_ref().into(),
+            PySubjectRef::BlankNode(value) => value.inner.as_ref().into(),
+            PySubjectRef::Triple(value) => (&value.inner).into(),
+        }
+    }
+}
+
+impl<'a> TryFrom<&'a PyAny> for PySubjectRef<'a> {
+    type Error = PyErr;
+
+    fn try_from(value: &'a PyAny) -> PyResult<Self> {
+        if let Ok(node) = value.downcast::<PyCell<PyNamedNode>>() {
+            Ok(Self::NamedNode(node.borrow()))
+        } else if let Ok(node) = value.downcast::<PyCell<PyBlankNode>>() {
+            Ok(Self::BlankNode(node.borrow()))
+        } else if let Ok(node) = value.downcast::<PyCell<PyTriple>>() {
+            Ok(Self::Triple(node.borrow()))
+        } else {
+            Err(PyTypeError::new_err(format!(
+                "{} is not an RDF named node, blank node or triple",
+                value.get_type().name()?,
+            )))
+        }
+    }
+}
+
 pub enum PyTermRef<'a> {
     NamedNode(PyRef<'a, PyNamedNode>),
     BlankNode(PyRef<'a, PyBlankNode>),
     Literal(PyRef<'a, PyLiteral>),
+    Triple(PyRef<'a, PyTriple>),
 }
 
 impl<'a> From<&'a PyTermRef<'a>> for TermRef<'a> {
@@ -972,6 +1060,7 @@ impl<'a> From<&'a PyTermRef<'a>> for TermRef<'a> {
             PyTermRef::NamedNode(value) => value.inner.as_ref().into(),
             PyTermRef::BlankNode(value) => value.inner.as_ref().into(),
             PyTermRef::Literal(value) => value.inner.as_ref().into(),
+            PyTermRef::Triple(value) => (&value.inner).into(),
         }
     }
 }
@@ -992,6 +1081,8 @@ impl<'a> TryFrom<&'a PyAny> for PyTermRef<'a> {
             Ok(Self::BlankNode(node.borrow()))
         } else if let Ok(node) = value.downcast::<PyCell<PyLiteral>>() {
             Ok(Self::Literal(node.borrow()))
+        } else if let Ok(node) = value.downcast::<PyCell<PyTriple>>() {
+            Ok(Self::Triple(node.borrow()))
         } else {
             Err(PyTypeError::new_err(format!(
                 "{} is not an RDF term",
@@ -1109,9 +1200,20 @@ pub fn term_repr(term: TermRef<'_>, buffer: &mut String) {
         TermRef::NamedNode(node) => named_node_repr(node, buffer),
         TermRef::BlankNode(node) => blank_node_repr(node, buffer),
         TermRef::Literal(literal) => literal_repr(literal, buffer),
+        TermRef::Triple(triple) => triple_repr(triple, buffer),
     }
 }
 
+fn triple_repr(triple: &Triple, buffer: &mut String) {
+    buffer.push_str("<Triple subject=");
+    term_repr(triple.subject.as_ref().into(), buffer);
+    buffer.push_str(" predicate=");
+    named_node_repr(triple.predicate.as_ref(), buffer);
+    buffer.push_str(" object=");
+    term_repr(triple.object.as_ref(), buffer);
+    buffer.push('>');
+}
+
 fn graph_name_repr(term: GraphNameRef<'_>, buffer: &mut String) {
     match term {
         GraphNameRef::NamedNode(node) => named_node_repr(node, buffer),
@@ -19,8 +19,11 @@ use http_types::content::{Accept, ContentType};
 use http_types::{
     bail_status, headers, Error, Method, Mime, Request, Response, Result, StatusCode,
 };
-use oxigraph::io::{DatasetFormat, GraphFormat};
-use oxigraph::model::{GraphName, GraphNameRef, NamedNode, NamedOrBlankNode};
+use oxigraph::io::{
+    CompressionFormat, DatasetFormat, DatasetSerializer, DecompressingReader, GraphFormat,
+    GraphSerializer, RdfFormat,
+};
+use oxigraph::model::{GraphName, GraphNameRef, NamedNode, NamedOrBlankNode, Quad};
 use oxigraph::sparql::algebra::GraphUpdateOperation;
 use oxigraph::sparql::{Query, QueryResults, QueryResultsFormat, Update};
 #[cfg(feature = "rocksdb")]
@@ -28,7 +31,7 @@ use oxigraph::RocksDbStore as Store;
 #[cfg(all(feature = "sled", not(feature = "rocksdb")))]
 use oxigraph::SledStore as Store;
 use rand::random;
-use std::io::BufReader;
+use std::io::{BufRead, BufReader};
 use std::str::FromStr;
 use url::{form_urlencoded, Url};
 
@@ -36,6 +39,13 @@ const MAX_SPARQL_BODY_SIZE: u64 = 1_048_576;
 const HTML_ROOT_PAGE: &str = include_str!("../templates/query.html");
 const LOGO: &str = include_str!("../logo.svg");
 const SERVER: &str = concat!("Oxigraph/", env!("CARGO_PKG_VERSION"));
+/// The prefixes used to write the pretty Turtle and TriG dumps
+const PREFIXES: &[(&str, &str)] = &[
+    ("rdf", "http://www.w3.org/1999/02/22-rdf-syntax-ns#"),
+    ("rdfs", "http://www.w3.org/2000/01/rdf-schema#"),
+    ("xsd", "http://www.w3.org/2001/XMLSchema#"),
+    ("owl", "http://www.w3.org/2002/07/owl#"),
+];
 
 #[derive(FromArgs)]
 /// Oxigraph SPARQL server
@@ -47,12 +57,17 @@ struct Args {
     /// directory in which persist the data
     #[argh(option, short = 'f')]
     file: String,
+
+    /// maximal number of query results to cache, the cache is disabled by default
+    #[argh(option, default = "0")]
+    query_cache_size: usize,
 }
 
 #[async_std::main]
 pub async fn main() -> Result<()> {
     let args: Args = argh::from_env();
     let store = Store::open(args.file)?;
+    store.set_query_cache_capacity(args.query_cache_size);
 
     println!("Listening for requests at http://{}", &args.bind);
     http_server(&args.bind, move |request| {
@@ -76,7 +91,11 @@ async fn handle_request(request: Request, store: Store) -> Result<Response> {
             response
         }
         ("/query", Method::Get) => {
-            configure_and_evaluate_sparql_query(store, url_query(&request), None, request)?
+            if request.url().query().is_none() {
+                service_description(store, request)?
+            } else {
+                configure_and_evaluate_sparql_query(store, url_query(&request), None, request)?
+            }
         }
         ("/query", Method::Post) => {
             if let Some(content_type) = request.content_type() {
@@ -145,6 +164,7 @@ async fn handle_request(request: Request, store: Store) -> Result<Response> {
         (path, Method::Get) if path.starts_with("/store") => {
             //TODO: stream
             let mut body = Vec::default();
+            let pretty = is_pretty_output(&request);
             let format = if let Some(target) = store_target(&request)? {
                 if !match &target {
                     GraphName::DefaultGraph => true,
@@ -154,11 +174,43 @@ async fn handle_request(request: Request, store: Store) -> Result<Response> {
                     bail_status!(404, "The graph {} does not exists", target)
                 }
                 let format = graph_content_negotiation(request)?;
-                store.dump_graph(&mut body, format, &target)?;
+                match format {
+                    RdfFormat::Graph(format) => {
+                        let mut serializer = GraphSerializer::from_format(format);
+                        if pretty {
+                            for (prefix_name, prefix_iri) in PREFIXES {
+                                serializer = serializer.with_prefix(*prefix_name, *prefix_iri)?;
+                            }
+                        }
+                        store.dump_graph(&mut body, serializer, &target)?;
+                    }
+                    RdfFormat::Dataset(format) => {
+                        // The graph is written as the default graph of the dataset
+                        let mut writer =
+                            DatasetSerializer::from_format(format).quad_writer(&mut body)?;
+                        for quad in store.quads_for_pattern(None, None, None, Some(target.as_ref()))
+                        {
+                            let quad = quad?;
+                            writer.write(&Quad::new(
+                                quad.subject,
+                                quad.predicate,
+                                quad.object,
+                                GraphName::DefaultGraph,
+                            ))?;
+                        }
+                        writer.finish()?;
+                    }
+                }
                 format.media_type()
             } else {
                 let format = dataset_content_negotiation(request)?;
-                store.dump_dataset(&mut body, format)?;
+                let mut serializer = DatasetSerializer::from_format(format);
+                if pretty {
+                    for (prefix_name, prefix_iri) in PREFIXES {
+                        serializer = serializer.with_prefix(*prefix_name, *prefix_iri)?;
+                    }
+                }
+                store.dump_dataset(&mut body, serializer)?;
                 format.media_type()
             };
             let mut response = Response::from(body);
@@ -166,69 +218,61 @@ async fn handle_request(request: Request, store: Store) -> Result<Response> {
             response
         }
         (path, Method::Put) if path.starts_with("/store") => {
-            if let Some(content_type) = request.content_type() {
-                if let Some(target) = store_target(&request)? {
-                    if let Some(format) = GraphFormat::from_media_type(content_type.essence()) {
-                        let new = !match &target {
-                            GraphName::NamedNode(target) => {
-                                if store.contains_named_graph(target)? {
-                                    store.clear_graph(target)?;
-                                    true
-                                } else {
-                                    store.insert_named_graph(target)?;
-                                    false
-                                }
-                            }
-                            GraphName::BlankNode(target) => {
-                                if store.contains_named_graph(target)? {
-                                    store.clear_graph(target)?;
-                                    true
-                                } else {
-                                    store.insert_named_graph(target)?;
-                                    false
-                                }
+            let target = store_target(&request)?;
+            let (body, format) = request_body_and_format(request)?;
+            if let Some(target) = target {
+                if let RdfFormat::Graph(format) = format {
+                    let new = !match &target {
+                        GraphName::NamedNode(target) => {
+                            if store.contains_named_graph(target)? {
+                                store.clear_graph(target)?;
+                                true
+                            } else {
+                                store.insert_named_graph(target)?;
+                                false
                             }
-                            GraphName::DefaultGraph => {
-                                store.clear_graph(&target)?;
+                        }
+                        GraphName::BlankNode(target) => {
+                            if store.contains_named_graph(target)? {
+                                store.clear_graph(target)?;
                                 true
+                            } else {
+                                store.insert_named_graph(target)?;
+                                false
                             }
-                        };
-                        store
-                            .load_graph(
-                                BufReader::new(SyncAsyncReader::from(request)),
-                                format,
-                                &target,
-                                None,
-                            )
-                            .map_err(bad_request)?;
-                        Response::new(if new {
-                            StatusCode::Created
-                        } else {
-                            StatusCode::NoContent
-                        })
-                    } else {
-                        bail_status!(
-                            415,
-                            "No supported content Content-Type given: {}",
-                            content_type
-                        )
-                    }
-                } else if let Some(format) = DatasetFormat::from_media_type(content_type.essence())
-                {
-                    store.clear()?;
+                        }
+                        GraphName::DefaultGraph => {
+                            store.clear_graph(&target)?;
+                            true
+                        }
+                    };
                     store
-                        .load_dataset(BufReader::new(SyncAsyncReader::from(request)), format, None)
+                        .load_graph(body, format, &target, None)
                         .map_err(bad_request)?;
-                    Response::new(StatusCode::NoContent)
+                    Response::new(if new {
+                        StatusCode::Created
+                    } else {
+                        StatusCode::NoContent
+                    })
                 } else {
                     bail_status!(
                         415,
                         "No supported content Content-Type given: {}",
-                        content_type
+                        format.media_type()
                     )
                 }
+            } else if let RdfFormat::Dataset(format) = format {
+                store.clear()?;
+                store
+                    .load_dataset(body, format, None)
+                    .map_err(bad_request)?;
+                Response::new(StatusCode::NoContent)
             } else {
-                bail_status!(400, "No Content-Type given")
+                bail_status!(
+                    415,
+                    "No supported content Content-Type given: {}",
+                    format.media_type()
+                )
             }
         }
         (path, Method::Delete) if path.starts_with("/store") => {
@@ -256,66 +300,53 @@ async fn handle_request(request: Request, store: Store) -> Result<Response> {
             Response::new(StatusCode::NoContent)
         }
         (path, Method::Post) if path.starts_with("/store") => {
-            if let Some(content_type) = request.content_type() {
-                if let Some(target) = store_target(&request)? {
-                    if let Some(format) = GraphFormat::from_media_type(content_type.essence()) {
-                        let new = !match &target {
-                            GraphName::NamedNode(target) => store.contains_named_graph(target)?,
-                            GraphName::BlankNode(target) => store.contains_named_graph(target)?,
-                            GraphName::DefaultGraph => true,
-                        };
-                        store
-                            .load_graph(
-                                BufReader::new(SyncAsyncReader::from(request)),
-                                format,
-                                &target,
-                                None,
-                            )
-                            .map_err(bad_request)?;
-                        Response::new(if new {
-                            StatusCode::Created
-                        } else {
-                            StatusCode::NoContent
-                        })
-                    } else {
-                        bail_status!(
-                            415,
-                            "No supported content Content-Type given: {}",
-                            content_type
-                        )
-                    }
-                } else if let Some(format) = DatasetFormat::from_media_type(content_type.essence())
-                {
+            let target = store_target(&request)?;
+            let base_url = base_url(&request)?;
+            let (body, format) = request_body_and_format(request)?;
+            if let Some(target) = target {
+                if let RdfFormat::Graph(format) = format {
+                    let new = !match &target {
+                        GraphName::NamedNode(target) => store.contains_named_graph(target)?,
+                        GraphName::BlankNode(target) => store.contains_named_graph(target)?,
+                        GraphName::DefaultGraph => true,
+                    };
                     store
-                        .load_dataset(BufReader::new(SyncAsyncReader::from(request)), format, None)
+                        .load_graph(body, format, &target, None)
                         .map_err(bad_request)?;
-                    Response::new(StatusCode::NoContent)
-                } else if let Some(format) = GraphFormat::from_media_type(content_type.essence()) {
-                    let graph = NamedNode::new(
-                        base_url(&request)?
-                            .join(&format!("/store/{:x}", random::<u128>()))?
-                            .into_string(),
-                    )?;
-                    store
-                        .load_graph(
-                            BufReader::new(SyncAsyncReader::from(request)),
-                            format,
-                            &graph,
-                            None,
-                        )
-                        .map_err(bad_request)?;
-                    let mut response = Response::new(StatusCode::Created);
-                    response.insert_header(headers::LOCATION, graph.into_string());
-                    response
+                    Response::new(if new {
+                        StatusCode::Created
+                    } else {
+                        StatusCode::NoContent
+                    })
                 } else {
                     bail_status!(
                         415,
                         "No supported content Content-Type given: {}",
-                        content_type
+                        format.media_type()
                     )
                 }
             } else {
-                bail_status!(400, "No Content-Type given")
+                match format {
+                    RdfFormat::Dataset(format) => {
+                        store
+                            .load_dataset(body, format, None)
+                            .map_err(bad_request)?;
+                        Response::new(StatusCode::NoContent)
+                    }
+                    RdfFormat::Graph(format) => {
+                        let graph = NamedNode::new(
+                            base_url
+                                .join(&format!("/store/{:x}", random::<u128>()))?
+                                .into_string(),
+                        )?;
+                        store
+                            .load_graph(body, format, &graph, None)
+                            .map_err(bad_request)?;
+                        let mut response = Response::new(StatusCode::Created);
+                        response.insert_header(headers::LOCATION, graph.into_string());
+                        response
+                    }
+                }
             }
         }
         (path, Method::Head) if path.starts_with("/store") => {
@@ -438,6 +469,18 @@ fn evaluate_sparql_query(
     }
 }
 
+fn service_description(store: Store, request: Request) -> Result<Response> {
+    let description = store
+        .service_description()?
+        .with_endpoint(NamedNode::new(base_url(&request)?.as_str()).map_err(bad_request)?);
+    let format = graph_content_negotiation(request)?;
+    let mut body = Vec::default();
+    description.write(&mut body, format)?;
+    let mut response = Response::from(body);
+    ContentType::new(format.media_type()).apply(&mut response);
+    Ok(response)
+}
+
 fn configure_and_evaluate_sparql_update(
     store: Store,
     encoded: Vec<u8>,
@@ -502,6 +545,12 @@ fn evaluate_sparql_update(
     Ok(Response::new(StatusCode::NoContent))
 }
 
+/// Returns if the `pretty` parameter asks for a pretty output with prefixes, which requires buffering all the triples
+fn is_pretty_output(request: &Request) -> bool {
+    form_urlencoded::parse(request.url().query().unwrap_or("").as_bytes())
+        .any(|(k, _)| k == "pretty")
+}
+
 fn store_target(request: &Request) -> Result<Option<GraphName>> {
     if request.url().path() == "/store" {
         let mut graph = None;
@@ -510,6 +559,7 @@ fn store_target(request: &Request) -> Result<Option<GraphName>> {
             match k.as_ref() {
                 "graph" => graph = Some(v.into_owned()),
                 "default" => default = true,
+                "pretty" if request.method() == Method::Get => (),
                 _ => bail_status!(400, "Unexpected parameter: {}", k),
             }
         }
@@ -586,15 +636,16 @@ async fn http_server<
     Ok(())
 }
 
-fn graph_content_negotiation(request: Request) -> Result<GraphFormat> {
+fn graph_content_negotiation(request: Request) -> Result<RdfFormat> {
     content_negotiation(
         request,
         &[
             GraphFormat::NTriples.media_type(),
             GraphFormat::Turtle.media_type(),
             GraphFormat::RdfXml.media_type(),
+            DatasetFormat::JsonLd.media_type(),
         ],
-        GraphFormat::from_media_type,
+        RdfFormat::from_media_type,
     )
 }
 
@@ -604,6 +655,7 @@ fn dataset_content_negotiation(request: Request) -> Result<DatasetFormat> {
         &[
             DatasetFormat::NQuads.media_type(),
             DatasetFormat::TriG.media_type(),
+            DatasetFormat::JsonLd.media_type(),
         ],
         DatasetFormat::from_media_type,
     )
@@ -631,6 +683,58 @@ fn content_negotiation<F>(
     .ok_or_else(|| Error::from_str(StatusCode::InternalServerError, "Unknown mime type"))
 }
 
+/// Returns the request body and its format read from the `Content-Type` header or, if not set, guessed from the body content
+fn request_body_and_format(
+    request: Request,
+) -> Result<(
+    DecompressingReader<BufReader<SyncAsyncReader<Request>>>,
+    RdfFormat,
+)> {
+    let content_type = request
+        .content_type()
+        .filter(|content_type| content_type.essence() != "application/octet-stream");
+    let mut body = request_body(request)?;
+    let format = if let Some(content_type) = content_type {
+        if let Some(format) = RdfFormat::from_media_type(content_type.essence()) {
+            format
+        } else {
+            bail_status!(415, "Not supported Content-Type given: {}", content_type)
+        }
+    } else if let Some(guess) = RdfFormat::from_content(body.fill_buf().map_err(bad_request)?) {
+        guess.format()
+    } else {
+        bail_status!(
+            400,
+            "No Content-Type given and the body format could not be detected"
+        )
+    };
+    Ok((body, format))
+}
+
+fn request_body(
+    request: Request,
+) -> Result<DecompressingReader<BufReader<SyncAsyncReader<Request>>>> {
+    let compression = if let Some(content_encoding) = request.header(headers::CONTENT_ENCODING) {
+        let content_encoding = content_encoding.last().as_str();
+        if content_encoding.trim().eq_ignore_ascii_case("identity") {
+            None
+        } else if let Some(compression) = CompressionFormat::from_content_encoding(content_encoding)
+        {
+            Some(compression)
+        } else {
+            bail_status!(
+                415,
+                "Not supported Content-Encoding given: {}",
+                content_encoding
+            )
+        }
+    } else {
+        None
+    };
+    DecompressingReader::with_format(BufReader::new(SyncAsyncReader::from(request)), compression)
+        .map_err(bad_request)
+}
+
 fn bad_request(e: impl Into<Error>) -> Error {
     let mut e = e.into();
     e.set_status(StatusCode::BadRequest);
@@ -660,6 +764,7 @@ mod tests {
     use super::*;
     use crate::handle_request;
     use async_std::task::block_on;
+    use oxigraph::model::Triple;
     use tempfile::{tempdir, TempDir};
 
     #[test]
@@ -678,6 +783,147 @@ mod tests {
         ServerTest::new().test_status(request, StatusCode::NoContent)
     }
 
+    #[test]
+    fn post_json_ld_dataset_file() {
+        let mut request = Request::new(Method::Post, Url::parse("http://localhost/store").unwrap());
+        request.insert_header("Content-Type", "application/ld+json");
+        request.set_body(
+            r#"{"@id": "http://example.com", "http://example.com": {"@id": "http://example.com"}}"#,
+        );
+        ServerTest::new().test_status(request, StatusCode::NoContent)
+    }
+
+    #[test]
+    fn get_pretty_graph() {
+        let server = ServerTest::new();
+        let mut request = Request::new(
+            Method::Post,
+            Url::parse("http://localhost/store?default").unwrap(),
+        );
+        request.insert_header("Content-Type", "text/turtle");
+        request.set_body("<http://example.com/s> a <http://www.w3.org/2002/07/owl#Class> ; <http://www.w3.org/2000/01/rdf-schema#label> \"s\" .");
+        server.test_status(request, StatusCode::NoContent);
+
+        // Without the pretty parameter the output is not buffered to declare the prefixes
+        let mut request = Request::new(
+            Method::Get,
+            Url::parse("http://localhost/store?default").unwrap(),
+        );
+        request.insert_header("Accept", "text/turtle");
+        let mut response = server.exec(request);
+        assert_eq!(response.status(), StatusCode::Ok);
+        assert!(!block_on(response.body_string())
+            .unwrap()
+            .contains("@prefix"));
+
+        let mut request = Request::new(
+            Method::Get,
+            Url::parse("http://localhost/store?default&pretty").unwrap(),
+        );
+        request.insert_header("Accept", "text/turtle");
+        let mut response = server.exec(request);
+        assert_eq!(response.status(), StatusCode::Ok);
+        assert_eq!(
+            block_on(response.body_string()).unwrap(),
+            "@prefix rdfs: <http://www.w3.org/2000/01/rdf-schema#> .\n\
+             @prefix owl: <http://www.w3.org/2002/07/owl#> .\n\n\
+             <http://example.com/s> a owl:Class ;\n\trdfs:label \"s\" .\n"
+        );
+    }
+
+    #[test]
+    fn get_json_ld_graph() {
+        let server = ServerTest::new();
+        let mut request = Request::new(
+            Method::Put,
+            Url::parse("http://localhost/store?graph=http://example.com/g").unwrap(),
+        );
+        request.insert_header("Content-Type", "application/n-triples");
+        request.set_body("<http://example.com/s> <http://example.com/p> \"o\" .");
+        server.test_status(request, StatusCode::Created);
+
+        let mut request = Request::new(
+            Method::Get,
+            Url::parse("http://localhost/store?graph=http://example.com/g").unwrap(),
+        );
+        request.insert_header("Accept", "application/ld+json");
+        let mut response = server.exec(request);
+        assert_eq!(response.status(), StatusCode::Ok);
+        assert_eq!(
+            response.content_type().map(|c| c.essence().to_owned()),
+            Some("application/ld+json".to_owned())
+        );
+        let body = block_on(response.body_string()).unwrap();
+        assert!(body.contains("\"@id\":\"http://example.com/s\""));
+        assert!(!body.contains("http://example.com/g"));
+    }
+
+    #[test]
+    fn post_dataset_file_without_content_type() {
+        let server = ServerTest::new();
+        let mut request = Request::new(Method::Post, Url::parse("http://localhost/store").unwrap());
+        request.set_body("<http://example.com/s> <http://example.com/p> <http://example.com/o> <http://example.com/g> .\n".as_bytes().to_vec());
+        server.test_status(request, StatusCode::NoContent);
+        server.test_status(
+            Request::new(
+                Method::Head,
+                Url::parse("http://localhost/store?graph=http://example.com/g").unwrap(),
+            ),
+            StatusCode::Ok,
+        );
+
+        let request = Request::new(Method::Post, Url::parse("http://localhost/store").unwrap());
+        server.test_status(request, StatusCode::BadRequest)
+    }
+
+    #[test]
+    fn post_compressed_graph_file() {
+        let mut body = Vec::new();
+        let mut writer = GraphSerializer::from_format(GraphFormat::NTriples)
+            .with_compression(CompressionFormat::Gzip)
+            .triple_writer(&mut body)
+            .unwrap();
+        writer
+            .write(&Triple::new(
+                NamedNode::new("http://example.com/s").unwrap(),
+                NamedNode::new("http://example.com/p").unwrap(),
+                NamedNode::new("http://example.com/o").unwrap(),
+            ))
+            .unwrap();
+        writer.finish().unwrap();
+
+        let server = ServerTest::new();
+        let mut request = Request::new(
+            Method::Post,
+            Url::parse("http://localhost/store?default").unwrap(),
+        );
+        request.insert_header("Content-Type", "application/n-triples");
+        request.insert_header("Content-Encoding", "gzip");
+        request.set_body(body);
+        server.test_status(request, StatusCode::NoContent);
+
+        let mut request = Request::new(
+            Method::Get,
+            Url::parse("http://localhost/store?default").unwrap(),
+        );
+        request.insert_header("Accept", "application/n-triples");
+        let mut response = server.exec(request);
+        assert_eq!(response.status(), StatusCode::Ok);
+        assert_eq!(
+            block_on(response.body_string()).unwrap(),
+            "<http://example.com/s> <http://example.com/p> <http://example.com/o> .\n"
+        );
+    }
+
+    #[test]
+    fn post_unsupported_encoding() {
+        let mut request = Request::new(Method::Post, Url::parse("http://localhost/store").unwrap());
+        request.insert_header("Content-Type", "application/trig");
+        request.insert_header("Content-Encoding", "br");
+        request.set_body("<http://example.com> <http://example.com> <http://example.com> .");
+        ServerTest::new().test_status(request, StatusCode::UnsupportedMediaType)
+    }
+
     #[test]
     fn post_wrong_file() {
         let mut request = Request::new(Method::Post, Url::parse("http://localhost/store").unwrap());
@@ -721,11 +967,45 @@ mod tests {
     #[test]
     fn get_without_query() {
         ServerTest::new().test_status(
-            Request::new(Method::Get, Url::parse("http://localhost/query").unwrap()),
+            Request::new(
+                Method::Get,
+                Url::parse("http://localhost/query?default-graph-uri=http://example.com").unwrap(),
+            ),
             StatusCode::BadRequest,
         );
     }
 
+    #[test]
+    fn get_service_description() {
+        let mut request = Request::new(Method::Get, Url::parse("http://localhost/query").unwrap());
+        request.insert_header("Accept", "text/turtle");
+        let mut response = ServerTest::new().exec(request);
+        assert_eq!(response.status(), StatusCode::Ok);
+        assert_eq!(
+            response.content_type().map(|c| c.essence().to_owned()),
+            Some("text/turtle".to_owned())
+        );
+        let body = block_on(response.body_string()).unwrap();
+        assert!(body.contains(
+            "<http://www.w3.org/ns/sparql-service-description#endpoint> <http://localhost/query>"
+        ));
+    }
+
+    #[test]
+    fn get_json_ld_service_description() {
+        let mut request = Request::new(Method::Get, Url::parse("http://localhost/query").unwrap());
+        request.insert_header("Accept", "application/ld+json");
+        let mut response = ServerTest::new().exec(request);
+        assert_eq!(response.status(), StatusCode::Ok);
+        assert_eq!(
+            response.content_type().map(|c| c.essence().to_owned()),
+            Some("application/ld+json".to_owned())
+        );
+        let body = block_on(response.body_string()).unwrap();
+        assert!(body.contains("http://www.w3.org/ns/sparql-service-description#endpoint"));
+        assert!(body.contains("http://localhost/query"));
+    }
+
     #[test]
     fn post_query() {
         let mut request = Request::new(Method::Post, Url::parse("http://localhost/query").unwrap());
@@ -1,8 +1,7 @@
 use anyhow::Result;
 use chrono::{DateTime, Utc};
-use oxigraph::model::NamedNode;
+use oxigraph::model::{dataset_diff, NamedNode};
 use oxigraph::MemoryStore;
-use text_diff::{diff, Difference};
 
 #[derive(Debug)]
 pub struct TestResult {
@@ -12,41 +11,20 @@ pub struct TestResult {
 }
 
 pub fn store_diff(expected: &MemoryStore, actual: &MemoryStore) -> String {
-    let (_, changeset) = diff(
-        &normalize_store_text(expected),
-        &normalize_store_text(actual),
-        "\n",
-    );
+    let diff = dataset_diff(expected.iter(), actual.iter());
     let mut ret = String::new();
     ret.push_str("Note: missing quads in yellow and extra quads in blue\n");
-    for seq in changeset {
-        match seq {
-            Difference::Same(x) => {
-                ret.push_str(&x);
-                ret.push('\n');
-            }
-            Difference::Add(x) => {
-                ret.push_str("\x1B[94m");
-                ret.push_str(&x);
-                ret.push_str("\x1B[0m");
-                ret.push('\n');
-            }
-            Difference::Rem(x) => {
-                ret.push_str("\x1B[93m");
-                ret.push_str(&x);
-                ret.push_str("\x1B[0m");
-                ret.push('\n');
-            }
-        }
+    for quad in diff.only_in_left() {
+        ret.push_str("\x1B[93m");
+        ret.push_str(&quad.to_string());
+        ret.push_str("\x1B[0m");
+        ret.push('\n');
+    }
+    for quad in diff.only_in_right() {
+        ret.push_str("\x1B[94m");
+        ret.push_str(&quad.to_string());
+        ret.push_str("\x1B[0m");
+        ret.push('\n');
     }
     ret
 }
-
-fn normalize_store_text(store: &MemoryStore) -> String {
-    let mut quads: Vec<_> = store
-        .quads_for_pattern(None, None, None, None)
-        .map(|q| q.to_string())
-        .collect();
-    quads.sort();
-    quads.join("\n")
-}